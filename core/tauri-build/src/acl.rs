@@ -6,7 +6,7 @@ use std::{
   collections::{BTreeMap, BTreeSet},
   fs::{copy, create_dir_all, read_to_string, File},
   io::{BufWriter, Write},
-  path::PathBuf,
+  path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Result};
@@ -18,7 +18,16 @@ use schemars::{
   schema_for,
 };
 use tauri_utils::{
-  acl::{build::CapabilityFile, capability::Capability, plugin::Manifest},
+  acl::{
+    build::{CapabilityFile, UnknownFields},
+    capability::{Capability, CapabilityGroups},
+    diagnostic::Diagnostic,
+    diff::{AclDiffAllowlist, AclDifference},
+    plugin::{Manifest, SCOPE_PRESET_KEY},
+    resolved::{ResolutionReport, Resolved, ScopeHooks, ScopeSize, WarningSeverity},
+    Deprecation, Error, ALL_PERMISSIONS,
+  },
+  config::SecurityConfig,
   platform::Target,
 };
 
@@ -28,19 +37,49 @@ const CAPABILITIES_SCHEMA_FOLDER_PATH: &str = "capabilities/schemas";
 const CAPABILITIES_FILE_NAME: &str = "capabilities.json";
 const PLUGIN_MANIFESTS_FILE_NAME: &str = "plugin-manifests.json";
 
+/// Environment variable with the path of the file to write the ACL errors to as JSON diagnostics,
+/// see [`write_diagnostics`].
+pub const DIAGNOSTICS_ENV: &str = "TAURI_ACL_DIAGNOSTICS";
+
+/// Environment variable with the path of the file to write the ACL resolution traces to,
+/// see [`init_tracing`].
+#[cfg(feature = "tracing")]
+pub const TRACE_ENV: &str = "TAURI_ACL_TRACE";
+
+/// Code of the diagnostic of a build failure that is not an ACL [`Error`], e.g. exceeded scope size limits.
+const DIAGNOSTIC_CODE_ACL_CHECK: &str = "acl-check";
+
 fn capabilities_schema(plugin_manifests: &BTreeMap<String, Manifest>) -> RootSchema {
   let mut schema = schema_for!(CapabilityFile);
 
-  fn schema_from(plugin: &str, id: &str, description: Option<&str>) -> Schema {
+  fn schema_from(
+    plugin: &str,
+    id: &str,
+    description: Option<&str>,
+    deprecated: Option<&Deprecation>,
+  ) -> Schema {
+    // editors strike through the `deprecated` values and show the `deprecationMessage`
+    let extensions = deprecated
+      .map(|deprecation| {
+        [(
+          "deprecationMessage".to_string(),
+          format!("{plugin}:{id} is {}", deprecation.message(plugin)).into(),
+        )]
+        .into_iter()
+        .collect()
+      })
+      .unwrap_or_default();
     Schema::Object(SchemaObject {
       metadata: Some(Box::new(Metadata {
         description: description
           .as_ref()
           .map(|d| format!("{plugin}:{id} -> {d}")),
+        deprecated: deprecated.is_some(),
         ..Default::default()
       })),
       instance_type: Some(InstanceType::String.into()),
       enum_values: Some(vec![serde_json::Value::String(format!("{plugin}:{id}"))]),
+      extensions,
       ..Default::default()
     })
   }
@@ -49,7 +88,12 @@ fn capabilities_schema(plugin_manifests: &BTreeMap<String, Manifest>) -> RootSch
 
   for (plugin, manifest) in plugin_manifests {
     for (set_id, set) in &manifest.permission_sets {
-      permission_schemas.push(schema_from(plugin, set_id, Some(&set.description)));
+      permission_schemas.push(schema_from(
+        plugin,
+        set_id,
+        Some(&set.description),
+        set.deprecated.as_ref(),
+      ));
     }
 
     if let Some(default) = &manifest.default_permission {
@@ -57,6 +101,14 @@ fn capabilities_schema(plugin_manifests: &BTreeMap<String, Manifest>) -> RootSch
         plugin,
         "default",
         Some(default.description.as_ref()),
+        default.deprecated.as_ref(),
+      ));
+    } else if let Some(permission) = manifest.implicit_default_permission() {
+      permission_schemas.push(schema_from(
+        plugin,
+        "default",
+        permission.description.as_deref(),
+        permission.deprecated.as_ref(),
       ));
     }
 
@@ -65,8 +117,18 @@ fn capabilities_schema(plugin_manifests: &BTreeMap<String, Manifest>) -> RootSch
         plugin,
         permission_id,
         permission.description.as_deref(),
+        permission.deprecated.as_ref(),
       ));
     }
+
+    permission_schemas.push(schema_from(
+      plugin,
+      ALL_PERMISSIONS,
+      Some(
+        "Every permission of the plugin, except its deny- permissions unless include-deny is set.",
+      ),
+      None,
+    ));
   }
 
   if let Some(Schema::Object(obj)) = schema.definitions.get_mut("Identifier") {
@@ -91,15 +153,61 @@ fn capabilities_schema(plugin_manifests: &BTreeMap<String, Manifest>) -> RootSch
       permission_entry_any_of_schemas.remove(permission_entry_any_of_schemas.len() - 1)
     {
       let mut global_scope_one_of = Vec::new();
+      // the entry fields that do not depend on the plugin, e.g. `config`
+      let shared_properties = scope_extended_schema_obj
+        .object
+        .as_ref()
+        .map(|object| {
+          object
+            .properties
+            .iter()
+            .filter(|(name, _)| !matches!(name.as_str(), "identifier" | "allow" | "deny"))
+            .map(|(name, schema)| (name.clone(), schema.clone()))
+            .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
 
       for (plugin, manifest) in plugin_manifests {
         if let Some(global_scope_schema) = &manifest.global_scope_schema {
-          let global_scope_schema_def: Schema = serde_json::from_value(global_scope_schema.clone())
-            .unwrap_or_else(|e| panic!("invalid JSON schema for plugin {plugin}: {e}"));
+          let mut global_scope_schema_def: Schema =
+            serde_json::from_value(global_scope_schema.clone())
+              .unwrap_or_else(|e| panic!("invalid JSON schema for plugin {plugin}: {e}"));
+
+          // scope entries can also reference a preset of the plugin
+          if !manifest.presets.is_empty() {
+            global_scope_schema_def = Schema::Object(SchemaObject {
+              subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![
+                  global_scope_schema_def,
+                  preset_reference_schema(plugin, manifest),
+                ]),
+                ..Default::default()
+              })),
+              ..Default::default()
+            });
+          }
 
+          // a single scope entry object or a list of entries
           let global_scope_schema = Schema::Object(SchemaObject {
-            array: Some(Box::new(ArrayValidation {
-              items: Some(global_scope_schema_def.into()),
+            subschemas: Some(Box::new(SubschemaValidation {
+              one_of: Some(vec![
+                Schema::Object(SchemaObject {
+                  instance_type: Some(InstanceType::Object.into()),
+                  subschemas: Some(Box::new(SubschemaValidation {
+                    all_of: Some(vec![global_scope_schema_def.clone()]),
+                    ..Default::default()
+                  })),
+                  ..Default::default()
+                }),
+                Schema::Object(SchemaObject {
+                  instance_type: Some(InstanceType::Array.into()),
+                  array: Some(Box::new(ArrayValidation {
+                    items: Some(global_scope_schema_def.into()),
+                    ..Default::default()
+                  })),
+                  ..Default::default()
+                }),
+              ]),
               ..Default::default()
             })),
             ..Default::default()
@@ -115,16 +223,34 @@ fn capabilities_schema(plugin_manifests: &BTreeMap<String, Manifest>) -> RootSch
 
           let mut permission_schemas = Vec::new();
           if let Some(default) = &manifest.default_permission {
-            permission_schemas.push(schema_from(plugin, "default", Some(&default.description)));
+            permission_schemas.push(schema_from(
+              plugin,
+              "default",
+              Some(&default.description),
+              default.deprecated.as_ref(),
+            ));
+          } else if let Some(permission) = manifest.implicit_default_permission() {
+            permission_schemas.push(schema_from(
+              plugin,
+              "default",
+              permission.description.as_deref(),
+              permission.deprecated.as_ref(),
+            ));
           }
           for set in manifest.permission_sets.values() {
-            permission_schemas.push(schema_from(plugin, &set.identifier, Some(&set.description)));
+            permission_schemas.push(schema_from(
+              plugin,
+              &set.identifier,
+              Some(&set.description),
+              set.deprecated.as_ref(),
+            ));
           }
           for permission in manifest.permissions.values() {
             permission_schemas.push(schema_from(
               plugin,
               &permission.identifier,
               permission.description.as_deref(),
+              permission.deprecated.as_ref(),
             ));
           }
 
@@ -145,6 +271,7 @@ fn capabilities_schema(plugin_manifests: &BTreeMap<String, Manifest>) -> RootSch
           object
             .properties
             .insert("deny".to_string(), global_scope_schema);
+          object.properties.extend(shared_properties.clone());
 
           global_scope_one_of.push(Schema::Object(SchemaObject {
             instance_type: Some(InstanceType::Object.into()),
@@ -171,6 +298,32 @@ fn capabilities_schema(plugin_manifests: &BTreeMap<String, Manifest>) -> RootSch
   schema
 }
 
+/// The schema of a scope entry referencing a preset of the plugin, e.g. `{ "preset": "documents-read" }`.
+fn preset_reference_schema(plugin: &str, manifest: &Manifest) -> Schema {
+  let preset = Schema::Object(SchemaObject {
+    metadata: Some(Box::new(Metadata {
+      description: Some(format!("A scope preset of the {plugin} plugin.")),
+      ..Default::default()
+    })),
+    instance_type: Some(InstanceType::String.into()),
+    enum_values: Some(manifest.presets.keys().cloned().map(Into::into).collect()),
+    ..Default::default()
+  });
+
+  let mut object = ObjectValidation::default();
+  object.required.insert(SCOPE_PRESET_KEY.to_string());
+  object
+    .properties
+    .insert(SCOPE_PRESET_KEY.to_string(), preset);
+  object.additional_properties = Some(Box::new(Schema::Bool(false)));
+
+  Schema::Object(SchemaObject {
+    instance_type: Some(InstanceType::Object.into()),
+    object: Some(Box::new(object)),
+    ..Default::default()
+  })
+}
+
 pub fn generate_schema(
   plugin_manifests: &BTreeMap<String, Manifest>,
   target: Target,
@@ -219,15 +372,18 @@ pub fn save_plugin_manifests(plugin_manifests: &BTreeMap<String, Manifest>) -> R
   Ok(plugin_manifests_path)
 }
 
-pub fn get_plugin_manifests() -> Result<BTreeMap<String, Manifest>> {
-  let permission_map =
-    tauri_utils::acl::build::read_permissions().context("failed to read plugin permissions")?;
+pub fn get_plugin_manifests(unknown_fields: UnknownFields) -> Result<BTreeMap<String, Manifest>> {
+  let permission_map = tauri_utils::acl::build::read_permissions(unknown_fields)
+    .context("failed to read plugin permissions")?;
   let mut global_scope_map = tauri_utils::acl::build::read_global_scope_schemas()
     .context("failed to read global scope schemas")?;
 
+  let mut version_map = tauri_utils::acl::build::read_plugin_versions();
+
   let mut processed = BTreeMap::new();
   for (plugin_name, permission_files) in permission_map {
-    let manifest = Manifest::new(permission_files, global_scope_map.remove(&plugin_name));
+    let mut manifest = Manifest::new(permission_files, global_scope_map.remove(&plugin_name));
+    manifest.version = version_map.remove(&plugin_name);
     processed.insert(plugin_name, manifest);
   }
 
@@ -247,12 +403,15 @@ pub fn validate_capabilities(
 
     for permission_entry in &capability.permissions {
       let permission_id = permission_entry.identifier();
-      if let Some((plugin_name, permission_name)) = permission_id.get().split_once(':') {
+      if let Some(plugin_name) = permission_id.get_prefix() {
+        let permission_name = permission_id.get_base();
         let permission_exists = plugin_manifests
           .get(plugin_name)
           .map(|manifest| {
             if permission_name == "default" {
-              manifest.default_permission.is_some()
+              manifest.has_default_permission()
+            } else if permission_id.is_all_permissions() {
+              true
             } else {
               manifest.permissions.contains_key(permission_name)
                 || manifest.permission_sets.contains_key(permission_name)
@@ -263,7 +422,7 @@ pub fn validate_capabilities(
         if !permission_exists {
           let mut available_permissions = Vec::new();
           for (plugin, manifest) in plugin_manifests {
-            if manifest.default_permission.is_some() {
+            if manifest.has_default_permission() {
               available_permissions.push(format!("{plugin}:default"));
             }
             for p in manifest.permissions.keys() {
@@ -274,11 +433,26 @@ pub fn validate_capabilities(
             }
           }
 
-          anyhow::bail!(
+          let error = if plugin_manifests.contains_key(plugin_name) {
+            Error::UnknownPermission {
+              plugin: plugin_name.to_string(),
+              permission: permission_name.to_string(),
+            }
+          } else {
+            Error::UnknownPlugin {
+              plugin: plugin_name.to_string(),
+              available: plugin_manifests
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", "),
+            }
+          };
+          return Err(anyhow::Error::new(error).context(format!(
             "Permission {} not found, expected one of {}",
             permission_id.get(),
             available_permissions.join(", ")
-          );
+          )));
         }
       }
     }
@@ -286,3 +460,311 @@ pub fn validate_capabilities(
 
   Ok(())
 }
+
+/// Writes the spans and debug events of the ACL loading and resolution to the path set by [`TRACE_ENV`],
+/// including the time spent in each span when it closes.
+///
+/// The returned guard removes the subscriber when dropped. Nothing is traced when the variable is not set.
+#[cfg(feature = "tracing")]
+pub fn init_tracing() -> Result<Option<tracing::subscriber::DefaultGuard>> {
+  use tracing_subscriber::{fmt::format::FmtSpan, util::SubscriberInitExt};
+
+  let Some(path) = std::env::var_os(TRACE_ENV) else {
+    return Ok(None);
+  };
+
+  let file = std::fs::File::create(&path).with_context(|| {
+    format!(
+      "failed to create the ACL trace file {}",
+      path.to_string_lossy()
+    )
+  })?;
+  let subscriber = tracing_subscriber::fmt()
+    .with_writer(std::sync::Mutex::new(file))
+    .with_max_level(tracing_subscriber::filter::LevelFilter::DEBUG)
+    .with_span_events(FmtSpan::CLOSE)
+    .with_ansi(false)
+    .finish();
+  Ok(Some(subscriber.set_default()))
+}
+
+/// Writes the ACL error of the build, if any, as JSON diagnostics to the path set by [`DIAGNOSTICS_ENV`].
+///
+/// The file contains a list of [`Diagnostic`]s, empty when the ACL is valid so editors can clear the previous errors.
+/// Relative file paths of the diagnostics are resolved against the crate directory.
+pub fn write_diagnostics(error: Option<&anyhow::Error>) -> Result<()> {
+  let Some(path) = std::env::var_os(DIAGNOSTICS_ENV) else {
+    return Ok(());
+  };
+
+  let diagnostics = error.map(diagnostic).into_iter().collect::<Vec<_>>();
+  std::fs::write(&path, serde_json::to_string_pretty(&diagnostics)?).with_context(|| {
+    format!(
+      "failed to write ACL diagnostics to {}",
+      path.to_string_lossy()
+    )
+  })
+}
+
+fn diagnostic(error: &anyhow::Error) -> Diagnostic {
+  let mut diagnostic = match error.chain().find_map(|e| e.downcast_ref::<Error>()) {
+    Some(error) => Diagnostic::from(error),
+    None => Diagnostic {
+      path: None,
+      range: None,
+      code: DIAGNOSTIC_CODE_ACL_CHECK,
+      message: format!("{error:#}"),
+      related: Vec::new(),
+    },
+  };
+  if let Ok(cwd) = std::env::current_dir() {
+    diagnostic.path = diagnostic.path.map(|path| cwd.join(path));
+  }
+  diagnostic
+}
+
+/// Limits for the size of the resolved ACL scopes.
+///
+/// Each limit applies to the scope of a single command and to the global scope of a plugin.
+/// Scopes exceeding a limit are reported as warnings unless [`Self::deny_excessive`] is set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScopeSizeLimits {
+  max_entries: Option<usize>,
+  max_serialized_size: Option<usize>,
+  deny: bool,
+}
+
+impl ScopeSizeLimits {
+  /// Creates limits that do not restrict the scope size.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the maximum number of allow and deny entries of a scope.
+  #[must_use]
+  pub fn max_entries(mut self, max_entries: usize) -> Self {
+    self.max_entries.replace(max_entries);
+    self
+  }
+
+  /// Sets the maximum size of a scope serialized as JSON, in bytes.
+  #[must_use]
+  pub fn max_serialized_size(mut self, max_serialized_size: usize) -> Self {
+    self.max_serialized_size.replace(max_serialized_size);
+    self
+  }
+
+  /// Fail the build instead of warning when a scope exceeds the limits.
+  #[must_use]
+  pub fn deny_excessive(mut self) -> Self {
+    self.deny = true;
+    self
+  }
+
+  fn exceeded_by(&self, size: &ScopeSize) -> bool {
+    self.max_entries.is_some_and(|max| size.entries > max)
+      || self
+        .max_serialized_size
+        .is_some_and(|max| size.serialized_size > max)
+  }
+}
+
+/// Resolves the ACL to report resolution errors and warnings from the build script.
+///
+/// Also warns about the remote origins granted by the capabilities that the configured CSP blocks.
+pub fn check_resolution(
+  plugin_manifests: &BTreeMap<String, Manifest>,
+  capabilities: &BTreeMap<String, Capability>,
+  target: Target,
+  security: &SecurityConfig,
+  scope_size_limits: &ScopeSizeLimits,
+  scope_hooks: &ScopeHooks,
+  suppressed_warnings: &BTreeSet<String>,
+) -> Result<ResolutionReport> {
+  let (resolved, mut report) = Resolved::resolve_with_hooks(
+    plugin_manifests.clone(),
+    capabilities.clone(),
+    target,
+    scope_hooks,
+  )
+  .context("failed to resolve ACL")?;
+  report.check_connect_src(&resolved, security);
+
+  report
+    .warnings
+    .retain(|warning| !suppressed_warnings.contains(warning.code));
+  for warning in &report.warnings {
+    match warning.severity {
+      WarningSeverity::Normal => println!("cargo:warning={} [{}]", warning.message, warning.code),
+      WarningSeverity::High => println!(
+        "cargo:warning=high severity: {} [{}]",
+        warning.message, warning.code
+      ),
+    }
+  }
+
+  let mut excessive = Vec::new();
+  for (plugin, sizes) in &report.scope_sizes {
+    if scope_size_limits.exceeded_by(&sizes.global_scope) {
+      excessive.push(format!(
+        "global scope of plugin {plugin} has {} entries ({} bytes)",
+        sizes.global_scope.entries, sizes.global_scope.serialized_size
+      ));
+    }
+    for (command, size) in &sizes.commands {
+      if scope_size_limits.exceeded_by(size) {
+        excessive.push(format!(
+          "scope of command {plugin}.{command} has {} entries ({} bytes)",
+          size.entries, size.serialized_size
+        ));
+      }
+    }
+  }
+
+  if scope_size_limits.deny && !excessive.is_empty() {
+    anyhow::bail!(
+      "ACL scopes exceed the size limits:\n{}",
+      excessive.join("\n")
+    );
+  }
+  for message in excessive {
+    println!("cargo:warning={message} exceeding the ACL scope size limits");
+  }
+
+  Ok(report)
+}
+
+/// The capability groups of the dev and release builds, resolved on every build to compare their ACLs,
+/// see [`crate::Attributes::verify_profile_acl`].
+#[derive(Debug, Default, Clone)]
+pub struct ProfileAclVerification {
+  dev_groups: CapabilityGroups,
+  release_groups: CapabilityGroups,
+  allowlist: Option<PathBuf>,
+}
+
+impl ProfileAclVerification {
+  /// Creates a verification enabling all capabilities on both profiles and accepting no difference.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Sets the capability groups enabled on dev builds.
+  #[must_use]
+  pub fn dev_groups(mut self, groups: CapabilityGroups) -> Self {
+    self.dev_groups = groups;
+    self
+  }
+
+  /// Sets the capability groups enabled on release builds.
+  #[must_use]
+  pub fn release_groups(mut self, groups: CapabilityGroups) -> Self {
+    self.release_groups = groups;
+    self
+  }
+
+  /// Sets the path of the file listing the accepted differences, relative to the app manifest directory.
+  ///
+  /// See [`AclDiffAllowlist`] for the file format.
+  #[must_use]
+  pub fn allowlist(mut self, path: impl Into<PathBuf>) -> Self {
+    self.allowlist.replace(path.into());
+    self
+  }
+
+  /// The capability groups of the build profile.
+  pub(crate) fn groups(&self, release: bool) -> &CapabilityGroups {
+    if release {
+      &self.release_groups
+    } else {
+      &self.dev_groups
+    }
+  }
+}
+
+/// Resolves the ACL with the capability groups of the dev and release builds,
+/// failing when they differ by more than the allowlist accepts.
+///
+/// Returns the differences between the dev and release ACLs.
+pub fn verify_profiles(
+  plugin_manifests: &BTreeMap<String, Manifest>,
+  capabilities: &BTreeMap<String, Capability>,
+  target: Target,
+  scope_hooks: &ScopeHooks,
+  verification: &ProfileAclVerification,
+) -> Result<Vec<AclDifference>> {
+  let resolve = |profile: &str, groups: &CapabilityGroups| {
+    let mut capabilities = capabilities.clone();
+    groups.filter(&mut capabilities)?;
+    Resolved::resolve_with_hooks(plugin_manifests.clone(), capabilities, target, scope_hooks)
+      .map(|(resolved, _report)| resolved)
+      .with_context(|| format!("failed to resolve the ACL of {profile} builds"))
+  };
+  let dev = resolve("dev", &verification.dev_groups)?;
+  let release = resolve("release", &verification.release_groups)?;
+  let differences = dev.diff(&release);
+
+  let allowlist = match &verification.allowlist {
+    Some(path) => {
+      println!("cargo:rerun-if-changed={}", path.display());
+      load_allowlist(path)?
+    }
+    None => AclDiffAllowlist::default(),
+  };
+  let unexpected = differences
+    .iter()
+    .filter(|difference| !allowlist.allows(difference))
+    .map(ToString::to_string)
+    .collect::<Vec<_>>();
+  if !unexpected.is_empty() {
+    anyhow::bail!(
+      "the ACL of release builds differs from the ACL of dev builds (dev -> release):\n{}",
+      unexpected.join("\n")
+    );
+  }
+
+  Ok(differences)
+}
+
+fn load_allowlist(path: &Path) -> Result<AclDiffAllowlist> {
+  let allowlist = read_to_string(path)
+    .with_context(|| format!("failed to read ACL diff allowlist {}", path.display()))?;
+  AclDiffAllowlist::parse(&allowlist).map_err(|(line, error)| {
+    anyhow::anyhow!(
+      "invalid pattern on line {line} of ACL diff allowlist {}: {error}",
+      path.display()
+    )
+  })
+}
+
+/// Fails on the capabilities referencing permissions deprecated since the denied version of their plugin or earlier,
+/// see [`crate::Attributes::deny_deprecated_permissions`].
+pub fn check_deprecations(
+  report: &ResolutionReport,
+  denied_deprecations: &BTreeMap<String, String>,
+) -> Result<()> {
+  let mut denied = Vec::new();
+  for (plugin, version) in denied_deprecations {
+    let version = semver::Version::parse(version)
+      .with_context(|| format!("invalid deprecation version {version} of plugin {plugin}"))?;
+    for reference in report.deprecations.iter().filter(|r| &r.plugin == plugin) {
+      // deprecations without a semver version cannot be compared, so they stay warnings
+      if semver::Version::parse(&reference.deprecation.since).is_ok_and(|since| since <= version) {
+        denied.push(format!(
+          "capability {} references {plugin}:{}, which is {}",
+          reference.capability,
+          reference.identifier,
+          reference.deprecation.message(plugin)
+        ));
+      }
+    }
+  }
+  if !denied.is_empty() {
+    anyhow::bail!(
+      "capabilities reference denied deprecated permissions:\n{}",
+      denied.join("\n")
+    );
+  }
+
+  Ok(())
+}
@@ -10,7 +10,10 @@ use std::{
   path::PathBuf,
 };
 use tauri_codegen::{context_codegen, ContextData};
-use tauri_utils::config::{AppUrl, WebviewUrl};
+use tauri_utils::{
+  acl::resolved::AclCodegenDetail,
+  config::{AppUrl, WebviewUrl},
+};
 
 // TODO docs
 /// A builder for generating a Tauri application context during compile time.
@@ -20,6 +23,7 @@ pub struct CodegenContext {
   dev: bool,
   config_path: PathBuf,
   out_file: PathBuf,
+  acl_detail: AclCodegenDetail,
 }
 
 impl Default for CodegenContext {
@@ -28,6 +32,7 @@ impl Default for CodegenContext {
       dev: false,
       config_path: PathBuf::from("tauri.conf.json"),
       out_file: PathBuf::from("tauri-build-context.rs"),
+      acl_detail: AclCodegenDetail::Full,
     }
   }
 }
@@ -74,6 +79,19 @@ impl CodegenContext {
     self
   }
 
+  /// Sets the level of detail of the access control list embedded in the application.
+  ///
+  /// [`AclCodegenDetail::Minimal`] only keeps what the IPC authorization needs, reducing the binary size.
+  /// It does not change which commands are allowed, but the debug messages of denied commands
+  /// can no longer list the permissions referencing them.
+  ///
+  /// Defaults to [`AclCodegenDetail::Full`].
+  #[must_use]
+  pub fn acl_detail(mut self, detail: AclCodegenDetail) -> Self {
+    self.acl_detail = detail;
+    self
+  }
+
   /// Generate the code and write it to the output file - returning the path it was saved to.
   ///
   /// Unless you are doing something special with this builder, you don't need to do anything with
@@ -127,6 +145,7 @@ impl CodegenContext {
       // it's very hard to have a build script for unit tests, so assume this is always called from
       // outside the tauri crate, making the ::tauri root valid.
       root: quote::quote!(::tauri),
+      acl_detail: self.acl_detail,
     })?;
 
     // get the full output file path
@@ -18,12 +18,16 @@ use cargo_toml::Manifest;
 use heck::AsShoutySnakeCase;
 
 use tauri_utils::{
-  acl::build::parse_capabilities,
+  acl::{
+    build::{parse_capabilities, parse_target_capabilities, UnknownFields},
+    resolved::ScopeHooks,
+  },
   config::{BundleResources, Config, WebviewInstallMode},
   resources::{external_binaries, ResourcePaths},
 };
 
 use std::{
+  collections::{BTreeMap, BTreeSet},
   env::var_os,
   fs::copy,
   path::{Path, PathBuf},
@@ -39,12 +43,21 @@ mod manifest;
 pub mod mobile;
 mod static_vcruntime;
 
+pub use acl::{ProfileAclVerification, ScopeSizeLimits};
 #[cfg(feature = "codegen")]
 #[cfg_attr(docsrs, doc(cfg(feature = "codegen")))]
 pub use codegen::context::CodegenContext;
+#[cfg(feature = "codegen")]
+#[cfg_attr(docsrs, doc(cfg(feature = "codegen")))]
+pub use tauri_utils::acl::resolved::AclCodegenDetail;
+pub use tauri_utils::acl::{capability::CapabilityGroups, resolved::ResolvedScope, Number, Value};
 
 const PLUGIN_MANIFESTS_FILE_NAME: &str = "plugin-manifests.json";
 const CAPABILITIES_FILE_NAME: &str = "capabilities.json";
+const SCOPE_OVERRIDES_FILE_NAME: &str = "acl-scope-overrides.json";
+const EXTERNAL_SCOPES_FILE_NAME: &str = "acl-external-scopes.br";
+const RESOLUTION_REPORT_FILE_NAME: &str = "acl-resolution-report.json";
+const PROFILE_DIFF_FILE_NAME: &str = "acl-profile-diff.json";
 
 fn copy_file(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
   let from = from.as_ref();
@@ -340,6 +353,13 @@ pub struct Attributes {
   #[allow(dead_code)]
   windows_attributes: WindowsAttributes,
   capabilities_path_pattern: Option<&'static str>,
+  scope_size_limits: ScopeSizeLimits,
+  scope_hooks: ScopeHooks,
+  capability_groups: CapabilityGroups,
+  suppressed_acl_warnings: BTreeSet<String>,
+  denied_deprecations: BTreeMap<String, String>,
+  unknown_acl_fields: UnknownFields,
+  profile_acl_verification: Option<ProfileAclVerification>,
   #[cfg(feature = "codegen")]
   codegen: Option<codegen::context::CodegenContext>,
 }
@@ -358,12 +378,165 @@ impl Attributes {
   }
 
   /// Set the glob pattern to be used to find the capabilities.
+  ///
+  /// By default all files of the `capabilities` directory are used, except the `desktop` and `mobile`
+  /// subdirectories, which only apply to their targets, see [`parse_target_capabilities`].
+  /// A custom pattern does not apply these conventions.
   #[must_use]
   pub fn capabilities_path_pattern(mut self, pattern: &'static str) -> Self {
     self.capabilities_path_pattern.replace(pattern);
     self
   }
 
+  /// Set the capability groups enabled on this build, e.g. per build flavor.
+  ///
+  /// The capabilities of disabled groups are left out of the ACL,
+  /// and capabilities using a group that is neither enabled nor disabled fail the build.
+  ///
+  /// # Example
+  ///
+  /// ```rust,no_run
+  /// use tauri_build::CapabilityGroups;
+  ///
+  /// let groups = if cfg!(feature = "pro") {
+  ///   CapabilityGroups::new().enable("free").enable("pro")
+  /// } else {
+  ///   CapabilityGroups::new().enable("free").disable("pro")
+  /// };
+  /// let attrs = tauri_build::Attributes::new().capability_groups(groups);
+  /// tauri_build::try_build(attrs).expect("failed to run build script");
+  /// ```
+  #[must_use]
+  pub fn capability_groups(mut self, groups: CapabilityGroups) -> Self {
+    self.capability_groups = groups;
+    self
+  }
+
+  /// Set the limits for the size of the resolved ACL scopes.
+  #[must_use]
+  pub fn scope_size_limits(mut self, limits: ScopeSizeLimits) -> Self {
+    self.scope_size_limits = limits;
+    self
+  }
+
+  /// Suppress the ACL resolution warnings with the given code, e.g. `allow-deny-overlap`.
+  ///
+  /// Suppressed warnings are neither printed nor included in the resolution report.
+  #[must_use]
+  pub fn suppress_acl_warning(mut self, code: impl Into<String>) -> Self {
+    self.suppressed_acl_warnings.insert(code.into());
+    self
+  }
+
+  /// Fail the build when a capability references a permission or permission set of the plugin
+  /// deprecated since the given plugin version or earlier.
+  ///
+  /// Lets apps give their capabilities a grace period to migrate away from deprecated permissions,
+  /// which are otherwise reported with the `deprecated-permission` warning.
+  ///
+  /// # Example
+  ///
+  /// ```rust,no_run
+  /// let attrs = tauri_build::Attributes::new().deny_deprecated_permissions("fs", "2.1.0");
+  /// tauri_build::try_build(attrs).expect("failed to run build script");
+  /// ```
+  #[must_use]
+  pub fn deny_deprecated_permissions(
+    mut self,
+    plugin: impl Into<String>,
+    version: impl Into<String>,
+  ) -> Self {
+    self
+      .denied_deprecations
+      .insert(plugin.into(), version.into());
+    self
+  }
+
+  /// Fail the build on the fields of the capability and plugin permission files that Tauri does not know,
+  /// e.g. a misspelled `"permisions"` that would otherwise be silently ignored.
+  ///
+  /// Each unknown field is reported with its line and the closest known field.
+  /// Vendor-specific `x-` fields are always accepted. Disabled by default.
+  #[must_use]
+  pub fn strict_acl(mut self, strict: bool) -> Self {
+    self.unknown_acl_fields = if strict {
+      UnknownFields::Deny
+    } else {
+      UnknownFields::Ignore
+    };
+    self
+  }
+
+  /// Verify that the ACL of release builds only differs from the ACL of dev builds as expected,
+  /// e.g. by the commands of the capabilities only enabled on dev builds.
+  ///
+  /// Both ACLs are resolved on every build with the capability groups of their profile,
+  /// which replace the [`Self::capability_groups`] of the build.
+  /// Their semantic differences are written to the `acl-profile-diff.json` file of the build output directory,
+  /// and the build fails on the differences that are not listed in the allowlist file.
+  ///
+  /// # Example
+  ///
+  /// ```rust,no_run
+  /// use tauri_build::{CapabilityGroups, ProfileAclVerification};
+  ///
+  /// let verification = ProfileAclVerification::new()
+  ///   .dev_groups(CapabilityGroups::new().enable("devtools"))
+  ///   .release_groups(CapabilityGroups::new().disable("devtools"))
+  ///   .allowlist("acl-diff-allowlist.txt");
+  /// let attrs = tauri_build::Attributes::new().verify_profile_acl(verification);
+  /// tauri_build::try_build(attrs).expect("failed to run build script");
+  /// ```
+  #[must_use]
+  pub fn verify_profile_acl(mut self, verification: ProfileAclVerification) -> Self {
+    self.profile_acl_verification.replace(verification);
+    self
+  }
+
+  /// Register a hook to post-process the resolved ACL scopes of a plugin.
+  ///
+  /// The hook runs after the scopes of all capabilities are merged, receiving the plugin name,
+  /// the command name or `None` for the plugin global scope, and the scope to rewrite.
+  /// Returning an error fails the build. Plugins can expose their hook from their crate
+  /// so apps register it in their build script.
+  ///
+  /// # Example
+  ///
+  /// Lowercase the host of the `url` scope entries of the `http` plugin:
+  ///
+  /// ```rust,no_run
+  /// use tauri_build::{ResolvedScope, Value};
+  ///
+  /// fn lowercase_url_hosts(
+  ///   _plugin: &str,
+  ///   _command: Option<&str>,
+  ///   scope: &mut ResolvedScope,
+  /// ) -> Result<(), String> {
+  ///   for entry in scope.allow.iter_mut().chain(scope.deny.iter_mut()) {
+  ///     if let Value::Map(entry) = entry {
+  ///       if let Some(Value::String(url)) = entry.get_mut("url") {
+  ///         if let Some((scheme, rest)) = url.split_once("://") {
+  ///           let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+  ///           *url = format!("{scheme}://{}{path}", host.to_lowercase());
+  ///         }
+  ///       }
+  ///     }
+  ///   }
+  ///   Ok(())
+  /// }
+  ///
+  /// let attrs = tauri_build::Attributes::new().plugin_scope_hook("http", lowercase_url_hosts);
+  /// tauri_build::try_build(attrs).expect("failed to run build script");
+  /// ```
+  #[must_use]
+  pub fn plugin_scope_hook<F>(mut self, plugin: impl Into<String>, hook: F) -> Self
+  where
+    F: Fn(&str, Option<&str>, &mut ResolvedScope) -> std::result::Result<(), String> + 'static,
+  {
+    self.scope_hooks.insert(plugin, hook);
+    self
+  }
+
   #[cfg(feature = "codegen")]
   #[cfg_attr(docsrs, doc(cfg(feature = "codegen")))]
   #[must_use]
@@ -373,6 +546,89 @@ impl Attributes {
   }
 }
 
+/// Loads, validates and resolves the ACL, writing the files used by the code generation.
+fn build_acl(
+  attributes: &Attributes,
+  target: tauri_utils::platform::Target,
+  config: &Config,
+  out_dir: &Path,
+) -> Result<()> {
+  let plugin_manifests = acl::get_plugin_manifests(attributes.unknown_acl_fields)?;
+  std::fs::write(
+    out_dir.join(PLUGIN_MANIFESTS_FILE_NAME),
+    serde_json::to_string(&plugin_manifests)?,
+  )?;
+  let (mut capabilities, overridden_capabilities) =
+    if let Some(pattern) = attributes.capabilities_path_pattern {
+      (
+        parse_capabilities(pattern, attributes.unknown_acl_fields)?,
+        Vec::new(),
+      )
+    } else {
+      parse_target_capabilities("capabilities", target, attributes.unknown_acl_fields)?
+    };
+  let profile_diff = attributes
+    .profile_acl_verification
+    .as_ref()
+    .map(|verification| {
+      acl::verify_profiles(
+        &plugin_manifests,
+        &capabilities,
+        target,
+        &attributes.scope_hooks,
+        verification,
+      )
+    })
+    .transpose()?;
+  let capability_groups = attributes
+    .profile_acl_verification
+    .as_ref()
+    .map_or(&attributes.capability_groups, |verification| {
+      verification.groups(has_feature("custom-protocol"))
+    });
+  let filtered_capabilities = capability_groups.filter(&mut capabilities)?;
+  acl::generate_schema(&plugin_manifests, target)?;
+  acl::validate_capabilities(&plugin_manifests, &capabilities)?;
+  let mut resolution_report = acl::check_resolution(
+    &plugin_manifests,
+    &capabilities,
+    target,
+    &config.tauri.security,
+    &attributes.scope_size_limits,
+    &attributes.scope_hooks,
+    &attributes.suppressed_acl_warnings,
+  )?;
+  acl::check_deprecations(&resolution_report, &attributes.denied_deprecations)?;
+  resolution_report.filtered_capabilities = filtered_capabilities;
+  resolution_report.overridden_capabilities = overridden_capabilities;
+  std::fs::write(
+    out_dir.join(SCOPE_OVERRIDES_FILE_NAME),
+    serde_json::to_string(&resolution_report.scope_overrides)?,
+  )?;
+  // always written so apps can embed it before declaring an external scope
+  std::fs::write(
+    out_dir.join(EXTERNAL_SCOPES_FILE_NAME),
+    resolution_report.external_scopes.compress()?,
+  )?;
+  std::fs::write(
+    out_dir.join(RESOLUTION_REPORT_FILE_NAME),
+    serde_json::to_string_pretty(&resolution_report)?,
+  )?;
+  if let Some(profile_diff) = profile_diff {
+    std::fs::write(
+      out_dir.join(PROFILE_DIFF_FILE_NAME),
+      serde_json::to_string_pretty(&profile_diff)?,
+    )?;
+  }
+
+  let capabilities_path = acl::save_capabilities(&capabilities)?;
+  copy(capabilities_path, out_dir.join(CAPABILITIES_FILE_NAME))?;
+
+  acl::save_plugin_manifests(&plugin_manifests)?;
+
+  Ok(())
+}
+
 /// Run all build time helpers for your Tauri Application.
 ///
 /// The current helpers include the following:
@@ -407,17 +663,28 @@ pub fn build() {
 }
 
 /// Non-panicking [`build()`].
+///
+/// Set the `TAURI_ACL_DIAGNOSTICS` environment variable to a file path to also get the ACL loading and resolution errors
+/// as JSON, e.g. for editors to annotate the capability and permission files. The file contains a list of objects with
+/// the `path` and byte `range` of the error when known, its stable `code`, its `message`
+/// and the `related` plugin, permission and capability identifiers.
 #[allow(unused_variables)]
 pub fn try_build(attributes: Attributes) -> Result<()> {
   use anyhow::anyhow;
 
   println!("cargo:rerun-if-env-changed=TAURI_CONFIG");
+  println!("cargo:rerun-if-env-changed={}", acl::DIAGNOSTICS_ENV);
+  #[cfg(feature = "tracing")]
+  println!("cargo:rerun-if-env-changed={}", acl::TRACE_ENV);
   println!("cargo:rerun-if-changed=tauri.conf.json");
   #[cfg(feature = "config-json5")]
   println!("cargo:rerun-if-changed=tauri.conf.json5");
   #[cfg(feature = "config-toml")]
   println!("cargo:rerun-if-changed=Tauri.toml");
 
+  #[cfg(feature = "tracing")]
+  let _tracing = acl::init_tracing()?;
+
   let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap();
   let mobile = target_os == "ios" || target_os == "android";
   cfg_alias("desktop", !mobile);
@@ -471,23 +738,9 @@ pub fn try_build(attributes: Attributes) -> Result<()> {
   let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
 
   manifest::check(&config, &mut manifest)?;
-  let plugin_manifests = acl::get_plugin_manifests()?;
-  std::fs::write(
-    out_dir.join(PLUGIN_MANIFESTS_FILE_NAME),
-    serde_json::to_string(&plugin_manifests)?,
-  )?;
-  let capabilities = if let Some(pattern) = attributes.capabilities_path_pattern {
-    parse_capabilities(pattern)?
-  } else {
-    parse_capabilities("./capabilities/**/*")?
-  };
-  acl::generate_schema(&plugin_manifests, target)?;
-  acl::validate_capabilities(&plugin_manifests, &capabilities)?;
-
-  let capabilities_path = acl::save_capabilities(&capabilities)?;
-  copy(capabilities_path, out_dir.join(CAPABILITIES_FILE_NAME))?;
-
-  acl::save_plugin_manifests(&plugin_manifests)?;
+  let acl = build_acl(&attributes, target, &config, &out_dir);
+  acl::write_diagnostics(acl.as_ref().err())?;
+  acl?;
 
   println!("cargo:rustc-env=TAURI_ENV_TARGET_TRIPLE={target_triple}");
 
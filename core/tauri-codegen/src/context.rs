@@ -13,7 +13,9 @@ use sha2::{Digest, Sha256};
 
 use tauri_utils::acl::capability::Capability;
 use tauri_utils::acl::plugin::Manifest;
-use tauri_utils::acl::resolved::Resolved;
+use tauri_utils::acl::resolved::{
+  external_scope_plugins, AclCodegenDetail, Resolved, ScopeOverrides,
+};
 use tauri_utils::assets::AssetKey;
 use tauri_utils::config::{AppUrl, Config, PatternKind, WebviewUrl};
 use tauri_utils::html::{
@@ -25,6 +27,7 @@ use crate::embedded_assets::{AssetOptions, CspHashes, EmbeddedAssets, EmbeddedAs
 
 const PLUGIN_MANIFESTS_FILE_NAME: &str = "plugin-manifests.json";
 const CAPABILITIES_FILE_NAME: &str = "capabilities.json";
+const SCOPE_OVERRIDES_FILE_NAME: &str = "acl-scope-overrides.json";
 
 /// Necessary data needed by [`context_codegen`] to generate code for a Tauri application context.
 pub struct ContextData {
@@ -32,6 +35,8 @@ pub struct ContextData {
   pub config: Config,
   pub config_parent: PathBuf,
   pub root: TokenStream,
+  /// Level of detail of the generated ACL.
+  pub acl_detail: AclCodegenDetail,
 }
 
 fn map_core_assets(
@@ -126,6 +131,7 @@ pub fn context_codegen(data: ContextData) -> Result<TokenStream, EmbeddedAssetsE
     config,
     config_parent,
     root,
+    acl_detail,
   } = data;
 
   let target = std::env::var("TARGET")
@@ -398,7 +404,24 @@ pub fn context_codegen(data: ContextData) -> Result<TokenStream, EmbeddedAssetsE
     Default::default()
   };
 
-  let resolved_act = Resolved::resolve(acl, capabilities, target).expect("failed to resolve ACL");
+  let external_scope_plugins = external_scope_plugins(&acl);
+  let mut resolved_act =
+    Resolved::resolve(acl, capabilities, target).expect("failed to resolve ACL");
+
+  // scopes rewritten by the hooks registered in the build script
+  let scope_overrides_file_path = out_dir.join(SCOPE_OVERRIDES_FILE_NAME);
+  if scope_overrides_file_path.exists() {
+    let scope_overrides_file = std::fs::read_to_string(scope_overrides_file_path)
+      .expect("failed to read ACL scope overrides");
+    let scope_overrides: ScopeOverrides =
+      serde_json::from_str(&scope_overrides_file).expect("failed to parse ACL scope overrides");
+    resolved_act.apply_scope_overrides(&scope_overrides);
+  }
+
+  // the build script stores the values of the external scopes in an asset the app embeds
+  resolved_act.externalize_scopes(&external_scope_plugins);
+
+  let resolved_act = resolved_act.into_tokens(acl_detail);
 
   Ok(quote!({
     #[allow(unused_mut, clippy::let_and_return)]
@@ -83,6 +83,7 @@ pub(crate) fn generate_context(context: ContextItems) -> TokenStream {
       config,
       config_parent,
       root: context.root.to_token_stream(),
+      acl_detail: Default::default(),
     })
     .and_then(|data| context_codegen(data).map_err(|e| e.to_string()));
 
@@ -60,6 +60,12 @@ impl<'a> Builder<'a> {
     }
 
     let permissions = acl::build::define_permissions("./permissions/**/*.*", &name, &out_dir)?;
+    acl::build::define_plugin_version(&build_var("CARGO_PKG_VERSION")?, &name);
+    acl::build::validate_command_aliases(
+      name.strip_prefix("tauri-plugin-").unwrap_or(&name),
+      &permissions,
+      self.commands,
+    )?;
 
     acl::build::generate_schema(&permissions, "./permissions")?;
 
@@ -108,11 +108,13 @@ pub struct PendingWebview<T: UserEvent, R: Runtime<T>> {
 
 impl<T: UserEvent, R: Runtime<T>> PendingWebview<T, R> {
   /// Create a new [`PendingWebview`] with a label from the given [`WebviewAttributes`].
+  ///
+  /// The label is normalized with [`tauri_utils::normalize_label`], see [`is_label_valid`] for the allowed characters.
   pub fn new(
     webview_attributes: WebviewAttributes,
     label: impl Into<String>,
   ) -> crate::Result<Self> {
-    let label = label.into();
+    let label = tauri_utils::normalize_label(&label.into()).into_owned();
     if !is_label_valid(&label) {
       Err(crate::Error::InvalidWindowLabel)
     } else {
@@ -404,6 +404,12 @@ pub struct PendingWindow<T: UserEvent, R: Runtime<T>> {
   pub webview: Option<PendingWebview<T, R>>,
 }
 
+/// Checks if the label is a valid window or webview label.
+///
+/// A label can only include Unicode alphanumeric characters, `-`, `/`, `:` and `_`, so emojis and whitespace are rejected.
+/// The window and webview labels are normalized with [`tauri_utils::normalize_label`] before this check,
+/// which composes the letters spelled with combining marks, e.g. `e` followed by U+0301, into their alphanumeric form.
+/// Labels are case-sensitive.
 pub fn is_label_valid(label: &str) -> bool {
   label
     .chars()
@@ -419,11 +425,13 @@ pub fn assert_label_is_valid(label: &str) {
 
 impl<T: UserEvent, R: Runtime<T>> PendingWindow<T, R> {
   /// Create a new [`PendingWindow`] with a label from the given [`WindowBuilder`].
+  ///
+  /// The label is normalized with [`tauri_utils::normalize_label`], see [`is_label_valid`] for the allowed characters.
   pub fn new(
     window_builder: <R::WindowDispatcher as WindowDispatch<T>>::WindowBuilder,
     label: impl Into<String>,
   ) -> crate::Result<Self> {
-    let label = label.into();
+    let label = tauri_utils::normalize_label(&label.into()).into_owned();
     if !is_label_valid(&label) {
       Err(crate::Error::InvalidWindowLabel)
     } else {
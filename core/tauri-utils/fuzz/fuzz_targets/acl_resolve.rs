@@ -0,0 +1,16 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Resolves generated plugin manifests and capabilities, checking the invariants of the resolved ACL.
+//!
+//! Run with `cargo +nightly fuzz run acl_resolve` from the `core/tauri-utils` directory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tauri_utils::acl::arbitrary::AclInput;
+
+fuzz_target!(|input: AclInput| {
+  input.assert_invariants();
+});
@@ -0,0 +1,408 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! [`Arbitrary`] implementations generating structurally valid ACL inputs, for fuzzing and property testing.
+//!
+//! The identifiers, commands, window labels and domains are picked from small vocabularies,
+//! so the generated capabilities reference the permissions of the generated manifests
+//! and the resolution gets past the reference checks most of the time.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::{
+  capability::{
+    Capability, CapabilityContext, CapabilityWindow, PermissionEntry, RemoteAttestation,
+    RemoteDomain,
+  },
+  plugin::{DefaultPermission, Manifest, PermissionFile},
+  resolved::Resolved,
+  Commands, ExecutionContext, Identifier, Number, Permission, Scopes, Value,
+};
+use crate::platform::Target;
+
+/// The plugins of the generated [`AclInput`].
+pub const PLUGINS: &[&str] = &["fs", "http"];
+/// The permission identifiers of the generated manifests, without the plugin prefix.
+pub const PERMISSIONS: &[&str] = &["allow-read", "allow-write", "deny-write", "read-write"];
+/// The command names the generated permissions allow or deny, including a glob pattern.
+pub const COMMANDS: &[&str] = &["read", "read_file", "write", "remove", "read_*"];
+/// The window label patterns of the generated capabilities.
+pub const WINDOWS: &[&str] = &["main", "editor-*", "*"];
+/// The remote domain patterns of the generated capabilities.
+pub const DOMAINS: &[&str] = &["tauri.app", "*.tauri.app"];
+
+const CAPABILITIES: &[&str] = &["main", "editor", "remote", "extra"];
+const SCOPE_KEYS: &[&str] = &["path", "url", "recursive"];
+const SCOPE_STRINGS: &[&str] = &["$APPDATA/**", "$HOME/*", "https://tauri.app/*"];
+const MAX_VALUE_DEPTH: usize = 3;
+const MAX_ENTRIES: usize = 4;
+
+fn pick<'a>(u: &mut Unstructured<'_>, values: &[&'a str]) -> Result<&'a str> {
+  u.choose(values).copied()
+}
+
+fn entries<'a, T>(
+  u: &mut Unstructured<'a>,
+  min: usize,
+  mut f: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+  let len = u.int_in_range(min..=MAX_ENTRIES)?;
+  (0..len).map(|_| f(u)).collect()
+}
+
+fn value(u: &mut Unstructured<'_>, depth: usize) -> Result<Value> {
+  let kinds = if depth >= MAX_VALUE_DEPTH { 4 } else { 6 };
+  Ok(match u.int_in_range(0..=kinds - 1)? {
+    0 => Value::Null,
+    1 => Value::Bool(u.arbitrary()?),
+    2 => Value::Number(Number::Int(u.int_in_range(-2..=2)?)),
+    3 => Value::String(pick(u, SCOPE_STRINGS)?.into()),
+    4 => Value::List(entries(u, 0, |u| value(u, depth + 1))?),
+    _ => Value::Map(
+      entries(u, 0, |u| {
+        Ok((pick(u, SCOPE_KEYS)?.to_string(), value(u, depth + 1)?))
+      })?
+      .into_iter()
+      .collect(),
+    ),
+  })
+}
+
+/// A scope entry, an object with a `path` most of the time so entries overlap across permissions.
+fn scope_entry(u: &mut Unstructured<'_>) -> Result<Value> {
+  if u.ratio(3, 4)? {
+    let mut entry = BTreeMap::new();
+    entry.insert("path".into(), Value::String(pick(u, SCOPE_STRINGS)?.into()));
+    if u.arbitrary()? {
+      entry.insert("recursive".into(), Value::Bool(u.arbitrary()?));
+    }
+    Ok(Value::Map(entry))
+  } else {
+    value(u, 0)
+  }
+}
+
+fn identifier(plugin: &str, permission: &str) -> Identifier {
+  Identifier::try_from(format!("{plugin}:{permission}"))
+    .expect("the vocabulary identifiers are valid")
+}
+
+fn platforms(u: &mut Unstructured<'_>) -> Result<Vec<Target>> {
+  let mut platforms = entries(u, 0, Target::arbitrary)?;
+  platforms.dedup();
+  Ok(platforms)
+}
+
+impl<'a> Arbitrary<'a> for Value {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    value(u, 0)
+  }
+}
+
+impl<'a> Arbitrary<'a> for Target {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    u.choose(&[
+      Self::MacOS,
+      Self::Windows,
+      Self::Linux,
+      Self::Android,
+      Self::Ios,
+    ])
+    .copied()
+  }
+}
+
+impl<'a> Arbitrary<'a> for Identifier {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let plugin = pick(u, PLUGINS)?;
+    let permission = if u.ratio(1, 4)? {
+      "default"
+    } else {
+      pick(u, PERMISSIONS)?
+    };
+    Ok(identifier(plugin, permission))
+  }
+}
+
+impl<'a> Arbitrary<'a> for Scopes {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let mut scopes = || -> Result<Option<Vec<Value>>> {
+      Ok(if u.arbitrary()? {
+        Some(entries(u, 0, scope_entry)?)
+      } else {
+        None
+      })
+    };
+    Ok(Self {
+      allow: scopes()?,
+      deny: scopes()?,
+    })
+  }
+}
+
+impl<'a> Arbitrary<'a> for Commands {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let mut commands = || entries(u, 0, |u| pick(u, COMMANDS).map(Into::into));
+    Ok(Self {
+      allow: commands()?,
+      deny: commands()?,
+    })
+  }
+}
+
+impl<'a> Arbitrary<'a> for Permission {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    Ok(Self {
+      version: None,
+      identifier: pick(u, PERMISSIONS)?.into(),
+      description: None,
+      commands: u.arbitrary()?,
+      scope: u.arbitrary()?,
+      class: None,
+      validator: None,
+      args: Vec::new(),
+      requires_scope: u.ratio(1, 8)?,
+      platforms: if u.ratio(1, 4)? {
+        Some(platforms(u)?)
+      } else {
+        None
+      },
+      returns: Vec::new(),
+      delegable: false,
+      deprecated: None,
+    })
+  }
+}
+
+impl<'a> Arbitrary<'a> for PermissionEntry {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let identifier = u.arbitrary()?;
+    if u.arbitrary()? {
+      Ok(Self::PermissionRef(identifier))
+    } else {
+      Ok(Self::ExtendedPermission {
+        identifier,
+        scope: u.arbitrary()?,
+        subtract: u.ratio(1, 8)?,
+        include_deny: false,
+        config: None,
+        plugin_version: None,
+        extensions: BTreeMap::new(),
+      })
+    }
+  }
+}
+
+impl<'a> Arbitrary<'a> for CapabilityWindow {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let pattern = pick(u, WINDOWS)?.to_string();
+    Ok(match u.int_in_range(0..=3)? {
+      0 => Self::ParentOf { parent_of: pattern },
+      1 => Self::DescendantOf {
+        descendant_of: pattern,
+      },
+      _ => Self::Label(pattern),
+    })
+  }
+}
+
+impl<'a> Arbitrary<'a> for CapabilityContext {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    if u.ratio(2, 3)? {
+      Ok(Self::Local)
+    } else {
+      Ok(Self::Remote {
+        domains: entries(u, 1, |u| {
+          Ok(RemoteDomain::Pattern(pick(u, DOMAINS)?.into()))
+        })?,
+        attestation: RemoteAttestation::default(),
+      })
+    }
+  }
+}
+
+impl<'a> Arbitrary<'a> for Capability {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    Ok(Self {
+      identifier: pick(u, CAPABILITIES)?.into(),
+      description: String::new(),
+      context: u.arbitrary()?,
+      windows: entries(u, 1, CapabilityWindow::arbitrary)?,
+      permissions: entries(u, 1, PermissionEntry::arbitrary)?,
+      platforms: if u.ratio(1, 4)? {
+        platforms(u)?
+      } else {
+        super::capability::default_platforms()
+      },
+      group: None,
+      inherit_from_window: false,
+      roles: Vec::new(),
+      attenuate: None,
+      pages: Vec::new(),
+      plugin_version: Default::default(),
+      extensions: BTreeMap::new(),
+    })
+  }
+}
+
+/// The plugin manifests, capabilities and target of an ACL resolution.
+#[derive(Debug)]
+pub struct AclInput {
+  /// The plugin manifests, one for each of [`PLUGINS`] defining each of [`PERMISSIONS`].
+  pub acl: BTreeMap<String, Manifest>,
+  /// The capabilities, keyed by identifier.
+  pub capabilities: BTreeMap<String, Capability>,
+  /// The target the ACL is resolved for.
+  pub target: Target,
+}
+
+impl<'a> Arbitrary<'a> for AclInput {
+  fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+    let mut acl = BTreeMap::new();
+    for plugin in PLUGINS {
+      let permission_file = PermissionFile {
+        default: Some(DefaultPermission {
+          version: None,
+          description: None,
+          permissions: entries(u, 0, |u| pick(u, PERMISSIONS).map(Into::into))?,
+        }),
+        set: Vec::new(),
+        // every identifier of the vocabulary is defined, so the capabilities can reference them
+        permission: PERMISSIONS
+          .iter()
+          .map(|identifier| {
+            Ok(Permission {
+              identifier: identifier.to_string(),
+              ..u.arbitrary()?
+            })
+          })
+          .collect::<Result<_>>()?,
+        window_prefixes: Vec::new(),
+        merge_key: if u.arbitrary()? {
+          Some("path".into())
+        } else {
+          None
+        },
+        required_deny: Vec::new(),
+        external_scope: false,
+        global_api: BTreeMap::new(),
+        shares_scope_with: Vec::new(),
+        aliases: BTreeMap::new(),
+        presets: BTreeMap::new(),
+      };
+      acl.insert(
+        plugin.to_string(),
+        Manifest::new(vec![permission_file], None),
+      );
+    }
+
+    let capabilities = entries(u, 1, Capability::arbitrary)?
+      .into_iter()
+      .map(|capability| (capability.identifier.clone(), capability))
+      .collect();
+
+    Ok(Self {
+      acl,
+      capabilities,
+      target: u.arbitrary()?,
+    })
+  }
+}
+
+impl AclInput {
+  /// Resolves the ACL and checks the invariants of the resolution.
+  ///
+  /// A resolution error is fine, the generated inputs are structurally valid but can still be rejected.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the resolution panics or if the resolved ACL breaks one of these invariants:
+  ///
+  /// - the allowed and denied commands are commands of the plugins of the ACL,
+  ///   and their remote contexts are domains of the capabilities;
+  /// - the scope keys of the allowed and denied commands are present in [`Resolved::command_scope`];
+  /// - resolving the same input again gives the same resolved ACL.
+  pub fn assert_invariants(&self) {
+    let resolve = || Resolved::resolve(self.acl.clone(), self.capabilities.clone(), self.target);
+    let Ok(resolved) = resolve() else {
+      return;
+    };
+
+    let domains = self
+      .capabilities
+      .values()
+      .filter_map(|capability| match &capability.context {
+        CapabilityContext::Remote { domains, .. } => Some(domains),
+        CapabilityContext::Local => None,
+      })
+      .flatten()
+      .flat_map(RemoteDomain::patterns)
+      .collect::<BTreeSet<_>>();
+
+    for (key, command) in resolved
+      .allowed_commands
+      .iter()
+      .chain(&resolved.denied_commands)
+    {
+      let plugin = key
+        .name
+        .strip_prefix(super::PLUGIN_COMMAND_PREFIX)
+        .and_then(|name| name.split_once(super::PLUGIN_COMMAND_SEPARATOR))
+        .map(|(plugin, _)| plugin);
+      assert!(
+        plugin.is_some_and(|plugin| self.acl.contains_key(plugin)),
+        "{} is not a command of the ACL plugins",
+        key.name
+      );
+      if let ExecutionContext::Remote { domain, .. } = &key.context {
+        assert!(
+          domains.contains(domain.as_str()),
+          "{} is allowed on {domain}, which no capability references",
+          key.name
+        );
+      }
+      for scope in &command.scopes {
+        assert!(
+          resolved.command_scope.contains_key(&scope.scope),
+          "the scope {} of {} is not in the command scopes",
+          scope.scope,
+          key.name
+        );
+      }
+    }
+
+    let again = resolve().expect("resolving the same input again failed");
+    assert_eq!(
+      format!("{resolved:?}"),
+      format!("{again:?}"),
+      "resolving the same input again gave a different ACL"
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use arbitrary::{Arbitrary, Unstructured};
+
+  use super::AclInput;
+
+  #[test]
+  fn resolution_invariants() {
+    // a fixed pseudo-random byte stream, the fuzz target explores the inputs further
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut bytes = vec![0u8; 4096];
+    for _ in 0..256 {
+      for byte in &mut bytes {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        *byte = state as u8;
+      }
+      let mut u = Unstructured::new(&bytes);
+      AclInput::arbitrary(&mut u).unwrap().assert_invariants();
+    }
+  }
+}
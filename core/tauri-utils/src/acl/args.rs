@@ -0,0 +1,626 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Declarative constraints on the arguments of the commands, see [`Permission#structfield.args`](super::Permission#structfield.args).
+//!
+//! The rules are checked on the IPC payload after the ACL allows a request, before the command handler runs,
+//! so simple argument checks do not need scope-checking code in every command.
+//! The commands keep full access to their scopes for anything more complex.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+use super::{Number, Value};
+
+/// A constraint on an argument of the commands allowed by a permission.
+///
+/// ```toml
+/// [[permission]]
+/// identifier = "allow-set-size"
+/// commands.allow = ["set_size"]
+///
+/// [[permission.args]]
+/// name = "width"
+/// kind = "range"
+/// min = 1
+/// max = 4096
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ArgumentRule {
+  /// The argument name, as sent by the frontend, e.g. `filePath` for a `file_path` command argument.
+  pub name: String,
+  /// Whether the request can omit the argument or set it to `null`.
+  #[serde(default)]
+  pub optional: bool,
+  /// How the argument value is checked.
+  #[serde(flatten)]
+  pub matcher: ArgumentMatcher,
+}
+
+// permission files cannot hold NaN numbers, so the rules are always equal to themselves
+impl Eq for ArgumentRule {}
+
+/// How an argument is checked, see [`ArgumentRule`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ArgumentMatcher {
+  /// A path allowed by the command and global scopes of the calling window.
+  ///
+  /// The scope entries are glob patterns, either strings or objects with a `path` field,
+  /// and can start with a base directory variable, e.g. `$APPDATA/**`. The deny entries take precedence.
+  PathScope,
+  /// A URL allowed by the command and global scopes of the calling window.
+  ///
+  /// The scope entries are glob patterns, either strings or objects with a `url` field. The deny entries take precedence.
+  UrlScope,
+  /// One of the values, compared exactly.
+  Enum {
+    /// The allowed values.
+    values: Vec<Value>,
+  },
+  /// A number within the inclusive bounds.
+  Range {
+    /// The lower bound.
+    #[serde(default)]
+    min: Option<Number>,
+    /// The upper bound.
+    #[serde(default)]
+    max: Option<Number>,
+  },
+}
+
+impl ArgumentMatcher {
+  /// Whether the rule is checked against the scopes of the command.
+  pub fn is_scope(&self) -> bool {
+    matches!(self, Self::PathScope | Self::UrlScope)
+  }
+}
+
+impl fmt::Display for ArgumentMatcher {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::PathScope => "path-scope",
+      Self::UrlScope => "url-scope",
+      Self::Enum { .. } => "enum",
+      Self::Range { .. } => "range",
+    })
+  }
+}
+
+impl ArgumentRule {
+  /// Checks that the rule can match a value, returning why it cannot.
+  pub fn validate(&self) -> Result<(), String> {
+    if self.name.is_empty() {
+      return Err("the argument name is empty".into());
+    }
+    match &self.matcher {
+      ArgumentMatcher::Enum { values } if values.is_empty() => {
+        Err(format!("enum rule of argument {} has no values", self.name))
+      }
+      ArgumentMatcher::Range {
+        min: None,
+        max: None,
+      } => Err(format!(
+        "range rule of argument {} has no bounds",
+        self.name
+      )),
+      ArgumentMatcher::Range {
+        min: Some(min),
+        max: Some(max),
+      } if as_f64(min) > as_f64(max) => Err(format!(
+        "range rule of argument {} has a lower bound above its upper bound",
+        self.name
+      )),
+      _ => Ok(()),
+    }
+  }
+
+  /// Checks the argument in the IPC payload, using `in_scope` for the [`ArgumentMatcher::PathScope`]
+  /// and [`ArgumentMatcher::UrlScope`] rules.
+  ///
+  /// The arguments that no rule references are not checked.
+  pub fn check(
+    &self,
+    args: &serde_json::Map<String, JsonValue>,
+    in_scope: impl FnOnce(&ArgumentMatcher, &str) -> bool,
+  ) -> Result<(), ArgumentError> {
+    let argument = || self.name.clone();
+    let value = match args.get(&self.name) {
+      None | Some(JsonValue::Null) if self.optional => return Ok(()),
+      None | Some(JsonValue::Null) => {
+        return Err(ArgumentError::Missing {
+          argument: argument(),
+        })
+      }
+      Some(value) => value,
+    };
+
+    match &self.matcher {
+      ArgumentMatcher::PathScope | ArgumentMatcher::UrlScope => {
+        let value = value.as_str().ok_or_else(|| ArgumentError::InvalidType {
+          argument: argument(),
+          expected: "string".into(),
+        })?;
+        if !in_scope(&self.matcher, value) {
+          return Err(ArgumentError::OutOfScope {
+            argument: argument(),
+          });
+        }
+      }
+      ArgumentMatcher::Enum { values } => {
+        if !values.contains(&Value::from(value.clone())) {
+          return Err(ArgumentError::NotAllowed {
+            argument: argument(),
+          });
+        }
+      }
+      ArgumentMatcher::Range { min, max } => {
+        let number = value.as_f64().ok_or_else(|| ArgumentError::InvalidType {
+          argument: argument(),
+          expected: "number".into(),
+        })?;
+        let above_min = min.as_ref().map_or(true, |min| number >= as_f64(min));
+        let below_max = max.as_ref().map_or(true, |max| number <= as_f64(max));
+        if !(above_min && below_max) {
+          return Err(ArgumentError::OutOfRange {
+            argument: argument(),
+            min: min.map(|min| as_f64(&min)),
+            max: max.map(|max| as_f64(&max)),
+          });
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Checks the IPC payload of a command against its argument rules, returning the first violation.
+///
+/// The payload must be a JSON object when there is any rule, `None` standing for a raw payload.
+/// The arguments that no rule references are not checked.
+pub fn check_arguments(
+  rules: &[ArgumentRule],
+  payload: Option<&JsonValue>,
+  mut in_scope: impl FnMut(&ArgumentMatcher, &str) -> bool,
+) -> Result<(), ArgumentError> {
+  if rules.is_empty() {
+    return Ok(());
+  }
+  let Some(JsonValue::Object(args)) = payload else {
+    return Err(ArgumentError::NotAnObject);
+  };
+  for rule in rules {
+    rule.check(args, &mut in_scope)?;
+  }
+  Ok(())
+}
+
+fn as_f64(number: &Number) -> f64 {
+  match *number {
+    Number::Int(n) => n as f64,
+    Number::UInt(n) => n as f64,
+    Number::Float(n) => n,
+  }
+}
+
+/// Why the arguments of an IPC request do not satisfy the rules of the command, see [`ArgumentRule`].
+#[derive(Debug, Clone, PartialEq, Serialize, Error)]
+#[serde(tag = "reason", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ArgumentError {
+  /// The arguments are not a JSON object.
+  #[error("the arguments must be a JSON object")]
+  NotAnObject,
+  /// A required argument is missing or `null`.
+  #[serde(rename_all = "camelCase")]
+  #[error("missing argument {argument}")]
+  Missing {
+    /// The argument name.
+    argument: String,
+  },
+  /// The argument does not have the type the rule expects.
+  #[serde(rename_all = "camelCase")]
+  #[error("argument {argument} must be a {expected}")]
+  InvalidType {
+    /// The argument name.
+    argument: String,
+    /// The expected JSON type.
+    expected: String,
+  },
+  /// The argument is not one of the values of its enum rule.
+  #[serde(rename_all = "camelCase")]
+  #[error("argument {argument} is not one of the allowed values")]
+  NotAllowed {
+    /// The argument name.
+    argument: String,
+  },
+  /// The argument is out of the bounds of its range rule.
+  #[serde(rename_all = "camelCase")]
+  #[error("argument {argument} must be within {}..={}", fmt_bound(.min), fmt_bound(.max))]
+  OutOfRange {
+    /// The argument name.
+    argument: String,
+    /// The lower bound.
+    min: Option<f64>,
+    /// The upper bound.
+    max: Option<f64>,
+  },
+  /// The argument is not allowed by the scopes of the command.
+  #[serde(rename_all = "camelCase")]
+  #[error("argument {argument} is not allowed by the command scope")]
+  OutOfScope {
+    /// The argument name.
+    argument: String,
+  },
+}
+
+fn fmt_bound(bound: &Option<f64>) -> String {
+  bound.map(|bound| bound.to_string()).unwrap_or_default()
+}
+
+#[cfg(feature = "tokens")]
+mod build {
+  use proc_macro2::TokenStream;
+  use quote::{quote, ToTokens, TokenStreamExt};
+
+  use super::*;
+  use crate::{literal_struct, tokens::*};
+
+  impl ToTokens for ArgumentRule {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let name = str_lit(&self.name);
+      let optional = self.optional;
+      let matcher = &self.matcher;
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::args::ArgumentRule,
+        name,
+        optional,
+        matcher
+      )
+    }
+  }
+
+  impl ToTokens for ArgumentMatcher {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let prefix = quote! { ::tauri::utils::acl::args::ArgumentMatcher };
+
+      tokens.append_all(match self {
+        Self::PathScope => quote! { #prefix::PathScope },
+        Self::UrlScope => quote! { #prefix::UrlScope },
+        Self::Enum { values } => {
+          let values = vec_lit(values, |value| quote!(#value));
+          quote! { #prefix::Enum { values: #values } }
+        }
+        Self::Range { min, max } => {
+          let min = opt_lit(min.as_ref());
+          let max = opt_lit(max.as_ref());
+          quote! { #prefix::Range { min: #min, max: #max } }
+        }
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  fn rules(toml: &str) -> Vec<ArgumentRule> {
+    #[derive(Deserialize)]
+    struct Rules {
+      args: Vec<ArgumentRule>,
+    }
+    toml::from_str::<Rules>(toml).unwrap().args
+  }
+
+  fn check(rules: &[ArgumentRule], payload: JsonValue) -> Result<(), ArgumentError> {
+    check_arguments(rules, Some(&payload), |matcher, value| match matcher {
+      ArgumentMatcher::PathScope => value.starts_with("/home/user/"),
+      ArgumentMatcher::UrlScope => value.starts_with("https://tauri.app/"),
+      _ => unreachable!(),
+    })
+  }
+
+  #[test]
+  fn parse_rules() {
+    let rules = rules(
+      r#"
+      [[args]]
+      name = "path"
+      kind = "path-scope"
+
+      [[args]]
+      name = "url"
+      kind = "url-scope"
+      optional = true
+
+      [[args]]
+      name = "mode"
+      kind = "enum"
+      values = ["read", "write"]
+
+      [[args]]
+      name = "width"
+      kind = "range"
+      min = 1
+      max = 4096.5
+      "#,
+    );
+    assert_eq!(
+      rules,
+      vec![
+        ArgumentRule {
+          name: "path".into(),
+          optional: false,
+          matcher: ArgumentMatcher::PathScope,
+        },
+        ArgumentRule {
+          name: "url".into(),
+          optional: true,
+          matcher: ArgumentMatcher::UrlScope,
+        },
+        ArgumentRule {
+          name: "mode".into(),
+          optional: false,
+          matcher: ArgumentMatcher::Enum {
+            values: vec!["read".to_string().into(), "write".to_string().into()],
+          },
+        },
+        ArgumentRule {
+          name: "width".into(),
+          optional: false,
+          matcher: ArgumentMatcher::Range {
+            min: Some(Number::Int(1)),
+            max: Some(Number::Float(4096.5)),
+          },
+        },
+      ]
+    );
+    assert!(rules.iter().all(|rule| rule.validate().is_ok()));
+
+    // the serialized rules parse back
+    let json = serde_json::to_value(&rules).unwrap();
+    assert_eq!(
+      json[0],
+      json!({ "name": "path", "optional": false, "kind": "path-scope" })
+    );
+    assert_eq!(
+      serde_json::from_value::<Vec<ArgumentRule>>(json).unwrap(),
+      rules
+    );
+  }
+
+  #[test]
+  fn invalid_rules() {
+    for (toml, expected) in [
+      (
+        r#"name = ""
+        kind = "path-scope""#,
+        "the argument name is empty",
+      ),
+      (
+        r#"name = "mode"
+        kind = "enum"
+        values = []"#,
+        "enum rule of argument mode has no values",
+      ),
+      (
+        r#"name = "width"
+        kind = "range""#,
+        "range rule of argument width has no bounds",
+      ),
+      (
+        r#"name = "width"
+        kind = "range"
+        min = 10
+        max = 1"#,
+        "range rule of argument width has a lower bound above its upper bound",
+      ),
+    ] {
+      let rule = toml::from_str::<ArgumentRule>(toml).unwrap();
+      assert_eq!(rule.validate().unwrap_err(), expected);
+    }
+    assert!(toml::from_str::<ArgumentRule>("name = \"path\"\nkind = \"regex\"").is_err());
+    assert!(toml::from_str::<ArgumentRule>("name = \"path\"").is_err());
+  }
+
+  #[test]
+  fn enum_and_range() {
+    let rules = rules(
+      r#"
+      [[args]]
+      name = "mode"
+      kind = "enum"
+      values = ["read", "write", 1]
+
+      [[args]]
+      name = "width"
+      kind = "range"
+      min = 1
+      max = 4096
+
+      [[args]]
+      name = "scale"
+      kind = "range"
+      max = 2.5
+      optional = true
+      "#,
+    );
+
+    assert_eq!(check(&rules, json!({ "mode": "read", "width": 1 })), Ok(()));
+    assert_eq!(
+      check(&rules, json!({ "mode": 1, "width": 4096, "scale": -3.5 })),
+      Ok(())
+    );
+    assert_eq!(
+      check(&rules, json!({ "mode": "append", "width": 10 })),
+      Err(ArgumentError::NotAllowed {
+        argument: "mode".into()
+      })
+    );
+    // the values are compared exactly
+    assert_eq!(
+      check(&rules, json!({ "mode": 1.0, "width": 10 })),
+      Err(ArgumentError::NotAllowed {
+        argument: "mode".into()
+      })
+    );
+    let error = check(&rules, json!({ "mode": "read", "width": 4097 })).unwrap_err();
+    assert_eq!(
+      error,
+      ArgumentError::OutOfRange {
+        argument: "width".into(),
+        min: Some(1.0),
+        max: Some(4096.0),
+      }
+    );
+    assert_eq!(error.to_string(), "argument width must be within 1..=4096");
+    assert_eq!(
+      check(&rules, json!({ "mode": "read", "width": 1, "scale": 2.6 }))
+        .unwrap_err()
+        .to_string(),
+      "argument scale must be within ..=2.5"
+    );
+    assert_eq!(
+      check(&rules, json!({ "mode": "read", "width": "10" })),
+      Err(ArgumentError::InvalidType {
+        argument: "width".into(),
+        expected: "number".into(),
+      })
+    );
+  }
+
+  #[test]
+  fn scopes() {
+    let rules = rules(
+      r#"
+      [[args]]
+      name = "path"
+      kind = "path-scope"
+
+      [[args]]
+      name = "url"
+      kind = "url-scope"
+      optional = true
+      "#,
+    );
+
+    assert_eq!(check(&rules, json!({ "path": "/home/user/a.txt" })), Ok(()));
+    assert_eq!(
+      check(
+        &rules,
+        json!({ "path": "/home/user/a.txt", "url": "https://tauri.app/docs" })
+      ),
+      Ok(())
+    );
+    assert_eq!(
+      check(&rules, json!({ "path": "/etc/passwd" })),
+      Err(ArgumentError::OutOfScope {
+        argument: "path".into()
+      })
+    );
+    assert_eq!(
+      check(
+        &rules,
+        json!({ "path": "/home/user/a.txt", "url": "https://evil.com" })
+      ),
+      Err(ArgumentError::OutOfScope {
+        argument: "url".into()
+      })
+    );
+    assert_eq!(
+      check(&rules, json!({ "path": ["/home/user/a.txt"] })),
+      Err(ArgumentError::InvalidType {
+        argument: "path".into(),
+        expected: "string".into(),
+      })
+    );
+  }
+
+  #[test]
+  fn missing_and_extra_arguments() {
+    let rules = rules(
+      r#"
+      [[args]]
+      name = "path"
+      kind = "path-scope"
+
+      [[args]]
+      name = "mode"
+      kind = "enum"
+      values = ["read"]
+      optional = true
+      "#,
+    );
+
+    // the arguments without a rule are not checked
+    assert_eq!(
+      check(
+        &rules,
+        json!({ "path": "/home/user/a.txt", "recursive": true, "other": "/etc/passwd" })
+      ),
+      Ok(())
+    );
+    // optional arguments can be omitted or null
+    assert_eq!(
+      check(&rules, json!({ "path": "/home/user/a.txt", "mode": null })),
+      Ok(())
+    );
+    for payload in [
+      json!({}),
+      json!({ "path": null }),
+      json!({ "mode": "read" }),
+    ] {
+      assert_eq!(
+        check(&rules, payload),
+        Err(ArgumentError::Missing {
+          argument: "path".into()
+        })
+      );
+    }
+    // the argument names are case sensitive
+    assert_eq!(
+      check(&rules, json!({ "Path": "/home/user/a.txt" })),
+      Err(ArgumentError::Missing {
+        argument: "path".into()
+      })
+    );
+
+    // the payload must be an object when there are rules
+    assert_eq!(
+      check_arguments(&rules, None, |_, _| true),
+      Err(ArgumentError::NotAnObject)
+    );
+    assert_eq!(
+      check(&rules, json!(["/home/user/a.txt"])),
+      Err(ArgumentError::NotAnObject)
+    );
+    assert_eq!(check_arguments(&[], None, |_, _| false), Ok(()));
+  }
+
+  #[test]
+  fn structured_errors() {
+    assert_eq!(
+      serde_json::to_value(ArgumentError::OutOfRange {
+        argument: "width".into(),
+        min: None,
+        max: Some(10.0),
+      })
+      .unwrap(),
+      json!({ "reason": "outOfRange", "argument": "width", "min": null, "max": 10.0 })
+    );
+    assert_eq!(
+      serde_json::to_value(ArgumentError::NotAnObject).unwrap(),
+      json!({ "reason": "notAnObject" })
+    );
+  }
+}
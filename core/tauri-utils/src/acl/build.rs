@@ -19,7 +19,17 @@ use schemars::{
 };
 use serde::Deserialize;
 
-use super::{capability::Capability, plugin::PermissionFile};
+use super::{
+  capability::Capability,
+  format::{deserialize_capability_file, CapabilityFormat, FieldChange},
+  is_command_pattern,
+  plugin::PermissionFile,
+  resolved::CapabilityOverride,
+  CommandId, LocalResource,
+};
+use crate::platform::Target;
+
+pub use super::strict::{UnknownField, UnknownFields};
 
 /// Cargo cfg key for permissions file paths
 pub const PERMISSION_FILES_PATH_KEY: &str = "PERMISSION_FILES_PATH";
@@ -27,6 +37,9 @@ pub const PERMISSION_FILES_PATH_KEY: &str = "PERMISSION_FILES_PATH";
 /// Cargo cfg key for global scope schemas
 pub const GLOBAL_SCOPE_SCHEMA_PATH_KEY: &str = "GLOBAL_SCOPE_SCHEMA_PATH";
 
+/// Cargo cfg key for plugin versions
+pub const PLUGIN_VERSION_KEY: &str = "PLUGIN_VERSION";
+
 /// Allowed permission file extensions
 pub const PERMISSION_FILE_EXTENSIONS: &[&str] = &["json", "toml"];
 
@@ -37,16 +50,33 @@ pub const PERMISSION_SCHEMAS_FOLDER_NAME: &str = "schemas";
 pub const PERMISSION_SCHEMA_FILE_NAME: &str = "schema.json";
 
 /// Allowed capability file extensions
+#[cfg(not(feature = "capability-yaml"))]
 const CAPABILITY_FILE_EXTENSIONS: &[&str] = &["json", "toml"];
+#[cfg(feature = "capability-yaml")]
+const CAPABILITY_FILE_EXTENSIONS: &[&str] = &["json", "toml", "yaml", "yml"];
 
 /// Known folder name of the capability schemas
 const CAPABILITIES_SCHEMA_FOLDER_NAME: &str = "schemas";
 
+/// Folder name of the capabilities that only apply to desktop targets
+const TARGET_CAPABILITIES_DESKTOP_FOLDER_NAME: &str = "desktop";
+
+/// Folder name of the capabilities that only apply to mobile targets
+const TARGET_CAPABILITIES_MOBILE_FOLDER_NAME: &str = "mobile";
+
+/// Folder names of the capabilities that only apply to a target OS, inside the desktop or mobile folders
+const TARGET_CAPABILITIES_OS_FOLDER_NAMES: &[&str] =
+  &["macos", "windows", "linux", "android", "ios"];
+
 const CORE_PLUGIN_PERMISSIONS_TOKEN: &str = "__CORE_PLUGIN__";
 
 /// Capability formats accepted in a capability file.
+///
+/// Capability files can be written in JSON, TOML or, with the `capability-yaml` feature, YAML.
+/// They declare the version of their format with a top-level `version` field, see [`super::format`].
 #[derive(Deserialize, schemars::JsonSchema)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum CapabilityFile {
   /// A single capability.
   Capability(Capability),
@@ -100,7 +130,7 @@ pub fn define_permissions(
     );
   }
 
-  parse_permissions(permission_files)
+  parse_permissions(permission_files, UnknownFields::Ignore)
 }
 
 /// Define the global scope schema JSON file path if it exists and pass it to the immediate consuming crate.
@@ -124,48 +154,323 @@ pub fn define_global_scope_schema(
   Ok(())
 }
 
+/// Pass the plugin crate version to the immediate consuming crate,
+/// checked against the plugin version requirements of the capabilities.
+pub fn define_plugin_version(version: &str, pkg_name: &str) {
+  if let Some(plugin_name) = pkg_name.strip_prefix("tauri:") {
+    println!("cargo:{plugin_name}{CORE_PLUGIN_PERMISSIONS_TOKEN}_{PLUGIN_VERSION_KEY}={version}");
+  } else {
+    println!("cargo:{PLUGIN_VERSION_KEY}={version}");
+  }
+}
+
+/// Checks the command aliases of the plugin permission files against the plugin commands,
+/// see [`Manifest#structfield.aliases`](super::plugin::Manifest#structfield.aliases).
+///
+/// An alias cannot be the name of one of the `commands`, and the aliases cannot form a cycle.
+pub fn validate_command_aliases(
+  plugin: &str,
+  permission_files: &[PermissionFile],
+  commands: &[&str],
+) -> Result<(), Error> {
+  let aliases = permission_files
+    .iter()
+    .flat_map(|file| file.aliases.clone())
+    .collect::<BTreeMap<_, _>>();
+  if let Some(alias) = aliases
+    .keys()
+    .find(|alias| commands.contains(&alias.as_str()))
+  {
+    return Err(Error::CommandAliasCollision {
+      plugin: plugin.into(),
+      alias: alias.clone(),
+    });
+  }
+  super::plugin::validate_alias_cycles(plugin, &aliases)
+}
+
 /// Parses all capability files with the given glob pattern.
 pub fn parse_capabilities(
   capabilities_path_pattern: &str,
+  unknown_fields: UnknownFields,
 ) -> Result<BTreeMap<String, Capability>, Error> {
-  let mut capabilities_map = BTreeMap::new();
+  let capabilities =
+    parse_capability_files(capability_files(capabilities_path_pattern)?, unknown_fields)?;
+  Ok(
+    capabilities
+      .into_iter()
+      .map(|(identifier, (_path, capability))| (identifier, capability))
+      .collect(),
+  )
+}
 
-  for path in glob::glob(capabilities_path_pattern)?
-    .flatten() // filter extension
-    .filter(|p| {
-      p.extension()
-        .and_then(|e| e.to_str())
-        .map(|e| CAPABILITY_FILE_EXTENSIONS.contains(&e))
-        .unwrap_or_default()
-    })
-    // filter schema files
-    .filter(|p| p.parent().unwrap().file_name().unwrap() != CAPABILITIES_SCHEMA_FOLDER_NAME)
-  {
+/// Parses the capability files of the given directory that apply to the target.
+///
+/// Besides the common capabilities, the `desktop` and `mobile` subdirectories only apply to the targets of that kind,
+/// and their OS subdirectories (e.g. `mobile/android` or `desktop/macos`) only to that target.
+/// A capability of a more specific directory replaces a capability with the same identifier
+/// of a less specific one, and the replacements are returned along with the capabilities.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(
+    name = "acl::parse_target_capabilities",
+    skip_all,
+    fields(dir = %capabilities_dir.as_ref().display(), %target)
+  )
+)]
+pub fn parse_target_capabilities(
+  capabilities_dir: impl AsRef<Path>,
+  target: Target,
+  unknown_fields: UnknownFields,
+) -> Result<(BTreeMap<String, Capability>, Vec<CapabilityOverride>), Error> {
+  let capabilities_dir = capabilities_dir.as_ref();
+  let kind = if target.is_mobile() {
+    TARGET_CAPABILITIES_MOBILE_FOLDER_NAME
+  } else {
+    TARGET_CAPABILITIES_DESKTOP_FOLDER_NAME
+  };
+  let os = target_capabilities_folder_name(target);
+
+  // common, target kind and target OS capability files
+  let mut layers: [Vec<PathBuf>; 3] = Default::default();
+  for path in capability_files(&format!(
+    "{}/**/*",
+    glob::Pattern::escape(&capabilities_dir.display().to_string())
+  ))? {
+    let relative = path.strip_prefix(capabilities_dir).unwrap_or(&path);
+    let dirs = relative
+      .parent()
+      .into_iter()
+      .flat_map(|parent| parent.components())
+      .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+      .collect::<Vec<_>>();
+
+    match dirs.first().map(String::as_str) {
+      Some(TARGET_CAPABILITIES_DESKTOP_FOLDER_NAME | TARGET_CAPABILITIES_MOBILE_FOLDER_NAME) => {
+        if dirs[0] != kind {
+          #[cfg(feature = "tracing")]
+          tracing::debug!(path = %path.display(), "skipping capability file of another target kind");
+          continue;
+        }
+        match dirs.get(1).map(String::as_str) {
+          Some(name) if name == os => layers[2].push(path),
+          Some(name) if TARGET_CAPABILITIES_OS_FOLDER_NAMES.contains(&name) => {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(path = %path.display(), "skipping capability file of another target");
+            continue;
+          }
+          _ => layers[1].push(path),
+        }
+      }
+      _ => layers[0].push(path),
+    }
+  }
+
+  let mut capabilities = BTreeMap::new();
+  let mut overrides = Vec::new();
+  for layer in layers {
+    for (identifier, (path, capability)) in parse_capability_files(layer, unknown_fields)? {
+      if let Some((overridden, _)) =
+        capabilities.insert(identifier.clone(), (path.clone(), capability))
+      {
+        overrides.push(CapabilityOverride {
+          identifier,
+          overridden,
+          by: path,
+        });
+      }
+    }
+  }
+
+  Ok((
+    capabilities
+      .into_iter()
+      .map(|(identifier, (_path, capability))| (identifier, capability))
+      .collect(),
+    overrides,
+  ))
+}
+
+fn target_capabilities_folder_name(target: Target) -> &'static str {
+  match target {
+    Target::MacOS => "macos",
+    Target::Windows => "windows",
+    Target::Linux => "linux",
+    Target::Android => "android",
+    Target::Ios => "ios",
+  }
+}
+
+/// The capability files matching the glob pattern, skipping the schema files.
+fn capability_files(capabilities_path_pattern: &str) -> Result<Vec<PathBuf>, Error> {
+  Ok(
+    glob::glob(capabilities_path_pattern)?
+      .flatten() // filter extension
+      .filter(|p| {
+        p.extension()
+          .and_then(|e| e.to_str())
+          .map(|e| CAPABILITY_FILE_EXTENSIONS.contains(&e))
+          .unwrap_or_default()
+      })
+      // filter schema files
+      .filter(|p| p.parent().unwrap().file_name().unwrap() != CAPABILITIES_SCHEMA_FOLDER_NAME)
+      .collect(),
+  )
+}
+
+/// Parses the capability files, returning the capabilities along with the path of the file defining them.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(name = "acl::parse_capability_files", skip_all, fields(files = paths.len()))
+)]
+fn parse_capability_files(
+  paths: Vec<PathBuf>,
+  unknown_fields: UnknownFields,
+) -> Result<BTreeMap<String, (PathBuf, Capability)>, Error> {
+  let mut capabilities_map = BTreeMap::<String, (PathBuf, Capability)>::new();
+
+  for path in paths {
     println!("cargo:rerun-if-changed={}", path.display());
 
-    let capability_file = std::fs::read_to_string(&path).map_err(Error::ReadFile)?;
+    let in_file = |error: Error| error.with_file(&path);
+    let capability_file = std::fs::read_to_string(&path)
+      .map_err(Error::ReadFile)
+      .map_err(in_file)?;
     let ext = path.extension().unwrap().to_string_lossy().to_string();
-    let capability: CapabilityFile = match ext.as_str() {
-      "toml" => toml::from_str(&capability_file)?,
-      "json" => serde_json::from_str(&capability_file)?,
-      _ => return Err(Error::UnknownCapabilityFormat(ext)),
-    };
+    if !CAPABILITY_FILE_EXTENSIONS.contains(&ext.as_str()) {
+      return Err(in_file(Error::UnknownCapabilityFormat(ext)));
+    }
+    let file = parse_value(&path, &capability_file).map_err(in_file)?;
+    if unknown_fields == UnknownFields::Deny {
+      deny_unknown_fields(
+        &path,
+        super::strict::capability_file(&file, &capability_file),
+      )?;
+    }
 
-    match capability {
-      CapabilityFile::Capability(capability) => {
-        capabilities_map.insert(capability.identifier.clone(), capability);
-      }
-      CapabilityFile::List { capabilities } => {
-        for capability in capabilities {
-          capabilities_map.insert(capability.identifier.clone(), capability);
-        }
+    // the file is read with the deserializer of the format version it declares
+    let capabilities = deserialize_capability_file(file).map_err(in_file)?;
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+      path = %path.display(),
+      capabilities = capabilities.len(),
+      "parsed capability file"
+    );
+    for capability in capabilities {
+      capability.parse_window_patterns(Some(&path))?;
+      if let Some((first, _)) = capabilities_map.get(&capability.identifier) {
+        return Err(Error::DuplicateCapability {
+          identifier: capability.identifier,
+          first: first.clone(),
+          second: path,
+        });
       }
+      capabilities_map.insert(capability.identifier.clone(), (path.clone(), capability));
     }
   }
 
   Ok(capabilities_map)
 }
 
+/// Rewrites a capability file to the current format version, see [`Capability::migrate_from_v1`],
+/// returning the transformed fields.
+///
+/// JSON and TOML files are rewritten with their fields sorted. The file is left untouched when nothing changes.
+/// YAML files are not rewritten since their anchors would be lost, the changes must be applied manually.
+pub fn migrate_capability_file(path: &Path) -> Result<Vec<FieldChange>, Error> {
+  let in_file = |error: Error| error.with_file(path);
+  let content = read_to_string(path)
+    .map_err(Error::ReadFile)
+    .map_err(in_file)?;
+  let file = parse_value(path, &content).map_err(in_file)?;
+  let migration = Capability::migrate_from_v1(&file).map_err(in_file)?;
+  if migration.changes.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let content = match capability_file_content(path, &migration.file).map_err(in_file)? {
+    Some(content) => content,
+    None => {
+      return Err(in_file(Error::CapabilityFormat {
+        version: CapabilityFormat::of(&file)?.to_string(),
+        reason: format!(
+          "only JSON and TOML files can be rewritten, apply the changes manually: {}",
+          migration
+            .changes
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+        ),
+      }))
+    }
+  };
+  write(path, content)
+    .map_err(Error::WriteFile)
+    .map_err(in_file)?;
+
+  Ok(migration.changes)
+}
+
+/// Writes a capability file, e.g. a [`CapabilityTemplate`](super::template::CapabilityTemplate),
+/// in the JSON or TOML format depending on its extension.
+pub fn write_capability_file(path: &Path, capability: &Capability) -> Result<(), Error> {
+  let in_file = |error: Error| error.with_file(path);
+  let content = capability_file_content(path, &serde_json::to_value(capability)?)
+    .map_err(in_file)?
+    .ok_or_else(|| {
+      in_file(Error::WriteFile(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "capability files can only be written in JSON or TOML",
+      )))
+    })?;
+  write(path, content)
+    .map_err(Error::WriteFile)
+    .map_err(in_file)
+}
+
+/// The content of a JSON or TOML capability file, [`None`] for the other formats.
+fn capability_file_content(path: &Path, file: &serde_json::Value) -> Result<Option<String>, Error> {
+  match path.extension().and_then(|e| e.to_str()) {
+    Some("json") => Ok(Some(serde_json::to_string_pretty(file)? + "\n")),
+    Some("toml") => toml::to_string_pretty(file).map(Some).map_err(|error| {
+      Error::WriteFile(std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }),
+    _ => Ok(None),
+  }
+}
+
+/// Parses a capability or permission file as a JSON value, to walk its fields.
+fn parse_value(path: &Path, content: &str) -> Result<serde_json::Value, Error> {
+  match path.extension().and_then(|e| e.to_str()) {
+    Some("toml") => Ok(toml::from_str(content)?),
+    #[cfg(feature = "capability-yaml")]
+    Some("yaml" | "yml") => {
+      let parse = || {
+        let mut value: serde_yaml::Value = serde_yaml::from_str(content)?;
+        value.apply_merge()?;
+        serde_yaml::from_value(value)
+      };
+      parse().map_err(|error| Error::Yaml {
+        path: path.to_path_buf(),
+        error,
+      })
+    }
+    _ => Ok(serde_json::from_str(content)?),
+  }
+}
+
+fn deny_unknown_fields(path: &Path, fields: Vec<UnknownField>) -> Result<(), Error> {
+  if fields.is_empty() {
+    Ok(())
+  } else {
+    Err(Error::UnknownFields {
+      path: path.to_path_buf(),
+      fields,
+    })
+  }
+}
+
 fn permissions_schema(permissions: &[PermissionFile]) -> RootSchema {
   let mut schema = schema_for!(PermissionFile);
 
@@ -252,34 +557,31 @@ pub fn generate_schema<P: AsRef<Path>>(
 }
 
 /// Read all permissions listed from the defined cargo cfg key value.
-pub fn read_permissions() -> Result<HashMap<String, Vec<PermissionFile>>, Error> {
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(name = "acl::read_permissions", skip_all)
+)]
+pub fn read_permissions(
+  unknown_fields: UnknownFields,
+) -> Result<HashMap<String, Vec<PermissionFile>>, Error> {
   let mut permissions_map = HashMap::new();
 
   for (key, value) in vars_os() {
     let key = key.to_string_lossy();
 
-    if let Some(plugin_crate_name_var) = key
-      .strip_prefix("DEP_")
-      .and_then(|v| v.strip_suffix(&format!("_{PERMISSION_FILES_PATH_KEY}")))
-      .map(|v| {
-        v.strip_suffix(CORE_PLUGIN_PERMISSIONS_TOKEN)
-          .and_then(|v| v.strip_prefix("TAURI_"))
-          .unwrap_or(v)
-      })
-    {
+    if let Some(plugin_name) = dep_plugin_name(&key, PERMISSION_FILES_PATH_KEY) {
       let permissions_path = PathBuf::from(value);
       let permissions_str = std::fs::read_to_string(&permissions_path).map_err(Error::ReadFile)?;
       let permissions: Vec<PathBuf> = serde_json::from_str(&permissions_str)?;
-      let permissions = parse_permissions(permissions)?;
-
-      let plugin_crate_name = plugin_crate_name_var.to_lowercase().replace('_', "-");
-      permissions_map.insert(
-        plugin_crate_name
-          .strip_prefix("tauri-plugin-")
-          .map(|n| n.to_string())
-          .unwrap_or(plugin_crate_name),
-        permissions,
+      let permissions = parse_permissions(permissions, unknown_fields)?;
+      #[cfg(feature = "tracing")]
+      tracing::debug!(
+        plugin = %plugin_name,
+        files = permissions.len(),
+        "read plugin permissions"
       );
+
+      permissions_map.insert(plugin_name, permissions);
     }
   }
 
@@ -287,56 +589,141 @@ pub fn read_permissions() -> Result<HashMap<String, Vec<PermissionFile>>, Error>
 }
 
 /// Read all global scope schemas listed from the defined cargo cfg key value.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(name = "acl::read_global_scope_schemas", skip_all)
+)]
 pub fn read_global_scope_schemas() -> Result<HashMap<String, serde_json::Value>, Error> {
   let mut permissions_map = HashMap::new();
 
   for (key, value) in vars_os() {
     let key = key.to_string_lossy();
 
-    if let Some(plugin_crate_name_var) = key
-      .strip_prefix("DEP_")
-      .and_then(|v| v.strip_suffix(&format!("_{GLOBAL_SCOPE_SCHEMA_PATH_KEY}")))
-      .map(|v| {
-        v.strip_suffix(CORE_PLUGIN_PERMISSIONS_TOKEN)
-          .and_then(|v| v.strip_prefix("TAURI_"))
-          .unwrap_or(v)
-      })
-    {
+    if let Some(plugin_name) = dep_plugin_name(&key, GLOBAL_SCOPE_SCHEMA_PATH_KEY) {
       let path = PathBuf::from(value);
       let json = std::fs::read_to_string(&path).map_err(Error::ReadFile)?;
       let schema: serde_json::Value = serde_json::from_str(&json)?;
 
-      let plugin_crate_name = plugin_crate_name_var.to_lowercase().replace('_', "-");
-      permissions_map.insert(
-        plugin_crate_name
-          .strip_prefix("tauri-plugin-")
-          .map(|n| n.to_string())
-          .unwrap_or(plugin_crate_name),
-        schema,
-      );
+      permissions_map.insert(plugin_name, schema);
     }
   }
 
   Ok(permissions_map)
 }
 
-fn parse_permissions(paths: Vec<PathBuf>) -> Result<Vec<PermissionFile>, Error> {
+/// Read the plugin versions from the defined cargo cfg key value.
+pub fn read_plugin_versions() -> HashMap<String, String> {
+  vars_os()
+    .filter_map(|(key, value)| {
+      let plugin_name = dep_plugin_name(&key.to_string_lossy(), PLUGIN_VERSION_KEY)?;
+      Some((plugin_name, value.into_string().ok()?))
+    })
+    .collect()
+}
+
+/// Plugin name of a `DEP_{LINKS}_{KEY}` environment variable set by the build script of a plugin,
+/// or by the tauri build script for the core plugins.
+fn dep_plugin_name(var: &str, key: &str) -> Option<String> {
+  let plugin_crate_name_var = var
+    .strip_prefix("DEP_")
+    .and_then(|v| v.strip_suffix(&format!("_{key}")))
+    .map(|v| {
+      v.strip_suffix(CORE_PLUGIN_PERMISSIONS_TOKEN)
+        .and_then(|v| v.strip_prefix("TAURI_"))
+        .unwrap_or(v)
+    })?;
+
+  let plugin_crate_name = plugin_crate_name_var.to_lowercase().replace('_', "-");
+  Some(
+    plugin_crate_name
+      .strip_prefix("tauri-plugin-")
+      .map(|n| n.to_string())
+      .unwrap_or(plugin_crate_name),
+  )
+}
+
+pub(crate) fn parse_permissions(
+  paths: Vec<PathBuf>,
+  unknown_fields: UnknownFields,
+) -> Result<Vec<PermissionFile>, Error> {
   let mut permissions = Vec::new();
   for path in paths {
-    let permission_file = std::fs::read_to_string(&path).map_err(Error::ReadFile)?;
+    let in_file = |error: Error| error.with_file(&path);
+    let permission_file = std::fs::read_to_string(&path)
+      .map_err(Error::ReadFile)
+      .map_err(in_file)?;
     let ext = path.extension().unwrap().to_string_lossy().to_string();
     let permission: PermissionFile = match ext.as_str() {
-      "toml" => toml::from_str(&permission_file)?,
-      "json" => serde_json::from_str(&permission_file)?,
-      _ => return Err(Error::UnknownPermissionFormat(ext)),
+      "toml" => toml::from_str(&permission_file)
+        .map_err(Error::from)
+        .map_err(in_file)?,
+      "json" => serde_json::from_str(&permission_file)
+        .map_err(Error::from)
+        .map_err(in_file)?,
+      _ => return Err(in_file(Error::UnknownPermissionFormat(ext))),
     };
+    if unknown_fields == UnknownFields::Deny {
+      let file = parse_value(&path, &permission_file).map_err(in_file)?;
+      deny_unknown_fields(
+        &path,
+        super::strict::permission_file(&file, &permission_file),
+      )?;
+    }
+    validate_permission_commands(&permission).map_err(in_file)?;
     permissions.push(permission);
   }
   Ok(permissions)
 }
 
+/// Validates the command names of the permissions, which are used to build [`CommandId`]s,
+/// and their argument rules.
+fn validate_permission_commands(permission_file: &PermissionFile) -> Result<(), Error> {
+  for permission in &permission_file.permission {
+    for rule in &permission.args {
+      rule
+        .validate()
+        .map_err(|reason| Error::InvalidArgumentRule {
+          permission: permission.identifier.clone(),
+          reason,
+        })?;
+    }
+    for command in permission
+      .commands
+      .allow
+      .iter()
+      .chain(&permission.commands.deny)
+    {
+      CommandId::validate_command(command).map_err(|error| Error::InvalidCommand {
+        permission: permission.identifier.clone(),
+        command: command.clone(),
+        error,
+      })?;
+      if is_command_pattern(command) {
+        glob::Pattern::new(command).map_err(|error| Error::InvalidCommandPattern {
+          permission: permission.identifier.clone(),
+          command: command.clone(),
+          error,
+        })?;
+      }
+    }
+  }
+  Ok(())
+}
+
 /// Autogenerate permission files for a list of commands.
 pub fn autogenerate_command_permissions(path: &Path, commands: &[&str], license_header: &str) {
+  autogenerate_command_permissions_with_returns(path, commands, &[], license_header)
+}
+
+/// Autogenerate permission files like [`autogenerate_command_permissions`],
+/// annotating the `allow-` permission of the given commands with the local resource identifiers they return,
+/// see [`Permission#structfield.returns`](super::Permission#structfield.returns).
+pub fn autogenerate_command_permissions_with_returns(
+  path: &Path,
+  commands: &[&str],
+  returns: &[(&str, &[LocalResource])],
+  license_header: &str,
+) {
   if !path.exists() {
     create_dir_all(path).expect("unable to create autogenerated commands dir");
   }
@@ -351,6 +738,20 @@ pub fn autogenerate_command_permissions(path: &Path, commands: &[&str], license_
 
   for command in commands {
     let slugified_command = command.replace('_', "-");
+    let returns = returns
+      .iter()
+      .find(|(name, _)| name == command)
+      .map(|(_, returns)| {
+        format!(
+          "\nreturns = [{}]",
+          returns
+            .iter()
+            .map(|r| format!("\"{r}\""))
+            .collect::<Vec<_>>()
+            .join(", ")
+        )
+      })
+      .unwrap_or_default();
     let toml = format!(
       r###"{license_header}# Automatically generated - DO NOT EDIT!
 
@@ -359,7 +760,7 @@ pub fn autogenerate_command_permissions(path: &Path, commands: &[&str], license_
 [[permission]]
 identifier = "allow-{slugified_command}"
 description = "Enables the {command} command without any pre-configured scope."
-commands.allow = ["{command}"]
+commands.allow = ["{command}"]{returns}
 
 [[permission]]
 identifier = "deny-{slugified_command}"
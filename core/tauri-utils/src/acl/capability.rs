@@ -4,10 +4,21 @@
 
 //! End-user abstraction for selecting permissions a window has access to.
 
-use crate::{acl::Identifier, platform::Target};
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{acl::Identifier, normalize_label, platform::Target};
+use glob::Pattern;
 use serde::{Deserialize, Serialize};
 
-use super::Scopes;
+use super::{window::WindowPattern, Error, Scopes, Value};
+
+/// Identifier of the reserved capability rejecting every invoke from a remote origin,
+/// regardless of the remote grants of the other capabilities.
+///
+/// The capability selects no window and grants no permission, e.g.
+/// `{ "identifier": "core:lockdown-remote", "windows": [], "permissions": [] }`.
+/// The webviews loading a remote URL are also created without the IPC bridge.
+pub const LOCKDOWN_REMOTE_CAPABILITY: &str = "core:lockdown-remote";
 
 /// An entry for a permission value in a [`Capability`] can be either a raw permission [`Identifier`]
 /// or an object that references a permission and extends its scope.
@@ -24,6 +35,40 @@ pub enum PermissionEntry {
     /// Scope to append to the existing permission scope.
     #[serde(default, flatten)]
     scope: Scopes,
+    /// Subtract the scope values of this permission from the scope allowed by other permissions
+    /// instead of denying its commands.
+    ///
+    /// Only permissions that do not allow commands can subtract scope values.
+    /// They subtract from the scope of the commands they deny, or from the plugin global scope if they do not deny commands.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    subtract: bool,
+    /// Include the `deny-` permissions of the plugin when the identifier references all of its permissions, e.g. `fs:*`.
+    #[serde(
+      default,
+      rename = "include-deny",
+      skip_serializing_if = "std::ops::Not::not"
+    )]
+    include_deny: bool,
+    /// A configuration payload for the commands this entry allows on the capability windows,
+    /// e.g. `{ "user-agent-suffix": "partner" }`.
+    ///
+    /// Commands read it with the `CommandConfig` extractor, which lists the payloads of every capability
+    /// granting the command to the calling window.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config: Option<Value>,
+    /// The version requirement of the plugin of the permission, e.g. `>=2.0, <3`,
+    /// see [`Capability#structfield.plugin_version`].
+    #[serde(
+      default,
+      rename = "plugin-version",
+      skip_serializing_if = "Option::is_none"
+    )]
+    plugin_version: Option<String>,
+    /// Unknown fields of the entry, e.g. vendor-specific `x-` fields.
+    ///
+    /// They are kept so tools can rewrite capability files without losing them, and ignored by the resolution.
+    #[serde(default, flatten, skip_serializing_if = "BTreeMap::is_empty")]
+    extensions: BTreeMap<String, serde_json::Value>,
   },
 }
 
@@ -32,10 +77,15 @@ impl PermissionEntry {
   pub fn identifier(&self) -> &Identifier {
     match self {
       Self::PermissionRef(identifier) => identifier,
-      Self::ExtendedPermission {
-        identifier,
-        scope: _,
-      } => identifier,
+      Self::ExtendedPermission { identifier, .. } => identifier,
+    }
+  }
+
+  /// The version requirement of the plugin declared by this entry.
+  pub fn plugin_version(&self) -> Option<&str> {
+    match self {
+      Self::PermissionRef(_) => None,
+      Self::ExtendedPermission { plugin_version, .. } => plugin_version.as_deref(),
     }
   }
 }
@@ -52,23 +102,393 @@ pub struct Capability {
   /// Identifier of the capability.
   pub identifier: String,
   /// Description of the capability.
-  #[serde(default)]
+  #[serde(default, skip_serializing_if = "String::is_empty")]
   pub description: String,
   /// Execution context of the capability.
   ///
   /// At runtime, Tauri filters the IPC command together with the context to determine wheter it is allowed or not and its scope.
-  #[serde(default)]
+  #[serde(default, skip_serializing_if = "CapabilityContext::is_local")]
   pub context: CapabilityContext,
   /// List of windows that uses this capability. Can be a glob pattern.
-  pub windows: Vec<String>,
+  ///
+  /// A label can also be a template with typed parameters, e.g. `editor-{uuid}` or `doc-{int}`,
+  /// which unlike `editor-*` does not match `editor-settings`.
+  /// The parameter types are `uuid`, `int`, `hex` and `alnum`.
+  ///
+  /// Windows created by another window can be selected by their parent with `{ "parent-of": "main" }`,
+  /// or by any of their ancestors with `{ "descendant-of": "main" }`.
+  /// Windows created by a plugin can be selected with `{ "plugin-windows": "oauth" }`,
+  /// which matches the window label prefixes declared by the plugin.
+  ///
+  /// The patterns select a webview by the label of its window first, and by its own label otherwise,
+  /// so every webview of a matched multiwebview window uses this capability.
+  /// A single webview is selected with `{ "webview": "partner", "window": "main" }`.
+  pub windows: Vec<CapabilityWindow>,
+  /// Paths of the local pages this capability applies to, e.g. `["settings.html", "admin/*"]`. All pages when empty.
+  ///
+  /// The glob patterns match the path of the URL the webview is on when it invokes a command, without the leading `/`,
+  /// the root path being `index.html`. `*` does not match `/`, use `admin/**` to select the nested pages.
+  /// Navigating to another page changes the commands granted to the webview on the next invoke.
+  ///
+  /// Only the allowed commands and their scopes are restricted, the denied commands apply on every page.
+  /// Remote capabilities cannot select pages.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub pages: Vec<String>,
   /// List of permissions attached to this capability. Must include the plugin name as prefix in the form of `${plugin-name}:${permission-name}`.
   pub permissions: Vec<PermissionEntry>,
   /// Target platforms this capability applies. By default all platforms applies.
-  #[serde(default = "default_platforms")]
+  #[serde(
+    default = "default_platforms",
+    skip_serializing_if = "is_default_platforms"
+  )]
   pub platforms: Vec<Target>,
+  /// Group of the capability, used to enable or disable related capabilities together per build.
+  ///
+  /// Capabilities without a group are always enabled.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub group: Option<String>,
+  /// The user roles the capability is granted to, e.g. `["admin"]`. Granted whatever the roles when empty.
+  ///
+  /// The resolution keeps the grants of such a capability apart, in a pending [`RoleGrant`](super::resolved::RoleGrant)
+  /// the runtime authority only applies while one of these roles is active, e.g. once the user logged in,
+  /// see `RuntimeAuthority::set_roles`.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub roles: Vec<String>,
+  /// Grant the selected webviews the commands granted to their window, reduced by [`Self::attenuate`].
+  ///
+  /// The windows of such a capability can only be webview selectors, e.g. `{ "webview": "sidebar", "window": "main" }`.
+  /// The resolution copies the commands, scopes and configurations granted to the window by a label pattern
+  /// to the selected webviews, which are then no longer selected by their window label at runtime.
+  /// A grant added to the window after the resolution does not reach them.
+  /// The plugin global scopes do not depend on the window and are not attenuated.
+  #[serde(
+    default,
+    rename = "inherit-from-window",
+    skip_serializing_if = "std::ops::Not::not"
+  )]
+  pub inherit_from_window: bool,
+  /// What the webviews inheriting the grants of their window do not get, see [`Self::inherit_from_window`].
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub attenuate: Option<Attenuation>,
+  /// Version requirements of the plugins referenced by the capability, e.g. `{ "fs": ">=2.0, <3" }`.
+  ///
+  /// The resolution fails with a dedicated error when the installed version of a plugin does not match,
+  /// instead of failing on the permissions that do not exist in that version.
+  /// A permission entry can declare the requirement of its own plugin with `plugin-version`, which takes precedence.
+  /// The requirements use the Cargo semver syntax.
+  #[serde(
+    default,
+    rename = "plugin-version",
+    skip_serializing_if = "BTreeMap::is_empty"
+  )]
+  pub plugin_version: BTreeMap<String, String>,
+  /// Unknown fields of the capability, e.g. vendor-specific `x-` fields.
+  ///
+  /// They are kept so tools can rewrite capability files without losing them, and ignored by the resolution.
+  #[serde(default, flatten, skip_serializing_if = "BTreeMap::is_empty")]
+  pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+impl Capability {
+  /// Parses the window label patterns of this capability.
+  pub fn window_patterns(&self) -> Result<Vec<WindowPattern>, Error> {
+    self.parse_window_patterns(None)
+  }
+
+  /// Parses the page path patterns of this capability, see [`Self::pages`].
+  ///
+  /// Fails if the capability is remote or inherits the grants of the window, or if a pattern is invalid.
+  pub fn page_patterns(&self) -> Result<Vec<Pattern>, Error> {
+    if self.pages.is_empty() {
+      return Ok(Vec::new());
+    }
+    let invalid = |reason: String| Error::InvalidCapabilityPages {
+      capability: self.identifier.clone(),
+      reason,
+    };
+
+    if !self.context.is_local() {
+      return Err(invalid(
+        "pages can only be selected by local capabilities, remote domains are not local pages"
+          .into(),
+      ));
+    }
+    if self.inherit_from_window {
+      return Err(invalid(
+        "the grants inherited from the window cannot be restricted to pages".into(),
+      ));
+    }
+    self
+      .pages
+      .iter()
+      .map(|page| {
+        if page.is_empty() || page.starts_with('/') || page.contains('#') {
+          return Err(invalid(format!(
+            "invalid page {page:?}: expected a path without the leading `/` or a fragment"
+          )));
+        }
+        Pattern::new(page).map_err(|error| invalid(format!("invalid page {page:?}: {error}")))
+      })
+      .collect()
+  }
+
+  /// The attenuation of the grants the selected webviews inherit from their window, if the capability inherits them.
+  ///
+  /// Fails if the capability selects windows or if the attenuation patterns are invalid,
+  /// see [`Self::inherit_from_window`].
+  pub fn inheritance(&self) -> Result<Option<Attenuation>, Error> {
+    let invalid = |reason: String| Error::InvalidInheritance {
+      capability: self.identifier.clone(),
+      reason,
+    };
+
+    if !self.inherit_from_window {
+      return match self.attenuate {
+        Some(_) => Err(invalid(
+          "attenuate requires inherit-from-window to be enabled".into(),
+        )),
+        None => Ok(None),
+      };
+    }
+
+    if let Some(window) = self
+      .windows
+      .iter()
+      .find(|window| !matches!(window, CapabilityWindow::Webview { .. }))
+    {
+      return Err(invalid(format!(
+        "{:?} is not a webview selector, only webviews can inherit the grants of their window",
+        window.pattern()
+      )));
+    }
+
+    let attenuation = self.attenuate.clone().unwrap_or_default();
+    for pattern in attenuation
+      .deny_commands
+      .iter()
+      .chain(attenuation.scope_filter.values().flatten())
+    {
+      Pattern::new(pattern)
+        .map_err(|error| invalid(format!("invalid pattern {pattern:?}: {error}")))?;
+    }
+
+    Ok(Some(attenuation))
+  }
+
+  pub(crate) fn parse_window_patterns(
+    &self,
+    path: Option<&Path>,
+  ) -> Result<Vec<WindowPattern>, Error> {
+    self
+      .windows
+      .iter()
+      .flat_map(|window| match window {
+        CapabilityWindow::PluginWindows { .. } => Vec::new(),
+        CapabilityWindow::Webview { webview, window } => vec![window.as_str(), webview.as_str()],
+        window => vec![window.pattern()],
+      })
+      .map(|pattern| {
+        WindowPattern::new(&normalize_label(pattern)).map_err(|error| Error::InvalidWindowPattern {
+          capability: self.identifier.clone(),
+          window: pattern.into(),
+          path: path.map(Into::into),
+          error,
+        })
+      })
+      .collect()
+  }
+}
+
+/// The grants of a window a webview does not inherit, see [`Capability#structfield.inherit_from_window`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct Attenuation {
+  /// The commands that are not inherited, e.g. `plugin:fs|write_file`.
+  ///
+  /// Can be a glob pattern, e.g. `plugin:fs|write_*`.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub deny_commands: Vec<String>,
+  /// Glob patterns the fields of the inherited allowed scope entries must match, e.g. `{ "path": ["$APPDATA/public/**"] }`.
+  ///
+  /// An entry is kept if it is an object whose filtered fields are strings matching one of their patterns.
+  /// The patterns match the text of the field, so `$APPDATA/public/**` keeps `$APPDATA/public/*` but drops `$APPDATA/**`.
+  /// The denied entries are always kept.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub scope_filter: BTreeMap<String, Vec<String>>,
+}
+
+impl Attenuation {
+  /// Whether the inherited allowed scope entry passes the scope filter.
+  ///
+  /// The patterns must be valid, see [`Capability::inheritance`].
+  pub fn keeps_scope_value(&self, value: &Value) -> bool {
+    self.scope_filter.iter().all(|(field, patterns)| {
+      let Value::Map(entry) = value else {
+        return false;
+      };
+      let Some(Value::String(text)) = entry.get(field) else {
+        return false;
+      };
+      patterns
+        .iter()
+        .any(|pattern| Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(text)))
+    })
+  }
+
+  /// Whether the command is not inherited.
+  pub fn denies_command(&self, command: &str) -> bool {
+    self
+      .deny_commands
+      .iter()
+      .any(|pattern| Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(command)))
+  }
+}
+
+/// The groups of capabilities enabled on a build, see [`Capability#structfield.group`].
+///
+/// Every group used by the capabilities must be either enabled or disabled, so typos in group names are caught.
+/// When no group is configured, all capabilities are enabled.
+#[derive(Debug, Default, Clone)]
+pub struct CapabilityGroups(BTreeMap<String, bool>);
+
+impl CapabilityGroups {
+  /// Creates a configuration without groups, enabling all capabilities.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Enables the capabilities of the given group.
+  #[must_use]
+  pub fn enable(mut self, group: impl Into<String>) -> Self {
+    self.0.insert(group.into(), true);
+    self
+  }
+
+  /// Disables the capabilities of the given group.
+  #[must_use]
+  pub fn disable(mut self, group: impl Into<String>) -> Self {
+    self.0.insert(group.into(), false);
+    self
+  }
+
+  /// Removes the capabilities of the disabled groups, returning their identifiers.
+  pub fn filter(
+    &self,
+    capabilities: &mut BTreeMap<String, Capability>,
+  ) -> Result<Vec<String>, Error> {
+    if self.0.is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let mut filtered = Vec::new();
+    for capability in capabilities.values() {
+      let Some(group) = &capability.group else {
+        continue;
+      };
+      match self.0.get(group) {
+        Some(true) => (),
+        Some(false) => {
+          #[cfg(feature = "tracing")]
+          tracing::debug!(
+            capability = %capability.identifier,
+            group,
+            "skipping capability of a disabled group"
+          );
+          filtered.push(capability.identifier.clone())
+        }
+        None => {
+          return Err(Error::UnknownCapabilityGroup {
+            capability: capability.identifier.clone(),
+            group: group.clone(),
+            available: self.0.keys().cloned().collect::<Vec<_>>().join(", "),
+          })
+        }
+      }
+    }
+
+    capabilities.retain(|identifier, _| !filtered.contains(identifier));
+    Ok(filtered)
+  }
+}
+
+/// A window selector of a [`Capability`].
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum CapabilityWindow {
+  /// A window label, can be a glob pattern or a template with typed parameters such as `editor-{uuid}`.
+  Label(String),
+  /// Selects the windows whose parent window label matches the glob pattern.
+  #[serde(rename_all = "kebab-case")]
+  ParentOf {
+    /// The parent window label pattern.
+    parent_of: String,
+  },
+  /// Selects the windows that have an ancestor window whose label matches the glob pattern,
+  /// e.g. the popups opened by the popups of a window.
+  #[serde(rename_all = "kebab-case")]
+  DescendantOf {
+    /// The ancestor window label pattern.
+    descendant_of: String,
+  },
+  /// Selects the windows created by a plugin, matching the window label prefixes declared in its manifest.
+  #[serde(rename_all = "kebab-case")]
+  PluginWindows {
+    /// The plugin name.
+    plugin_windows: String,
+  },
+  /// Selects the webviews whose own label matches the glob pattern, in the windows whose label matches `window`.
+  ///
+  /// Unlike a label pattern, it does not select the other webviews of the window,
+  /// so a remote capability can be bound to the webview loading the remote domain, e.g. one created with `WebviewBuilder::new`.
+  /// An allowed domain loaded in another webview of the same window does not get the capability.
+  Webview {
+    /// The webview label pattern.
+    webview: String,
+    /// The label pattern of the windows hosting the webview, any window by default.
+    #[serde(default = "any_window", skip_serializing_if = "is_any_window")]
+    window: String,
+  },
+}
+
+impl CapabilityWindow {
+  /// The window label glob pattern of this selector.
+  ///
+  /// For [`Self::PluginWindows`] this is the plugin name, its patterns are only known on resolution.
+  /// For [`Self::Webview`] this is the webview label pattern.
+  pub fn pattern(&self) -> &str {
+    match self {
+      Self::Label(pattern) => pattern,
+      Self::ParentOf { parent_of } => parent_of,
+      Self::DescendantOf { descendant_of } => descendant_of,
+      Self::PluginWindows { plugin_windows } => plugin_windows,
+      Self::Webview { webview, .. } => webview,
+    }
+  }
+}
+
+fn any_window() -> String {
+  "*".into()
+}
+
+fn is_any_window(window: &str) -> bool {
+  window == "*"
+}
+
+impl From<&str> for CapabilityWindow {
+  fn from(label: &str) -> Self {
+    Self::Label(label.into())
+  }
+}
+
+impl From<String> for CapabilityWindow {
+  fn from(label: String) -> Self {
+    Self::Label(label)
+  }
 }
 
-fn default_platforms() -> Vec<Target> {
+pub(crate) fn default_platforms() -> Vec<Target> {
   vec![
     Target::Linux,
     Target::MacOS,
@@ -78,8 +498,14 @@ fn default_platforms() -> Vec<Target> {
   ]
 }
 
+fn is_default_platforms(platforms: &[Target]) -> bool {
+  platforms == default_platforms()
+}
+
 /// Context of the capability.
-#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord, Hash)]
+///
+/// Either `"local"` or an object like `{ "remote": { "domains": ["tauri.app"] } }`.
+#[derive(Debug, Default, Clone, Serialize, Eq, PartialEq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub enum CapabilityContext {
@@ -89,6 +515,474 @@ pub enum CapabilityContext {
   /// Capability refers to remote usage.
   Remote {
     /// Remote domains this capability refers to. Can use glob patterns.
-    domains: Vec<String>,
+    ///
+    /// At least one domain is required.
+    #[cfg_attr(feature = "schema", schemars(length(min = 1)))]
+    domains: Vec<RemoteDomain>,
+    /// Additional constraints the webview navigation to the remote domains must have satisfied.
+    ///
+    /// Invokes from a remote origin are only allowed by this capability once the constraints are verified.
+    /// If the webview cannot verify them on the current platform, the capability does not apply.
+    #[serde(default, flatten, skip_serializing_if = "RemoteAttestation::is_empty")]
+    attestation: RemoteAttestation,
+  },
+}
+
+impl CapabilityContext {
+  /// Whether this is the [`CapabilityContext::Local`] context.
+  pub fn is_local(&self) -> bool {
+    matches!(self, Self::Local)
+  }
+}
+
+const REMOTE_CONTEXT_EXAMPLE: &str = r#"{ "remote": { "domains": ["tauri.app"] } }"#;
+
+impl<'de> Deserialize<'de> for CapabilityContext {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    use serde::de::Error;
+
+    #[derive(Deserialize)]
+    struct RemoteContext {
+      domains: Option<Vec<RemoteDomain>>,
+      #[serde(flatten)]
+      attestation: RemoteAttestation,
+    }
+
+    match serde_json::Value::deserialize(deserializer)? {
+      serde_json::Value::String(context) if context == "local" => Ok(Self::Local),
+      serde_json::Value::String(context) if context == "remote" => Err(D::Error::custom(format!(
+        r#"the "remote" context requires the domains it applies to, use {REMOTE_CONTEXT_EXAMPLE}"#
+      ))),
+      serde_json::Value::Object(mut object)
+        if object.len() == 1 && object.contains_key("remote") =>
+      {
+        let remote = RemoteContext::deserialize(object.remove("remote").unwrap())
+          .map_err(|error| D::Error::custom(format!("invalid remote context: {error}")))?;
+        match remote.domains {
+          None => Err(D::Error::custom(format!(
+            "the remote context is missing its domains, use {REMOTE_CONTEXT_EXAMPLE}"
+          ))),
+          Some(domains) if domains.is_empty() => Err(D::Error::custom(format!(
+            "the remote context requires at least one domain, e.g. {REMOTE_CONTEXT_EXAMPLE}"
+          ))),
+          Some(domains) => Ok(Self::Remote {
+            domains,
+            attestation: remote.attestation,
+          }),
+        }
+      }
+      context => Err(D::Error::custom(format!(
+        r#"invalid capability context {context}, expected "local" or a remote context like {REMOTE_CONTEXT_EXAMPLE}"#
+      ))),
+    }
+  }
+}
+
+/// A remote domain entry of a [`CapabilityContext::Remote`].
+#[derive(Debug, Clone, Serialize, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum RemoteDomain {
+  /// A domain glob pattern, e.g. `tauri.app` or `*.tauri.app`.
+  ///
+  /// Note that `*.tauri.app` does not match the apex domain `tauri.app`.
+  Pattern(String),
+  /// A domain with its subdomains optionally included.
+  #[serde(rename_all = "kebab-case")]
+  Domain {
+    /// The domain, e.g. `tauri.app`.
+    domain: String,
+    /// Whether `*.{domain}` should also be matched, including deeply nested subdomains.
+    #[serde(default)]
+    include_subdomains: bool,
   },
 }
+
+impl<'de> Deserialize<'de> for RemoteDomain {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    use serde::de::Error;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Domain {
+      domain: String,
+      #[serde(default)]
+      include_subdomains: bool,
+    }
+
+    match serde_json::Value::deserialize(deserializer)? {
+      serde_json::Value::String(pattern) => Ok(Self::Pattern(pattern)),
+      serde_json::Value::Object(object) => {
+        let domain = Domain::deserialize(serde_json::Value::Object(object))
+          .map_err(|error| D::Error::custom(format!("invalid remote domain object: {error}")))?;
+        Ok(Self::Domain {
+          domain: domain.domain,
+          include_subdomains: domain.include_subdomains,
+        })
+      }
+      domain => Err(D::Error::custom(format!(
+        r#"invalid remote domain {domain}, expected a domain pattern string like "*.tauri.app" or an object like {{ "domain": "tauri.app", "include-subdomains": true }}"#
+      ))),
+    }
+  }
+}
+
+impl RemoteDomain {
+  /// The domain glob patterns this entry expands to.
+  pub fn patterns(&self) -> Vec<String> {
+    match self {
+      Self::Pattern(pattern) => vec![pattern.clone()],
+      Self::Domain {
+        domain,
+        include_subdomains,
+      } => {
+        let mut patterns = vec![domain.clone()];
+        if *include_subdomains {
+          patterns.push(format!("*.{domain}"));
+        }
+        patterns
+      }
+    }
+  }
+}
+
+/// Constraints on the navigation of a webview to a remote domain, see [`CapabilityContext::Remote`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub struct RemoteAttestation {
+  /// A header the response of the navigation must have included.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub required_header: Option<RequiredHeader>,
+  /// The pin of the public key of a certificate the remote domain must have served,
+  /// formatted as `sha256/` followed by the base64 encoded SHA-256 hash of the key.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub certificate_pin: Option<String>,
+}
+
+/// A response header required by a [`RemoteAttestation`].
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RequiredHeader {
+  /// The header name, compared case-insensitively.
+  pub name: String,
+  /// The exact header value.
+  pub value: String,
+}
+
+/// What a webview observed while navigating to a remote origin.
+///
+/// A [`None`] field means the webview does not expose that information on the current platform.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct NavigationEvidence {
+  /// The headers of the navigation response.
+  pub headers: Option<Vec<(String, String)>>,
+  /// The `sha256/` pins of the public keys of the certificate chain served by the origin.
+  pub certificate_pins: Option<Vec<String>>,
+}
+
+impl RemoteAttestation {
+  /// Whether this does not constrain the navigation.
+  pub fn is_empty(&self) -> bool {
+    self.required_header.is_none() && self.certificate_pin.is_none()
+  }
+
+  /// Checks the constraints against what the webview observed while navigating to the origin,
+  /// returning why they are not satisfied.
+  ///
+  /// Constraints that cannot be verified are not satisfied.
+  pub fn verify(&self, evidence: Option<&NavigationEvidence>) -> Result<(), String> {
+    if let Some(header) = &self.required_header {
+      let headers = evidence
+        .and_then(|evidence| evidence.headers.as_ref())
+        .ok_or_else(|| {
+          format!(
+            "the {} header cannot be verified because the webview does not expose response headers on this platform",
+            header.name
+          )
+        })?;
+      if !headers
+        .iter()
+        .any(|(name, value)| name.eq_ignore_ascii_case(&header.name) && *value == header.value)
+      {
+        return Err(format!(
+          "the navigation response did not include the expected {} header",
+          header.name
+        ));
+      }
+    }
+
+    if let Some(pin) = &self.certificate_pin {
+      let pins = evidence
+        .and_then(|evidence| evidence.certificate_pins.as_ref())
+        .ok_or("the certificate pin cannot be verified because the webview does not expose certificates on this platform")?;
+      if !pins.contains(pin) {
+        return Err(format!(
+          "the origin did not serve a certificate matching the pin {pin}"
+        ));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Checks that the constraints can be satisfied, returning why they cannot.
+  pub(crate) fn validate(&self) -> Result<(), String> {
+    if let Some(header) = &self.required_header {
+      let is_token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+      if header.name.is_empty() || !header.name.chars().all(is_token) {
+        return Err(format!("{:?} is not a valid header name", header.name));
+      }
+    }
+    if let Some(pin) = &self.certificate_pin {
+      if pin.strip_prefix("sha256/").map_or(true, str::is_empty) {
+        return Err(format!(
+          "expected the certificate pin {pin:?} to be formatted as sha256/<base64 hash>"
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::acl::plugin::Manifest;
+
+  #[test]
+  fn round_trip_extensions() {
+    let original = r#"{
+  "identifier": "main",
+  "description": "main window capability",
+  "windows": [
+    "main"
+  ],
+  "permissions": [
+    "fs:default",
+    {
+      "identifier": "fs:scope",
+      "allow": [
+        "$APPDATA/*"
+      ],
+      "x-reason": "user documents"
+    }
+  ],
+  "x-owner": "desktop-team",
+  "x-review": {
+    "approved": true
+  }
+}"#;
+
+    let mut capability: Capability = serde_json::from_str(original).unwrap();
+    assert_eq!(
+      capability.extensions.get("x-owner"),
+      Some(&serde_json::json!("desktop-team"))
+    );
+    capability.permissions.push(PermissionEntry::PermissionRef(
+      "shell:allow-open".to_string().try_into().unwrap(),
+    ));
+
+    let edited = original.replace(
+      r#"      "x-reason": "user documents"
+    }
+  ],"#,
+      r#"      "x-reason": "user documents"
+    },
+    "shell:allow-open"
+  ],"#,
+    );
+    assert_ne!(original, edited);
+    assert_eq!(serde_json::to_string_pretty(&capability).unwrap(), edited);
+
+    let manifest = r#"{
+  "default_permission": null,
+  "permissions": {},
+  "permission_sets": {},
+  "global_scope_schema": null,
+  "x-generator": "custom"
+}"#;
+    let parsed: Manifest = serde_json::from_str(manifest).unwrap();
+    assert_eq!(serde_json::to_string_pretty(&parsed).unwrap(), manifest);
+  }
+
+  #[test]
+  fn scope_entries_shorthand() {
+    let entries = |scope: &str| -> Vec<serde_json::Value> {
+      let scopes: Scopes = serde_json::from_str(scope).unwrap();
+      scopes
+        .allow
+        .unwrap_or_default()
+        .into_iter()
+        .chain(scopes.deny.unwrap_or_default())
+        .map(Into::into)
+        .collect()
+    };
+    let entry = serde_json::json!({ "path": "$HOME/x" });
+
+    assert_eq!(
+      entries(r#"{ "allow": { "path": "$HOME/x" } }"#),
+      vec![entry.clone()]
+    );
+    assert_eq!(
+      entries(r#"{ "deny": { "path": "$HOME/x" } }"#),
+      vec![entry.clone()]
+    );
+    assert_eq!(
+      entries(r#"{ "allow": [{ "path": "$HOME/x" }, "$HOME/y"] }"#),
+      [entry.clone(), serde_json::json!("$HOME/y")]
+    );
+    assert!(entries("{}").is_empty());
+
+    let permission: crate::acl::Permission = toml::from_str(
+      r#"
+identifier = "allow-home"
+commands.allow = ["read"]
+scope.allow = { path = "$HOME/x" }
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+      permission.scope.allow.unwrap(),
+      [crate::acl::Value::from(entry.clone())]
+    );
+
+    let capability: Capability = serde_json::from_str(
+      r#"{ "identifier": "main", "windows": ["main"], "permissions": [{ "identifier": "fs:scope", "allow": { "path": "$HOME/x" } }] }"#,
+    )
+    .unwrap();
+    let PermissionEntry::ExtendedPermission { scope, .. } = &capability.permissions[0] else {
+      panic!("expected an extended permission");
+    };
+    assert_eq!(scope.allow.as_ref().unwrap().len(), 1);
+
+    for invalid in [r#"{ "allow": "$HOME/x" }"#, r#"{ "deny": 1 }"#] {
+      let error = serde_json::from_str::<Scopes>(invalid).unwrap_err();
+      assert!(
+        error
+          .to_string()
+          .contains("expected a scope entry object or an array of scope entries"),
+        "{error}"
+      );
+    }
+  }
+
+  #[test]
+  fn capability_context_forms() {
+    let parse = |context: &str| serde_json::from_str::<CapabilityContext>(context);
+
+    assert_eq!(parse(r#""local""#).unwrap(), CapabilityContext::Local);
+    assert_eq!(
+      parse(r#"{ "remote": { "domains": ["tauri.app", { "domain": "example.com" }], "certificate-pin": "sha256/AAAA" } }"#)
+        .unwrap(),
+      CapabilityContext::Remote {
+        domains: vec![
+          RemoteDomain::Pattern("tauri.app".into()),
+          RemoteDomain::Domain {
+            domain: "example.com".into(),
+            include_subdomains: false
+          }
+        ],
+        attestation: RemoteAttestation {
+          certificate_pin: Some("sha256/AAAA".into()),
+          ..Default::default()
+        },
+      }
+    );
+
+    for (context, message) in [
+      (
+        r#""remote""#,
+        r#"the "remote" context requires the domains it applies to, use { "remote": { "domains": ["tauri.app"] } }"#,
+      ),
+      (
+        r#"{ "remote": {} }"#,
+        "the remote context is missing its domains",
+      ),
+      (
+        r#"{ "remote": { "domains": [] } }"#,
+        "the remote context requires at least one domain",
+      ),
+      (
+        r#"{ "remote": { "domains": [1] } }"#,
+        r#"invalid remote domain 1, expected a domain pattern string like "*.tauri.app""#,
+      ),
+      (
+        r#"{ "remote": { "domains": [{ "include-subdomains": true }] } }"#,
+        "invalid remote domain object: missing field `domain`",
+      ),
+      (
+        r#"{ "remote": { "domains": "tauri.app" } }"#,
+        "invalid remote context: invalid type: string",
+      ),
+      (
+        r#""global""#,
+        r#"invalid capability context "global", expected "local" or a remote context"#,
+      ),
+      (
+        r#"{ "remote": { "domains": ["tauri.app"] }, "local": null }"#,
+        "invalid capability context",
+      ),
+    ] {
+      let error = parse(context).unwrap_err().to_string();
+      assert!(error.contains(message), "{context}: {error}");
+    }
+  }
+
+  #[cfg(feature = "schema")]
+  #[test]
+  fn capability_context_schema() {
+    let schema = serde_json::to_value(schemars::schema_for!(CapabilityContext)).unwrap();
+    let forms = schema["oneOf"].as_array().unwrap();
+    assert_eq!(forms[0]["enum"], serde_json::json!(["local"]));
+    let remote = &forms[1]["properties"]["remote"];
+    assert_eq!(remote["required"], serde_json::json!(["domains"]));
+    assert_eq!(remote["properties"]["domains"]["minItems"], 1);
+  }
+
+  #[test]
+  fn verify_remote_attestation() {
+    let attestation = RemoteAttestation {
+      required_header: Some(RequiredHeader {
+        name: "X-Tauri-Attest".into(),
+        value: "token".into(),
+      }),
+      certificate_pin: Some("sha256/AAAA".into()),
+    };
+    let evidence = |header: &str, pin: &str| NavigationEvidence {
+      headers: Some(vec![("x-tauri-attest".into(), header.into())]),
+      certificate_pins: Some(vec!["sha256/BBBB".into(), pin.into()]),
+    };
+
+    assert!(RemoteAttestation::default().verify(None).is_ok());
+    assert!(attestation
+      .verify(Some(&evidence("token", "sha256/AAAA")))
+      .is_ok());
+    assert!(attestation
+      .verify(Some(&evidence("other", "sha256/AAAA")))
+      .is_err());
+    assert!(attestation
+      .verify(Some(&evidence("token", "sha256/CCCC")))
+      .is_err());
+
+    // fails closed when the webview cannot observe the navigation
+    assert!(attestation.verify(None).is_err());
+    let unsupported = NavigationEvidence {
+      headers: Some(Vec::new()),
+      certificate_pins: None,
+    };
+    let reason = RemoteAttestation {
+      required_header: None,
+      ..attestation.clone()
+    }
+    .verify(Some(&unsupported))
+    .unwrap_err();
+    assert!(reason.contains("does not expose certificates"), "{reason}");
+  }
+}
@@ -0,0 +1,168 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Identifier for plugin commands.
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+/// Prefix of the IPC command names that target a plugin.
+pub const PLUGIN_COMMAND_PREFIX: &str = "plugin:";
+/// Separator between the plugin name and the command name.
+pub const PLUGIN_COMMAND_SEPARATOR: char = '|';
+
+/// Plugin command identifier, formatted as `plugin:{plugin}|{command}` in IPC requests.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CommandId {
+  plugin: String,
+  command: String,
+}
+
+/// Errors that can happen when parsing a plugin command identifier.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseCommandIdError {
+  /// Command identifier does not start with the plugin prefix.
+  #[error("plugin commands must start with {}", PLUGIN_COMMAND_PREFIX)]
+  MissingPrefix,
+
+  /// Command identifier does not separate the plugin and the command names.
+  #[error(
+    "plugin commands must separate the plugin and command names with '{}'",
+    PLUGIN_COMMAND_SEPARATOR
+  )]
+  MissingSeparator,
+
+  /// Plugin name is empty.
+  #[error("plugin names cannot be empty")]
+  EmptyPlugin,
+
+  /// Command name is empty.
+  #[error("command names cannot be empty")]
+  EmptyCommand,
+
+  /// Plugin or command name includes the separator.
+  #[error(
+    "plugin and command names cannot include the separator '{}'",
+    PLUGIN_COMMAND_SEPARATOR
+  )]
+  ContainsSeparator,
+}
+
+impl CommandId {
+  /// Creates the identifier of the given plugin command.
+  pub fn new(
+    plugin: impl Into<String>,
+    command: impl Into<String>,
+  ) -> Result<Self, ParseCommandIdError> {
+    let plugin = plugin.into();
+    let command = command.into();
+    Self::validate_name(&plugin, ParseCommandIdError::EmptyPlugin)?;
+    Self::validate_command(&command)?;
+    Ok(Self { plugin, command })
+  }
+
+  /// Parses a `plugin:{plugin}|{command}` command identifier.
+  pub fn parse(id: &str) -> Result<Self, ParseCommandIdError> {
+    let (plugin, command) = id
+      .strip_prefix(PLUGIN_COMMAND_PREFIX)
+      .ok_or(ParseCommandIdError::MissingPrefix)?
+      .split_once(PLUGIN_COMMAND_SEPARATOR)
+      .ok_or(ParseCommandIdError::MissingSeparator)?;
+    Self::new(plugin, command)
+  }
+
+  /// Validates a command name, which cannot be empty or include the separator.
+  pub fn validate_command(command: &str) -> Result<(), ParseCommandIdError> {
+    Self::validate_name(command, ParseCommandIdError::EmptyCommand)
+  }
+
+  /// The plugin name.
+  pub fn plugin(&self) -> &str {
+    &self.plugin
+  }
+
+  /// The command name.
+  pub fn command(&self) -> &str {
+    &self.command
+  }
+
+  fn validate_name(name: &str, empty: ParseCommandIdError) -> Result<(), ParseCommandIdError> {
+    if name.is_empty() {
+      Err(empty)
+    } else if name.contains(PLUGIN_COMMAND_SEPARATOR) {
+      Err(ParseCommandIdError::ContainsSeparator)
+    } else {
+      Ok(())
+    }
+  }
+}
+
+impl fmt::Display for CommandId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{PLUGIN_COMMAND_PREFIX}{}{PLUGIN_COMMAND_SEPARATOR}{}",
+      self.plugin, self.command
+    )
+  }
+}
+
+impl FromStr for CommandId {
+  type Err = ParseCommandIdError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::parse(s)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse() {
+    let id = CommandId::parse("plugin:fs|read_file").unwrap();
+    assert_eq!(id.plugin(), "fs");
+    assert_eq!(id.command(), "read_file");
+    assert_eq!(id.to_string(), "plugin:fs|read_file");
+
+    assert_eq!(
+      CommandId::parse("fs|read_file"),
+      Err(ParseCommandIdError::MissingPrefix)
+    );
+    assert_eq!(
+      CommandId::parse("plugin:fs"),
+      Err(ParseCommandIdError::MissingSeparator)
+    );
+    assert_eq!(
+      CommandId::parse("plugin:|read_file"),
+      Err(ParseCommandIdError::EmptyPlugin)
+    );
+    assert_eq!(
+      CommandId::parse("plugin:fs|"),
+      Err(ParseCommandIdError::EmptyCommand)
+    );
+    assert_eq!(
+      CommandId::parse("plugin:fs|read|write"),
+      Err(ParseCommandIdError::ContainsSeparator)
+    );
+  }
+
+  #[test]
+  fn new() {
+    assert_eq!(
+      CommandId::new("fs", "read_file").unwrap(),
+      "plugin:fs|read_file".parse().unwrap()
+    );
+    assert_eq!(
+      CommandId::new("fs", "read|write"),
+      Err(ParseCommandIdError::ContainsSeparator)
+    );
+    assert_eq!(
+      CommandId::new("f|s", "read"),
+      Err(ParseCommandIdError::ContainsSeparator)
+    );
+  }
+}
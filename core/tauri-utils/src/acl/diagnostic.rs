@@ -0,0 +1,323 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Machine-readable ACL errors, e.g. for editors to annotate the capability and permission files.
+
+use std::{ops::Range, path::PathBuf};
+
+use serde::Serialize;
+
+use super::{Error, ErrorContext};
+
+/// An ACL loading or resolution error in a machine-readable form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+  /// Path of the file causing the error, if known.
+  pub path: Option<PathBuf>,
+  /// Byte range of the error in the file, if known.
+  pub range: Option<Range<usize>>,
+  /// Stable code of the error, see [`Error::code`].
+  pub code: &'static str,
+  /// Human-readable description of the error.
+  pub message: String,
+  /// Identifiers of the plugins, permissions, permission sets, capabilities and commands related to the error.
+  pub related: Vec<String>,
+}
+
+impl From<&Error> for Diagnostic {
+  fn from(error: &Error) -> Self {
+    Self {
+      path: error.path().map(Into::into),
+      range: range(error),
+      code: error.code(),
+      message: error.to_string(),
+      related: related(error),
+    }
+  }
+}
+
+fn range(error: &Error) -> Option<Range<usize>> {
+  match error.root() {
+    Error::Toml(error) => error.span(),
+    #[cfg(feature = "capability-yaml")]
+    Error::Yaml { error, .. } => error
+      .location()
+      .map(|location| location.index()..location.index()),
+    _ => None,
+  }
+}
+
+fn related(error: &Error) -> Vec<String> {
+  let qualified = |plugin: &str, identifier: &str| format!("{plugin}:{identifier}");
+  match error {
+    Error::Context { context, error } => {
+      let mut related = related(error);
+      if let ErrorContext::Capability(capability) = context {
+        if !related.contains(capability) {
+          related.insert(0, capability.clone());
+        }
+      }
+      related
+    }
+    Error::DuplicateCapability { identifier, .. } => vec![identifier.clone()],
+    Error::UnknownCapabilityGroup { capability, .. }
+    | Error::MissingRequiredScope { capability, .. }
+    | Error::PermissionsUnavailableOnTarget { capability, .. }
+    | Error::InvalidWindowPattern { capability, .. }
+    | Error::InvalidRemoteDomain { capability, .. }
+    | Error::InvalidRemoteAttestation { capability, .. }
+    | Error::InvalidInheritance { capability, .. }
+    | Error::InvalidReservedCapability { capability, .. } => {
+      let mut related = vec![capability.clone()];
+      if let Error::MissingRequiredScope {
+        command,
+        permission,
+        ..
+      } = error
+      {
+        related.extend([permission.clone(), command.clone()]);
+      }
+      related
+    }
+    Error::SetPermissionNotFound { permission, set }
+    | Error::SetOperandNotFound {
+      permission, set, ..
+    }
+    | Error::DelegatedPermissionNotFound { set, permission }
+    | Error::PermissionNotDelegable { set, permission } => vec![set.clone(), permission.clone()],
+    Error::DelegatedPluginNotFound {
+      set,
+      permission,
+      plugin,
+    } => vec![set.clone(), permission.clone(), plugin.clone()],
+    Error::PermissionSetCycle { plugin, cycle, .. } => {
+      // the cycle starts and ends with the same set
+      cycle[..cycle.len().saturating_sub(1)]
+        .iter()
+        .map(|set| qualified(plugin, set))
+        .collect()
+    }
+    Error::CommandAliasCycle { plugin, cycle } => cycle[..cycle.len().saturating_sub(1)]
+      .iter()
+      .map(|alias| format!("plugin:{plugin}|{alias}"))
+      .collect(),
+    Error::CommandAliasCollision { plugin, alias } => vec![format!("plugin:{plugin}|{alias}")],
+    Error::DuplicatePermissionTier { plugin, sets, .. } => {
+      sets.iter().map(|set| qualified(plugin, set)).collect()
+    }
+    Error::UnknownScopePreset { plugin, .. } => vec![plugin.clone()],
+    Error::ScopePresetCycle { plugin, cycle } => cycle[..cycle.len().saturating_sub(1)]
+      .iter()
+      .map(|preset| qualified(plugin, preset))
+      .collect(),
+    Error::MissingDefaultPermission { plugin, .. }
+    | Error::UnknownPlugin { plugin, .. }
+    | Error::ScopeHook { plugin, .. } => vec![plugin.clone()],
+    Error::UnknownPermission { plugin, permission } => vec![qualified(plugin, permission)],
+    Error::SubtractingPermissionAllowsCommands { permission }
+    | Error::InvalidArgumentRule { permission, .. } => vec![permission.clone()],
+    Error::InvalidCommand {
+      permission,
+      command,
+      ..
+    }
+    | Error::InvalidCommandPattern {
+      permission,
+      command,
+      ..
+    } => vec![permission.clone(), command.clone()],
+    Error::UnmatchedCommandPattern {
+      plugin,
+      permission,
+      command,
+    } => vec![qualified(plugin, permission), command.clone()],
+    Error::InvalidCommandWindowPattern { command, .. } | Error::MergeConflict { command, .. } => {
+      vec![command.clone()]
+    }
+    Error::UnknownPluginWindows {
+      capability, plugin, ..
+    }
+    | Error::PluginVersionMismatch {
+      capability, plugin, ..
+    }
+    | Error::InvalidPluginVersionRequirement {
+      capability, plugin, ..
+    } => vec![capability.clone(), plugin.clone()],
+    _ => Vec::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use serde_json::json;
+
+  use super::*;
+  use crate::{
+    acl::{
+      capability::Capability,
+      plugin::{Manifest, PermissionFile},
+      resolved::Resolved,
+    },
+    platform::Target,
+  };
+
+  fn resolve(permissions: &str, permission: &str) -> Error {
+    let permissions: PermissionFile = toml::from_str(permissions).unwrap();
+    let acl: BTreeMap<String, Manifest> =
+      [("fs".to_string(), Manifest::new(vec![permissions], None))]
+        .into_iter()
+        .collect();
+    let capability: Capability = serde_json::from_value(json!({
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": [permission]
+    }))
+    .unwrap();
+    Resolved::resolve(
+      acl,
+      [("main".to_string(), capability)].into_iter().collect(),
+      Target::current(),
+    )
+    .unwrap_err()
+  }
+
+  fn diagnostic(error: &Error) -> serde_json::Value {
+    serde_json::to_value(Diagnostic::from(error)).unwrap()
+  }
+
+  #[test]
+  fn unknown_permission() {
+    let error = resolve(
+      r#"
+[[permission]]
+identifier = "allow-read-file"
+commands.allow = ["read_file"]
+"#,
+      "fs:allow-missing",
+    );
+    assert_eq!(
+      diagnostic(&error),
+      json!({
+        "path": null,
+        "range": null,
+        "code": "unknown-permission",
+        "message": "in capability main: unknown permission allow-missing for plugin fs",
+        "related": ["main", "fs:allow-missing"]
+      })
+    );
+  }
+
+  #[test]
+  fn permission_set_cycle() {
+    let error = resolve(
+      r#"
+[[set]]
+identifier = "cycle"
+description = "Includes a set including it."
+permissions = ["includes-cycle"]
+
+[[set]]
+identifier = "includes-cycle"
+description = "Includes a set including it."
+permissions = ["cycle"]
+"#,
+      "fs:cycle",
+    );
+    assert_eq!(
+      diagnostic(&error),
+      json!({
+        "path": null,
+        "range": null,
+        "code": "permission-set-cycle",
+        "message": "in capability main: permission set fs:cycle references itself: cycle -> includes-cycle -> cycle",
+        "related": ["main", "fs:cycle", "fs:includes-cycle"]
+      })
+    );
+  }
+
+  #[test]
+  fn invalid_patterns() {
+    let error = resolve(
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read_[file"]
+"#,
+      "fs:allow-read",
+    );
+    let diagnostic = diagnostic(&error);
+    assert_eq!(diagnostic["code"], "invalid-command-pattern");
+    assert_eq!(
+      diagnostic["related"],
+      json!(["main", "allow-read", "read_[file"])
+    );
+
+    let capability: Capability = serde_json::from_value(json!({
+      "identifier": "main",
+      "windows": ["main-[*"],
+      "permissions": []
+    }))
+    .unwrap();
+    let error = capability
+      .parse_window_patterns(Some(std::path::Path::new("capabilities/main.json")))
+      .unwrap_err();
+    assert_eq!(
+      serde_json::to_value(Diagnostic::from(&error)).unwrap(),
+      json!({
+        "path": "capabilities/main.json",
+        "range": null,
+        "code": "invalid-window-pattern",
+        "message": error.to_string(),
+        "related": ["main"]
+      })
+    );
+  }
+
+  #[test]
+  fn toml_error_range() {
+    let error = Error::from(toml::from_str::<PermissionFile>("[[permission]\n").unwrap_err())
+      .with_file("permissions/fs.toml");
+    let diagnostic = Diagnostic::from(&error);
+    assert_eq!(diagnostic.code, "invalid-toml");
+    assert_eq!(diagnostic.path, Some("permissions/fs.toml".into()));
+    assert!(diagnostic.range.is_some());
+  }
+
+  #[test]
+  fn error_context() {
+    let error = Error::UnknownPermissionFormat("ini".into())
+      .with_file("permissions/fs.ini")
+      .with_capability("main");
+    assert_eq!(
+      error.to_string(),
+      "in capability main: in permissions/fs.ini: unknown permission format ini"
+    );
+    assert_eq!(error.code(), "unknown-permission-format");
+    assert_eq!(error.capability(), Some("main"));
+    assert!(matches!(error.root(), Error::UnknownPermissionFormat(_)));
+    assert_eq!(
+      diagnostic(&error),
+      json!({
+        "path": "permissions/fs.ini",
+        "range": null,
+        "code": "unknown-permission-format",
+        "message": error.to_string(),
+        "related": ["main"]
+      })
+    );
+
+    // the context the error already carries is not repeated
+    let error = Error::PermissionsUnavailableOnTarget {
+      capability: "main".into(),
+      target: Target::Linux,
+    }
+    .with_capability("main");
+    assert!(matches!(
+      error,
+      Error::PermissionsUnavailableOnTarget { .. }
+    ));
+  }
+}
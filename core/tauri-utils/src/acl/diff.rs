@@ -0,0 +1,366 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Semantic differences between two resolved ACLs, e.g. the ACLs of the dev and release builds of an app.
+//!
+//! Only what the runtime enforces is compared: the capabilities referencing the commands,
+//! whether they are granted through a `default` permission set and the keys of their scopes are ignored.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+
+use super::{
+  resolved::{
+    CommandKey, Resolved, ResolvedCommand, ResolvedScope, WebviewPattern, WindowAncestor,
+    WindowGlobalScope,
+  },
+  window::WindowPattern,
+  ExecutionContext,
+};
+
+/// A difference between two resolved ACLs, see [`Resolved::diff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AclDifference {
+  /// What differs, e.g. `allowed plugin:fs|read_file (local) windows`.
+  ///
+  /// Matched by the patterns of an [`AclDiffAllowlist`].
+  pub subject: String,
+  /// The value in the first ACL, [`None`] if only the second ACL has it.
+  pub left: Option<JsonValue>,
+  /// The value in the second ACL, [`None`] if only the first ACL has it.
+  pub right: Option<JsonValue>,
+}
+
+impl fmt::Display for AclDifference {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let value = |value: &Option<JsonValue>| {
+      value
+        .as_ref()
+        .map_or_else(|| "(none)".to_string(), JsonValue::to_string)
+    };
+    write!(
+      f,
+      "{}: {} -> {}",
+      self.subject,
+      value(&self.left),
+      value(&self.right)
+    )
+  }
+}
+
+/// The differences between two resolved ACLs that are expected, e.g. the commands of a dev-only capability.
+///
+/// Parsed from a list of glob patterns matching the [`AclDifference#structfield.subject`], one per line.
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// ```text
+/// # the devtools capability is only enabled on dev builds
+/// allowed plugin:devtools|* (local)
+/// allowed plugin:fs|read_file (local) scopes
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct AclDiffAllowlist(Vec<glob::Pattern>);
+
+impl AclDiffAllowlist {
+  /// Parses the allowlist patterns, returning the line and the error of the first invalid pattern.
+  pub fn parse(allowlist: &str) -> Result<Self, (usize, glob::PatternError)> {
+    allowlist
+      .lines()
+      .enumerate()
+      .map(|(index, line)| (index + 1, line.trim()))
+      .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+      .map(|(line, pattern)| glob::Pattern::new(pattern).map_err(|error| (line, error)))
+      .collect::<Result<_, _>>()
+      .map(Self)
+  }
+
+  /// Whether the difference is expected.
+  pub fn allows(&self, difference: &AclDifference) -> bool {
+    self
+      .0
+      .iter()
+      .any(|pattern| pattern.matches(&difference.subject))
+  }
+}
+
+impl Resolved {
+  /// The semantic differences between this ACL and another one, ordered by subject.
+  ///
+  /// The commands only allowed or denied by one of the ACLs are reported as a whole,
+  /// the others field by field. Scopes are compared by value, in any order.
+  pub fn diff(&self, other: &Self) -> Vec<AclDifference> {
+    let mut differences = Vec::new();
+    for (kind, left, right) in [
+      ("allowed", &self.allowed_commands, &other.allowed_commands),
+      ("denied", &self.denied_commands, &other.denied_commands),
+    ] {
+      let left = commands(self, left);
+      let right = commands(other, right);
+      for (key, left_command) in &left {
+        let subject = format!("{kind} {key}");
+        match right.get(key) {
+          Some(right_command) => {
+            for (field, left_value) in left_command {
+              let right_value = &right_command[field];
+              if left_value != right_value {
+                differences.push(AclDifference {
+                  subject: format!("{subject} {field}"),
+                  left: Some(left_value.clone()),
+                  right: Some(right_value.clone()),
+                });
+              }
+            }
+          }
+          None => differences.push(AclDifference {
+            subject,
+            left: Some(json!(left_command)),
+            right: None,
+          }),
+        }
+      }
+      for (key, right_command) in right.iter().filter(|(key, _)| !left.contains_key(*key)) {
+        differences.push(AclDifference {
+          subject: format!("{kind} {key}"),
+          left: None,
+          right: Some(json!(right_command)),
+        });
+      }
+    }
+
+    let plugins = self
+      .global_scope
+      .keys()
+      .chain(other.global_scope.keys())
+      .collect::<std::collections::BTreeSet<_>>();
+    for plugin in plugins {
+      let left = self
+        .global_scope
+        .get(plugin)
+        .map(|scopes| global_scopes(scopes));
+      let right = other
+        .global_scope
+        .get(plugin)
+        .map(|scopes| global_scopes(scopes));
+      if left != right {
+        differences.push(AclDifference {
+          subject: format!("global-scope {plugin}"),
+          left,
+          right,
+        });
+      }
+    }
+
+    let left = json!(webviews(&self.inheriting_webviews));
+    let right = json!(webviews(&other.inheriting_webviews));
+    if left != right {
+      differences.push(AclDifference {
+        subject: "inheriting-webviews".into(),
+        left: Some(left),
+        right: Some(right),
+      });
+    }
+
+    if self.lockdown_remote != other.lockdown_remote {
+      differences.push(AclDifference {
+        subject: "lockdown-remote".into(),
+        left: Some(json!(self.lockdown_remote)),
+        right: Some(json!(other.lockdown_remote)),
+      });
+    }
+
+    differences.sort_by(|a, b| a.subject.cmp(&b.subject));
+    differences
+  }
+}
+
+/// The comparable fields of each command, keyed by the command name and context.
+fn commands(
+  resolved: &Resolved,
+  commands: &BTreeMap<CommandKey, ResolvedCommand>,
+) -> BTreeMap<String, BTreeMap<&'static str, JsonValue>> {
+  commands
+    .iter()
+    .map(|(key, command)| {
+      (
+        format!("{} ({})", key.name, context(&key.context)),
+        command_fields(resolved, command),
+      )
+    })
+    .collect()
+}
+
+fn command_fields(
+  resolved: &Resolved,
+  command: &ResolvedCommand,
+) -> BTreeMap<&'static str, JsonValue> {
+  let mut scopes = command
+    .scopes
+    .iter()
+    .map(|window_scope| {
+      let value = resolved
+        .command_scope
+        .get(&window_scope.scope)
+        .or_else(|| resolved.command_scope_shards.get(window_scope.scope))
+        .map(scope);
+      json!({
+        "windows": patterns(&window_scope.windows),
+        "window-ancestors": ancestors(&window_scope.window_ancestors),
+        "webviews": webviews(&window_scope.webviews),
+        "pages": pages(&window_scope.pages),
+        "scope": value,
+      })
+    })
+    .collect::<Vec<_>>();
+  scopes.sort_by_key(JsonValue::to_string);
+
+  let mut configs = command
+    .configs
+    .iter()
+    .map(|config| {
+      json!({
+        "windows": patterns(&config.windows),
+        "window-ancestors": ancestors(&config.window_ancestors),
+        "webviews": webviews(&config.webviews),
+        "pages": pages(&config.pages),
+        "config": JsonValue::from(config.config.clone()),
+      })
+    })
+    .collect::<Vec<_>>();
+  configs.sort_by_key(JsonValue::to_string);
+
+  let mut page_grants = command
+    .pages
+    .iter()
+    .map(|grant| {
+      json!({
+        "windows": patterns(&grant.windows),
+        "window-ancestors": ancestors(&grant.window_ancestors),
+        "webviews": webviews(&grant.webviews),
+        "pages": pages(&grant.pages),
+      })
+    })
+    .collect::<Vec<_>>();
+  page_grants.sort_by_key(JsonValue::to_string);
+
+  let sorted = |values: &[String]| {
+    let mut values = values.to_vec();
+    values.sort();
+    values.dedup();
+    json!(values)
+  };
+
+  BTreeMap::from([
+    ("windows", json!(patterns(&command.windows))),
+    (
+      "window-ancestors",
+      json!(ancestors(&command.window_ancestors)),
+    ),
+    ("webviews", json!(webviews(&command.webviews))),
+    ("pages", json!(page_grants)),
+    ("scopes", json!(scopes)),
+    ("classes", sorted(&command.classes)),
+    ("validators", sorted(&command.validators)),
+    ("args", json!(command.args)),
+    ("configs", json!(configs)),
+  ])
+}
+
+fn global_scopes(scopes: &[WindowGlobalScope]) -> JsonValue {
+  let mut scopes = scopes
+    .iter()
+    .map(|window_scope| {
+      json!({
+        "windows": patterns(&window_scope.windows),
+        "window-ancestors": ancestors(&window_scope.window_ancestors),
+        "webviews": webviews(&window_scope.webviews),
+        "pages": pages(&window_scope.pages),
+        "scope": scope(&window_scope.scope),
+      })
+    })
+    .collect::<Vec<_>>();
+  scopes.sort_by_key(JsonValue::to_string);
+  json!(scopes)
+}
+
+fn context(context: &ExecutionContext) -> String {
+  match context {
+    ExecutionContext::Local => "local".into(),
+    ExecutionContext::Remote {
+      domain,
+      attestation: None,
+    } => format!("remote: {domain}"),
+    ExecutionContext::Remote {
+      domain,
+      attestation: Some(attestation),
+    } => format!(
+      "remote: {domain}, attestation: {}",
+      serde_json::to_string(attestation).expect("failed to serialize attestation")
+    ),
+  }
+}
+
+fn scope(scope: &ResolvedScope) -> JsonValue {
+  let values = |values: &[super::Value]| {
+    let mut values = values
+      .iter()
+      .cloned()
+      .map(JsonValue::from)
+      .collect::<Vec<_>>();
+    values.sort_by_key(JsonValue::to_string);
+    values
+  };
+  json!({
+    "allow": values(&scope.allow),
+    "deny": values(&scope.deny),
+  })
+}
+
+fn patterns(patterns: &[WindowPattern]) -> Vec<String> {
+  let mut patterns = patterns
+    .iter()
+    .map(|pattern| pattern.as_str().to_string())
+    .collect::<Vec<_>>();
+  patterns.sort();
+  patterns.dedup();
+  patterns
+}
+
+fn ancestors(ancestors: &[WindowAncestor]) -> Vec<String> {
+  let mut ancestors = ancestors
+    .iter()
+    .map(|ancestor| {
+      let selector = if ancestor.parent_only {
+        "parent-of"
+      } else {
+        "descendant-of"
+      };
+      format!("{selector} {}", ancestor.pattern)
+    })
+    .collect::<Vec<_>>();
+  ancestors.sort();
+  ancestors.dedup();
+  ancestors
+}
+
+fn pages(pages: &[glob::Pattern]) -> Vec<String> {
+  let mut pages = pages
+    .iter()
+    .map(|page| page.as_str().to_string())
+    .collect::<Vec<_>>();
+  pages.sort();
+  pages.dedup();
+  pages
+}
+
+fn webviews(webviews: &[WebviewPattern]) -> Vec<String> {
+  let mut webviews = webviews
+    .iter()
+    .map(|webview| format!("webview {} in {}", webview.webview, webview.window))
+    .collect::<Vec<_>>();
+  webviews.sort();
+  webviews.dedup();
+  webviews
+}
@@ -0,0 +1,435 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Helpers for testing how plugin permissions resolve.
+//!
+//! Plugins can load their permission files, build capabilities inline and compare the resolved ACL
+//! against a golden file, so CI catches accidental changes to the permissions they document.
+//!
+//! ```rust,no_run
+//! use tauri_utils::{
+//!   acl::fixtures::{self, CapabilityBuilder},
+//!   assert_command_allowed, assert_command_denied,
+//!   platform::Target,
+//! };
+//!
+//! let manifest = fixtures::load_manifest("fs", "./permissions").unwrap();
+//! let resolved = fixtures::resolve(
+//!   [manifest],
+//!   [CapabilityBuilder::new("main")
+//!     .window("main")
+//!     .permission("fs:default")
+//!     .build()],
+//!   Target::current(),
+//! )
+//! .unwrap();
+//!
+//! assert_command_allowed!(resolved, "plugin:fs|read_file", window = "main");
+//! assert_command_denied!(resolved, "plugin:fs|remove", window = "main");
+//! fixtures::assert_golden(&resolved, "tests/golden/default.json");
+//! ```
+
+use std::{
+  collections::BTreeMap,
+  fs::{create_dir_all, read_to_string, write},
+  path::{Path, PathBuf},
+};
+
+use serde_json::{json, Map, Value as JsonValue};
+
+use super::{
+  build::{
+    parse_permissions, UnknownFields, PERMISSION_FILE_EXTENSIONS, PERMISSION_SCHEMAS_FOLDER_NAME,
+  },
+  capability::{
+    Attenuation, Capability, CapabilityContext, CapabilityWindow, PermissionEntry,
+    RemoteAttestation, RemoteDomain,
+  },
+  plugin::Manifest,
+  resolved::{
+    flatten_global_scope, CommandKey, Resolved, ResolvedCommand, ResolvedScope, ScopeKey,
+    WebviewPattern, WindowAncestor,
+  },
+  window::WindowPattern,
+  Error, ExecutionContext, Identifier, Scopes, Value,
+};
+use crate::platform::Target;
+
+/// Environment variable that makes [`assert_golden`] write the golden files instead of comparing them.
+pub const UPDATE_GOLDEN_FILES_ENV: &str = "TAURI_UPDATE_GOLDEN_FILES";
+
+/// Loads the permission files in the given directory and its subdirectories into the manifest of a plugin.
+///
+/// The permission schemas are skipped like in the plugin build script.
+pub fn load_manifest(
+  plugin: impl Into<String>,
+  dir: impl AsRef<Path>,
+) -> Result<(String, Manifest), Error> {
+  let pattern = dir.as_ref().join("**").join("*");
+  let permission_files = glob::glob(&pattern.to_string_lossy())?
+    .flatten()
+    .filter(|path| {
+      path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| PERMISSION_FILE_EXTENSIONS.contains(&e))
+        .unwrap_or_default()
+    })
+    .filter(|path| {
+      path.parent().and_then(Path::file_name) != Some(PERMISSION_SCHEMAS_FOLDER_NAME.as_ref())
+    })
+    .collect::<Vec<PathBuf>>();
+
+  Ok((
+    plugin.into(),
+    Manifest::new(
+      parse_permissions(permission_files, UnknownFields::Ignore)?,
+      None,
+    ),
+  ))
+}
+
+/// Resolves the ACL of the given plugin manifests and capabilities.
+pub fn resolve(
+  manifests: impl IntoIterator<Item = (String, Manifest)>,
+  capabilities: impl IntoIterator<Item = Capability>,
+  target: Target,
+) -> Result<Resolved, Error> {
+  Resolved::resolve(
+    manifests.into_iter().collect(),
+    capabilities
+      .into_iter()
+      .map(|capability| (capability.identifier.clone(), capability))
+      .collect(),
+    target,
+  )
+}
+
+/// Builds a [`Capability`] inline.
+///
+/// Invalid permission identifiers panic, as the builder is meant to be used in tests.
+#[derive(Debug)]
+pub struct CapabilityBuilder(Capability);
+
+impl CapabilityBuilder {
+  /// Creates a local capability with the given identifier, no windows and no permissions.
+  pub fn new(identifier: impl Into<String>) -> Self {
+    Self(Capability {
+      identifier: identifier.into(),
+      description: String::new(),
+      context: CapabilityContext::Local,
+      windows: Vec::new(),
+      permissions: Vec::new(),
+      platforms: super::capability::default_platforms(),
+      group: None,
+      inherit_from_window: false,
+      roles: Vec::new(),
+      attenuate: None,
+      pages: Vec::new(),
+      plugin_version: Default::default(),
+      extensions: BTreeMap::new(),
+    })
+  }
+
+  /// Adds a window to the capability.
+  #[must_use]
+  pub fn window(mut self, window: impl Into<CapabilityWindow>) -> Self {
+    self.0.windows.push(window.into());
+    self
+  }
+
+  /// Adds a permission to the capability.
+  #[must_use]
+  pub fn permission(mut self, identifier: &str) -> Self {
+    self
+      .0
+      .permissions
+      .push(PermissionEntry::PermissionRef(parse_identifier(identifier)));
+    self
+  }
+
+  /// Adds a permission to the capability, extending its scope with the given values.
+  #[must_use]
+  pub fn permission_scoped(
+    mut self,
+    identifier: &str,
+    allow: impl IntoIterator<Item = Value>,
+    deny: impl IntoIterator<Item = Value>,
+  ) -> Self {
+    let allow = allow.into_iter().collect::<Vec<_>>();
+    let deny = deny.into_iter().collect::<Vec<_>>();
+    self
+      .0
+      .permissions
+      .push(PermissionEntry::ExtendedPermission {
+        identifier: parse_identifier(identifier),
+        scope: Scopes {
+          allow: (!allow.is_empty()).then_some(allow),
+          deny: (!deny.is_empty()).then_some(deny),
+        },
+        subtract: false,
+        include_deny: false,
+        config: None,
+        plugin_version: None,
+        extensions: BTreeMap::new(),
+      });
+    self
+  }
+
+  /// Makes the capability apply to the given remote domain patterns instead of the local app URL.
+  #[must_use]
+  pub fn remote<S: Into<String>>(mut self, domains: impl IntoIterator<Item = S>) -> Self {
+    self.0.context = CapabilityContext::Remote {
+      domains: domains
+        .into_iter()
+        .map(|domain| RemoteDomain::Pattern(domain.into()))
+        .collect(),
+      attestation: RemoteAttestation::default(),
+    };
+    self
+  }
+
+  /// Sets the constraints the navigation to the remote domains must satisfy.
+  ///
+  /// Must be called after [`Self::remote`].
+  #[must_use]
+  pub fn attestation(mut self, attestation: RemoteAttestation) -> Self {
+    match &mut self.0.context {
+      CapabilityContext::Remote {
+        attestation: current,
+        ..
+      } => *current = attestation,
+      CapabilityContext::Local => panic!("only remote capabilities can require an attestation"),
+    }
+    self
+  }
+
+  /// Restricts the capability to the given platforms.
+  #[must_use]
+  pub fn platforms(mut self, platforms: impl IntoIterator<Item = Target>) -> Self {
+    self.0.platforms = platforms.into_iter().collect();
+    self
+  }
+
+  /// Sets the group of the capability.
+  #[must_use]
+  pub fn group(mut self, group: impl Into<String>) -> Self {
+    self.0.group.replace(group.into());
+    self
+  }
+
+  /// Makes the webviews selected by the capability inherit the grants of their window, reduced by the attenuation.
+  #[must_use]
+  pub fn inherit_from_window(mut self, attenuation: Attenuation) -> Self {
+    self.0.inherit_from_window = true;
+    self.0.attenuate.replace(attenuation);
+    self
+  }
+
+  /// Builds the capability.
+  pub fn build(self) -> Capability {
+    self.0
+  }
+}
+
+fn parse_identifier(identifier: &str) -> Identifier {
+  Identifier::try_from(identifier.to_string())
+    .unwrap_or_else(|e| panic!("invalid permission identifier {identifier}: {e}"))
+}
+
+/// Whether the command is allowed on the given window and origin.
+///
+/// The origin is [`None`] for the local app URL and the remote domain otherwise.
+/// Like the runtime authority, a command denied on the origin is not allowed on any window,
+/// and only the first allowed entry matching the origin is checked.
+/// Entries that require a [`RemoteAttestation`] never match, as there is no webview navigation to verify.
+pub fn is_command_allowed(
+  resolved: &Resolved,
+  command: &str,
+  window: &str,
+  origin: Option<&str>,
+) -> bool {
+  let matches_origin = |context: &ExecutionContext| match (context, origin) {
+    (ExecutionContext::Local, None) => true,
+    (ExecutionContext::Remote { domain, .. }, Some(origin)) => domain.matches(origin),
+    _ => false,
+  };
+
+  !resolved
+    .denied_commands
+    .keys()
+    .any(|key| key.name == command && matches_origin(&key.context))
+    && resolved
+      .allowed_commands
+      .iter()
+      .find(|(key, _)| {
+        key.name == command
+          && matches_origin(&key.context)
+          && !matches!(
+            key.context,
+            ExecutionContext::Remote {
+              attestation: Some(_),
+              ..
+            }
+          )
+      })
+      .is_some_and(|(_, resolved_command)| resolved_command.matches_window::<&str>(window, &[]))
+}
+
+/// Serializes the resolved ACL as JSON that does not depend on the resolution order or build profile.
+///
+/// Scopes are inlined in their commands instead of referenced by key,
+/// and the capabilities that reference each command are omitted.
+pub fn snapshot(resolved: &Resolved) -> String {
+  let commands = |commands: &BTreeMap<CommandKey, ResolvedCommand>| {
+    commands
+      .iter()
+      .map(|(key, command)| {
+        let context = match &key.context {
+          ExecutionContext::Local => "local".to_string(),
+          ExecutionContext::Remote {
+            domain,
+            attestation: None,
+          } => format!("remote: {domain}"),
+          ExecutionContext::Remote {
+            domain,
+            attestation: Some(attestation),
+          } => format!(
+            "remote: {domain}, attestation: {}",
+            serde_json::to_string(attestation).expect("failed to serialize attestation")
+          ),
+        };
+        (
+          format!("{} ({context})", key.name),
+          command_snapshot(command, &resolved.command_scope),
+        )
+      })
+      .collect::<Map<_, _>>()
+  };
+
+  let value = json!({
+    "allowed_commands": commands(&resolved.allowed_commands),
+    "denied_commands": commands(&resolved.denied_commands),
+    "global_scope": resolved
+      .global_scope
+      .iter()
+      .map(|(plugin, scopes)| (plugin.clone(), scope_snapshot(&flatten_global_scope(scopes))))
+      .collect::<Map<_, _>>(),
+  });
+  let mut snapshot = serde_json::to_string_pretty(&value).expect("failed to serialize snapshot");
+  snapshot.push('\n');
+  snapshot
+}
+
+fn command_snapshot(
+  command: &ResolvedCommand,
+  command_scope: &BTreeMap<ScopeKey, ResolvedScope>,
+) -> JsonValue {
+  let patterns = |patterns: &[WindowPattern]| {
+    let mut patterns = patterns
+      .iter()
+      .map(|pattern| pattern.as_str().to_string())
+      .collect::<Vec<_>>();
+    patterns.sort();
+    patterns
+  };
+  let ancestors = |ancestors: &[WindowAncestor]| {
+    let mut ancestors = ancestors
+      .iter()
+      .map(|ancestor| {
+        let selector = if ancestor.parent_only {
+          "parent-of"
+        } else {
+          "descendant-of"
+        };
+        format!("{selector} {}", ancestor.pattern)
+      })
+      .collect::<Vec<_>>();
+    ancestors.sort();
+    ancestors
+  };
+
+  // only listed when set, so the snapshots of the capabilities without webview selectors are unchanged
+  let with_webviews = |mut snapshot: JsonValue, webviews: &[WebviewPattern]| {
+    if !webviews.is_empty() {
+      let mut webviews = webviews
+        .iter()
+        .map(|webview| format!("webview {} in {}", webview.webview, webview.window))
+        .collect::<Vec<_>>();
+      webviews.sort();
+      snapshot["webviews"] = json!(webviews);
+    }
+    snapshot
+  };
+
+  let mut scopes = command
+    .scopes
+    .iter()
+    .map(|scope| {
+      with_webviews(
+        json!({
+          "windows": patterns(&scope.windows),
+          "window_ancestors": ancestors(&scope.window_ancestors),
+          "scope": command_scope.get(&scope.scope).map(scope_snapshot),
+        }),
+        &scope.webviews,
+      )
+    })
+    .collect::<Vec<_>>();
+  scopes.sort_by_key(|scope| scope.to_string());
+
+  with_webviews(
+    json!({
+      "windows": patterns(&command.windows),
+      "window_ancestors": ancestors(&command.window_ancestors),
+      "scopes": scopes,
+      "classes": command.classes,
+    }),
+    &command.webviews,
+  )
+}
+
+fn scope_snapshot(scope: &ResolvedScope) -> JsonValue {
+  let values = |values: &[Value]| {
+    values
+      .iter()
+      .cloned()
+      .map(JsonValue::from)
+      .collect::<Vec<_>>()
+  };
+  json!({
+    "allow": values(&scope.allow),
+    "deny": values(&scope.deny),
+  })
+}
+
+/// Compares the [`snapshot`] of the resolved ACL with the golden file at the given path.
+///
+/// The golden file is written if it does not exist or if the [`UPDATE_GOLDEN_FILES_ENV`] environment variable is set.
+///
+/// # Panics
+///
+/// Panics if the snapshot does not match the golden file or if the file cannot be read or written.
+pub fn assert_golden(resolved: &Resolved, path: impl AsRef<Path>) {
+  let path = path.as_ref();
+  let snapshot = snapshot(resolved);
+
+  if !path.exists() || std::env::var_os(UPDATE_GOLDEN_FILES_ENV).is_some() {
+    if let Some(parent) = path.parent() {
+      create_dir_all(parent)
+        .unwrap_or_else(|e| panic!("failed to create {}: {e}", parent.display()));
+    }
+    write(path, snapshot).unwrap_or_else(|e| panic!("failed to write {}: {e}", path.display()));
+    return;
+  }
+
+  let golden =
+    read_to_string(path).unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
+  if golden.replace("\r\n", "\n") != snapshot {
+    panic!(
+      "resolved ACL does not match the golden file {}, set {UPDATE_GOLDEN_FILES_ENV}=1 to update it\n\nexpected:\n{golden}\nactual:\n{snapshot}",
+      path.display()
+    );
+  }
+}
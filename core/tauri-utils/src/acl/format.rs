@@ -0,0 +1,474 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Versions of the on-disk format of the capability files.
+//!
+//! A capability file declares its format with a top-level `version` field,
+//! next to the capability fields or to the `capabilities` list. Files without it are version 1,
+//! the earliest supported format, so existing projects keep building unchanged.
+//!
+//! | Version | Changes |
+//! |---------|---------|
+//! | 1       | The scope of an extended permission entry is flattened in the entry: `{ "identifier": "fs:allow-read", "allow": [..] }`. |
+//! | 2       | The scope of an extended permission entry is nested: `{ "identifier": "fs:allow-read", "scope": { "allow": [..] } }`, so it cannot be mistaken for an unknown field of the entry. |
+//!
+//! The files are rewritten to the current format with [`Capability::migrate_from_v1`].
+
+use std::fmt;
+
+use serde_json::{Map, Value as JsonValue};
+
+use super::{capability::Capability, Error};
+
+/// The field declaring the format version of a capability file.
+pub const VERSION_FIELD: &str = "version";
+
+/// The fields of an extended permission entry nested under `scope` since version 2.
+const SCOPE_FIELDS: &[&str] = &["allow", "deny"];
+
+/// A version of the on-disk format of the capability files, see the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CapabilityFormat {
+  /// The format of the unversioned files.
+  V1,
+  /// The format nesting the scope of the extended permission entries.
+  V2,
+}
+
+impl CapabilityFormat {
+  /// The format of the files without a version.
+  pub const EARLIEST: Self = Self::V1;
+  /// The format the files are migrated to.
+  pub const CURRENT: Self = Self::V2;
+
+  /// The version number declared in the files.
+  pub fn number(self) -> u64 {
+    match self {
+      Self::V1 => 1,
+      Self::V2 => 2,
+    }
+  }
+
+  /// The format declared by the capability file, [`Self::EARLIEST`] when it has no `version` field.
+  pub fn of(file: &JsonValue) -> Result<Self, Error> {
+    match file.get(VERSION_FIELD) {
+      None => Ok(Self::EARLIEST),
+      Some(version) => match version.as_u64() {
+        Some(1) => Ok(Self::V1),
+        Some(2) => Ok(Self::V2),
+        _ => Err(Error::CapabilityFormat {
+          version: version.to_string(),
+          reason: format!(
+            "unsupported version, expected a number from {} to {}",
+            Self::EARLIEST.number(),
+            Self::CURRENT.number()
+          ),
+        }),
+      },
+    }
+  }
+
+  fn error(self, reason: impl Into<String>) -> Error {
+    Error::CapabilityFormat {
+      version: self.number().to_string(),
+      reason: reason.into(),
+    }
+  }
+}
+
+impl fmt::Display for CapabilityFormat {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.number())
+  }
+}
+
+/// Deserializes the capabilities of a capability file, which defines a capability or a list of capabilities,
+/// with the deserializer of the format it declares.
+pub fn deserialize_capability_file(file: JsonValue) -> Result<Vec<Capability>, Error> {
+  match CapabilityFormat::of(&file)? {
+    CapabilityFormat::V1 => deserialize_v1(file),
+    CapabilityFormat::V2 => deserialize_v2(file),
+  }
+}
+
+/// The version 1 layout is the layout of [`Capability`].
+fn deserialize_v1(mut file: JsonValue) -> Result<Vec<Capability>, Error> {
+  if let Some(file) = file.as_object_mut() {
+    file.remove(VERSION_FIELD);
+  }
+  match file.get("capabilities") {
+    Some(_) if file.get("identifier").is_none() => {
+      let capabilities = file
+        .as_object_mut()
+        .and_then(|file| file.remove("capabilities"))
+        .unwrap_or_default();
+      serde_json::from_value(capabilities).map_err(Into::into)
+    }
+    _ => Ok(vec![serde_json::from_value(file)?]),
+  }
+}
+
+/// Lifts the nested scopes of the extended permission entries to the version 1 layout.
+fn deserialize_v2(mut file: JsonValue) -> Result<Vec<Capability>, Error> {
+  for (path, capability) in capabilities_mut(&mut file) {
+    for (path, entry) in permission_entries_mut(capability, &path) {
+      if let Some(field) = SCOPE_FIELDS
+        .iter()
+        .find(|field| entry.contains_key(**field))
+      {
+        return Err(CapabilityFormat::V2.error(format!(
+          "`{}` must be nested in `{}`",
+          join(&path, field),
+          join(&path, &format!("scope.{field}"))
+        )));
+      }
+      match entry.remove("scope") {
+        Some(JsonValue::Object(scope)) => {
+          if let Some(field) = scope
+            .keys()
+            .find(|field| !SCOPE_FIELDS.contains(&field.as_str()))
+          {
+            return Err(CapabilityFormat::V2.error(format!(
+              "unknown scope field `{}`, expected `allow` or `deny`",
+              join(&path, &format!("scope.{field}"))
+            )));
+          }
+          entry.extend(scope);
+        }
+        Some(_) => {
+          return Err(CapabilityFormat::V2.error(format!(
+            "`{}` must be an object with `allow` and `deny` entries",
+            join(&path, "scope")
+          )))
+        }
+        None => (),
+      }
+    }
+  }
+  deserialize_v1(file)
+}
+
+/// A field of a capability file transformed by a migration, see [`Capability::migrate_from_v1`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldChange {
+  /// The field was added with the value.
+  Added {
+    /// The path of the field in the file, e.g. `version`.
+    path: String,
+    /// The value of the field.
+    value: JsonValue,
+  },
+  /// The field was moved without changing its value.
+  Moved {
+    /// The previous path of the field, e.g. `permissions[1].allow`.
+    from: String,
+    /// The new path of the field, e.g. `permissions[1].scope.allow`.
+    to: String,
+  },
+}
+
+impl fmt::Display for FieldChange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Added { path, value } => write!(f, "added `{path}` = {value}"),
+      Self::Moved { from, to } => write!(f, "moved `{from}` to `{to}`"),
+    }
+  }
+}
+
+/// A capability file rewritten to the current format, see [`Capability::migrate_from_v1`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapabilityMigration {
+  /// The file in the [`CapabilityFormat::CURRENT`] format.
+  pub file: JsonValue,
+  /// The fields transformed by the migration, in file order. Empty if the file was already in the current format.
+  pub changes: Vec<FieldChange>,
+}
+
+impl Capability {
+  /// Rewrites a version 1 capability file to the [`CapabilityFormat::CURRENT`] format,
+  /// reporting each transformed field.
+  ///
+  /// The file defines a capability or a list of capabilities, parsed from JSON, TOML or YAML.
+  /// The rewritten file resolves to the same ACL. The unknown fields are kept as is,
+  /// except a `scope` field of a version 1 permission entry, which version 2 reserves.
+  /// A file already in the current format is returned unchanged.
+  pub fn migrate_from_v1(file: &JsonValue) -> Result<CapabilityMigration, Error> {
+    let format = CapabilityFormat::of(file)?;
+    if format == CapabilityFormat::CURRENT {
+      return Ok(CapabilityMigration {
+        file: file.clone(),
+        changes: Vec::new(),
+      });
+    }
+    // only valid files are rewritten
+    deserialize_v1(file.clone())?;
+
+    let mut file = file.clone();
+    let mut changes = Vec::new();
+    for (path, capability) in capabilities_mut(&mut file) {
+      for (path, entry) in permission_entries_mut(capability, &path) {
+        if entry.contains_key("scope") {
+          return Err(format.error(format!(
+            "`{}` is reserved for the scope of the entry in version {}, rename it before migrating",
+            join(&path, "scope"),
+            CapabilityFormat::CURRENT
+          )));
+        }
+        let mut scope = Map::new();
+        for field in SCOPE_FIELDS {
+          if let Some(value) = entry.remove(*field) {
+            changes.push(FieldChange::Moved {
+              from: join(&path, field),
+              to: join(&path, &format!("scope.{field}")),
+            });
+            scope.insert(field.to_string(), value);
+          }
+        }
+        if !scope.is_empty() {
+          entry.insert("scope".into(), JsonValue::Object(scope));
+        }
+      }
+    }
+
+    let version = JsonValue::from(CapabilityFormat::CURRENT.number());
+    if let Some(file) = file.as_object_mut() {
+      file.insert(VERSION_FIELD.into(), version.clone());
+    }
+    changes.insert(
+      0,
+      FieldChange::Added {
+        path: VERSION_FIELD.into(),
+        value: version,
+      },
+    );
+
+    Ok(CapabilityMigration { file, changes })
+  }
+}
+
+/// The capability objects of the file along with their path.
+fn capabilities_mut(file: &mut JsonValue) -> Vec<(String, &mut Map<String, JsonValue>)> {
+  let Some(file) = file.as_object_mut() else {
+    return Vec::new();
+  };
+  if file.contains_key("identifier") || !file.contains_key("capabilities") {
+    return vec![(String::new(), file)];
+  }
+  match file.get_mut("capabilities") {
+    Some(JsonValue::Array(capabilities)) => capabilities
+      .iter_mut()
+      .enumerate()
+      .filter_map(|(i, capability)| {
+        Some((format!("capabilities[{i}]"), capability.as_object_mut()?))
+      })
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+/// The extended permission entries of the capability along with their path.
+fn permission_entries_mut<'a>(
+  capability: &'a mut Map<String, JsonValue>,
+  path: &str,
+) -> Vec<(String, &'a mut Map<String, JsonValue>)> {
+  match capability.get_mut("permissions") {
+    Some(JsonValue::Array(entries)) => entries
+      .iter_mut()
+      .enumerate()
+      .filter_map(|(i, entry)| {
+        Some((
+          join(path, &format!("permissions[{i}]")),
+          entry.as_object_mut()?,
+        ))
+      })
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+fn join(path: &str, field: &str) -> String {
+  if path.is_empty() {
+    field.to_string()
+  } else {
+    format!("{path}.{field}")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::acl::capability::PermissionEntry;
+
+  fn v1_file() -> JsonValue {
+    json!({
+      "$schema": "../gen/schemas/desktop-schema.json",
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": [
+        "fs:default",
+        {
+          "identifier": "fs:allow-read",
+          "allow": [{ "path": "$APPDATA/**" }],
+          "deny": [{ "path": "$APPDATA/secret" }],
+          "x-owner": "team-a"
+        },
+        { "identifier": "http:default", "config": { "timeout": 5 } }
+      ]
+    })
+  }
+
+  #[test]
+  fn versions() {
+    assert_eq!(
+      CapabilityFormat::of(&v1_file()).unwrap(),
+      CapabilityFormat::V1
+    );
+    assert_eq!(
+      CapabilityFormat::of(&json!({ "version": 1 })).unwrap(),
+      CapabilityFormat::V1
+    );
+    assert_eq!(
+      CapabilityFormat::of(&json!({ "version": 2, "capabilities": [] })).unwrap(),
+      CapabilityFormat::V2
+    );
+    for version in [json!(3), json!("2"), json!(0)] {
+      assert!(matches!(
+        CapabilityFormat::of(&json!({ "version": version })),
+        Err(Error::CapabilityFormat { .. })
+      ));
+    }
+  }
+
+  #[test]
+  fn migrate_capability() {
+    let migration = Capability::migrate_from_v1(&v1_file()).unwrap();
+    assert_eq!(
+      migration.file,
+      json!({
+        "$schema": "../gen/schemas/desktop-schema.json",
+        "version": 2,
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": [
+          "fs:default",
+          {
+            "identifier": "fs:allow-read",
+            "scope": {
+              "allow": [{ "path": "$APPDATA/**" }],
+              "deny": [{ "path": "$APPDATA/secret" }]
+            },
+            "x-owner": "team-a"
+          },
+          { "identifier": "http:default", "config": { "timeout": 5 } }
+        ]
+      })
+    );
+    assert_eq!(
+      migration
+        .changes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>(),
+      [
+        "added `version` = 2",
+        "moved `permissions[1].allow` to `permissions[1].scope.allow`",
+        "moved `permissions[1].deny` to `permissions[1].scope.deny`",
+      ]
+    );
+
+    // the file is read the same way in both formats
+    let before = deserialize_capability_file(v1_file()).unwrap();
+    let after = deserialize_capability_file(migration.file.clone()).unwrap();
+    assert_eq!(
+      serde_json::to_value(&before).unwrap(),
+      serde_json::to_value(&after).unwrap()
+    );
+    let PermissionEntry::ExtendedPermission { scope, .. } = &after[0].permissions[1] else {
+      panic!("expected an extended permission entry");
+    };
+    assert_eq!(scope.allow.as_ref().map(Vec::len), Some(1));
+    assert_eq!(scope.deny.as_ref().map(Vec::len), Some(1));
+
+    // migrating again changes nothing
+    let again = Capability::migrate_from_v1(&migration.file).unwrap();
+    assert_eq!(again.file, migration.file);
+    assert!(again.changes.is_empty());
+  }
+
+  #[test]
+  fn migrate_capability_list() {
+    let file = json!({
+      "capabilities": [
+        { "identifier": "a", "windows": ["a"], "permissions": ["fs:default"] },
+        {
+          "identifier": "b",
+          "windows": ["b"],
+          "permissions": [{ "identifier": "fs:allow-read", "allow": ["$HOME/**"] }]
+        }
+      ]
+    });
+    let migration = Capability::migrate_from_v1(&file).unwrap();
+    assert_eq!(migration.file["version"], 2);
+    assert_eq!(
+      migration.file["capabilities"][1]["permissions"][0],
+      json!({ "identifier": "fs:allow-read", "scope": { "allow": ["$HOME/**"] } })
+    );
+    assert_eq!(
+      migration.changes[1],
+      FieldChange::Moved {
+        from: "capabilities[1].permissions[0].allow".into(),
+        to: "capabilities[1].permissions[0].scope.allow".into(),
+      }
+    );
+
+    let capabilities = deserialize_capability_file(migration.file).unwrap();
+    assert_eq!(
+      capabilities
+        .iter()
+        .map(|capability| capability.identifier.as_str())
+        .collect::<Vec<_>>(),
+      ["a", "b"]
+    );
+  }
+
+  #[test]
+  fn invalid_files() {
+    // version 2 files cannot use the version 1 layout
+    let error = deserialize_capability_file(json!({
+      "version": 2,
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": [{ "identifier": "fs:allow-read", "allow": ["$HOME/**"] }]
+    }))
+    .unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "capability file format version 2: `permissions[0].allow` must be nested in `permissions[0].scope.allow`"
+    );
+
+    let error = deserialize_capability_file(json!({
+      "version": 2,
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": [{ "identifier": "fs:allow-read", "scope": { "alow": [] } }]
+    }))
+    .unwrap_err();
+    assert!(error.to_string().contains("`permissions[0].scope.alow`"));
+
+    // the scope field of a version 1 entry would become its scope
+    let error = Capability::migrate_from_v1(&json!({
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": [{ "identifier": "fs:allow-read", "scope": "custom" }]
+    }))
+    .unwrap_err();
+    assert!(matches!(error, Error::CapabilityFormat { version, .. } if version == "1"));
+
+    // invalid version 1 files are not rewritten
+    assert!(Capability::migrate_from_v1(&json!({ "identifier": "main" })).is_err());
+  }
+}
@@ -16,8 +16,23 @@ const MAX_LEN_PREFIX: usize = 64 - PLUGIN_PREFIX.len();
 const MAX_LEN_BASE: usize = 64;
 const MAX_LEN_IDENTIFIER: usize = MAX_LEN_PREFIX + 1 + MAX_LEN_BASE;
 
+/// Base of the identifier referencing every permission of a plugin, e.g. `fs:*`.
+pub const ALL_PERMISSIONS: &str = "*";
+
+/// Default maximum number of namespace segments in the prefix of an [`Identifier`].
+pub const DEFAULT_MAX_PREFIX_SEGMENTS: usize = 2;
+
 /// Plugin identifier.
-#[derive(Debug, Clone)]
+///
+/// Identifiers are made of lowercase ASCII segments separated by colons,
+/// where each segment can include hyphens that are not leading or trailing.
+/// The last segment is the base of the identifier and the other ones form its prefix,
+/// e.g. `core:window` is the prefix of `core:window:allow-set-title`.
+/// The base can also be [`ALL_PERMISSIONS`] to reference every permission of a plugin.
+///
+/// An identifier cannot be longer than 116 bytes
+/// and its prefix has at most [`DEFAULT_MAX_PREFIX_SEGMENTS`] segments unless parsed with [`Identifier::with_max_prefix_segments`].
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Identifier {
   inner: String,
@@ -32,6 +47,71 @@ impl AsRef<str> for Identifier {
 }
 
 impl Identifier {
+  /// Parses an identifier whose prefix can have up to `max_prefix_segments` namespace segments.
+  pub fn with_max_prefix_segments(
+    value: impl Into<String>,
+    max_prefix_segments: usize,
+  ) -> Result<Self, ParseIdentifierError> {
+    let value = value.into();
+
+    if value.starts_with(PLUGIN_PREFIX) {
+      return Err(ParseIdentifierError::StartsWithTauriPlugin(value));
+    }
+
+    if value.is_empty() {
+      return Err(ParseIdentifierError::Empty);
+    }
+
+    let mut bytes = value.bytes();
+    if bytes.len() > MAX_LEN_IDENTIFIER {
+      return Err(ParseIdentifierError::Humungous {
+        len: bytes.len(),
+        identifier: value,
+      });
+    }
+
+    // grab the first byte only before parsing the rest
+    let Some(mut prev) = bytes.next().and_then(ValidByte::lower_alpha) else {
+      return Err(ParseIdentifierError::InvalidFormat(value));
+    };
+
+    let mut idx = 0;
+    let mut separator = None;
+    let mut prefix_segments = 0;
+    for byte in bytes {
+      idx += 1; // we already consumed first item
+      match prev.next(byte) {
+        None => return Err(ParseIdentifierError::InvalidFormat(value)),
+        Some(next @ (ValidByte::Byte(_) | ValidByte::AllPermissions)) => prev = next,
+        Some(ValidByte::Separator) => {
+          prefix_segments += 1;
+          if prefix_segments > max_prefix_segments {
+            return Err(ParseIdentifierError::TooManyPrefixSegments {
+              identifier: value,
+              max: max_prefix_segments,
+            });
+          }
+          // safe to unwrap because idx starts at 1 and cannot go over MAX_IDENTIFIER_LEN
+          separator = Some(idx.try_into().unwrap());
+          prev = ValidByte::Separator
+        }
+      }
+    }
+
+    match prev {
+      // empty base
+      ValidByte::Separator => Err(ParseIdentifierError::PrefixWithoutBase(value)),
+
+      // trailing hyphen
+      ValidByte::Byte(b'-') => Err(ParseIdentifierError::TrailingHyphen(value)),
+
+      _ => Ok(Self {
+        inner: value,
+        separator,
+      }),
+    }
+  }
+
   /// Get the identifier str.
   #[inline(always)]
   pub fn get(&self) -> &str {
@@ -46,17 +126,30 @@ impl Identifier {
     }
   }
 
-  /// Get the prefix of the identifier.
+  /// Get the prefix of the identifier, joining all of its namespace segments.
   pub fn get_prefix(&self) -> Option<&str> {
     self.separator_index().map(|i| &self.inner[0..i])
   }
 
+  /// Get the namespace segments of the identifier prefix.
+  pub fn get_prefix_segments(&self) -> Vec<&str> {
+    self
+      .get_prefix()
+      .map(|prefix| prefix.split(IDENTIFIER_SEPARATOR as char).collect())
+      .unwrap_or_default()
+  }
+
   /// Set the identifier prefix.
   pub fn set_prefix(&mut self) -> Result<(), ParseIdentifierError> {
     todo!()
   }
 
-  /// Get the identifier string and its separator.
+  /// Whether the identifier references every permission of its plugin, e.g. `fs:*`.
+  pub fn is_all_permissions(&self) -> bool {
+    self.separator.is_some() && self.get_base() == ALL_PERMISSIONS
+  }
+
+  /// Get the identifier string and the index of the separator between its prefix and base.
   pub fn into_inner(self) -> (String, Option<NonZeroU8>) {
     (self.inner, self.separator)
   }
@@ -70,6 +163,8 @@ impl Identifier {
 enum ValidByte {
   Separator,
   Byte(u8),
+  /// The [`ALL_PERMISSIONS`] base, which must end the identifier.
+  AllPermissions,
 }
 
 impl ValidByte {
@@ -83,8 +178,12 @@ impl ValidByte {
 
   fn next(&self, next: u8) -> Option<ValidByte> {
     match (self, next) {
+      (ValidByte::AllPermissions, _) => None,
+      (ValidByte::Separator, b'*') => Some(ValidByte::AllPermissions),
+
       (ValidByte::Byte(b'-'), IDENTIFIER_SEPARATOR) => None,
       (ValidByte::Separator, b'-') => None,
+      (ValidByte::Separator, IDENTIFIER_SEPARATOR) => None,
 
       (_, IDENTIFIER_SEPARATOR) => Some(ValidByte::Separator),
       (ValidByte::Separator, next) => ValidByte::lower_alpha(next),
@@ -98,93 +197,49 @@ impl ValidByte {
 #[derive(Debug, Error)]
 pub enum ParseIdentifierError {
   /// Identifier start with the plugin prefix.
-  #[error("identifiers cannot start with {}", PLUGIN_PREFIX)]
-  StartsWithTauriPlugin,
+  #[error("identifier `{0}` cannot start with {PLUGIN_PREFIX}")]
+  StartsWithTauriPlugin(String),
 
   /// Identifier empty.
   #[error("identifiers cannot be empty")]
   Empty,
 
   /// Identifier is too long.
-  #[error("identifiers cannot be longer than {}, found {0}", MAX_LEN_IDENTIFIER)]
-  Humungous(usize),
+  #[error("identifier `{identifier}` cannot be longer than {MAX_LEN_IDENTIFIER}, found {len}")]
+  Humungous {
+    /// The identifier.
+    identifier: String,
+    /// Length of the identifier.
+    len: usize,
+  },
 
   /// Identifier is not in a valid format.
-  #[error("identifiers can only include lowercase ASCII, hyphens which are not leading or trailing, and a single colon if using a prefix")]
-  InvalidFormat,
-
-  /// Identifier has multiple separators.
-  #[error(
-    "identifiers can only include a single separator '{}'",
-    IDENTIFIER_SEPARATOR
-  )]
-  MultipleSeparators,
+  #[error("identifier `{0}` can only include lowercase ASCII, hyphens which are not leading or trailing, and colons separating non-empty segments, or end with `:*`")]
+  InvalidFormat(String),
+
+  /// Identifier prefix has too many namespace segments.
+  #[error("identifier `{identifier}` can only have {max} prefix segments")]
+  TooManyPrefixSegments {
+    /// The identifier.
+    identifier: String,
+    /// Maximum number of prefix segments.
+    max: usize,
+  },
 
   /// Identifier has a trailing hyphen.
-  #[error("identifiers cannot have a trailing hyphen")]
-  TrailingHyphen,
+  #[error("identifier `{0}` cannot have a trailing hyphen")]
+  TrailingHyphen(String),
 
   /// Identifier has a prefix without a base.
-  #[error("identifiers cannot have a prefix without a base")]
-  PrefixWithoutBase,
+  #[error("identifier `{0}` cannot have a prefix without a base")]
+  PrefixWithoutBase(String),
 }
 
 impl TryFrom<String> for Identifier {
   type Error = ParseIdentifierError;
 
   fn try_from(value: String) -> Result<Self, Self::Error> {
-    if value.starts_with(PLUGIN_PREFIX) {
-      return Err(Self::Error::StartsWithTauriPlugin);
-    }
-
-    if value.is_empty() {
-      return Err(Self::Error::Empty);
-    }
-
-    let mut bytes = value.bytes();
-    if bytes.len() > MAX_LEN_IDENTIFIER {
-      return Err(Self::Error::Humungous(bytes.len()));
-    }
-
-    // grab the first byte only before parsing the rest
-    let mut prev = bytes
-      .next()
-      .and_then(ValidByte::lower_alpha)
-      .ok_or(Self::Error::InvalidFormat)?;
-
-    let mut idx = 0;
-    let mut seperator = None;
-    for byte in bytes {
-      idx += 1; // we already consumed first item
-      match prev.next(byte) {
-        None => return Err(Self::Error::InvalidFormat),
-        Some(next @ ValidByte::Byte(_)) => prev = next,
-        Some(ValidByte::Separator) => {
-          if seperator.is_none() {
-            // safe to unwrap because idx starts at 1 and cannot go over MAX_IDENTIFIER_LEN
-            seperator = Some(idx.try_into().unwrap());
-            prev = ValidByte::Separator
-          } else {
-            return Err(Self::Error::MultipleSeparators);
-          }
-        }
-      }
-    }
-
-    match prev {
-      // empty base
-      ValidByte::Separator => return Err(Self::Error::PrefixWithoutBase),
-
-      // trailing hyphen
-      ValidByte::Byte(b'-') => return Err(Self::Error::TrailingHyphen),
-
-      _ => (),
-    }
-
-    Ok(Self {
-      inner: value,
-      separator: seperator,
-    })
+    Self::with_max_prefix_segments(value, DEFAULT_MAX_PREFIX_SEGMENTS)
   }
 }
 
@@ -206,6 +261,25 @@ impl Serialize for Identifier {
   }
 }
 
+#[cfg(feature = "tokens")]
+mod build {
+  use proc_macro2::TokenStream;
+  use quote::{quote, ToTokens, TokenStreamExt};
+
+  use super::*;
+
+  impl ToTokens for Identifier {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let identifier = self.get();
+      // the identifier was parsed with enough prefix segments for itself
+      let prefix_segments = self.get_prefix_segments().len();
+      tokens.append_all(quote! {
+        ::tauri::utils::acl::Identifier::with_max_prefix_segments(#identifier, #prefix_segments).unwrap()
+      });
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -248,6 +322,22 @@ mod tests {
     assert!(ident("a".repeat(MAX_LEN_IDENTIFIER + 1)).is_err());
   }
 
+  #[test]
+  fn all_permissions() {
+    let all = ident("fs:*").unwrap();
+    assert!(all.is_all_permissions());
+    assert_eq!(all.get_prefix(), Some("fs"));
+    assert_eq!(all.get_base(), ALL_PERMISSIONS);
+    assert!(ident("core:window:*").unwrap().is_all_permissions());
+    assert!(!ident("fs:allow-read").unwrap().is_all_permissions());
+
+    assert!(ident("*").is_err());
+    assert!(ident("fs:*-read").is_err());
+    assert!(ident("fs:allow-*").is_err());
+    assert!(ident("fs:*:read").is_err());
+    assert!(ident("*:read").is_err());
+  }
+
   #[test]
   fn base() {
     assert_eq!(ident("prefix:base").unwrap().get_base(), "base");
@@ -259,4 +349,73 @@ mod tests {
     assert_eq!(ident("prefix:base").unwrap().get_prefix(), Some("prefix"));
     assert_eq!(ident("base").unwrap().get_prefix(), None);
   }
+
+  #[test]
+  fn prefix_segments() {
+    let id = ident("core:window:allow-set-title").unwrap();
+    assert_eq!(id.get_prefix(), Some("core:window"));
+    assert_eq!(id.get_prefix_segments(), vec!["core", "window"]);
+    assert_eq!(id.get_base(), "allow-set-title");
+    assert!(ident("base").unwrap().get_prefix_segments().is_empty());
+
+    assert!(matches!(
+      ident("a:b:c:base"),
+      Err(ParseIdentifierError::TooManyPrefixSegments { max: 2, .. })
+    ));
+    assert_eq!(
+      Identifier::with_max_prefix_segments("a:b:c:base", 3)
+        .unwrap()
+        .get_prefix(),
+      Some("a:b:c")
+    );
+    assert!(Identifier::with_max_prefix_segments("core:window:allow-set-title", 1).is_err());
+    assert!(Identifier::with_max_prefix_segments("base", 0).is_ok());
+  }
+
+  #[test]
+  fn errors_name_the_identifier() {
+    for invalid in [
+      "Prefix:base",
+      "prefix:base-",
+      "prefix:",
+      "a:b:c:base",
+      "tauri-plugin-fs:base",
+    ] {
+      let error = ident(invalid).unwrap_err().to_string();
+      assert!(error.contains(invalid), "{error}");
+    }
+    let long = "a".repeat(MAX_LEN_IDENTIFIER + 1);
+    assert!(ident(long.clone()).unwrap_err().to_string().contains(&long));
+  }
+
+  #[test]
+  fn serde_round_trip() {
+    for id in ["base", "prefix:base", "core:window:allow-set-title"] {
+      let identifier = ident(id).unwrap();
+      let json = serde_json::to_string(&identifier).unwrap();
+      assert_eq!(json, format!("\"{id}\""));
+      assert_eq!(
+        serde_json::from_str::<Identifier>(&json).unwrap(),
+        identifier
+      );
+    }
+    assert!(serde_json::from_str::<Identifier>("\"a:b:c:base\"").is_err());
+  }
+
+  #[cfg(feature = "tokens")]
+  #[test]
+  fn to_tokens() {
+    use quote::ToTokens;
+
+    let identifier = Identifier::with_max_prefix_segments("a:b:c:base", 3).unwrap();
+    assert_eq!(
+      identifier.into_token_stream().to_string(),
+      quote::quote!(::tauri::utils::acl::Identifier::with_max_prefix_segments(
+        "a:b:c:base",
+        3usize
+      )
+      .unwrap())
+      .to_string()
+    );
+  }
 }
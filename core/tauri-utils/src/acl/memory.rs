@@ -0,0 +1,558 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Estimated heap footprint of the resolved ACL, see [`Resolved::memory_stats`].
+//!
+//! The sizes are computed from the length and capacity of the values owned by the ACL types,
+//! without the allocator overhead nor the internal nodes of the maps,
+//! so they are meant to track regressions rather than to match the allocated memory exactly.
+
+use std::{
+  collections::{BTreeMap, HashSet},
+  mem::size_of,
+};
+
+use serde::Serialize;
+
+use super::{
+  args::{ArgumentMatcher, ArgumentRule},
+  capability::{RemoteAttestation, RequiredHeader},
+  command::CommandId,
+  resolved::{
+    CommandKey, ExternalScope, PageGrant, RemoteOrigin, Resolved, ResolvedCommand, ResolvedScope,
+    ScopeKey, WebviewPattern, WindowAncestor, WindowConfig, WindowGlobalScope, WindowScope,
+  },
+  tables::ScopeShards,
+  window::WindowPattern,
+  ExecutionContext, Number, Value,
+};
+
+/// Estimated size of a compiled glob pattern token, either a character or a character class.
+pub(crate) const GLOB_TOKEN_SIZE: usize = 4 * size_of::<usize>();
+
+/// Size accounting of the values owned by the ACL types.
+pub trait HeapSize {
+  /// Estimated number of bytes the value owns on the heap, excluding its own inline size.
+  fn heap_size(&self) -> usize;
+}
+
+impl HeapSize for String {
+  fn heap_size(&self) -> usize {
+    self.capacity()
+  }
+}
+
+impl HeapSize for bool {
+  fn heap_size(&self) -> usize {
+    0
+  }
+}
+
+impl HeapSize for usize {
+  fn heap_size(&self) -> usize {
+    0
+  }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+  fn heap_size(&self) -> usize {
+    self.as_ref().map_or(0, HeapSize::heap_size)
+  }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+  fn heap_size(&self) -> usize {
+    self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+  }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+  fn heap_size(&self) -> usize {
+    self
+      .iter()
+      .map(|(key, value)| size_of::<K>() + size_of::<V>() + key.heap_size() + value.heap_size())
+      .sum()
+  }
+}
+
+impl HeapSize for glob::Pattern {
+  fn heap_size(&self) -> usize {
+    let pattern = self.as_str();
+    pattern.len() + pattern.chars().count() * GLOB_TOKEN_SIZE
+  }
+}
+
+impl HeapSize for Number {
+  fn heap_size(&self) -> usize {
+    0
+  }
+}
+
+impl HeapSize for Value {
+  fn heap_size(&self) -> usize {
+    match self {
+      Self::Null | Self::Bool(_) | Self::Number(_) => 0,
+      Self::String(string) => string.heap_size(),
+      Self::List(list) => list.heap_size(),
+      Self::Map(map) => map.heap_size(),
+      Self::Bytes(bytes) => bytes.capacity(),
+    }
+  }
+}
+
+impl HeapSize for RequiredHeader {
+  fn heap_size(&self) -> usize {
+    self.name.heap_size() + self.value.heap_size()
+  }
+}
+
+impl HeapSize for RemoteAttestation {
+  fn heap_size(&self) -> usize {
+    self.required_header.heap_size() + self.certificate_pin.heap_size()
+  }
+}
+
+impl HeapSize for ExecutionContext {
+  fn heap_size(&self) -> usize {
+    match self {
+      Self::Local => 0,
+      Self::Remote {
+        domain,
+        attestation,
+      } => domain.heap_size() + attestation.heap_size(),
+    }
+  }
+}
+
+impl HeapSize for CommandKey {
+  fn heap_size(&self) -> usize {
+    self.name.heap_size() + self.context.heap_size()
+  }
+}
+
+impl HeapSize for WindowAncestor {
+  fn heap_size(&self) -> usize {
+    self.pattern.heap_size()
+  }
+}
+
+impl HeapSize for WebviewPattern {
+  fn heap_size(&self) -> usize {
+    self.window.heap_size() + self.webview.heap_size()
+  }
+}
+
+impl HeapSize for PageGrant {
+  fn heap_size(&self) -> usize {
+    self.windows.heap_size()
+      + self.window_ancestors.heap_size()
+      + self.webviews.heap_size()
+      + self.pages.heap_size()
+  }
+}
+
+impl HeapSize for WindowScope {
+  fn heap_size(&self) -> usize {
+    self.windows.heap_size()
+      + self.window_ancestors.heap_size()
+      + self.webviews.heap_size()
+      + self.pages.heap_size()
+  }
+}
+
+impl HeapSize for ArgumentRule {
+  fn heap_size(&self) -> usize {
+    self.name.heap_size()
+      + match &self.matcher {
+        ArgumentMatcher::Enum { values } => values.heap_size(),
+        ArgumentMatcher::PathScope | ArgumentMatcher::UrlScope | ArgumentMatcher::Range { .. } => 0,
+      }
+  }
+}
+
+impl HeapSize for WindowConfig {
+  fn heap_size(&self) -> usize {
+    self.capability.heap_size()
+      + self.windows.heap_size()
+      + self.window_ancestors.heap_size()
+      + self.webviews.heap_size()
+      + self.pages.heap_size()
+      + self.config.heap_size()
+  }
+}
+
+#[cfg(debug_assertions)]
+impl HeapSize for super::resolved::ResolvedCommandReference {
+  fn heap_size(&self) -> usize {
+    self.capability.heap_size() + self.permission.heap_size() + self.delegated_by.heap_size()
+  }
+}
+
+impl HeapSize for ResolvedCommand {
+  fn heap_size(&self) -> usize {
+    #[cfg(debug_assertions)]
+    let referenced_by = self.referenced_by.heap_size();
+    #[cfg(not(debug_assertions))]
+    let referenced_by = 0;
+    referenced_by
+      + self.windows.heap_size()
+      + self.window_ancestors.heap_size()
+      + self.webviews.heap_size()
+      + self.pages.heap_size()
+      + self.scopes.heap_size()
+      + self.classes.heap_size()
+      + self.validators.heap_size()
+      + self.args.heap_size()
+      + self.configs.heap_size()
+  }
+}
+
+impl HeapSize for ExternalScope {
+  fn heap_size(&self) -> usize {
+    self.key.heap_size() + self.hash.heap_size()
+  }
+}
+
+impl HeapSize for ResolvedScope {
+  fn heap_size(&self) -> usize {
+    self.allow.heap_size() + self.deny.heap_size() + self.external.heap_size()
+  }
+}
+
+impl HeapSize for WindowGlobalScope {
+  fn heap_size(&self) -> usize {
+    self.windows.heap_size()
+      + self.window_ancestors.heap_size()
+      + self.webviews.heap_size()
+      + self.pages.heap_size()
+      + self.scope.heap_size()
+  }
+}
+
+impl HeapSize for RemoteOrigin {
+  fn heap_size(&self) -> usize {
+    self.domain.heap_size()
+      + self.windows.heap_size()
+      + self.window_ancestors.heap_size()
+      + self.webviews.heap_size()
+  }
+}
+
+/// Number of entries and their estimated heap size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HeapStats {
+  /// Number of entries.
+  pub count: usize,
+  /// Estimated heap size of the entries, in bytes, including the map slots holding them.
+  pub bytes: usize,
+}
+
+impl HeapStats {
+  /// Accounts an entry of the given size.
+  pub fn add(&mut self, bytes: usize) {
+    self.count += 1;
+    self.bytes += bytes;
+  }
+}
+
+/// Number and estimated heap size of the window patterns, see [`WindowPattern`].
+///
+/// The equal patterns share their compiled pattern, which is only accounted once.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct WindowPatternStats {
+  /// Number of patterns.
+  pub count: usize,
+  /// Number of distinct compiled patterns.
+  pub unique: usize,
+  /// Number of template patterns.
+  pub templates: usize,
+  /// Size of the distinct pattern strings, in bytes.
+  pub source_bytes: usize,
+  /// Estimated size of the distinct compiled glob tokens and template parts, in bytes.
+  pub compiled_bytes: usize,
+  /// Estimated size the patterns sharing a compiled pattern would take if they each held a copy, in bytes.
+  pub shared_bytes: usize,
+  #[serde(skip)]
+  allocations: HashSet<usize>,
+}
+
+impl WindowPatternStats {
+  fn add(&mut self, pattern: &WindowPattern) {
+    let (source, compiled) = pattern.heap_sizes();
+    self.count += 1;
+    self.templates += usize::from(pattern.is_template());
+    if self.allocations.insert(pattern.allocation()) {
+      self.unique += 1;
+      self.source_bytes += source;
+      self.compiled_bytes += compiled;
+    } else {
+      self.shared_bytes += source + compiled;
+    }
+  }
+
+  fn add_all<'a>(&mut self, patterns: impl IntoIterator<Item = &'a WindowPattern>) {
+    for pattern in patterns {
+      self.add(pattern);
+    }
+  }
+
+  /// The estimated heap size of the distinct compiled patterns, in bytes.
+  pub fn bytes(&self) -> usize {
+    self.source_bytes + self.compiled_bytes
+  }
+}
+
+/// Estimated heap footprint of a resolved ACL, see [`Resolved::memory_stats`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct MemoryStats {
+  /// The allowed command entries.
+  pub allowed_commands: HeapStats,
+  /// The denied command entries.
+  pub denied_commands: HeapStats,
+  /// The window patterns of the commands, scopes and inheriting webviews,
+  /// not accounted in the entries holding them.
+  pub window_patterns: WindowPatternStats,
+  /// The command scopes of each plugin, app commands under an empty plugin name.
+  pub command_scopes: BTreeMap<String, HeapStats>,
+  /// The scope shards, with the scopes decoded so far, see [`ScopeShards`].
+  pub scope_shards: HeapStats,
+  /// The global scope entries of each plugin.
+  pub global_scope: BTreeMap<String, HeapStats>,
+  /// The inheriting webviews, the global API functions, the command aliases and the state derived from the ACL.
+  pub other: HeapStats,
+}
+
+impl MemoryStats {
+  /// The estimated heap size of the whole ACL, in bytes.
+  pub fn total_bytes(&self) -> usize {
+    self.allowed_commands.bytes
+      + self.denied_commands.bytes
+      + self
+        .command_scopes
+        .values()
+        .map(|stats| stats.bytes)
+        .sum::<usize>()
+      + self.window_patterns.bytes()
+      + self.scope_shards.bytes
+      + self
+        .global_scope
+        .values()
+        .map(|stats| stats.bytes)
+        .sum::<usize>()
+      + self.other.bytes
+  }
+
+  /// Accounts the allowed or denied command entries and their window patterns.
+  pub fn add_commands(&mut self, commands: &BTreeMap<CommandKey, ResolvedCommand>, denied: bool) {
+    let stats = if denied {
+      &mut self.denied_commands
+    } else {
+      &mut self.allowed_commands
+    };
+    for (key, command) in commands {
+      stats.add(
+        size_of::<CommandKey>()
+          + size_of::<ResolvedCommand>()
+          + key.heap_size()
+          + command.heap_size(),
+      );
+
+      let patterns = &mut self.window_patterns;
+      patterns.add_all(&command.windows);
+      patterns.add_all(command.window_ancestors.iter().map(|a| &a.pattern));
+      patterns.add_all(command.webviews.iter().flat_map(webview_patterns));
+      for grant in &command.pages {
+        patterns.add_all(&grant.windows);
+        patterns.add_all(grant.window_ancestors.iter().map(|a| &a.pattern));
+        patterns.add_all(grant.webviews.iter().flat_map(webview_patterns));
+      }
+      for scope in &command.scopes {
+        patterns.add_all(&scope.windows);
+        patterns.add_all(scope.window_ancestors.iter().map(|a| &a.pattern));
+        patterns.add_all(scope.webviews.iter().flat_map(webview_patterns));
+      }
+      for config in &command.configs {
+        patterns.add_all(&config.windows);
+        patterns.add_all(config.window_ancestors.iter().map(|a| &a.pattern));
+        patterns.add_all(config.webviews.iter().flat_map(webview_patterns));
+      }
+    }
+  }
+
+  /// Accounts the command scopes, each under the plugin of the first of the given commands referencing it.
+  pub fn add_command_scopes<'a>(
+    &mut self,
+    commands: impl IntoIterator<Item = (&'a CommandKey, &'a ResolvedCommand)>,
+    command_scope: &BTreeMap<ScopeKey, ResolvedScope>,
+  ) {
+    let mut plugins = BTreeMap::<ScopeKey, String>::new();
+    for (key, command) in commands {
+      let plugin = CommandId::parse(&key.name)
+        .map(|id| id.plugin().to_string())
+        .unwrap_or_default();
+      for scope in &command.scopes {
+        plugins.entry(scope.scope).or_insert_with(|| plugin.clone());
+      }
+    }
+    for (key, scope) in command_scope {
+      self
+        .command_scopes
+        .entry(plugins.get(key).cloned().unwrap_or_default())
+        .or_default()
+        .add(size_of::<ScopeKey>() + size_of::<ResolvedScope>() + scope.heap_size());
+    }
+  }
+
+  /// Accounts the encoded scope shards and the scopes decoded so far.
+  pub fn add_scope_shards(&mut self, shards: &ScopeShards) {
+    if !shards.is_empty() {
+      self.scope_shards.add(shards.heap_size());
+    }
+  }
+
+  /// Accounts the global scope entries and their window patterns.
+  pub fn add_global_scope(&mut self, global_scope: &BTreeMap<String, Vec<WindowGlobalScope>>) {
+    for (plugin, scopes) in global_scope {
+      let stats = self.global_scope.entry(plugin.clone()).or_default();
+      for scope in scopes {
+        stats.add(size_of::<WindowGlobalScope>() + scope.heap_size());
+        self.window_patterns.add_all(&scope.windows);
+        self
+          .window_patterns
+          .add_all(scope.window_ancestors.iter().map(|a| &a.pattern));
+        self
+          .window_patterns
+          .add_all(scope.webviews.iter().flat_map(webview_patterns));
+      }
+    }
+  }
+
+  /// Accounts the inheriting webviews and their window patterns.
+  pub fn add_inheriting_webviews(&mut self, webviews: &[WebviewPattern]) {
+    for webview in webviews {
+      self
+        .other
+        .add(size_of::<WebviewPattern>() + webview.heap_size());
+      self.window_patterns.add_all(webview_patterns(webview));
+    }
+  }
+
+  /// Accounts any other state derived from the ACL, skipping the values that own nothing on the heap.
+  pub fn add_other(&mut self, value: &impl HeapSize) {
+    let bytes = value.heap_size();
+    if bytes > 0 {
+      self.other.add(bytes);
+    }
+  }
+}
+
+fn webview_patterns(webview: &WebviewPattern) -> [&WindowPattern; 2] {
+  [&webview.window, &webview.webview]
+}
+
+impl Resolved {
+  /// Estimates the heap footprint of the resolved ACL.
+  ///
+  /// The manifests kept in debug builds for the error messages are not accounted.
+  pub fn memory_stats(&self) -> MemoryStats {
+    let mut stats = MemoryStats::default();
+    stats.add_commands(&self.allowed_commands, false);
+    stats.add_commands(&self.denied_commands, true);
+    stats.add_command_scopes(
+      self.allowed_commands.iter().chain(&self.denied_commands),
+      &self.command_scope,
+    );
+    stats.add_scope_shards(&self.command_scope_shards);
+    stats.add_global_scope(&self.global_scope);
+    stats.add_inheriting_webviews(&self.inheriting_webviews);
+    stats.add_other(&self.global_api);
+    stats.add_other(&self.command_aliases);
+    stats.add_other(&self.shares_scope_with);
+    stats
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::acl::{resolved::Resolved, ExecutionContext, Value};
+
+  #[test]
+  fn memory_stats() {
+    let resolved = Resolved::builder()
+      .allow_command(
+        "plugin:fs|read",
+        ExecutionContext::Local,
+        ["main", "doc-{int}"],
+      )
+      .unwrap()
+      .allow_command("plugin:fs|write", ExecutionContext::Local, ["main"])
+      .unwrap()
+      .deny_command("plugin:fs|remove", ExecutionContext::Local, ["*"])
+      .unwrap()
+      .build();
+
+    let stats = resolved.memory_stats();
+    assert_eq!(stats.allowed_commands.count, 2);
+    assert_eq!(stats.denied_commands.count, 1);
+    assert_eq!(stats.window_patterns.count, 4);
+    assert_eq!(stats.window_patterns.unique, 3);
+    assert_eq!(stats.window_patterns.templates, 1);
+    assert!(stats.window_patterns.compiled_bytes > stats.window_patterns.source_bytes);
+    assert!(stats.command_scopes.is_empty());
+    let empty = Resolved::default().memory_stats().total_bytes();
+    assert_eq!(empty, 0);
+
+    // larger scope values are accounted under their plugin
+    let scoped = |path: &str| {
+      Resolved::builder()
+        .allow_command_scoped(
+          "plugin:fs|read",
+          ExecutionContext::Local,
+          ["main"],
+          crate::acl::resolved::ResolvedScope {
+            allow: vec![Value::String(path.into())],
+            ..Default::default()
+          },
+        )
+        .unwrap()
+        .build()
+        .memory_stats()
+    };
+    let short = scoped("$HOME");
+    let long = scoped(&"$HOME/".repeat(100));
+    assert_eq!(short.command_scopes["fs"].count, 1);
+    assert!(long.command_scopes["fs"].bytes >= short.command_scopes["fs"].bytes + 500);
+    assert_eq!(
+      long.total_bytes() - short.total_bytes(),
+      long.command_scopes["fs"].bytes - short.command_scopes["fs"].bytes
+    );
+  }
+
+  #[test]
+  fn shared_window_patterns() {
+    let commands = 500;
+    let mut builder = Resolved::builder();
+    for i in 0..commands {
+      builder = builder
+        .allow_command(
+          format!("plugin:fs|command_{i}"),
+          ExecutionContext::Local,
+          ["*", "editor-{uuid}"],
+        )
+        .unwrap();
+    }
+    let resolved = builder.build();
+
+    let stats = resolved.memory_stats().window_patterns;
+    assert_eq!(stats.count, commands * 2);
+    assert_eq!(stats.unique, 2);
+    // each pattern after the first one of its kind is shared instead of copied
+    assert_eq!(
+      stats.shared_bytes,
+      (commands - 1) * stats.bytes(),
+      "{stats:?}"
+    );
+    assert!(stats.shared_bytes > 100 * stats.bytes());
+  }
+}
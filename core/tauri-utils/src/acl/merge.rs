@@ -0,0 +1,390 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Merging of resolved ACLs, e.g. the ACL of an app with the ACLs of extension packs resolved separately.
+
+use std::collections::BTreeMap;
+
+use super::{
+  resolved::{CommandKey, Resolved, ResolvedCommand, ResolvedScope, RoleGrant, ScopeKey},
+  Error,
+};
+
+impl Resolved {
+  /// Merges another resolved ACL into this one.
+  ///
+  /// - The allowed and denied commands are united. A command in both ACLs gets the windows, scopes,
+  ///   classes, validators and configurations of both, and is only granted through a `default`
+  ///   permission set if it is in both ACLs.
+  /// - The command scopes of `other` are moved to unused keys of this ACL,
+  ///   the [scope shards](Resolved#structfield.command_scope_shards) are decoded first.
+  /// - The global scopes are concatenated per plugin.
+  /// - The inheriting webviews, the global API functions, the command aliases and the shared scopes are united,
+  ///   and the remote lockdown applies if either ACL enables it.
+  ///
+  /// Fails with [`Error::MergeConflict`] when the ACLs map a global API function or a command alias
+  /// to different commands, since either mapping would silently change what the other ACL grants.
+  pub fn merge(mut self, mut other: Resolved) -> Result<Resolved, Error> {
+    for (plugin, functions) in &other.global_api {
+      for (function, command) in functions {
+        if let Some(existing) = self.global_api.get(plugin).and_then(|f| f.get(function)) {
+          if existing != command {
+            return Err(Error::MergeConflict {
+              command: format!("plugin:{plugin}|{command}"),
+              reason: format!(
+                "the global API function {plugin}.{function} invokes {existing} in the other ACL"
+              ),
+            });
+          }
+        }
+      }
+    }
+    for (plugin, aliases) in &other.command_aliases {
+      for (alias, canonical) in aliases {
+        if let Some(existing) = self.command_aliases.get(plugin).and_then(|a| a.get(alias)) {
+          if existing != canonical {
+            return Err(Error::MergeConflict {
+              command: format!("plugin:{plugin}|{alias}"),
+              reason: format!("the alias resolves to {canonical} and {existing}"),
+            });
+          }
+        }
+      }
+    }
+
+    decode_scope_shards(&mut self);
+    decode_scope_shards(&mut other);
+
+    let keys = move_scopes(&mut self, std::mem::take(&mut other.command_scope));
+    for (commands, other_commands) in [
+      (&mut self.allowed_commands, other.allowed_commands),
+      (&mut self.denied_commands, other.denied_commands),
+    ] {
+      for (key, mut command) in other_commands {
+        for window_scope in &mut command.scopes {
+          window_scope.scope = keys[&window_scope.scope];
+        }
+        merge_command(commands, key, command);
+      }
+    }
+
+    for (plugin, scopes) in other.global_scope {
+      self.global_scope.entry(plugin).or_default().extend(scopes);
+    }
+    for webview in other.inheriting_webviews {
+      if !self.inheriting_webviews.contains(&webview) {
+        self.inheriting_webviews.push(webview);
+      }
+    }
+    for (plugin, functions) in other.global_api {
+      self.global_api.entry(plugin).or_default().extend(functions);
+    }
+    for (plugin, aliases) in other.command_aliases {
+      self
+        .command_aliases
+        .entry(plugin)
+        .or_default()
+        .extend(aliases);
+    }
+    for (plugin, plugins) in other.shares_scope_with {
+      let shared = self.shares_scope_with.entry(plugin).or_default();
+      for plugin in plugins {
+        if !shared.contains(&plugin) {
+          shared.push(plugin);
+        }
+      }
+    }
+    self.lockdown_remote |= other.lockdown_remote;
+    // the role grants hold their own scopes, they are not re-keyed
+    self.role_grants.extend(other.role_grants);
+
+    #[cfg(debug_assertions)]
+    for (plugin, manifest) in other.acl {
+      self.acl.entry(plugin).or_insert(manifest);
+    }
+
+    Ok(self)
+  }
+}
+
+impl Resolved {
+  /// Moves the [role grants](Resolved#structfield.role_grants) out of the ACL for the runtime authority,
+  /// moving their command scopes to unused keys of the command scopes of the ACL.
+  ///
+  /// The commands of the returned grants reference the moved scopes, their own command scopes are left empty.
+  pub fn take_role_grants(&mut self) -> Vec<RoleGrant> {
+    let mut grants = std::mem::take(&mut self.role_grants);
+    for grant in &mut grants {
+      decode_scope_shards(&mut grant.acl);
+      let keys = move_scopes(self, std::mem::take(&mut grant.acl.command_scope));
+      for command in grant
+        .acl
+        .allowed_commands
+        .values_mut()
+        .chain(grant.acl.denied_commands.values_mut())
+      {
+        for window_scope in &mut command.scopes {
+          window_scope.scope = keys[&window_scope.scope];
+        }
+      }
+    }
+    grants
+  }
+}
+
+/// Moves the scopes to the lowest unused keys of the command scopes of the ACL, returning the new key of each scope.
+///
+/// The scope keys are hashes spread over the whole key range, so an offset added to them could overflow
+/// or collide with an existing key.
+fn move_scopes(
+  resolved: &mut Resolved,
+  scopes: BTreeMap<ScopeKey, ResolvedScope>,
+) -> BTreeMap<ScopeKey, ScopeKey> {
+  let mut free_keys = std::iter::successors(Some(0), |key: &ScopeKey| key.checked_add(1));
+  let mut keys = BTreeMap::new();
+  for (key, scope) in scopes {
+    let free_key = free_keys
+      .find(|key| {
+        !resolved.command_scope.contains_key(key)
+          && !resolved.command_scope_shards.keys().contains(key)
+      })
+      .expect("the scope keys are exhausted");
+    resolved.command_scope.insert(free_key, scope);
+    keys.insert(key, free_key);
+  }
+  keys
+}
+
+/// Moves the scopes embedded in shards to the command scopes, so they can be re-keyed.
+fn decode_scope_shards(resolved: &mut Resolved) {
+  let shards = std::mem::take(&mut resolved.command_scope_shards);
+  for &key in shards.keys() {
+    if let Some(scope) = shards.get(key) {
+      resolved.command_scope.insert(key, scope.clone());
+    }
+  }
+}
+
+fn merge_command(
+  commands: &mut BTreeMap<CommandKey, ResolvedCommand>,
+  key: CommandKey,
+  command: ResolvedCommand,
+) {
+  let Some(existing) = commands.get_mut(&key) else {
+    commands.insert(key, command);
+    return;
+  };
+
+  fn extend_unique<T: PartialEq>(values: &mut Vec<T>, other: Vec<T>) {
+    for value in other {
+      if !values.contains(&value) {
+        values.push(value);
+      }
+    }
+  }
+
+  #[cfg(debug_assertions)]
+  extend_unique(&mut existing.referenced_by, command.referenced_by);
+  extend_unique(&mut existing.windows, command.windows);
+  extend_unique(&mut existing.window_ancestors, command.window_ancestors);
+  extend_unique(&mut existing.webviews, command.webviews);
+  extend_unique(&mut existing.pages, command.pages);
+  existing.scopes.extend(command.scopes);
+  extend_unique(&mut existing.classes, command.classes);
+  extend_unique(&mut existing.validators, command.validators);
+  extend_unique(&mut existing.args, command.args);
+  existing.via_default &= command.via_default;
+  existing.configs.extend(command.configs);
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use crate::{
+    acl::{
+      capability::Capability,
+      plugin::{Manifest, PermissionFile},
+      resolved::{Resolved, ResolvedScope, ScopeKey},
+      Error, ExecutionContext, Value,
+    },
+    platform::Target,
+  };
+
+  fn acl() -> BTreeMap<String, Manifest> {
+    let permissions: PermissionFile = toml::from_str(
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+
+[[permission]]
+identifier = "allow-list"
+commands.allow = ["list"]
+
+[[permission]]
+identifier = "deny-write"
+commands.deny = ["write"]
+
+[[permission]]
+identifier = "scope-home"
+scope.allow = [{ path = "$HOME" }]
+"#,
+    )
+    .unwrap();
+    [("fs".to_string(), Manifest::new(vec![permissions], None))].into()
+  }
+
+  fn capability(identifier: &str, windows: &str, path: &str) -> Capability {
+    serde_json::from_value(serde_json::json!({
+      "identifier": identifier,
+      "windows": [windows],
+      "permissions": [
+        { "identifier": "fs:allow-read", "allow": [{ "path": path }] },
+        { "identifier": "fs:allow-list", "allow": [{ "path": format!("{path}/*") }] },
+        "fs:deny-write",
+        "fs:scope-home"
+      ]
+    }))
+    .unwrap()
+  }
+
+  /// The scope keys of the allowed commands, and the values of the scope each key references.
+  fn scope_keys(resolved: &Resolved) -> Vec<(ScopeKey, String)> {
+    resolved
+      .allowed_commands
+      .values()
+      .flat_map(|command| command.scopes.iter().map(|scope| scope.scope))
+      .map(|key| (key, format!("{:?}", resolved.command_scope[&key])))
+      .collect()
+  }
+
+  fn resolve(capabilities: Vec<Capability>) -> Resolved {
+    Resolved::resolve(
+      acl(),
+      capabilities
+        .into_iter()
+        .map(|capability| (capability.identifier.clone(), capability))
+        .collect(),
+      Target::current(),
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn merge_matches_resolving_together() {
+    let app = capability("app", "main", "$APPDATA");
+    let pack = capability("pack", "pack-*", "$DOCUMENT");
+
+    let app_acl = resolve(vec![app.clone()]);
+    let pack_acl = resolve(vec![pack.clone()]);
+    let mut expected = scope_keys(&app_acl)
+      .into_iter()
+      .chain(scope_keys(&pack_acl))
+      .map(|(_, scope)| scope)
+      .collect::<Vec<_>>();
+    assert_eq!(expected.len(), 4);
+
+    let merged = app_acl.merge(pack_acl).unwrap();
+    assert!(merged.diff(&resolve(vec![app, pack])).is_empty());
+
+    // every scope of the pack is moved to its own key, still referencing its own values
+    let keys = scope_keys(&merged);
+    let mut unique = keys.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), 4);
+    let mut scopes = keys.into_iter().map(|(_, scope)| scope).collect::<Vec<_>>();
+    expected.sort();
+    scopes.sort();
+    assert_eq!(scopes, expected);
+    assert_eq!(merged.global_scope["fs"].len(), 2);
+  }
+
+  #[test]
+  fn merge_builder_acls() {
+    let app = Resolved::builder()
+      .allow_command("plugin:fs|read", ExecutionContext::Local, ["main"])
+      .unwrap()
+      .build();
+    let pack = Resolved::builder()
+      .allow_command_scoped(
+        "plugin:fs|read",
+        ExecutionContext::Local,
+        ["pack"],
+        ResolvedScope {
+          allow: vec![Value::String("$DOCUMENT".into())],
+          ..Default::default()
+        },
+      )
+      .unwrap()
+      .lockdown_remote()
+      .build();
+
+    let merged = app.merge(pack).unwrap();
+    let read = merged.allowed_commands.values().next().unwrap();
+    assert!(read.matches_window("main", &[] as &[&str]));
+    assert_eq!(read.window_scope_keys("pack", &[] as &[&str]), [0]);
+    assert!(read.window_scope_keys("main", &[] as &[&str]).is_empty());
+    assert!(merged.lockdown_remote);
+  }
+
+  #[test]
+  fn role_grants_are_rekeyed() {
+    let app = capability("app", "main", "$APPDATA");
+    let mut admin = capability("admin", "main", "$DOCUMENT");
+    admin.roles = vec!["admin".into()];
+
+    let mut resolved = resolve(vec![app, admin]);
+    // the role capability is only resolved into its grant
+    let app_scopes = scope_keys(&resolved);
+    let grant_scopes = scope_keys(&resolved.role_grants[0].acl);
+    assert_eq!(app_scopes.len(), 2);
+    assert_eq!(grant_scopes.len(), 2);
+    assert_eq!(resolved.global_scope["fs"].len(), 1);
+    assert_eq!(resolved.role_grants.len(), 1);
+    assert!(resolved.role_grants[0].is_active(&["user", "admin"]));
+    assert!(!resolved.role_grants[0].is_active(&["user"]));
+
+    let grants = resolved.take_role_grants();
+    assert!(resolved.role_grants.is_empty());
+    assert_eq!(grants[0].capability, "admin");
+    assert!(grants[0].acl.command_scope.is_empty());
+    // the app scopes keep their keys, the grant scopes reference their own values under new keys
+    for (key, scope) in &app_scopes {
+      assert_eq!(&format!("{:?}", resolved.command_scope[key]), scope);
+    }
+    let grant_keys = grants[0]
+      .acl
+      .allowed_commands
+      .values()
+      .flat_map(|command| command.scopes.iter().map(|scope| scope.scope))
+      .collect::<Vec<_>>();
+    assert_eq!(grant_keys.len(), 2);
+    for (key, (_, scope)) in grant_keys.iter().zip(&grant_scopes) {
+      assert!(app_scopes.iter().all(|(app_key, _)| app_key != key));
+      assert_eq!(&format!("{:?}", resolved.command_scope[key]), scope);
+    }
+    assert_ne!(grant_keys[0], grant_keys[1]);
+  }
+
+  #[test]
+  fn merge_conflicting_aliases() {
+    let aliases = |canonical: &str| Resolved {
+      command_aliases: [(
+        "fs".to_string(),
+        [("readFile".to_string(), canonical.to_string())].into(),
+      )]
+      .into(),
+      ..Default::default()
+    };
+
+    assert!(aliases("read").merge(aliases("read")).is_ok());
+    let error = aliases("read").merge(aliases("read_file")).unwrap_err();
+    assert!(matches!(
+      &error,
+      Error::MergeConflict { command, .. } if command == "plugin:fs|readFile"
+    ));
+  }
+}
@@ -0,0 +1,605 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Conversion of the v1 `tauri > allowlist` configuration into capabilities.
+
+use std::{
+  collections::BTreeSet,
+  fs::{create_dir_all, write},
+  path::Path,
+};
+
+use serde_json::{Map, Value as JsonValue};
+
+use super::{
+  capability::{Capability, CapabilityContext, PermissionEntry},
+  Identifier, Scopes, Value,
+};
+
+/// Identifier of the capability created from the allowlist.
+pub const MIGRATED_CAPABILITY_IDENTIFIER: &str = "migrated";
+
+/// Default permissions of the core plugins, which did not have allowlist entries in v1.
+const CORE_PERMISSIONS: &[&str] = &[
+  "path:default",
+  "event:default",
+  "window:default",
+  "app:default",
+  "resources:default",
+  "menu:default",
+  "tray:default",
+];
+
+/// An allowlist object and the permissions enabled by each of its flags.
+struct AllowlistModule {
+  name: &'static str,
+  flags: &'static [(&'static str, &'static [&'static str])],
+  /// Keys that are migrated with custom logic.
+  special: &'static [&'static str],
+  /// Flags that have no equivalent, reported when enabled.
+  unsupported: &'static [&'static str],
+}
+
+const MODULES: &[AllowlistModule] = &[
+  AllowlistModule {
+    name: "fs",
+    flags: &[
+      ("readFile", &["fs:allow-read-file"]),
+      ("writeFile", &["fs:allow-write-file"]),
+      ("readDir", &["fs:allow-read-dir"]),
+      ("copyFile", &["fs:allow-copy-file"]),
+      ("createDir", &["fs:allow-mkdir"]),
+      ("removeDir", &["fs:allow-remove"]),
+      ("removeFile", &["fs:allow-remove"]),
+      ("renameFile", &["fs:allow-rename"]),
+      ("exists", &["fs:allow-exists"]),
+    ],
+    special: &["scope"],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "window",
+    flags: &[
+      ("create", &["window:allow-create"]),
+      ("center", &["window:allow-center"]),
+      (
+        "requestUserAttention",
+        &["window:allow-request-user-attention"],
+      ),
+      ("setResizable", &["window:allow-set-resizable"]),
+      ("setMaximizable", &["window:allow-set-maximizable"]),
+      ("setMinimizable", &["window:allow-set-minimizable"]),
+      ("setClosable", &["window:allow-set-closable"]),
+      ("setTitle", &["window:allow-set-title"]),
+      ("maximize", &["window:allow-maximize"]),
+      ("unmaximize", &["window:allow-unmaximize"]),
+      ("minimize", &["window:allow-minimize"]),
+      ("unminimize", &["window:allow-unminimize"]),
+      ("show", &["window:allow-show"]),
+      ("hide", &["window:allow-hide"]),
+      ("close", &["window:allow-close"]),
+      ("setDecorations", &["window:allow-set-decorations"]),
+      ("setAlwaysOnTop", &["window:allow-set-always-on-top"]),
+      (
+        "setContentProtected",
+        &["window:allow-set-content-protected"],
+      ),
+      ("setSize", &["window:allow-set-size"]),
+      ("setMinSize", &["window:allow-set-min-size"]),
+      ("setMaxSize", &["window:allow-set-max-size"]),
+      ("setPosition", &["window:allow-set-position"]),
+      ("setFullscreen", &["window:allow-set-fullscreen"]),
+      ("setFocus", &["window:allow-set-focus"]),
+      ("setIcon", &["window:allow-set-icon"]),
+      ("setSkipTaskbar", &["window:allow-set-skip-taskbar"]),
+      ("setCursorGrab", &["window:allow-set-cursor-grab"]),
+      ("setCursorVisible", &["window:allow-set-cursor-visible"]),
+      ("setCursorIcon", &["window:allow-set-cursor-icon"]),
+      ("setCursorPosition", &["window:allow-set-cursor-position"]),
+      (
+        "setIgnoreCursorEvents",
+        &["window:allow-set-ignore-cursor-events"],
+      ),
+      ("startDragging", &["window:allow-start-dragging"]),
+      ("print", &["webview:allow-print"]),
+    ],
+    special: &[],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "shell",
+    flags: &[],
+    special: &["execute", "sidecar", "open", "scope"],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "dialog",
+    flags: &[
+      ("open", &["dialog:allow-open"]),
+      ("save", &["dialog:allow-save"]),
+      ("message", &["dialog:allow-message"]),
+      ("ask", &["dialog:allow-ask"]),
+      ("confirm", &["dialog:allow-confirm"]),
+    ],
+    special: &[],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "http",
+    flags: &[],
+    special: &["request", "scope"],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "notification",
+    flags: &[("all", &["notification:default"])],
+    special: &[],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "globalShortcut",
+    flags: &[(
+      "all",
+      &[
+        "global-shortcut:allow-is-registered",
+        "global-shortcut:allow-register",
+        "global-shortcut:allow-register-all",
+        "global-shortcut:allow-unregister",
+        "global-shortcut:allow-unregister-all",
+      ],
+    )],
+    special: &[],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "os",
+    flags: &[(
+      "all",
+      &[
+        "os:allow-platform",
+        "os:allow-version",
+        "os:allow-os-type",
+        "os:allow-family",
+        "os:allow-arch",
+        "os:allow-exe-extension",
+        "os:allow-locale",
+        "os:allow-hostname",
+      ],
+    )],
+    special: &[],
+    unsupported: &[],
+  },
+  // the path APIs are granted by `path:default`
+  AllowlistModule {
+    name: "path",
+    flags: &[],
+    special: &[],
+    unsupported: &[],
+  },
+  // the asset protocol is configured in `app > security > assetProtocol` instead
+  AllowlistModule {
+    name: "protocol",
+    flags: &[],
+    special: &["asset", "assetScope"],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "process",
+    flags: &[
+      ("relaunch", &["process:allow-restart"]),
+      ("exit", &["process:allow-exit"]),
+    ],
+    special: &[],
+    unsupported: &["relaunchDangerousAllowSymlinkMacos"],
+  },
+  AllowlistModule {
+    name: "clipboard",
+    flags: &[
+      ("readText", &["clipboard-manager:allow-read"]),
+      ("writeText", &["clipboard-manager:allow-write"]),
+    ],
+    special: &[],
+    unsupported: &[],
+  },
+  AllowlistModule {
+    name: "app",
+    flags: &[
+      ("show", &["app:allow-app-show"]),
+      ("hide", &["app:allow-app-hide"]),
+    ],
+    special: &[],
+    unsupported: &[],
+  },
+];
+
+/// The result of migrating a v1 allowlist.
+#[derive(Debug, Clone)]
+pub struct MigratedAllowlist {
+  /// Capability granting the core plugins defaults and the permissions enabled in the allowlist.
+  pub capability: Capability,
+  /// Allowlist entries that have no equivalent permission,
+  /// such as `allowlist > process > relaunchDangerousAllowSymlinkMacos`.
+  pub unsupported: Vec<String>,
+}
+
+impl MigratedAllowlist {
+  /// Names of the plugins referenced by the migrated permissions.
+  pub fn plugins(&self) -> BTreeSet<&str> {
+    self
+      .capability
+      .permissions
+      .iter()
+      .filter_map(|p| p.identifier().get_prefix())
+      .collect()
+  }
+
+  /// Writes the capability to `{identifier}.json` in the given capabilities directory.
+  pub fn write(&self, capabilities_dir: &Path) -> std::io::Result<()> {
+    create_dir_all(capabilities_dir)?;
+    write(
+      capabilities_dir.join(format!("{}.json", self.capability.identifier)),
+      serde_json::to_string_pretty(&self.capability)?,
+    )
+  }
+}
+
+/// Converts the v1 `tauri > allowlist` configuration object into a capability for the `main` window.
+///
+/// Scopes of the `fs`, `shell` and `http` allowlists are converted to scoped permission entries.
+/// The `protocol` allowlist is not migrated since the asset protocol is configured in `app > security > assetProtocol`.
+pub fn migrate_allowlist(allowlist: &JsonValue) -> MigratedAllowlist {
+  let empty = Map::new();
+  let mut migration = Migration::default();
+
+  let allowlist = match allowlist {
+    JsonValue::Object(allowlist) => allowlist,
+    JsonValue::Null => &empty,
+    _ => {
+      migration.unsupported.push("allowlist".into());
+      &empty
+    }
+  };
+
+  let all = flag(allowlist, "all");
+  for (key, _) in allowlist {
+    if key != "all" && !MODULES.iter().any(|m| m.name == key) {
+      migration.unsupported.push(format!("allowlist > {key}"));
+    }
+  }
+
+  for module in MODULES {
+    let object = match allowlist.get(module.name) {
+      Some(JsonValue::Object(object)) => object,
+      Some(_) => {
+        migration
+          .unsupported
+          .push(format!("allowlist > {}", module.name));
+        &empty
+      }
+      None => &empty,
+    };
+    migration.module(module, object, all || flag(object, "all"));
+  }
+
+  let mut permissions: Vec<PermissionEntry> = CORE_PERMISSIONS
+    .iter()
+    .map(|p| PermissionEntry::PermissionRef(identifier(p)))
+    .collect();
+  permissions.extend(migration.permissions);
+
+  MigratedAllowlist {
+    capability: Capability {
+      identifier: MIGRATED_CAPABILITY_IDENTIFIER.into(),
+      description: "permissions that were migrated from v1".into(),
+      context: CapabilityContext::Local,
+      windows: vec!["main".into()],
+      permissions,
+      platforms: super::capability::default_platforms(),
+      group: None,
+      inherit_from_window: false,
+      roles: Vec::new(),
+      attenuate: None,
+      pages: Vec::new(),
+      plugin_version: Default::default(),
+      extensions: Default::default(),
+    },
+    unsupported: migration.unsupported,
+  }
+}
+
+#[derive(Default)]
+struct Migration {
+  permissions: Vec<PermissionEntry>,
+  unsupported: Vec<String>,
+}
+
+impl Migration {
+  fn module(&mut self, module: &AllowlistModule, object: &Map<String, JsonValue>, all: bool) {
+    for (flag_name, permissions) in module.flags {
+      if all || flag(object, flag_name) {
+        for permission in *permissions {
+          self.allow(permission);
+        }
+      }
+    }
+
+    for (key, value) in object {
+      if module.unsupported.contains(&key.as_str()) {
+        if value != &JsonValue::Bool(false) {
+          self
+            .unsupported
+            .push(format!("allowlist > {} > {key}", module.name));
+        }
+      } else if key != "all"
+        && !module.special.contains(&key.as_str())
+        && !module.flags.iter().any(|(f, _)| f == key)
+      {
+        self
+          .unsupported
+          .push(format!("allowlist > {} > {key}", module.name));
+      }
+    }
+
+    match module.name {
+      "fs" => self.fs_scope(object.get("scope")),
+      "shell" => {
+        if all || flag(object, "execute") || flag(object, "sidecar") {
+          let scope = scope_list(object.get("scope"))
+            .into_iter()
+            .map(Value::from)
+            .collect();
+          self.allow_scoped("shell:allow-execute", scope);
+        }
+        // `open` is either a flag or the regex validating the URLs, which is a shell plugin config
+        if all || !matches!(object.get("open"), None | Some(JsonValue::Bool(false))) {
+          self.allow("shell:allow-open");
+        }
+      }
+      "http" if all || flag(object, "request") => {
+        let scope = scope_list(object.get("scope"))
+          .into_iter()
+          .map(Value::from)
+          .collect();
+        self.allow_scoped("http:default", scope);
+      }
+      _ => {}
+    }
+  }
+
+  fn fs_scope(&mut self, scope: Option<&JsonValue>) {
+    let (allow, deny) = match scope {
+      Some(JsonValue::Object(scope)) => {
+        for key in scope.keys() {
+          if key != "allow" && key != "deny" {
+            self
+              .unsupported
+              .push(format!("allowlist > fs > scope > {key}"));
+          }
+        }
+        (
+          scope_list(scope.get("allow")),
+          scope_list(scope.get("deny")),
+        )
+      }
+      scope => (scope_list(scope), Vec::new()),
+    };
+
+    if !(allow.is_empty() && deny.is_empty()) {
+      self.permissions.push(PermissionEntry::ExtendedPermission {
+        identifier: identifier("fs:scope"),
+        scope: Scopes {
+          allow: non_empty(allow),
+          deny: non_empty(deny),
+        },
+        subtract: false,
+        include_deny: false,
+        config: None,
+        plugin_version: None,
+        extensions: Default::default(),
+      });
+    }
+  }
+
+  fn allow(&mut self, permission: &str) {
+    let identifier = identifier(permission);
+    if !self
+      .permissions
+      .iter()
+      .any(|p| p.identifier().get() == identifier.get())
+    {
+      self
+        .permissions
+        .push(PermissionEntry::PermissionRef(identifier));
+    }
+  }
+
+  fn allow_scoped(&mut self, permission: &str, scope: Vec<Value>) {
+    if scope.is_empty() {
+      self.allow(permission);
+    } else {
+      self.permissions.push(PermissionEntry::ExtendedPermission {
+        identifier: identifier(permission),
+        scope: Scopes {
+          allow: Some(scope),
+          deny: None,
+        },
+        subtract: false,
+        include_deny: false,
+        config: None,
+        plugin_version: None,
+        extensions: Default::default(),
+      });
+    }
+  }
+}
+
+fn flag(object: &Map<String, JsonValue>, key: &str) -> bool {
+  object
+    .get(key)
+    .and_then(JsonValue::as_bool)
+    .unwrap_or(false)
+}
+
+fn scope_list(scope: Option<&JsonValue>) -> Vec<JsonValue> {
+  match scope {
+    Some(JsonValue::Array(list)) => list.clone(),
+    _ => Vec::new(),
+  }
+}
+
+fn non_empty(list: Vec<JsonValue>) -> Option<Vec<Value>> {
+  if list.is_empty() {
+    None
+  } else {
+    Some(list.into_iter().map(Value::from).collect())
+  }
+}
+
+fn identifier(permission: &str) -> Identifier {
+  permission
+    .to_string()
+    .try_into()
+    .expect("invalid migrated permission identifier")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn identifiers(migrated: &MigratedAllowlist) -> Vec<&str> {
+    migrated
+      .capability
+      .permissions
+      .iter()
+      .map(|p| p.identifier().get())
+      .collect()
+  }
+
+  #[test]
+  fn migrate() {
+    let migrated = migrate_allowlist(&serde_json::json!({
+      "fs": {
+        "readFile": true,
+        "removeDir": true,
+        "removeFile": true,
+        "scope": {
+          "allow": ["$APPDATA/db/**", "$DOWNLOAD/**"],
+          "deny": ["$APPDATA/db/*.stronghold"],
+          "requireLiteralLeadingDot": true
+        }
+      },
+      "shell": {
+        "open": "^https://",
+        "execute": true,
+        "scope": [{ "name": "sh", "cmd": "sh", "args": ["-c", { "validator": "\\S+" }] }]
+      },
+      "http": {
+        "request": true,
+        "scope": ["http://localhost:3003/"]
+      },
+      "dialog": { "all": true },
+      "process": { "relaunch": true, "relaunchDangerousAllowSymlinkMacos": true },
+      "protocol": { "asset": true, "assetScope": ["$RESOURCE/**"] },
+      "window": { "center": true, "setTitlebarStyle": true },
+      "notifications": { "all": true }
+    }));
+
+    assert_eq!(
+      identifiers(&migrated)[CORE_PERMISSIONS.len()..],
+      [
+        "fs:allow-read-file",
+        "fs:allow-remove",
+        "fs:scope",
+        "window:allow-center",
+        "shell:allow-execute",
+        "shell:allow-open",
+        "dialog:allow-open",
+        "dialog:allow-save",
+        "dialog:allow-message",
+        "dialog:allow-ask",
+        "dialog:allow-confirm",
+        "http:default",
+        "process:allow-restart",
+      ]
+    );
+    assert_eq!(
+      migrated.unsupported,
+      [
+        "allowlist > notifications",
+        "allowlist > fs > scope > requireLiteralLeadingDot",
+        "allowlist > window > setTitlebarStyle",
+        "allowlist > process > relaunchDangerousAllowSymlinkMacos",
+      ]
+    );
+    assert_eq!(
+      migrated.plugins().into_iter().collect::<Vec<_>>(),
+      [
+        "app",
+        "dialog",
+        "event",
+        "fs",
+        "http",
+        "menu",
+        "path",
+        "process",
+        "resources",
+        "shell",
+        "tray",
+        "window"
+      ]
+    );
+
+    let capability = serde_json::to_value(&migrated.capability).unwrap();
+    let permissions = capability["permissions"].as_array().unwrap();
+    assert_eq!(
+      permissions[CORE_PERMISSIONS.len() + 2],
+      serde_json::json!({
+        "identifier": "fs:scope",
+        "allow": ["$APPDATA/db/**", "$DOWNLOAD/**"],
+        "deny": ["$APPDATA/db/*.stronghold"]
+      })
+    );
+    assert_eq!(
+      permissions[CORE_PERMISSIONS.len() + 4]["allow"][0]["args"][1],
+      serde_json::json!({ "validator": "\\S+" })
+    );
+    assert_eq!(
+      permissions[CORE_PERMISSIONS.len() + 11],
+      serde_json::json!({
+        "identifier": "http:default",
+        "allow": ["http://localhost:3003/"]
+      })
+    );
+  }
+
+  #[test]
+  fn migrate_all() {
+    let migrated = migrate_allowlist(&serde_json::json!({ "all": true }));
+    let identifiers = identifiers(&migrated);
+    assert!(migrated.unsupported.is_empty());
+    for permission in [
+      "fs:allow-write-file",
+      "window:allow-start-dragging",
+      "webview:allow-print",
+      "shell:allow-execute",
+      "shell:allow-open",
+      "http:default",
+      "notification:default",
+      "global-shortcut:allow-register",
+      "os:allow-hostname",
+      "process:allow-exit",
+      "clipboard-manager:allow-read",
+      "app:allow-app-hide",
+    ] {
+      assert!(identifiers.contains(&permission), "missing {permission}");
+    }
+    assert_eq!(
+      identifiers
+        .iter()
+        .filter(|p| **p == "shell:allow-execute")
+        .count(),
+      1
+    );
+  }
+}
@@ -6,21 +6,44 @@
 
 use glob::Pattern;
 use serde::{Deserialize, Serialize};
-use std::num::NonZeroU64;
+use std::{fmt, num::NonZeroU64, path::PathBuf};
 use thiserror::Error;
 
-pub use self::{identifier::*, value::*};
+use crate::platform::Target;
 
+pub use self::{command::*, identifier::*, value::*};
+
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod args;
 #[cfg(feature = "build")]
 pub mod build;
 pub mod capability;
+pub mod command;
+pub mod diagnostic;
+pub mod diff;
+#[cfg(feature = "test-fixtures")]
+pub mod fixtures;
+pub mod format;
 pub mod identifier;
+pub mod memory;
+pub mod merge;
+#[cfg(feature = "allowlist-migration")]
+pub mod migrate;
 pub mod plugin;
 pub mod resolved;
+#[cfg(feature = "build")]
+mod strict;
+pub mod tables;
+pub mod template;
 pub mod value;
+pub mod window;
 
 /// Possible errors while processing ACL files.
+///
+/// New variants can be added in minor releases, match on [`Error::code`] for a stable identification.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
   /// Could not find an environmental variable that is set inside of build scripts.
   ///
@@ -59,6 +82,10 @@ pub enum Error {
   #[error("failed to execute: {0}")]
   Metadata(#[from] ::cargo_metadata::Error),
 
+  /// Failed to compress or decompress the external scopes, see [`tables::ExternalScopes`].
+  #[error("failed to compress or decompress the external scopes: {0}")]
+  ExternalScopes(std::io::Error),
+
   /// Invalid glob
   #[error("failed to run glob: {0}")]
   Glob(#[from] glob::PatternError),
@@ -71,6 +98,27 @@ pub enum Error {
   #[error("failed to parse JSON: {0}")]
   Json(#[from] serde_json::Error),
 
+  /// Invalid YAML capability file encountered
+  #[cfg(feature = "capability-yaml")]
+  #[error("failed to parse YAML capability file {}: {error}", path.display())]
+  Yaml {
+    /// Path of the capability file.
+    path: PathBuf,
+    /// The YAML error, including its location in the file.
+    error: serde_yaml::Error,
+  },
+
+  /// Capability or permission file with fields Tauri does not know,
+  /// see [`UnknownFields::Deny`](build::UnknownFields::Deny).
+  #[cfg(feature = "build")]
+  #[error("{} has unknown fields: {}", path.display(), fields.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+  UnknownFields {
+    /// Path of the file.
+    path: PathBuf,
+    /// The unknown fields.
+    fields: Vec<build::UnknownField>,
+  },
+
   /// Invalid permissions file format
   #[error("unknown permission format {0}")]
   UnknownPermissionFormat(String),
@@ -79,6 +127,42 @@ pub enum Error {
   #[error("unknown capability format {0}")]
   UnknownCapabilityFormat(String),
 
+  /// Capability file declaring an unsupported format version or not following its version,
+  /// see [`format::CapabilityFormat`].
+  #[error("capability file format version {version}: {reason}")]
+  CapabilityFormat {
+    /// The declared version, `1` for an unversioned file.
+    version: String,
+    /// Why the file cannot be read.
+    reason: String,
+  },
+
+  /// Capability identifier defined more than once.
+  #[error(
+    "capability {identifier} is defined in both {} and {}",
+    first.display(),
+    second.display()
+  )]
+  DuplicateCapability {
+    /// Capability identifier.
+    identifier: String,
+    /// Path of the file that first defined the capability.
+    first: PathBuf,
+    /// Path of the file that defined the capability again.
+    second: PathBuf,
+  },
+
+  /// Capability group is not one of the configured groups.
+  #[error("capability {capability} has unknown group {group}, expected one of {available}")]
+  UnknownCapabilityGroup {
+    /// Capability identifier.
+    capability: String,
+    /// Group of the capability.
+    group: String,
+    /// The configured groups.
+    available: String,
+  },
+
   /// Permission referenced in set not found.
   #[error("permission {permission} not found from set {set}")]
   SetPermissionNotFound {
@@ -88,11 +172,64 @@ pub enum Error {
     set: String,
   },
 
+  /// Permission referenced by the `intersect` or `exclude` operator of a set not found.
+  #[error("permission {permission} referenced by the {operator} operator of set {set} not found")]
+  SetOperandNotFound {
+    /// Permission identifier.
+    permission: String,
+    /// The operator referencing the permission, `intersect` or `exclude`.
+    operator: String,
+    /// Set identifier.
+    set: String,
+  },
+
+  /// Permission set referencing itself, through its permissions or its operators.
+  #[error("permission set {plugin}:{set} references itself: {}", cycle.join(" -> "))]
+  PermissionSetCycle {
+    /// Plugin name.
+    plugin: String,
+    /// Set identifier.
+    set: String,
+    /// The identifiers of the sets forming the cycle, starting and ending with the set.
+    cycle: Vec<String>,
+  },
+
+  /// Permission set references a permission of a plugin that does not exist.
+  #[error("permission set {set} references permission {permission} of unknown plugin {plugin}")]
+  DelegatedPluginNotFound {
+    /// Set identifier.
+    set: String,
+    /// Referenced permission identifier.
+    permission: String,
+    /// Referenced plugin name.
+    plugin: String,
+  },
+
+  /// Permission set references a permission another plugin does not define.
+  #[error("permission {permission} referenced by set {set} not found")]
+  DelegatedPermissionNotFound {
+    /// Set identifier.
+    set: String,
+    /// Referenced permission identifier.
+    permission: String,
+  },
+
+  /// Permission set references a permission of another plugin that is not delegable.
+  #[error("permission set {set} references permission {permission} which is not delegable")]
+  PermissionNotDelegable {
+    /// Set identifier.
+    set: String,
+    /// Referenced permission identifier.
+    permission: String,
+  },
+
   /// Plugin has no default permission.
-  #[error("plugin {plugin} has no default permission")]
+  #[error("plugin {plugin} has no default permission, expected one of {available}")]
   MissingDefaultPermission {
     /// Plugin name.
     plugin: String,
+    /// Available permissions.
+    available: String,
   },
 
   /// Unknown plugin.
@@ -113,23 +250,478 @@ pub enum Error {
     /// Permission identifier.
     permission: String,
   },
+
+  /// A scope hook failed to process a resolved scope.
+  #[error("scope hook of plugin {plugin} failed: {error}")]
+  ScopeHook {
+    /// Plugin name.
+    plugin: String,
+    /// Command name, `None` for the plugin global scope.
+    command: Option<String>,
+    /// The hook error.
+    error: String,
+  },
+
+  /// A permission subtracting scope values also allows commands.
+  #[error("permission {permission} cannot subtract scope values because it allows commands")]
+  SubtractingPermissionAllowsCommands {
+    /// Permission identifier.
+    permission: String,
+  },
+
+  /// Invalid command name in a permission.
+  #[error("invalid command {command} in permission {permission}: {error}")]
+  InvalidCommand {
+    /// Permission identifier.
+    permission: String,
+    /// The command name.
+    command: String,
+    /// Why the command name is invalid.
+    error: ParseCommandIdError,
+  },
+
+  /// Invalid command glob pattern in a permission.
+  #[error("invalid command pattern {command} in permission {permission}: {error}")]
+  InvalidCommandPattern {
+    /// Permission identifier.
+    permission: String,
+    /// The command pattern.
+    command: String,
+    /// Why the pattern is invalid.
+    error: glob::PatternError,
+  },
+
+  /// Invalid argument rule in a permission, see [`Permission#structfield.args`].
+  #[error("invalid argument rule in permission {permission}: {reason}")]
+  InvalidArgumentRule {
+    /// Permission identifier.
+    permission: String,
+    /// Why the rule is invalid.
+    reason: String,
+  },
+
+  /// A command glob pattern in a permission does not match any command of the plugin.
+  #[error("command pattern {command} in permission {plugin}:{permission} does not match any command of the plugin")]
+  UnmatchedCommandPattern {
+    /// Plugin name.
+    plugin: String,
+    /// Permission identifier.
+    permission: String,
+    /// The command pattern.
+    command: String,
+  },
+
+  /// A command that requires a scope is allowed without one.
+  #[error("command {command} requires a scope but capability {capability} allows it without one, reference {permission} with a scope instead, e.g. {{ \"identifier\": \"{permission}\", \"allow\": [...] }}")]
+  MissingRequiredScope {
+    /// Command name.
+    command: String,
+    /// Identifier of the capability allowing the command.
+    capability: String,
+    /// Identifier of the permission allowing the command.
+    permission: String,
+  },
+
+  /// Every permission referenced by a capability is unavailable on the target platform.
+  #[error(
+    "capability {capability} only references permissions that are not available on {target}"
+  )]
+  PermissionsUnavailableOnTarget {
+    /// Capability identifier.
+    capability: String,
+    /// Target platform.
+    target: Target,
+  },
+
+  /// Invalid window pattern in a capability.
+  #[error(
+    "invalid window pattern {window:?} in capability {capability:?}{}: {error}",
+    .path.as_ref().map(|p| format!(" ({})", p.display())).unwrap_or_default()
+  )]
+  InvalidWindowPattern {
+    /// Capability identifier.
+    capability: String,
+    /// The window pattern.
+    window: String,
+    /// Path of the file defining the capability, if it was loaded from a file.
+    path: Option<PathBuf>,
+    /// The pattern error.
+    error: window::WindowPatternError,
+  },
+
+  /// Remote domain pattern that can never match a valid host.
+  #[error("invalid remote domain {domain:?} in capability {capability}: {reason}")]
+  InvalidRemoteDomain {
+    /// Capability identifier.
+    capability: String,
+    /// The domain pattern.
+    domain: String,
+    /// Why the domain pattern is invalid.
+    reason: String,
+  },
+
+  /// Remote attestation constraint that can never be satisfied.
+  #[error("invalid remote attestation in capability {capability}: {reason}")]
+  InvalidRemoteAttestation {
+    /// Capability identifier.
+    capability: String,
+    /// Why the constraint is invalid.
+    reason: String,
+  },
+
+  /// Capability inheriting the grants of the window of its webviews that cannot be resolved,
+  /// see [`Capability#structfield.inherit_from_window`](capability::Capability#structfield.inherit_from_window).
+  #[error("invalid window inheritance in capability {capability}: {reason}")]
+  InvalidInheritance {
+    /// Capability identifier.
+    capability: String,
+    /// Why the inheritance is invalid.
+    reason: String,
+  },
+
+  /// Invalid page selection of a capability,
+  /// see [`Capability#structfield.pages`](capability::Capability#structfield.pages).
+  #[error("invalid pages in capability {capability}: {reason}")]
+  InvalidCapabilityPages {
+    /// Capability identifier.
+    capability: String,
+    /// Why the pages are invalid.
+    reason: String,
+  },
+
+  /// Capability using a reserved identifier that cannot be resolved,
+  /// see [`LOCKDOWN_REMOTE_CAPABILITY`](capability::LOCKDOWN_REMOTE_CAPABILITY).
+  #[error("invalid reserved capability {capability}: {reason}")]
+  InvalidReservedCapability {
+    /// Capability identifier.
+    capability: String,
+    /// Why the capability is invalid.
+    reason: String,
+  },
+
+  /// Invalid window pattern of a command added with [`ResolvedBuilder`](resolved::ResolvedBuilder).
+  #[error("invalid window pattern {window:?} for command {command}: {error}")]
+  InvalidCommandWindowPattern {
+    /// Command name.
+    command: String,
+    /// The window pattern.
+    window: String,
+    /// The pattern error.
+    error: window::WindowPatternError,
+  },
+
+  /// A capability references the windows of a plugin that does not declare window label prefixes.
+  #[error("capability {capability} references the windows of plugin {plugin}, but {}", if *.plugin_exists { "it does not declare any window-prefixes" } else { "the plugin does not exist" })]
+  UnknownPluginWindows {
+    /// Capability identifier.
+    capability: String,
+    /// The referenced plugin name.
+    plugin: String,
+    /// Whether the plugin exists but does not declare window label prefixes.
+    plugin_exists: bool,
+  },
+
+  /// The installed version of a plugin does not match the requirement of a capability,
+  /// see [`Capability#structfield.plugin_version`](capability::Capability#structfield.plugin_version).
+  #[error("capability {capability} requires plugin {plugin} {requirement}, but version {version} is installed")]
+  PluginVersionMismatch {
+    /// Capability identifier.
+    capability: String,
+    /// Plugin name.
+    plugin: String,
+    /// The version requirement.
+    requirement: String,
+    /// The installed plugin version.
+    version: String,
+  },
+
+  /// Invalid plugin version requirement in a capability.
+  #[error("invalid version requirement {requirement:?} for plugin {plugin} in capability {capability}: {error}")]
+  InvalidPluginVersionRequirement {
+    /// Capability identifier.
+    capability: String,
+    /// Plugin name.
+    plugin: String,
+    /// The version requirement.
+    requirement: String,
+    /// The parsing error.
+    error: semver::Error,
+  },
+
+  /// Command aliases of a plugin forming a cycle,
+  /// see [`Manifest#structfield.aliases`](plugin::Manifest#structfield.aliases).
+  #[error("command aliases of plugin {plugin} form a cycle: {}", cycle.join(" -> "))]
+  CommandAliasCycle {
+    /// Plugin name.
+    plugin: String,
+    /// The aliases forming the cycle, starting and ending with the same alias.
+    cycle: Vec<String>,
+  },
+
+  /// Several permission sets of a plugin marked with the same tier,
+  /// see [`PermissionSet#structfield.tier`].
+  #[error("permission sets {} of plugin {plugin} are all marked with the {tier} tier", sets.join(", "))]
+  DuplicatePermissionTier {
+    /// Plugin name.
+    plugin: String,
+    /// The tier.
+    tier: PermissionTier,
+    /// Identifiers of the permission sets marked with the tier.
+    sets: Vec<String>,
+  },
+
+  /// Scope entry referencing a preset the plugin does not define,
+  /// see [`Manifest#structfield.presets`](plugin::Manifest#structfield.presets).
+  #[error("plugin {plugin} does not define the scope preset {preset}, available presets: {}", if available.is_empty() { "none".to_string() } else { available.join(", ") })]
+  UnknownScopePreset {
+    /// Plugin name.
+    plugin: String,
+    /// The referenced preset.
+    preset: String,
+    /// The presets of the plugin.
+    available: Vec<String>,
+  },
+
+  /// Scope presets of a plugin referencing each other in a cycle,
+  /// see [`Manifest#structfield.presets`](plugin::Manifest#structfield.presets).
+  #[error("scope presets of plugin {plugin} form a cycle: {}", cycle.join(" -> "))]
+  ScopePresetCycle {
+    /// Plugin name.
+    plugin: String,
+    /// The presets forming the cycle, starting and ending with the same preset.
+    cycle: Vec<String>,
+  },
+
+  /// Command alias with the name of a command of the plugin.
+  #[error("command alias {alias} of plugin {plugin} is also the name of a command")]
+  CommandAliasCollision {
+    /// Plugin name.
+    plugin: String,
+    /// The alias.
+    alias: String,
+  },
+
+  /// Resolved ACLs that cannot be merged safely, see [`Resolved::merge`](resolved::Resolved::merge).
+  #[error("cannot merge the resolved ACLs on command {command}: {reason}")]
+  MergeConflict {
+    /// Command name.
+    command: String,
+    /// Why the ACLs cannot be merged.
+    reason: String,
+  },
+
+  /// An error along with the capability or file being processed when it happened,
+  /// see [`Error::with_capability`] and [`Error::with_file`].
+  #[error("{context}: {error}")]
+  Context {
+    /// What was being processed.
+    context: ErrorContext,
+    /// The error.
+    #[source]
+    error: Box<Error>,
+  },
+}
+
+/// The origin of an [`Error`], see [`Error::Context`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorContext {
+  /// The identifier of the capability being resolved.
+  Capability(String),
+  /// The path of the capability or permission file being loaded.
+  File(PathBuf),
+}
+
+impl fmt::Display for ErrorContext {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Capability(identifier) => write!(f, "in capability {identifier}"),
+      Self::File(path) => write!(f, "in {}", path.display()),
+    }
+  }
+}
+
+impl Error {
+  /// Stable code of the error, e.g. `unknown-permission`, see [`diagnostic::Diagnostic`].
+  pub fn code(&self) -> &'static str {
+    match self {
+      Self::BuildVar(_) => "build-var",
+      Self::CrateName => "invalid-crate-name",
+      Self::LinksMissing => "links-missing",
+      Self::LinksName => "links-name-mismatch",
+      Self::ReadFile(_) => "read-file",
+      Self::WriteFile(_) => "write-file",
+      Self::CreateFile(_) => "create-file",
+      #[cfg(feature = "build")]
+      Self::Metadata(_) => "cargo-metadata",
+      Self::ExternalScopes(_) => "external-scopes",
+      Self::Glob(_) => "invalid-glob",
+      Self::Toml(_) => "invalid-toml",
+      Self::Json(_) => "invalid-json",
+      #[cfg(feature = "capability-yaml")]
+      Self::Yaml { .. } => "invalid-yaml",
+      #[cfg(feature = "build")]
+      Self::UnknownFields { .. } => "unknown-fields",
+      Self::UnknownPermissionFormat(_) => "unknown-permission-format",
+      Self::UnknownCapabilityFormat(_) => "unknown-capability-format",
+      Self::CapabilityFormat { .. } => "capability-format",
+      Self::DuplicateCapability { .. } => "duplicate-capability",
+      Self::UnknownCapabilityGroup { .. } => "unknown-capability-group",
+      Self::SetPermissionNotFound { .. } => "set-permission-not-found",
+      Self::SetOperandNotFound { .. } => "set-operand-not-found",
+      Self::PermissionSetCycle { .. } => "permission-set-cycle",
+      Self::DelegatedPluginNotFound { .. } => "delegated-plugin-not-found",
+      Self::DelegatedPermissionNotFound { .. } => "delegated-permission-not-found",
+      Self::PermissionNotDelegable { .. } => "permission-not-delegable",
+      Self::MissingDefaultPermission { .. } => "missing-default-permission",
+      Self::UnknownPlugin { .. } => "unknown-plugin",
+      Self::UnknownPermission { .. } => "unknown-permission",
+      Self::ScopeHook { .. } => "scope-hook",
+      Self::SubtractingPermissionAllowsCommands { .. } => "subtracting-permission-allows-commands",
+      Self::InvalidCommand { .. } => "invalid-command",
+      Self::InvalidCommandPattern { .. } => "invalid-command-pattern",
+      Self::InvalidArgumentRule { .. } => "invalid-argument-rule",
+      Self::UnmatchedCommandPattern { .. } => "unmatched-command-pattern",
+      Self::MissingRequiredScope { .. } => "missing-required-scope",
+      Self::PermissionsUnavailableOnTarget { .. } => "permissions-unavailable-on-target",
+      Self::InvalidWindowPattern { .. } => "invalid-window-pattern",
+      Self::InvalidRemoteDomain { .. } => "invalid-remote-domain",
+      Self::InvalidRemoteAttestation { .. } => "invalid-remote-attestation",
+      Self::InvalidInheritance { .. } => "invalid-inheritance",
+      Self::InvalidCapabilityPages { .. } => "invalid-capability-pages",
+      Self::InvalidReservedCapability { .. } => "invalid-reserved-capability",
+      Self::InvalidCommandWindowPattern { .. } => "invalid-command-window-pattern",
+      Self::UnknownPluginWindows { .. } => "unknown-plugin-windows",
+      Self::PluginVersionMismatch { .. } => "plugin-version-mismatch",
+      Self::InvalidPluginVersionRequirement { .. } => "invalid-plugin-version-requirement",
+      Self::CommandAliasCycle { .. } => "command-alias-cycle",
+      Self::CommandAliasCollision { .. } => "command-alias-collision",
+      Self::DuplicatePermissionTier { .. } => "duplicate-permission-tier",
+      Self::UnknownScopePreset { .. } => "unknown-scope-preset",
+      Self::ScopePresetCycle { .. } => "scope-preset-cycle",
+      Self::MergeConflict { .. } => "merge-conflict",
+      Self::Context { error, .. } => error.code(),
+    }
+  }
+
+  /// Attaches the identifier of the capability being resolved, unless the error already refers to it.
+  #[must_use]
+  pub fn with_capability(self, identifier: impl Into<String>) -> Self {
+    let identifier = identifier.into();
+    if self.capability() == Some(identifier.as_str()) {
+      self
+    } else {
+      Self::Context {
+        context: ErrorContext::Capability(identifier),
+        error: Box::new(self),
+      }
+    }
+  }
+
+  /// Attaches the path of the file being loaded, unless the error already refers to it.
+  #[must_use]
+  pub fn with_file(self, path: impl Into<PathBuf>) -> Self {
+    let path = path.into();
+    if self.path() == Some(path.as_path()) {
+      self
+    } else {
+      Self::Context {
+        context: ErrorContext::File(path),
+        error: Box::new(self),
+      }
+    }
+  }
+
+  /// The error without the context attached to it.
+  pub fn root(&self) -> &Self {
+    match self {
+      Self::Context { error, .. } => error.root(),
+      _ => self,
+    }
+  }
+
+  /// Identifier of the capability causing the error, if the error or its context carries it.
+  pub fn capability(&self) -> Option<&str> {
+    match self {
+      Self::DuplicateCapability { identifier, .. } => Some(identifier),
+      Self::UnknownCapabilityGroup { capability, .. }
+      | Self::MissingRequiredScope { capability, .. }
+      | Self::PermissionsUnavailableOnTarget { capability, .. }
+      | Self::InvalidWindowPattern { capability, .. }
+      | Self::InvalidRemoteDomain { capability, .. }
+      | Self::InvalidRemoteAttestation { capability, .. }
+      | Self::InvalidInheritance { capability, .. }
+      | Self::InvalidCapabilityPages { capability, .. }
+      | Self::InvalidReservedCapability { capability, .. }
+      | Self::UnknownPluginWindows { capability, .. }
+      | Self::PluginVersionMismatch { capability, .. }
+      | Self::InvalidPluginVersionRequirement { capability, .. } => Some(capability),
+      Self::Context {
+        context: ErrorContext::Capability(identifier),
+        ..
+      } => Some(identifier),
+      Self::Context { error, .. } => error.capability(),
+      _ => None,
+    }
+  }
+
+  /// Path of the file causing the error, if the error carries it.
+  pub fn path(&self) -> Option<&std::path::Path> {
+    match self {
+      #[cfg(feature = "capability-yaml")]
+      Self::Yaml { path, .. } => Some(path),
+      #[cfg(feature = "build")]
+      Self::UnknownFields { path, .. } => Some(path),
+      Self::DuplicateCapability { second, .. } => Some(second),
+      Self::InvalidWindowPattern { path, .. } => path.as_deref(),
+      Self::Context {
+        context: ErrorContext::File(path),
+        ..
+      } => Some(path),
+      Self::Context { error, .. } => error.path(),
+      _ => None,
+    }
+  }
 }
 
 /// Allowed and denied commands inside a permission.
 ///
 /// If two commands clash inside of `allow` and `deny`, it should be denied by default.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Commands {
   /// Allowed command.
+  ///
+  /// Can be a glob pattern matching commands of the plugin, e.g. `read_*`.
   #[serde(default)]
   pub allow: Vec<String>,
 
   /// Denied command, which takes priority.
+  ///
+  /// Can be a glob pattern matching commands of the plugin, e.g. `read_*`.
   #[serde(default)]
   pub deny: Vec<String>,
 }
 
+impl Commands {
+  /// Whether the given command name is allowed, matching the glob patterns.
+  pub fn allows(&self, command: &str) -> bool {
+    self.allow.iter().any(|allowed| {
+      allowed == command
+        || (is_command_pattern(allowed)
+          && Pattern::new(allowed).is_ok_and(|pattern| pattern.matches(command)))
+    })
+  }
+}
+
+/// Whether the command name in [`Commands`] is a glob pattern, e.g. `read_*`.
+///
+/// Patterns are expanded at resolution time against the commands of the plugin,
+/// see [`plugin::Manifest::commands`].
+pub fn is_command_pattern(command: &str) -> bool {
+  command.contains(['*', '?', '['])
+}
+
 /// A restriction of the command/endpoint functionality.
 ///
 /// It can be of any serde serializable type and is used for allowing or preventing certain actions inside a Tauri command.
@@ -139,19 +731,101 @@ pub struct Commands {
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Scopes {
   /// Data that defines what is allowed by the scope.
-  #[serde(skip_serializing_if = "Option::is_none")]
+  ///
+  /// A single scope entry object can be used instead of an array with one entry.
+  #[serde(
+    default,
+    deserialize_with = "deserialize_scope_entries",
+    skip_serializing_if = "Option::is_none"
+  )]
+  #[cfg_attr(feature = "schema", schemars(schema_with = "scope_entries_schema"))]
   pub allow: Option<Vec<Value>>,
   /// Data that defines what is denied by the scope.
-  #[serde(skip_serializing_if = "Option::is_none")]
+  ///
+  /// A single scope entry object can be used instead of an array with one entry.
+  #[serde(
+    default,
+    deserialize_with = "deserialize_scope_entries",
+    skip_serializing_if = "Option::is_none"
+  )]
+  #[cfg_attr(feature = "schema", schemars(schema_with = "scope_entries_schema"))]
   pub deny: Option<Vec<Value>>,
 }
 
+const SCOPE_ENTRIES_EXPECTED: &str = "a scope entry object or an array of scope entries";
+
+/// Deserializes the scope entries of [`Scopes`], normalizing a single entry object to a list.
+fn deserialize_scope_entries<'de, D>(deserializer: D) -> Result<Option<Vec<Value>>, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  use serde::de::{Error, Unexpected};
+
+  let unexpected = match Value::deserialize(deserializer)? {
+    Value::List(entries) => return Ok(Some(entries)),
+    entry @ Value::Map(_) => return Ok(Some(vec![entry])),
+    Value::Null => Unexpected::Unit,
+    Value::Bool(b) => Unexpected::Bool(b),
+    Value::Number(Number::Int(i)) => Unexpected::Signed(i),
+    Value::Number(Number::UInt(u)) => Unexpected::Unsigned(u),
+    Value::Number(Number::Float(f)) => Unexpected::Float(f),
+    Value::Bytes(bytes) => {
+      return Err(D::Error::invalid_type(
+        Unexpected::Bytes(&bytes),
+        &SCOPE_ENTRIES_EXPECTED,
+      ))
+    }
+    Value::String(s) => {
+      return Err(D::Error::invalid_type(
+        Unexpected::Str(&s),
+        &SCOPE_ENTRIES_EXPECTED,
+      ))
+    }
+  };
+  Err(D::Error::invalid_type(unexpected, &SCOPE_ENTRIES_EXPECTED))
+}
+
+#[cfg(feature = "schema")]
+fn scope_entries_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+  use schemars::schema::{InstanceType, Metadata, SchemaObject, SubschemaValidation};
+
+  let one_of = vec![
+    schemars::_private::apply_metadata(
+      SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        ..Default::default()
+      }
+      .into(),
+      Metadata {
+        description: Some("A single scope entry.".to_owned()),
+        ..Default::default()
+      },
+    ),
+    schemars::_private::apply_metadata(
+      gen.subschema_for::<Vec<Value>>(),
+      Metadata {
+        description: Some("A list of scope entries.".to_owned()),
+        ..Default::default()
+      },
+    ),
+  ];
+
+  SchemaObject {
+    subschemas: Some(Box::new(SubschemaValidation {
+      one_of: Some(one_of),
+      ..Default::default()
+    })),
+    ..Default::default()
+  }
+  .into()
+}
+
 /// Descriptions of explicit privileges of commands.
 ///
 /// It can enable commands to be accessible in the frontend of the application.
 ///
 /// If the scope is defined it can be used to fine grain control the access of individual or multiple commands.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Permission {
   /// The version of the permission.
@@ -170,10 +844,162 @@ pub struct Permission {
   /// Allowed or denied scoped when using this permission.
   #[serde(default)]
   pub scope: Scopes,
+
+  /// The side-effect class of the commands allowed by this permission,
+  /// e.g. `fs-read`, `fs-write`, `network` or `shell`.
+  ///
+  /// Used to ask for consent the first time a window uses a class of commands.
+  #[serde(default)]
+  pub class: Option<String>,
+
+  /// The identifier of the validator run on the IPC requests of the commands allowed by this permission,
+  /// e.g. `strip-pii`.
+  ///
+  /// Validators are registered with `tauri::Builder::isolation_validator`
+  /// and run after the ACL allows a request, before the command executes.
+  /// The app fails to start if a resolved command references a validator that is not registered.
+  #[serde(default)]
+  pub validator: Option<String>,
+
+  /// Constraints on the arguments of the commands allowed by this permission,
+  /// checked on each IPC request before the command executes, see [`args::ArgumentRule`].
+  ///
+  /// The rules of all the permissions allowing a command apply.
+  #[serde(default)]
+  pub args: Vec<args::ArgumentRule>,
+
+  /// Whether the commands allowed by this permission must have a scope.
+  ///
+  /// Use it for commands that are unrestricted when their scope is empty, e.g. a fetch command that accepts any URL.
+  /// Resolution fails when a capability allows such a command without a command or global scope.
+  #[serde(default)]
+  pub requires_scope: bool,
+
+  /// Target platforms this permission applies. By default all platforms applies.
+  ///
+  /// Capabilities skip the permission on other platforms.
+  #[serde(default)]
+  pub platforms: Option<Vec<Target>>,
+
+  /// The local resource identifiers returned by the commands allowed by this permission.
+  ///
+  /// Granting such a permission to a remote capability is reported by the resolution,
+  /// since leaking local paths or handles to remote origins aids exploitation.
+  #[serde(default)]
+  pub returns: Vec<LocalResource>,
+
+  /// Whether permission sets of other plugins can reference this permission, e.g. `fs:allow-read-file`.
+  ///
+  /// Lets plugins wrapping other plugins grant their commands with a single permission set,
+  /// only for the permissions the wrapped plugins explicitly delegate.
+  #[serde(default)]
+  pub delegable: bool,
+
+  /// Marks the permission as deprecated, see [`Deprecation`].
+  #[serde(default)]
+  pub deprecated: Option<Deprecation>,
+}
+
+/// A kind of local resource identifier returned by a command, see [`Permission#structfield.returns`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum LocalResource {
+  /// A filesystem path.
+  Path,
+  /// A handle to a resource owned by the application, e.g. a resource ID.
+  Handle,
+}
+
+impl fmt::Display for LocalResource {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Path => write!(f, "path"),
+      Self::Handle => write!(f, "handle"),
+    }
+  }
+}
+
+/// The tier of a permission set, marking the sets plugins recommend to scaffold capabilities from,
+/// see [`template::capability_template`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionTier {
+  /// The smallest set of permissions needed to use the plugin.
+  Minimal,
+  /// The permissions most apps using the plugin need.
+  Recommended,
+}
+
+impl fmt::Display for PermissionTier {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Minimal => write!(f, "minimal"),
+      Self::Recommended => write!(f, "recommended"),
+    }
+  }
+}
+
+/// Deprecation metadata of a permission or permission set.
+///
+/// Capabilities can still reference deprecated identifiers,
+/// but the resolution reports a warning pointing to the replacements.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Deprecation {
+  /// The plugin version that deprecated the identifier, e.g. `2.1.0`.
+  pub since: String,
+
+  /// Why the identifier is deprecated or how to migrate away from it.
+  #[serde(default)]
+  pub note: Option<String>,
+
+  /// The identifiers replacing the deprecated one.
+  ///
+  /// Identifiers without a plugin prefix refer to the plugin defining the deprecated identifier.
+  #[serde(default, rename = "replaced-by")]
+  pub replaced_by: Vec<String>,
+}
+
+impl Deprecation {
+  /// Describes the deprecation of an identifier of the given plugin, including its replacements.
+  pub fn message(&self, plugin: &str) -> String {
+    let mut message = format!("deprecated since {}", self.since);
+    if let Some(note) = &self.note {
+      message.push_str(": ");
+      message.push_str(note);
+    }
+    if !self.replaced_by.is_empty() {
+      let replacements = self
+        .replaced_by
+        .iter()
+        .map(|identifier| {
+          if identifier.contains(':') {
+            identifier.clone()
+          } else {
+            format!("{plugin}:{identifier}")
+          }
+        })
+        .collect::<Vec<_>>();
+      message.push_str(&format!(", use {} instead", replacements.join(", ")));
+    }
+    message
+  }
+}
+
+impl Permission {
+  /// Whether the permission applies to the given target platform.
+  pub fn is_available_on(&self, target: Target) -> bool {
+    self
+      .platforms
+      .as_ref()
+      .map_or(true, |platforms| platforms.contains(&target))
+  }
 }
 
 /// A set of direct permissions grouped together under a new name.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PermissionSet {
   /// A unique identifier for the permission.
@@ -183,7 +1009,35 @@ pub struct PermissionSet {
   pub description: String,
 
   /// All permissions this set contains.
+  ///
+  /// Permissions of other plugins are referenced by their fully qualified identifier, e.g. `fs:allow-read-file`,
+  /// and must be [delegable](Permission#structfield.delegable).
+  /// The `default` identifier references the default permission of the plugin.
   pub permissions: Vec<String>,
+
+  /// Restricts the set to the permissions also granted by each of these identifiers.
+  ///
+  /// Accepts the same identifiers as [`Self::permissions`], e.g. `safe-remote` can be the `default` permissions
+  /// intersected with a `remote-safe` set. It is applied before [`Self::exclude`].
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub intersect: Vec<String>,
+
+  /// Removes the permissions granted by these identifiers from the set.
+  ///
+  /// Accepts the same identifiers as [`Self::permissions`], e.g. `read-only` can be the `default` permissions
+  /// excluding `allow-write-file` and `allow-remove`.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub exclude: Vec<String>,
+
+  /// Marks the permission set as deprecated, see [`Deprecation`].
+  #[serde(default)]
+  pub deprecated: Option<Deprecation>,
+
+  /// Marks the permission set as the plugin recommendation for a tier, see [`PermissionTier`].
+  ///
+  /// A plugin can only mark one set per tier.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub tier: Option<PermissionTier>,
 }
 
 /// Execution context of an IPC call.
@@ -195,10 +1049,12 @@ pub enum ExecutionContext {
   Remote {
     /// The domain trying to access the IPC (glob pattern).
     domain: Pattern,
+    /// Constraints the webview navigation to the domain must have satisfied.
+    attestation: Option<capability::RemoteAttestation>,
   },
 }
 
-#[cfg(feature = "build")]
+#[cfg(feature = "tokens")]
 mod build_ {
   use std::convert::identity;
 
@@ -216,14 +1072,44 @@ mod build_ {
         Self::Local => {
           quote! { #prefix::Local }
         }
-        Self::Remote { domain } => {
+        Self::Remote {
+          domain,
+          attestation,
+        } => {
           let domain = domain.as_str();
-          quote! { #prefix::Remote { domain: #domain.parse().unwrap() } }
+          let attestation = opt_lit(attestation.as_ref());
+          quote! { #prefix::Remote { domain: #domain.parse().unwrap(), attestation: #attestation } }
         }
       });
     }
   }
 
+  impl ToTokens for capability::RemoteAttestation {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let required_header = opt_lit(self.required_header.as_ref());
+      let certificate_pin = opt_str_lit(self.certificate_pin.as_ref());
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::capability::RemoteAttestation,
+        required_header,
+        certificate_pin
+      )
+    }
+  }
+
+  impl ToTokens for capability::RequiredHeader {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let name = str_lit(&self.name);
+      let value = str_lit(&self.value);
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::capability::RequiredHeader,
+        name,
+        value
+      )
+    }
+  }
+
   impl ToTokens for Commands {
     fn to_tokens(&self, tokens: &mut TokenStream) {
       let allow = vec_lit(&self.allow, str_lit);
@@ -250,6 +1136,14 @@ mod build_ {
       let description = opt_str_lit(self.description.as_ref());
       let commands = &self.commands;
       let scope = &self.scope;
+      let class = opt_str_lit(self.class.as_ref());
+      let validator = opt_str_lit(self.validator.as_ref());
+      let args = vec_lit(&self.args, identity);
+      let requires_scope = self.requires_scope;
+      let platforms = opt_vec_lit(self.platforms.as_ref(), identity);
+      let returns = vec_lit(&self.returns, identity);
+      let delegable = self.delegable;
+      let deprecated = opt_lit(self.deprecated.as_ref());
       literal_struct!(
         tokens,
         ::tauri::utils::acl::Permission,
@@ -257,22 +1151,75 @@ mod build_ {
         identifier,
         description,
         commands,
-        scope
+        scope,
+        class,
+        validator,
+        args,
+        requires_scope,
+        platforms,
+        returns,
+        delegable,
+        deprecated
       )
     }
   }
 
+  impl ToTokens for PermissionTier {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let prefix = quote! { ::tauri::utils::acl::PermissionTier };
+
+      tokens.append_all(match self {
+        Self::Minimal => quote! { #prefix::Minimal },
+        Self::Recommended => quote! { #prefix::Recommended },
+      })
+    }
+  }
+
+  impl ToTokens for LocalResource {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let prefix = quote! { ::tauri::utils::acl::LocalResource };
+
+      tokens.append_all(match self {
+        Self::Path => quote! { #prefix::Path },
+        Self::Handle => quote! { #prefix::Handle },
+      })
+    }
+  }
+
   impl ToTokens for PermissionSet {
     fn to_tokens(&self, tokens: &mut TokenStream) {
       let identifier = str_lit(&self.identifier);
       let description = str_lit(&self.description);
       let permissions = vec_lit(&self.permissions, str_lit);
+      let intersect = vec_lit(&self.intersect, str_lit);
+      let exclude = vec_lit(&self.exclude, str_lit);
+      let deprecated = opt_lit(self.deprecated.as_ref());
+      let tier = opt_lit(self.tier.as_ref());
       literal_struct!(
         tokens,
         ::tauri::utils::acl::PermissionSet,
         identifier,
         description,
-        permissions
+        permissions,
+        intersect,
+        exclude,
+        deprecated,
+        tier
+      )
+    }
+  }
+
+  impl ToTokens for Deprecation {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let since = str_lit(&self.since);
+      let note = opt_str_lit(self.note.as_ref());
+      let replaced_by = vec_lit(&self.replaced_by, str_lit);
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::Deprecation,
+        since,
+        note,
+        replaced_by
       )
     }
   }
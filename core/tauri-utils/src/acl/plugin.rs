@@ -4,11 +4,18 @@
 
 //! Plugin ACL types.
 
-use std::{collections::BTreeMap, num::NonZeroU64};
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  num::NonZeroU64,
+};
 
-use super::{Permission, PermissionSet};
+use super::{is_command_pattern, Error, Permission, PermissionSet, PermissionTier, Value};
 use serde::{Deserialize, Serialize};
 
+/// The key of the scope entry referencing a [scope preset](Manifest#structfield.presets)
+/// in the scope of a capability, e.g. `{ "preset": "documents-read" }`.
+pub const SCOPE_PRESET_KEY: &str = "preset";
+
 /// The default permission set of the plugin.
 ///
 /// Works similarly to a permission with the "default" identifier.
@@ -22,6 +29,9 @@ pub struct DefaultPermission {
   pub description: Option<String>,
 
   /// All permissions this set contains.
+  ///
+  /// Can reference [delegable](super::Permission#structfield.delegable) permissions of other plugins,
+  /// see [`PermissionSet#structfield.permissions`].
   pub permissions: Vec<String>,
 }
 
@@ -39,12 +49,58 @@ pub struct PermissionFile {
   /// A list of inlined permissions
   #[serde(default)]
   pub permission: Vec<Permission>,
+
+  /// Label prefixes of the windows created by the plugin.
+  ///
+  /// Capabilities can select these windows with `{ "plugin-windows": "<plugin-name>" }`.
+  #[serde(default, rename = "window-prefixes")]
+  pub window_prefixes: Vec<String>,
+
+  /// The scope object field identifying a scope entry of the plugin.
+  ///
+  /// Scope entries with the same value on this field are deep-merged instead of duplicated,
+  /// see [`Manifest#structfield.merge_key`].
+  #[serde(default, rename = "merge-key")]
+  pub merge_key: Option<String>,
+
+  /// Scope entries denied on every command and global scope of the plugin,
+  /// see [`Manifest#structfield.required_deny`].
+  #[serde(default, rename = "required-deny")]
+  pub required_deny: Vec<Value>,
+
+  /// Whether the scope values of the plugin are stored out of the resolved ACL,
+  /// see [`Manifest#structfield.external_scope`].
+  #[serde(default, rename = "external-scope")]
+  pub external_scope: bool,
+
+  /// The functions of the plugin global API mapped to the commands they invoke,
+  /// see [`Manifest#structfield.global_api`].
+  #[serde(default, rename = "global-api")]
+  pub global_api: BTreeMap<String, String>,
+
+  /// The plugins whose global scopes the plugin reads,
+  /// see [`Manifest#structfield.shares_scope_with`].
+  #[serde(default, rename = "shares-scope-with")]
+  pub shares_scope_with: Vec<String>,
+
+  /// The former names of the plugin commands mapped to their current names,
+  /// see [`Manifest#structfield.aliases`].
+  #[serde(default)]
+  pub aliases: BTreeMap<String, String>,
+
+  /// Named lists of scope entries the capabilities can reference,
+  /// see [`Manifest#structfield.presets`].
+  #[serde(default)]
+  pub presets: BTreeMap<String, Vec<Value>>,
 }
 
 /// Plugin manifest.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Manifest {
   /// Default permission.
+  ///
+  /// When not set and the plugin defines exactly one permission,
+  /// that permission is used as the default, see [`Self::implicit_default_permission`].
   pub default_permission: Option<PermissionSet>,
   /// Plugin permissions.
   pub permissions: BTreeMap<String, Permission>,
@@ -52,6 +108,73 @@ pub struct Manifest {
   pub permission_sets: BTreeMap<String, PermissionSet>,
   /// The global scope schema.
   pub global_scope_schema: Option<serde_json::Value>,
+  /// Label prefixes of the windows created by the plugin, see [`PermissionFile#structfield.window_prefixes`].
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub window_prefixes: Vec<String>,
+  /// The scope object field identifying a scope entry of the plugin.
+  ///
+  /// When set, the resolved scope entries that are objects with the same value on this field
+  /// are deep-merged into the first of them, with later capabilities winning per field,
+  /// see [`Value::deep_merge`](super::Value::deep_merge).
+  /// Entries without the field are kept as is.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub merge_key: Option<String>,
+  /// Scope entries denied on every command and global scope of the plugin, e.g. the secrets of a filesystem plugin.
+  ///
+  /// This is a hardening controlled by the plugin author: the resolution adds these entries to the deny list
+  /// of the plugin scopes whatever the capabilities grant, so replacing the default permission,
+  /// subtracting scope values or rewriting the scopes with [`ScopeHooks`](super::resolved::ScopeHooks) cannot remove them.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub required_deny: Vec<Value>,
+  /// Whether the scope values of the plugin are stored out of the resolved ACL, e.g. for very large scopes.
+  ///
+  /// The code generation then only embeds a key and a content hash in each scope,
+  /// and the app loads the values with the `tauri::command::ScopeProvider` it registers,
+  /// see [`ExternalScopes`](super::tables::ExternalScopes).
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub external_scope: bool,
+  /// The functions of the plugin namespace in `window.__TAURI__` mapped to the commands they invoke,
+  /// without the `plugin:{name}|` prefix.
+  ///
+  /// The runtime leaves the functions out of the injected global API of the webviews not allowed to use their command.
+  /// Functions not listed here are always injected.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub global_api: BTreeMap<String, String>,
+  /// The plugins whose global scopes the plugin reads in addition to its own, e.g. `["fs"]`
+  /// for a persistence plugin honoring the filesystem scopes.
+  ///
+  /// The sharing is read-only: the runtime global scope accessor of the plugin also returns
+  /// the global scope entries the capabilities grant to these plugins on the window,
+  /// but the resolved ACL still stores them under their plugin only.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub shares_scope_with: Vec<String>,
+  /// The former names of the plugin commands mapped to their current names, e.g. `readTextFile -> read_text_file`.
+  ///
+  /// The resolution replaces the aliases in the permission command lists with their canonical command,
+  /// and the runtime invokes the canonical command when the frontend uses an alias,
+  /// so renaming a command does not break existing permissions and frontends.
+  /// An alias can point to another alias but cannot be the name of a command, see [`Self::canonical_command`].
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub aliases: BTreeMap<String, String>,
+  /// Named lists of scope entries, e.g. `documents-read -> [{ path = "$DOCUMENT/**" }]`.
+  ///
+  /// The allow and deny lists of a capability scope reference a preset of the plugin with
+  /// `{ "preset": "documents-read" }`, which the resolution replaces with the preset entries,
+  /// so the runtime only sees plain scope entries. A preset can reference other presets of the plugin,
+  /// see [`Self::expand_presets`].
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub presets: BTreeMap<String, Vec<Value>>,
+  /// The version of the plugin crate, checked against the version requirements of the capabilities,
+  /// see [`define_plugin_version`](super::build::define_plugin_version).
+  ///
+  /// Unknown for the plugins built with an older version of `tauri-plugin`, whose requirements are not checked.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub version: Option<String>,
+  /// Unknown fields of the manifest, e.g. vendor-specific `x-` fields.
+  ///
+  /// They are kept so tools can rewrite manifests without losing them, and ignored by the resolution.
+  #[serde(default, flatten, skip_serializing_if = "BTreeMap::is_empty")]
+  pub extensions: BTreeMap<String, serde_json::Value>,
 }
 
 impl Manifest {
@@ -65,9 +188,42 @@ impl Manifest {
       permissions: BTreeMap::new(),
       permission_sets: BTreeMap::new(),
       global_scope_schema,
+      window_prefixes: Vec::new(),
+      merge_key: None,
+      required_deny: Vec::new(),
+      external_scope: false,
+      global_api: BTreeMap::new(),
+      shares_scope_with: Vec::new(),
+      aliases: BTreeMap::new(),
+      presets: BTreeMap::new(),
+      version: None,
+      extensions: BTreeMap::new(),
     };
 
     for permission_file in permission_files {
+      for prefix in permission_file.window_prefixes {
+        if !manifest.window_prefixes.contains(&prefix) {
+          manifest.window_prefixes.push(prefix);
+        }
+      }
+      if permission_file.merge_key.is_some() {
+        manifest.merge_key = permission_file.merge_key;
+      }
+      manifest.external_scope |= permission_file.external_scope;
+      manifest.global_api.extend(permission_file.global_api);
+      for plugin in permission_file.shares_scope_with {
+        if !manifest.shares_scope_with.contains(&plugin) {
+          manifest.shares_scope_with.push(plugin);
+        }
+      }
+      manifest.aliases.extend(permission_file.aliases);
+      manifest.presets.extend(permission_file.presets);
+      for value in permission_file.required_deny {
+        if !manifest.required_deny.contains(&value) {
+          manifest.required_deny.push(value);
+        }
+      }
+
       if let Some(default) = permission_file.default {
         manifest.default_permission.replace(PermissionSet {
           identifier: "default".into(),
@@ -75,6 +231,10 @@ impl Manifest {
             .description
             .unwrap_or_else(|| "Default plugin permissions.".to_string()),
           permissions: default.permissions,
+          intersect: Vec::new(),
+          exclude: Vec::new(),
+          deprecated: None,
+          tier: None,
         });
       }
 
@@ -97,6 +257,10 @@ impl Manifest {
                 identifier: set.identifier,
                 description: set.description,
                 permissions: set.permissions,
+                intersect: set.intersect,
+                exclude: set.exclude,
+                deprecated: set.deprecated,
+                tier: set.tier,
               },
             )
           })
@@ -106,9 +270,218 @@ impl Manifest {
 
     manifest
   }
+
+  /// The permission resolved as `default` when the plugin does not define a default permission set.
+  ///
+  /// This is the only permission of the plugin if it defines exactly one.
+  pub fn implicit_default_permission(&self) -> Option<&Permission> {
+    if self.default_permission.is_none() && self.permissions.len() == 1 {
+      self.permissions.values().next()
+    } else {
+      None
+    }
+  }
+
+  /// The command names referenced by the plugin permissions, excluding glob patterns.
+  ///
+  /// Aliases are replaced with their canonical command.
+  /// Command patterns in the permissions are expanded against this list.
+  pub fn commands(&self) -> BTreeSet<&str> {
+    self
+      .permissions
+      .values()
+      .flat_map(|p| p.commands.allow.iter().chain(&p.commands.deny))
+      .map(String::as_str)
+      .filter(|command| !is_command_pattern(command))
+      .map(|command| self.canonical_command(command))
+      .collect()
+  }
+
+  /// The command invoked for the given command name, following the [aliases](Self#structfield.aliases).
+  ///
+  /// Returns the name itself when it is not an alias.
+  /// Aliases forming a cycle are rejected by [`Self::validate_aliases`], the lookup stops after visiting every alias.
+  pub fn canonical_command<'a>(&'a self, command: &'a str) -> &'a str {
+    let mut canonical = command;
+    for _ in 0..self.aliases.len() {
+      match self.aliases.get(canonical) {
+        Some(target) => canonical = target,
+        None => break,
+      }
+    }
+    canonical
+  }
+
+  /// Checks that the [aliases](Self#structfield.aliases) of the plugin do not form a cycle.
+  pub fn validate_aliases(&self, plugin: &str) -> Result<(), Error> {
+    validate_alias_cycles(plugin, &self.aliases)
+  }
+
+  /// The window label glob patterns matching the windows created by the plugin.
+  pub fn window_patterns(&self) -> impl Iterator<Item = String> + '_ {
+    self
+      .window_prefixes
+      .iter()
+      .map(|prefix| format!("{}*", glob::Pattern::escape(prefix)))
+  }
+
+  /// The permission sets marked with a tier, see [`PermissionSet#structfield.tier`].
+  ///
+  /// When several sets are marked with the same tier, which [`Self::validate_tiers`] rejects, the first one is used.
+  pub fn tiers(&self) -> BTreeMap<PermissionTier, &PermissionSet> {
+    let mut tiers = BTreeMap::new();
+    for set in self.permission_sets.values() {
+      if let Some(tier) = set.tier {
+        tiers.entry(tier).or_insert(set);
+      }
+    }
+    tiers
+  }
+
+  /// The permission set marked with the given tier.
+  pub fn tier_set(&self, tier: PermissionTier) -> Option<&PermissionSet> {
+    self
+      .permission_sets
+      .values()
+      .find(|set| set.tier == Some(tier))
+  }
+
+  /// Checks that the plugin marks at most one permission set per tier.
+  pub fn validate_tiers(&self, plugin: &str) -> Result<(), Error> {
+    for tier in self.tiers().into_keys() {
+      let sets = self
+        .permission_sets
+        .values()
+        .filter(|set| set.tier == Some(tier))
+        .map(|set| set.identifier.clone())
+        .collect::<Vec<_>>();
+      if sets.len() > 1 {
+        return Err(Error::DuplicatePermissionTier {
+          plugin: plugin.into(),
+          tier,
+          sets,
+        });
+      }
+    }
+    Ok(())
+  }
+
+  /// Replaces the scope entries referencing a [preset](Self#structfield.presets) with the preset entries,
+  /// recursively.
+  ///
+  /// Fails when a preset does not exist or references itself, directly or through other presets.
+  pub fn expand_presets(&self, plugin: &str, values: Vec<Value>) -> Result<Vec<Value>, Error> {
+    if !values.iter().any(|value| preset_reference(value).is_some()) {
+      return Ok(values);
+    }
+    let mut expanded = Vec::with_capacity(values.len());
+    expand_presets_into(
+      plugin,
+      &self.presets,
+      values,
+      &mut Vec::new(),
+      &mut expanded,
+    )?;
+    Ok(expanded)
+  }
+
+  /// Checks that the [presets](Self#structfield.presets) of the plugin only reference existing presets
+  /// and do not form a cycle.
+  pub fn validate_presets(&self, plugin: &str) -> Result<(), Error> {
+    for (name, values) in &self.presets {
+      expand_presets_into(
+        plugin,
+        &self.presets,
+        values.clone(),
+        &mut vec![name.clone()],
+        &mut Vec::new(),
+      )?;
+    }
+    Ok(())
+  }
+
+  /// Whether `default` can be resolved for this plugin.
+  pub fn has_default_permission(&self) -> bool {
+    self.default_permission.is_some() || self.implicit_default_permission().is_some()
+  }
 }
 
-#[cfg(feature = "build")]
+/// The name of the preset referenced by a scope entry, i.e. a map with the [`SCOPE_PRESET_KEY`] string field only.
+pub fn preset_reference(value: &Value) -> Option<&str> {
+  match value {
+    Value::Map(map) if map.len() == 1 => match map.get(SCOPE_PRESET_KEY) {
+      Some(Value::String(preset)) => Some(preset),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// Expands the preset references of the values into `expanded`,
+/// `visiting` holding the presets being expanded to detect the cycles.
+fn expand_presets_into(
+  plugin: &str,
+  presets: &BTreeMap<String, Vec<Value>>,
+  values: Vec<Value>,
+  visiting: &mut Vec<String>,
+  expanded: &mut Vec<Value>,
+) -> Result<(), Error> {
+  for value in values {
+    let Some(preset) = preset_reference(&value) else {
+      expanded.push(value);
+      continue;
+    };
+    if let Some(start) = visiting.iter().position(|visited| visited == preset) {
+      let mut cycle = visiting[start..].to_vec();
+      cycle.push(preset.to_string());
+      return Err(Error::ScopePresetCycle {
+        plugin: plugin.into(),
+        cycle,
+      });
+    }
+    let values = presets
+      .get(preset)
+      .ok_or_else(|| Error::UnknownScopePreset {
+        plugin: plugin.into(),
+        preset: preset.into(),
+        available: presets.keys().cloned().collect(),
+      })?
+      .clone();
+    visiting.push(preset.to_string());
+    expand_presets_into(plugin, presets, values, visiting, expanded)?;
+    visiting.pop();
+  }
+  Ok(())
+}
+
+/// Checks that the command aliases of a plugin do not form a cycle.
+pub(crate) fn validate_alias_cycles(
+  plugin: &str,
+  aliases: &BTreeMap<String, String>,
+) -> Result<(), Error> {
+  for alias in aliases.keys() {
+    let mut cycle = vec![alias.clone()];
+    let mut current = alias;
+    while let Some(target) = aliases.get(current) {
+      if let Some(start) = cycle.iter().position(|visited| visited == target) {
+        // only report the cycle once, from its smallest alias
+        if start == 0 {
+          cycle.push(target.clone());
+          return Err(Error::CommandAliasCycle {
+            plugin: plugin.into(),
+            cycle,
+          });
+        }
+        break;
+      }
+      cycle.push(target.clone());
+      current = target;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(feature = "tokens")]
 mod build {
   use proc_macro2::TokenStream;
   use quote::{quote, ToTokens, TokenStreamExt};
@@ -156,13 +529,51 @@ mod build {
       let global_scope_schema =
         opt_lit_owned(self.global_scope_schema.as_ref().map(json_value_lit));
 
+      let window_prefixes = vec_lit(&self.window_prefixes, str_lit);
+      let merge_key = opt_str_lit(self.merge_key.as_ref());
+      let required_deny = vec_lit(&self.required_deny, identity);
+      let external_scope = self.external_scope;
+      let global_api = map_lit(
+        quote! { ::std::collections::BTreeMap },
+        &self.global_api,
+        str_lit,
+        str_lit,
+      );
+      let shares_scope_with = vec_lit(&self.shares_scope_with, str_lit);
+      let aliases = map_lit(
+        quote! { ::std::collections::BTreeMap },
+        &self.aliases,
+        str_lit,
+        str_lit,
+      );
+      let presets = map_lit(
+        quote! { ::std::collections::BTreeMap },
+        &self.presets,
+        str_lit,
+        |values| vec_lit(values, identity),
+      );
+      let version = opt_str_lit(self.version.as_ref());
+
+      // the runtime does not read the extensions, no need to embed them
+      let extensions = quote! { ::std::collections::BTreeMap::new() };
+
       literal_struct!(
         tokens,
         ::tauri::utils::acl::plugin::Manifest,
         default_permission,
         permissions,
         permission_sets,
-        global_scope_schema
+        global_scope_schema,
+        window_prefixes,
+        merge_key,
+        required_deny,
+        external_scope,
+        global_api,
+        shares_scope_with,
+        aliases,
+        presets,
+        version,
+        extensions
       )
     }
   }
@@ -5,22 +5,40 @@
 //! Resolved ACL for runtime usage.
 
 use std::{
-  collections::{hash_map::DefaultHasher, BTreeMap, HashSet},
+  borrow::Cow,
+  collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap},
   fmt,
   hash::{Hash, Hasher},
+  ops::AddAssign,
+  path::PathBuf,
 };
 
 use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::platform::Target;
+use crate::{
+  config::{Csp, CspDirectiveSources, SecurityConfig},
+  normalize_label,
+  platform::Target,
+};
 
 use super::{
-  capability::{Capability, CapabilityContext, PermissionEntry},
+  args::ArgumentRule,
+  capability::{
+    Attenuation, Capability, CapabilityContext, CapabilityWindow, PermissionEntry, RemoteDomain,
+    LOCKDOWN_REMOTE_CAPABILITY,
+  },
+  diagnostic::Diagnostic,
+  is_command_pattern,
   plugin::Manifest,
-  Error, ExecutionContext, Permission, PermissionSet, Scopes, Value,
+  tables::{ExternalScopes, ScopeShards},
+  window::WindowPattern,
+  CommandId, Deprecation, Error, ExecutionContext, Permission, PermissionSet, Scopes, Value,
+  ALL_PERMISSIONS, PLUGIN_COMMAND_PREFIX, PLUGIN_COMMAND_SEPARATOR,
 };
 
-/// A key for a scope, used to link a [`ResolvedCommand#structfield.scope`] to the store [`Resolved#structfield.scopes`].
+/// A key for a scope, used to link a [`WindowScope#structfield.scope`] to the store [`Resolved#structfield.command_scope`].
 pub type ScopeKey = usize;
 
 /// Metadata for what referenced a [`ResolvedCommand`].
@@ -30,7 +48,12 @@ pub struct ResolvedCommandReference {
   /// Identifier of the capability.
   pub capability: String,
   /// Identifier of the permission.
+  ///
+  /// Prefixed with the plugin name when the permission is delegated, e.g. `fs:allow-read-file`.
   pub permission: String,
+  /// The fully qualified identifier of the permission set of another plugin delegating the permission,
+  /// see [`Permission#structfield.delegable`].
+  pub delegated_by: Option<String>,
 }
 
 /// A resolved command permission.
@@ -40,27 +63,493 @@ pub struct ResolvedCommand {
   #[cfg(debug_assertions)]
   pub referenced_by: Vec<ResolvedCommandReference>,
   /// The list of window label patterns that was resolved for this command.
-  pub windows: Vec<glob::Pattern>,
-  /// The reference of the scope that is associated with this command. See [`Resolved#structfield.scopes`].
-  pub scope: Option<ScopeKey>,
+  pub windows: Vec<WindowPattern>,
+  /// The windows selected by their parent or ancestors, see [`CapabilityWindow`].
+  pub window_ancestors: Vec<WindowAncestor>,
+  /// The webviews selected by their own label, see [`CapabilityWindow::Webview`].
+  pub webviews: Vec<WebviewPattern>,
+  /// The windows that are only granted this command on some local pages, see [`Capability#structfield.pages`].
+  ///
+  /// They are not matched by [`Self::matches_window`], see [`Self::on_page`].
+  pub pages: Vec<PageGrant>,
+  /// The scopes associated with this command, each applying to the windows of the capabilities that granted it.
+  pub scopes: Vec<WindowScope>,
+  /// The side-effect classes of the permissions that allow this command, see [`Permission#structfield.class`].
+  pub classes: Vec<String>,
+  /// The validators of the permissions that allow this command, see [`Permission#structfield.validator`].
+  pub validators: Vec<String>,
+  /// The argument rules of the permissions that allow this command, see [`Permission#structfield.args`].
+  pub args: Vec<ArgumentRule>,
+  /// Whether the command is only granted through the `default` permission sets of its plugin,
+  /// instead of permissions referenced by the capabilities.
+  pub via_default: bool,
+  /// The configuration payloads supplied by the capabilities granting this command,
+  /// see [`PermissionEntry::ExtendedPermission`].
+  pub configs: Vec<WindowConfig>,
 }
 
 impl fmt::Debug for ResolvedCommand {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     f.debug_struct("ResolvedCommand")
       .field("windows", &self.windows)
-      .field("scope", &self.scope)
+      .field("window_ancestors", &self.window_ancestors)
+      .field("webviews", &self.webviews)
+      .field("pages", &self.pages)
+      .field("scopes", &self.scopes)
+      .field("classes", &self.classes)
+      .field("validators", &self.validators)
+      .field("args", &self.args)
+      .field("via_default", &self.via_default)
+      .field("configs", &self.configs)
       .finish()
   }
 }
 
+impl ResolvedCommand {
+  /// Checks if this command is allowed on the given window.
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  /// The labels are normalized before matching, see [`crate::normalize_label`].
+  pub fn matches_window<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> bool {
+    matches_window(&self.windows, &self.window_ancestors, window, ancestors)
+  }
+
+  /// Checks if this command is allowed on the given webview by its own label, see [`CapabilityWindow::Webview`].
+  ///
+  /// The labels are normalized before matching, see [`crate::normalize_label`].
+  pub fn matches_webview(&self, window: &str, webview: &str) -> bool {
+    matches_webview(&self.webviews, window, webview)
+  }
+
+  /// The grants of this command on the local page the calling webview is on, `None` for a remote page.
+  ///
+  /// The windows of the [page grants](Self::pages) matching the page are granted the command like the other windows,
+  /// and the scopes and configurations supplied for other pages are removed.
+  /// The page is normalized with [`page_path`].
+  pub fn on_page(&self, page: Option<&str>) -> ResolvedCommand {
+    let mut command = self.clone();
+    for grant in std::mem::take(&mut command.pages) {
+      if !grant.matches_page(page) {
+        continue;
+      }
+      for window in grant.windows {
+        if !command.windows.contains(&window) {
+          command.windows.push(window);
+        }
+      }
+      for ancestor in grant.window_ancestors {
+        if !command.window_ancestors.contains(&ancestor) {
+          command.window_ancestors.push(ancestor);
+        }
+      }
+      for webview in grant.webviews {
+        if !command.webviews.contains(&webview) {
+          command.webviews.push(webview);
+        }
+      }
+    }
+    command.scopes.retain(|scope| scope.matches_page(page));
+    command.configs.retain(|config| config.matches_page(page));
+    command
+  }
+
+  /// The keys of the scopes that apply to the given window.
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  pub fn window_scope_keys<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> Vec<ScopeKey> {
+    self
+      .scopes
+      .iter()
+      .filter(|scope| scope.matches_window(window, ancestors))
+      .map(|scope| scope.scope)
+      .collect()
+  }
+
+  /// The keys of the scopes that apply to the given webview by its own label, see [`Self::matches_webview`].
+  pub fn webview_scope_keys(&self, window: &str, webview: &str) -> Vec<ScopeKey> {
+    self
+      .scopes
+      .iter()
+      .filter(|scope| scope.matches_webview(window, webview))
+      .map(|scope| scope.scope)
+      .collect()
+  }
+
+  /// The configuration payloads that apply to the given window.
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  pub fn window_configs<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> Vec<&WindowConfig> {
+    self
+      .configs
+      .iter()
+      .filter(|config| config.matches_window(window, ancestors))
+      .collect()
+  }
+
+  /// The configuration payloads that apply to the given webview by its own label, see [`Self::matches_webview`].
+  pub fn webview_configs(&self, window: &str, webview: &str) -> Vec<&WindowConfig> {
+    self
+      .configs
+      .iter()
+      .filter(|config| config.matches_webview(window, webview))
+      .collect()
+  }
+}
+
+/// The windows of a capability granting a command on some local pages only, see [`ResolvedCommand#structfield.pages`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageGrant {
+  /// The window label patterns of the capability.
+  pub windows: Vec<WindowPattern>,
+  /// The windows of the capability selected by their parent or ancestors.
+  pub window_ancestors: Vec<WindowAncestor>,
+  /// The webviews of the capability selected by their own label.
+  pub webviews: Vec<WebviewPattern>,
+  /// The page path patterns of the capability.
+  pub pages: Vec<Pattern>,
+}
+
+impl PageGrant {
+  /// Checks if the command is granted to the given window on the pages of this grant.
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  pub fn matches_window<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> bool {
+    matches_window(&self.windows, &self.window_ancestors, window, ancestors)
+  }
+
+  /// Checks if the command is granted to the given webview by its own label on the pages of this grant.
+  pub fn matches_webview(&self, window: &str, webview: &str) -> bool {
+    matches_webview(&self.webviews, window, webview)
+  }
+
+  /// Checks if the grant applies on the given local page, see [`page_path`].
+  pub fn matches_page(&self, page: Option<&str>) -> bool {
+    matches_page(&self.pages, page)
+  }
+}
+
+/// A command scope granted to the windows of the capabilities that reference it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowScope {
+  /// The window label patterns the scope applies to.
+  pub windows: Vec<WindowPattern>,
+  /// The windows selected by their parent or ancestors the scope applies to.
+  pub window_ancestors: Vec<WindowAncestor>,
+  /// The webviews selected by their own label the scope applies to.
+  pub webviews: Vec<WebviewPattern>,
+  /// The local pages the scope applies to, all pages when empty.
+  pub pages: Vec<Pattern>,
+  /// The reference of the scope. See [`Resolved#structfield.command_scope`].
+  pub scope: ScopeKey,
+}
+
+impl WindowScope {
+  /// Checks if this scope applies to the given window.
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  pub fn matches_window<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> bool {
+    matches_window(&self.windows, &self.window_ancestors, window, ancestors)
+  }
+
+  /// Checks if this scope applies to the given webview by its own label.
+  pub fn matches_webview(&self, window: &str, webview: &str) -> bool {
+    matches_webview(&self.webviews, window, webview)
+  }
+
+  /// Checks if this scope applies on the given local page, see [`page_path`].
+  pub fn matches_page(&self, page: Option<&str>) -> bool {
+    matches_page(&self.pages, page)
+  }
+}
+
+/// A configuration payload a capability supplies to a command on its windows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowConfig {
+  /// The identifier of the capability supplying the configuration.
+  pub capability: String,
+  /// The window label patterns of the capability.
+  pub windows: Vec<WindowPattern>,
+  /// The windows of the capability selected by their parent or ancestors.
+  pub window_ancestors: Vec<WindowAncestor>,
+  /// The webviews of the capability selected by their own label.
+  pub webviews: Vec<WebviewPattern>,
+  /// The local pages of the capability, all pages when empty.
+  pub pages: Vec<Pattern>,
+  /// The configuration payload.
+  pub config: Value,
+}
+
+// capability files cannot hold NaN numbers, so configurations are always equal to themselves
+impl Eq for WindowConfig {}
+
+impl WindowConfig {
+  /// Checks if this configuration applies to the given window.
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  pub fn matches_window<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> bool {
+    matches_window(&self.windows, &self.window_ancestors, window, ancestors)
+  }
+
+  /// Checks if this configuration applies to the given webview by its own label.
+  pub fn matches_webview(&self, window: &str, webview: &str) -> bool {
+    matches_webview(&self.webviews, window, webview)
+  }
+
+  /// Checks if this configuration applies on the given local page, see [`page_path`].
+  pub fn matches_page(&self, page: Option<&str>) -> bool {
+    matches_page(&self.pages, page)
+  }
+}
+
+/// A part of the global scope of a plugin, granted to the windows of the capabilities that reference it.
+#[derive(Debug, Clone, Default)]
+pub struct WindowGlobalScope {
+  /// The window label patterns the scope applies to.
+  pub windows: Vec<WindowPattern>,
+  /// The windows selected by their parent or ancestors the scope applies to.
+  pub window_ancestors: Vec<WindowAncestor>,
+  /// The webviews selected by their own label the scope applies to.
+  pub webviews: Vec<WebviewPattern>,
+  /// The local pages the scope applies to, all pages when empty.
+  pub pages: Vec<Pattern>,
+  /// The scope values.
+  pub scope: ResolvedScope,
+}
+
+impl WindowGlobalScope {
+  /// Checks if this scope applies to the given window.
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  pub fn matches_window<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> bool {
+    matches_window(&self.windows, &self.window_ancestors, window, ancestors)
+  }
+
+  /// Checks if this scope applies to the given webview by its own label.
+  pub fn matches_webview(&self, window: &str, webview: &str) -> bool {
+    matches_webview(&self.webviews, window, webview)
+  }
+
+  /// Checks if this scope applies on the given local page, see [`page_path`].
+  pub fn matches_page(&self, page: Option<&str>) -> bool {
+    matches_page(&self.pages, page)
+  }
+}
+
+/// Merges the global scopes granted to every window, in order.
+///
+/// This is the global scope of the plugin before it was narrowed to the windows of the capabilities,
+/// see [`Resolved#structfield.global_scope`].
+pub fn flatten_global_scope(scopes: &[WindowGlobalScope]) -> ResolvedScope {
+  let mut flattened = ResolvedScope::default();
+  for scope in scopes {
+    flattened += &scope.scope;
+  }
+  flattened
+}
+
+/// A remote domain the capabilities allow to use the IPC on a set of windows, see [`Resolved::remote_origins`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteOrigin {
+  /// The remote domain glob pattern.
+  pub domain: glob::Pattern,
+  /// The window label patterns the domain is granted on.
+  pub windows: Vec<WindowPattern>,
+  /// The windows selected by their parent or ancestors the domain is granted on.
+  pub window_ancestors: Vec<WindowAncestor>,
+  /// The webviews selected by their own label the domain is granted on.
+  ///
+  /// They are not matched by [`Self::matches_window`], since the domain is loaded by the webview itself
+  /// instead of being fetched by the local pages of the window.
+  pub webviews: Vec<WebviewPattern>,
+}
+
+impl RemoteOrigin {
+  /// Checks if the domain is granted on the given window.
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  pub fn matches_window<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> bool {
+    matches_window(&self.windows, &self.window_ancestors, window, ancestors)
+  }
+
+  /// The CSP source allowing the domain over HTTPS, e.g. `https://*.tauri.app`.
+  ///
+  /// Returns `None` when the domain pattern cannot be expressed as a CSP host source,
+  /// which only supports a wildcard for the leftmost subdomains.
+  pub fn csp_source(&self) -> Option<String> {
+    let domain = self.domain.as_str();
+    let host = domain.strip_prefix("*.").unwrap_or(domain);
+    if host.is_empty() || host.contains(['*', '?', '[', ']']) {
+      None
+    } else {
+      Some(format!("https://{domain}"))
+    }
+  }
+
+  /// Whether the `connect-src` directive of the CSP, or `default-src` when it is not set, allows this origin.
+  ///
+  /// A CSP without either directive allows every origin,
+  /// while an origin that cannot be expressed as a CSP source is only allowed by wildcard sources.
+  pub fn is_allowed_by(&self, csp: &Csp) -> bool {
+    let directives = HashMap::<String, CspDirectiveSources>::from(csp.clone());
+    let Some(sources) = directives
+      .get("connect-src")
+      .or_else(|| directives.get("default-src"))
+    else {
+      return true;
+    };
+    let sources = Vec::<String>::from(sources.clone());
+    let source = self.csp_source();
+    sources.iter().any(|allowed| match allowed.as_str() {
+      "*" | "https:" => true,
+      allowed => source.as_deref().is_some_and(|source| {
+        source == allowed
+          || allowed.strip_prefix("https://*.").is_some_and(|suffix| {
+            source
+              .strip_prefix("https://")
+              .and_then(|host| host.strip_suffix(suffix))
+              .is_some_and(|subdomain| subdomain.ends_with('.') && subdomain.len() > 1)
+          })
+      }),
+    })
+  }
+}
+
+/// Matches the windows that have a parent or ancestor window matching a label pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowAncestor {
+  /// The ancestor window label pattern.
+  pub pattern: WindowPattern,
+  /// Only match the windows whose parent matches the pattern.
+  pub parent_only: bool,
+}
+
+impl WindowAncestor {
+  /// Checks if a window with the given ancestors, ordered from its parent to the root window, is matched.
+  ///
+  /// The labels are normalized before matching, see [`crate::normalize_label`].
+  pub fn matches<S: AsRef<str>>(&self, ancestors: &[S]) -> bool {
+    let ancestors = if self.parent_only {
+      &ancestors[..ancestors.len().min(1)]
+    } else {
+      ancestors
+    };
+    ancestors
+      .iter()
+      .any(|a| self.pattern.matches(&normalize_label(a.as_ref())))
+  }
+}
+
+/// Matches the webviews whose own label matches a pattern, in the windows whose label matches another pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebviewPattern {
+  /// The label pattern of the window hosting the webview.
+  pub window: WindowPattern,
+  /// The webview label pattern.
+  pub webview: WindowPattern,
+}
+
+impl WebviewPattern {
+  /// Checks if the webview hosted by the window is matched.
+  ///
+  /// The labels are normalized before matching, see [`crate::normalize_label`].
+  pub fn matches(&self, window: &str, webview: &str) -> bool {
+    self.window.matches(&normalize_label(window)) && self.webview.matches(&normalize_label(webview))
+  }
+}
+
+fn matches_webview(webviews: &[WebviewPattern], window: &str, webview: &str) -> bool {
+  webviews.iter().any(|w| w.matches(window, webview))
+}
+
+/// Checks if the page matches one of the page patterns, every page matching when there is none.
+fn matches_page(pages: &[Pattern], page: Option<&str>) -> bool {
+  pages.is_empty()
+    || page.is_some_and(|page| {
+      let page = page_path(page);
+      pages
+        .iter()
+        .any(|pattern| pattern.matches_with(page, PAGE_MATCH_OPTIONS))
+    })
+}
+
+/// `*` only matches a path segment, so `admin/*` does not select `admin/users/edit.html`.
+const PAGE_MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+  case_sensitive: true,
+  require_literal_separator: true,
+  require_literal_leading_dot: false,
+};
+
+/// The page path of a local URL path, matched by the [page patterns](Capability#structfield.pages) of the capabilities.
+///
+/// The leading `/` is removed and the root path is `index.html`, e.g. `/admin/users.html` is `admin/users.html`.
+pub fn page_path(path: &str) -> &str {
+  match path.trim_start_matches('/') {
+    "" => "index.html",
+    page => page,
+  }
+}
+
+/// Checks if the window or one of its ancestors matches the selectors, normalizing the window label first.
+fn matches_window<S: AsRef<str>>(
+  windows: &[WindowPattern],
+  window_ancestors: &[WindowAncestor],
+  window: &str,
+  ancestors: &[S],
+) -> bool {
+  let window = normalize_label(window);
+  windows.iter().any(|w| w.matches(&window))
+    || window_ancestors.iter().any(|a| a.matches(ancestors))
+}
+
 /// A resolved scope. Merges all scopes defined for a single command.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ResolvedScope {
   /// Allows something on the command.
   pub allow: Vec<Value>,
   /// Denies something on the command.
   pub deny: Vec<Value>,
+  /// The reference to the values of the scope when they are stored out of the resolved ACL,
+  /// see [`Manifest#structfield.external_scope`].
+  ///
+  /// The allowed and denied values are empty in that case.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub external: Option<ExternalScope>,
+}
+
+impl ResolvedScope {
+  /// The hex encoded SHA-256 hash of the allowed and denied values.
+  pub fn content_hash(&self) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&(&self.allow, &self.deny)).unwrap_or_default());
+    format!("{:x}", hasher.finalize())
+  }
+}
+
+/// A reference to the values of a scope stored out of the resolved ACL,
+/// see [`Manifest#structfield.external_scope`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ExternalScope {
+  /// The key the values are stored with, see [`ExternalScopes`].
+  pub key: String,
+  /// The [content hash](ResolvedScope::content_hash) of the values, verified when they are loaded.
+  pub hash: String,
+}
+
+impl AddAssign<&ResolvedScope> for ResolvedScope {
+  /// Merges the allowed and denied values of another scope into this one.
+  fn add_assign(&mut self, other: &ResolvedScope) {
+    self.allow.extend(other.allow.iter().cloned());
+    self.deny.extend(other.deny.iter().cloned());
+  }
+}
+
+impl<'a> std::iter::Sum<&'a ResolvedScope> for ResolvedScope {
+  fn sum<I: Iterator<Item = &'a ResolvedScope>>(iter: I) -> Self {
+    iter.fold(Self::default(), |mut merged, scope| {
+      merged += scope;
+      merged
+    })
+  }
 }
 
 /// A command key for the map of allowed and denied commands.
@@ -73,6 +562,420 @@ pub struct CommandKey {
   pub context: ExecutionContext,
 }
 
+/// A non-fatal finding produced while resolving the ACL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolutionWarning {
+  /// Stable code of the warning.
+  pub code: &'static str,
+  /// How likely the finding is to be exploitable.
+  pub severity: WarningSeverity,
+  /// Human-readable description of the warning.
+  pub message: String,
+}
+
+/// Severity of a [`ResolutionWarning`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarningSeverity {
+  /// A likely mistake in the capabilities or plugin manifests.
+  #[default]
+  Normal,
+  /// A grant that exposes the application to remote origins in a way that aids exploitation.
+  High,
+}
+
+/// Size of a [`ResolvedScope`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ScopeSize {
+  /// Number of allow and deny entries.
+  pub entries: usize,
+  /// Estimated size of the entries serialized as JSON, in bytes.
+  pub serialized_size: usize,
+}
+
+impl ScopeSize {
+  fn of(scope: &ResolvedScope) -> Self {
+    let values = scope.allow.iter().chain(&scope.deny);
+    Self {
+      entries: values.clone().count(),
+      serialized_size: values
+        .map(|v| serde_json::to_vec(v).map(|v| v.len()).unwrap_or_default())
+        .sum(),
+    }
+  }
+
+  fn add(&mut self, other: Self) {
+    self.entries += other.entries;
+    self.serialized_size += other.serialized_size;
+  }
+}
+
+/// Sizes of the resolved scopes of a plugin.
+#[derive(Debug, Default, Serialize)]
+pub struct PluginScopeSizes {
+  /// Size of the plugin global scope.
+  pub global_scope: ScopeSize,
+  /// Size of the scope of each command, summed across all execution contexts.
+  pub commands: BTreeMap<String, ScopeSize>,
+}
+
+/// Resolved access of a plugin command, see [`PluginCommandStatus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CommandStatus {
+  /// The command is allowed on some windows.
+  Granted,
+  /// The command is denied, which takes priority over the permissions allowing it.
+  Denied,
+  /// The command is neither allowed nor denied.
+  NotGranted,
+}
+
+/// A plugin command with its resolved access, see [`Resolved::plugin_commands`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginCommandStatus {
+  /// The command name, without the plugin prefix.
+  pub command: String,
+  /// Whether the command is allowed.
+  pub status: CommandStatus,
+  /// The window label patterns the command is allowed on, across all execution contexts.
+  pub windows: Vec<String>,
+  /// Whether a command scope is attached to the allowed command.
+  pub scoped: bool,
+}
+
+/// Number of allowed commands of a plugin, summed across all execution contexts,
+/// by whether they are granted through the `default` permission set, see [`ResolvedCommand#structfield.via_default`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GrantCounts {
+  /// Commands only granted through the `default` permission set.
+  pub via_default: usize,
+  /// Commands granted by permissions or sets referenced by the capabilities.
+  pub explicit: usize,
+}
+
+/// Report of an ACL resolution, see [`Resolved::resolve_with_report`].
+#[derive(Debug, Default, Serialize)]
+pub struct ResolutionReport {
+  /// Warnings found while resolving the ACL.
+  pub warnings: Vec<ResolutionWarning>,
+  /// Scope sizes of each plugin.
+  pub scope_sizes: BTreeMap<String, PluginScopeSizes>,
+  /// Allowed commands of each plugin by the origin of their grant.
+  pub grants: BTreeMap<String, GrantCounts>,
+  /// Scopes rewritten by the [`ScopeHooks`].
+  pub scope_overrides: ScopeOverrides,
+  /// Capabilities filtered out before the resolution because their group is disabled,
+  /// see [`CapabilityGroups`](super::capability::CapabilityGroups).
+  pub filtered_capabilities: Vec<String>,
+  /// Capabilities replaced by a target-specific capability with the same identifier when loading the capability files.
+  pub overridden_capabilities: Vec<CapabilityOverride>,
+  /// Deprecated permissions and permission sets referenced by the capabilities.
+  pub deprecations: Vec<DeprecatedReference>,
+  /// The values of the scopes stored out of the resolved ACL, see [`Resolved::externalize_scopes`].
+  #[serde(skip)]
+  pub external_scopes: ExternalScopes,
+}
+
+/// A deprecated permission or permission set granted by a capability, see [`Deprecation`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeprecatedReference {
+  /// Identifier of the capability.
+  pub capability: String,
+  /// The plugin defining the deprecated identifier.
+  pub plugin: String,
+  /// The deprecated permission or permission set identifier, without the plugin prefix.
+  pub identifier: String,
+  /// Fully qualified identifiers of the permission sets the capability references the deprecated identifier through,
+  /// outermost first. Empty when the capability references it directly.
+  pub via: Vec<String>,
+  /// The deprecation metadata.
+  pub deprecation: Deprecation,
+}
+
+/// The changes a capability would make to a resolved ACL, see [`Resolved::simulate`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedDelta {
+  /// The commands the capability allows that were not allowed before.
+  pub new_commands: Vec<CommandDelta>,
+  /// The allowed commands the capability allows on more windows or with more scope values.
+  pub widened_commands: Vec<CommandDelta>,
+  /// The values the capability adds to the global scope of each plugin.
+  pub global_scopes: BTreeMap<String, Vec<Value>>,
+}
+
+impl ResolvedDelta {
+  /// Whether the capability does not change the resolved ACL.
+  pub fn is_empty(&self) -> bool {
+    self.new_commands.is_empty()
+      && self.widened_commands.is_empty()
+      && self.global_scopes.is_empty()
+  }
+}
+
+/// A command allowed or widened by a capability, see [`ResolvedDelta`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandDelta {
+  /// The command name.
+  pub command: String,
+  /// The remote domain pattern the command is allowed on, [`None`] for the local app URL.
+  pub remote: Option<String>,
+  /// The window selectors the command is newly allowed on.
+  pub windows: Vec<CapabilityWindow>,
+  /// The scope values newly allowed to the command.
+  pub scope: Vec<Value>,
+}
+
+/// What a single capability grants on its own, see [`preview_capability`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityPreview {
+  /// Identifier of the capability.
+  pub identifier: String,
+  /// The commands the capability allows, with their windows and merged scopes.
+  pub commands: Vec<CommandPreview>,
+  /// The commands the capability denies on every window.
+  pub denied_commands: Vec<String>,
+  /// The scope values the capability adds to the global scope of each plugin.
+  pub global_scopes: BTreeMap<String, PreviewScope>,
+  /// The permissions left out of the preview because they cannot be resolved, e.g. unknown permissions,
+  /// or the capability error when nothing can be resolved.
+  pub diagnostics: Vec<Diagnostic>,
+  /// The non-fatal findings of the resolution.
+  pub warnings: Vec<ResolutionWarning>,
+}
+
+/// A command allowed by a capability, see [`CapabilityPreview`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandPreview {
+  /// The command name.
+  pub command: String,
+  /// The remote domain pattern the command is allowed on, [`None`] for the local app URL.
+  pub remote: Option<String>,
+  /// The window selectors the command is allowed on.
+  pub windows: Vec<CapabilityWindow>,
+  /// The merged scope of the command.
+  pub scope: PreviewScope,
+}
+
+/// The allowed and denied values of a scope, see [`CapabilityPreview`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PreviewScope {
+  /// The allowed values.
+  pub allow: Vec<Value>,
+  /// The denied values.
+  pub deny: Vec<Value>,
+}
+
+impl From<ResolvedScope> for PreviewScope {
+  fn from(scope: ResolvedScope) -> Self {
+    Self {
+      allow: scope.allow,
+      deny: scope.deny,
+    }
+  }
+}
+
+/// A capability replaced by a capability with the same identifier from a more target-specific directory,
+/// e.g. `capabilities/mobile/android` over `capabilities/mobile` or the common `capabilities` directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapabilityOverride {
+  /// Identifier of the capability.
+  pub identifier: String,
+  /// Path of the file defining the replaced capability.
+  pub overridden: PathBuf,
+  /// Path of the file defining the capability that replaced it.
+  pub by: PathBuf,
+}
+
+impl ResolutionReport {
+  /// Warns about the remote origins granted by the capabilities that the configured CSPs block at the network layer.
+  ///
+  /// An origin is not blocked when [`SecurityConfig#structfield.capability_connect_src`] adds it to the CSP at runtime.
+  pub fn check_connect_src(&mut self, resolved: &Resolved, security: &SecurityConfig) {
+    let origins = resolved.remote_origins();
+    let extended = security.capability_connect_src
+      && security
+        .dangerous_disable_asset_csp_modification
+        .can_modify("connect-src");
+    for (name, csp) in [("CSP", &security.csp), ("dev CSP", &security.dev_csp)] {
+      let Some(csp) = csp else {
+        continue;
+      };
+      let blocked = origins
+        .iter()
+        // the runtime adds the origins it can express as sources
+        .filter(|origin| !(origin.is_allowed_by(csp) || extended && origin.csp_source().is_some()))
+        .map(|origin| {
+          let windows = origin
+            .windows
+            .iter()
+            .map(|window| window.as_str().to_string())
+            .chain(origin.window_ancestors.iter().map(|ancestor| {
+              let selector = if ancestor.parent_only {
+                "parent-of"
+              } else {
+                "descendant-of"
+              };
+              format!("{selector} {}", ancestor.pattern.as_str())
+            }))
+            .chain(origin.webviews.iter().map(|webview| {
+              format!(
+                "webview {} in {}",
+                webview.webview.as_str(),
+                webview.window.as_str()
+              )
+            }))
+            .collect::<Vec<_>>();
+          format!(
+            "{} (windows {})",
+            origin.domain.as_str(),
+            windows.join(", ")
+          )
+        })
+        .collect::<Vec<_>>();
+      if !blocked.is_empty() {
+        self.warn(
+          WARNING_CSP_BLOCKS_REMOTE_ORIGINS,
+          format!(
+            "the {name} connect-src directive blocks the remote origins granted by the capabilities: {}",
+            blocked.join("; ")
+          ),
+        );
+      }
+    }
+  }
+
+  fn warn(&mut self, code: &'static str, message: impl Into<String>) {
+    self.warnings.push(ResolutionWarning {
+      code,
+      severity: WarningSeverity::Normal,
+      message: message.into(),
+    });
+  }
+
+  fn warn_high(&mut self, code: &'static str, message: impl Into<String>) {
+    self.warnings.push(ResolutionWarning {
+      code,
+      severity: WarningSeverity::High,
+      message: message.into(),
+    });
+  }
+}
+
+/// A hook to post-process a resolved scope of a plugin.
+///
+/// Receives the plugin name, the command name or `None` for the plugin global scope,
+/// and the merged scope to rewrite. Returning an error fails the ACL resolution.
+pub type ScopeHook = Box<dyn Fn(&str, Option<&str>, &mut ResolvedScope) -> Result<(), String>>;
+
+/// Hooks to post-process the resolved scopes of plugins, keyed by plugin name.
+///
+/// The hooks run while resolving the ACL, after the scopes of all capabilities are merged,
+/// and can be used to normalize scope values or expand shorthands.
+#[derive(Default)]
+pub struct ScopeHooks(BTreeMap<String, ScopeHook>);
+
+impl fmt::Debug for ScopeHooks {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_set().entries(self.0.keys()).finish()
+  }
+}
+
+impl ScopeHooks {
+  /// Creates an empty set of hooks.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers the hook of the given plugin, replacing its previous hook.
+  pub fn insert<F>(&mut self, plugin: impl Into<String>, hook: F)
+  where
+    F: Fn(&str, Option<&str>, &mut ResolvedScope) -> Result<(), String> + 'static,
+  {
+    self.0.insert(plugin.into(), Box::new(hook));
+  }
+
+  /// Whether no hook is registered.
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+}
+
+/// Scopes rewritten by [`ScopeHooks`].
+///
+/// The hooks only exist in the build script, so it persists the rewritten scopes
+/// for the code generation to apply them with [`Resolved::apply_scope_overrides`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScopeOverrides {
+  /// Rewritten command scopes, keyed by plugin name, command name and original scope key.
+  pub commands: BTreeMap<String, BTreeMap<String, BTreeMap<ScopeKey, ResolvedScope>>>,
+  /// Rewritten global scopes, keyed by plugin name, in the order of [`Resolved#structfield.global_scope`].
+  pub global: BTreeMap<String, Vec<ResolvedScope>>,
+}
+
+/// Warning code for a remote domain pattern that matches every domain.
+pub const WARNING_REMOTE_MATCHES_ALL: &str = "remote-matches-all";
+
+/// Warning code for a subtracted scope value that is not allowed by any permission.
+pub const WARNING_UNUSED_SCOPE_SUBTRACTION: &str = "unused-scope-subtraction";
+
+/// Warning code for a plugin with multiple permissions but no default permission set.
+pub const WARNING_MISSING_DEFAULT_PERMISSION: &str = "missing-default-permission";
+
+/// Warning code for a capability entry whose permissions are not available on the target platform.
+pub const WARNING_PERMISSION_UNAVAILABLE_ON_TARGET: &str = "permission-unavailable-on-target";
+
+/// Warning code for a capability referencing every permission of a plugin, e.g. `fs:*`.
+pub const WARNING_ALL_PERMISSIONS: &str = "all-permissions";
+
+/// Warning code for a command that is allowed and denied on windows that may be the same.
+pub const WARNING_ALLOW_DENY_OVERLAP: &str = "allow-deny-overlap";
+
+/// Warning code for a remote capability granting commands that return local resource identifiers,
+/// see [`Permission#structfield.returns`].
+///
+/// This warning has a [`WarningSeverity::High`] severity.
+pub const WARNING_REMOTE_LOCAL_RESOURCES: &str = "remote-local-resources";
+
+/// Warning code for a remote origin granted by the capabilities but blocked by the CSP `connect-src` directive,
+/// see [`ResolutionReport::check_connect_src`].
+pub const WARNING_CSP_BLOCKS_REMOTE_ORIGINS: &str = "csp-blocks-remote-origins";
+
+/// Warning code for a capability granting a deprecated permission or permission set, see [`Deprecation`].
+pub const WARNING_DEPRECATED_PERMISSION: &str = "deprecated-permission";
+
+/// Warning code for the remote grants of the capabilities rejected by the [`LOCKDOWN_REMOTE_CAPABILITY`].
+pub const WARNING_REMOTE_LOCKDOWN: &str = "remote-lockdown";
+
+/// Warning code for a permission referencing a command by one of its aliases,
+/// see [`Manifest#structfield.aliases`].
+pub const WARNING_DEPRECATED_COMMAND_ALIAS: &str = "deprecated-command-alias";
+
+/// Warning code for the scope values of a capability entry that are not applied to the permissions
+/// another plugin delegates through the referenced permission set, since they are written for the referenced plugin.
+pub const WARNING_CROSS_PLUGIN_SCOPE: &str = "cross-plugin-scope";
+
+/// Warning code for a plugin sharing the global scope of a plugin that is not in the ACL,
+/// see [`Manifest#structfield.shares_scope_with`].
+pub const WARNING_UNKNOWN_SHARED_SCOPE: &str = "unknown-shared-scope";
+
+/// Level of detail of the resolved ACL embedded in the application by the code generation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AclCodegenDetail {
+  /// Embeds the resolved ACL as is.
+  #[default]
+  Full,
+  /// Embeds only what the authorization needs, see [`Resolved::minimize`].
+  ///
+  /// The window patterns are also interned in the generated code.
+  Minimal,
+}
+
 /// Resolved access control list.
 #[derive(Default)]
 pub struct Resolved {
@@ -85,8 +988,65 @@ pub struct Resolved {
   pub denied_commands: BTreeMap<CommandKey, ResolvedCommand>,
   /// The store of scopes referenced by a [`ResolvedCommand`].
   pub command_scope: BTreeMap<ScopeKey, ResolvedScope>,
-  /// The global scope.
-  pub global_scope: BTreeMap<String, ResolvedScope>,
+  /// The scopes referenced by a [`ResolvedCommand`] that are decoded on their first use.
+  ///
+  /// The code generation embeds large command scopes this way instead of [`Self::command_scope`].
+  pub command_scope_shards: ScopeShards,
+  /// The global scope of each plugin, split by the windows of the capabilities granting it.
+  ///
+  /// Use [`flatten_global_scope`] to get the scope granted to every window.
+  pub global_scope: BTreeMap<String, Vec<WindowGlobalScope>>,
+  /// The webviews granted a copy of the grants of their window on resolution,
+  /// see [`Capability#structfield.inherit_from_window`].
+  ///
+  /// They are not selected by their window label, so the grants added to the window afterwards do not reach them.
+  pub inheriting_webviews: Vec<WebviewPattern>,
+  /// The functions of the plugins global API mapped to the commands they invoke,
+  /// see [`Manifest#structfield.global_api`].
+  ///
+  /// The runtime injects only the functions whose command the webview is allowed to use.
+  pub global_api: BTreeMap<String, BTreeMap<String, String>>,
+  /// The command aliases of the plugins mapped to their canonical command,
+  /// see [`Manifest#structfield.aliases`].
+  ///
+  /// The runtime invokes the canonical command when the frontend uses an alias.
+  pub command_aliases: BTreeMap<String, BTreeMap<String, String>>,
+  /// The plugins whose global scopes each plugin reads in addition to its own,
+  /// see [`Manifest#structfield.shares_scope_with`].
+  ///
+  /// The shared scopes are not copied in [`Self::global_scope`], the runtime reads them under their plugin.
+  pub shares_scope_with: BTreeMap<String, Vec<String>>,
+  /// Whether every invoke from a remote origin is rejected, see [`LOCKDOWN_REMOTE_CAPABILITY`].
+  ///
+  /// The remote grants are kept, so the authority can still explain what the lockdown denies.
+  pub lockdown_remote: bool,
+  /// The grants of the capabilities restricted to user roles, pending until one of their roles is active,
+  /// see [`Capability#structfield.roles`].
+  ///
+  /// They are not part of the other fields, the runtime authority checks them after the grants of this ACL.
+  pub role_grants: Vec<RoleGrant>,
+}
+
+/// The grants of a capability restricted to user roles, see [`Resolved#structfield.role_grants`].
+///
+/// The capability is resolved on its own, without the [`ScopeHooks`], so its grants can be applied and withdrawn as a whole.
+#[derive(Debug, Default)]
+pub struct RoleGrant {
+  /// Identifier of the capability.
+  pub capability: String,
+  /// The roles activating the grants, any of them is enough.
+  pub roles: Vec<String>,
+  /// The resolved grants of the capability.
+  pub acl: Resolved,
+}
+
+impl RoleGrant {
+  /// Whether one of the given roles activates the grants.
+  pub fn is_active<S: AsRef<str>>(&self, roles: &[S]) -> bool {
+    roles
+      .iter()
+      .any(|role| self.roles.iter().any(|r| r == role.as_ref()))
+  }
 }
 
 impl fmt::Debug for Resolved {
@@ -95,67 +1055,342 @@ impl fmt::Debug for Resolved {
       .field("allowed_commands", &self.allowed_commands)
       .field("denied_commands", &self.denied_commands)
       .field("command_scope", &self.command_scope)
+      .field("command_scope_shards", &self.command_scope_shards)
       .field("global_scope", &self.global_scope)
+      .field("inheriting_webviews", &self.inheriting_webviews)
+      .field("lockdown_remote", &self.lockdown_remote)
+      .field("role_grants", &self.role_grants)
       .finish()
   }
 }
 
 impl Resolved {
+  /// Creates a builder to construct the resolved ACL at runtime,
+  /// for applications whose capabilities are not known at build time.
+  pub fn builder() -> ResolvedBuilder {
+    ResolvedBuilder::default()
+  }
+
   /// Resolves the ACL for the given plugin permissions and app capabilities.
   pub fn resolve(
     acl: BTreeMap<String, Manifest>,
     capabilities: BTreeMap<String, Capability>,
     target: Target,
   ) -> Result<Self, Error> {
+    Self::resolve_with_report(acl, capabilities, target).map(|(resolved, _report)| resolved)
+  }
+
+  /// Resolves the ACL for the given plugin permissions and app capabilities,
+  /// also returning a report with the non-fatal findings of the resolution.
+  pub fn resolve_with_report(
+    acl: BTreeMap<String, Manifest>,
+    capabilities: BTreeMap<String, Capability>,
+    target: Target,
+  ) -> Result<(Self, ResolutionReport), Error> {
+    Self::resolve_with_hooks(acl, capabilities, target, &ScopeHooks::default())
+  }
+
+  /// Resolves the ACL like [`Self::resolve_with_report`],
+  /// running the given hooks on the merged scopes of their plugins.
+  #[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+      name = "acl::resolve",
+      skip_all,
+      fields(plugins = acl.len(), capabilities = capabilities.len(), %target)
+    )
+  )]
+  pub fn resolve_with_hooks(
+    acl: BTreeMap<String, Manifest>,
+    capabilities: BTreeMap<String, Capability>,
+    target: Target,
+    hooks: &ScopeHooks,
+  ) -> Result<(Self, ResolutionReport), Error> {
+    if capabilities
+      .values()
+      .any(|capability| !capability.roles.is_empty())
+    {
+      return Self::resolve_with_roles(acl, capabilities, target, hooks);
+    }
+
+    let mut report = ResolutionReport::default();
+
+    for (plugin_name, manifest) in &acl {
+      manifest.validate_aliases(plugin_name)?;
+      manifest.validate_tiers(plugin_name)?;
+      manifest.validate_presets(plugin_name)?;
+      for permission in manifest.permissions.values() {
+        for command in permission
+          .commands
+          .allow
+          .iter()
+          .chain(&permission.commands.deny)
+        {
+          let canonical = manifest.canonical_command(command);
+          if canonical != command {
+            report.warn(
+              WARNING_DEPRECATED_COMMAND_ALIAS,
+              format!(
+                "permission {plugin_name}:{} references command {command}, a deprecated alias of {canonical}",
+                permission.identifier
+              ),
+            );
+          }
+        }
+      }
+      if manifest.default_permission.is_none() && manifest.permissions.len() > 1 {
+        report.warn(
+          WARNING_MISSING_DEFAULT_PERMISSION,
+          format!(
+            "plugin {plugin_name} defines multiple permissions but no default permission set, so {plugin_name}:default cannot be used"
+          ),
+        );
+      }
+    }
+
     let mut allowed_commands = BTreeMap::new();
     let mut denied_commands = BTreeMap::new();
 
     let mut current_scope_id = 0;
     let mut command_scopes = BTreeMap::new();
-    let mut global_scope: BTreeMap<String, Vec<Scopes>> = BTreeMap::new();
+    // global scopes of each plugin, grouped by the windows and pages of the capabilities granting them
+    let mut global_scope: BTreeMap<String, BTreeMap<Grantees, Vec<Scopes>>> = BTreeMap::new();
+    // scope values to subtract from the allowed scopes, along with the permission subtracting them
+    let mut subtractions: BTreeMap<CommandKey, Vec<Subtraction>> = BTreeMap::new();
+    let mut global_subtractions: BTreeMap<String, Vec<Subtraction>> = BTreeMap::new();
+    // webviews inheriting the grants of their window, along with their attenuation
+    let mut inheritances = Vec::new();
+    let mut lockdown_remote = false;
 
     // resolve commands
     for capability in capabilities.values() {
       if !capability.platforms.contains(&target) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+          capability = %capability.identifier,
+          platforms = ?capability.platforms,
+          "skipping capability not applying to the target"
+        );
+        continue;
+      }
+
+      #[cfg(feature = "tracing")]
+      let _span =
+        tracing::debug_span!("acl::resolve::capability", identifier = %capability.identifier)
+          .entered();
+
+      if capability.identifier == LOCKDOWN_REMOTE_CAPABILITY {
+        check_lockdown_capability(capability)?;
+        lockdown_remote = true;
         continue;
       }
 
+      let capability = &*expand_plugin_windows(capability, &acl)
+        .map_err(|error| error.with_capability(&capability.identifier))?;
+      let in_capability = |error: Error| error.with_capability(&capability.identifier);
+      let contexts = capability_contexts(capability, &mut report).map_err(in_capability)?;
+      capability.window_patterns().map_err(in_capability)?;
+      capability.page_patterns().map_err(in_capability)?;
+      if let Some(attenuation) = capability.inheritance().map_err(in_capability)? {
+        inheritances.push((
+          parse_webview_patterns(&capability.windows.iter().cloned().collect()),
+          attenuation,
+        ));
+      }
+
+      // entries skipped because none of their permissions are available on the target
+      let mut unavailable_entries = 0;
+      let mut available_entries = 0;
+
       for permission_entry in &capability.permissions {
         let permission_id = permission_entry.identifier();
         let permission_name = permission_id.get_base();
 
         if let Some(plugin_name) = permission_id.get_prefix() {
-          let permissions = get_permissions(plugin_name, permission_name, &acl)?;
-
-          for permission in permissions {
-            let scope = match permission_entry {
+          let include_deny = matches!(
+            permission_entry,
+            PermissionEntry::ExtendedPermission {
+              include_deny: true,
+              ..
+            }
+          );
+          check_plugin_version(capability, permission_entry, plugin_name, &acl)?;
+          let mut permissions = get_permissions(plugin_name, permission_name, include_deny, &acl)
+            .map_err(in_capability)?;
+          let referenced = permissions.len();
+          #[cfg(feature = "tracing")]
+          tracing::debug!(
+            permission = permission_id.get(),
+            expanded = referenced,
+            "expanded permission reference"
+          );
+          if permission_id.is_all_permissions() {
+            report.warn(
+              WARNING_ALL_PERMISSIONS,
+              format!(
+                "capability {} grants all {referenced} permissions of plugin {plugin_name}{}",
+                capability.identifier,
+                if include_deny {
+                  ", including its deny- permissions"
+                } else {
+                  ""
+                }
+              ),
+            );
+          }
+          permissions.retain(|expanded| expanded.permission.is_available_on(target));
+
+          if permissions.is_empty() && referenced > 0 {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+              permission = permission_id.get(),
+              "skipping permission reference not available on the target"
+            );
+            unavailable_entries += 1;
+            report.warn(
+              WARNING_PERMISSION_UNAVAILABLE_ON_TARGET,
+              format!(
+                "capability {} references {} which is not available on {target}, skipping it",
+                capability.identifier,
+                permission_id.get()
+              ),
+            );
+            continue;
+          }
+          available_entries += 1;
+
+          // `*` references the deprecated permissions only because it references every permission
+          if !permission_id.is_all_permissions() {
+            for reference in
+              deprecated_references(&capability.identifier, plugin_name, &permissions, &acl)
+            {
+              if report.deprecations.iter().any(|r| {
+                r.capability == reference.capability
+                  && r.plugin == reference.plugin
+                  && r.identifier == reference.identifier
+              }) {
+                continue;
+              }
+              report.warn(
+                WARNING_DEPRECATED_PERMISSION,
+                format!(
+                  "capability {} references {}:{}{}, which is {}",
+                  capability.identifier,
+                  reference.plugin,
+                  reference.identifier,
+                  if reference.via.is_empty() {
+                    String::new()
+                  } else {
+                    format!(" through {}", reference.via.join(" > "))
+                  },
+                  reference.deprecation.message(&reference.plugin)
+                ),
+              );
+              report.deprecations.push(reference);
+            }
+          }
+
+          // plugins delegating permissions the scope values of the entry are not applied to
+          let mut cross_plugin_scope = Vec::<&str>::new();
+
+          for expanded in permissions {
+            let permission = expanded.permission;
+            // the scope values of the entry are written for the referenced plugin
+            let extends_scope = expanded.plugin == plugin_name;
+            // delegated permissions grant the commands and scopes of the plugin defining them
+            let plugin_name = expanded.plugin;
+
+            let scope = match permission_entry {
               PermissionEntry::PermissionRef(_) => permission.scope.clone(),
-              PermissionEntry::ExtendedPermission {
-                identifier: _,
-                scope,
-              } => {
+              PermissionEntry::ExtendedPermission { scope, .. } if !extends_scope => {
+                if (scope.allow.is_some() || scope.deny.is_some())
+                  && !cross_plugin_scope.contains(&plugin_name)
+                {
+                  cross_plugin_scope.push(plugin_name);
+                }
+                permission.scope.clone()
+              }
+              PermissionEntry::ExtendedPermission { scope, .. } => {
+                let manifest = &acl[plugin_name];
                 let mut merged = permission.scope.clone();
                 if let Some(allow) = scope.allow.clone() {
-                  merged
-                    .allow
-                    .get_or_insert_with(Default::default)
-                    .extend(allow);
+                  merged.allow.get_or_insert_with(Default::default).extend(
+                    manifest
+                      .expand_presets(plugin_name, allow)
+                      .map_err(in_capability)?,
+                  );
                 }
                 if let Some(deny) = scope.deny.clone() {
-                  merged
-                    .deny
-                    .get_or_insert_with(Default::default)
-                    .extend(deny);
+                  merged.deny.get_or_insert_with(Default::default).extend(
+                    manifest
+                      .expand_presets(plugin_name, deny)
+                      .map_err(in_capability)?,
+                  );
                 }
                 merged
               }
             };
 
+            if let PermissionEntry::ExtendedPermission { subtract: true, .. } = permission_entry {
+              if !permission.commands.allow.is_empty() {
+                return Err(Error::SubtractingPermissionAllowsCommands {
+                  permission: permission_id.get().to_string(),
+                });
+              }
+
+              let values = scope
+                .allow
+                .into_iter()
+                .chain(scope.deny)
+                .flatten()
+                .map(|value| Subtraction {
+                  value,
+                  permission: permission_id.get().to_string(),
+                  grantees: grantees(capability),
+                })
+                .collect::<Vec<_>>();
+
+              if permission.commands.deny.is_empty() {
+                global_subtractions
+                  .entry(plugin_name.to_string())
+                  .or_default()
+                  .extend(values);
+              } else {
+                let manifest = &acl[plugin_name];
+                for name in
+                  command_names(plugin_name, &permission.commands.deny, permission, manifest)
+                    .map_err(in_capability)?
+                {
+                  for context in &contexts {
+                    subtractions
+                      .entry(CommandKey {
+                        name: name.clone(),
+                        context: context.clone(),
+                      })
+                      .or_default()
+                      .extend(values.iter().cloned());
+                  }
+                }
+              }
+
+              continue;
+            }
+
             if permission.commands.allow.is_empty() && permission.commands.deny.is_empty() {
-              // global scope
+              // global scope, only stored under the plugin defining the permission
+              debug_assert!(
+                acl[plugin_name]
+                  .permissions
+                  .get(&permission.identifier)
+                  .is_some_and(|defined| std::ptr::eq(defined, permission)),
+                "global scope of permission {} stored under plugin {plugin_name} not defining it",
+                permission.identifier
+              );
               global_scope
                 .entry(plugin_name.to_string())
                 .or_default()
+                .entry(grantees(capability))
+                .or_default()
                 .push(scope.clone());
             } else {
               let has_scope = scope.allow.is_some() || scope.deny.is_some();
@@ -170,82 +1405,346 @@ impl Resolved {
                 None
               };
 
-              for allowed_command in &permission.commands.allow {
+              let manifest = &acl[plugin_name];
+              let allowed_names = command_names(
+                plugin_name,
+                &permission.commands.allow,
+                permission,
+                manifest,
+              )
+              .map_err(in_capability)?;
+
+              if let CapabilityContext::Remote { domains, .. } = &capability.context {
+                if !permission.returns.is_empty() && !allowed_names.is_empty() {
+                  report.warn_high(
+                    WARNING_REMOTE_LOCAL_RESOURCES,
+                    format!(
+                      "capability {} grants {} to remote domains {} but its commands {} return local {} identifiers",
+                      capability.identifier,
+                      permission_id.get(),
+                      domains
+                        .iter()
+                        .flat_map(RemoteDomain::patterns)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                      allowed_names.join(", "),
+                      permission
+                        .returns
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" and "),
+                    ),
+                  );
+                }
+              }
+
+              for name in allowed_names {
                 resolve_command(
                   &mut allowed_commands,
-                  format!("plugin:{plugin_name}|{allowed_command}"),
+                  name,
                   capability,
+                  &contexts,
                   scope_id,
-                  #[cfg(debug_assertions)]
-                  permission,
+                  &expanded,
+                  permission_entry,
+                  false,
                 );
               }
 
-              for denied_command in &permission.commands.deny {
+              for name in
+                command_names(plugin_name, &permission.commands.deny, permission, manifest)
+                  .map_err(in_capability)?
+              {
                 resolve_command(
                   &mut denied_commands,
-                  format!("plugin:{plugin_name}|{denied_command}"),
+                  name,
                   capability,
+                  &contexts,
                   scope_id,
-                  #[cfg(debug_assertions)]
-                  permission,
+                  &expanded,
+                  permission_entry,
+                  true,
                 );
               }
             }
           }
+
+          if !cross_plugin_scope.is_empty() {
+            report.warn(
+              WARNING_CROSS_PLUGIN_SCOPE,
+              format!(
+                "capability {} extends {} with scope values of plugin {plugin_name}, \
+                 they are not applied to the permissions it delegates to {}",
+                capability.identifier,
+                permission_id.get(),
+                cross_plugin_scope.join(", ")
+              ),
+            );
+          }
         }
       }
+
+      if unavailable_entries > 0 && available_entries == 0 {
+        return Err(Error::PermissionsUnavailableOnTarget {
+          capability: capability.identifier.clone(),
+          target,
+        });
+      }
     }
 
     // resolve scopes
+    #[cfg(feature = "tracing")]
+    let scopes_span = tracing::debug_span!("acl::resolve::scopes").entered();
     let mut resolved_scopes = BTreeMap::new();
+    let mut unused_subtractions = BTreeSet::new();
+
+    for (key, allowed) in allowed_commands.iter_mut() {
+      let subtraction = subtractions.remove(key).unwrap_or_default();
+      let mut scopes = Vec::new();
 
-    for allowed in allowed_commands.values_mut() {
-      if !allowed.scope.is_empty() {
-        allowed.scope.sort();
+      // each window set only gets the scopes of the capabilities granting it the command
+      for (windows, ids) in &mut allowed.scopes {
+        ids.sort();
 
         let mut hasher = DefaultHasher::new();
-        allowed.scope.hash(&mut hasher);
+        ids.hash(&mut hasher);
+        // commands sharing the same scopes might subtract different values
+        for Subtraction { value, .. } in subtraction.iter().filter(|s| s.grantees == *windows) {
+          serde_json::to_string(value)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        }
         let hash = hasher.finish() as usize;
 
-        allowed.resolved_scope_key.replace(hash);
-
+        let merge_key = CommandId::parse(&key.name)
+          .ok()
+          .and_then(|id| acl.get(id.plugin()))
+          .and_then(|manifest| manifest.merge_key.as_deref());
         let resolved_scope = ResolvedScope {
-          allow: allowed
-            .scope
-            .iter()
-            .flat_map(|s| command_scopes.get(s).unwrap().allow.clone())
-            .flatten()
-            .collect(),
-          deny: allowed
-            .scope
-            .iter()
-            .flat_map(|s| command_scopes.get(s).unwrap().deny.clone())
-            .flatten()
-            .collect(),
+          allow: merge_scope_values(
+            ids
+              .iter()
+              .flat_map(|s| command_scopes.get(s).unwrap().allow.clone())
+              .flatten(),
+            merge_key,
+          ),
+          deny: merge_scope_values(
+            ids
+              .iter()
+              .flat_map(|s| command_scopes.get(s).unwrap().deny.clone())
+              .flatten(),
+            merge_key,
+          ),
+          external: None,
         };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+          command = %key.name,
+          capabilities = ids.len(),
+          allow = resolved_scope.allow.len(),
+          deny = resolved_scope.deny.len(),
+          "merged command scope"
+        );
 
+        scopes.push((windows.clone(), hash, resolved_scope));
+      }
+
+      subtract_scope_values(
+        scopes
+          .iter_mut()
+          .map(|(windows, _, scope)| (&*windows, scope))
+          .collect(),
+        subtraction,
+        &key.name,
+        &mut unused_subtractions,
+      );
+
+      for (windows, hash, resolved_scope) in scopes {
+        allowed.resolved_scopes.push((windows, hash));
         resolved_scopes.insert(hash, resolved_scope);
       }
     }
 
-    let global_scope = global_scope
+    // subtractions from commands that are not allowed
+    for (key, subtraction) in subtractions {
+      subtract_scope_values(Vec::new(), subtraction, &key.name, &mut unused_subtractions);
+    }
+
+    // the windows and pages of each global scope, in the order of the resolved global scopes
+    let global_scope_grantees = global_scope
+      .iter()
+      .map(|(plugin_name, window_scopes)| {
+        let grantees = window_scopes.keys().cloned().collect::<Vec<_>>();
+        (plugin_name.clone(), grantees)
+      })
+      .collect::<BTreeMap<_, _>>();
+    let mut global_scope = global_scope
       .into_iter()
-      .map(|(plugin_name, scopes)| {
-        let mut resolved_scope = ResolvedScope::default();
-        for scope in scopes {
-          if let Some(allow) = scope.allow {
-            resolved_scope.allow.extend(allow);
-          }
-          if let Some(deny) = scope.deny {
-            resolved_scope.deny.extend(deny);
+      .map(|(plugin_name, window_scopes)| {
+        let merge_key = acl
+          .get(&plugin_name)
+          .and_then(|manifest| manifest.merge_key.as_deref());
+        let window_scopes = window_scopes
+          .into_iter()
+          .map(|((windows, pages), scopes)| {
+            let mut resolved_scope = ResolvedScope::default();
+            for scope in scopes {
+              if let Some(allow) = scope.allow {
+                resolved_scope.allow.extend(allow);
+              }
+              if let Some(deny) = scope.deny {
+                resolved_scope.deny.extend(deny);
+              }
+            }
+            if let Some(merge_key) = merge_key {
+              resolved_scope.allow = merge_scope_values(resolved_scope.allow, Some(merge_key));
+              resolved_scope.deny = merge_scope_values(resolved_scope.deny, Some(merge_key));
+            }
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+              plugin = %plugin_name,
+              allow = resolved_scope.allow.len(),
+              deny = resolved_scope.deny.len(),
+              "merged global scope"
+            );
+            WindowGlobalScope {
+              windows: parse_window_patterns(&windows),
+              window_ancestors: parse_window_ancestors(&windows),
+              webviews: parse_webview_patterns(&windows),
+              pages: parse_page_patterns(&pages),
+              scope: resolved_scope,
+            }
+          })
+          .collect::<Vec<_>>();
+        (plugin_name, window_scopes)
+      })
+      .collect::<BTreeMap<_, _>>();
+
+    for (plugin_name, subtraction) in global_subtractions {
+      subtract_scope_values(
+        global_scope_grantees
+          .get(&plugin_name)
+          .into_iter()
+          .flatten()
+          .zip(global_scope.get_mut(&plugin_name).into_iter().flatten())
+          .map(|(grantees, window_scope)| (grantees, &mut window_scope.scope))
+          .collect(),
+        subtraction,
+        &format!("{plugin_name} global scope"),
+        &mut unused_subtractions,
+      );
+    }
+    #[cfg(feature = "tracing")]
+    drop(scopes_span);
+
+    for (key, allowed) in &allowed_commands {
+      let plugin = CommandId::parse(&key.name)
+        .ok()
+        .map(|id| id.plugin().to_string());
+      let global_scopes = plugin
+        .as_ref()
+        .and_then(|plugin| {
+          Some(
+            global_scope_grantees
+              .get(plugin)?
+              .iter()
+              .zip(&global_scope[plugin]),
+          )
+        })
+        .into_iter()
+        .flatten();
+      // the scopes granting values to a set of windows
+      let scoped_windows = allowed
+        .resolved_scopes
+        .iter()
+        .filter(|(_, key)| !resolved_scopes[key].allow.is_empty())
+        .map(|((windows, _), _)| windows)
+        .chain(
+          global_scopes
+            .filter(|(_, window_scope)| !window_scope.scope.allow.is_empty())
+            .map(|((windows, _), _)| windows),
+        )
+        .collect::<Vec<_>>();
+
+      for (capability, permission, windows) in &allowed.requires_scope {
+        // every window of the capability must get allowed values, from any capability
+        let has_scope = windows.iter().all(|window| {
+          scoped_windows.iter().any(|scoped| {
+            scoped.contains(window)
+              || matches!(window, CapabilityWindow::Label(label)
+                if grants_window(&parse_window_patterns(scoped), &parse_window_pattern(label)))
+          })
+        });
+        if !has_scope {
+          return Err(Error::MissingRequiredScope {
+            command: key.name.clone(),
+            capability: capability.clone(),
+            permission: permission.clone(),
+          });
+        }
+      }
+    }
+
+    for message in unused_subtractions {
+      report.warn(WARNING_UNUSED_SCOPE_SUBTRACTION, message);
+    }
+
+    // added after the required scope check, the plugin deny entries do not scope its commands
+    let required_deny = acl
+      .iter()
+      .filter(|(_, manifest)| !manifest.required_deny.is_empty())
+      .map(|(plugin_name, manifest)| (plugin_name.clone(), manifest.required_deny.clone()))
+      .collect::<BTreeMap<_, _>>();
+    for (plugin_name, required) in &required_deny {
+      let mut granted = false;
+      for (key, allowed) in &allowed_commands {
+        if CommandId::parse(&key.name).is_ok_and(|id| id.plugin() == plugin_name) {
+          granted = true;
+          for (_, hash) in &allowed.resolved_scopes {
+            if let Some(scope) = resolved_scopes.get_mut(hash) {
+              require_deny(scope, required);
+            }
           }
         }
-        (plugin_name, resolved_scope)
+      }
+
+      if granted && !global_scope.contains_key(plugin_name) {
+        // commands without a scope still get the deny entries from the global scope
+        global_scope.insert(
+          plugin_name.clone(),
+          vec![WindowGlobalScope {
+            windows: vec![WindowPattern::new("*").expect("the wildcard window pattern is valid")],
+            ..Default::default()
+          }],
+        );
+      }
+      for window_scope in global_scope.get_mut(plugin_name).into_iter().flatten() {
+        require_deny(&mut window_scope.scope, required);
+      }
+    }
+
+    let external_plugins = external_scope_plugins(&acl);
+    let global_api = acl
+      .iter()
+      .filter(|(_, manifest)| !manifest.global_api.is_empty())
+      .map(|(plugin, manifest)| (plugin.clone(), manifest.global_api.clone()))
+      .collect();
+    let shares_scope_with = shared_scopes(&acl, &mut report);
+    let command_aliases = acl
+      .iter()
+      .filter(|(_, manifest)| !manifest.aliases.is_empty())
+      .map(|(plugin, manifest)| {
+        let aliases = manifest
+          .aliases
+          .keys()
+          .map(|alias| (alias.clone(), manifest.canonical_command(alias).to_string()))
+          .collect();
+        (plugin.clone(), aliases)
       })
       .collect();
 
-    let resolved = Self {
+    let mut resolved = Self {
       #[cfg(debug_assertions)]
       acl,
       allowed_commands: allowed_commands
@@ -256,8 +1755,16 @@ impl Resolved {
             ResolvedCommand {
               #[cfg(debug_assertions)]
               referenced_by: cmd.referenced_by,
-              windows: parse_window_patterns(cmd.windows)?,
-              scope: cmd.resolved_scope_key,
+              windows: parse_window_patterns(&cmd.windows),
+              window_ancestors: parse_window_ancestors(&cmd.windows),
+              webviews: parse_webview_patterns(&cmd.windows),
+              pages: page_grants(&cmd.page_windows),
+              scopes: window_scopes(&cmd.resolved_scopes),
+              classes: cmd.classes.into_iter().collect(),
+              validators: cmd.validators.into_iter().collect(),
+              args: cmd.args,
+              via_default: cmd.via_default && !cmd.explicit,
+              configs: window_configs(cmd.configs),
             },
           ))
         })
@@ -270,271 +1777,6073 @@ impl Resolved {
             ResolvedCommand {
               #[cfg(debug_assertions)]
               referenced_by: cmd.referenced_by,
-              windows: parse_window_patterns(cmd.windows)?,
-              scope: cmd.resolved_scope_key,
+              windows: parse_window_patterns(&cmd.windows),
+              window_ancestors: parse_window_ancestors(&cmd.windows),
+              webviews: parse_webview_patterns(&cmd.windows),
+              pages: page_grants(&cmd.page_windows),
+              scopes: window_scopes(&cmd.resolved_scopes),
+              classes: cmd.classes.into_iter().collect(),
+              validators: cmd.validators.into_iter().collect(),
+              args: cmd.args,
+              via_default: cmd.via_default && !cmd.explicit,
+              configs: window_configs(cmd.configs),
             },
           ))
         })
         .collect::<Result<_, Error>>()?,
       command_scope: resolved_scopes,
+      command_scope_shards: ScopeShards::default(),
       global_scope,
+      inheriting_webviews: Vec::new(),
+      global_api,
+      command_aliases,
+      shares_scope_with,
+      lockdown_remote,
+      role_grants: Vec::new(),
     };
 
-    Ok(resolved)
-  }
-}
-
-fn parse_window_patterns(windows: HashSet<String>) -> Result<Vec<glob::Pattern>, Error> {
-  let mut patterns = Vec::new();
-  for window in windows {
-    patterns.push(glob::Pattern::new(&window)?);
-  }
-  Ok(patterns)
-}
-
-#[derive(Debug, Default)]
-struct ResolvedCommandTemp {
-  #[cfg(debug_assertions)]
-  pub referenced_by: Vec<ResolvedCommandReference>,
-  pub windows: HashSet<String>,
-  pub scope: Vec<usize>,
-  pub resolved_scope_key: Option<usize>,
-}
+    for (webviews, attenuation) in &inheritances {
+      resolved.inherit_window_grants(webviews, attenuation);
+    }
 
-fn resolve_command(
-  commands: &mut BTreeMap<CommandKey, ResolvedCommandTemp>,
-  command: String,
-  capability: &Capability,
-  scope_id: Option<usize>,
-  #[cfg(debug_assertions)] permission: &Permission,
-) {
-  let contexts = match &capability.context {
-    CapabilityContext::Local => {
-      vec![ExecutionContext::Local]
+    if lockdown_remote {
+      if let Some(message) = remote_lockdown_message(&capabilities, &resolved, target) {
+        report.warn(WARNING_REMOTE_LOCKDOWN, message);
+      }
     }
-    CapabilityContext::Remote { domains } => domains
-      .iter()
-      .map(|domain| ExecutionContext::Remote {
-        domain: Pattern::new(domain)
-          .unwrap_or_else(|e| panic!("invalid glob pattern for remote domain {domain}: {e}")),
-      })
-      .collect(),
-  };
 
-  for context in contexts {
-    let resolved = commands
-      .entry(CommandKey {
-        name: command.clone(),
-        context,
-      })
-      .or_default();
+    for message in resolved.allow_deny_overlaps() {
+      report.warn(WARNING_ALLOW_DENY_OVERLAP, message);
+    }
 
-    #[cfg(debug_assertions)]
-    resolved.referenced_by.push(ResolvedCommandReference {
-      capability: capability.identifier.clone(),
-      permission: permission.identifier.clone(),
+    report.scope_overrides = resolved.run_scope_hooks(hooks, &required_deny)?;
+    resolved.for_each_plugin_scope(&external_plugins, |plugin, scope| {
+      report.external_scopes.insert(plugin, scope)
     });
+    report.scope_sizes = resolved.scope_sizes();
+    report.grants = resolved.grants();
 
-    resolved.windows.extend(capability.windows.clone());
-    if let Some(id) = scope_id {
-      resolved.scope.push(id);
-    }
+    Ok((resolved, report))
   }
-}
 
-// get the permissions from a permission set
-fn get_permission_set_permissions<'a>(
-  manifest: &'a Manifest,
-  set: &'a PermissionSet,
-) -> Result<Vec<&'a Permission>, Error> {
-  let mut permissions = Vec::new();
+  /// Resolves the capabilities without roles like [`Self::resolve_with_hooks`],
+  /// and each capability restricted to user roles on its own into a [`RoleGrant`].
+  fn resolve_with_roles(
+    acl: BTreeMap<String, Manifest>,
+    capabilities: BTreeMap<String, Capability>,
+    target: Target,
+    hooks: &ScopeHooks,
+  ) -> Result<(Self, ResolutionReport), Error> {
+    let (role_capabilities, capabilities): (BTreeMap<_, _>, BTreeMap<_, _>) = capabilities
+      .into_iter()
+      .partition(|(_, capability)| !capability.roles.is_empty());
+    let (mut resolved, mut report) =
+      Self::resolve_with_hooks(acl.clone(), capabilities, target, hooks)?;
 
-  for p in &set.permissions {
-    if let Some(permission) = manifest.permissions.get(p) {
-      permissions.push(permission);
-    } else if let Some(permission_set) = manifest.permission_sets.get(p) {
-      permissions.extend(get_permission_set_permissions(manifest, permission_set)?);
-    } else {
-      return Err(Error::SetPermissionNotFound {
-        permission: p.to_string(),
-        set: set.identifier.clone(),
+    for (identifier, mut capability) in role_capabilities {
+      if !capability.platforms.contains(&target) {
+        continue;
+      }
+      let roles = std::mem::take(&mut capability.roles);
+      let (mut grant, grant_report) = Self::resolve_with_hooks(
+        acl.clone(),
+        [(identifier.clone(), capability)].into_iter().collect(),
+        target,
+        &ScopeHooks::default(),
+      )?;
+      // the manifests are already embedded once in the ACL
+      #[cfg(debug_assertions)]
+      grant.acl.clear();
+
+      report.warnings.extend(grant_report.warnings);
+      report.deprecations.extend(grant_report.deprecations);
+      report.external_scopes.extend(grant_report.external_scopes);
+      resolved.role_grants.push(RoleGrant {
+        capability: identifier,
+        roles,
+        acl: grant,
       });
     }
+
+    Ok((resolved, report))
   }
 
-  Ok(permissions)
-}
+  /// Lists the commands of the given plugin with their resolved access, sorted by command name.
+  ///
+  /// In debug builds, the commands referenced by the plugin permissions
+  /// that are neither allowed nor denied are included as [`CommandStatus::NotGranted`].
+  pub fn plugin_commands(&self, plugin: &str) -> Vec<PluginCommandStatus> {
+    let prefix = format!("{PLUGIN_COMMAND_PREFIX}{plugin}{PLUGIN_COMMAND_SEPARATOR}");
+    let mut commands = BTreeMap::new();
+    fn status<'a>(
+      commands: &'a mut BTreeMap<String, PluginCommandStatus>,
+      prefix: &str,
+      name: &str,
+    ) -> Option<&'a mut PluginCommandStatus> {
+      name.strip_prefix(prefix).map(|command| {
+        commands
+          .entry(command.to_string())
+          .or_insert_with(|| PluginCommandStatus {
+            command: command.to_string(),
+            status: CommandStatus::NotGranted,
+            windows: Vec::new(),
+            scoped: false,
+          })
+      })
+    }
 
-fn get_permissions<'a>(
-  plugin_name: &'a str,
-  permission_name: &'a str,
-  acl: &'a BTreeMap<String, Manifest>,
-) -> Result<Vec<&'a Permission>, Error> {
-  let manifest = acl.get(plugin_name).ok_or_else(|| Error::UnknownPlugin {
-    plugin: plugin_name.to_string(),
-    available: acl.keys().cloned().collect::<Vec<_>>().join(", "),
-  })?;
+    for (key, resolved) in &self.allowed_commands {
+      if let Some(status) = status(&mut commands, &prefix, &key.name) {
+        status.status = CommandStatus::Granted;
+        status
+          .windows
+          .extend(resolved.windows.iter().map(|w| w.as_str().to_string()));
+        status.scoped |= !resolved.scopes.is_empty();
+      }
+    }
 
-  if permission_name == "default" {
-    manifest
-      .default_permission
-      .as_ref()
-      .ok_or_else(|| Error::UnknownPermission {
-        plugin: plugin_name.to_string(),
-        permission: permission_name.to_string(),
+    for key in self.denied_commands.keys() {
+      if let Some(status) = status(&mut commands, &prefix, &key.name) {
+        status.status = CommandStatus::Denied;
+      }
+    }
+
+    #[cfg(debug_assertions)]
+    if let Some(manifest) = self.acl.get(plugin) {
+      for command in manifest.commands() {
+        status(&mut commands, &prefix, &format!("{prefix}{command}"));
+      }
+    }
+
+    commands
+      .into_values()
+      .map(|mut status| {
+        status.windows.sort();
+        status.windows.dedup();
+        status
       })
-      .and_then(|default| get_permission_set_permissions(manifest, default))
-  } else if let Some(set) = manifest.permission_sets.get(permission_name) {
-    get_permission_set_permissions(manifest, set)
-  } else if let Some(permission) = manifest.permissions.get(permission_name) {
-    Ok(vec![permission])
-  } else {
-    Err(Error::UnknownPermission {
-      plugin: plugin_name.to_string(),
-      permission: permission_name.to_string(),
-    })
+      .collect()
   }
-}
 
-#[cfg(feature = "build")]
-mod build {
-  use proc_macro2::TokenStream;
-  use quote::{quote, ToTokens, TokenStreamExt};
-  use std::convert::identity;
+  /// Reports what resolving the given capability on top of this ACL would change, without changing it.
+  ///
+  /// Use it to preview a capability before granting it at runtime, e.g. to ask the user for consent.
+  /// A capability that only duplicates existing grants produces an empty [`ResolvedDelta`].
+  pub fn simulate(
+    &self,
+    acl: &BTreeMap<String, Manifest>,
+    capability: &Capability,
+    target: Target,
+  ) -> Result<ResolvedDelta, Error> {
+    let (simulated, _report) = resolve_capability(acl, capability, target)?;
 
-  use super::*;
-  use crate::{literal_struct, tokens::*};
+    let mut delta = ResolvedDelta::default();
 
-  impl ToTokens for CommandKey {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-      let name = str_lit(&self.name);
-      let context = &self.context;
-      literal_struct!(
-        tokens,
-        ::tauri::utils::acl::resolved::CommandKey,
-        name,
-        context
-      )
-    }
-  }
+    for (key, command) in &simulated.allowed_commands {
+      let scope = allowed_scope_values(&simulated.command_scope, command);
+      let remote = remote_domain(key);
 
-  #[cfg(debug_assertions)]
-  impl ToTokens for ResolvedCommandReference {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-      let capability = str_lit(&self.capability);
-      let permission = str_lit(&self.permission);
-      literal_struct!(
-        tokens,
-        ::tauri::utils::acl::resolved::ResolvedCommandReference,
-        capability,
-        permission
-      )
+      match self.allowed_commands.get(key) {
+        None => delta.new_commands.push(CommandDelta {
+          command: key.name.clone(),
+          remote,
+          windows: capability_windows(command, |_| true),
+          scope,
+        }),
+        Some(existing) => {
+          let existing_scope = allowed_scope_values(&self.command_scope, existing);
+          let windows = capability_windows(command, |selector| match selector {
+            WindowSelector::Label(pattern) => !grants_window(&existing.windows, pattern),
+            WindowSelector::Ancestor(ancestor) => !existing.window_ancestors.contains(ancestor),
+            WindowSelector::Webview(webview) => !existing.webviews.contains(webview),
+          });
+          let scope = scope
+            .into_iter()
+            .filter(|value| !existing_scope.contains(value))
+            .collect::<Vec<_>>();
+
+          if !windows.is_empty() || !scope.is_empty() {
+            delta.widened_commands.push(CommandDelta {
+              command: key.name.clone(),
+              remote,
+              windows,
+              scope,
+            });
+          }
+        }
+      }
     }
-  }
 
-  impl ToTokens for ResolvedCommand {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-      #[cfg(debug_assertions)]
-      let referenced_by = vec_lit(&self.referenced_by, identity);
+    for (plugin, scopes) in &simulated.global_scope {
+      let scope = flatten_global_scope(scopes);
+      let existing = self
+        .global_scope
+        .get(plugin)
+        .map(|existing| flatten_global_scope(existing));
+      let mut added = Vec::new();
+      for value in &scope.allow {
+        if !existing
+          .as_ref()
+          .is_some_and(|existing| existing.allow.contains(value))
+          && !added.contains(value)
+        {
+          added.push(value.clone());
+        }
+      }
+      if !added.is_empty() {
+        delta.global_scopes.insert(plugin.clone(), added);
+      }
+    }
 
-      let windows = vec_lit(&self.windows, |window| {
-        let w = window.as_str();
-        quote!(#w.parse().unwrap())
-      });
-      let scope = opt_lit(self.scope.as_ref());
+    Ok(delta)
+  }
 
-      #[cfg(debug_assertions)]
+  /// Removes the data that does not change the authorization of the IPC requests.
+  ///
+  /// Strips the command references and the plugin manifests,
+  /// drops the allowed commands that do not match any window and the command scopes they no longer reference.
+  pub fn minimize(&mut self) {
+    #[cfg(debug_assertions)]
+    {
+      self.acl.clear();
+      for command in self
+        .allowed_commands
+        .values_mut()
+        .chain(self.denied_commands.values_mut())
       {
-        literal_struct!(
-          tokens,
-          ::tauri::utils::acl::resolved::ResolvedCommand,
-          referenced_by,
-          windows,
-          scope
-        )
+        command.referenced_by.clear();
       }
-      #[cfg(not(debug_assertions))]
-      literal_struct!(
-        tokens,
-        ::tauri::utils::acl::resolved::ResolvedCommand,
-        windows,
-        scope
-      )
     }
-  }
 
-  impl ToTokens for ResolvedScope {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-      let allow = vec_lit(&self.allow, identity);
-      let deny = vec_lit(&self.deny, identity);
-      literal_struct!(
-        tokens,
-        ::tauri::utils::acl::resolved::ResolvedScope,
-        allow,
-        deny
-      )
+    // denied commands apply to every window, so only the allowed ones can be dropped
+    self.allowed_commands.retain(|_, command| {
+      !(command.windows.is_empty()
+        && command.window_ancestors.is_empty()
+        && command.webviews.is_empty())
+    });
+
+    let scopes = self
+      .allowed_commands
+      .values()
+      .chain(self.denied_commands.values())
+      .flat_map(|command| command.scopes.iter().map(|scope| scope.scope))
+      .collect::<BTreeSet<_>>();
+    self.command_scope.retain(|key, _| scopes.contains(key));
+
+    for grant in &mut self.role_grants {
+      grant.acl.minimize();
     }
   }
 
-  impl ToTokens for Resolved {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-      #[cfg(debug_assertions)]
-      let acl = map_lit(
-        quote! { ::std::collections::BTreeMap },
-        &self.acl,
-        str_lit,
-        identity,
-      );
+  /// Grants the webviews the commands, scopes and configurations granted to their window by a label pattern,
+  /// without the commands and scope values removed by the attenuation.
+  ///
+  /// The attenuated scopes are stored under a new key, as the window keeps the original ones.
+  fn inherit_window_grants(&mut self, webviews: &[WebviewPattern], attenuation: &Attenuation) {
+    let Self {
+      allowed_commands,
+      command_scope,
+      global_scope,
+      inheriting_webviews,
+      ..
+    } = self;
 
-      let allowed_commands = map_lit(
-        quote! { ::std::collections::BTreeMap },
-        &self.allowed_commands,
-        identity,
-        identity,
-      );
+    for webview in webviews {
+      if !inheriting_webviews.contains(webview) {
+        inheriting_webviews.push(webview.clone());
+      }
+    }
 
-      let denied_commands = map_lit(
-        quote! { ::std::collections::BTreeMap },
-        &self.denied_commands,
-        identity,
-        identity,
-      );
+    let mut attenuated_keys = BTreeMap::new();
+    for (key, command) in allowed_commands {
+      if attenuation.denies_command(&key.name) {
+        continue;
+      }
 
-      let command_scope = map_lit(
-        quote! { ::std::collections::BTreeMap },
-        &self.command_scope,
-        identity,
-        identity,
-      );
+      for webview in webviews {
+        // the grants restricted to some pages are inherited on the same pages
+        let inherited_pages = command
+          .pages
+          .iter()
+          .filter(|grant| grants_window(&grant.windows, &webview.window))
+          .map(|grant| PageGrant {
+            windows: Vec::new(),
+            window_ancestors: Vec::new(),
+            webviews: vec![webview.clone()],
+            pages: grant.pages.clone(),
+          })
+          .collect::<Vec<_>>();
+        let inherits_window = grants_window(&command.windows, &webview.window);
+        if !inherits_window && inherited_pages.is_empty() {
+          continue;
+        }
+        if inherits_window && !command.webviews.contains(webview) {
+          command.webviews.push(webview.clone());
+        }
+        for grant in inherited_pages {
+          if !command.pages.contains(&grant) {
+            command.pages.push(grant);
+          }
+        }
+
+        let inherited_scopes = command
+          .scopes
+          .iter()
+          .filter(|scope| grants_window(&scope.windows, &webview.window))
+          .map(|scope| (scope.pages.clone(), scope.scope))
+          .collect::<Vec<_>>();
+        for (pages, scope_key) in inherited_scopes {
+          let scope = attenuated_scope(command_scope, &mut attenuated_keys, scope_key, attenuation);
+          command.scopes.push(WindowScope {
+            windows: Vec::new(),
+            window_ancestors: Vec::new(),
+            webviews: vec![webview.clone()],
+            pages,
+            scope,
+          });
+        }
+
+        let inherited_configs = command
+          .configs
+          .iter()
+          .filter(|config| grants_window(&config.windows, &webview.window))
+          .map(|config| WindowConfig {
+            capability: config.capability.clone(),
+            windows: Vec::new(),
+            window_ancestors: Vec::new(),
+            webviews: vec![webview.clone()],
+            pages: config.pages.clone(),
+            config: config.config.clone(),
+          })
+          .collect::<Vec<_>>();
+        command.configs.extend(inherited_configs);
+      }
+    }
+
+    for window_scopes in global_scope.values_mut() {
+      let inherited_scopes = webviews
+        .iter()
+        .flat_map(|webview| {
+          window_scopes
+            .iter()
+            .filter(|window_scope| grants_window(&window_scope.windows, &webview.window))
+            .map(|window_scope| WindowGlobalScope {
+              windows: Vec::new(),
+              window_ancestors: Vec::new(),
+              webviews: vec![webview.clone()],
+              pages: window_scope.pages.clone(),
+              scope: ResolvedScope {
+                allow: window_scope
+                  .scope
+                  .allow
+                  .iter()
+                  .filter(|value| attenuation.keeps_scope_value(value))
+                  .cloned()
+                  .collect(),
+                deny: window_scope.scope.deny.clone(),
+                external: None,
+              },
+            })
+        })
+        .collect::<Vec<_>>();
+      window_scopes.extend(inherited_scopes);
+    }
+  }
+
+  /// The remote domains the allowed commands can be invoked from, along with the windows they are granted on.
+  pub fn remote_origins(&self) -> Vec<RemoteOrigin> {
+    remote_origins(&self.allowed_commands)
+  }
+
+  /// Moves the values of the scopes of the given plugins out of the resolved ACL,
+  /// leaving a reference to them in each scope, see [`Manifest#structfield.external_scope`].
+  ///
+  /// The keys only depend on the scope values, so the store of a previous resolution of the same ACL,
+  /// e.g. [`ResolutionReport#structfield.external_scopes`], has the values of the returned references.
+  pub fn externalize_scopes(&mut self, plugins: &BTreeSet<String>) -> ExternalScopes {
+    let mut external_scopes = ExternalScopes::default();
+    self.for_each_plugin_scope(plugins, |plugin, scope| {
+      external_scopes.externalize(plugin, scope)
+    });
+    for grant in &mut self.role_grants {
+      external_scopes.extend(grant.acl.externalize_scopes(plugins));
+    }
+    external_scopes
+  }
+
+  /// Visits the command and global scopes of the given plugins.
+  fn for_each_plugin_scope(
+    &mut self,
+    plugins: &BTreeSet<String>,
+    mut visit: impl FnMut(&str, &mut ResolvedScope),
+  ) {
+    if plugins.is_empty() {
+      return;
+    }
+
+    let command_scopes = self
+      .allowed_commands
+      .iter()
+      .chain(&self.denied_commands)
+      .filter_map(|(key, command)| {
+        let id = CommandId::parse(&key.name).ok()?;
+        plugins
+          .contains(id.plugin())
+          .then(|| (id.plugin().to_string(), command))
+      })
+      .flat_map(|(plugin, command)| {
+        command
+          .scopes
+          .iter()
+          .map(move |scope| (scope.scope, plugin.clone()))
+      })
+      .collect::<BTreeMap<_, _>>();
+    for (key, plugin) in command_scopes {
+      if let Some(scope) = self.command_scope.get_mut(&key) {
+        visit(&plugin, scope);
+      }
+    }
+
+    for (plugin, window_scopes) in &mut self.global_scope {
+      if plugins.contains(plugin) {
+        for window_scope in window_scopes {
+          visit(plugin, &mut window_scope.scope);
+        }
+      }
+    }
+  }
+
+  /// Applies the scopes rewritten by the [`ScopeHooks`] of a previous resolution of the same ACL.
+  pub fn apply_scope_overrides(&mut self, overrides: &ScopeOverrides) {
+    let mut command_scope = BTreeMap::new();
+    let mut rewritten_keys = BTreeMap::new();
+
+    for (key, command) in &mut self.allowed_commands {
+      for window_scope in &mut command.scopes {
+        let scope_key = window_scope.scope;
+
+        let rewritten = CommandId::parse(&key.name).ok().and_then(|id| {
+          overrides
+            .commands
+            .get(id.plugin())?
+            .get(id.command())?
+            .get(&scope_key)
+            .map(|scope| (id, scope))
+        });
+
+        if let Some((id, scope)) = rewritten {
+          // the scope might be shared with other commands, so the rewritten scope gets its own key
+          let rewritten_key = match rewritten_keys.get(&(id.clone(), scope_key)) {
+            Some(&rewritten_key) => rewritten_key,
+            None => {
+              let rewritten_key = unused_scope_key(|key| {
+                self.command_scope.contains_key(key) || command_scope.contains_key(key)
+              });
+              rewritten_keys.insert((id, scope_key), rewritten_key);
+              command_scope.insert(rewritten_key, scope.clone());
+              rewritten_key
+            }
+          };
+
+          window_scope.scope = rewritten_key;
+        } else if let Some(scope) = self.command_scope.get(&scope_key) {
+          command_scope.insert(scope_key, scope.clone());
+        }
+      }
+    }
+
+    self.command_scope = command_scope;
+
+    for (plugin, scopes) in &overrides.global {
+      if let Some(global_scope) = self.global_scope.get_mut(plugin) {
+        for (window_scope, scope) in global_scope.iter_mut().zip(scopes) {
+          window_scope.scope = scope.clone();
+        }
+      }
+    }
+  }
+}
+
+/// The plugins whose scopes are stored out of the resolved ACL, see [`Manifest#structfield.external_scope`].
+pub fn external_scope_plugins(acl: &BTreeMap<String, Manifest>) -> BTreeSet<String> {
+  acl
+    .iter()
+    .filter(|(_, manifest)| manifest.external_scope)
+    .map(|(plugin, _)| plugin.clone())
+    .collect()
+}
+
+/// The plugins whose global scopes each plugin reads, see [`Manifest#structfield.shares_scope_with`].
+///
+/// The plugins not in the ACL are left out with a warning, as nothing can grant their global scope.
+fn shared_scopes(
+  acl: &BTreeMap<String, Manifest>,
+  report: &mut ResolutionReport,
+) -> BTreeMap<String, Vec<String>> {
+  let mut shared = BTreeMap::new();
+  for (plugin, manifest) in acl {
+    let mut plugins = Vec::new();
+    for shared_plugin in &manifest.shares_scope_with {
+      if shared_plugin == plugin {
+        continue;
+      }
+      if acl.contains_key(shared_plugin) {
+        plugins.push(shared_plugin.clone());
+      } else {
+        report.warn(
+          WARNING_UNKNOWN_SHARED_SCOPE,
+          format!("plugin {plugin} shares the scope of unknown plugin {shared_plugin}"),
+        );
+      }
+    }
+    if !plugins.is_empty() {
+      shared.insert(plugin.clone(), plugins);
+    }
+  }
+  shared
+}
+
+/// The remote domains of the given allowed commands, along with the windows they are granted on,
+/// see [`Resolved::remote_origins`].
+pub fn remote_origins(
+  allowed_commands: &BTreeMap<CommandKey, ResolvedCommand>,
+) -> Vec<RemoteOrigin> {
+  let mut origins: Vec<RemoteOrigin> = Vec::new();
+  for (key, command) in allowed_commands {
+    let ExecutionContext::Remote { domain, .. } = &key.context else {
+      continue;
+    };
+    let index = match origins.iter().position(|origin| origin.domain == *domain) {
+      Some(index) => index,
+      None => {
+        origins.push(RemoteOrigin {
+          domain: domain.clone(),
+          windows: Vec::new(),
+          window_ancestors: Vec::new(),
+          webviews: Vec::new(),
+        });
+        origins.len() - 1
+      }
+    };
+    let origin = &mut origins[index];
+    for window in &command.windows {
+      if !origin.windows.contains(window) {
+        origin.windows.push(window.clone());
+      }
+    }
+    for ancestor in &command.window_ancestors {
+      if !origin.window_ancestors.contains(ancestor) {
+        origin.window_ancestors.push(ancestor.clone());
+      }
+    }
+    for webview in &command.webviews {
+      if !origin.webviews.contains(webview) {
+        origin.webviews.push(webview.clone());
+      }
+    }
+  }
+  origins
+}
+
+/// Builds a [`Resolved`] ACL at runtime, see [`Resolved::builder`].
+///
+/// The window label patterns are validated when the commands are added.
+/// Adding a command twice extends its windows and scopes.
+///
+/// # Examples
+///
+/// ```
+/// use tauri_utils::acl::{resolved::{Resolved, ResolvedScope}, ExecutionContext};
+///
+/// let resolved = Resolved::builder()
+///   .allow_command("plugin:fs|read_file", ExecutionContext::Local, ["main", "editor-*"])?
+///   .deny_command("plugin:fs|read_file", ExecutionContext::Local, ["editor-untrusted"])?
+///   .allow_command_scoped(
+///     "plugin:fs|write_file",
+///     ExecutionContext::Local,
+///     ["main"],
+///     ResolvedScope {
+///       allow: vec![serde_json::json!({ "path": "$APPDATA/*" }).into()],
+///       deny: Vec::new(),
+///       external: None,
+///     },
+///   )?
+///   .build();
+/// assert_eq!(resolved.allowed_commands.len(), 2);
+/// # Ok::<(), tauri_utils::acl::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct ResolvedBuilder {
+  resolved: Resolved,
+}
+
+impl ResolvedBuilder {
+  /// Allows the command on the windows matching the label patterns.
+  ///
+  /// The command is the full command name, e.g. `plugin:fs|read_file` for plugin commands.
+  pub fn allow_command<I, S>(
+    mut self,
+    command: impl Into<String>,
+    context: ExecutionContext,
+    windows: I,
+  ) -> Result<Self, Error>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    let key = CommandKey {
+      name: command.into(),
+      context,
+    };
+    let windows = validate_window_patterns(&key.name, windows)?;
+    extend_windows(
+      self.resolved.allowed_commands.entry(key).or_default(),
+      windows,
+    );
+    Ok(self)
+  }
+
+  /// Allows the command on the windows matching the label patterns with the given scope,
+  /// which only applies to those windows.
+  pub fn allow_command_scoped<I, S>(
+    mut self,
+    command: impl Into<String>,
+    context: ExecutionContext,
+    windows: I,
+    scope: ResolvedScope,
+  ) -> Result<Self, Error>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    let key = CommandKey {
+      name: command.into(),
+      context,
+    };
+    let windows = validate_window_patterns(&key.name, windows)?;
+
+    let scope_key = self
+      .resolved
+      .command_scope
+      .keys()
+      .next_back()
+      .map_or(0, |key| key + 1);
+    self.resolved.command_scope.insert(scope_key, scope);
+
+    let resolved = self.resolved.allowed_commands.entry(key).or_default();
+    resolved.scopes.push(WindowScope {
+      windows: windows.clone(),
+      window_ancestors: Vec::new(),
+      webviews: Vec::new(),
+      pages: Vec::new(),
+      scope: scope_key,
+    });
+    extend_windows(resolved, windows);
+    Ok(self)
+  }
+
+  /// Allows the local command on the windows matching the label patterns while they are on one of the pages,
+  /// see [`Capability#structfield.pages`].
+  pub fn allow_command_on_pages<I, S, P>(
+    mut self,
+    command: impl Into<String>,
+    windows: I,
+    pages: P,
+  ) -> Result<Self, Error>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+    P: IntoIterator<Item = S>,
+  {
+    let key = CommandKey {
+      name: command.into(),
+      context: ExecutionContext::Local,
+    };
+    let windows = validate_window_patterns(&key.name, windows)?;
+    let pages = pages
+      .into_iter()
+      .map(|page| Pattern::new(page.as_ref()))
+      .collect::<Result<Vec<_>, _>>()?;
+    let grant = PageGrant {
+      windows,
+      window_ancestors: Vec::new(),
+      webviews: Vec::new(),
+      pages,
+    };
+    let resolved = self.resolved.allowed_commands.entry(key).or_default();
+    if !resolved.pages.contains(&grant) {
+      resolved.pages.push(grant);
+    }
+    Ok(self)
+  }
+
+  /// Denies the command on the windows matching the label patterns.
+  pub fn deny_command<I, S>(
+    mut self,
+    command: impl Into<String>,
+    context: ExecutionContext,
+    windows: I,
+  ) -> Result<Self, Error>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    let key = CommandKey {
+      name: command.into(),
+      context,
+    };
+    let windows = validate_window_patterns(&key.name, windows)?;
+    extend_windows(
+      self.resolved.denied_commands.entry(key).or_default(),
+      windows,
+    );
+    Ok(self)
+  }
+
+  /// Adds the scope to the global scope of the plugin on every window.
+  #[must_use]
+  pub fn global_scope(self, plugin: impl Into<String>, scope: ResolvedScope) -> Self {
+    self
+      .window_global_scope(plugin, ["*"], scope)
+      .expect("the wildcard window pattern is valid")
+  }
+
+  /// Adds the scope to the global scope of the plugin on the windows matching the label patterns.
+  pub fn window_global_scope<I, S>(
+    mut self,
+    plugin: impl Into<String>,
+    windows: I,
+    scope: ResolvedScope,
+  ) -> Result<Self, Error>
+  where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    let plugin = plugin.into();
+    let windows = validate_window_patterns(&format!("{plugin} global scope"), windows)?;
+    let scopes = self.resolved.global_scope.entry(plugin).or_default();
+    match scopes.iter_mut().find(|window_scope| {
+      window_scope.windows == windows
+        && window_scope.webviews.is_empty()
+        && window_scope.pages.is_empty()
+    }) {
+      Some(window_scope) => window_scope.scope += &scope,
+      None => scopes.push(WindowGlobalScope {
+        windows,
+        scope,
+        ..Default::default()
+      }),
+    }
+    Ok(self)
+  }
+
+  /// Lets the plugin read the global scope of another plugin,
+  /// see [`Manifest#structfield.shares_scope_with`].
+  #[must_use]
+  pub fn share_scope(mut self, plugin: impl Into<String>, with: impl Into<String>) -> Self {
+    let plugins = self
+      .resolved
+      .shares_scope_with
+      .entry(plugin.into())
+      .or_default();
+    let with = with.into();
+    if !plugins.contains(&with) {
+      plugins.push(with);
+    }
+    self
+  }
+
+  /// Rejects every invoke from a remote origin, like the [`LOCKDOWN_REMOTE_CAPABILITY`].
+  #[must_use]
+  pub fn lockdown_remote(mut self) -> Self {
+    self.resolved.lockdown_remote = true;
+    self
+  }
+
+  /// Builds the resolved ACL.
+  pub fn build(self) -> Resolved {
+    self.resolved
+  }
+}
+
+fn validate_window_patterns<I, S>(command: &str, windows: I) -> Result<Vec<WindowPattern>, Error>
+where
+  I: IntoIterator<Item = S>,
+  S: AsRef<str>,
+{
+  windows
+    .into_iter()
+    .map(|window| {
+      let window = window.as_ref();
+      WindowPattern::new(&normalize_label(window)).map_err(|error| {
+        Error::InvalidCommandWindowPattern {
+          command: command.into(),
+          window: window.into(),
+          error,
+        }
+      })
+    })
+    .collect()
+}
+
+fn extend_windows(command: &mut ResolvedCommand, windows: Vec<WindowPattern>) {
+  for window in windows {
+    if !command.windows.contains(&window) {
+      command.windows.push(window);
+    }
+  }
+}
+
+impl Resolved {
+  /// Runs the scope hooks, keeping the [required deny entries](Manifest#structfield.required_deny) of the plugins.
+  fn run_scope_hooks(
+    &mut self,
+    hooks: &ScopeHooks,
+    required_deny: &BTreeMap<String, Vec<Value>>,
+  ) -> Result<ScopeOverrides, Error> {
+    let mut overrides = ScopeOverrides::default();
+
+    for (key, command) in &self.allowed_commands {
+      let Ok(id) = CommandId::parse(&key.name) else {
+        continue;
+      };
+      let (plugin, name) = (id.plugin(), id.command());
+      let Some(hook) = hooks.0.get(plugin) else {
+        continue;
+      };
+
+      for scope_key in command.scopes.iter().map(|scope| scope.scope) {
+        let command_overrides = overrides
+          .commands
+          .entry(plugin.to_string())
+          .or_default()
+          .entry(name.to_string())
+          .or_default();
+        if command_overrides.contains_key(&scope_key) {
+          continue;
+        }
+
+        let mut scope = self
+          .command_scope
+          .get(&scope_key)
+          .cloned()
+          .unwrap_or_default();
+        hook(plugin, Some(name), &mut scope).map_err(|error| Error::ScopeHook {
+          plugin: plugin.to_string(),
+          command: Some(name.to_string()),
+          error,
+        })?;
+        if let Some(required) = required_deny.get(plugin) {
+          require_deny(&mut scope, required);
+        }
+        command_overrides.insert(scope_key, scope);
+      }
+    }
+
+    for (plugin, window_scopes) in &self.global_scope {
+      if let Some(hook) = hooks.0.get(plugin) {
+        let scopes = window_scopes
+          .iter()
+          .map(|window_scope| {
+            let mut scope = window_scope.scope.clone();
+            hook(plugin, None, &mut scope).map_err(|error| Error::ScopeHook {
+              plugin: plugin.clone(),
+              command: None,
+              error,
+            })?;
+            if let Some(required) = required_deny.get(plugin) {
+              require_deny(&mut scope, required);
+            }
+            Ok(scope)
+          })
+          .collect::<Result<_, Error>>()?;
+        overrides.global.insert(plugin.clone(), scopes);
+      }
+    }
+
+    self.apply_scope_overrides(&overrides);
+
+    Ok(overrides)
+  }
+
+  /// Describes the commands whose allowed and denied window selectors may match the same window.
+  ///
+  /// Overlaps between label patterns are exact when one of them is a literal label,
+  /// other selectors are reported when they can possibly match the same window.
+  fn allow_deny_overlaps(&self) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    for (denied_key, denied) in &self.denied_commands {
+      let allowed = self.allowed_commands.iter().filter(|(key, _)| {
+        key.name == denied_key.name && contexts_overlap(&key.context, &denied_key.context)
+      });
+      for (allowed_key, allowed) in allowed {
+        let pairs = WindowSelector::of(allowed)
+          .flat_map(|allow| {
+            WindowSelector::of(denied)
+              .filter(move |deny| allow.overlaps(deny))
+              .map(move |deny| format!("allowed on {allow} and denied on {deny}"))
+          })
+          .collect::<Vec<_>>();
+
+        if !pairs.is_empty() {
+          let origin = match (&allowed_key.context, &denied_key.context) {
+            (
+              ExecutionContext::Remote { domain: allow, .. },
+              ExecutionContext::Remote { domain: deny, .. },
+            ) => {
+              format!(" for remote domains {allow} and {deny}")
+            }
+            _ => String::new(),
+          };
+          messages.push(format!(
+            "command {}{origin} is {}; the deny takes precedence at runtime, on every window",
+            denied_key.name,
+            pairs.join(", ")
+          ));
+        }
+      }
+    }
+
+    messages
+  }
+
+  fn grants(&self) -> BTreeMap<String, GrantCounts> {
+    let mut grants = BTreeMap::<String, GrantCounts>::new();
+
+    for (key, command) in &self.allowed_commands {
+      if let Ok(id) = CommandId::parse(&key.name) {
+        let counts = grants.entry(id.plugin().to_string()).or_default();
+        if command.via_default {
+          counts.via_default += 1;
+        } else {
+          counts.explicit += 1;
+        }
+      }
+    }
+
+    grants
+  }
+
+  fn scope_sizes(&self) -> BTreeMap<String, PluginScopeSizes> {
+    let mut sizes = BTreeMap::<String, PluginScopeSizes>::new();
+
+    for (key, command) in &self.allowed_commands {
+      let Ok(id) = CommandId::parse(&key.name) else {
+        continue;
+      };
+      let scopes = command
+        .scopes
+        .iter()
+        .filter_map(|scope| self.command_scope.get(&scope.scope));
+      for scope in scopes {
+        sizes
+          .entry(id.plugin().to_string())
+          .or_default()
+          .commands
+          .entry(id.command().to_string())
+          .or_default()
+          .add(ScopeSize::of(scope));
+      }
+    }
+
+    for (plugin, scopes) in &self.global_scope {
+      sizes.entry(plugin.clone()).or_default().global_scope =
+        ScopeSize::of(&flatten_global_scope(scopes));
+    }
+
+    sizes
+  }
+}
+
+/// Previews what a single capability grants, without the other capabilities of the app.
+///
+/// Resolves the capability alone like [`Resolved::simulate`] does. The permission entries that cannot be resolved,
+/// such as unknown permissions, are reported as [`CapabilityPreview#structfield.diagnostics`] and left out,
+/// so a capability being written still gets a partial preview.
+pub fn preview_capability(
+  capability: &Capability,
+  acl: &BTreeMap<String, Manifest>,
+  target: Target,
+) -> CapabilityPreview {
+  let mut preview = CapabilityPreview {
+    identifier: capability.identifier.clone(),
+    ..Default::default()
+  };
+
+  let resolved = match resolve_capability(acl, capability, target) {
+    Ok(resolved) => resolved,
+    Err(error) => {
+      // the capability itself is invalid, e.g. with an invalid window pattern
+      let mut empty = capability.clone();
+      empty.permissions.clear();
+      if let Err(error) = resolve_capability(acl, &empty, target) {
+        preview.diagnostics.push((&error).into());
+        return preview;
+      }
+
+      let mut resolvable = empty;
+      for entry in &capability.permissions {
+        let mut probe = resolvable.clone();
+        probe.permissions = vec![entry.clone()];
+        match resolve_capability(acl, &probe, target) {
+          Ok(_) => resolvable.permissions.push(entry.clone()),
+          Err(error) => preview.diagnostics.push((&error).into()),
+        }
+      }
+
+      match resolve_capability(acl, &resolvable, target) {
+        Ok(resolved) => resolved,
+        Err(_) => {
+          // the permissions only fail together, e.g. a subtraction without the permission it subtracts from
+          preview.diagnostics.push((&error).into());
+          return preview;
+        }
+      }
+    }
+  };
+  let (resolved, report) = resolved;
+
+  for (key, command) in &resolved.allowed_commands {
+    let scope = command
+      .scopes
+      .iter()
+      .filter_map(|scope| resolved.command_scope.get(&scope.scope))
+      .sum::<ResolvedScope>();
+    preview.commands.push(CommandPreview {
+      command: key.name.clone(),
+      remote: remote_domain(key),
+      windows: capability_windows(command, |_| true),
+      scope: scope.into(),
+    });
+  }
+  preview.denied_commands = resolved
+    .denied_commands
+    .keys()
+    .map(|key| key.name.clone())
+    .collect();
+  preview.global_scopes = resolved
+    .global_scope
+    .iter()
+    .map(|(plugin, scopes)| (plugin.clone(), flatten_global_scope(scopes).into()))
+    .collect();
+  preview.warnings = report.warnings;
+
+  preview
+}
+
+/// Resolves a single capability, the slice of the ACL shared by [`Resolved::simulate`] and [`preview_capability`].
+fn resolve_capability(
+  acl: &BTreeMap<String, Manifest>,
+  capability: &Capability,
+  target: Target,
+) -> Result<(Resolved, ResolutionReport), Error> {
+  Resolved::resolve_with_report(
+    acl.clone(),
+    [(capability.identifier.clone(), capability.clone())]
+      .into_iter()
+      .collect(),
+    target,
+  )
+}
+
+/// The remote domain pattern of a command key, [`None`] for the local app URL.
+fn remote_domain(key: &CommandKey) -> Option<String> {
+  match &key.context {
+    ExecutionContext::Local => None,
+    ExecutionContext::Remote { domain, .. } => Some(domain.as_str().to_string()),
+  }
+}
+
+/// The distinct values allowed by the scopes of a command.
+fn allowed_scope_values(
+  command_scope: &BTreeMap<ScopeKey, ResolvedScope>,
+  command: &ResolvedCommand,
+) -> Vec<Value> {
+  let mut values = Vec::new();
+  for scope in command
+    .scopes
+    .iter()
+    .filter_map(|scope| command_scope.get(&scope.scope))
+  {
+    for value in &scope.allow {
+      if !values.contains(value) {
+        values.push(value.clone());
+      }
+    }
+  }
+  values
+}
+
+/// Whether the label patterns grant the window pattern,
+/// i.e. one of them is the same pattern or matches it if it is a plain label.
+fn grants_window(patterns: &[WindowPattern], window: &WindowPattern) -> bool {
+  patterns
+    .iter()
+    .any(|pattern| pattern == window || (window.is_literal() && pattern.matches(window.as_str())))
+}
+
+/// The key of the scope with the allowed values filtered by the attenuation,
+/// inserting the filtered scope in the store if it differs from the original one.
+///
+/// `attenuated_keys` maps the original keys to the filtered ones already inserted for this attenuation.
+fn attenuated_scope(
+  command_scope: &mut BTreeMap<ScopeKey, ResolvedScope>,
+  attenuated_keys: &mut BTreeMap<ScopeKey, ScopeKey>,
+  key: ScopeKey,
+  attenuation: &Attenuation,
+) -> ScopeKey {
+  if let Some(&attenuated_key) = attenuated_keys.get(&key) {
+    return attenuated_key;
+  }
+  let Some(scope) = command_scope.get(&key) else {
+    return key;
+  };
+  if scope
+    .allow
+    .iter()
+    .all(|value| attenuation.keeps_scope_value(value))
+  {
+    return key;
+  }
+
+  let attenuated = ResolvedScope {
+    allow: scope
+      .allow
+      .iter()
+      .filter(|value| attenuation.keeps_scope_value(value))
+      .cloned()
+      .collect(),
+    deny: scope.deny.clone(),
+    external: None,
+  };
+  let attenuated_key = unused_scope_key(|key| command_scope.contains_key(key));
+  command_scope.insert(attenuated_key, attenuated);
+  attenuated_keys.insert(key, attenuated_key);
+  attenuated_key
+}
+
+/// The lowest scope key not used yet, the way [`merge`](super::merge) re-keys the merged scopes.
+fn unused_scope_key(is_used: impl Fn(&ScopeKey) -> bool) -> ScopeKey {
+  std::iter::successors(Some(0), |key: &ScopeKey| key.checked_add(1))
+    .find(|key| !is_used(key))
+    .expect("the scope keys are exhausted")
+}
+
+/// The window selectors of a command accepted by the filter, in the shape of [`Capability#structfield.windows`].
+fn capability_windows(
+  command: &ResolvedCommand,
+  filter: impl Fn(&WindowSelector<'_>) -> bool,
+) -> Vec<CapabilityWindow> {
+  WindowSelector::of(command)
+    .filter(|selector| filter(selector))
+    .map(|selector| match selector {
+      WindowSelector::Label(pattern) => CapabilityWindow::Label(pattern.as_str().to_string()),
+      WindowSelector::Ancestor(ancestor) if ancestor.parent_only => CapabilityWindow::ParentOf {
+        parent_of: ancestor.pattern.as_str().to_string(),
+      },
+      WindowSelector::Ancestor(ancestor) => CapabilityWindow::DescendantOf {
+        descendant_of: ancestor.pattern.as_str().to_string(),
+      },
+      WindowSelector::Webview(webview) => CapabilityWindow::Webview {
+        webview: webview.webview.as_str().to_string(),
+        window: webview.window.as_str().to_string(),
+      },
+    })
+    .collect()
+}
+
+/// Removes the subtracted values from the allowed values of the scopes,
+/// collecting a message for each subtracted value that none of them allow.
+fn subtract_scope_values(
+  scopes: Vec<(&Grantees, &mut ResolvedScope)>,
+  subtraction: Vec<Subtraction>,
+  target: &str,
+  unused: &mut BTreeSet<String>,
+) {
+  for Subtraction {
+    value,
+    permission,
+    grantees,
+  } in &subtraction
+  {
+    if !scopes
+      .iter()
+      .any(|(windows, scope)| *windows == grantees && scope.allow.contains(value))
+    {
+      unused.insert(format!(
+        "scope value {} subtracted from {target} by {permission} is not allowed by any permission",
+        serde_json::to_string(value).unwrap_or_default()
+      ));
+    }
+  }
+  for (windows, scope) in scopes {
+    scope.allow.retain(|value| {
+      !subtraction
+        .iter()
+        .any(|s| s.grantees == *windows && s.value == *value)
+    });
+  }
+}
+
+/// Adds the [required deny entries](Manifest#structfield.required_deny) missing from the scope.
+fn require_deny(scope: &mut ResolvedScope, required: &[Value]) {
+  for value in required {
+    if !scope.deny.contains(value) {
+      scope.deny.push(value.clone());
+    }
+  }
+}
+
+/// Builds the [`CommandKey::name`] of a plugin command.
+fn command_name(plugin: &str, command: &str, permission: &Permission) -> Result<String, Error> {
+  CommandId::new(plugin, command)
+    .map(|id| id.to_string())
+    .map_err(|error| Error::InvalidCommand {
+      permission: permission.identifier.clone(),
+      command: command.to_string(),
+      error,
+    })
+}
+
+/// Resolves the command names of a permission, replacing the aliases with their canonical command
+/// and expanding the glob patterns against the commands referenced by the plugin permissions.
+fn command_names(
+  plugin: &str,
+  commands: &[String],
+  permission: &Permission,
+  manifest: &Manifest,
+) -> Result<Vec<String>, Error> {
+  let mut names = Vec::new();
+  let push = |names: &mut Vec<String>, command: &str| -> Result<(), Error> {
+    let name = command_name(plugin, manifest.canonical_command(command), permission)?;
+    if !names.contains(&name) {
+      names.push(name);
+    }
+    Ok(())
+  };
+
+  for command in commands {
+    if is_command_pattern(command) {
+      let pattern = glob::Pattern::new(command).map_err(|error| Error::InvalidCommandPattern {
+        permission: permission.identifier.clone(),
+        command: command.clone(),
+        error,
+      })?;
+      let matches = manifest
+        .commands()
+        .into_iter()
+        .filter(|c| pattern.matches(c))
+        .collect::<Vec<_>>();
+      if matches.is_empty() {
+        return Err(Error::UnmatchedCommandPattern {
+          plugin: plugin.to_string(),
+          permission: permission.identifier.clone(),
+          command: command.clone(),
+        });
+      }
+      for command in matches {
+        push(&mut names, command)?;
+      }
+    } else {
+      push(&mut names, command)?;
+    }
+  }
+
+  Ok(names)
+}
+
+fn parse_window_patterns(windows: &BTreeSet<CapabilityWindow>) -> Vec<WindowPattern> {
+  windows
+    .iter()
+    .filter_map(|window| match window {
+      CapabilityWindow::Label(label) => Some(parse_window_pattern(label)),
+      _ => None,
+    })
+    .collect()
+}
+
+fn parse_window_ancestors(windows: &BTreeSet<CapabilityWindow>) -> Vec<WindowAncestor> {
+  windows
+    .iter()
+    .filter_map(|window| match window {
+      CapabilityWindow::Label(_)
+      | CapabilityWindow::PluginWindows { .. }
+      | CapabilityWindow::Webview { .. } => None,
+      CapabilityWindow::ParentOf { parent_of } => Some(WindowAncestor {
+        pattern: parse_window_pattern(parent_of),
+        parent_only: true,
+      }),
+      CapabilityWindow::DescendantOf { descendant_of } => Some(WindowAncestor {
+        pattern: parse_window_pattern(descendant_of),
+        parent_only: false,
+      }),
+    })
+    .collect()
+}
+
+fn parse_webview_patterns(windows: &BTreeSet<CapabilityWindow>) -> Vec<WebviewPattern> {
+  windows
+    .iter()
+    .filter_map(|window| match window {
+      CapabilityWindow::Webview { webview, window } => Some(WebviewPattern {
+        window: parse_window_pattern(window),
+        webview: parse_window_pattern(webview),
+      }),
+      _ => None,
+    })
+    .collect()
+}
+
+fn parse_page_patterns(pages: &BTreeSet<String>) -> Vec<Pattern> {
+  pages
+    .iter()
+    .map(|page| Pattern::new(page).expect("page patterns are validated per capability"))
+    .collect()
+}
+
+fn page_grants(
+  page_windows: &BTreeMap<BTreeSet<String>, BTreeSet<CapabilityWindow>>,
+) -> Vec<PageGrant> {
+  page_windows
+    .iter()
+    .map(|(pages, windows)| PageGrant {
+      windows: parse_window_patterns(windows),
+      window_ancestors: parse_window_ancestors(windows),
+      webviews: parse_webview_patterns(windows),
+      pages: parse_page_patterns(pages),
+    })
+    .collect()
+}
+
+fn window_scopes(scopes: &[(Grantees, ScopeKey)]) -> Vec<WindowScope> {
+  scopes
+    .iter()
+    .map(|((windows, pages), scope)| WindowScope {
+      windows: parse_window_patterns(windows),
+      window_ancestors: parse_window_ancestors(windows),
+      webviews: parse_webview_patterns(windows),
+      pages: parse_page_patterns(pages),
+      scope: *scope,
+    })
+    .collect()
+}
+
+fn window_configs(configs: Vec<(String, Grantees, Value)>) -> Vec<WindowConfig> {
+  configs
+    .into_iter()
+    .map(|(capability, (windows, pages), config)| WindowConfig {
+      capability,
+      windows: parse_window_patterns(&windows),
+      window_ancestors: parse_window_ancestors(&windows),
+      webviews: parse_webview_patterns(&windows),
+      pages: parse_page_patterns(&pages),
+      config,
+    })
+    .collect()
+}
+
+/// Deep-merges the scope values that share the same value on the merge key,
+/// see [`Manifest#structfield.merge_key`].
+///
+/// The merged entry keeps the position of the first value, later values win per field.
+fn merge_scope_values(
+  values: impl IntoIterator<Item = Value>,
+  merge_key: Option<&str>,
+) -> Vec<Value> {
+  let Some(merge_key) = merge_key else {
+    return values.into_iter().collect();
+  };
+
+  let key_of = |value: &Value| match value {
+    Value::Map(map) => map.get(merge_key).cloned(),
+    _ => None,
+  };
+
+  let mut merged: Vec<Value> = Vec::new();
+  for value in values {
+    let existing = key_of(&value).and_then(|key| {
+      merged
+        .iter_mut()
+        .find(|entry| key_of(entry).as_ref() == Some(&key))
+    });
+    match existing {
+      Some(entry) => entry.deep_merge(value),
+      None => merged.push(value),
+    }
+  }
+  merged
+}
+
+/// Replaces the [`CapabilityWindow::PluginWindows`] selectors of the capability
+/// with the window label patterns declared by the plugins.
+fn expand_plugin_windows<'a>(
+  capability: &'a Capability,
+  acl: &BTreeMap<String, Manifest>,
+) -> Result<Cow<'a, Capability>, Error> {
+  if !capability
+    .windows
+    .iter()
+    .any(|window| matches!(window, CapabilityWindow::PluginWindows { .. }))
+  {
+    return Ok(Cow::Borrowed(capability));
+  }
+
+  let mut windows = Vec::new();
+  for window in &capability.windows {
+    let CapabilityWindow::PluginWindows { plugin_windows } = window else {
+      windows.push(window.clone());
+      continue;
+    };
+
+    let manifest = acl
+      .get(plugin_windows)
+      .filter(|manifest| !manifest.window_prefixes.is_empty())
+      .ok_or_else(|| Error::UnknownPluginWindows {
+        capability: capability.identifier.clone(),
+        plugin: plugin_windows.clone(),
+        plugin_exists: acl.contains_key(plugin_windows),
+      })?;
+    windows.extend(manifest.window_patterns().map(CapabilityWindow::Label));
+  }
+
+  Ok(Cow::Owned(Capability {
+    windows,
+    ..capability.clone()
+  }))
+}
+
+fn parse_window_pattern(window: &str) -> WindowPattern {
+  WindowPattern::new(&normalize_label(window))
+    .expect("window patterns are validated per capability")
+}
+
+/// The windows and local pages of a capability, see [`Capability#structfield.pages`].
+type Grantees = (BTreeSet<CapabilityWindow>, BTreeSet<String>);
+
+fn grantees(capability: &Capability) -> Grantees {
+  (
+    capability.windows.iter().cloned().collect(),
+    capability.pages.iter().cloned().collect(),
+  )
+}
+
+/// A scope value subtracted by a capability, only from the scopes of the same windows and pages.
+#[derive(Clone)]
+struct Subtraction {
+  value: Value,
+  permission: String,
+  grantees: Grantees,
+}
+
+#[derive(Debug, Default)]
+struct ResolvedCommandTemp {
+  #[cfg(debug_assertions)]
+  pub referenced_by: Vec<ResolvedCommandReference>,
+  pub windows: BTreeSet<CapabilityWindow>,
+  /// The windows granted the command on some pages only, grouped by pages.
+  pub page_windows: BTreeMap<BTreeSet<String>, BTreeSet<CapabilityWindow>>,
+  /// The scopes granted to each set of capability windows and pages.
+  pub scopes: BTreeMap<Grantees, Vec<usize>>,
+  pub resolved_scopes: Vec<(Grantees, ScopeKey)>,
+  pub classes: BTreeSet<String>,
+  pub validators: BTreeSet<String>,
+  pub args: Vec<ArgumentRule>,
+  /// The capabilities and permissions allowing this command with a required scope, with the capability windows.
+  pub requires_scope: Vec<(String, String, BTreeSet<CapabilityWindow>)>,
+  /// Whether a permission allows this command through a `default` permission set.
+  pub via_default: bool,
+  /// Whether a permission allows this command without going through a `default` permission set.
+  pub explicit: bool,
+  /// The capability identifier, windows and pages, and configuration payload of the entries allowing this command with a config.
+  pub configs: Vec<(String, Grantees, Value)>,
+}
+
+/// A window selector of a [`ResolvedCommand`], see [`CapabilityWindow`].
+#[derive(Clone, Copy)]
+enum WindowSelector<'a> {
+  Label(&'a WindowPattern),
+  Ancestor(&'a WindowAncestor),
+  Webview(&'a WebviewPattern),
+}
+
+impl<'a> WindowSelector<'a> {
+  fn of(command: &'a ResolvedCommand) -> impl Iterator<Item = Self> + Clone + 'a {
+    command
+      .windows
+      .iter()
+      .map(Self::Label)
+      .chain(command.window_ancestors.iter().map(Self::Ancestor))
+      .chain(command.webviews.iter().map(Self::Webview))
+  }
+
+  fn overlaps(&self, other: &Self) -> bool {
+    match (self, other) {
+      (Self::Label(a), Self::Label(b)) => patterns_overlap(a.as_str(), b.as_str()),
+      (Self::Ancestor(a), Self::Ancestor(b)) => {
+        patterns_overlap(a.pattern.as_str(), b.pattern.as_str())
+      }
+      (Self::Webview(a), Self::Webview(b)) => {
+        patterns_overlap(a.window.as_str(), b.window.as_str())
+          && patterns_overlap(a.webview.as_str(), b.webview.as_str())
+      }
+      // label patterns select a webview by its window label or its own label
+      (Self::Label(label), Self::Webview(webview))
+      | (Self::Webview(webview), Self::Label(label)) => {
+        patterns_overlap(label.as_str(), webview.window.as_str())
+          || patterns_overlap(label.as_str(), webview.webview.as_str())
+      }
+      // a label pattern and an ancestor pattern select unrelated windows
+      _ => true,
+    }
+  }
+}
+
+impl fmt::Display for WindowSelector<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Label(pattern) => write!(f, "`{pattern}`"),
+      Self::Ancestor(ancestor) if ancestor.parent_only => {
+        write!(f, "children of `{}`", ancestor.pattern)
+      }
+      Self::Ancestor(ancestor) => write!(f, "descendants of `{}`", ancestor.pattern),
+      Self::Webview(webview) => {
+        write!(f, "webviews `{}` of `{}`", webview.webview, webview.window)
+      }
+    }
+  }
+}
+
+/// Whether two glob or template patterns can match the same string.
+///
+/// Exact when one of the patterns is a literal, otherwise only the literal prefixes
+/// and suffixes of the patterns are compared, so disjoint patterns may be reported as overlapping.
+fn patterns_overlap(a: &str, b: &str) -> bool {
+  const META: [char; 4] = ['*', '?', '[', '{'];
+
+  if !a.contains(META) {
+    return WindowPattern::new(b).is_ok_and(|b| b.matches(a));
+  }
+  if !b.contains(META) {
+    return WindowPattern::new(a).is_ok_and(|a| a.matches(b));
+  }
+
+  fn prefix(pattern: &str) -> &str {
+    &pattern[..pattern.find(META).unwrap_or(pattern.len())]
+  }
+  fn suffix(pattern: &str) -> &str {
+    &pattern[pattern.rfind(['*', '?', ']', '}']).map_or(0, |i| i + 1)..]
+  }
+
+  let (prefix_a, prefix_b) = (prefix(a), prefix(b));
+  let (suffix_a, suffix_b) = (suffix(a), suffix(b));
+  (prefix_a.starts_with(prefix_b) || prefix_b.starts_with(prefix_a))
+    && (suffix_a.ends_with(suffix_b) || suffix_b.ends_with(suffix_a))
+}
+
+fn contexts_overlap(a: &ExecutionContext, b: &ExecutionContext) -> bool {
+  match (a, b) {
+    (ExecutionContext::Local, ExecutionContext::Local) => true,
+    (ExecutionContext::Remote { domain: a, .. }, ExecutionContext::Remote { domain: b, .. }) => {
+      patterns_overlap(a.as_str(), b.as_str())
+    }
+    _ => false,
+  }
+}
+
+/// Validates the capability context and expands it to the execution contexts it applies to.
+fn capability_contexts(
+  capability: &Capability,
+  report: &mut ResolutionReport,
+) -> Result<Vec<ExecutionContext>, Error> {
+  match &capability.context {
+    CapabilityContext::Local => Ok(vec![ExecutionContext::Local]),
+    CapabilityContext::Remote {
+      domains,
+      attestation,
+    } => {
+      attestation
+        .validate()
+        .map_err(|reason| Error::InvalidRemoteAttestation {
+          capability: capability.identifier.clone(),
+          reason,
+        })?;
+      let attestation = (!attestation.is_empty()).then(|| attestation.clone());
+
+      let mut contexts = Vec::new();
+      for domain in domains.iter().flat_map(|d| d.patterns()) {
+        let invalid = |reason: &str| Error::InvalidRemoteDomain {
+          capability: capability.identifier.clone(),
+          domain: domain.clone(),
+          reason: reason.into(),
+        };
+
+        if domain.is_empty() {
+          return Err(invalid("the domain cannot be empty"));
+        }
+        if domain.contains("**") {
+          return Err(invalid(
+            "`**` is not a valid domain wildcard, use `*` to match any sequence of characters",
+          ));
+        }
+        if domain.contains(['/', ':']) || domain.contains(char::is_whitespace) {
+          return Err(invalid(
+            "expected a domain without scheme, port, path or whitespace",
+          ));
+        }
+        if domain == "*" {
+          report.warn(
+            WARNING_REMOTE_MATCHES_ALL,
+            format!(
+              "capability {} grants its permissions to every remote domain",
+              capability.identifier
+            ),
+          );
+        }
+
+        contexts.push(ExecutionContext::Remote {
+          domain: Pattern::new(&domain).map_err(|e| invalid(&e.to_string()))?,
+          attestation: attestation.clone(),
+        });
+      }
+      Ok(contexts)
+    }
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn resolve_command(
+  commands: &mut BTreeMap<CommandKey, ResolvedCommandTemp>,
+  command: String,
+  capability: &Capability,
+  contexts: &[ExecutionContext],
+  scope_id: Option<usize>,
+  expanded: &ExpandedPermission<'_>,
+  permission_entry: &PermissionEntry,
+  deny: bool,
+) {
+  let permission = expanded.permission;
+  let config = match permission_entry {
+    PermissionEntry::ExtendedPermission { config, .. } => config.as_ref(),
+    PermissionEntry::PermissionRef(_) => None,
+  };
+  for context in contexts {
+    let resolved = commands
+      .entry(CommandKey {
+        name: command.clone(),
+        context: context.clone(),
+      })
+      .or_default();
+
+    #[cfg(debug_assertions)]
+    resolved.referenced_by.push(ResolvedCommandReference {
+      capability: capability.identifier.clone(),
+      permission: match &expanded.delegated_by {
+        Some(_) => format!("{}:{}", expanded.plugin, permission.identifier),
+        None => permission.identifier.clone(),
+      },
+      delegated_by: expanded.delegated_by.clone(),
+    });
+
+    // the denied commands apply on every page
+    let grantees = if deny {
+      (
+        capability.windows.iter().cloned().collect(),
+        BTreeSet::new(),
+      )
+    } else {
+      grantees(capability)
+    };
+    if grantees.1.is_empty() {
+      resolved.windows.extend(capability.windows.clone());
+    } else {
+      resolved
+        .page_windows
+        .entry(grantees.1.clone())
+        .or_default()
+        .extend(capability.windows.clone());
+    }
+    if let Some(id) = scope_id {
+      resolved
+        .scopes
+        .entry(grantees.clone())
+        .or_default()
+        .push(id);
+    }
+    if let Some(class) = &permission.class {
+      resolved.classes.insert(class.clone());
+    }
+    if let Some(validator) = &permission.validator {
+      resolved.validators.insert(validator.clone());
+    }
+    for rule in &permission.args {
+      if !resolved.args.contains(rule) {
+        resolved.args.push(rule.clone());
+      }
+    }
+    if permission.requires_scope {
+      let requirement = (
+        capability.identifier.clone(),
+        permission_entry.identifier().get().into(),
+        grantees.0.clone(),
+      );
+      if !resolved.requires_scope.contains(&requirement) {
+        resolved.requires_scope.push(requirement);
+      }
+    }
+    if let Some(config) = config {
+      // permission sets expand to several permissions granting the same command
+      let config = (
+        capability.identifier.clone(),
+        grantees.clone(),
+        config.clone(),
+      );
+      if !resolved.configs.contains(&config) {
+        resolved.configs.push(config);
+      }
+    }
+    if expanded.via_default() {
+      resolved.via_default = true;
+    } else {
+      resolved.explicit = true;
+    }
+  }
+}
+
+/// A permission referenced by a capability entry, directly or through permission sets.
+struct ExpandedPermission<'a> {
+  /// The plugin defining the permission, which differs from the referenced plugin for delegated permissions.
+  plugin: &'a str,
+  permission: &'a Permission,
+  /// Identifiers of the permission sets the permission was expanded from, outermost first.
+  sets: Vec<&'a str>,
+  /// The fully qualified identifier of the permission set delegating the permission of another plugin.
+  #[cfg_attr(not(debug_assertions), allow(dead_code))]
+  delegated_by: Option<String>,
+}
+
+impl ExpandedPermission<'_> {
+  /// Whether the permission was expanded from the `default` permission set.
+  fn via_default(&self) -> bool {
+    self.sets.first() == Some(&"default")
+  }
+}
+
+/// The deprecated permissions and permission sets a capability entry references, directly or through permission sets.
+fn deprecated_references(
+  capability: &str,
+  plugin_name: &str,
+  permissions: &[ExpandedPermission<'_>],
+  acl: &BTreeMap<String, Manifest>,
+) -> Vec<DeprecatedReference> {
+  let manifest = &acl[plugin_name];
+  let mut references = Vec::<DeprecatedReference>::new();
+  let mut push = |plugin: &str, identifier: &str, via: &[&str], deprecation: &Deprecation| {
+    if !references
+      .iter()
+      .any(|r| r.plugin == plugin && r.identifier == identifier)
+    {
+      references.push(DeprecatedReference {
+        capability: capability.to_string(),
+        plugin: plugin.to_string(),
+        identifier: identifier.to_string(),
+        via: via
+          .iter()
+          .map(|set| format!("{plugin_name}:{set}"))
+          .collect(),
+        deprecation: deprecation.clone(),
+      });
+    }
+  };
+
+  for expanded in permissions {
+    // nested permission sets are always defined by the referenced plugin
+    for (i, set) in expanded.sets.iter().enumerate() {
+      let set_permission = if *set == "default" {
+        manifest.default_permission.as_ref()
+      } else {
+        manifest.permission_sets.get(*set)
+      };
+      if let Some(deprecation) = set_permission.and_then(|set| set.deprecated.as_ref()) {
+        push(plugin_name, set, &expanded.sets[..i], deprecation);
+      }
+    }
+    if let Some(deprecation) = &expanded.permission.deprecated {
+      push(
+        expanded.plugin,
+        &expanded.permission.identifier,
+        &expanded.sets,
+        deprecation,
+      );
+    }
+  }
+
+  references
+}
+
+// get the permissions from a permission set
+fn get_permission_set_permissions<'a>(
+  plugin_name: &'a str,
+  set: &'a PermissionSet,
+  parents: &[&'a str],
+  acl: &'a BTreeMap<String, Manifest>,
+) -> Result<Vec<ExpandedPermission<'a>>, Error> {
+  if parents.contains(&set.identifier.as_str()) {
+    let start = parents
+      .iter()
+      .position(|parent| *parent == set.identifier)
+      .unwrap_or_default();
+    return Err(Error::PermissionSetCycle {
+      plugin: plugin_name.to_string(),
+      set: set.identifier.clone(),
+      cycle: parents[start..]
+        .iter()
+        .map(|parent| parent.to_string())
+        .chain(std::iter::once(set.identifier.clone()))
+        .collect(),
+    });
+  }
+
+  let mut sets = parents.to_vec();
+  sets.push(&set.identifier);
+
+  let mut permissions = Vec::new();
+  for p in &set.permissions {
+    permissions.extend(
+      get_set_reference_permissions(plugin_name, set, p, &sets, acl)?.ok_or_else(|| {
+        Error::SetPermissionNotFound {
+          permission: p.to_string(),
+          set: set.identifier.clone(),
+        }
+      })?,
+    );
+  }
+
+  let operand = |operator: &str, p: &'a String| {
+    get_set_reference_permissions(plugin_name, set, p, &sets, acl)
+      .and_then(|operand| {
+        operand.ok_or(Error::SetPermissionNotFound {
+          permission: p.to_string(),
+          set: set.identifier.clone(),
+        })
+      })
+      .map_err(|error| match error {
+        Error::SetPermissionNotFound { .. }
+        | Error::DelegatedPluginNotFound { .. }
+        | Error::DelegatedPermissionNotFound { .. } => Error::SetOperandNotFound {
+          permission: p.to_string(),
+          operator: operator.to_string(),
+          set: format!("{plugin_name}:{}", set.identifier),
+        },
+        error => error,
+      })
+  };
+  let contains = |operand: &[ExpandedPermission<'_>], permission: &ExpandedPermission<'_>| {
+    operand.iter().any(|p| {
+      p.plugin == permission.plugin && p.permission.identifier == permission.permission.identifier
+    })
+  };
+  for p in &set.intersect {
+    let operand = operand("intersect", p)?;
+    permissions.retain(|permission| contains(&operand, permission));
+  }
+  for p in &set.exclude {
+    let operand = operand("exclude", p)?;
+    permissions.retain(|permission| !contains(&operand, permission));
+  }
+
+  Ok(permissions)
+}
+
+/// Expands an identifier referenced by a permission set, in its permissions or its operators.
+///
+/// Returns `None` when the plugin defines no such permission or permission set.
+fn get_set_reference_permissions<'a>(
+  plugin_name: &'a str,
+  set: &'a PermissionSet,
+  reference: &'a str,
+  sets: &[&'a str],
+  acl: &'a BTreeMap<String, Manifest>,
+) -> Result<Option<Vec<ExpandedPermission<'a>>>, Error> {
+  let manifest = &acl[plugin_name];
+  let permission_set = if reference == "default" {
+    manifest.default_permission.as_ref()
+  } else {
+    manifest.permission_sets.get(reference)
+  };
+
+  if let Some(permission) = manifest.permissions.get(reference) {
+    Ok(Some(vec![ExpandedPermission {
+      plugin: plugin_name,
+      permission,
+      sets: sets.to_vec(),
+      delegated_by: None,
+    }]))
+  } else if let Some(permission_set) = permission_set {
+    get_permission_set_permissions(plugin_name, permission_set, sets, acl).map(Some)
+  } else if let Some(permission) = (reference == "default")
+    .then(|| manifest.implicit_default_permission())
+    .flatten()
+  {
+    Ok(Some(vec![ExpandedPermission {
+      plugin: plugin_name,
+      permission,
+      sets: sets.to_vec(),
+      delegated_by: None,
+    }]))
+  } else if let Some((delegated_plugin, delegated)) = reference.split_once(':') {
+    get_delegated_permission(plugin_name, set, delegated_plugin, delegated, sets, acl)
+      .map(|permission| Some(vec![permission]))
+  } else {
+    Ok(None)
+  }
+}
+
+/// Resolves a permission of another plugin referenced by a permission set.
+///
+/// Only [delegable](Permission#structfield.delegable) permissions can be referenced,
+/// so a plugin cannot grant the commands of another plugin without its consent.
+fn get_delegated_permission<'a>(
+  plugin_name: &str,
+  set: &PermissionSet,
+  delegated_plugin: &'a str,
+  delegated: &str,
+  sets: &[&'a str],
+  acl: &'a BTreeMap<String, Manifest>,
+) -> Result<ExpandedPermission<'a>, Error> {
+  let set_id = format!("{plugin_name}:{}", set.identifier);
+  let permission_id = format!("{delegated_plugin}:{delegated}");
+
+  let manifest = acl
+    .get(delegated_plugin)
+    .ok_or_else(|| Error::DelegatedPluginNotFound {
+      set: set_id.clone(),
+      permission: permission_id.clone(),
+      plugin: delegated_plugin.to_string(),
+    })?;
+  // permission sets cannot be delegated, only the permissions themselves
+  let permission =
+    manifest
+      .permissions
+      .get(delegated)
+      .ok_or_else(|| Error::DelegatedPermissionNotFound {
+        set: set_id.clone(),
+        permission: permission_id.clone(),
+      })?;
+  if !permission.delegable {
+    return Err(Error::PermissionNotDelegable {
+      set: set_id,
+      permission: permission_id,
+    });
+  }
+
+  Ok(ExpandedPermission {
+    plugin: delegated_plugin,
+    permission,
+    sets: sets.to_vec(),
+    delegated_by: Some(set_id),
+  })
+}
+
+/// Checks that the [`LOCKDOWN_REMOTE_CAPABILITY`] only disables the remote IPC,
+/// as the windows and permissions it would select or grant are not resolved.
+fn check_lockdown_capability(capability: &Capability) -> Result<(), Error> {
+  let invalid = |reason: &str| Error::InvalidReservedCapability {
+    capability: capability.identifier.clone(),
+    reason: reason.into(),
+  };
+
+  if !capability.context.is_local() {
+    Err(invalid("it cannot have a remote context"))
+  } else if !capability.windows.is_empty() {
+    Err(invalid("it cannot select windows"))
+  } else if !capability.permissions.is_empty() {
+    Err(invalid("it cannot grant permissions"))
+  } else {
+    Ok(())
+  }
+}
+
+/// Describes the remote grants rejected by the [`LOCKDOWN_REMOTE_CAPABILITY`],
+/// as capabilities granting remote origins contradict it.
+fn remote_lockdown_message(
+  capabilities: &BTreeMap<String, Capability>,
+  resolved: &Resolved,
+  target: Target,
+) -> Option<String> {
+  let neutralized = capabilities
+    .values()
+    .filter(|capability| capability.platforms.contains(&target))
+    .filter_map(|capability| match &capability.context {
+      CapabilityContext::Local => None,
+      CapabilityContext::Remote { domains, .. } => Some(format!(
+        "{} ({})",
+        capability.identifier,
+        domains
+          .iter()
+          .flat_map(RemoteDomain::patterns)
+          .collect::<Vec<_>>()
+          .join(", ")
+      )),
+    })
+    .collect::<Vec<_>>();
+  if neutralized.is_empty() {
+    return None;
+  }
+
+  let commands = resolved
+    .allowed_commands
+    .keys()
+    .filter(|key| matches!(key.context, ExecutionContext::Remote { .. }))
+    .count();
+  Some(format!(
+    "{LOCKDOWN_REMOTE_CAPABILITY} rejects the {commands} commands granted to remote origins by capabilities {}",
+    neutralized.join(", ")
+  ))
+}
+
+/// Checks the installed version of a plugin against the requirement of a permission entry,
+/// or of its capability otherwise, see [`Capability::plugin_version`].
+///
+/// Plugins of an unknown version are not checked.
+fn check_plugin_version(
+  capability: &Capability,
+  entry: &PermissionEntry,
+  plugin_name: &str,
+  acl: &BTreeMap<String, Manifest>,
+) -> Result<(), Error> {
+  let Some(requirement) = entry.plugin_version().or_else(|| {
+    capability
+      .plugin_version
+      .get(plugin_name)
+      .map(String::as_str)
+  }) else {
+    return Ok(());
+  };
+
+  let version_req = semver::VersionReq::parse(requirement).map_err(|error| {
+    Error::InvalidPluginVersionRequirement {
+      capability: capability.identifier.clone(),
+      plugin: plugin_name.to_string(),
+      requirement: requirement.to_string(),
+      error,
+    }
+  })?;
+
+  let Some(version) = acl
+    .get(plugin_name)
+    .and_then(|manifest| manifest.version.as_deref())
+  else {
+    return Ok(());
+  };
+
+  match semver::Version::parse(version) {
+    Ok(parsed) if !version_req.matches(&parsed) => Err(Error::PluginVersionMismatch {
+      capability: capability.identifier.clone(),
+      plugin: plugin_name.to_string(),
+      requirement: requirement.to_string(),
+      version: version.to_string(),
+    }),
+    _ => Ok(()),
+  }
+}
+
+/// Expands a permission reference of a capability to the permissions it grants.
+///
+/// [`ALL_PERMISSIONS`] expands to every permission of the plugin once,
+/// skipping the `deny-` permissions unless `include_deny` is set.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(name = "acl::get_permissions", level = "debug", skip(acl))
+)]
+fn get_permissions<'a>(
+  plugin_name: &'a str,
+  permission_name: &'a str,
+  include_deny: bool,
+  acl: &'a BTreeMap<String, Manifest>,
+) -> Result<Vec<ExpandedPermission<'a>>, Error> {
+  let manifest = acl.get(plugin_name).ok_or_else(|| Error::UnknownPlugin {
+    plugin: plugin_name.to_string(),
+    available: acl.keys().cloned().collect::<Vec<_>>().join(", "),
+  })?;
+
+  if permission_name == ALL_PERMISSIONS {
+    // the permission sets only reference permissions of the manifest
+    Ok(
+      manifest
+        .permissions
+        .iter()
+        .filter(|(identifier, _)| include_deny || !identifier.starts_with("deny-"))
+        .map(|(_, permission)| ExpandedPermission {
+          plugin: plugin_name,
+          permission,
+          sets: Vec::new(),
+          delegated_by: None,
+        })
+        .collect(),
+    )
+  } else if permission_name == "default" {
+    if let Some(default) = &manifest.default_permission {
+      get_permission_set_permissions(plugin_name, default, &[], acl)
+    } else if let Some(permission) = manifest.implicit_default_permission() {
+      Ok(vec![ExpandedPermission {
+        plugin: plugin_name,
+        permission,
+        sets: vec!["default"],
+        delegated_by: None,
+      }])
+    } else {
+      Err(Error::MissingDefaultPermission {
+        plugin: plugin_name.to_string(),
+        available: manifest
+          .permission_sets
+          .keys()
+          .chain(manifest.permissions.keys())
+          .map(|p| format!("{plugin_name}:{p}"))
+          .collect::<Vec<_>>()
+          .join(", "),
+      })
+    }
+  } else if let Some(set) = manifest.permission_sets.get(permission_name) {
+    get_permission_set_permissions(plugin_name, set, &[], acl)
+  } else if let Some(permission) = manifest.permissions.get(permission_name) {
+    Ok(vec![ExpandedPermission {
+      plugin: plugin_name,
+      permission,
+      sets: Vec::new(),
+      delegated_by: None,
+    }])
+  } else {
+    Err(Error::UnknownPermission {
+      plugin: plugin_name.to_string(),
+      permission: permission_name.to_string(),
+    })
+  }
+}
+
+#[cfg(feature = "tokens")]
+mod build {
+  use proc_macro2::TokenStream;
+  use quote::{quote, ToTokens, TokenStreamExt};
+  use std::{collections::HashMap, convert::identity};
+
+  use super::*;
+  use crate::{acl::tables::CommandTables, literal_struct, tokens::*};
+
+  /// Maximum number of allowed and denied commands embedded as struct literals.
+  ///
+  /// Larger command tables are embedded as a serialized [`CommandTables`] byte string,
+  /// since the nested literals dominate the expansion and compile time of `generate_context!`.
+  const COMMAND_LITERALS_LIMIT: usize = 64;
+
+  /// Maximum number of command scope values embedded as literals.
+  ///
+  /// Larger command scopes are embedded as [`ScopeShards`] byte strings, decoded when a command first uses them,
+  /// since building every scope value delays the app startup.
+  const SCOPE_VALUE_LITERALS_LIMIT: usize = 4096;
+
+  impl ToTokens for CommandKey {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let name = str_lit(&self.name);
+      let context = &self.context;
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::resolved::CommandKey,
+        name,
+        context
+      )
+    }
+  }
+
+  #[cfg(debug_assertions)]
+  impl ToTokens for ResolvedCommandReference {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let capability = str_lit(&self.capability);
+      let permission = str_lit(&self.permission);
+      let delegated_by = opt_str_lit(self.delegated_by.as_ref());
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::resolved::ResolvedCommandReference,
+        capability,
+        permission,
+        delegated_by
+      )
+    }
+  }
+
+  impl ToTokens for ResolvedCommand {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      tokens.append_all(command_lit(self, None));
+    }
+  }
+
+  fn command_lit(
+    command: &ResolvedCommand,
+    windows: Option<&WindowPatternPool<'_>>,
+  ) -> TokenStream {
+    let mut tokens = TokenStream::new();
+
+    #[cfg(debug_assertions)]
+    let referenced_by = vec_lit(&command.referenced_by, identity);
+
+    let window_ancestors = vec_lit(&command.window_ancestors, |ancestor| {
+      ancestor_lit(ancestor, windows)
+    });
+    let webviews = vec_lit(&command.webviews, |webview| webview_lit(webview, windows));
+    let pages = vec_lit(&command.pages, |grant| page_grant_lit(grant, windows));
+    let scopes = vec_lit(&command.scopes, |scope| window_scope_lit(scope, windows));
+    let configs = vec_lit(&command.configs, |config| {
+      window_config_lit(config, windows)
+    });
+    let windows = vec_lit(&command.windows, |window| {
+      window_pattern_lit(window, windows)
+    });
+    let classes = vec_lit(&command.classes, str_lit);
+    let validators = vec_lit(&command.validators, str_lit);
+    let args = vec_lit(&command.args, identity);
+    let via_default = command.via_default;
+
+    #[cfg(debug_assertions)]
+    {
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::resolved::ResolvedCommand,
+        referenced_by,
+        windows,
+        window_ancestors,
+        webviews,
+        pages,
+        scopes,
+        classes,
+        validators,
+        args,
+        via_default,
+        configs
+      )
+    }
+    #[cfg(not(debug_assertions))]
+    literal_struct!(
+      tokens,
+      ::tauri::utils::acl::resolved::ResolvedCommand,
+      windows,
+      window_ancestors,
+      webviews,
+      pages,
+      scopes,
+      classes,
+      validators,
+      args,
+      via_default,
+      configs
+    );
+
+    tokens
+  }
+
+  impl ToTokens for PageGrant {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      tokens.append_all(page_grant_lit(self, None));
+    }
+  }
+
+  fn page_grant_lit(grant: &PageGrant, windows: Option<&WindowPatternPool<'_>>) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let window_ancestors = vec_lit(&grant.window_ancestors, |ancestor| {
+      ancestor_lit(ancestor, windows)
+    });
+    let webviews = vec_lit(&grant.webviews, |webview| webview_lit(webview, windows));
+    let pages = pages_lit(&grant.pages);
+    let windows = vec_lit(&grant.windows, |window| window_pattern_lit(window, windows));
+    literal_struct!(
+      tokens,
+      ::tauri::utils::acl::resolved::PageGrant,
+      windows,
+      window_ancestors,
+      webviews,
+      pages
+    );
+    tokens
+  }
+
+  fn pages_lit(pages: &[Pattern]) -> TokenStream {
+    vec_lit(pages, |page| {
+      let page = page.as_str();
+      quote!(#page.parse().unwrap())
+    })
+  }
+
+  impl ToTokens for WindowScope {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      tokens.append_all(window_scope_lit(self, None));
+    }
+  }
+
+  fn window_scope_lit(scope: &WindowScope, windows: Option<&WindowPatternPool<'_>>) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let window_ancestors = vec_lit(&scope.window_ancestors, |ancestor| {
+      ancestor_lit(ancestor, windows)
+    });
+    let webviews = vec_lit(&scope.webviews, |webview| webview_lit(webview, windows));
+    let pages = pages_lit(&scope.pages);
+    let windows = vec_lit(&scope.windows, |window| window_pattern_lit(window, windows));
+    let scope = scope.scope;
+    literal_struct!(
+      tokens,
+      ::tauri::utils::acl::resolved::WindowScope,
+      windows,
+      window_ancestors,
+      webviews,
+      pages,
+      scope
+    );
+    tokens
+  }
+
+  impl ToTokens for WindowGlobalScope {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      tokens.append_all(window_global_scope_lit(self, None, |scope| quote!(#scope)));
+    }
+  }
+
+  fn window_global_scope_lit(
+    scope: &WindowGlobalScope,
+    windows: Option<&WindowPatternPool<'_>>,
+    scope_lit: impl Fn(&ResolvedScope) -> TokenStream,
+  ) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let window_ancestors = vec_lit(&scope.window_ancestors, |ancestor| {
+      ancestor_lit(ancestor, windows)
+    });
+    let webviews = vec_lit(&scope.webviews, |webview| webview_lit(webview, windows));
+    let pages = pages_lit(&scope.pages);
+    let windows = vec_lit(&scope.windows, |window| window_pattern_lit(window, windows));
+    let scope = scope_lit(&scope.scope);
+    literal_struct!(
+      tokens,
+      ::tauri::utils::acl::resolved::WindowGlobalScope,
+      windows,
+      window_ancestors,
+      webviews,
+      pages,
+      scope
+    );
+    tokens
+  }
+
+  impl ToTokens for WindowConfig {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      tokens.append_all(window_config_lit(self, None));
+    }
+  }
+
+  fn window_config_lit(
+    config: &WindowConfig,
+    windows: Option<&WindowPatternPool<'_>>,
+  ) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let capability = str_lit(&config.capability);
+    let window_ancestors = vec_lit(&config.window_ancestors, |ancestor| {
+      ancestor_lit(ancestor, windows)
+    });
+    let webviews = vec_lit(&config.webviews, |webview| webview_lit(webview, windows));
+    let pages = pages_lit(&config.pages);
+    let windows = vec_lit(&config.windows, |window| {
+      window_pattern_lit(window, windows)
+    });
+    let config = &config.config;
+    literal_struct!(
+      tokens,
+      ::tauri::utils::acl::resolved::WindowConfig,
+      capability,
+      windows,
+      window_ancestors,
+      webviews,
+      pages,
+      config
+    );
+    tokens
+  }
+
+  impl ToTokens for WindowAncestor {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      tokens.append_all(ancestor_lit(self, None));
+    }
+  }
+
+  fn ancestor_lit(
+    ancestor: &WindowAncestor,
+    windows: Option<&WindowPatternPool<'_>>,
+  ) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let pattern = window_pattern_lit(&ancestor.pattern, windows);
+    let parent_only = ancestor.parent_only;
+    literal_struct!(
+      tokens,
+      ::tauri::utils::acl::resolved::WindowAncestor,
+      pattern,
+      parent_only
+    );
+    tokens
+  }
+
+  impl ToTokens for WebviewPattern {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      tokens.append_all(webview_lit(self, None));
+    }
+  }
+
+  fn webview_lit(webview: &WebviewPattern, windows: Option<&WindowPatternPool<'_>>) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let window = window_pattern_lit(&webview.window, windows);
+    let webview = window_pattern_lit(&webview.webview, windows);
+    literal_struct!(
+      tokens,
+      ::tauri::utils::acl::resolved::WebviewPattern,
+      window,
+      webview
+    );
+    tokens
+  }
+
+  fn window_pattern_lit(
+    pattern: &WindowPattern,
+    windows: Option<&WindowPatternPool<'_>>,
+  ) -> TokenStream {
+    match windows.and_then(|pool| pool.indexes.get(pattern.as_str())) {
+      Some(index) => quote!(window_patterns[#index].clone()),
+      None => {
+        let pattern = pattern.as_str();
+        quote!(#pattern.parse().unwrap())
+      }
+    }
+  }
+
+  /// Window pattern strings of all resolved commands.
+  ///
+  /// They are parsed once in the generated code with [`AclCodegenDetail::Minimal`]
+  /// and cloned where they are used, sharing the compiled pattern.
+  struct WindowPatternPool<'a> {
+    patterns: Vec<&'a str>,
+    indexes: HashMap<&'a str, usize>,
+  }
+
+  impl<'a> WindowPatternPool<'a> {
+    fn new(commands: impl Iterator<Item = &'a ResolvedCommand>) -> Self {
+      let mut pool = Self {
+        patterns: Vec::new(),
+        indexes: HashMap::new(),
+      };
+      for command in commands {
+        let patterns = command
+          .windows
+          .iter()
+          .chain(command.window_ancestors.iter().map(|a| &a.pattern))
+          .chain(webview_patterns(&command.webviews))
+          .chain(command.pages.iter().flat_map(|grant| {
+            grant
+              .windows
+              .iter()
+              .chain(grant.window_ancestors.iter().map(|a| &a.pattern))
+              .chain(webview_patterns(&grant.webviews))
+          }))
+          .chain(command.scopes.iter().flat_map(|scope| {
+            scope
+              .windows
+              .iter()
+              .chain(scope.window_ancestors.iter().map(|a| &a.pattern))
+              .chain(webview_patterns(&scope.webviews))
+          }))
+          .chain(command.configs.iter().flat_map(|config| {
+            config
+              .windows
+              .iter()
+              .chain(config.window_ancestors.iter().map(|a| &a.pattern))
+              .chain(webview_patterns(&config.webviews))
+          }));
+        for pattern in patterns {
+          let pattern = pattern.as_str();
+          if !pool.indexes.contains_key(pattern) {
+            pool.indexes.insert(pattern, pool.patterns.len());
+            pool.patterns.push(pattern);
+          }
+        }
+      }
+      pool
+    }
+  }
+
+  fn webview_patterns(webviews: &[WebviewPattern]) -> impl Iterator<Item = &WindowPattern> {
+    webviews.iter().flat_map(|w| [&w.window, &w.webview])
+  }
+
+  impl ToTokens for ResolvedScope {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let allow = vec_lit(&self.allow, identity);
+      let deny = vec_lit(&self.deny, identity);
+      let external = opt_lit(self.external.as_ref());
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::resolved::ResolvedScope,
+        allow,
+        deny,
+        external
+      )
+    }
+  }
+
+  impl ToTokens for ExternalScope {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let key = str_lit(&self.key);
+      let hash = str_lit(&self.hash);
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::resolved::ExternalScope,
+        key,
+        hash
+      )
+    }
+  }
+
+  /// Scope values that are referenced more than once across all resolved scopes.
+  ///
+  /// They are emitted once in the generated code and cloned where they are used,
+  /// which reduces the size of the generated literals for scopes sharing many entries.
+  struct ScopeValuePool<'a> {
+    values: Vec<&'a Value>,
+    indexes: HashMap<String, usize>,
+  }
+
+  impl<'a> ScopeValuePool<'a> {
+    fn new(scopes: impl Iterator<Item = &'a ResolvedScope> + Clone) -> Self {
+      let values = scopes.flat_map(|scope| scope.allow.iter().chain(&scope.deny));
+
+      let mut occurrences = HashMap::<String, usize>::new();
+      for value in values.clone() {
+        if let Ok(key) = serde_json::to_string(value) {
+          *occurrences.entry(key).or_default() += 1;
+        }
+      }
+
+      let mut pool = Self {
+        values: Vec::new(),
+        indexes: HashMap::new(),
+      };
+      for value in values {
+        if let Ok(key) = serde_json::to_string(value) {
+          if occurrences.get(&key).copied().unwrap_or_default() > 1
+            && !pool.indexes.contains_key(&key)
+          {
+            pool.indexes.insert(key, pool.values.len());
+            pool.values.push(value);
+          }
+        }
+      }
+
+      pool
+    }
+
+    fn value_lit(&self, value: &Value) -> TokenStream {
+      match serde_json::to_string(value)
+        .ok()
+        .and_then(|key| self.indexes.get(&key))
+      {
+        Some(index) => quote!(scope_values[#index].clone()),
+        None => quote!(#value),
+      }
+    }
+
+    fn scope_lit(&self, scope: &ResolvedScope) -> TokenStream {
+      let allow = vec_lit(&scope.allow, |v| self.value_lit(v));
+      let deny = vec_lit(&scope.deny, |v| self.value_lit(v));
+      let external = opt_lit(scope.external.as_ref());
+      quote!(::tauri::utils::acl::resolved::ResolvedScope {
+        allow: #allow,
+        deny: #deny,
+        external: #external
+      })
+    }
+  }
+
+  impl Resolved {
+    /// Generates the code of the resolved ACL with the given level of detail.
+    pub fn into_tokens(mut self, detail: AclCodegenDetail) -> TokenStream {
+      match detail {
+        AclCodegenDetail::Full => self.into_token_stream(),
+        AclCodegenDetail::Minimal => {
+          self.minimize();
+          let windows = WindowPatternPool::new(
+            self
+              .allowed_commands
+              .values()
+              .chain(self.denied_commands.values()),
+          );
+          self.lit(Some(&windows))
+        }
+      }
+    }
+
+    fn lit(&self, windows: Option<&WindowPatternPool<'_>>) -> TokenStream {
+      #[cfg(debug_assertions)]
+      let acl = map_lit(
+        quote! { ::std::collections::BTreeMap },
+        &self.acl,
+        str_lit,
+        identity,
+      );
+
+      let scope_values = self
+        .command_scope
+        .values()
+        .map(|scope| scope.allow.len() + scope.deny.len())
+        .sum::<usize>();
+      let shards = (scope_values > SCOPE_VALUE_LITERALS_LIMIT).then(|| {
+        ScopeShards::encode(&self.command_scope).expect("failed to encode the ACL scope shards")
+      });
+      let no_command_scope = BTreeMap::new();
+      let command_scope = if shards.is_some() {
+        &no_command_scope
+      } else {
+        &self.command_scope
+      };
+
+      let command_count = self.allowed_commands.len() + self.denied_commands.len();
+      let tables = (command_count > COMMAND_LITERALS_LIMIT).then(|| {
+        CommandTables::new(&self.allowed_commands, &self.denied_commands, command_scope)
+          .encode()
+          .expect("failed to encode the ACL command tables")
+      });
+      // the window patterns are interned in the command tables
+      let windows = windows.filter(|_| tables.is_none());
+
+      let pool = ScopeValuePool::new(
+        command_scope.values().filter(|_| tables.is_none()).chain(
+          self
+            .global_scope
+            .values()
+            .flatten()
+            .map(|window_scope| &window_scope.scope),
+        ),
+      );
+
+      let (allowed_commands, denied_commands, command_scope) = if tables.is_some() {
+        (
+          quote!(allowed_commands),
+          quote!(denied_commands),
+          quote!(command_scope),
+        )
+      } else {
+        (
+          map_lit(
+            quote! { ::std::collections::BTreeMap },
+            &self.allowed_commands,
+            identity,
+            |command| command_lit(command, windows),
+          ),
+          map_lit(
+            quote! { ::std::collections::BTreeMap },
+            &self.denied_commands,
+            identity,
+            |command| command_lit(command, windows),
+          ),
+          map_lit(
+            quote! { ::std::collections::BTreeMap },
+            command_scope,
+            identity,
+            |scope| pool.scope_lit(scope),
+          ),
+        )
+      };
+
+      let command_scope_shards = match &shards {
+        Some((keys, shards)) => {
+          let shards = shards.iter().map(|shard| {
+            let shard = proc_macro2::Literal::byte_string(shard);
+            quote!(#shard as &'static [u8])
+          });
+          quote!(::tauri::utils::acl::tables::ScopeShards::new(
+            vec![#(#keys),*],
+            [#(#shards),*]
+          ))
+        }
+        None => quote!(::core::default::Default::default()),
+      };
+
+      let global_scope = map_lit(
+        quote! { ::std::collections::BTreeMap },
+        &self.global_scope,
+        str_lit,
+        |scopes| {
+          vec_lit(scopes, |scope| {
+            window_global_scope_lit(scope, windows, |scope| pool.scope_lit(scope))
+          })
+        },
+      );
+      let inheriting_webviews = vec_lit(&self.inheriting_webviews, |webview| {
+        webview_lit(webview, windows)
+      });
+      let global_api = map_lit(
+        quote! { ::std::collections::BTreeMap },
+        &self.global_api,
+        str_lit,
+        |functions| {
+          map_lit(
+            quote! { ::std::collections::BTreeMap },
+            functions,
+            str_lit,
+            str_lit,
+          )
+        },
+      );
+
+      let command_aliases = map_lit(
+        quote! { ::std::collections::BTreeMap },
+        &self.command_aliases,
+        str_lit,
+        |aliases| {
+          map_lit(
+            quote! { ::std::collections::BTreeMap },
+            aliases,
+            str_lit,
+            str_lit,
+          )
+        },
+      );
+
+      let shares_scope_with = map_lit(
+        quote! { ::std::collections::BTreeMap },
+        &self.shares_scope_with,
+        str_lit,
+        |plugins| vec_lit(plugins, str_lit),
+      );
+
+      let lockdown_remote = self.lockdown_remote;
+      let role_grants = vec_lit(&self.role_grants, identity);
+
+      let mut resolved = TokenStream::new();
+
+      #[cfg(debug_assertions)]
+      {
+        literal_struct!(
+          resolved,
+          ::tauri::utils::acl::resolved::Resolved,
+          acl,
+          allowed_commands,
+          denied_commands,
+          command_scope,
+          command_scope_shards,
+          global_scope,
+          inheriting_webviews,
+          global_api,
+          command_aliases,
+          shares_scope_with,
+          lockdown_remote,
+          role_grants
+        )
+      }
+      #[cfg(not(debug_assertions))]
+      literal_struct!(
+        resolved,
+        ::tauri::utils::acl::resolved::Resolved,
+        allowed_commands,
+        denied_commands,
+        command_scope,
+        command_scope_shards,
+        global_scope,
+        inheriting_webviews,
+        global_api,
+        command_aliases,
+        shares_scope_with,
+        lockdown_remote,
+        role_grants
+      );
+
+      let window_patterns = windows
+        .filter(|pool| !pool.patterns.is_empty())
+        .map(|pool| {
+          let patterns = &pool.patterns;
+          let len = patterns.len();
+          quote!(
+            let window_patterns: [::tauri::utils::acl::window::WindowPattern; #len] =
+              [#(#patterns.parse().unwrap()),*];
+          )
+        });
+
+      let tables = tables.map(|tables| {
+        let tables = proc_macro2::Literal::byte_string(&tables);
+        quote! {
+          let (allowed_commands, denied_commands, command_scope) =
+            ::tauri::utils::acl::tables::CommandTables::decode(#tables)
+              .and_then(|tables| tables.into_parts())
+              .expect("failed to decode the ACL command tables");
+        }
+      });
+
+      if pool.values.is_empty() && window_patterns.is_none() && tables.is_none() {
+        resolved
+      } else {
+        let scope_values = (!pool.values.is_empty()).then(|| {
+          let values = &pool.values;
+          let len = values.len();
+          quote!(let scope_values: [::tauri::utils::acl::Value; #len] = [#(#values),*];)
+        });
+        quote! {{
+          #window_patterns
+          #scope_values
+          #tables
+          #resolved
+        }}
+      }
+    }
+  }
+
+  impl ToTokens for Resolved {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      tokens.append_all(self.lit(None));
+    }
+  }
+
+  impl ToTokens for RoleGrant {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+      let capability = str_lit(&self.capability);
+      let roles = vec_lit(&self.roles, str_lit);
+      let acl = &self.acl;
+      literal_struct!(
+        tokens,
+        ::tauri::utils::acl::resolved::RoleGrant,
+        capability,
+        roles,
+        acl
+      )
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::*;
+  use crate::acl::{
+    capability::{RemoteAttestation, RequiredHeader},
+    plugin::PermissionFile,
+    ParseCommandIdError,
+  };
+
+  /// The manifests of the given plugins, each defined by a single permission file in TOML.
+  fn manifests<'a, P: AsRef<str>>(
+    plugins: impl IntoIterator<Item = (&'a str, P)>,
+  ) -> BTreeMap<String, Manifest> {
+    plugins
+      .into_iter()
+      .map(|(plugin, permissions)| {
+        let permissions: PermissionFile = toml::from_str(permissions.as_ref()).unwrap();
+        (plugin.to_string(), Manifest::new(vec![permissions], None))
+      })
+      .collect()
+  }
+
+  fn ping_acl() -> BTreeMap<String, Manifest> {
+    manifests([(
+      "ping",
+      r#"
+[[permission]]
+identifier = "allow-ping"
+commands.allow = ["ping"]
+"#,
+    )])
+  }
+
+  /// A local capability granting the permissions to the `main` window.
+  fn capability(identifier: &str, permissions: &[&str]) -> Capability {
+    Capability {
+      identifier: identifier.into(),
+      description: String::new(),
+      context: CapabilityContext::Local,
+      windows: vec!["main".into()],
+      permissions: permissions
+        .iter()
+        .map(|permission| {
+          PermissionEntry::PermissionRef(permission.to_string().try_into().unwrap())
+        })
+        .collect(),
+      platforms: vec![Target::current()],
+      group: None,
+      inherit_from_window: false,
+      roles: Vec::new(),
+      attenuate: None,
+      pages: Vec::new(),
+      plugin_version: Default::default(),
+      extensions: Default::default(),
+    }
+  }
+
+  /// The capabilities keyed by their identifier.
+  fn capabilities(
+    capabilities: impl IntoIterator<Item = Capability>,
+  ) -> BTreeMap<String, Capability> {
+    capabilities
+      .into_iter()
+      .map(|capability| (capability.identifier.clone(), capability))
+      .collect()
+  }
+
+  fn remote_capability(domains: Vec<RemoteDomain>) -> BTreeMap<String, Capability> {
+    capabilities([Capability {
+      context: CapabilityContext::Remote {
+        domains,
+        attestation: Default::default(),
+      },
+      ..capability("remote", &["ping:allow-ping"])
+    }])
+  }
+
+  fn remote_domains(resolved: &Resolved) -> Vec<Pattern> {
+    resolved
+      .allowed_commands
+      .keys()
+      .filter_map(|key| match &key.context {
+        ExecutionContext::Remote { domain, .. } => Some(domain.clone()),
+        ExecutionContext::Local => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn remote_domain_include_subdomains() {
+    let resolved = Resolved::resolve(
+      ping_acl(),
+      remote_capability(vec![RemoteDomain::Domain {
+        domain: "example.com".into(),
+        include_subdomains: true,
+      }]),
+      Target::current(),
+    )
+    .unwrap();
+
+    let domains = remote_domains(&resolved);
+    let matches = |host: &str| domains.iter().any(|d| d.matches(host));
+    assert!(matches("example.com"));
+    assert!(matches("app.example.com"));
+    assert!(matches("deep.app.example.com"));
+    assert!(!matches("example.com.evil.com"));
+    assert!(!matches("notexample.com"));
+  }
+
+  #[test]
+  fn remote_domain_pattern_keeps_semantics() {
+    let resolved = Resolved::resolve(
+      ping_acl(),
+      remote_capability(vec![RemoteDomain::Pattern("*.example.com".into())]),
+      Target::current(),
+    )
+    .unwrap();
+
+    let domains = remote_domains(&resolved);
+    assert!(!domains.iter().any(|d| d.matches("example.com")));
+    assert!(domains.iter().any(|d| d.matches("app.example.com")));
+  }
+
+  #[cfg(feature = "tracing")]
+  #[test]
+  fn resolution_spans() {
+    use std::sync::{
+      atomic::{AtomicU64, Ordering},
+      Arc, Mutex,
+    };
+    use tracing::{span, Event, Metadata, Subscriber};
+
+    /// Records the names of the spans and the messages of the events.
+    #[derive(Default)]
+    struct Capture {
+      next_id: AtomicU64,
+      spans: Arc<Mutex<Vec<&'static str>>>,
+      events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Subscriber for Capture {
+      fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+      }
+      fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        self.spans.lock().unwrap().push(span.metadata().name());
+        span::Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+      }
+      fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+      fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+      fn event(&self, event: &Event<'_>) {
+        struct Message<'a>(&'a mut String);
+        impl tracing::field::Visit for Message<'_> {
+          fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+              *self.0 = format!("{value:?}");
+            }
+          }
+        }
+        let mut message = String::new();
+        event.record(&mut Message(&mut message));
+        self.events.lock().unwrap().push(message);
+      }
+      fn enter(&self, _span: &span::Id) {}
+      fn exit(&self, _span: &span::Id) {}
+    }
+
+    let mut capabilities = default_capability("ping");
+    let mut other_target = capabilities["main"].clone();
+    other_target.identifier = "other-target".into();
+    other_target.platforms = vec![if Target::current() == Target::Linux {
+      Target::Windows
+    } else {
+      Target::Linux
+    }];
+    capabilities.insert(other_target.identifier.clone(), other_target);
+
+    let capture = Capture::default();
+    let (spans, events) = (capture.spans.clone(), capture.events.clone());
+    tracing::subscriber::with_default(capture, || {
+      Resolved::resolve(ping_acl(), capabilities, Target::current()).unwrap();
+    });
+
+    let spans = spans.lock().unwrap();
+    for name in [
+      "acl::resolve",
+      "acl::resolve::capability",
+      "acl::get_permissions",
+      "acl::resolve::scopes",
+    ] {
+      assert!(spans.contains(&name), "missing span {name} in {spans:?}");
+    }
+    let events = events.lock().unwrap();
+    for message in [
+      "skipping capability not applying to the target",
+      "expanded permission reference",
+    ] {
+      assert!(
+        events.iter().any(|event| event == message),
+        "missing event {message:?} in {events:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn remote_lockdown() {
+    let mut capabilities = remote_capability(vec![RemoteDomain::Pattern("*.example.com".into())]);
+    let lockdown: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": LOCKDOWN_REMOTE_CAPABILITY,
+      "windows": [],
+      "permissions": []
+    }))
+    .unwrap();
+    capabilities.insert(lockdown.identifier.clone(), lockdown.clone());
+
+    let (resolved, report) =
+      Resolved::resolve_with_report(ping_acl(), capabilities.clone(), Target::current()).unwrap();
+    assert!(resolved.lockdown_remote);
+    // the remote grants are kept for the denial explanations
+    assert!(!remote_domains(&resolved).is_empty());
+    let warning = report
+      .warnings
+      .iter()
+      .find(|w| w.code == WARNING_REMOTE_LOCKDOWN)
+      .expect("the neutralized remote grants are reported");
+    assert!(warning.message.contains("remote (*.example.com)"));
+
+    let resolved =
+      Resolved::resolve(ping_acl(), remote_capability(Vec::new()), Target::current()).unwrap();
+    assert!(!resolved.lockdown_remote);
+
+    let mut granting = lockdown;
+    granting.permissions = vec![PermissionEntry::PermissionRef(
+      "ping:allow-ping".to_string().try_into().unwrap(),
+    )];
+    capabilities.insert(granting.identifier.clone(), granting);
+    assert!(matches!(
+      Resolved::resolve(ping_acl(), capabilities, Target::current()),
+      Err(Error::InvalidReservedCapability { .. })
+    ));
+  }
+
+  #[test]
+  fn command_aliases() {
+    let acl = |aliases: &str| -> BTreeMap<String, Manifest> {
+      manifests([(
+        "fs",
+        &format!(
+          r#"
+{aliases}
+
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["readTextFile", "read_*"]
+"#
+        ),
+      )])
+    };
+    let capability: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": ["fs:allow-read"]
+    }))
+    .unwrap();
+    let capabilities = capabilities([capability]);
+
+    let (resolved, report) = Resolved::resolve_with_report(
+      acl(r#"aliases = { readTextFile = "readFile", readFile = "read_file" }"#),
+      capabilities.clone(),
+      Target::current(),
+    )
+    .unwrap();
+    // the alias is granted as its canonical command, and patterns only match canonical commands
+    assert_eq!(
+      resolved
+        .allowed_commands
+        .keys()
+        .map(|key| key.name.as_str())
+        .collect::<Vec<_>>(),
+      ["plugin:fs|read_file"]
+    );
+    assert_eq!(
+      resolved.command_aliases["fs"],
+      [
+        ("readFile".to_string(), "read_file".to_string()),
+        ("readTextFile".to_string(), "read_file".to_string())
+      ]
+      .into()
+    );
+    let warnings = report
+      .warnings
+      .iter()
+      .filter(|w| w.code == WARNING_DEPRECATED_COMMAND_ALIAS)
+      .map(|w| w.message.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      warnings,
+      ["permission fs:allow-read references command readTextFile, a deprecated alias of read_file"]
+    );
+
+    let error = Resolved::resolve(
+      acl(r#"aliases = { a = "b", b = "readTextFile", readTextFile = "b" }"#),
+      capabilities,
+      Target::current(),
+    )
+    .unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "command aliases of plugin fs form a cycle: b -> readTextFile -> b"
+    );
+  }
+
+  #[test]
+  fn remote_domain_object_form() {
+    let context: CapabilityContext = serde_json::from_str(
+      r#"{ "remote": { "domains": ["tauri.app", { "domain": "example.com", "include-subdomains": true }] } }"#,
+    )
+    .unwrap();
+    assert_eq!(
+      context,
+      CapabilityContext::Remote {
+        domains: vec![
+          RemoteDomain::Pattern("tauri.app".into()),
+          RemoteDomain::Domain {
+            domain: "example.com".into(),
+            include_subdomains: true
+          }
+        ],
+        attestation: Default::default(),
+      }
+    );
+  }
+
+  #[test]
+  fn remote_attestation() {
+    let context: CapabilityContext = serde_json::from_str(
+      r#"{ "remote": { "domains": ["tauri.app"], "required-header": { "name": "X-Tauri-Attest", "value": "token" }, "certificate-pin": "sha256/AAAA" } }"#,
+    )
+    .unwrap();
+    let mut capabilities = remote_capability(vec![RemoteDomain::Pattern("tauri.app".into())]);
+    capabilities.get_mut("remote").unwrap().context = context;
+
+    let resolved = Resolved::resolve(ping_acl(), capabilities.clone(), Target::current()).unwrap();
+    let contexts = resolved
+      .allowed_commands
+      .keys()
+      .map(|key| &key.context)
+      .collect::<Vec<_>>();
+    assert_eq!(
+      contexts,
+      [&ExecutionContext::Remote {
+        domain: Pattern::new("tauri.app").unwrap(),
+        attestation: Some(RemoteAttestation {
+          required_header: Some(RequiredHeader {
+            name: "X-Tauri-Attest".into(),
+            value: "token".into(),
+          }),
+          certificate_pin: Some("sha256/AAAA".into()),
+        }),
+      }]
+    );
+
+    for (header, pin) in [("X Attest", "sha256/AAAA"), ("X-Attest", "AAAA")] {
+      let mut capabilities = capabilities.clone();
+      if let CapabilityContext::Remote { attestation, .. } =
+        &mut capabilities.get_mut("remote").unwrap().context
+      {
+        attestation.required_header.as_mut().unwrap().name = header.into();
+        attestation.certificate_pin = Some(pin.into());
+      }
+      assert!(matches!(
+        Resolved::resolve(ping_acl(), capabilities, Target::current()),
+        Err(Error::InvalidRemoteAttestation { capability, .. }) if capability == "remote"
+      ));
+    }
+  }
+
+  #[test]
+  fn resolved_builder() {
+    let scope = |path: &str| ResolvedScope {
+      allow: vec![serde_json::json!({ "path": path }).into()],
+      deny: Vec::new(),
+      external: None,
+    };
+    let resolved = Resolved::builder()
+      .allow_command("plugin:fs|read", ExecutionContext::Local, ["main"])
+      .unwrap()
+      .allow_command_scoped(
+        "plugin:fs|read",
+        ExecutionContext::Local,
+        ["main", "editor-*"],
+        scope("$HOME"),
+      )
+      .unwrap()
+      .allow_command_scoped(
+        "plugin:fs|read",
+        ExecutionContext::Local,
+        ["editor-*"],
+        scope("$TEMP"),
+      )
+      .unwrap()
+      .deny_command(
+        "plugin:fs|read",
+        ExecutionContext::Local,
+        ["editor-untrusted"],
+      )
+      .unwrap()
+      .global_scope("fs", scope("$APPDATA"))
+      .global_scope("fs", scope("$CACHE"))
+      .window_global_scope("fs", ["editor-*"], scope("$DOCUMENT"))
+      .unwrap()
+      .build();
+
+    let key = CommandKey {
+      name: "plugin:fs|read".into(),
+      context: ExecutionContext::Local,
+    };
+    let command = &resolved.allowed_commands[&key];
+    assert_eq!(
+      command.windows,
+      [
+        WindowPattern::new("main").unwrap(),
+        WindowPattern::new("editor-*").unwrap()
+      ]
+    );
+    assert_eq!(command.window_scope_keys::<&str>("main", &[]), [0]);
+    assert_eq!(command.window_scope_keys::<&str>("editor-1", &[]), [0, 1]);
+    assert_eq!(resolved.command_scope.len(), 2);
+    assert!(resolved.denied_commands[&key].matches_window::<&str>("editor-untrusted", &[]));
+    let global_scope = &resolved.global_scope["fs"];
+    assert_eq!(global_scope.len(), 2);
+    assert_eq!(global_scope[0].scope.allow.len(), 2);
+    assert!(global_scope[0].matches_window::<&str>("main", &[]));
+    assert!(!global_scope[1].matches_window::<&str>("main", &[]));
+    assert!(global_scope[1].matches_window::<&str>("editor-1", &[]));
+
+    let error = Resolved::builder()
+      .allow_command("plugin:fs|read", ExecutionContext::Local, ["main-[*"])
+      .unwrap_err();
+    assert!(matches!(
+      error,
+      Error::InvalidCommandWindowPattern { command, window, .. } if command == "plugin:fs|read" && window == "main-[*"
+    ));
+  }
+
+  #[test]
+  fn plugin_windows() {
+    let mut acl = ping_acl();
+    acl.extend(manifests([(
+      "oauth",
+      r#"
+window-prefixes = ["oauth-", "popup["]
+
+[[permission]]
+identifier = "allow-authorize"
+commands.allow = ["authorize"]
+"#,
+    )]));
+
+    let mut capabilities = remote_capability(Vec::new());
+    let capability = capabilities.get_mut("remote").unwrap();
+    capability.context = CapabilityContext::Local;
+    capability.windows = serde_json::from_str(r#"[{ "plugin-windows": "oauth" }]"#).unwrap();
+
+    let resolved = Resolved::resolve(acl.clone(), capabilities.clone(), Target::current()).unwrap();
+    let command = resolved.allowed_commands.values().next().unwrap();
+    assert!(command.matches_window::<&str>("oauth-google", &[]));
+    assert!(command.matches_window::<&str>("popup[1]", &[]));
+    assert!(!command.matches_window::<&str>("popup1", &[]));
+    assert!(!command.matches_window::<&str>("main", &[]));
+
+    for (plugin, exists) in [("ping", true), ("oauth2", false)] {
+      capabilities.get_mut("remote").unwrap().windows = vec![CapabilityWindow::PluginWindows {
+        plugin_windows: plugin.into(),
+      }];
+      assert!(matches!(
+        Resolved::resolve(acl.clone(), capabilities.clone(), Target::current()),
+        Err(Error::UnknownPluginWindows { plugin: p, plugin_exists, .. }) if p == plugin && plugin_exists == exists
+      ));
+    }
+  }
+
+  #[test]
+  fn invalid_remote_domains() {
+    for domain in [
+      "",
+      "**",
+      "**.example.com",
+      "https://example.com",
+      "example.com/*",
+    ] {
+      let err = Resolved::resolve(
+        ping_acl(),
+        remote_capability(vec![RemoteDomain::Pattern(domain.into())]),
+        Target::current(),
+      )
+      .unwrap_err();
+      assert!(
+        matches!(err, Error::InvalidRemoteDomain { .. }),
+        "unexpected error for {domain:?}: {err}"
+      );
+    }
+  }
+
+  #[test]
+  fn scope_sizes_report() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+scope.allow = [{ path = "$HOME" }, { path = "$APP" }]
+scope.deny = [{ path = "$HOME/.ssh" }]
+
+[[permission]]
+identifier = "global"
+scope.allow = [{ path = "$TEMP" }]
+"#,
+    )]);
+    let capability = capability("main", &["fs:allow-read", "fs:global"]);
+    let capabilities = capabilities([capability]);
+
+    let (_resolved, report) =
+      Resolved::resolve_with_report(acl, capabilities, Target::current()).unwrap();
+    let fs = &report.scope_sizes["fs"];
+    assert_eq!(fs.global_scope.entries, 1);
+    assert_eq!(fs.global_scope.serialized_size, r#"{"path":"$TEMP"}"#.len());
+    assert_eq!(fs.commands["read"].entries, 3);
+  }
+
+  #[test]
+  fn remote_local_resources_warning() {
+    let acl = manifests([(
+      "path",
+      r#"
+[[permission]]
+identifier = "allow-resolve"
+commands.allow = ["resolve", "resolve_directory"]
+returns = ["path", "handle"]
+
+[[permission]]
+identifier = "allow-join"
+commands.allow = ["join"]
+"#,
+    )]);
+
+    let mut capabilities = remote_capability(vec![
+      RemoteDomain::Pattern("tauri.app".into()),
+      RemoteDomain::Domain {
+        domain: "github.com".into(),
+        include_subdomains: true,
+      },
+    ]);
+    capabilities.get_mut("remote").unwrap().permissions = ["path:allow-resolve", "path:allow-join"]
+      .into_iter()
+      .map(|p| PermissionEntry::PermissionRef(p.to_string().try_into().unwrap()))
+      .collect();
+
+    let remote_local_resources = |report: ResolutionReport| {
+      report
+        .warnings
+        .into_iter()
+        .filter(|w| w.code == WARNING_REMOTE_LOCAL_RESOURCES)
+        .collect::<Vec<_>>()
+    };
+
+    let (_resolved, report) =
+      Resolved::resolve_with_report(acl.clone(), capabilities.clone(), Target::current()).unwrap();
+    let warnings = remote_local_resources(report);
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].severity, WarningSeverity::High);
+    assert_eq!(
+      warnings[0].message,
+      "capability remote grants path:allow-resolve to remote domains tauri.app, github.com, *.github.com but its commands plugin:path|resolve, plugin:path|resolve_directory return local path and handle identifiers"
+    );
+
+    // local capabilities can use them
+    capabilities.get_mut("remote").unwrap().context = CapabilityContext::Local;
+    let (_resolved, report) =
+      Resolved::resolve_with_report(acl, capabilities, Target::current()).unwrap();
+    assert!(remote_local_resources(report).is_empty());
+  }
+
+  #[test]
+  fn remote_matches_all_warning() {
+    let (_resolved, report) = Resolved::resolve_with_report(
+      ping_acl(),
+      remote_capability(vec![RemoteDomain::Pattern("*".into())]),
+      Target::current(),
+    )
+    .unwrap();
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].code, WARNING_REMOTE_MATCHES_ALL);
+  }
+
+  fn http_acl_and_capability() -> (BTreeMap<String, Manifest>, BTreeMap<String, Capability>) {
+    let acl = manifests([(
+      "http",
+      r#"
+[[permission]]
+identifier = "allow-fetch"
+commands.allow = ["fetch"]
+scope.allow = [{ url = "https://EXAMPLE.com/API" }]
+"#,
+    )]);
+    let capability = capability("main", &["http:allow-fetch"]);
+    let capabilities = capabilities([capability]);
+    (acl, capabilities)
+  }
+
+  #[test]
+  fn scope_merge_key() {
+    let permissions = |merge_key: &str| {
+      format!(
+        r#"
+{merge_key}
+
+[[permission]]
+identifier = "allow-fetch"
+commands.allow = ["fetch"]
+scope.allow = [{{ url = "https://tauri.app", headers = {{ accept = "text/html" }} }}]
+"#
+      )
+    };
+    let capabilities: BTreeMap<String, Capability> = [
+      r#"{ "identifier": "a", "windows": ["main"], "permissions": [{ "identifier": "http:allow-fetch", "allow": [{ "url": "https://tauri.app", "headers": { "x-a": "1" }, "methods": ["GET", "POST"] }, "https://github.com"] }] }"#,
+      r#"{ "identifier": "b", "windows": ["main"], "permissions": [{ "identifier": "http:allow-fetch", "allow": [{ "methods": ["GET"], "headers": { "x-a": "2" }, "url": "https://tauri.app" }] }] }"#,
+    ]
+    .into_iter()
+    .map(|capability| {
+      let capability: Capability = serde_json::from_str(capability).unwrap();
+      (capability.identifier.clone(), capability)
+    })
+    .collect();
+
+    let allowed_scope = |merge_key: &str| -> Vec<serde_json::Value> {
+      let acl = manifests([("http", permissions(merge_key))]);
+      let resolved = Resolved::resolve(acl, capabilities.clone(), Target::current()).unwrap();
+      assert_eq!(resolved.command_scope.len(), 1);
+      let scope = resolved.command_scope.values().next().unwrap();
+      scope.allow.iter().cloned().map(Into::into).collect()
+    };
+
+    assert_eq!(
+      allowed_scope(r#"merge-key = "url""#),
+      [
+        serde_json::json!({
+          "url": "https://tauri.app",
+          "headers": { "accept": "text/html", "x-a": "2" },
+          "methods": ["GET"]
+        }),
+        serde_json::json!("https://github.com"),
+      ]
+    );
+    // without a merge key, the entries are kept as is
+    assert_eq!(allowed_scope("").len(), 5);
+  }
+
+  #[test]
+  fn simulate_capability() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+
+[[permission]]
+identifier = "allow-write"
+commands.allow = ["write"]
+"#,
+    )]);
+    let capability =
+      |json: serde_json::Value| -> Capability { serde_json::from_value(json).unwrap() };
+
+    let main = capability(serde_json::json!({
+      "identifier": "main",
+      "windows": ["main", "editor-*"],
+      "permissions": [{ "identifier": "fs:allow-read", "allow": [{ "path": "$APPDATA/**" }] }]
+    }));
+    let resolved = Resolved::resolve(
+      acl.clone(),
+      [(main.identifier.clone(), main.clone())]
+        .into_iter()
+        .collect(),
+      Target::current(),
+    )
+    .unwrap();
+    let before = format!("{resolved:?}");
+
+    // duplicating existing grants, including a window already matched by a pattern
+    let duplicate = capability(serde_json::json!({
+      "identifier": "duplicate",
+      "windows": ["editor-1"],
+      "permissions": [{ "identifier": "fs:allow-read", "allow": [{ "path": "$APPDATA/**" }] }]
+    }));
+    let delta = resolved
+      .simulate(&acl, &duplicate, Target::current())
+      .unwrap();
+    assert!(delta.is_empty(), "{delta:?}");
+
+    let widening = capability(serde_json::json!({
+      "identifier": "widening",
+      "windows": ["main", "settings"],
+      "permissions": [
+        "fs:allow-write",
+        { "identifier": "fs:allow-read", "allow": [{ "path": "$HOME/**" }] }
+      ]
+    }));
+    let delta = resolved
+      .simulate(&acl, &widening, Target::current())
+      .unwrap();
+    assert_eq!(
+      delta.new_commands,
+      [CommandDelta {
+        command: "plugin:fs|write".into(),
+        remote: None,
+        windows: vec!["main".into(), "settings".into()],
+        scope: Vec::new(),
+      }]
+    );
+    assert_eq!(
+      delta.widened_commands,
+      [CommandDelta {
+        command: "plugin:fs|read".into(),
+        remote: None,
+        windows: vec!["settings".into()],
+        scope: vec![serde_json::json!({ "path": "$HOME/**" }).into()],
+      }]
+    );
+    assert!(delta.global_scopes.is_empty());
+    assert_eq!(
+      serde_json::to_value(&delta).unwrap()["widenedCommands"][0]["scope"],
+      serde_json::json!([{ "path": "$HOME/**" }])
+    );
+
+    // the simulation does not change the resolved ACL
+    assert_eq!(format!("{resolved:?}"), before);
+  }
+
+  #[test]
+  fn preview_capability_partially() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+[[permission.scope.allow]]
+path = "$RESOURCE/**"
+
+[[permission]]
+identifier = "deny-write"
+commands.deny = ["write"]
+
+[[permission]]
+identifier = "allow-home"
+[[permission.scope.allow]]
+path = "$HOME/**"
+"#,
+    )]);
+    let capability: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "draft",
+      "windows": ["main", { "parent-of": "main" }],
+      "permissions": [
+        { "identifier": "fs:allow-read", "deny": [{ "path": "$RESOURCE/*.key" }] },
+        "fs:allow-raed",
+        "fs:deny-write",
+        "fs:allow-home",
+        "http:default"
+      ]
+    }))
+    .unwrap();
+
+    let preview = preview_capability(&capability, &acl, Target::current());
+    assert_eq!(preview.identifier, "draft");
+    assert_eq!(
+      preview.commands,
+      [CommandPreview {
+        command: "plugin:fs|read".into(),
+        remote: None,
+        windows: vec![
+          "main".into(),
+          CapabilityWindow::ParentOf {
+            parent_of: "main".into()
+          }
+        ],
+        scope: PreviewScope {
+          allow: vec![serde_json::json!({ "path": "$RESOURCE/**" }).into()],
+          deny: vec![serde_json::json!({ "path": "$RESOURCE/*.key" }).into()],
+        },
+      }]
+    );
+    assert_eq!(preview.denied_commands, ["plugin:fs|write"]);
+    assert_eq!(
+      preview.global_scopes["fs"].allow,
+      [serde_json::json!({ "path": "$HOME/**" }).into()]
+    );
+    // the unknown permission and plugin are soft diagnostics
+    assert_eq!(
+      preview
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect::<Vec<_>>(),
+      ["unknown-permission", "unknown-plugin"]
+    );
+
+    // the preview matches the commands granted by the resolution of the valid capability
+    let mut valid = capability.clone();
+    valid
+      .permissions
+      .retain(|entry| !matches!(entry.identifier().get(), "fs:allow-raed" | "http:default"));
+    let delta = Resolved::default()
+      .simulate(&acl, &valid, Target::current())
+      .unwrap();
+    assert_eq!(
+      delta
+        .new_commands
+        .iter()
+        .map(|command| (&command.command, &command.windows))
+        .collect::<Vec<_>>(),
+      preview
+        .commands
+        .iter()
+        .map(|command| (&command.command, &command.windows))
+        .collect::<Vec<_>>()
+    );
+    assert!(Resolved::default()
+      .simulate(&acl, &capability, Target::current())
+      .is_err());
+
+    // an invalid capability has no partial preview
+    let mut invalid = capability;
+    invalid.windows = vec!["[main".into()];
+    let preview = preview_capability(&invalid, &acl, Target::current());
+    assert!(preview.commands.is_empty());
+    assert_eq!(
+      preview
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code)
+        .collect::<Vec<_>>(),
+      ["invalid-window-pattern"]
+    );
+  }
+
+  #[test]
+  fn delegated_permissions() {
+    let fs = r#"
+[[permission]]
+identifier = "allow-read-file"
+commands.allow = ["read_file"]
+scope.allow = [{ path = "$APPDATA/**" }]
+delegable = true
+
+[[permission]]
+identifier = "allow-remove"
+commands.allow = ["remove"]
+"#;
+    let project = |reference: &str| {
+      format!(
+        r#"
+[default]
+permissions = ["allow-open", "{reference}"]
+
+[[permission]]
+identifier = "allow-open"
+commands.allow = ["open"]
+"#
+      )
+    };
+    let capabilities: BTreeMap<String, Capability> = [(
+      "main".to_string(),
+      serde_json::from_str(
+        r#"{ "identifier": "main", "windows": ["main"], "permissions": ["project:default"] }"#,
+      )
+      .unwrap(),
+    )]
+    .into_iter()
+    .collect();
+    let resolve = |reference: &str| {
+      let acl = manifests([("fs", fs.to_string()), ("project", project(reference))]);
+      Resolved::resolve(acl, capabilities.clone(), Target::current())
+    };
+
+    let resolved = resolve("fs:allow-read-file").unwrap();
+    let read_file = resolved
+      .allowed_commands
+      .iter()
+      .find(|(key, _)| key.name == "plugin:fs|read_file")
+      .map(|(_, command)| command)
+      .expect("delegated command is allowed");
+    assert_eq!(read_file.scopes.len(), 1);
+    assert!(resolved
+      .allowed_commands
+      .keys()
+      .any(|key| key.name == "plugin:project|open"));
+    #[cfg(debug_assertions)]
+    assert_eq!(
+      read_file.referenced_by,
+      [ResolvedCommandReference {
+        capability: "main".into(),
+        permission: "fs:allow-read-file".into(),
+        delegated_by: Some("project:default".into()),
+      }]
+    );
+
+    assert!(matches!(
+      resolve("shell:allow-execute").as_ref().map_err(Error::root),
+      Err(Error::DelegatedPluginNotFound { plugin, .. }) if plugin == "shell"
+    ));
+    assert!(matches!(
+      resolve("fs:allow-write-file").as_ref().map_err(Error::root),
+      Err(Error::DelegatedPermissionNotFound { permission, .. }) if permission == "fs:allow-write-file"
+    ));
+    assert!(matches!(
+      resolve("fs:allow-remove").as_ref().map_err(Error::root),
+      Err(Error::PermissionNotDelegable { set, permission }) if set == "project:default" && permission == "fs:allow-remove"
+    ));
+  }
+
+  #[test]
+  fn scope_values_stay_with_their_plugin() {
+    let acl = manifests([
+      (
+        "fs",
+        r#"
+[[permission]]
+identifier = "scope-appdata"
+scope.allow = [{ path = "$APPDATA/**" }]
+delegable = true
+"#,
+      ),
+      (
+        "project",
+        r#"
+[default]
+permissions = ["allow-open", "fs:scope-appdata"]
+
+[[permission]]
+identifier = "allow-open"
+commands.allow = ["open"]
+"#,
+      ),
+    ]);
+    let capabilities = [(
+      "main".to_string(),
+      serde_json::from_value(serde_json::json!({
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": [
+          { "identifier": "project:default", "allow": [{ "url": "https://tauri.app" }] }
+        ]
+      }))
+      .unwrap(),
+    )]
+    .into_iter()
+    .collect();
+
+    let (resolved, report) =
+      Resolved::resolve_with_report(acl, capabilities, Target::current()).unwrap();
+
+    // the url is written for the project plugin, the delegated fs global scope does not get it
+    assert_eq!(resolved.global_scope.keys().collect::<Vec<_>>(), ["fs"]);
+    assert_eq!(
+      flatten_global_scope(&resolved.global_scope["fs"]).allow,
+      [Value::Map(
+        [("path".into(), Value::String("$APPDATA/**".into()))].into()
+      )]
+    );
+    let open = resolved
+      .allowed_commands
+      .iter()
+      .find(|(key, _)| key.name == "plugin:project|open")
+      .map(|(_, command)| command)
+      .unwrap();
+    assert_eq!(
+      resolved.command_scope[&open.scopes[0].scope].allow,
+      [Value::Map(
+        [("url".into(), Value::String("https://tauri.app".into()))].into()
+      )]
+    );
+    let warnings = report
+      .warnings
+      .iter()
+      .filter(|w| w.code == WARNING_CROSS_PLUGIN_SCOPE)
+      .map(|w| w.message.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      warnings,
+      [
+        "capability main extends project:default with scope values of plugin project, \
+        they are not applied to the permissions it delegates to fs"
+      ]
+    );
+  }
+
+  #[test]
+  fn shared_scopes() {
+    let acl = manifests([
+      (
+        "fs",
+        r#"
+[[permission]]
+identifier = "scope-appdata"
+scope.allow = [{ path = "$APPDATA/**" }]
+"#,
+      ),
+      (
+        "store",
+        r#"
+shares-scope-with = ["fs", "store", "sql"]
+
+[[permission]]
+identifier = "allow-save"
+commands.allow = ["save"]
+"#,
+      ),
+    ]);
+    let capabilities = [(
+      "main".to_string(),
+      serde_json::from_value(serde_json::json!({
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": ["fs:default", "store:default"]
+      }))
+      .unwrap(),
+    )]
+    .into_iter()
+    .collect();
+
+    let (resolved, report) =
+      Resolved::resolve_with_report(acl, capabilities, Target::current()).unwrap();
+
+    assert_eq!(
+      resolved.shares_scope_with,
+      [("store".to_string(), vec!["fs".to_string()])].into()
+    );
+    // the shared scope is not copied to the plugin reading it
+    assert_eq!(resolved.global_scope.keys().collect::<Vec<_>>(), ["fs"]);
+    assert_eq!(
+      report
+        .warnings
+        .iter()
+        .filter(|w| w.code == WARNING_UNKNOWN_SHARED_SCOPE)
+        .map(|w| w.message.as_str())
+        .collect::<Vec<_>>(),
+      ["plugin store shares the scope of unknown plugin sql"]
+    );
+  }
+
+  fn lowercase_url_hosts(
+    _plugin: &str,
+    _command: Option<&str>,
+    scope: &mut ResolvedScope,
+  ) -> Result<(), String> {
+    for entry in scope.allow.iter_mut().chain(scope.deny.iter_mut()) {
+      if let Value::Map(entry) = entry {
+        if let Some(Value::String(url)) = entry.get_mut("url") {
+          let (scheme, rest) = url.split_once("://").ok_or("invalid url")?;
+          let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+          *url = format!("{scheme}://{}{path}", host.to_lowercase());
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn fetch_scope_urls(resolved: &Resolved) -> Vec<String> {
+    let command = resolved
+      .allowed_commands
+      .iter()
+      .find(|(key, _)| key.name == "plugin:http|fetch")
+      .map(|(_, command)| command)
+      .unwrap();
+    resolved.command_scope[&command.scopes[0].scope]
+      .allow
+      .iter()
+      .filter_map(|entry| match entry {
+        Value::Map(entry) => match entry.get("url") {
+          Some(Value::String(url)) => Some(url.clone()),
+          _ => None,
+        },
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn scope_hooks_rewrite_scopes() {
+    let (acl, capabilities) = http_acl_and_capability();
+    let mut hooks = ScopeHooks::new();
+    hooks.insert("http", lowercase_url_hosts);
+
+    let (resolved, report) =
+      Resolved::resolve_with_hooks(acl.clone(), capabilities.clone(), Target::current(), &hooks)
+        .unwrap();
+    assert_eq!(fetch_scope_urls(&resolved), vec!["https://example.com/API"]);
+
+    // the codegen applies the persisted overrides without running the hooks
+    let mut codegen_resolved = Resolved::resolve(acl, capabilities, Target::current()).unwrap();
+    assert_eq!(
+      fetch_scope_urls(&codegen_resolved),
+      vec!["https://EXAMPLE.com/API"]
+    );
+    let overrides: ScopeOverrides =
+      serde_json::from_str(&serde_json::to_string(&report.scope_overrides).unwrap()).unwrap();
+    codegen_resolved.apply_scope_overrides(&overrides);
+    assert_eq!(
+      fetch_scope_urls(&codegen_resolved),
+      vec!["https://example.com/API"]
+    );
+  }
+
+  #[test]
+  fn scope_overrides_use_unused_keys() {
+    let (mut acl, capabilities) = http_acl_and_capability();
+    acl.extend(manifests([(
+      "http",
+      r#"
+[[permission]]
+identifier = "allow-fetch"
+commands.allow = ["fetch", "fetch_send"]
+scope.allow = [{ url = "https://EXAMPLE.com/API" }]
+
+[[permission]]
+identifier = "allow-cancel"
+commands.allow = ["fetch_cancel"]
+scope.allow = [{ url = "https://example.com/cancel" }]
+"#,
+    )]));
+    let mut capabilities = capabilities;
+    capabilities
+      .get_mut("main")
+      .unwrap()
+      .permissions
+      .push(PermissionEntry::PermissionRef(
+        "http:allow-cancel".to_string().try_into().unwrap(),
+      ));
+
+    let mut resolved = Resolved::resolve(acl, capabilities, Target::current()).unwrap();
+    let scope_key = |resolved: &Resolved, name: &str| {
+      resolved
+        .allowed_commands
+        .iter()
+        .find(|(key, _)| key.name == name)
+        .unwrap()
+        .1
+        .scopes[0]
+        .scope
+    };
+    let previous_keys = resolved.command_scope.keys().copied().collect::<Vec<_>>();
+    let fetch_key = scope_key(&resolved, "plugin:http|fetch");
+    let cancel_scope = format!(
+      "{:?}",
+      resolved.command_scope[&scope_key(&resolved, "plugin:http|fetch_cancel")]
+    );
+
+    let rewritten = ResolvedScope {
+      allow: vec![Value::String("rewritten".into())],
+      deny: Vec::new(),
+      external: None,
+    };
+    let overrides = ScopeOverrides {
+      commands: [(
+        "http".to_string(),
+        [(
+          "fetch".to_string(),
+          [(fetch_key, rewritten.clone())].into_iter().collect(),
+        )]
+        .into_iter()
+        .collect(),
+      )]
+      .into_iter()
+      .collect(),
+      global: Default::default(),
+    };
+    resolved.apply_scope_overrides(&overrides);
+
+    // the rewritten scope never replaces a scope of the store
+    let rewritten_key = scope_key(&resolved, "plugin:http|fetch");
+    assert!(!previous_keys.contains(&rewritten_key));
+    assert_eq!(
+      format!("{:?}", resolved.command_scope[&rewritten_key]),
+      format!("{rewritten:?}")
+    );
+    assert_eq!(scope_key(&resolved, "plugin:http|fetch_send"), fetch_key);
+    assert_eq!(
+      format!(
+        "{:?}",
+        resolved.command_scope[&scope_key(&resolved, "plugin:http|fetch_cancel")]
+      ),
+      cancel_scope
+    );
+  }
+
+  #[test]
+  fn scope_hook_error() {
+    let (acl, capabilities) = http_acl_and_capability();
+    let mut hooks = ScopeHooks::new();
+    hooks.insert(
+      "http",
+      |_plugin: &str, _command: Option<&str>, _scope: &mut ResolvedScope| {
+        Err("unsupported url".to_string())
+      },
+    );
+
+    match Resolved::resolve_with_hooks(acl, capabilities, Target::current(), &hooks) {
+      Err(Error::ScopeHook {
+        plugin, command, ..
+      }) => {
+        assert_eq!(plugin, "http");
+        assert_eq!(command.as_deref(), Some("fetch"));
+      }
+      other => panic!("unexpected resolution result {:?}", other.map(|(_, r)| r)),
+    }
+  }
+
+  #[test]
+  fn permission_classes_validators_and_args_are_merged() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["open"]
+class = "fs-read"
+args = [{ name = "path", kind = "path-scope" }]
+
+[[permission]]
+identifier = "allow-write"
+commands.allow = ["open"]
+class = "fs-write"
+validator = "strip-pii"
+args = [
+  { name = "path", kind = "path-scope" },
+  { name = "mode", kind = "enum", values = ["read", "write"], optional = true },
+]
+
+[[permission]]
+identifier = "allow-exists"
+commands.allow = ["open"]
+"#,
+    )]);
+    let capability = Capability {
+      permissions: ["fs:allow-write", "fs:allow-read", "fs:allow-exists"]
+        .into_iter()
+        .map(|p| PermissionEntry::PermissionRef(p.to_string().try_into().unwrap()))
+        .collect(),
+      ..capability("main", &[])
+    };
+    let capabilities = capabilities([capability]);
+
+    let resolved = Resolved::resolve(acl, capabilities, Target::current()).unwrap();
+    let (_key, command) = resolved.allowed_commands.first_key_value().unwrap();
+    assert_eq!(command.classes, vec!["fs-read", "fs-write"]);
+    assert_eq!(command.validators, vec!["strip-pii"]);
+    assert_eq!(
+      command
+        .args
+        .iter()
+        .map(|rule| rule.name.as_str())
+        .collect::<Vec<_>>(),
+      vec!["path", "mode"]
+    );
+  }
+
+  #[test]
+  fn invalid_command_name() {
+    let acl = manifests([(
+      "ping",
+      r#"
+[[permission]]
+identifier = "allow-ping"
+commands.allow = ["ping|pong"]
+"#,
+    )]);
+
+    match Resolved::resolve(acl, default_capability("ping"), Target::current())
+      .as_ref()
+      .map_err(Error::root)
+    {
+      Err(Error::InvalidCommand {
+        permission,
+        command,
+        error,
+      }) => {
+        assert_eq!(permission, "allow-ping");
+        assert_eq!(command, "ping|pong");
+        assert_eq!(*error, ParseCommandIdError::ContainsSeparator);
+      }
+      other => panic!("unexpected resolution result {other:?}"),
+    }
+  }
+
+  #[test]
+  fn command_patterns() {
+    let acl = |patterns: &str| -> BTreeMap<String, Manifest> {
+      manifests([(
+        "fs",
+        &format!(
+          r#"
+[default]
+permissions = ["allow-read"]
+
+[[permission]]
+identifier = "allow-read-file"
+commands.allow = ["read_file"]
+
+[[permission]]
+identifier = "allow-read-dir"
+commands.allow = ["read_dir"]
+
+[[permission]]
+identifier = "allow-write-file"
+commands.allow = ["write_file"]
+
+[[permission]]
+identifier = "allow-read"
+commands.allow = [{patterns}]
+"#
+        ),
+      )])
+    };
+
+    let resolved = Resolved::resolve(
+      acl(r#""read_*", "read_file""#),
+      default_capability("fs"),
+      Target::current(),
+    )
+    .unwrap();
+    assert_eq!(
+      resolved
+        .allowed_commands
+        .keys()
+        .map(|key| key.name.as_str())
+        .collect::<Vec<_>>(),
+      ["plugin:fs|read_dir", "plugin:fs|read_file"]
+    );
+
+    match Resolved::resolve(
+      acl(r#""remove_*""#),
+      default_capability("fs"),
+      Target::current(),
+    )
+    .as_ref()
+    .map_err(Error::root)
+    {
+      Err(Error::UnmatchedCommandPattern {
+        plugin,
+        permission,
+        command,
+      }) => {
+        assert_eq!(plugin, "fs");
+        assert_eq!(permission, "allow-read");
+        assert_eq!(command, "remove_*");
+      }
+      other => panic!("unexpected resolution result {other:?}"),
+    }
+
+    assert!(matches!(
+      Resolved::resolve(
+        acl(r#""read_[""#),
+        default_capability("fs"),
+        Target::current()
+      )
+      .as_ref()
+      .map_err(Error::root),
+      Err(Error::InvalidCommandPattern { .. })
+    ));
+  }
+
+  #[test]
+  fn plugin_commands() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[default]
+permissions = ["allow-read-file", "allow-read-dir", "deny-remove"]
+
+[[permission]]
+identifier = "allow-read-file"
+commands.allow = ["read_file"]
+
+[[permission]]
+identifier = "allow-read-dir"
+commands.allow = ["read_dir"]
+scope.allow = [{ path = "$APPDATA" }]
+
+[[permission]]
+identifier = "allow-write-file"
+commands.allow = ["write_file"]
+
+[[permission]]
+identifier = "deny-remove"
+commands.deny = ["remove"]
+"#,
+    )]);
+
+    let resolved = Resolved::resolve(acl, default_capability("fs"), Target::current()).unwrap();
+    let commands = resolved.plugin_commands("fs");
+
+    let status = |command: &str| {
+      commands
+        .iter()
+        .find(|status| status.command == command)
+        .unwrap()
+    };
+    assert_eq!(status("read_file").status, CommandStatus::Granted);
+    assert_eq!(status("read_file").windows, ["main"]);
+    assert!(!status("read_file").scoped);
+    assert!(status("read_dir").scoped);
+    assert_eq!(status("remove").status, CommandStatus::Denied);
+    #[cfg(debug_assertions)]
+    assert_eq!(status("write_file").status, CommandStatus::NotGranted);
+
+    assert_eq!(
+      serde_json::to_value(status("read_dir")).unwrap(),
+      serde_json::json!({
+        "command": "read_dir",
+        "status": "granted",
+        "windows": ["main"],
+        "scoped": true
+      })
+    );
+    assert!(resolved.plugin_commands("ping").is_empty());
+  }
+
+  #[test]
+  fn default_grants() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[default]
+permissions = ["read"]
+
+[[set]]
+identifier = "read"
+description = "read access"
+permissions = ["allow-read-file", "allow-read-dir"]
+
+[[permission]]
+identifier = "allow-read-file"
+commands.allow = ["read_file"]
+
+[[permission]]
+identifier = "allow-read-dir"
+commands.allow = ["read_dir"]
+
+[[permission]]
+identifier = "allow-write-file"
+commands.allow = ["write_file"]
+"#,
+    )]);
+    let capability: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": ["fs:default", "fs:allow-write-file", "fs:allow-read-dir"],
+    }))
+    .unwrap();
+    let capabilities = capabilities([capability]);
+
+    let (resolved, report) =
+      Resolved::resolve_with_report(acl, capabilities, Target::current()).unwrap();
+    let via_default = resolved
+      .allowed_commands
+      .iter()
+      .map(|(key, command)| (key.name.as_str(), command.via_default))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      via_default,
+      [
+        ("plugin:fs|read_dir", false),
+        ("plugin:fs|read_file", true),
+        ("plugin:fs|write_file", false)
+      ]
+    );
+    assert_eq!(
+      report.grants["fs"],
+      GrantCounts {
+        via_default: 1,
+        explicit: 2
+      }
+    );
+  }
+
+  #[test]
+  fn required_scope() {
+    let acl = manifests([(
+      "http",
+      r#"
+[default]
+permissions = ["allow-fetch"]
+
+[[permission]]
+identifier = "allow-fetch"
+commands.allow = ["fetch"]
+requires_scope = true
+
+[[permission]]
+identifier = "allow-localhost"
+scope.allow = [{ url = "http://localhost" }]
+"#,
+    )]);
+    let capability = |permissions: serde_json::Value| {
+      let capability: Capability = serde_json::from_value(serde_json::json!({
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": permissions,
+      }))
+      .unwrap();
+      capabilities([capability])
+    };
+
+    let err = Resolved::resolve(
+      acl.clone(),
+      capability(serde_json::json!(["http:default"])),
+      Target::current(),
+    )
+    .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      r#"command plugin:http|fetch requires a scope but capability main allows it without one, reference http:default with a scope instead, e.g. { "identifier": "http:default", "allow": [...] }"#
+    );
 
-      let global_scope = map_lit(
-        quote! { ::std::collections::BTreeMap },
-        &self.global_scope,
-        str_lit,
-        identity,
+    for permissions in [
+      serde_json::json!([{ "identifier": "http:default", "allow": [{ "any": true }] }]),
+      serde_json::json!(["http:default", "http:allow-localhost"]),
+    ] {
+      Resolved::resolve(acl.clone(), capability(permissions), Target::current()).unwrap();
+    }
+
+    // a deny-only scope or the global scope of other windows does not satisfy the requirement
+    let mut capabilities = capability(serde_json::json!([
+      "http:default",
+      { "identifier": "http:allow-fetch", "deny": [{ "url": "http://localhost/admin" }] }
+    ]));
+    let other: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "other",
+      "windows": ["other"],
+      "permissions": ["http:allow-localhost"],
+    }))
+    .unwrap();
+    capabilities.insert(other.identifier.clone(), other.clone());
+    let err = Resolved::resolve(acl.clone(), capabilities.clone(), Target::current()).unwrap_err();
+    assert!(matches!(err, Error::MissingRequiredScope { .. }));
+
+    // a global scope of a window pattern covers the windows it matches
+    let mut wildcard = other;
+    wildcard.windows = vec!["*".into()];
+    capabilities.insert(wildcard.identifier.clone(), wildcard);
+    Resolved::resolve(acl, capabilities, Target::current()).unwrap();
+  }
+
+  #[test]
+  fn permission_platforms() {
+    let acl = manifests([(
+      "opener",
+      r#"
+[default]
+permissions = ["allow-open", "allow-share"]
+
+[[permission]]
+identifier = "allow-open"
+commands.allow = ["open"]
+
+[[permission]]
+identifier = "allow-share"
+commands.allow = ["share"]
+platforms = ["android", "iOS"]
+"#,
+    )]);
+    let capability = |permissions: serde_json::Value| {
+      let capability: Capability = serde_json::from_value(serde_json::json!({
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": permissions,
+      }))
+      .unwrap();
+      capabilities([capability])
+    };
+    let commands = |resolved: &Resolved| {
+      resolved
+        .allowed_commands
+        .keys()
+        .map(|key| key.name.clone())
+        .collect::<Vec<_>>()
+    };
+
+    // sets skip the unavailable permissions silently
+    let (resolved, report) = Resolved::resolve_with_report(
+      acl.clone(),
+      capability(serde_json::json!(["opener:default"])),
+      Target::Linux,
+    )
+    .unwrap();
+    assert_eq!(commands(&resolved), ["plugin:opener|open"]);
+    assert!(report.warnings.is_empty());
+
+    let (resolved, _) = Resolved::resolve_with_report(
+      acl.clone(),
+      capability(serde_json::json!(["opener:default"])),
+      Target::Android,
+    )
+    .unwrap();
+    assert_eq!(
+      commands(&resolved),
+      ["plugin:opener|open", "plugin:opener|share"]
+    );
+
+    // direct references are skipped with a warning
+    let (resolved, report) = Resolved::resolve_with_report(
+      acl.clone(),
+      capability(serde_json::json!([
+        "opener:allow-open",
+        "opener:allow-share"
+      ])),
+      Target::Linux,
+    )
+    .unwrap();
+    assert_eq!(commands(&resolved), ["plugin:opener|open"]);
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(
+      report.warnings[0].code,
+      WARNING_PERMISSION_UNAVAILABLE_ON_TARGET
+    );
+    assert_eq!(
+      report.warnings[0].message,
+      "capability main references opener:allow-share which is not available on linux, skipping it"
+    );
+
+    // but a capability must not end up empty
+    let err = Resolved::resolve(
+      acl,
+      capability(serde_json::json!(["opener:allow-share"])),
+      Target::Linux,
+    )
+    .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "capability main only references permissions that are not available on linux"
+    );
+  }
+
+  #[test]
+  fn permission_set_operators() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[default]
+permissions = ["allow-read-file", "allow-write-file", "allow-remove", "allow-stat"]
+
+[[set]]
+identifier = "remote-safe"
+description = "Commands safe for remote content."
+permissions = ["allow-read-file", "allow-stat", "allow-exists"]
+
+[[set]]
+identifier = "read-only"
+description = "Default without the writes."
+permissions = ["default"]
+exclude = ["allow-write-file", "allow-remove"]
+
+[[set]]
+identifier = "safe-remote"
+description = "Default restricted to the remote safe commands."
+permissions = ["default"]
+intersect = ["remote-safe"]
+
+[[set]]
+identifier = "safe-read-file"
+description = "Nested sets with both operators."
+permissions = ["read-only", "allow-exists"]
+intersect = ["remote-safe"]
+exclude = ["safe-stat"]
+
+[[set]]
+identifier = "safe-stat"
+description = "Stat only."
+permissions = ["safe-remote"]
+intersect = ["allow-stat"]
+
+[[set]]
+identifier = "unknown-operand"
+description = "Excludes a permission that does not exist."
+permissions = ["default"]
+exclude = ["allow-missing"]
+
+[[set]]
+identifier = "cycle"
+description = "Excludes a set including itself."
+permissions = ["default"]
+exclude = ["includes-cycle"]
+
+[[set]]
+identifier = "includes-cycle"
+description = "Includes a set excluding it."
+permissions = ["cycle"]
+
+[[permission]]
+identifier = "allow-read-file"
+commands.allow = ["read_file"]
+
+[[permission]]
+identifier = "allow-write-file"
+commands.allow = ["write_file"]
+
+[[permission]]
+identifier = "allow-remove"
+commands.allow = ["remove"]
+
+[[permission]]
+identifier = "allow-stat"
+commands.allow = ["stat"]
+
+[[permission]]
+identifier = "allow-exists"
+commands.allow = ["exists"]
+"#,
+    )]);
+    let resolve = |permission: &str| {
+      let capability: Capability = serde_json::from_value(serde_json::json!({
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": [permission],
+      }))
+      .unwrap();
+      Resolved::resolve(acl.clone(), capabilities([capability]), Target::Linux).map(|resolved| {
+        resolved
+          .allowed_commands
+          .keys()
+          .map(|key| key.name.trim_start_matches("plugin:fs|").to_string())
+          .collect::<Vec<_>>()
+      })
+    };
+
+    assert_eq!(resolve("fs:read-only").unwrap(), ["read_file", "stat"]);
+    assert_eq!(resolve("fs:safe-remote").unwrap(), ["read_file", "stat"]);
+    assert_eq!(resolve("fs:safe-stat").unwrap(), ["stat"]);
+    assert_eq!(
+      resolve("fs:safe-read-file").unwrap(),
+      ["exists", "read_file"]
+    );
+
+    let error = resolve("fs:unknown-operand").unwrap_err();
+    assert!(matches!(
+      error.root(),
+      Error::SetOperandNotFound { permission, operator, set }
+        if permission == "allow-missing" && operator == "exclude" && set == "fs:unknown-operand"
+    ));
+    assert_eq!(
+      error.to_string(),
+      "in capability main: permission allow-missing referenced by the exclude operator of set fs:unknown-operand not found"
+    );
+
+    let error = resolve("fs:cycle").unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "in capability main: permission set fs:cycle references itself: cycle -> includes-cycle -> cycle"
+    );
+    assert_eq!(error.code(), "permission-set-cycle");
+    assert_eq!(error.capability(), Some("main"));
+  }
+
+  #[test]
+  fn deprecated_permissions() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[default]
+permissions = ["read-all"]
+
+[[set]]
+identifier = "read-all"
+description = "Read everything."
+permissions = ["allow-read"]
+
+[[set]]
+identifier = "legacy"
+description = "Legacy commands."
+permissions = ["allow-read"]
+deprecated = { since = "2.0.0", note = "split into narrower sets" }
+
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read", "stat"]
+deprecated = { since = "2.1.0", replaced-by = ["allow-read-file", "opener:allow-stat"] }
+
+[[permission]]
+identifier = "allow-read-file"
+commands.allow = ["read"]
+"#,
+    )]);
+    let resolve = |permissions: serde_json::Value| {
+      let capability: Capability = serde_json::from_value(serde_json::json!({
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": permissions,
+      }))
+      .unwrap();
+      Resolved::resolve_with_report(acl.clone(), capabilities([capability]), Target::Linux)
+        .unwrap()
+        .1
+    };
+    let messages = |report: &ResolutionReport| {
+      report
+        .warnings
+        .iter()
+        .filter(|w| w.code == WARNING_DEPRECATED_PERMISSION)
+        .map(|w| w.message.clone())
+        .collect::<Vec<_>>()
+    };
+
+    // direct reference, reported once per capability
+    let report = resolve(serde_json::json!(["fs:allow-read", "fs:allow-read"]));
+    assert_eq!(
+      messages(&report),
+      ["capability main references fs:allow-read, which is deprecated since 2.1.0, use fs:allow-read-file, opener:allow-stat instead"]
+    );
+    assert_eq!(report.deprecations.len(), 1);
+    assert!(report.deprecations[0].via.is_empty());
+
+    // through nested permission sets
+    let report = resolve(serde_json::json!(["fs:default"]));
+    assert_eq!(
+      messages(&report),
+      ["capability main references fs:allow-read through fs:default > fs:read-all, which is deprecated since 2.1.0, use fs:allow-read-file, opener:allow-stat instead"]
+    );
+    assert_eq!(report.deprecations[0].via, ["fs:default", "fs:read-all"]);
+
+    // deprecated set along with its deprecated permission
+    let report = resolve(serde_json::json!(["fs:legacy"]));
+    assert_eq!(
+      messages(&report),
+      [
+        "capability main references fs:legacy, which is deprecated since 2.0.0: split into narrower sets",
+        "capability main references fs:allow-read through fs:legacy, which is deprecated since 2.1.0, use fs:allow-read-file, opener:allow-stat instead"
+      ]
+    );
+
+    // the replacements and `*` are not reported
+    assert!(messages(&resolve(serde_json::json!(["fs:allow-read-file"]))).is_empty());
+    assert!(messages(&resolve(serde_json::json!(["fs:*"]))).is_empty());
+  }
+
+  #[test]
+  fn window_ancestor_selectors() {
+    let capability: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "popups",
+      "windows": ["settings", { "parent-of": "main" }, { "descendant-of": "editor-*" }],
+      "permissions": ["ping:allow-ping"]
+    }))
+    .unwrap();
+    let capabilities = capabilities([capability]);
+
+    let resolved = Resolved::resolve(ping_acl(), capabilities, Target::current()).unwrap();
+    let command = resolved.allowed_commands.values().next().unwrap();
+    assert_eq!(command.windows, [WindowPattern::new("settings").unwrap()]);
+    assert_eq!(command.window_ancestors.len(), 2);
+
+    // main > popup > preview, only main is named in the parent-of selector
+    assert!(!command.matches_window("main", &[] as &[&str]));
+    assert!(command.matches_window("popup", &["main"]));
+    assert!(!command.matches_window("preview", &["popup", "main"]));
+    // editor-1 > popup > preview, only editor-1 is matched by the descendant-of selector
+    assert!(!command.matches_window("editor-1", &[] as &[&str]));
+    assert!(command.matches_window("popup", &["editor-1"]));
+    assert!(command.matches_window("preview", &["popup", "editor-1"]));
+  }
+
+  #[test]
+  fn webview_selectors() {
+    let capability: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "partner",
+      "context": { "remote": { "domains": ["partner.com"] } },
+      "windows": [{ "webview": "partner" }, { "webview": "preview-*", "window": "editor-*" }],
+      "permissions": [{ "identifier": "ping:allow-ping", "config": { "user-agent-suffix": "partner" } }]
+    }))
+    .unwrap();
+    assert_eq!(
+      serde_json::to_value(&capability.windows).unwrap(),
+      serde_json::json!([
+        { "webview": "partner" },
+        { "webview": "preview-*", "window": "editor-*" }
+      ])
+    );
+    let capabilities = capabilities([capability]);
+
+    let mut resolved = Resolved::resolve(ping_acl(), capabilities, Target::current()).unwrap();
+    resolved.minimize();
+    let command = resolved.allowed_commands.values().next().unwrap();
+    assert!(command.windows.is_empty() && command.window_ancestors.is_empty());
+    assert_eq!(command.webviews.len(), 2);
+
+    // the selected webviews only, not the other webviews of their windows
+    assert!(command.matches_webview("main", "partner"));
+    assert!(!command.matches_webview("main", "other"));
+    assert!(!command.matches_window::<&str>("partner", &[]));
+    assert!(command.matches_webview("editor-1", "preview-1"));
+    assert!(!command.matches_webview("main", "preview-1"));
+    assert_eq!(command.webview_configs("main", "partner").len(), 1);
+    assert!(command.webview_configs("main", "other").is_empty());
+
+    let origins = resolved.remote_origins();
+    assert_eq!(origins.len(), 1);
+    assert!(origins[0].windows.is_empty());
+    assert_eq!(origins[0].webviews, command.webviews);
+  }
+
+  #[test]
+  fn webview_inherits_attenuated_window_grants() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+scope.allow = [{ path = "$APPDATA/public/*" }, { path = "$APPDATA/**" }]
+
+[[permission]]
+identifier = "allow-write"
+commands.allow = ["write"]
+"#,
+    )]);
+    let capabilities = |sidebar: serde_json::Value| {
+      capabilities(
+        [
+          serde_json::json!({
+            "identifier": "main",
+            "windows": ["main"],
+            "permissions": [
+              { "identifier": "fs:allow-read", "config": { "theme": "dark" } },
+              "fs:allow-write"
+            ]
+          }),
+          sidebar,
+        ]
+        .into_iter()
+        .map(|capability| serde_json::from_value::<Capability>(capability).unwrap()),
+      )
+    };
+
+    let resolved = Resolved::resolve(
+      acl.clone(),
+      capabilities(serde_json::json!({
+        "identifier": "sidebar",
+        "windows": [{ "webview": "sidebar", "window": "main" }],
+        "permissions": [],
+        "inherit-from-window": true,
+        "attenuate": {
+          "deny-commands": ["plugin:fs|write*"],
+          "scope-filter": { "path": ["$APPDATA/public/**"] }
+        }
+      })),
+      Target::current(),
+    )
+    .unwrap();
+    assert_eq!(resolved.inheriting_webviews.len(), 1);
+    let command = |name: &str| {
+      resolved
+        .allowed_commands
+        .iter()
+        .find(|(key, _)| key.name == name)
+        .unwrap()
+        .1
+    };
+
+    // the write command is not inherited
+    assert!(command("plugin:fs|read").matches_webview("main", "sidebar"));
+    assert!(!command("plugin:fs|write").matches_webview("main", "sidebar"));
+
+    // the window keeps its scope, the webview only gets the filtered values
+    let read = command("plugin:fs|read");
+    let scope_values = |keys: Vec<ScopeKey>| {
+      keys
+        .iter()
+        .flat_map(|key| &resolved.command_scope[key].allow)
+        .map(|value| serde_json::to_value(value).unwrap())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      scope_values(read.window_scope_keys::<&str>("main", &[])).len(),
+      2
+    );
+    assert_eq!(
+      scope_values(read.webview_scope_keys("main", "sidebar")),
+      [serde_json::json!({ "path": "$APPDATA/public/*" })]
+    );
+    // the filtered scope gets an unused key instead of replacing a scope of the store
+    let window_keys = read.window_scope_keys::<&str>("main", &[]);
+    for key in read.webview_scope_keys("main", "sidebar") {
+      assert!(!window_keys.contains(&key));
+    }
+    assert_eq!(resolved.command_scope.len(), 2);
+    assert_eq!(read.webview_configs("main", "sidebar").len(), 1);
+
+    // without attenuation, the webview gets the same scopes as its window
+    let resolved = Resolved::resolve(
+      acl.clone(),
+      capabilities(serde_json::json!({
+        "identifier": "sidebar",
+        "windows": [{ "webview": "sidebar", "window": "main" }],
+        "permissions": [],
+        "inherit-from-window": true
+      })),
+      Target::current(),
+    )
+    .unwrap();
+    let write = resolved
+      .allowed_commands
+      .iter()
+      .find(|(key, _)| key.name == "plugin:fs|write")
+      .unwrap()
+      .1;
+    assert!(write.matches_webview("main", "sidebar"));
+
+    // only webview selectors inherit, and the attenuation requires the inheritance
+    for sidebar in [
+      serde_json::json!({
+        "identifier": "sidebar",
+        "windows": ["sidebar"],
+        "permissions": [],
+        "inherit-from-window": true
+      }),
+      serde_json::json!({
+        "identifier": "sidebar",
+        "windows": [{ "webview": "sidebar", "window": "main" }],
+        "permissions": [],
+        "attenuate": { "deny-commands": ["plugin:fs|write"] }
+      }),
+      serde_json::json!({
+        "identifier": "sidebar",
+        "windows": [{ "webview": "sidebar", "window": "main" }],
+        "permissions": [],
+        "inherit-from-window": true,
+        "attenuate": { "deny-commands": ["plugin:fs|[write"] }
+      }),
+    ] {
+      let error = Resolved::resolve(acl.clone(), capabilities(sidebar), Target::current())
+        .err()
+        .unwrap();
+      assert!(matches!(error, Error::InvalidInheritance { .. }), "{error}");
+    }
+  }
+
+  #[test]
+  fn unicode_window_labels() {
+    // "café" spelled with a precomposed `é` (NFC) and with `e` followed by a combining acute accent (NFD)
+    const NFC: &str = "caf\u{e9}";
+    const NFD: &str = "cafe\u{301}";
+
+    // (pattern, label, matches)
+    let matrix = [
+      (NFC, NFC, true),
+      (NFC, NFD, true),
+      (NFD, NFC, true),
+      (NFD, NFD, true),
+      // `?` matches the whole `é` once normalized
+      ("caf?", NFD, true),
+      ("cafe?", NFD, false),
+      ("caf\u{e9}-*", "cafe\u{301}-\u{1f600}", true),
+      (
+        "editor-*",
+        "editor-\u{43f}\u{440}\u{43e}\u{435}\u{43a}\u{442}",
+        true,
+      ),
+      ("\u{7de8}\u{96c6}-?", "\u{7de8}\u{96c6}-1", true),
+      ("\u{7de8}\u{96c6}-?", "\u{7de8}\u{96c6}-12", false),
+      // the matching is case-sensitive
+      (NFC, "CAF\u{c9}", false),
+      ("editor-*", "Editor-1", false),
+      ("Settings", "Settings", true),
+      ("Settings", "settings", false),
+    ];
+
+    for (pattern, label, matches) in matrix {
+      let resolved = Resolved::builder()
+        .allow_command("plugin:ping|ping", ExecutionContext::Local, [pattern])
+        .unwrap()
+        .build();
+      let command = resolved.allowed_commands.values().next().unwrap();
+      assert_eq!(
+        command.matches_window::<&str>(label, &[]),
+        matches,
+        "pattern {pattern:?} on label {label:?}"
       );
 
-      #[cfg(debug_assertions)]
-      {
-        literal_struct!(
-          tokens,
-          ::tauri::utils::acl::resolved::Resolved,
-          acl,
-          allowed_commands,
-          denied_commands,
-          command_scope,
-          global_scope
+      let capability: Capability = serde_json::from_value(serde_json::json!({
+        "identifier": "unicode",
+        "windows": [{ "parent-of": pattern }],
+        "permissions": ["ping:allow-ping"]
+      }))
+      .unwrap();
+      let capabilities = capabilities([capability]);
+      let resolved = Resolved::resolve(ping_acl(), capabilities, Target::current()).unwrap();
+      let command = resolved.allowed_commands.values().next().unwrap();
+      assert_eq!(
+        command.matches_window("popup", &[label]),
+        matches,
+        "parent pattern {pattern:?} on label {label:?}"
+      );
+    }
+
+    // the capability window patterns are stored normalized
+    let capability: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "unicode",
+      "windows": [NFD],
+      "permissions": ["ping:allow-ping"]
+    }))
+    .unwrap();
+    assert_eq!(
+      capability.window_patterns().unwrap(),
+      [WindowPattern::new(NFC).unwrap()]
+    );
+  }
+
+  #[test]
+  fn remote_origins() {
+    use crate::config::Csp;
+
+    let remote = |domain: &str| ExecutionContext::Remote {
+      domain: domain.parse().unwrap(),
+      attestation: None,
+    };
+    let resolved = Resolved::builder()
+      .allow_command("plugin:ping|ping", remote("tauri.app"), ["main"])
+      .unwrap()
+      .allow_command(
+        "plugin:ping|pong",
+        remote("tauri.app"),
+        ["main", "editor-*"],
+      )
+      .unwrap()
+      .allow_command("plugin:ping|ping", remote("*.tauri.app"), ["docs"])
+      .unwrap()
+      .allow_command("plugin:ping|ping", remote("tauri.*"), ["main"])
+      .unwrap()
+      .allow_command("plugin:ping|ping", ExecutionContext::Local, ["*"])
+      .unwrap()
+      .build();
+
+    let origins = resolved.remote_origins();
+    let summary = origins
+      .iter()
+      .map(|origin| {
+        (
+          origin.domain.as_str(),
+          origin.csp_source(),
+          origin
+            .windows
+            .iter()
+            .map(WindowPattern::as_str)
+            .collect::<Vec<_>>(),
         )
-      }
-      #[cfg(not(debug_assertions))]
-      literal_struct!(
-        tokens,
-        ::tauri::utils::acl::resolved::Resolved,
-        allowed_commands,
-        denied_commands,
-        command_scope,
-        global_scope
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      summary,
+      [
+        (
+          "*.tauri.app",
+          Some("https://*.tauri.app".to_string()),
+          vec!["docs"]
+        ),
+        ("tauri.*", None, vec!["main"]),
+        (
+          "tauri.app",
+          Some("https://tauri.app".to_string()),
+          vec!["main", "editor-*"]
+        ),
+      ]
+    );
+    assert!(origins[2].matches_window::<&str>("editor-1", &[]));
+    assert!(!origins[2].matches_window::<&str>("docs", &[]));
+
+    let allowed = |csp: &str| {
+      let csp = Csp::Policy(csp.into());
+      origins
+        .iter()
+        .filter(|origin| origin.is_allowed_by(&csp))
+        .map(|origin| origin.domain.as_str())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      allowed("script-src 'self'"),
+      ["*.tauri.app", "tauri.*", "tauri.app"]
+    );
+    assert_eq!(
+      allowed("default-src 'self' https://tauri.app"),
+      ["tauri.app"]
+    );
+    assert_eq!(
+      allowed("default-src 'self'; connect-src ipc: https://*.tauri.app"),
+      ["*.tauri.app"]
+    );
+    assert_eq!(
+      allowed("connect-src https:"),
+      ["*.tauri.app", "tauri.*", "tauri.app"]
+    );
+
+    let mut security = crate::config::SecurityConfig {
+      csp: Some(Csp::Policy("default-src 'self' https://tauri.app".into())),
+      ..Default::default()
+    };
+    let mut report = ResolutionReport::default();
+    report.check_connect_src(&resolved, &security);
+    assert_eq!(
+      report
+        .warnings
+        .iter()
+        .map(|warning| (warning.code, warning.message.as_str()))
+        .collect::<Vec<_>>(),
+      [(
+        WARNING_CSP_BLOCKS_REMOTE_ORIGINS,
+        "the CSP connect-src directive blocks the remote origins granted by the capabilities: *.tauri.app (windows docs); tauri.* (windows main)"
+      )]
+    );
+
+    // the runtime extends the CSP with the origins it can express as sources
+    security.capability_connect_src = true;
+    let mut report = ResolutionReport::default();
+    report.check_connect_src(&resolved, &security);
+    assert_eq!(report.warnings.len(), 1);
+    assert!(report.warnings[0]
+      .message
+      .ends_with(": tauri.* (windows main)"));
+  }
+
+  #[test]
+  fn invalid_window_pattern() {
+    let capability = Capability {
+      windows: vec!["main[".into()],
+      ..capability("editor", &["ping:allow-ping"])
+    };
+
+    let err = capability
+      .parse_window_patterns(Some(std::path::Path::new("capabilities/editor.json")))
+      .unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      r#"invalid window pattern "main[" in capability "editor" (capabilities/editor.json): Pattern syntax error near position 4: invalid range pattern"#
+    );
+
+    let capabilities = capabilities([capability]);
+    let err = Resolved::resolve(ping_acl(), capabilities, Target::current()).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      r#"invalid window pattern "main[" in capability "editor": Pattern syntax error near position 4: invalid range pattern"#
+    );
+  }
+
+  #[test]
+  fn window_label_templates() {
+    let capability = |windows: &[&str]| Capability {
+      windows: windows
+        .iter()
+        .map(|w| CapabilityWindow::Label(w.to_string()))
+        .collect(),
+      ..capability("editor", &["ping:allow-ping"])
+    };
+    let resolve = |windows: &[&str]| {
+      let capability = capability(windows);
+      let capabilities = capabilities([capability]);
+      Resolved::resolve(ping_acl(), capabilities, Target::current())
+    };
+
+    let resolved = resolve(&["editor-{uuid}", "doc-{int}"]).unwrap();
+    let command = resolved.allowed_commands.values().next().unwrap();
+    assert!(command.matches_window::<&str>("editor-67e55044-10b1-426f-9247-bb680e5fe0c8", &[]));
+    assert!(command.matches_window::<&str>("doc-42", &[]));
+    assert!(!command.matches_window::<&str>("editor-settings", &[]));
+    assert!(!command.matches_window::<&str>("doc-42a", &[]));
+
+    let err = resolve(&["editor-{guid}"]).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      r#"invalid window pattern "editor-{guid}" in capability "editor": unknown template parameter type "guid", expected one of uuid, int, hex or alnum"#
+    );
+  }
+
+  #[test]
+  fn scope_subtraction() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+scope.allow = [{ path = "$HOME" }, { path = "$APP" }]
+
+[[permission]]
+identifier = "deny-read"
+commands.deny = ["read"]
+"#,
+    )]);
+    let capability = |subtracted: &str| {
+      let capability = Capability {
+        permissions: vec![
+          PermissionEntry::PermissionRef("fs:allow-read".to_string().try_into().unwrap()),
+          serde_json::from_value(serde_json::json!({
+            "identifier": subtracted,
+            "subtract": true,
+            "deny": [{ "path": "$HOME" }, { "path": "$TEMP" }]
+          }))
+          .unwrap(),
+        ],
+        ..capability("main", &[])
+      };
+      capabilities([capability])
+    };
+
+    let (resolved, report) =
+      Resolved::resolve_with_report(acl.clone(), capability("fs:deny-read"), Target::current())
+        .unwrap();
+    assert!(resolved.denied_commands.is_empty());
+    let (_key, command) = resolved.allowed_commands.first_key_value().unwrap();
+    let scope = &resolved.command_scope[&command.scopes[0].scope];
+    assert_eq!(
+      serde_json::to_value(&scope.allow).unwrap(),
+      serde_json::json!([{ "path": "$APP" }])
+    );
+    assert!(scope.deny.is_empty());
+    // $TEMP is not allowed so subtracting it has no effect
+    assert_eq!(
+      report
+        .warnings
+        .iter()
+        .filter(|w| w.code == WARNING_UNUSED_SCOPE_SUBTRACTION)
+        .count(),
+      1
+    );
+
+    // the values are only subtracted from the scopes of the subtracting capability windows
+    let mut capabilities = capability("fs:deny-read");
+    let other: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "other",
+      "windows": ["other"],
+      "permissions": ["fs:allow-read"],
+    }))
+    .unwrap();
+    capabilities.insert(other.identifier.clone(), other);
+    let resolved = Resolved::resolve(acl.clone(), capabilities, Target::current()).unwrap();
+    let (_key, command) = resolved.allowed_commands.first_key_value().unwrap();
+    let allowed = |window: &str| {
+      command
+        .window_scope_keys::<&str>(window, &[])
+        .iter()
+        .flat_map(|key| &resolved.command_scope[key].allow)
+        .map(|value| serde_json::to_value(value).unwrap())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(allowed("main"), [serde_json::json!({ "path": "$APP" })]);
+    assert_eq!(
+      allowed("other"),
+      [
+        serde_json::json!({ "path": "$HOME" }),
+        serde_json::json!({ "path": "$APP" })
+      ]
+    );
+
+    assert!(matches!(
+      Resolved::resolve(acl, capability("fs:allow-read"), Target::current()),
+      Err(Error::SubtractingPermissionAllowsCommands { .. })
+    ));
+  }
+
+  #[test]
+  fn window_scopes() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+"#,
+    )]);
+    let capability = |identifier: &str, window: &str, path: &str| {
+      let capability = Capability {
+        permissions: vec![serde_json::from_value(serde_json::json!({
+          "identifier": "fs:allow-read",
+          "allow": [{ "path": path }]
+        }))
+        .unwrap()],
+        windows: vec![window.into()],
+        ..capability(identifier, &[])
+      };
+      (capability.identifier.clone(), capability)
+    };
+    let capabilities = [
+      capability("editor", "editor-*", "$DOC/**"),
+      capability("importer", "importer", "$TMP/**"),
+      capability("viewer", "viewer", "$DOC/**"),
+    ]
+    .into_iter()
+    .collect();
+
+    let resolved = Resolved::resolve(acl, capabilities, Target::current()).unwrap();
+    let (_key, command) = resolved.allowed_commands.first_key_value().unwrap();
+    assert_eq!(command.scopes.len(), 3);
+
+    let paths = |window: &str| {
+      command
+        .window_scope_keys(window, &[] as &[&str])
+        .iter()
+        .map(|key| &resolved.command_scope[key])
+        .sum::<ResolvedScope>()
+        .allow
+        .into_iter()
+        .map(|value| serde_json::to_value(value).unwrap()["path"].clone())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(paths("editor-1"), vec!["$DOC/**"]);
+    assert_eq!(paths("importer"), vec!["$TMP/**"]);
+    assert_eq!(paths("viewer"), vec!["$DOC/**"]);
+    assert!(paths("other").is_empty());
+  }
+
+  #[test]
+  fn window_global_scopes() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "scope-home"
+scope.allow = [{ path = "$HOME/**" }]
+
+[[permission]]
+identifier = "scope-appdata"
+scope.allow = [{ path = "$APPDATA/**" }]
+"#,
+    )]);
+    let capabilities = capabilities([
+      serde_json::json!({
+        "identifier": "main",
+        "windows": ["*"],
+        "permissions": ["fs:scope-appdata"]
+      }),
+      serde_json::json!({
+        "identifier": "admin",
+        "windows": ["admin"],
+        "permissions": ["fs:scope-home"]
+      }),
+      serde_json::json!({
+        "identifier": "admin-appdata",
+        "windows": ["admin"],
+        "permissions": [{ "identifier": "fs:scope-appdata", "allow": [{ "path": "$DOCUMENT/**" }] }]
+      }),
+    ]
+    .into_iter()
+    .map(|capability| serde_json::from_value::<Capability>(capability).unwrap())
+);
+
+    let resolved = Resolved::resolve(acl, capabilities, Target::current()).unwrap();
+    let scopes = &resolved.global_scope["fs"];
+    // capabilities on the same windows share an entry
+    assert_eq!(scopes.len(), 2);
+
+    let paths = |window: &str| {
+      let mut paths = scopes
+        .iter()
+        .filter(|scope| scope.matches_window(window, &[] as &[&str]))
+        .flat_map(|scope| &scope.scope.allow)
+        .map(|value| serde_json::to_value(value).unwrap()["path"].clone())
+        .collect::<Vec<_>>();
+      paths.sort_by_key(|path| path.to_string());
+      paths
+    };
+    assert_eq!(paths("main"), vec!["$APPDATA/**"]);
+    assert_eq!(
+      paths("admin"),
+      vec!["$APPDATA/**", "$APPDATA/**", "$DOCUMENT/**", "$HOME/**"]
+    );
+    assert_eq!(flatten_global_scope(scopes).allow.len(), 4);
+  }
+
+  #[test]
+  fn page_grants() {
+    let acl = || -> BTreeMap<String, Manifest> {
+      manifests([(
+        "fs",
+        r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+scope.allow = [{ path = "$APPDATA/**" }]
+
+[[permission]]
+identifier = "deny-write"
+commands.deny = ["write"]
+
+[[permission]]
+identifier = "scope-home"
+scope.allow = [{ path = "$HOME/**" }]
+"#,
+      )])
+    };
+    let capability = |value: serde_json::Value| {
+      let capability = serde_json::from_value::<Capability>(value).unwrap();
+      (capability.identifier.clone(), capability)
+    };
+    let capabilities = [
+      capability(serde_json::json!({
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": ["fs:allow-read"]
+      })),
+      capability(serde_json::json!({
+        "identifier": "settings",
+        "windows": ["settings"],
+        "pages": ["settings.html", "admin/*"],
+        "permissions": [
+          { "identifier": "fs:allow-read", "allow": [{ "path": "$DOCUMENT/**" }] },
+          "fs:deny-write",
+          "fs:scope-home"
+        ]
+      })),
+    ]
+    .into_iter()
+    .collect();
+
+    let resolved = Resolved::resolve(acl(), capabilities, Target::current()).unwrap();
+    let (_, read) = resolved
+      .allowed_commands
+      .iter()
+      .find(|(key, _)| key.name == "plugin:fs|read")
+      .unwrap();
+    // the page grants are not matched until the command is narrowed to a page
+    assert!(!read.matches_window("settings", &[] as &[&str]));
+    assert_eq!(read.pages.len(), 1);
+    let scopes = |page| {
+      let read = read.on_page(page);
+      let mut paths = read
+        .window_scope_keys("settings", &[] as &[&str])
+        .into_iter()
+        .flat_map(|key| resolved.command_scope[&key].allow.clone())
+        .map(|value| serde_json::to_value(value).unwrap()["path"].clone())
+        .collect::<Vec<_>>();
+      paths.sort_by_key(|path| path.to_string());
+      (read.matches_window("settings", &[] as &[&str]), paths)
+    };
+    assert_eq!(
+      scopes(Some("/settings.html")),
+      (true, vec!["$APPDATA/**".into(), "$DOCUMENT/**".into()])
+    );
+    assert_eq!(
+      scopes(Some("/admin/users.html")),
+      (true, vec!["$APPDATA/**".into(), "$DOCUMENT/**".into()])
+    );
+    assert_eq!(scopes(Some("/admin/users/edit.html")), (false, Vec::new()));
+    assert_eq!(scopes(Some("/")), (false, Vec::new()));
+    assert_eq!(scopes(None), (false, Vec::new()));
+    assert!(read
+      .on_page(Some("index.html"))
+      .matches_window("main", &[] as &[&str]));
+
+    // the denied commands apply on every page
+    let (_, write) = resolved.denied_commands.iter().next().unwrap();
+    assert!(write.pages.is_empty());
+    assert!(write.matches_window("settings", &[] as &[&str]));
+
+    let global_scope = &resolved.global_scope["fs"];
+    assert!(global_scope[0].matches_page(Some("settings.html")));
+    assert!(!global_scope[0].matches_page(Some("index.html")));
+
+    let remote = [capability(serde_json::json!({
+      "identifier": "remote",
+      "windows": ["main"],
+      "context": { "remote": { "domains": ["tauri.app"] } },
+      "pages": ["settings.html"],
+      "permissions": ["fs:allow-read"]
+    }))]
+    .into_iter()
+    .collect();
+    assert!(matches!(
+      Resolved::resolve(acl(), remote, Target::current()),
+      Err(Error::InvalidCapabilityPages { capability, .. }) if capability == "remote"
+    ));
+  }
+
+  #[test]
+  fn required_deny() {
+    let acl = manifests([(
+      "fs",
+      r#"
+required-deny = [{ path = "$APPDATA/.secrets/**" }]
+
+[default]
+permissions = ["allow-read", "allow-stat"]
+
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+scope.allow = [{ path = "$APPDATA/**" }]
+
+[[permission]]
+identifier = "allow-stat"
+commands.allow = ["stat"]
+"#,
+    )]);
+    // does not reference the default permission
+    let capability: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": [
+        { "identifier": "fs:allow-read", "allow": [{ "path": "$DOCUMENT/**" }] },
+        "fs:allow-stat"
+      ]
+    }))
+    .unwrap();
+    let capabilities: BTreeMap<String, Capability> =
+      [("main".to_string(), capability)].into_iter().collect();
+    let secrets = Value::from(serde_json::json!({ "path": "$APPDATA/.secrets/**" }));
+
+    // nor can a hook rewriting the scopes remove the deny entry
+    let mut hooks = ScopeHooks::new();
+    hooks.insert(
+      "fs",
+      |_: &str, _: Option<&str>, scope: &mut ResolvedScope| {
+        scope.deny.clear();
+        Ok(())
+      },
+    );
+    let (resolved, report) =
+      Resolved::resolve_with_hooks(acl, capabilities, Target::current(), &hooks).unwrap();
+
+    let read = resolved
+      .allowed_commands
+      .iter()
+      .find(|(key, _)| key.name == "plugin:fs|read")
+      .map(|(_, command)| command)
+      .unwrap();
+    assert_eq!(
+      resolved.command_scope[&read.scopes[0].scope].deny,
+      vec![secrets.clone()]
+    );
+
+    // the unscoped stat command gets the deny entry from the global scope of every window
+    let global_scope = &resolved.global_scope["fs"];
+    assert_eq!(global_scope.len(), 1);
+    assert!(global_scope[0].matches_window("other", &[] as &[&str]));
+    assert_eq!(global_scope[0].scope.deny, vec![secrets.clone()]);
+
+    // the overrides persisted for the codegen keep the deny entry too
+    assert!(report.scope_overrides.commands["fs"]["read"]
+      .values()
+      .all(|scope| scope.deny == vec![secrets.clone()]));
+    assert_eq!(report.scope_overrides.global["fs"][0].deny, vec![secrets]);
+  }
+
+  #[test]
+  fn scope_presets() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[presets]
+documents-read = [{ path = "$DOCUMENT/**" }]
+media-read = [{ path = "$PICTURES/**" }, { path = "$VIDEO/**" }]
+user-read = [{ preset = "documents-read" }, { preset = "media-read" }]
+secrets = [{ path = "$DOCUMENT/.ssh/**" }]
+
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+scope.allow = [{ path = "$APPDATA/**" }]
+
+[[permission]]
+identifier = "scope-files"
+"#,
+    )]);
+    let resolve = |permissions: serde_json::Value| {
+      let capability: Capability = serde_json::from_value(serde_json::json!({
+        "identifier": "main",
+        "windows": ["main"],
+        "permissions": permissions
+      }))
+      .unwrap();
+      Resolved::resolve(
+        acl.clone(),
+        [("main".to_string(), capability)].into_iter().collect(),
+        Target::current(),
+      )
+    };
+    let paths = |values: &[Value]| {
+      values
+        .iter()
+        .map(|value| match value {
+          Value::Map(map) => match &map["path"] {
+            Value::String(path) => path.clone(),
+            _ => panic!("unexpected path {value:?}"),
+          },
+          _ => panic!("unexpected scope entry {value:?}"),
+        })
+        .collect::<Vec<_>>()
+    };
+
+    let resolved = resolve(serde_json::json!([
+      {
+        "identifier": "fs:allow-read",
+        "allow": [{ "preset": "user-read" }, { "path": "$DESKTOP/**" }],
+        "deny": [{ "preset": "secrets" }]
+      },
+      { "identifier": "fs:scope-files", "allow": [{ "preset": "documents-read" }] }
+    ]))
+    .unwrap();
+
+    // nested presets are expanded in place
+    let read = resolved
+      .allowed_commands
+      .iter()
+      .find(|(key, _)| key.name == "plugin:fs|read")
+      .map(|(_, command)| command)
+      .unwrap();
+    let scope = &resolved.command_scope[&read.scopes[0].scope];
+    assert_eq!(
+      paths(&scope.allow),
+      [
+        "$APPDATA/**",
+        "$DOCUMENT/**",
+        "$PICTURES/**",
+        "$VIDEO/**",
+        "$DESKTOP/**"
+      ]
+    );
+    assert_eq!(paths(&scope.deny), ["$DOCUMENT/.ssh/**"]);
+    assert_eq!(
+      paths(&resolved.global_scope["fs"][0].scope.allow),
+      ["$DOCUMENT/**"]
+    );
+
+    let error = resolve(serde_json::json!([
+      { "identifier": "fs:allow-read", "allow": [{ "preset": "downloads-read" }] }
+    ]))
+    .unwrap_err();
+    assert!(matches!(
+      error.root(),
+      Error::UnknownScopePreset { plugin, preset, available }
+        if plugin == "fs" && preset == "downloads-read" && available.len() == 4
+    ));
+    assert_eq!(error.capability(), Some("main"));
+
+    let mut cyclic = acl["fs"].clone();
+    cyclic.presets.insert(
+      "documents-read".into(),
+      vec![serde_json::json!({ "preset": "user-read" }).into()],
+    );
+    assert!(matches!(
+      cyclic.validate_presets("fs"),
+      Err(Error::ScopePresetCycle { cycle, .. })
+        if cycle == ["documents-read", "user-read", "documents-read"]
+    ));
+  }
+
+  #[test]
+  fn external_scopes() {
+    let acl = manifests([(
+      "http",
+      r#"
+external-scope = true
+
+[[permission]]
+identifier = "allow-fetch"
+commands.allow = ["fetch"]
+scope.allow = [{ url = "https://tauri.app/*" }]
+
+[[permission]]
+identifier = "scope-blocklist"
+scope.deny = [{ url = "https://ads.example/*" }]
+"#,
+    )]);
+    let capability: Capability = serde_json::from_value(serde_json::json!({
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": ["http:allow-fetch", "http:scope-blocklist"]
+    }))
+    .unwrap();
+    let capabilities: BTreeMap<String, Capability> =
+      [("main".to_string(), capability)].into_iter().collect();
+    let plugins = external_scope_plugins(&acl);
+    assert_eq!(plugins, BTreeSet::from(["http".to_string()]));
+
+    let (mut resolved, report) =
+      Resolved::resolve_with_report(acl, capabilities, Target::current()).unwrap();
+    let external_scopes = resolved.externalize_scopes(&plugins);
+
+    let (_, fetch) = resolved.allowed_commands.first_key_value().unwrap();
+    let command_scope = &resolved.command_scope[&fetch.scopes[0].scope];
+    assert!(command_scope.allow.is_empty());
+    let external = command_scope.external.as_ref().unwrap();
+    assert!(external.key.starts_with("http/"));
+
+    let values = external_scopes.get(&external.key).unwrap();
+    assert_eq!(values.content_hash(), external.hash);
+    assert_eq!(
+      values.allow,
+      vec![Value::from(
+        serde_json::json!({ "url": "https://tauri.app/*" })
+      )]
+    );
+
+    let global_scope = &resolved.global_scope["http"][0].scope;
+    assert!(global_scope.deny.is_empty());
+    assert!(external_scopes
+      .get(&global_scope.external.as_ref().unwrap().key)
+      .is_some());
+
+    // the build script stores the values the code generation references
+    assert_eq!(
+      serde_json::to_value(&report.external_scopes).unwrap(),
+      serde_json::to_value(&external_scopes).unwrap()
+    );
+  }
+
+  #[test]
+  fn command_configs() {
+    let acl = manifests([(
+      "http",
+      r#"
+[[permission]]
+identifier = "allow-fetch"
+commands.allow = ["fetch"]
+
+[[permission]]
+identifier = "allow-fetch-cancel"
+commands.allow = ["fetch", "fetch_cancel"]
+
+[[set]]
+identifier = "fetch"
+description = "fetch"
+permissions = ["allow-fetch", "allow-fetch-cancel"]
+"#,
+    )]);
+    let capability = |identifier: &str, window: &str, entry: serde_json::Value| {
+      let capability = Capability {
+        permissions: vec![serde_json::from_value(entry).unwrap()],
+        windows: vec![window.into()],
+        ..capability(identifier, &[])
+      };
+      (capability.identifier.clone(), capability)
+    };
+    let capabilities = [
+      capability("main", "main", serde_json::json!("http:allow-fetch")),
+      capability(
+        "partner",
+        "partner-*",
+        serde_json::json!({
+          "identifier": "http:fetch",
+          "config": { "user-agent-suffix": "partner" }
+        }),
+      ),
+      capability(
+        "partner-beta",
+        "partner-beta",
+        serde_json::json!({
+          "identifier": "http:allow-fetch",
+          "config": { "user-agent-suffix": "beta" }
+        }),
+      ),
+    ]
+    .into_iter()
+    .collect();
+
+    let resolved = Resolved::resolve(acl, capabilities, Target::current()).unwrap();
+    let command = |name: &str| {
+      &resolved.allowed_commands[&CommandKey {
+        name: name.into(),
+        context: ExecutionContext::Local,
+      }]
+    };
+    let configs = |name: &str, window: &str| {
+      command(name)
+        .window_configs(window, &[] as &[&str])
+        .into_iter()
+        .map(|config| {
+          (
+            config.capability.as_str(),
+            serde_json::to_value(&config.config).unwrap()["user-agent-suffix"].clone(),
+          )
+        })
+        .collect::<Vec<_>>()
+    };
+
+    // the set expands to two permissions allowing fetch, its config is only listed once
+    assert_eq!(command("plugin:http|fetch").configs.len(), 2);
+    assert!(configs("plugin:http|fetch", "main").is_empty());
+    assert_eq!(
+      configs("plugin:http|fetch", "partner-beta"),
+      vec![
+        ("partner", "partner".into()),
+        ("partner-beta", "beta".into())
+      ]
+    );
+    assert_eq!(
+      configs("plugin:http|fetch_cancel", "partner-1"),
+      vec![("partner", "partner".into())]
+    );
+  }
+
+  #[test]
+  fn allow_deny_overlap() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+
+[[permission]]
+identifier = "deny-read"
+commands.deny = ["read"]
+"#,
+    )]);
+    let capability = |identifier: &str, window: &str, permission: &str| {
+      let capability = Capability {
+        permissions: vec![PermissionEntry::PermissionRef(
+          permission.to_string().try_into().unwrap(),
+        )],
+        windows: vec![window.into()],
+        ..capability(identifier, &[])
+      };
+      (capability.identifier.clone(), capability)
+    };
+    let overlaps = |allow: &str, deny: &str| {
+      let capabilities = [
+        capability("allow", allow, "fs:allow-read"),
+        capability("deny", deny, "fs:deny-read"),
+      ]
+      .into_iter()
+      .collect();
+      let (_resolved, report) =
+        Resolved::resolve_with_report(acl.clone(), capabilities, Target::current()).unwrap();
+      report
+        .warnings
+        .into_iter()
+        .filter(|w| w.code == WARNING_ALLOW_DENY_OVERLAP)
+        .map(|w| w.message)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      overlaps("win-admin", "win-*"),
+      ["command plugin:fs|read is allowed on `win-admin` and denied on `win-*`; the deny takes precedence at runtime, on every window"]
+    );
+    assert_eq!(overlaps("main", "main").len(), 1);
+    assert!(overlaps("main", "win-*").is_empty());
+    assert!(overlaps("admin", "win-admin").is_empty());
+    assert_eq!(overlaps("win-*", "*-admin").len(), 1);
+    assert!(overlaps("editor-*", "win-*").is_empty());
+  }
+
+  #[test]
+  fn all_permissions() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+
+[[permission]]
+identifier = "deny-read"
+commands.deny = ["read"]
+
+[[permission]]
+identifier = "allow-write"
+commands.allow = ["write"]
+
+[[permission]]
+identifier = "deny-write"
+commands.deny = ["write"]
+
+[[set]]
+identifier = "read"
+description = "read access"
+permissions = ["allow-read", "deny-write"]
+"#,
+    )]);
+    let resolve = |entry: serde_json::Value| {
+      let capability = Capability {
+        permissions: vec![serde_json::from_value(entry).unwrap()],
+        ..capability("main", &[])
+      };
+      let capabilities = capabilities([capability]);
+      let (resolved, report) =
+        Resolved::resolve_with_report(acl.clone(), capabilities, Target::current()).unwrap();
+      let names = |commands: &BTreeMap<CommandKey, ResolvedCommand>| {
+        commands
+          .keys()
+          .map(|key| key.name.clone())
+          .collect::<Vec<_>>()
+      };
+      let warnings = report
+        .warnings
+        .into_iter()
+        .filter(|w| w.code != WARNING_MISSING_DEFAULT_PERMISSION)
+        .collect::<Vec<_>>();
+      (
+        names(&resolved.allowed_commands),
+        names(&resolved.denied_commands),
+        warnings,
       )
+    };
+
+    let (allowed, denied, warnings) = resolve(serde_json::json!("fs:*"));
+    assert_eq!(allowed, ["plugin:fs|read", "plugin:fs|write"]);
+    assert!(denied.is_empty());
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].code, WARNING_ALL_PERMISSIONS);
+    assert_eq!(
+      warnings[0].message,
+      "capability main grants all 2 permissions of plugin fs"
+    );
+
+    let (allowed, denied, warnings) =
+      resolve(serde_json::json!({ "identifier": "fs:*", "include-deny": true }));
+    assert_eq!(allowed, ["plugin:fs|read", "plugin:fs|write"]);
+    assert_eq!(denied, ["plugin:fs|read", "plugin:fs|write"]);
+    assert_eq!(
+      warnings[0].message,
+      "capability main grants all 4 permissions of plugin fs, including its deny- permissions"
+    );
+  }
+
+  #[test]
+  fn glob_patterns_overlap() {
+    let overlap = |a: &str, b: &str| {
+      assert_eq!(patterns_overlap(a, b), patterns_overlap(b, a));
+      patterns_overlap(a, b)
+    };
+
+    assert!(overlap("main", "main"));
+    assert!(!overlap("main", "other"));
+    assert!(overlap("win-admin", "win-*"));
+    assert!(!overlap("admin", "win-*"));
+    assert!(overlap("win-1", "win-[0-9]"));
+    assert!(!overlap("win-a", "win-[0-9]"));
+    assert!(overlap("win-*", "*-admin"));
+    assert!(overlap("*", "editor-*"));
+    assert!(!overlap("editor-*", "win-*"));
+    assert!(!overlap("*-editor", "*-viewer"));
+    assert!(overlap("doc-42", "doc-{int}"));
+    assert!(!overlap("doc-settings", "doc-{int}"));
+    assert!(overlap("doc-*", "doc-{int}"));
+    assert!(!overlap("editor-{uuid}", "doc-{int}"));
+  }
+
+  fn default_capability(plugin: &str) -> BTreeMap<String, Capability> {
+    let capability = Capability {
+      permissions: vec![PermissionEntry::PermissionRef(
+        format!("{plugin}:default").try_into().unwrap(),
+      )],
+      ..capability("main", &[])
+    };
+    capabilities([capability])
+  }
+
+  #[test]
+  fn default_falls_back_to_single_permission() {
+    let (resolved, report) =
+      Resolved::resolve_with_report(ping_acl(), default_capability("ping"), Target::current())
+        .unwrap();
+    assert!(report.warnings.is_empty());
+    assert!(resolved
+      .allowed_commands
+      .keys()
+      .any(|key| key.name == "plugin:ping|ping"));
+  }
+
+  #[test]
+  fn plugin_version_requirements() {
+    let acl = |version: &str, permissions: &str| {
+      let mut acl = ping_acl();
+      let manifest = acl.get_mut("ping").unwrap();
+      *manifest = manifests([("ping", permissions)]).remove("ping").unwrap();
+      manifest.version = Some(version.into());
+      acl
+    };
+    let v1 = acl(
+      "1.4.0",
+      r#"
+[[permission]]
+identifier = "allow-ping"
+commands.allow = ["ping"]
+"#,
+    );
+    let v2 = acl(
+      "2.1.0",
+      r#"
+[[permission]]
+identifier = "allow-ping"
+commands.allow = ["ping"]
+
+[[permission]]
+identifier = "allow-pong"
+commands.allow = ["pong"]
+"#,
+    );
+
+    let mut capabilities = default_capability("ping");
+    let capability = capabilities.get_mut("main").unwrap();
+    capability.permissions = vec![PermissionEntry::PermissionRef(
+      "ping:allow-pong".to_string().try_into().unwrap(),
+    )];
+    capability
+      .plugin_version
+      .insert("ping".into(), ">=2.0, <3".into());
+
+    let resolved = Resolved::resolve(v2.clone(), capabilities.clone(), Target::current()).unwrap();
+    assert!(resolved
+      .allowed_commands
+      .keys()
+      .any(|key| key.name == "plugin:ping|pong"));
+
+    match Resolved::resolve(v1.clone(), capabilities.clone(), Target::current()) {
+      Err(Error::PluginVersionMismatch {
+        capability,
+        plugin,
+        requirement,
+        version,
+      }) => {
+        assert_eq!(capability, "main");
+        assert_eq!(plugin, "ping");
+        assert_eq!(requirement, ">=2.0, <3");
+        assert_eq!(version, "1.4.0");
+      }
+      other => panic!("expected a plugin version mismatch, got {other:?}"),
     }
+
+    // the requirement of the entry takes precedence over the one of the capability
+    let capability = capabilities.get_mut("main").unwrap();
+    capability.permissions = vec![serde_json::from_value(serde_json::json!({
+      "identifier": "ping:allow-ping",
+      "plugin-version": "1"
+    }))
+    .unwrap()];
+    assert!(Resolved::resolve(v1.clone(), capabilities.clone(), Target::current()).is_ok());
+    assert!(matches!(
+      Resolved::resolve(v2, capabilities.clone(), Target::current()),
+      Err(Error::PluginVersionMismatch { .. })
+    ));
+
+    // plugins of an unknown version are not checked
+    let mut unknown = v1.clone();
+    unknown.get_mut("ping").unwrap().version = None;
+    capabilities
+      .get_mut("main")
+      .unwrap()
+      .plugin_version
+      .insert("ping".into(), "3".into());
+    assert!(Resolved::resolve(unknown, capabilities.clone(), Target::current()).is_ok());
+
+    capabilities.get_mut("main").unwrap().permissions = vec![PermissionEntry::PermissionRef(
+      "ping:allow-ping".to_string().try_into().unwrap(),
+    )];
+    capabilities
+      .get_mut("main")
+      .unwrap()
+      .plugin_version
+      .insert("ping".into(), "not a version".into());
+    assert!(matches!(
+      Resolved::resolve(v1, capabilities, Target::current()),
+      Err(Error::InvalidPluginVersionRequirement { .. })
+    ));
+  }
+
+  #[test]
+  fn multi_segment_prefix() {
+    let acl = manifests([(
+      "core:window",
+      r#"
+[[permission]]
+identifier = "allow-set-title"
+commands.allow = ["set_title"]
+"#,
+    )]);
+
+    let mut capabilities = default_capability("core:window");
+    for capability in capabilities.values_mut() {
+      capability.permissions = vec![PermissionEntry::PermissionRef(
+        "core:window:allow-set-title"
+          .to_string()
+          .try_into()
+          .unwrap(),
+      )];
+    }
+
+    // the manifest is looked up by the joined prefix
+    let resolved = Resolved::resolve(acl, capabilities, Target::current()).unwrap();
+    assert!(resolved
+      .allowed_commands
+      .keys()
+      .any(|key| key.name == "plugin:core:window|set_title"));
+  }
+
+  #[test]
+  fn missing_default_permission() {
+    let acl = manifests([(
+      "fs",
+      r#"
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+
+[[permission]]
+identifier = "allow-write"
+commands.allow = ["write"]
+"#,
+    )]);
+
+    match Resolved::resolve_with_report(acl.clone(), default_capability("fs"), Target::current())
+      .as_ref()
+      .map_err(Error::root)
+    {
+      Err(Error::MissingDefaultPermission { plugin, available }) => {
+        assert_eq!(plugin, "fs");
+        assert_eq!(available, "fs:allow-read, fs:allow-write");
+      }
+      other => panic!("unexpected resolution result {:?}", other.map(|(_, r)| r)),
+    }
+
+    let (_resolved, report) =
+      Resolved::resolve_with_report(acl, BTreeMap::new(), Target::current()).unwrap();
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].code, WARNING_MISSING_DEFAULT_PERMISSION);
   }
 }
@@ -0,0 +1,459 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Detection of the fields of the capability and permission files that are not known to Tauri.
+//!
+//! Serde ignores the unknown fields, so a typo like `"permisions"` silently produces a capability that grants nothing.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use super::format::{CapabilityFormat, VERSION_FIELD};
+
+/// How the capability and permission files handle the fields Tauri does not know.
+///
+/// Vendor-specific fields prefixed with `x-` and the `$schema` field are always accepted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownFields {
+  /// Ignore the unknown fields.
+  #[default]
+  Ignore,
+  /// Fail on the unknown fields, reporting each of them with the closest known field.
+  Deny,
+}
+
+/// A field of a capability or permission file that Tauri does not know.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownField {
+  /// The path of the field in the file, e.g. `permissions[1].alow`.
+  pub path: String,
+  /// The line of the field in the file, starting at 1.
+  pub line: Option<usize>,
+  /// The known field closest to the unknown one.
+  pub suggestion: Option<&'static str>,
+}
+
+impl fmt::Display for UnknownField {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "`{}`", self.path)?;
+    if let Some(line) = self.line {
+      write!(f, " (line {line})")?;
+    }
+    if let Some(suggestion) = self.suggestion {
+      write!(f, ", did you mean `{suggestion}`?")?;
+    }
+    Ok(())
+  }
+}
+
+const CAPABILITY_FILE_FIELDS: &[&str] = &["$schema", "version", "capabilities"];
+const CAPABILITY_FIELDS: &[&str] = &[
+  "$schema",
+  "identifier",
+  "description",
+  "context",
+  "windows",
+  "pages",
+  "permissions",
+  "platforms",
+  "group",
+  "roles",
+  "inherit-from-window",
+  "attenuate",
+  "plugin-version",
+];
+const ATTENUATION_FIELDS: &[&str] = &["deny-commands", "scope-filter"];
+const PERMISSION_ENTRY_FIELDS: &[&str] = &[
+  "identifier",
+  "allow",
+  "deny",
+  "subtract",
+  "include-deny",
+  "config",
+  "plugin-version",
+];
+/// The extended permission entry fields since version 2 of the capability files, nesting the scope.
+const PERMISSION_ENTRY_FIELDS_V2: &[&str] = &[
+  "identifier",
+  "scope",
+  "subtract",
+  "include-deny",
+  "config",
+  "plugin-version",
+];
+
+const PERMISSION_FILE_FIELDS: &[&str] = &[
+  "$schema",
+  "default",
+  "set",
+  "permission",
+  "window-prefixes",
+  "merge-key",
+  "shares-scope-with",
+  "aliases",
+  "presets",
+];
+const DEFAULT_PERMISSION_FIELDS: &[&str] = &["version", "description", "permissions"];
+const PERMISSION_SET_FIELDS: &[&str] = &[
+  "identifier",
+  "description",
+  "permissions",
+  "intersect",
+  "exclude",
+  "deprecated",
+  "tier",
+];
+const PERMISSION_FIELDS: &[&str] = &[
+  "version",
+  "identifier",
+  "description",
+  "commands",
+  "scope",
+  "class",
+  "validator",
+  "args",
+  "requires_scope",
+  "platforms",
+  "returns",
+  "delegable",
+  "deprecated",
+];
+const DEPRECATED_FIELDS: &[&str] = &["since", "note", "replaced-by"];
+const COMMANDS_FIELDS: &[&str] = &["allow", "deny"];
+const ARGUMENT_RULE_FIELDS: &[&str] = &["name", "optional", "kind", "values", "min", "max"];
+const SCOPES_FIELDS: &[&str] = &["allow", "deny"];
+
+/// The unknown fields of a capability file, which defines a capability or a list of capabilities.
+///
+/// The fields are checked against the format version the file declares, an unsupported version is reported on load.
+pub(crate) fn capability_file(file: &Value, source: &str) -> Vec<UnknownField> {
+  let mut checker = Checker::new(source);
+  let format = CapabilityFormat::of(file).unwrap_or(CapabilityFormat::CURRENT);
+  let entry_fields = match format {
+    CapabilityFormat::V1 => PERMISSION_ENTRY_FIELDS,
+    CapabilityFormat::V2 => PERMISSION_ENTRY_FIELDS_V2,
+  };
+  match file.get("capabilities") {
+    Some(capabilities) if file.get("identifier").is_none() => {
+      checker.object(file, "", CAPABILITY_FILE_FIELDS);
+      checker.list(capabilities, "capabilities", |checker, capability, path| {
+        checker.capability(capability, path, CAPABILITY_FIELDS, entry_fields)
+      });
+    }
+    _ => {
+      // the version of a single capability file is declared next to the capability fields
+      let fields = [CAPABILITY_FIELDS, &[VERSION_FIELD]].concat();
+      checker.capability(file, "", &fields, entry_fields)
+    }
+  }
+  checker.unknown
+}
+
+/// The unknown fields of a plugin permission file.
+pub(crate) fn permission_file(file: &Value, source: &str) -> Vec<UnknownField> {
+  let mut checker = Checker::new(source);
+  if let Some(file) = checker.object(file, "", PERMISSION_FILE_FIELDS) {
+    if let Some(default) = file.get("default") {
+      checker.object(default, "default", DEFAULT_PERMISSION_FIELDS);
+    }
+    if let Some(sets) = file.get("set") {
+      checker.list(sets, "set", |checker, set, path| {
+        if let Some(set) = checker.object(set, path, PERMISSION_SET_FIELDS) {
+          checker.deprecated(set, path);
+        }
+      });
+    }
+    if let Some(permissions) = file.get("permission") {
+      checker.list(permissions, "permission", Checker::permission);
+    }
+  }
+  checker.unknown
+}
+
+struct Checker<'a> {
+  source: &'a str,
+  unknown: Vec<UnknownField>,
+}
+
+impl<'a> Checker<'a> {
+  fn new(source: &'a str) -> Self {
+    Self {
+      source,
+      unknown: Vec::new(),
+    }
+  }
+
+  fn capability(
+    &mut self,
+    capability: &Value,
+    path: &str,
+    fields: &[&'static str],
+    entry_fields: &[&'static str],
+  ) {
+    let Some(capability) = self.object(capability, path, fields) else {
+      return;
+    };
+    if let Some(attenuate) = capability.get("attenuate") {
+      self.object(attenuate, &join(path, "attenuate"), ATTENUATION_FIELDS);
+    }
+    if let Some(permissions) = capability.get("permissions") {
+      // permission identifiers are strings, only the extended entries are objects
+      self.list(
+        permissions,
+        &join(path, "permissions"),
+        |checker, entry, path| {
+          let scope = checker
+            .object(entry, path, entry_fields)
+            .and_then(|entry| entry.get("scope"))
+            .filter(|_| entry_fields.contains(&"scope"));
+          if let Some(scope) = scope {
+            checker.object(scope, &join(path, "scope"), SCOPES_FIELDS);
+          }
+        },
+      );
+    }
+  }
+
+  fn permission(&mut self, permission: &Value, path: &str) {
+    let Some(permission) = self.object(permission, path, PERMISSION_FIELDS) else {
+      return;
+    };
+    if let Some(commands) = permission.get("commands") {
+      self.object(commands, &join(path, "commands"), COMMANDS_FIELDS);
+    }
+    if let Some(scope) = permission.get("scope") {
+      self.object(scope, &join(path, "scope"), SCOPES_FIELDS);
+    }
+    if let Some(args) = permission.get("args") {
+      self.list(args, &join(path, "args"), |checker, rule, path| {
+        checker.object(rule, path, ARGUMENT_RULE_FIELDS);
+      });
+    }
+    self.deprecated(permission, path);
+  }
+
+  fn deprecated(&mut self, item: &Map<String, Value>, path: &str) {
+    if let Some(deprecated) = item.get("deprecated") {
+      self.object(deprecated, &join(path, "deprecated"), DEPRECATED_FIELDS);
+    }
+  }
+
+  /// Reports the unknown fields of the value if it is an object.
+  fn object<'v>(
+    &mut self,
+    value: &'v Value,
+    path: &str,
+    known: &[&'static str],
+  ) -> Option<&'v Map<String, Value>> {
+    let object = value.as_object()?;
+    for field in object.keys() {
+      if !known.contains(&field.as_str()) && !field.starts_with("x-") {
+        self.unknown.push(UnknownField {
+          path: join(path, field),
+          line: line_of(self.source, field),
+          suggestion: closest(field, known),
+        });
+      }
+    }
+    Some(object)
+  }
+
+  fn list(&mut self, value: &Value, path: &str, mut item: impl FnMut(&mut Self, &Value, &str)) {
+    for (i, value) in value.as_array().into_iter().flatten().enumerate() {
+      item(self, value, &format!("{path}[{i}]"));
+    }
+  }
+}
+
+fn join(path: &str, field: &str) -> String {
+  if path.is_empty() {
+    field.to_string()
+  } else {
+    format!("{path}.{field}")
+  }
+}
+
+/// The line of the first occurrence of the field name as a whole key in the source.
+fn line_of(source: &str, field: &str) -> Option<usize> {
+  let is_key_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '$');
+  source
+    .lines()
+    .position(|line| {
+      line.match_indices(field).any(|(start, _)| {
+        let before = line[..start].chars().next_back();
+        let after = line[start + field.len()..].chars().next();
+        !before.is_some_and(is_key_char) && !after.is_some_and(is_key_char)
+      })
+    })
+    .map(|index| index + 1)
+}
+
+/// The known field with the smallest edit distance to the unknown field, if it is close enough to be a typo.
+fn closest(field: &str, known: &[&'static str]) -> Option<&'static str> {
+  known
+    .iter()
+    .map(|candidate| (edit_distance(field, candidate), *candidate))
+    .filter(|(distance, candidate)| *distance <= candidate.len().max(3) / 3)
+    .min_by_key(|(distance, _)| *distance)
+    .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b = b.chars().collect::<Vec<_>>();
+  let mut row = (0..=b.len()).collect::<Vec<_>>();
+  for (i, a) in a.chars().enumerate() {
+    let mut previous = row[0];
+    row[0] = i + 1;
+    for (j, b) in b.iter().enumerate() {
+      let current = row[j + 1];
+      row[j + 1] = if a == *b {
+        previous
+      } else {
+        1 + previous.min(row[j]).min(current)
+      };
+      previous = current;
+    }
+  }
+  row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn paths(unknown: &[UnknownField]) -> Vec<(&str, Option<usize>, Option<&str>)> {
+    unknown
+      .iter()
+      .map(|field| (field.path.as_str(), field.line, field.suggestion))
+      .collect()
+  }
+
+  #[test]
+  fn unknown_capability_fields() {
+    let source = r#"{
+  "$schema": "../gen/schemas/desktop-schema.json",
+  "identifier": "main",
+  "window": ["main"],
+  "permisions": [],
+  "x-owner": "desktop-team"
+}"#;
+    let unknown = capability_file(&serde_json::from_str(source).unwrap(), source);
+    assert_eq!(
+      paths(&unknown),
+      [
+        ("permisions", Some(5), Some("permissions")),
+        ("window", Some(4), Some("windows")),
+      ]
+    );
+    assert_eq!(
+      unknown[0].to_string(),
+      "`permisions` (line 5), did you mean `permissions`?"
+    );
+  }
+
+  #[test]
+  fn unknown_extended_permission_fields() {
+    let source = r#"{
+  "capabilities": [
+    {
+      "identifier": "main",
+      "windows": ["main"],
+      "permissions": [
+        "fs:default",
+        { "identifier": "fs:allow-read", "alow": [{ "path": "$APPDATA" }], "x-reason": "docs" },
+        { "identifier": "fs:allow-write", "deny": [{ "path": "$HOME", "recursive": true }], "substract": true }
+      ]
+    }
+  ]
+}"#;
+    let unknown = capability_file(&serde_json::from_str(source).unwrap(), source);
+    // the scope entries are plugin-defined, so their fields are not checked
+    assert_eq!(
+      paths(&unknown),
+      [
+        (
+          "capabilities[0].permissions[1].alow",
+          Some(8),
+          Some("allow")
+        ),
+        (
+          "capabilities[0].permissions[2].substract",
+          Some(9),
+          Some("subtract")
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn unknown_versioned_capability_fields() {
+    let source = r#"{
+  "version": 2,
+  "identifier": "main",
+  "windows": ["main"],
+  "permissions": [
+    { "identifier": "fs:allow-write", "allow": ["$HOME"] },
+    { "identifier": "fs:allow-read", "scope": { "allow": ["$APPDATA"], "denny": [] } }
+  ]
+}"#;
+    let unknown = capability_file(&serde_json::from_str(source).unwrap(), source);
+    assert_eq!(
+      paths(&unknown),
+      [
+        ("permissions[0].allow", Some(6), None),
+        ("permissions[1].scope.denny", Some(7), Some("deny")),
+      ]
+    );
+
+    // the version is declared at the top of the file, not by each capability of a list
+    let source = r#"{
+  "version": 2,
+  "capabilities": [{ "version": 2, "identifier": "main", "windows": ["main"], "permissions": [] }]
+}"#;
+    let unknown = capability_file(&serde_json::from_str(source).unwrap(), source);
+    assert_eq!(
+      paths(&unknown),
+      [("capabilities[0].version", Some(2), None)]
+    );
+  }
+
+  #[test]
+  fn unknown_permission_file_fields() {
+    let source = r#"
+[default]
+description = "default"
+permisions = ["allow-read"]
+
+[[permission]]
+identifier = "allow-read"
+commands.alow = ["read"]
+scope.allow = [{ path = "$APPDATA" }]
+scope.denny = []
+requires-scope = true
+deprecated = { since = "2.1.0", replaced_by = ["allow-read-file"] }
+args = [{ name = "path", kind = "path-scope", optinal = true }]
+"#;
+    let file = toml::from_str::<Value>(source).unwrap();
+    assert_eq!(
+      paths(&permission_file(&file, source)),
+      [
+        ("default.permisions", Some(4), Some("permissions")),
+        (
+          "permission[0].requires-scope",
+          Some(11),
+          Some("requires_scope")
+        ),
+        ("permission[0].commands.alow", Some(8), Some("allow")),
+        ("permission[0].scope.denny", Some(10), Some("deny")),
+        ("permission[0].args[0].optinal", Some(13), Some("optional")),
+        (
+          "permission[0].deprecated.replaced_by",
+          Some(12),
+          Some("replaced-by")
+        ),
+      ]
+    );
+  }
+}
@@ -0,0 +1,919 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Compact serialized form of the resolved command tables.
+//!
+//! Large ACLs are embedded by the code generation as a single byte string instead of
+//! nested struct literals, which are expensive to expand and compile.
+//! Large command scopes are embedded as [`ScopeShards`], only decoded when a command uses them.
+//! The scopes of plugins declaring an external scope are stored out of the resolved ACL as [`ExternalScopes`].
+
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, HashMap},
+  fmt,
+  sync::OnceLock,
+};
+
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+
+#[cfg(debug_assertions)]
+use super::resolved::ResolvedCommandReference;
+use super::{
+  args::ArgumentRule,
+  capability::RemoteAttestation,
+  resolved::{
+    CommandKey, ExternalScope, PageGrant, ResolvedCommand, ResolvedScope, ScopeKey, WebviewPattern,
+    WindowAncestor, WindowConfig, WindowScope,
+  },
+  window::WindowPattern,
+  Error, ExecutionContext, Value,
+};
+
+/// Index of a glob pattern in [`CommandTables`].
+type PatternIndex = usize;
+
+/// A [`WindowAncestor`] with an interned pattern.
+#[derive(Serialize, Deserialize)]
+struct EncodedAncestor(PatternIndex, bool);
+
+/// A [`WebviewPattern`] with interned window and webview patterns.
+#[derive(Serialize, Deserialize)]
+struct EncodedWebview(PatternIndex, PatternIndex);
+
+/// A remote [`ExecutionContext`] with an interned domain pattern.
+#[derive(Serialize, Deserialize)]
+struct EncodedRemote(PatternIndex, Option<RemoteAttestation>);
+
+/// A [`WindowScope`] with interned patterns.
+#[derive(Serialize, Deserialize)]
+struct EncodedWindowScope(
+  Vec<PatternIndex>,
+  Vec<EncodedAncestor>,
+  ScopeKey,
+  Vec<EncodedWebview>,
+  Vec<PatternIndex>,
+);
+
+/// A [`WindowConfig`] with interned patterns.
+#[derive(Serialize, Deserialize)]
+struct EncodedWindowConfig(
+  String,
+  Vec<PatternIndex>,
+  Vec<EncodedAncestor>,
+  Value,
+  Vec<EncodedWebview>,
+  Vec<PatternIndex>,
+);
+
+/// A [`PageGrant`] with interned patterns.
+#[derive(Serialize, Deserialize)]
+struct EncodedPageGrant(
+  Vec<PatternIndex>,
+  Vec<EncodedAncestor>,
+  Vec<EncodedWebview>,
+  Vec<PatternIndex>,
+);
+
+/// A [`CommandKey`] and its [`ResolvedCommand`] with interned patterns.
+///
+/// The capability and permission references are always encoded,
+/// the decoder drops them when `debug_assertions` are disabled.
+#[derive(Serialize, Deserialize)]
+struct EncodedCommand(
+  String,
+  Option<EncodedRemote>,
+  Vec<PatternIndex>,
+  Vec<EncodedAncestor>,
+  Vec<EncodedWindowScope>,
+  Vec<String>,
+  bool,
+  Vec<(String, String, Option<String>)>,
+  Vec<EncodedWindowConfig>,
+  Vec<EncodedWebview>,
+  Vec<String>,
+  Vec<EncodedPageGrant>,
+  Vec<ArgumentRule>,
+);
+
+/// The allowed and denied commands and the command scopes of a [`super::resolved::Resolved`].
+///
+/// Window, page and remote domain patterns are interned, so each of them is only stored and compiled once.
+#[derive(Default, Serialize, Deserialize)]
+pub struct CommandTables {
+  patterns: Vec<String>,
+  allowed: Vec<EncodedCommand>,
+  denied: Vec<EncodedCommand>,
+  scopes: Vec<(ScopeKey, ResolvedScope)>,
+}
+
+impl CommandTables {
+  /// Encodes the given command tables.
+  pub fn new(
+    allowed_commands: &BTreeMap<CommandKey, ResolvedCommand>,
+    denied_commands: &BTreeMap<CommandKey, ResolvedCommand>,
+    command_scope: &BTreeMap<ScopeKey, ResolvedScope>,
+  ) -> Self {
+    let mut interner = PatternInterner::default();
+    let allowed = allowed_commands
+      .iter()
+      .map(|(key, command)| interner.command(key, command))
+      .collect();
+    let denied = denied_commands
+      .iter()
+      .map(|(key, command)| interner.command(key, command))
+      .collect();
+
+    Self {
+      patterns: interner.patterns,
+      allowed,
+      denied,
+      scopes: command_scope
+        .iter()
+        .map(|(key, scope)| (*key, scope.clone()))
+        .collect(),
+    }
+  }
+
+  /// Serializes the tables.
+  pub fn encode(&self) -> Result<Vec<u8>, Error> {
+    serde_json::to_vec(self).map_err(Into::into)
+  }
+
+  /// Deserializes tables serialized with [`Self::encode`].
+  pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+    serde_json::from_slice(bytes).map_err(Into::into)
+  }
+
+  /// Rebuilds the allowed commands, denied commands and command scopes.
+  #[allow(clippy::type_complexity)]
+  pub fn into_parts(
+    self,
+  ) -> Result<
+    (
+      BTreeMap<CommandKey, ResolvedCommand>,
+      BTreeMap<CommandKey, ResolvedCommand>,
+      BTreeMap<ScopeKey, ResolvedScope>,
+    ),
+    Error,
+  > {
+    let decoder = PatternDecoder(&self.patterns);
+
+    let allowed_commands = self
+      .allowed
+      .into_iter()
+      .map(|command| decoder.command(command))
+      .collect::<Result<_, _>>()?;
+    let denied_commands = self
+      .denied
+      .into_iter()
+      .map(|command| decoder.command(command))
+      .collect::<Result<_, _>>()?;
+
+    Ok((
+      allowed_commands,
+      denied_commands,
+      self.scopes.into_iter().collect(),
+    ))
+  }
+}
+
+/// Number of scope values encoded in each shard of [`ScopeShards`].
+const SCOPE_SHARD_VALUES: usize = 4096;
+
+/// Command scopes encoded in shards, each decoded on the first access to one of its scopes.
+///
+/// Apps with large scopes do not wait at startup for every scope value to be built,
+/// only the shards of the scopes used by the invoked commands are decoded.
+#[derive(Default)]
+pub struct ScopeShards {
+  keys: Vec<ScopeKey>,
+  shards: Vec<ScopeShard>,
+}
+
+struct ScopeShard {
+  bytes: Cow<'static, [u8]>,
+  scopes: OnceLock<BTreeMap<ScopeKey, ResolvedScope>>,
+}
+
+impl fmt::Debug for ScopeShards {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("ScopeShards")
+      .field("keys", &self.keys)
+      .field("shards", &self.shards.len())
+      .field(
+        "decoded",
+        &self
+          .shards
+          .iter()
+          .filter(|shard| shard.scopes.get().is_some())
+          .count(),
+      )
+      .finish()
+  }
+}
+
+impl ScopeShards {
+  /// Encodes the command scopes in shards of about the same number of scope values,
+  /// returning the scope keys and the shards for [`Self::new`].
+  pub fn encode(
+    command_scope: &BTreeMap<ScopeKey, ResolvedScope>,
+  ) -> Result<(Vec<ScopeKey>, Vec<Vec<u8>>), Error> {
+    let values = command_scope
+      .values()
+      .map(|scope| scope.allow.len() + scope.deny.len())
+      .sum::<usize>();
+    let count =
+      ((values + SCOPE_SHARD_VALUES - 1) / SCOPE_SHARD_VALUES).clamp(1, command_scope.len().max(1));
+
+    let mut shards = vec![Vec::new(); count];
+    for (key, scope) in command_scope {
+      shards[key % count].push((key, scope));
+    }
+    let shards = shards
+      .iter()
+      .map(serde_json::to_vec)
+      .collect::<Result<_, _>>()?;
+    Ok((command_scope.keys().copied().collect(), shards))
+  }
+
+  /// Creates the shards from the scope keys and the shards encoded with [`Self::encode`].
+  pub fn new<B: Into<Cow<'static, [u8]>>>(
+    keys: Vec<ScopeKey>,
+    shards: impl IntoIterator<Item = B>,
+  ) -> Self {
+    Self {
+      keys,
+      shards: shards
+        .into_iter()
+        .map(|bytes| ScopeShard {
+          bytes: bytes.into(),
+          scopes: OnceLock::new(),
+        })
+        .collect(),
+    }
+  }
+
+  /// Whether there are no scopes in the shards.
+  pub fn is_empty(&self) -> bool {
+    self.keys.is_empty()
+  }
+
+  /// The keys of the scopes in the shards.
+  pub fn keys(&self) -> &[ScopeKey] {
+    &self.keys
+  }
+
+  /// Gets a scope, decoding its shard on the first access.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the shard was not encoded with [`Self::encode`].
+  pub fn get(&self, key: ScopeKey) -> Option<&ResolvedScope> {
+    self.shard(key)?.get(&key)
+  }
+
+  /// Decodes the shards of the given scopes ahead of their first access.
+  pub fn prewarm(&self, keys: impl IntoIterator<Item = ScopeKey>) {
+    for key in keys {
+      self.shard(key);
+    }
+  }
+
+  fn shard(&self, key: ScopeKey) -> Option<&BTreeMap<ScopeKey, ResolvedScope>> {
+    let shard = self.shards.get(key % self.shards.len().max(1))?;
+    Some(shard.scopes.get_or_init(|| {
+      serde_json::from_slice::<Vec<(ScopeKey, ResolvedScope)>>(&shard.bytes)
+        .expect("failed to decode the ACL scope shard")
+        .into_iter()
+        .collect()
+    }))
+  }
+}
+
+impl super::memory::HeapSize for ScopeShards {
+  fn heap_size(&self) -> usize {
+    self.keys.heap_size()
+      + self.shards.capacity() * std::mem::size_of::<ScopeShard>()
+      + self
+        .shards
+        .iter()
+        .map(|shard| {
+          // shards embedded in the app binary are not on the heap
+          let bytes = match &shard.bytes {
+            Cow::Borrowed(_) => 0,
+            Cow::Owned(bytes) => bytes.capacity(),
+          };
+          bytes + shard.scopes.get().map_or(0, |scopes| scopes.heap_size())
+        })
+        .sum::<usize>()
+  }
+}
+
+/// Scope values stored out of the resolved ACL, keyed by [`ExternalScope::key`],
+/// see [`Manifest#structfield.external_scope`](super::plugin::Manifest#structfield.external_scope).
+///
+/// The build script writes them as a compressed asset the app embeds and loads with a scope provider.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ExternalScopes(BTreeMap<String, ResolvedScope>);
+
+impl ExternalScopes {
+  /// Moves the values of a scope of the given plugin to the store, leaving a reference to them in the scope.
+  ///
+  /// Empty scopes and scopes already referencing external values are left as is.
+  pub fn externalize(&mut self, plugin: &str, scope: &mut ResolvedScope) {
+    if let Some(external) = self.reference(plugin, scope) {
+      let values = std::mem::take(scope);
+      scope.external = Some(external.clone());
+      self.0.insert(external.key, values);
+    }
+  }
+
+  /// Copies the values of a scope of the given plugin to the store, see [`Self::externalize`].
+  pub fn insert(&mut self, plugin: &str, scope: &ResolvedScope) {
+    if let Some(external) = self.reference(plugin, scope) {
+      self.0.insert(external.key, scope.clone());
+    }
+  }
+
+  fn reference(&self, plugin: &str, scope: &ResolvedScope) -> Option<ExternalScope> {
+    if scope.external.is_some() || (scope.allow.is_empty() && scope.deny.is_empty()) {
+      return None;
+    }
+    // the key is derived from the content so resolving the same ACL twice yields the same keys
+    let hash = scope.content_hash();
+    Some(ExternalScope {
+      key: format!("{plugin}/{hash}"),
+      hash,
+    })
+  }
+
+  /// Adds the values of another store.
+  pub fn extend(&mut self, other: ExternalScopes) {
+    self.0.extend(other.0);
+  }
+
+  /// Gets the values stored with the given key.
+  pub fn get(&self, key: &str) -> Option<&ResolvedScope> {
+    self.0.get(key)
+  }
+
+  /// Whether no scope values are stored.
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// Serializes and compresses the store.
+  #[cfg(feature = "compression")]
+  pub fn compress(&self) -> Result<Vec<u8>, Error> {
+    use std::io::Write;
+
+    let json = serde_json::to_vec(self)?;
+    let mut compressed = Vec::new();
+    {
+      let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 9, 22);
+      writer.write_all(&json).map_err(Error::ExternalScopes)?;
+    }
+    Ok(compressed)
+  }
+
+  /// Decompresses and deserializes a store compressed with [`Self::compress`].
+  #[cfg(feature = "compression")]
+  pub fn decompress(mut bytes: &[u8]) -> Result<Self, Error> {
+    let mut json = Vec::with_capacity(bytes.len());
+    brotli::BrotliDecompress(&mut bytes, &mut json).map_err(Error::ExternalScopes)?;
+    serde_json::from_slice(&json).map_err(Into::into)
+  }
+}
+
+#[derive(Default)]
+struct PatternInterner {
+  patterns: Vec<String>,
+  indexes: HashMap<String, PatternIndex>,
+}
+
+impl PatternInterner {
+  fn intern(&mut self, pattern: &str) -> PatternIndex {
+    if let Some(index) = self.indexes.get(pattern) {
+      return *index;
+    }
+    let index = self.patterns.len();
+    self.patterns.push(pattern.to_string());
+    self.indexes.insert(pattern.to_string(), index);
+    index
+  }
+
+  fn windows(&mut self, windows: &[WindowPattern]) -> Vec<PatternIndex> {
+    windows
+      .iter()
+      .map(|window| self.intern(window.as_str()))
+      .collect()
+  }
+
+  fn ancestors(&mut self, ancestors: &[WindowAncestor]) -> Vec<EncodedAncestor> {
+    ancestors
+      .iter()
+      .map(|ancestor| EncodedAncestor(self.intern(ancestor.pattern.as_str()), ancestor.parent_only))
+      .collect()
+  }
+
+  fn webviews(&mut self, webviews: &[WebviewPattern]) -> Vec<EncodedWebview> {
+    webviews
+      .iter()
+      .map(|webview| {
+        EncodedWebview(
+          self.intern(webview.window.as_str()),
+          self.intern(webview.webview.as_str()),
+        )
+      })
+      .collect()
+  }
+
+  fn pages(&mut self, pages: &[Pattern]) -> Vec<PatternIndex> {
+    pages
+      .iter()
+      .map(|page| self.intern(page.as_str()))
+      .collect()
+  }
+
+  fn command(&mut self, key: &CommandKey, command: &ResolvedCommand) -> EncodedCommand {
+    let domain = match &key.context {
+      ExecutionContext::Local => None,
+      ExecutionContext::Remote {
+        domain,
+        attestation,
+      } => Some(EncodedRemote(
+        self.intern(domain.as_str()),
+        attestation.clone(),
+      )),
+    };
+    let scopes = command
+      .scopes
+      .iter()
+      .map(|scope| {
+        EncodedWindowScope(
+          self.windows(&scope.windows),
+          self.ancestors(&scope.window_ancestors),
+          scope.scope,
+          self.webviews(&scope.webviews),
+          self.pages(&scope.pages),
+        )
+      })
+      .collect();
+
+    let configs = command
+      .configs
+      .iter()
+      .map(|config| {
+        EncodedWindowConfig(
+          config.capability.clone(),
+          self.windows(&config.windows),
+          self.ancestors(&config.window_ancestors),
+          config.config.clone(),
+          self.webviews(&config.webviews),
+          self.pages(&config.pages),
+        )
+      })
+      .collect();
+
+    let pages = command
+      .pages
+      .iter()
+      .map(|grant| {
+        EncodedPageGrant(
+          self.windows(&grant.windows),
+          self.ancestors(&grant.window_ancestors),
+          self.webviews(&grant.webviews),
+          self.pages(&grant.pages),
+        )
+      })
+      .collect();
+
+    #[cfg(debug_assertions)]
+    let referenced_by = command
+      .referenced_by
+      .iter()
+      .map(|reference| {
+        (
+          reference.capability.clone(),
+          reference.permission.clone(),
+          reference.delegated_by.clone(),
+        )
+      })
+      .collect();
+    #[cfg(not(debug_assertions))]
+    let referenced_by = Vec::new();
+
+    EncodedCommand(
+      key.name.clone(),
+      domain,
+      self.windows(&command.windows),
+      self.ancestors(&command.window_ancestors),
+      scopes,
+      command.classes.clone(),
+      command.via_default,
+      referenced_by,
+      configs,
+      self.webviews(&command.webviews),
+      command.validators.clone(),
+      pages,
+      command.args.clone(),
+    )
+  }
+}
+
+struct PatternDecoder<'a>(&'a [String]);
+
+impl PatternDecoder<'_> {
+  fn pattern(&self, index: PatternIndex) -> Result<&str, Error> {
+    self.0.get(index).map(String::as_str).ok_or_else(|| {
+      Error::Json(serde::de::Error::custom(format!(
+        "pattern index {index} out of bounds"
+      )))
+    })
+  }
+
+  fn window(&self, index: PatternIndex) -> Result<WindowPattern, Error> {
+    WindowPattern::new(self.pattern(index)?)
+      .map_err(|error| Error::Json(serde::de::Error::custom(error)))
+  }
+
+  fn windows(&self, windows: Vec<PatternIndex>) -> Result<Vec<WindowPattern>, Error> {
+    windows
+      .into_iter()
+      .map(|index| self.window(index))
+      .collect()
+  }
+
+  fn ancestors(&self, ancestors: Vec<EncodedAncestor>) -> Result<Vec<WindowAncestor>, Error> {
+    ancestors
+      .into_iter()
+      .map(|EncodedAncestor(pattern, parent_only)| {
+        Ok(WindowAncestor {
+          pattern: self.window(pattern)?,
+          parent_only,
+        })
+      })
+      .collect()
+  }
+
+  fn webviews(&self, webviews: Vec<EncodedWebview>) -> Result<Vec<WebviewPattern>, Error> {
+    webviews
+      .into_iter()
+      .map(|EncodedWebview(window, webview)| {
+        Ok(WebviewPattern {
+          window: self.window(window)?,
+          webview: self.window(webview)?,
+        })
+      })
+      .collect()
+  }
+
+  fn pages(&self, pages: Vec<PatternIndex>) -> Result<Vec<Pattern>, Error> {
+    pages
+      .into_iter()
+      .map(|index| Ok(Pattern::new(self.pattern(index)?)?))
+      .collect()
+  }
+
+  fn command(&self, command: EncodedCommand) -> Result<(CommandKey, ResolvedCommand), Error> {
+    #[allow(unused_variables)]
+    let EncodedCommand(
+      name,
+      domain,
+      windows,
+      window_ancestors,
+      scopes,
+      classes,
+      via_default,
+      referenced_by,
+      configs,
+      webviews,
+      validators,
+      pages,
+      args,
+    ) = command;
+
+    let context = match domain {
+      Some(EncodedRemote(domain, attestation)) => ExecutionContext::Remote {
+        domain: Pattern::new(self.pattern(domain)?)?,
+        attestation,
+      },
+      None => ExecutionContext::Local,
+    };
+    let scopes = scopes
+      .into_iter()
+      .map(
+        |EncodedWindowScope(windows, window_ancestors, scope, webviews, pages)| {
+          Ok(WindowScope {
+            windows: self.windows(windows)?,
+            window_ancestors: self.ancestors(window_ancestors)?,
+            webviews: self.webviews(webviews)?,
+            pages: self.pages(pages)?,
+            scope,
+          })
+        },
+      )
+      .collect::<Result<_, Error>>()?;
+    let configs = configs
+      .into_iter()
+      .map(
+        |EncodedWindowConfig(capability, windows, window_ancestors, config, webviews, pages)| {
+          Ok(WindowConfig {
+            capability,
+            windows: self.windows(windows)?,
+            window_ancestors: self.ancestors(window_ancestors)?,
+            webviews: self.webviews(webviews)?,
+            pages: self.pages(pages)?,
+            config,
+          })
+        },
+      )
+      .collect::<Result<_, Error>>()?;
+    let pages = pages
+      .into_iter()
+      .map(
+        |EncodedPageGrant(windows, window_ancestors, webviews, pages)| {
+          Ok(PageGrant {
+            windows: self.windows(windows)?,
+            window_ancestors: self.ancestors(window_ancestors)?,
+            webviews: self.webviews(webviews)?,
+            pages: self.pages(pages)?,
+          })
+        },
+      )
+      .collect::<Result<_, Error>>()?;
+
+    Ok((
+      CommandKey { name, context },
+      ResolvedCommand {
+        #[cfg(debug_assertions)]
+        referenced_by: referenced_by
+          .into_iter()
+          .map(
+            |(capability, permission, delegated_by)| ResolvedCommandReference {
+              capability,
+              permission,
+              delegated_by,
+            },
+          )
+          .collect(),
+        windows: self.windows(windows)?,
+        window_ancestors: self.ancestors(window_ancestors)?,
+        webviews: self.webviews(webviews)?,
+        pages,
+        scopes,
+        classes,
+        validators,
+        args,
+        via_default,
+        configs,
+      },
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::acl::args::ArgumentMatcher;
+
+  fn command(windows: &[&str], ancestor: &str, scope: ScopeKey) -> ResolvedCommand {
+    let windows = windows
+      .iter()
+      .map(|window| WindowPattern::new(window).unwrap())
+      .collect::<Vec<_>>();
+    let window_ancestors = vec![WindowAncestor {
+      pattern: WindowPattern::new(ancestor).unwrap(),
+      parent_only: true,
+    }];
+    let webviews = vec![WebviewPattern {
+      window: WindowPattern::new("*").unwrap(),
+      webview: WindowPattern::new("partner").unwrap(),
+    }];
+    let pages = vec![Pattern::new("admin/*").unwrap()];
+    ResolvedCommand {
+      #[cfg(debug_assertions)]
+      referenced_by: vec![ResolvedCommandReference {
+        capability: "main".into(),
+        permission: "fs:allow-read".into(),
+        delegated_by: None,
+      }],
+      scopes: vec![WindowScope {
+        windows: windows.clone(),
+        window_ancestors: window_ancestors.clone(),
+        webviews: webviews.clone(),
+        pages: pages.clone(),
+        scope,
+      }],
+      configs: vec![WindowConfig {
+        capability: "main".into(),
+        windows: windows.clone(),
+        window_ancestors: window_ancestors.clone(),
+        webviews: webviews.clone(),
+        pages: Vec::new(),
+        config: Value::Map([("user-agent-suffix".into(), Value::String("partner".into()))].into()),
+      }],
+      pages: vec![PageGrant {
+        windows: windows.clone(),
+        window_ancestors: Vec::new(),
+        webviews: Vec::new(),
+        pages,
+      }],
+      windows,
+      window_ancestors,
+      webviews,
+      classes: vec!["read".into()],
+      validators: vec!["strip-pii".into()],
+      args: vec![ArgumentRule {
+        name: "path".into(),
+        optional: false,
+        matcher: ArgumentMatcher::PathScope,
+      }],
+      via_default: false,
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    let local = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Local,
+    };
+    let allowed_commands = BTreeMap::from([
+      (
+        local("plugin:fs|read"),
+        command(&["main", "editor-*"], "main", 0),
+      ),
+      (
+        CommandKey {
+          name: "plugin:fs|read".into(),
+          context: ExecutionContext::Remote {
+            domain: Pattern::new("*.tauri.app").unwrap(),
+            attestation: Some(RemoteAttestation {
+              required_header: None,
+              certificate_pin: Some("sha256/AAAA".into()),
+            }),
+          },
+        },
+        command(&["main"], "editor-*", 1),
+      ),
+    ]);
+    let denied_commands = BTreeMap::from([(local("plugin:fs|write"), command(&["*"], "main", 0))]);
+    let command_scope = BTreeMap::from([
+      (
+        0,
+        ResolvedScope {
+          allow: vec![Value::String("$HOME".into())],
+          deny: Vec::new(),
+          external: None,
+        },
+      ),
+      (
+        1,
+        ResolvedScope {
+          allow: Vec::new(),
+          deny: vec![Value::Bool(true)],
+          external: None,
+        },
+      ),
+    ]);
+
+    let tables = CommandTables::new(&allowed_commands, &denied_commands, &command_scope);
+    assert_eq!(
+      tables.patterns,
+      ["main", "editor-*", "*", "partner", "admin/*", "*.tauri.app"]
+    );
+
+    let bytes = tables.encode().unwrap();
+    let (allowed, denied, scopes) = CommandTables::decode(&bytes).unwrap().into_parts().unwrap();
+    assert_eq!(allowed, allowed_commands);
+    assert_eq!(denied, denied_commands);
+    #[cfg(debug_assertions)]
+    assert_eq!(
+      allowed[&local("plugin:fs|read")].referenced_by,
+      allowed_commands[&local("plugin:fs|read")].referenced_by
+    );
+    assert_eq!(
+      serde_json::to_value(&scopes).unwrap(),
+      serde_json::to_value(&command_scope).unwrap()
+    );
+  }
+
+  #[cfg(feature = "compression")]
+  #[test]
+  fn external_scopes_roundtrip() {
+    let mut external_scopes = ExternalScopes::default();
+    let mut scope = path_scopes(1, 100).remove(&0).unwrap();
+    let values = scope.clone();
+    external_scopes.externalize("fs", &mut scope);
+    let external = scope.external.clone().unwrap();
+    assert!(scope.allow.is_empty());
+    assert_eq!(external.hash, values.content_hash());
+
+    // externalizing again keeps the reference
+    external_scopes.externalize("fs", &mut scope);
+    assert_eq!(scope.external, Some(external.clone()));
+
+    let decompressed = ExternalScopes::decompress(&external_scopes.compress().unwrap()).unwrap();
+    assert_eq!(
+      serde_json::to_value(decompressed.get(&external.key)).unwrap(),
+      serde_json::to_value(&values).unwrap()
+    );
+  }
+
+  fn path_scopes(scopes: usize, values: usize) -> BTreeMap<ScopeKey, ResolvedScope> {
+    (0..scopes)
+      .map(|key| {
+        let scope = ResolvedScope {
+          allow: (0..values)
+            .map(|i| {
+              Value::Map(
+                [(
+                  "path".into(),
+                  Value::String(format!("$APPDATA/{key}/{i}/**")),
+                )]
+                .into(),
+              )
+            })
+            .collect(),
+          deny: Vec::new(),
+          external: None,
+        };
+        (key, scope)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn scope_shards_decode_on_first_access() {
+    let command_scope = path_scopes(8, 1024);
+    let (keys, shards) = ScopeShards::encode(&command_scope).unwrap();
+    assert_eq!(keys, (0..8).collect::<Vec<_>>());
+    assert_eq!(shards.len(), 2);
+
+    let shards = ScopeShards::new(keys, shards);
+    assert_eq!(
+      shards
+        .shards
+        .iter()
+        .filter(|s| s.scopes.get().is_some())
+        .count(),
+      0
+    );
+    assert_eq!(
+      serde_json::to_value(shards.get(3).unwrap()).unwrap(),
+      serde_json::to_value(&command_scope[&3]).unwrap()
+    );
+    // only the shard of the scope is decoded
+    assert!(shards.shards[1].scopes.get().is_some());
+    assert!(shards.shards[0].scopes.get().is_none());
+    assert!(shards.get(8).is_none());
+
+    shards.prewarm([0]);
+    assert!(shards.shards[0].scopes.get().is_some());
+
+    let empty = ScopeShards::default();
+    assert!(empty.is_empty());
+    assert!(empty.get(0).is_none());
+  }
+
+  /// Compares building 50k scope values at startup with decoding the shard of the first used scope.
+  ///
+  /// Run with `cargo test --lib scope_shards_startup_benchmark -- --ignored --nocapture`.
+  #[test]
+  #[ignore = "benchmark"]
+  fn scope_shards_startup_benchmark() {
+    use std::time::Instant;
+
+    let command_scope = path_scopes(500, 100);
+    let (keys, shards) = ScopeShards::encode(&command_scope).unwrap();
+    let tables = CommandTables::new(&BTreeMap::new(), &BTreeMap::new(), &command_scope)
+      .encode()
+      .unwrap();
+
+    let start = Instant::now();
+    let (_, _, eager) = CommandTables::decode(&tables)
+      .unwrap()
+      .into_parts()
+      .unwrap();
+    assert!(eager.contains_key(&42));
+    let eager = start.elapsed();
+
+    let start = Instant::now();
+    let lazy = ScopeShards::new(keys, shards);
+    assert!(lazy.get(42).is_some());
+    let lazy = start.elapsed();
+
+    println!("50k scope values: {eager:?} eager, {lazy:?} lazy until the first scope is used");
+    assert!(lazy < eager);
+  }
+
+  #[test]
+  fn invalid_pattern_index() {
+    let bytes = br#"{"patterns":["main"],"allowed":[["ping",null,[1],[],[],[],false,[],[],[],[],[],[]]],"denied":[],"scopes":[]}"#;
+    assert!(CommandTables::decode(bytes).unwrap().into_parts().is_err());
+  }
+}
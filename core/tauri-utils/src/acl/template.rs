@@ -0,0 +1,405 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Capabilities scaffolded from the permission tiers of the plugins, see [`PermissionTier`].
+//!
+//! When a plugin does not mark a permission set with the requested tier, the template falls back to,
+//! in order:
+//!
+//! 1. the `default` permission of the plugin;
+//! 2. the set marked with the other tier.
+//!
+//! A plugin without any of them is left out of the template. Each fallback is reported as a [`TemplateWarning`].
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+
+use super::{
+  capability::{Capability, CapabilityContext, CapabilityWindow, PermissionEntry},
+  identifier::Identifier,
+  plugin::Manifest,
+  PermissionTier,
+};
+
+/// The window selected by the capability templates.
+pub const TEMPLATE_WINDOW: &str = "main";
+
+/// A capability scaffolded with [`capability_template`].
+#[derive(Debug, Clone)]
+pub struct CapabilityTemplate {
+  /// The capability, selecting the [`TEMPLATE_WINDOW`] on the local app URL.
+  pub capability: Capability,
+  /// The plugins that do not define the requested tier.
+  pub warnings: Vec<TemplateWarning>,
+}
+
+impl CapabilityTemplate {
+  /// The capability file content, in JSON.
+  pub fn to_json(&self) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&self.capability).map(|json| json + "\n")
+  }
+}
+
+/// A plugin of a [`CapabilityTemplate`] that does not define the requested tier.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateWarning {
+  /// The plugin name.
+  pub plugin: String,
+  /// The requested tier.
+  pub tier: PermissionTier,
+  /// The permission granted instead, [`None`] when the plugin is left out of the template.
+  pub fallback: Option<String>,
+}
+
+impl fmt::Display for TemplateWarning {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "plugin {} does not define a {} permission set",
+      self.plugin, self.tier
+    )?;
+    match &self.fallback {
+      Some(fallback) => write!(f, ", using {fallback} instead"),
+      None => write!(f, " nor a fallback, it is left out of the capability"),
+    }
+  }
+}
+
+impl PermissionTier {
+  /// The other tier, used as the last fallback, see the [module documentation](crate::acl::template).
+  fn other(self) -> Self {
+    match self {
+      Self::Minimal => Self::Recommended,
+      Self::Recommended => Self::Minimal,
+    }
+  }
+}
+
+/// Scaffolds a local capability granting the permission set of the given tier of each plugin
+/// on the [`TEMPLATE_WINDOW`].
+///
+/// Plugins missing from `acl` are left out with a warning.
+pub fn capability_template(
+  identifier: impl Into<String>,
+  plugins: &[&str],
+  acl: &BTreeMap<String, Manifest>,
+  tier: PermissionTier,
+) -> CapabilityTemplate {
+  let mut permissions = Vec::new();
+  let mut warnings = Vec::new();
+
+  for plugin in plugins {
+    let manifest = acl.get(*plugin);
+    let set = manifest.and_then(|manifest| manifest.tier_set(tier));
+    let permission = match set {
+      Some(set) => Some(format!("{plugin}:{}", set.identifier)),
+      None => {
+        let fallback = manifest.and_then(|manifest| {
+          if manifest.has_default_permission() {
+            Some(format!("{plugin}:default"))
+          } else {
+            manifest
+              .tier_set(tier.other())
+              .map(|set| format!("{plugin}:{}", set.identifier))
+          }
+        });
+        warnings.push(TemplateWarning {
+          plugin: plugin.to_string(),
+          tier,
+          fallback: fallback.clone(),
+        });
+        fallback
+      }
+    };
+
+    if let Some(permission) = permission.and_then(|p| Identifier::try_from(p).ok()) {
+      permissions.push(PermissionEntry::PermissionRef(permission));
+    }
+  }
+
+  CapabilityTemplate {
+    capability: Capability {
+      identifier: identifier.into(),
+      description: format!("{tier} permissions of {}", plugins.join(", ")),
+      context: CapabilityContext::Local,
+      windows: vec![CapabilityWindow::Label(TEMPLATE_WINDOW.into())],
+      pages: Vec::new(),
+      permissions,
+      platforms: super::capability::default_platforms(),
+      group: None,
+      inherit_from_window: false,
+      roles: Vec::new(),
+      attenuate: None,
+      plugin_version: BTreeMap::new(),
+      extensions: BTreeMap::new(),
+    },
+    warnings,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    acl::{plugin::PermissionFile, resolved::Resolved, Error},
+    platform::Target,
+  };
+
+  fn manifest(toml: &str) -> Manifest {
+    let file: PermissionFile = toml::from_str(toml).unwrap();
+    Manifest::new(vec![file], None)
+  }
+
+  fn acl() -> BTreeMap<String, Manifest> {
+    [
+      (
+        "fs",
+        manifest(
+          r#"
+[default]
+permissions = ["allow-read"]
+
+[[set]]
+identifier = "read-only"
+description = "reads the app files"
+permissions = ["allow-read"]
+tier = "minimal"
+
+[[set]]
+identifier = "read-write"
+description = "reads and writes the app files"
+permissions = ["allow-read", "allow-write"]
+tier = "recommended"
+
+[[permission]]
+identifier = "allow-read"
+commands.allow = ["read"]
+
+[[permission]]
+identifier = "allow-write"
+commands.allow = ["write"]
+"#,
+        ),
+      ),
+      (
+        // only the recommended tier and a default permission
+        "http",
+        manifest(
+          r#"
+[default]
+permissions = ["allow-fetch"]
+
+[[set]]
+identifier = "web"
+description = "fetches and cancels requests"
+permissions = ["allow-fetch", "allow-cancel"]
+tier = "recommended"
+
+[[permission]]
+identifier = "allow-fetch"
+commands.allow = ["fetch"]
+
+[[permission]]
+identifier = "allow-cancel"
+commands.allow = ["cancel"]
+"#,
+        ),
+      ),
+      (
+        // only the minimal tier, without a default permission
+        "shell",
+        manifest(
+          r#"
+[[set]]
+identifier = "open-only"
+description = "opens URLs"
+permissions = ["allow-open"]
+tier = "minimal"
+
+[[permission]]
+identifier = "allow-open"
+commands.allow = ["open"]
+
+[[permission]]
+identifier = "allow-execute"
+commands.allow = ["execute"]
+"#,
+        ),
+      ),
+      (
+        // no tiers nor default permission
+        "log",
+        manifest(
+          r#"
+[[permission]]
+identifier = "allow-log"
+commands.allow = ["log"]
+
+[[permission]]
+identifier = "allow-clear"
+commands.allow = ["clear"]
+"#,
+        ),
+      ),
+    ]
+    .into_iter()
+    .map(|(name, manifest)| (name.to_string(), manifest))
+    .collect()
+  }
+
+  fn permissions(template: &CapabilityTemplate) -> Vec<&str> {
+    template
+      .capability
+      .permissions
+      .iter()
+      .map(|entry| entry.identifier().get())
+      .collect()
+  }
+
+  #[test]
+  fn manifest_tiers() {
+    let acl = acl();
+    assert_eq!(
+      acl["fs"]
+        .tiers()
+        .into_iter()
+        .map(|(tier, set)| (tier, set.identifier.as_str()))
+        .collect::<Vec<_>>(),
+      [
+        (PermissionTier::Minimal, "read-only"),
+        (PermissionTier::Recommended, "read-write")
+      ]
+    );
+    assert!(acl["log"].tiers().is_empty());
+    assert_eq!(
+      acl["http"]
+        .tier_set(PermissionTier::Recommended)
+        .map(|set| set.identifier.as_str()),
+      Some("web")
+    );
+    assert!(acl["http"].tier_set(PermissionTier::Minimal).is_none());
+
+    let duplicate = manifest(
+      r#"
+[[set]]
+identifier = "a"
+description = ""
+permissions = ["allow-log"]
+tier = "minimal"
+
+[[set]]
+identifier = "b"
+description = ""
+permissions = ["allow-log"]
+tier = "minimal"
+
+[[permission]]
+identifier = "allow-log"
+commands.allow = ["log"]
+"#,
+    );
+    assert!(matches!(
+      duplicate.validate_tiers("log"),
+      Err(Error::DuplicatePermissionTier { tier: PermissionTier::Minimal, sets, .. }) if sets == ["a", "b"]
+    ));
+  }
+
+  #[test]
+  fn minimal_template() {
+    let acl = acl();
+    let template = capability_template(
+      "scaffold",
+      &["fs", "http", "shell", "log", "unknown"],
+      &acl,
+      PermissionTier::Minimal,
+    );
+
+    assert_eq!(
+      permissions(&template),
+      ["fs:read-only", "http:default", "shell:open-only"]
+    );
+    assert_eq!(
+      template.warnings,
+      [
+        TemplateWarning {
+          plugin: "http".into(),
+          tier: PermissionTier::Minimal,
+          fallback: Some("http:default".into()),
+        },
+        TemplateWarning {
+          plugin: "log".into(),
+          tier: PermissionTier::Minimal,
+          fallback: None,
+        },
+        TemplateWarning {
+          plugin: "unknown".into(),
+          tier: PermissionTier::Minimal,
+          fallback: None,
+        },
+      ]
+    );
+    assert_eq!(template.capability.windows, ["main".into()]);
+    assert!(template.capability.context.is_local());
+  }
+
+  #[test]
+  fn recommended_template() {
+    let acl = acl();
+    let template = capability_template(
+      "scaffold",
+      &["fs", "http", "shell"],
+      &acl,
+      PermissionTier::Recommended,
+    );
+
+    assert_eq!(
+      permissions(&template),
+      ["fs:read-write", "http:web", "shell:open-only"]
+    );
+    assert_eq!(
+      template
+        .warnings
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>(),
+      ["plugin shell does not define a recommended permission set, using shell:open-only instead"]
+    );
+
+    // the template is ready to use
+    let resolved = Resolved::resolve(
+      acl,
+      [(
+        template.capability.identifier.clone(),
+        template.capability.clone(),
+      )]
+      .into_iter()
+      .collect(),
+      Target::current(),
+    )
+    .unwrap();
+    assert_eq!(
+      resolved
+        .allowed_commands
+        .keys()
+        .map(|key| key.name.as_str())
+        .collect::<Vec<_>>(),
+      [
+        "plugin:fs|read",
+        "plugin:fs|write",
+        "plugin:http|cancel",
+        "plugin:http|fetch",
+        "plugin:shell|open"
+      ]
+    );
+
+    // and round-trips through its file content
+    let capability: Capability = serde_json::from_str(&template.to_json().unwrap()).unwrap();
+    assert_eq!(
+      serde_json::to_value(&capability).unwrap(),
+      serde_json::to_value(&template.capability).unwrap()
+    );
+  }
+}
@@ -6,9 +6,18 @@
 //! to support both formats.
 
 use std::collections::BTreeMap;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
-use serde::{Deserialize, Serialize};
+use base64::Engine;
+use serde::{
+  de::{Error as _, MapAccess, SeqAccess, Visitor},
+  ser::SerializeMap,
+  Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// The key of the single-entry map holding the base64 encoded [`Value::Bytes`] in text formats,
+/// e.g. `{ "$bytes": "dGF1cmk=" }`.
+pub const BYTES_KEY: &str = "$bytes";
 
 /// A valid ACL number.
 #[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialOrd, PartialEq)]
@@ -18,6 +27,9 @@ pub enum Number {
   /// Represents an [`i64`].
   Int(i64),
 
+  /// Represents a [`u64`] above [`i64::MAX`], which a [`f64`] cannot represent exactly.
+  UInt(u64),
+
   /// Represents a [`f64`].
   Float(f64),
 }
@@ -29,6 +41,13 @@ impl From<i64> for Number {
   }
 }
 
+impl From<u64> for Number {
+  #[inline(always)]
+  fn from(value: u64) -> Self {
+    i64::try_from(value).map_or(Self::UInt(value), Self::Int)
+  }
+}
+
 impl From<f64> for Number {
   #[inline(always)]
   fn from(value: f64) -> Self {
@@ -37,7 +56,9 @@ impl From<f64> for Number {
 }
 
 /// All supported ACL values.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialOrd, PartialEq)]
+///
+/// Numbers are kept exactly: integers are [`Number::Int`], or [`Number::UInt`] above [`i64::MAX`].
+#[derive(Debug, Serialize, Clone, PartialOrd, PartialEq)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum Value {
@@ -58,6 +79,141 @@ pub enum Value {
 
   /// Represents a map of [`String`] keys to [`Value`]s.
   Map(BTreeMap<String, Value>),
+
+  /// Represents binary data.
+  ///
+  /// Text formats like JSON and TOML have no bytes type, so the data is written
+  /// as a base64 string in a single-entry map with the [`BYTES_KEY`] key, e.g. `{ "$bytes": "dGF1cmk=" }`.
+  #[serde(serialize_with = "serialize_bytes")]
+  #[cfg_attr(feature = "schema", schemars(with = "Base64Bytes"))]
+  Bytes(Vec<u8>),
+}
+
+/// The schema of [`Value::Bytes`] in text formats.
+#[cfg(feature = "schema")]
+#[derive(schemars::JsonSchema)]
+#[allow(dead_code)]
+struct Base64Bytes {
+  /// The base64 encoded bytes.
+  #[serde(rename = "$bytes")]
+  bytes: String,
+}
+
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+  if serializer.is_human_readable() {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry(
+      BYTES_KEY,
+      &base64::engine::general_purpose::STANDARD.encode(bytes),
+    )?;
+    map.end()
+  } else {
+    serializer.serialize_bytes(bytes)
+  }
+}
+
+impl<'de> Deserialize<'de> for Value {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(ValueVisitor)
+  }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+  type Value = Value;
+
+  fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("an ACL value")
+  }
+
+  fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+    Ok(Value::Bool(value))
+  }
+
+  fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+    Ok(value.into())
+  }
+
+  fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+    Ok(value.into())
+  }
+
+  fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+    Ok(value.into())
+  }
+
+  fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+    Ok(Value::String(value.into()))
+  }
+
+  fn visit_string<E>(self, value: String) -> Result<Value, E> {
+    Ok(Value::String(value))
+  }
+
+  fn visit_bytes<E>(self, value: &[u8]) -> Result<Value, E> {
+    Ok(Value::Bytes(value.into()))
+  }
+
+  fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<Value, E> {
+    Ok(Value::Bytes(value))
+  }
+
+  fn visit_none<E>(self) -> Result<Value, E> {
+    Ok(Value::Null)
+  }
+
+  fn visit_unit<E>(self) -> Result<Value, E> {
+    Ok(Value::Null)
+  }
+
+  fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+    Value::deserialize(deserializer)
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+    let mut list = Vec::new();
+    while let Some(value) = seq.next_element()? {
+      list.push(value);
+    }
+    Ok(Value::List(list))
+  }
+
+  fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+    let mut map = BTreeMap::new();
+    while let Some((key, value)) = access.next_entry()? {
+      map.insert(key, value);
+    }
+    match map.get(BYTES_KEY) {
+      Some(Value::String(encoded)) if map.len() == 1 => base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map(Value::Bytes)
+        .map_err(|error| A::Error::custom(format!("invalid `{BYTES_KEY}` base64 value: {error}"))),
+      _ => Ok(Value::Map(map)),
+    }
+  }
+}
+
+impl Value {
+  /// Deep-merges another value into this one, the other value wins on conflicts.
+  ///
+  /// Maps are merged key by key, recursively. Any other value, including lists, is replaced
+  /// by the other value, so a list is never concatenated with the list it overrides.
+  pub fn deep_merge(&mut self, other: Value) {
+    match (self, other) {
+      (Value::Map(map), Value::Map(other)) => {
+        for (key, value) in other {
+          match map.get_mut(&key) {
+            Some(existing) => existing.deep_merge(value),
+            None => {
+              map.insert(key, value);
+            }
+          }
+        }
+      }
+      (this, other) => *this = other,
+    }
+  }
 }
 
 impl From<Value> for serde_json::Value {
@@ -69,6 +225,7 @@ impl From<Value> for serde_json::Value {
         serde_json::Value::Number(serde_json::Number::from_f64(f).unwrap())
       }
       Value::Number(Number::Int(i)) => serde_json::Value::Number(i.into()),
+      Value::Number(Number::UInt(u)) => serde_json::Value::Number(u.into()),
       Value::String(s) => serde_json::Value::String(s),
       Value::List(list) => serde_json::Value::Array(list.into_iter().map(Into::into).collect()),
       Value::Map(map) => serde_json::Value::Object(
@@ -77,6 +234,8 @@ impl From<Value> for serde_json::Value {
           .map(|(key, value)| (key, value.into()))
           .collect(),
       ),
+      // the way serde_json serializes bytes, so scope types can deserialize them into `Vec<u8>`
+      Value::Bytes(bytes) => serde_json::Value::Array(bytes.into_iter().map(Into::into).collect()),
     }
   }
 }
@@ -86,14 +245,12 @@ impl From<serde_json::Value> for Value {
     match value {
       serde_json::Value::Null => Value::Null,
       serde_json::Value::Bool(b) => Value::Bool(b),
-      serde_json::Value::Number(n) => Value::Number(if let Some(f) = n.as_f64() {
-        Number::Float(f)
-      } else if let Some(n) = n.as_u64() {
-        Number::Int(n as i64)
-      } else if let Some(n) = n.as_i64() {
+      serde_json::Value::Number(n) => Value::Number(if let Some(n) = n.as_i64() {
         Number::Int(n)
+      } else if let Some(n) = n.as_u64() {
+        Number::UInt(n)
       } else {
-        Number::Int(0)
+        Number::Float(n.as_f64().unwrap_or_default())
       }),
       serde_json::Value::String(s) => Value::String(s),
       serde_json::Value::Array(list) => Value::List(list.into_iter().map(Into::into).collect()),
@@ -145,7 +302,7 @@ impl From<toml::Value> for Value {
   }
 }
 
-#[cfg(feature = "build")]
+#[cfg(feature = "tokens")]
 mod build {
   use std::convert::identity;
 
@@ -163,6 +320,9 @@ mod build {
         Self::Int(i) => {
           quote! { #prefix::Int(#i) }
         }
+        Self::UInt(u) => {
+          quote! { #prefix::UInt(#u) }
+        }
         Self::Float(f) => {
           quote! { #prefix::Float (#f) }
         }
@@ -195,7 +355,133 @@ mod build {
           );
           quote! { #prefix::Map(#map) }
         }
+        Value::Bytes(bytes) => {
+          let bytes = proc_macro2::Literal::byte_string(bytes);
+          quote! { #prefix::Bytes(#bytes.to_vec()) }
+        }
       });
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{Number, Value};
+
+  fn value(json: &str) -> Value {
+    serde_json::from_str::<serde_json::Value>(json)
+      .unwrap()
+      .into()
+  }
+
+  #[test]
+  fn deep_merge_nested_maps() {
+    let mut merged = value(
+      r#"{ "url": "https://tauri.app", "headers": { "accept": "text/html", "x-a": "1" }, "retry": { "count": 1 } }"#,
+    );
+    merged.deep_merge(value(
+      r#"{ "url": "https://tauri.app", "headers": { "x-a": "2", "x-b": "3" }, "timeout": 10 }"#,
+    ));
+    assert_eq!(
+      merged,
+      value(
+        r#"{ "url": "https://tauri.app", "headers": { "accept": "text/html", "x-a": "2", "x-b": "3" }, "retry": { "count": 1 }, "timeout": 10 }"#
+      )
+    );
+  }
+
+  #[test]
+  fn deep_merge_replaces_lists_and_scalars() {
+    let mut merged =
+      value(r#"{ "methods": ["GET", "POST"], "nested": { "ports": [80] }, "mode": { "a": 1 } }"#);
+    merged.deep_merge(value(
+      r#"{ "methods": ["GET"], "nested": { "ports": [] }, "mode": "strict" }"#,
+    ));
+    assert_eq!(
+      merged,
+      value(r#"{ "methods": ["GET"], "nested": { "ports": [] }, "mode": "strict" }"#)
+    );
+
+    let mut merged = value(r#"{ "a": 1 }"#);
+    merged.deep_merge(value(r#"["b"]"#));
+    assert_eq!(merged, value(r#"["b"]"#));
+
+    let mut merged = value(r#"{ "a": 1 }"#);
+    merged.deep_merge(value(r#"{ "a": null }"#));
+    assert_eq!(merged, value(r#"{ "a": null }"#));
+  }
+
+  #[test]
+  fn exact_numbers_round_trip() {
+    let json = format!(r#"{{"float":1.5,"max":{},"min":{}}}"#, u64::MAX, i64::MIN);
+    let parsed = serde_json::from_str::<Value>(&json).unwrap();
+    assert_eq!(
+      parsed,
+      Value::Map(
+        [
+          ("float".into(), 1.5.into()),
+          ("max".into(), Value::Number(Number::UInt(u64::MAX))),
+          ("min".into(), i64::MIN.into()),
+        ]
+        .into()
+      )
+    );
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+    assert_eq!(value(&json), parsed);
+    // an integer that fits an `i64` keeps being an `Int`
+    assert_eq!(value("9007199254740993"), Value::from(9007199254740993_i64));
+    assert_eq!(Value::from(42_u64), Value::from(42_i64));
+  }
+
+  #[test]
+  fn bytes_round_trip() {
+    let bytes = Value::Bytes(vec![0, 1, 2, 255]);
+    let json = serde_json::to_string(&bytes).unwrap();
+    assert_eq!(json, r#"{"$bytes":"AAEC/w=="}"#);
+    assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), bytes);
+
+    let toml = r#"token = { "$bytes" = "AAEC/w==" }"#;
+    assert_eq!(
+      toml::from_str::<Value>(toml).unwrap(),
+      Value::Map([("token".into(), bytes.clone())].into())
+    );
+
+    // other maps are left as is, even with the bytes key
+    assert!(matches!(
+      value(r#"{ "$bytes": "AAEC/w==", "len": 4 }"#),
+      Value::Map(_)
+    ));
+    assert!(serde_json::from_str::<Value>(r#"{ "$bytes": "not base64!" }"#).is_err());
+
+    // scope types get the bytes the way serde_json represents them
+    let raw: Vec<u8> = serde_json::from_value(bytes.into()).unwrap();
+    assert_eq!(raw, [0, 1, 2, 255]);
+  }
+
+  #[test]
+  fn strings_are_not_decoded() {
+    assert_eq!(value(r#""AAEC/w==""#), Value::String("AAEC/w==".into()));
+  }
+
+  #[cfg(feature = "tokens")]
+  #[test]
+  fn to_tokens() {
+    use quote::ToTokens;
+
+    assert_eq!(
+      Value::Number(Number::UInt(u64::MAX))
+        .into_token_stream()
+        .to_string(),
+      quote::quote!(::tauri::utils::acl::Value::Number(
+        ::tauri::utils::acl::Number::UInt(18446744073709551615u64)
+      ))
+      .to_string()
+    );
+    assert_eq!(
+      Value::Bytes(vec![0, 1, 255])
+        .into_token_stream()
+        .to_string(),
+      quote::quote!(::tauri::utils::acl::Value::Bytes(b"\0\x01\xFF".to_vec())).to_string()
+    );
+  }
+}
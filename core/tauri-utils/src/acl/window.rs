@@ -0,0 +1,515 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Window label patterns of capabilities.
+
+use std::{
+  cmp::Ordering,
+  collections::HashMap,
+  fmt,
+  hash::Hash,
+  str::FromStr,
+  sync::{Arc, Mutex, OnceLock, PoisonError, Weak},
+};
+
+use thiserror::Error;
+
+/// Errors parsing a [`WindowPattern`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum WindowPatternError {
+  /// Invalid glob pattern.
+  #[error(transparent)]
+  Glob(#[from] glob::PatternError),
+  /// Unknown template parameter type.
+  #[error("unknown template parameter type {0:?}, expected one of uuid, int, hex or alnum")]
+  UnknownParameter(String),
+  /// A template parameter is not closed.
+  #[error("unterminated template parameter at position {0}")]
+  UnterminatedParameter(usize),
+  /// A `}` does not close a template parameter.
+  #[error("unexpected `}}` at position {0}")]
+  UnexpectedClose(usize),
+  /// A template contains a glob wildcard.
+  #[error("templates cannot contain glob wildcards, found {0:?}")]
+  Wildcard(char),
+  /// A variable length template parameter is not followed by a literal it cannot match.
+  #[error(
+    "the {0} parameter must be followed by a character it does not match or end the template"
+  )]
+  AmbiguousParameter(LabelParameter),
+}
+
+/// The type of a window label template parameter, see [`WindowPattern`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LabelParameter {
+  /// A hyphenated UUID, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+  Uuid,
+  /// One or more ASCII digits.
+  Int,
+  /// One or more ASCII hexadecimal digits.
+  Hex,
+  /// One or more ASCII letters or digits.
+  Alnum,
+}
+
+impl LabelParameter {
+  fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "uuid" => Some(Self::Uuid),
+      "int" => Some(Self::Int),
+      "hex" => Some(Self::Hex),
+      "alnum" => Some(Self::Alnum),
+      _ => None,
+    }
+  }
+
+  /// Whether the parameter matches the character, for the variable length parameters.
+  fn accepts(self, c: char) -> bool {
+    match self {
+      Self::Uuid | Self::Hex => c.is_ascii_hexdigit(),
+      Self::Int => c.is_ascii_digit(),
+      Self::Alnum => c.is_ascii_alphanumeric(),
+    }
+  }
+
+  /// Strips the parameter value from the start of the label.
+  fn strip(self, label: &str) -> Option<&str> {
+    if self == Self::Uuid {
+      let uuid = label.get(..36)?;
+      let valid = uuid.char_indices().all(|(i, c)| match i {
+        8 | 13 | 18 | 23 => c == '-',
+        _ => c.is_ascii_hexdigit(),
+      });
+      return valid.then(|| &label[36..]);
+    }
+
+    let len = label.find(|c| !self.accepts(c)).unwrap_or(label.len());
+    (len > 0).then(|| &label[len..])
+  }
+}
+
+impl fmt::Display for LabelParameter {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Self::Uuid => "uuid",
+      Self::Int => "int",
+      Self::Hex => "hex",
+      Self::Alnum => "alnum",
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+enum TemplatePart {
+  Literal(String),
+  Parameter(LabelParameter),
+}
+
+enum Matcher {
+  Glob(glob::Pattern),
+  Template {
+    original: String,
+    parts: Vec<TemplatePart>,
+  },
+}
+
+/// A window label pattern of a capability.
+///
+/// Either a glob pattern, e.g. `editor-*`, or a template with typed parameters,
+/// e.g. `editor-{uuid}` or `doc-{int}`, which only matches the labels whose parameter segments
+/// have the expected shape: `editor-{uuid}` matches `editor-67e55044-10b1-426f-9247-bb680e5fe0c8` but not `editor-settings`.
+///
+/// The parameter types are `uuid`, `int`, `hex` and `alnum`, see [`LabelParameter`].
+/// Templates are matched in a single pass over the label, without the backtracking of glob patterns.
+/// A variable length parameter must be followed by a character it does not match, so the match is never ambiguous.
+///
+/// The compiled patterns are interned: the equal patterns parsed while they are in use share one allocation,
+/// so the many commands granted to the same windows, e.g. `*`, do not each hold a copy.
+#[derive(Clone)]
+pub struct WindowPattern(Arc<Matcher>);
+
+/// The compiled window patterns in use, keyed by their source string, see [`WindowPattern::new`].
+///
+/// The entries only hold a weak reference, so the patterns of the dynamic capabilities removed later are freed.
+/// The dropped entries are pruned once the table doubled in size since the last pruning.
+#[derive(Default)]
+struct Interner {
+  patterns: HashMap<String, Weak<Matcher>>,
+  prune_at: usize,
+}
+
+/// Minimum size of the interned pattern table before its dropped entries are pruned.
+const INTERNER_PRUNE_MIN: usize = 64;
+
+impl Interner {
+  fn global() -> &'static Mutex<Self> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+  }
+
+  fn insert(&mut self, pattern: &str, matcher: &Arc<Matcher>) {
+    if self.patterns.len() >= self.prune_at.max(INTERNER_PRUNE_MIN) {
+      self
+        .patterns
+        .retain(|_, matcher| matcher.strong_count() > 0);
+      self.prune_at = self.patterns.len() * 2;
+    }
+    self
+      .patterns
+      .insert(pattern.to_string(), Arc::downgrade(matcher));
+  }
+}
+
+impl WindowPattern {
+  /// Parses a window label pattern, as a template if it contains a `{` or `}`, as a glob pattern otherwise.
+  ///
+  /// Returns the compiled pattern shared with the equal patterns still in use, if any.
+  pub fn new(pattern: &str) -> Result<Self, WindowPatternError> {
+    let mut interner = Interner::global()
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner);
+    if let Some(matcher) = interner.patterns.get(pattern).and_then(Weak::upgrade) {
+      return Ok(Self(matcher));
+    }
+
+    let matcher = Arc::new(if pattern.contains(['{', '}']) {
+      Matcher::Template {
+        original: pattern.into(),
+        parts: parse_template(pattern)?,
+      }
+    } else {
+      Matcher::Glob(glob::Pattern::new(pattern)?)
+    });
+    interner.insert(pattern, &matcher);
+    Ok(Self(matcher))
+  }
+
+  /// Number of entries of the interned pattern table, including the dropped patterns not pruned yet.
+  #[cfg(test)]
+  pub(crate) fn interned_count() -> usize {
+    Interner::global()
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .patterns
+      .len()
+  }
+
+  /// Identifies the compiled pattern, shared by the equal patterns, to account its heap size once.
+  pub(crate) fn allocation(&self) -> usize {
+    Arc::as_ptr(&self.0) as usize
+  }
+
+  /// The original pattern.
+  pub fn as_str(&self) -> &str {
+    match &*self.0 {
+      Matcher::Glob(pattern) => pattern.as_str(),
+      Matcher::Template { original, .. } => original,
+    }
+  }
+
+  /// Whether this is a template pattern.
+  pub fn is_template(&self) -> bool {
+    matches!(*self.0, Matcher::Template { .. })
+  }
+
+  /// Whether the pattern only matches itself.
+  pub fn is_literal(&self) -> bool {
+    match &*self.0 {
+      Matcher::Glob(pattern) => glob::Pattern::escape(pattern.as_str()) == pattern.as_str(),
+      Matcher::Template { .. } => false,
+    }
+  }
+
+  /// The estimated heap sizes of the pattern string and of the compiled pattern, in bytes.
+  ///
+  /// The compiled pattern includes its shared allocation.
+  pub(crate) fn heap_sizes(&self) -> (usize, usize) {
+    let allocation = std::mem::size_of::<Matcher>() + 2 * std::mem::size_of::<usize>();
+    let (source, compiled) = match &*self.0 {
+      Matcher::Glob(pattern) => (
+        pattern.as_str().len(),
+        pattern.as_str().chars().count() * super::memory::GLOB_TOKEN_SIZE,
+      ),
+      Matcher::Template { original, parts } => (
+        original.capacity(),
+        parts.capacity() * std::mem::size_of::<TemplatePart>()
+          + parts
+            .iter()
+            .map(|part| match part {
+              TemplatePart::Literal(literal) => literal.capacity(),
+              TemplatePart::Parameter(_) => 0,
+            })
+            .sum::<usize>(),
+      ),
+    };
+    (source, allocation + compiled)
+  }
+
+  /// Whether the label matches this pattern.
+  pub fn matches(&self, label: &str) -> bool {
+    match &*self.0 {
+      Matcher::Glob(pattern) => pattern.matches(label),
+      Matcher::Template { parts, .. } => {
+        let mut label = label;
+        for part in parts {
+          let rest = match part {
+            TemplatePart::Literal(literal) => label.strip_prefix(literal.as_str()),
+            TemplatePart::Parameter(parameter) => parameter.strip(label),
+          };
+          match rest {
+            Some(rest) => label = rest,
+            None => return false,
+          }
+        }
+        label.is_empty()
+      }
+    }
+  }
+}
+
+impl super::memory::HeapSize for WindowPattern {
+  /// The compiled pattern is shared, so it is accounted once in the [`WindowPatternStats`](super::memory::WindowPatternStats)
+  /// instead of in each value holding it.
+  fn heap_size(&self) -> usize {
+    0
+  }
+}
+
+fn parse_template(pattern: &str) -> Result<Vec<TemplatePart>, WindowPatternError> {
+  let mut parts = Vec::new();
+  let mut literal = String::new();
+  let mut chars = pattern.char_indices();
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '{' => {
+        let name = pattern[i + 1..]
+          .split_once('}')
+          .map(|(name, _)| name)
+          .ok_or(WindowPatternError::UnterminatedParameter(i))?;
+        let parameter = LabelParameter::from_name(name)
+          .ok_or_else(|| WindowPatternError::UnknownParameter(name.into()))?;
+        if !literal.is_empty() {
+          parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+        }
+        parts.push(TemplatePart::Parameter(parameter));
+        chars.nth(name.chars().count());
+      }
+      '}' => return Err(WindowPatternError::UnexpectedClose(i)),
+      '*' | '?' | '[' | ']' => return Err(WindowPatternError::Wildcard(c)),
+      c => literal.push(c),
+    }
+  }
+  if !literal.is_empty() {
+    parts.push(TemplatePart::Literal(literal));
+  }
+
+  for (part, next) in parts
+    .iter()
+    .zip(parts.iter().skip(1).map(Some).chain([None]))
+  {
+    if let TemplatePart::Parameter(parameter) = part {
+      let ambiguous = *parameter != LabelParameter::Uuid
+        && match next {
+          Some(TemplatePart::Literal(literal)) => literal.starts_with(|c| parameter.accepts(c)),
+          Some(TemplatePart::Parameter(_)) => true,
+          None => false,
+        };
+      if ambiguous {
+        return Err(WindowPatternError::AmbiguousParameter(*parameter));
+      }
+    }
+  }
+
+  Ok(parts)
+}
+
+impl FromStr for WindowPattern {
+  type Err = WindowPatternError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::new(s)
+  }
+}
+
+impl fmt::Display for WindowPattern {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+impl fmt::Debug for WindowPattern {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &*self.0 {
+      Matcher::Glob(pattern) => pattern.fmt(f),
+      Matcher::Template { original, parts } => f
+        .debug_struct("Template")
+        .field("original", original)
+        .field("parts", parts)
+        .finish(),
+    }
+  }
+}
+
+impl PartialEq for WindowPattern {
+  fn eq(&self, other: &Self) -> bool {
+    self.as_str() == other.as_str()
+  }
+}
+
+impl Eq for WindowPattern {}
+
+impl PartialOrd for WindowPattern {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for WindowPattern {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.as_str().cmp(other.as_str())
+  }
+}
+
+impl Hash for WindowPattern {
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    self.as_str().hash(state)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{WindowPattern, WindowPatternError};
+
+  const UUID: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+  #[test]
+  fn template_parameters() {
+    let editor = WindowPattern::new("editor-{uuid}").unwrap();
+    assert!(editor.is_template());
+    assert!(editor.matches(&format!("editor-{UUID}")));
+    assert!(editor.matches(&format!("editor-{}", UUID.to_uppercase())));
+    assert!(!editor.matches("editor-settings"));
+    assert!(!editor.matches(&format!("editor-{UUID}-settings")));
+    assert!(!editor.matches(&format!("editor-{}", UUID.replace('-', "_"))));
+
+    let doc = WindowPattern::new("doc-{int}/view-{hex}").unwrap();
+    assert!(doc.matches("doc-42/view-ff00"));
+    assert!(!doc.matches("doc-/view-ff00"));
+    assert!(!doc.matches("doc-4a/view-ff00"));
+    assert!(!doc.matches("doc-42/view-fg"));
+
+    let tab = WindowPattern::new("{alnum}-tab").unwrap();
+    assert!(tab.matches("main2-tab"));
+    assert!(!tab.matches("main_2-tab"));
+    assert!(WindowPattern::new("{uuid}{int}").is_ok());
+  }
+
+  #[test]
+  fn invalid_templates() {
+    for (pattern, expected) in [
+      (
+        "editor-{guid}",
+        "unknown template parameter type \"guid\", expected one of uuid, int, hex or alnum",
+      ),
+      (
+        "editor-{uuid",
+        "unterminated template parameter at position 7",
+      ),
+      ("editor-uuid}", "unexpected `}` at position 11"),
+      (
+        "editor-*-{int}",
+        "templates cannot contain glob wildcards, found '*'",
+      ),
+      (
+        "doc-{hex}a",
+        "the hex parameter must be followed by a character it does not match or end the template",
+      ),
+      (
+        "doc-{int}{alnum}",
+        "the int parameter must be followed by a character it does not match or end the template",
+      ),
+    ] {
+      let error = WindowPattern::new(pattern).unwrap_err();
+      assert_eq!(error.to_string(), expected, "{pattern}");
+    }
+    assert!(matches!(
+      WindowPattern::new("main-[*"),
+      Err(WindowPatternError::Glob(_))
+    ));
+  }
+
+  #[test]
+  fn glob_patterns() {
+    let pattern = WindowPattern::new("editor-*").unwrap();
+    assert!(!pattern.is_template());
+    assert!(!pattern.is_literal());
+    assert!(pattern.matches("editor-settings"));
+    assert!(WindowPattern::new("main").unwrap().is_literal());
+    assert_eq!(
+      format!("{:?}", WindowPattern::new("main").unwrap()),
+      format!("{:?}", glob::Pattern::new("main").unwrap())
+    );
+  }
+
+  #[test]
+  fn interned_patterns() {
+    let main = WindowPattern::new("interned-main").unwrap();
+    assert_eq!(
+      main.allocation(),
+      WindowPattern::new("interned-main").unwrap().allocation()
+    );
+    assert_eq!(main.allocation(), main.clone().allocation());
+    assert_ne!(
+      main.allocation(),
+      WindowPattern::new("interned-main-*").unwrap().allocation()
+    );
+
+    // the patterns of removed capabilities are not kept alive by the table
+    for round in 0..10 {
+      let patterns = (0..1000)
+        .map(|i| WindowPattern::new(&format!("interned-{round}-{i}")).unwrap())
+        .collect::<Vec<_>>();
+      drop(patterns);
+    }
+    assert!(WindowPattern::interned_count() < 5000);
+    assert_eq!(
+      WindowPattern::new("interned-main").unwrap().allocation(),
+      main.allocation()
+    );
+  }
+
+  #[test]
+  #[ignore = "benchmark"]
+  fn template_matching_benchmark() {
+    use std::time::Instant;
+
+    let template = WindowPattern::new("editor-{uuid}").unwrap();
+    let hex = "[0-9a-fA-F]";
+    let glob = glob::Pattern::new(&format!(
+      "editor-{}-{}-{}-{}-{}",
+      hex.repeat(8),
+      hex.repeat(4),
+      hex.repeat(4),
+      hex.repeat(4),
+      hex.repeat(12)
+    ))
+    .unwrap();
+    let labels = (0..100_000u64)
+      .map(|i| format!("editor-{:08x}-10b1-426f-9247-{:012x}", i, i * 7))
+      .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    assert!(labels.iter().all(|label| glob.matches(label)));
+    let glob = start.elapsed();
+
+    let start = Instant::now();
+    assert!(labels.iter().all(|label| template.matches(label)));
+    let template = start.elapsed();
+
+    println!("100k labels: {glob:?} glob, {template:?} template");
+    assert!(template < glob);
+  }
+}
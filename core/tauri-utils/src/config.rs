@@ -1560,6 +1560,20 @@ pub struct SecurityConfig {
   /// Custom protocol config.
   #[serde(default, alias = "asset-protocol")]
   pub asset_protocol: AssetProtocolConfig,
+  /// Extends the `connect-src` directive of the CSP with the remote origins the capabilities grant to each window.
+  ///
+  /// Every window gets the `https://` sources of the remote domains of the capabilities matching its label,
+  /// so the frontend can reach the origins allowed to use the IPC without maintaining the list by hand.
+  /// Domain patterns that cannot be expressed as CSP sources, e.g. `*` or `tauri.*`, are never added.
+  #[serde(default, alias = "capability-connect-src")]
+  pub capability_connect_src: bool,
+  /// Binds the events emitted by a command to the webview that passed the ACL check for its invoke.
+  ///
+  /// While a command runs, the events it emits are only delivered to the JavaScript listeners of the webview that invoked it,
+  /// whatever their target, so a webview cannot use a command it is allowed to call to reach the listeners of other webviews.
+  /// The Rust listeners still receive them. A command opts out with `tauri::ipc::unbound`.
+  #[serde(default, alias = "bind-command-events")]
+  pub bind_command_events: bool,
 }
 
 /// The application pattern.
@@ -2138,7 +2152,7 @@ fn default_build() -> BuildConfig {
 /// This allows for a build script to output the values in a `Config` to a `TokenStream`, which can
 /// then be consumed by another crate. Useful for passing a config to both the build script and the
 /// application using tauri while only parsing it once (in the build script).
-#[cfg(feature = "build")]
+#[cfg(feature = "tokens")]
 mod build {
   use super::*;
   use crate::{literal_struct, tokens::*};
@@ -2616,6 +2630,8 @@ mod build {
       let freeze_prototype = self.freeze_prototype;
       let dangerous_disable_asset_csp_modification = &self.dangerous_disable_asset_csp_modification;
       let asset_protocol = &self.asset_protocol;
+      let capability_connect_src = self.capability_connect_src;
+      let bind_command_events = self.bind_command_events;
 
       literal_struct!(
         tokens,
@@ -2624,7 +2640,9 @@ mod build {
         dev_csp,
         freeze_prototype,
         dangerous_disable_asset_csp_modification,
-        asset_protocol
+        asset_protocol,
+        capability_connect_src,
+        bind_command_events
       );
     }
   }
@@ -2798,6 +2816,8 @@ mod test {
         freeze_prototype: false,
         dangerous_disable_asset_csp_modification: DisabledCspModificationKind::Flag(false),
         asset_protocol: AssetProtocolConfig::default(),
+        capability_connect_src: false,
+        bind_command_events: false,
       },
       tray_icon: None,
       macos_private_api: false,
@@ -14,6 +14,7 @@
 #![allow(clippy::deprecated_semver)]
 
 use std::{
+  borrow::Cow,
   ffi::OsString,
   fmt::Display,
   path::{Path, PathBuf},
@@ -21,6 +22,7 @@ use std::{
 
 use semver::Version;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use unicode_normalization::UnicodeNormalization;
 
 use log::warn;
 
@@ -34,7 +36,7 @@ pub mod platform;
 /// Prepare application resources and sidecars.
 #[cfg(feature = "resources")]
 pub mod resources;
-#[cfg(feature = "build")]
+#[cfg(feature = "tokens")]
 pub mod tokens;
 
 /// Application pattern.
@@ -413,6 +415,53 @@ macro_rules! debug_eprintln {
   };
 }
 
+/// Asserts that the resolved ACL allows the command on the given window.
+///
+/// The command is checked on the local app URL, or on a remote domain with `remote = "tauri.app"`.
+///
+/// ```rust,ignore
+/// assert_command_allowed!(resolved, "plugin:fs|read_file", window = "main");
+/// assert_command_allowed!(resolved, "plugin:fs|read_file", window = "main", remote = "tauri.app");
+/// ```
+#[cfg(feature = "test-fixtures")]
+#[macro_export]
+macro_rules! assert_command_allowed {
+  ($resolved:expr, $command:expr, window = $window:expr $(, remote = $domain:expr)? $(,)?) => {
+    assert!(
+      $crate::acl::fixtures::is_command_allowed(
+        &$resolved,
+        $command,
+        $window,
+        None::<&str>$(.or(Some($domain)))?
+      ),
+      "expected command {} to be allowed on window {}",
+      $command,
+      $window
+    )
+  };
+}
+
+/// Asserts that the resolved ACL does not allow the command on the given window.
+///
+/// Accepts the same arguments as [`assert_command_allowed!`].
+#[cfg(feature = "test-fixtures")]
+#[macro_export]
+macro_rules! assert_command_denied {
+  ($resolved:expr, $command:expr, window = $window:expr $(, remote = $domain:expr)? $(,)?) => {
+    assert!(
+      !$crate::acl::fixtures::is_command_allowed(
+        &$resolved,
+        $command,
+        $window,
+        None::<&str>$(.or(Some($domain)))?
+      ),
+      "expected command {} to not be allowed on window {}",
+      $command,
+      $window
+    )
+  };
+}
+
 /// Reconstructs a path from its components using the platform separator then converts it to String and removes UNC prefixes on Windows if it exists.
 pub fn display_path<P: AsRef<Path>>(p: P) -> String {
   dunce::simplified(&p.as_ref().components().collect::<PathBuf>())
@@ -420,6 +469,21 @@ pub fn display_path<P: AsRef<Path>>(p: P) -> String {
     .to_string()
 }
 
+/// Normalizes a window or webview label, or a window label pattern, to the Unicode Normalization Form C.
+///
+/// Canonically equivalent labels, e.g. spelling `é` with a single code point or with an `e` followed by a combining accent,
+/// are equal once normalized. Windows and webviews normalize their labels on creation, the label lookups
+/// and event targets normalize the requested label, and the ACL normalizes both the labels
+/// and the capability window patterns before matching them.
+/// The normalization does not fold the case, so the matching remains case-sensitive.
+pub fn normalize_label(label: &str) -> Cow<'_, str> {
+  if unicode_normalization::is_nfc(label) {
+    Cow::Borrowed(label)
+  } else {
+    Cow::Owned(label.nfc().collect())
+  }
+}
+
 /// Progress bar status.
 #[derive(Debug, Clone, Copy, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,288 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Benchmarks of the IPC authorization hot path.
+//!
+//! Run with `cargo bench -p tauri --features test --bench authority`.
+//! The fixtures are built with [`Resolved::builder`] for a range of ACL sizes.
+
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use tauri::{
+  command::{CommandScope, EphemeralPolicy, GlobalScope, Origin},
+  test::{get_ipc_response, invoke_request, mock_builder, mock_context, noop_assets, MockRuntime},
+  utils::acl::{
+    resolved::{Resolved, ResolvedBuilder, ResolvedScope},
+    ExecutionContext, Value,
+  },
+  App, Manager, WebviewWindow, WebviewWindowBuilder,
+};
+
+/// Number of allowed and denied commands in the fixtures.
+const COMMANDS: [usize; 3] = [10, 100, 1000];
+/// Number of remote domains granted a command.
+const REMOTE_PATTERNS: [usize; 3] = [1, 10, 100];
+/// Number of entries of the small and large scopes.
+const SCOPE_ENTRIES: [usize; 2] = [4, 1000];
+/// Number of runtime grants invalidated at once.
+const GRANTS: [usize; 3] = [1, 10, 100];
+
+#[derive(Debug, serde::Deserialize)]
+struct PathEntry {
+  #[allow(dead_code)]
+  path: String,
+}
+
+#[tauri::command]
+fn read(scope: CommandScope<'_, PathEntry>) -> usize {
+  scope.allows().len()
+}
+
+#[tauri::command]
+fn read_global(scope: GlobalScope<'_, PathEntry>) -> usize {
+  scope.allows().len()
+}
+
+fn path_scope(entries: usize) -> ResolvedScope {
+  ResolvedScope {
+    allow: (0..entries)
+      .map(|i| {
+        Value::Map(
+          [(
+            "path".to_string(),
+            Value::String(format!("$APPDATA/bench/{i}/**")),
+          )]
+          .into_iter()
+          .collect(),
+        )
+      })
+      .collect(),
+    ..Default::default()
+  }
+}
+
+/// `commands` commands allowed on the `main` window and on the `pool-*` windows, and as many denied commands.
+fn commands(commands: usize) -> ResolvedBuilder {
+  let mut builder = Resolved::builder();
+  for i in 0..commands {
+    builder = builder
+      .allow_command(
+        format!("plugin:bench|allow_{i}"),
+        ExecutionContext::Local,
+        ["main"],
+      )
+      .unwrap()
+      .allow_command(
+        format!("plugin:bench|pool_{i}"),
+        ExecutionContext::Local,
+        ["pool-*"],
+      )
+      .unwrap()
+      .deny_command(
+        format!("plugin:bench|deny_{i}"),
+        ExecutionContext::Local,
+        ["*"],
+      )
+      .unwrap();
+  }
+  builder
+}
+
+/// A command granted to `patterns` remote domains.
+fn remote_domains(patterns: usize) -> Resolved {
+  let mut builder = Resolved::builder();
+  for i in 0..patterns {
+    builder = builder
+      .allow_command(
+        "plugin:bench|fetch",
+        ExecutionContext::Remote {
+          domain: format!("app-{i}.*.example.com").parse().unwrap(),
+          attestation: None,
+        },
+        ["main"],
+      )
+      .unwrap();
+  }
+  builder.build()
+}
+
+fn app(resolved: Resolved) -> (App<MockRuntime>, WebviewWindow<MockRuntime>) {
+  let mut context = mock_context(noop_assets());
+  *context.resolved_acl_mut() = resolved;
+  let app = mock_builder()
+    .plugin(
+      tauri::plugin::Builder::<MockRuntime>::new("bench")
+        .invoke_handler(tauri::generate_handler![read, read_global])
+        .build(),
+    )
+    .build(context)
+    .unwrap();
+  let webview = WebviewWindowBuilder::new(&app, "main", Default::default())
+    .build()
+    .unwrap();
+  (app, webview)
+}
+
+fn lookups(c: &mut Criterion) {
+  let mut group = c.benchmark_group("lookup");
+  for size in COMMANDS {
+    let (app, _webview) = app(commands(size).build());
+    let authority = app.acl();
+    let last = size - 1;
+
+    group.bench_with_input(BenchmarkId::new("exact_label", size), &size, |b, _| {
+      let command = format!("plugin:bench|allow_{last}");
+      b.iter(|| {
+        assert!(authority
+          .resolve_access(&command, "main", &[], &Origin::Local)
+          .is_some())
+      })
+    });
+    group.bench_with_input(BenchmarkId::new("wildcard_window", size), &size, |b, _| {
+      let command = format!("plugin:bench|pool_{last}");
+      b.iter(|| {
+        assert!(authority
+          .resolve_access(&command, "pool-42", &[], &Origin::Local)
+          .is_some())
+      })
+    });
+    group.bench_with_input(BenchmarkId::new("denied", size), &size, |b, _| {
+      let command = format!("plugin:bench|deny_{last}");
+      b.iter(|| {
+        assert!(authority
+          .resolve_access(&command, "main", &[], &Origin::Local)
+          .is_none())
+      })
+    });
+  }
+  group.finish();
+}
+
+fn remote_origins(c: &mut Criterion) {
+  let mut group = c.benchmark_group("remote_origin");
+  for patterns in REMOTE_PATTERNS {
+    let (app, _webview) = app(remote_domains(patterns));
+    let authority = app.acl();
+    // the last pattern matches, every other one is tried first
+    let origin = Origin::Remote {
+      domain: format!("app-{}.eu.example.com", patterns - 1),
+      navigation: None,
+    };
+
+    group.bench_with_input(BenchmarkId::new("match", patterns), &patterns, |b, _| {
+      b.iter(|| {
+        assert!(authority
+          .resolve_access("plugin:bench|fetch", "main", &[], &origin)
+          .is_some())
+      })
+    });
+  }
+  group.finish();
+}
+
+fn scopes(c: &mut Criterion) {
+  let mut group = c.benchmark_group("scope");
+  for entries in SCOPE_ENTRIES {
+    // a single scope is deserialized on the first invoke and cached
+    let (_app, webview) = app(
+      Resolved::builder()
+        .allow_command_scoped(
+          "plugin:bench|read",
+          ExecutionContext::Local,
+          ["main"],
+          path_scope(entries),
+        )
+        .unwrap()
+        .build(),
+    );
+    group.bench_with_input(BenchmarkId::new("cached", entries), &entries, |b, _| {
+      b.iter(|| get_ipc_response(&webview, invoke_request("plugin:bench|read")).unwrap())
+    });
+
+    // scopes granted by different capabilities are merged and deserialized on each invoke
+    let (_app, webview) = app(
+      Resolved::builder()
+        .allow_command_scoped(
+          "plugin:bench|read",
+          ExecutionContext::Local,
+          ["main"],
+          path_scope(entries / 2),
+        )
+        .unwrap()
+        .allow_command_scoped(
+          "plugin:bench|read",
+          ExecutionContext::Local,
+          ["ma*"],
+          path_scope(entries / 2),
+        )
+        .unwrap()
+        .build(),
+    );
+    group.bench_with_input(BenchmarkId::new("merged", entries), &entries, |b, _| {
+      b.iter(|| get_ipc_response(&webview, invoke_request("plugin:bench|read")).unwrap())
+    });
+  }
+  group.finish();
+}
+
+fn runtime_grants(c: &mut Criterion) {
+  let mut group = c.benchmark_group("runtime_grant");
+  let (app, webview) = app(
+    Resolved::builder()
+      .allow_command(
+        "plugin:bench|read_global",
+        ExecutionContext::Local,
+        ["main"],
+      )
+      .unwrap()
+      .window_global_scope("bench", ["main"], path_scope(4))
+      .unwrap()
+      .build(),
+  );
+  let other = WebviewWindowBuilder::new(&app, "other", Default::default())
+    .build()
+    .unwrap();
+  let authority = app.acl();
+  let grant = |window: &WebviewWindow<MockRuntime>, grants: usize, policy: EphemeralPolicy| {
+    for i in 0..grants {
+      authority.grant_ephemeral(
+        "bench",
+        &window.as_ref().window(),
+        serde_json::json!({ "path": format!("$HOME/picked/{i}") }),
+        policy,
+      );
+    }
+  };
+
+  for grants in GRANTS {
+    // the expired grants are dropped by the next invoke
+    group.bench_with_input(BenchmarkId::new("expire", grants), &grants, |b, &grants| {
+      b.iter_batched(
+        || {
+          grant(
+            &webview,
+            grants,
+            EphemeralPolicy::new().expires_in(Duration::ZERO),
+          )
+        },
+        |()| get_ipc_response(&webview, invoke_request("plugin:bench|read_global")).unwrap(),
+        BatchSize::SmallInput,
+      )
+    });
+  }
+  // the grants of the other windows are kept and skipped on each invoke
+  let mut granted = 0;
+  for grants in GRANTS {
+    grant(&other, grants - granted, EphemeralPolicy::new());
+    granted = grants;
+    group.bench_with_input(BenchmarkId::new("other_window", grants), &grants, |b, _| {
+      b.iter(|| get_ipc_response(&webview, invoke_request("plugin:bench|read_global")).unwrap())
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, lookups, remote_origins, scopes, runtime_grants);
+criterion_main!(benches);
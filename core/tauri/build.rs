@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: MIT
 
 use heck::AsShoutySnakeCase;
+use tauri_utils::acl::LocalResource;
 
 use std::env::var_os;
 use std::fs::read_dir;
@@ -116,6 +117,8 @@ const PLUGINS: &[(&str, &[(&str, bool)])] = &[
       ("set_webview_position", false),
       ("set_webview_focus", false),
       ("print", false),
+      // data forwarded to the webview, never invoked
+      ("drag_drop_paths", true),
       // internal
       ("internal_toggle_devtools", true),
     ],
@@ -128,6 +131,7 @@ const PLUGINS: &[(&str, &[(&str, bool)])] = &[
       ("tauri_version", true),
       ("app_show", false),
       ("app_hide", false),
+      ("relaunch", false),
     ],
   ),
   ("resources", &[("close", true)]),
@@ -156,6 +160,8 @@ const PLUGINS: &[(&str, &[(&str, bool)])] = &[
       ("is_checked", false),
       ("set_checked", false),
       ("set_icon", false),
+      // data forwarded to the webview, never invoked
+      ("events", true),
     ],
   ),
   (
@@ -170,10 +176,28 @@ const PLUGINS: &[(&str, &[(&str, bool)])] = &[
       ("set_temp_dir_path", false),
       ("set_icon_as_template", false),
       ("set_show_menu_on_left_click", false),
+      // data forwarded to the webview, never invoked
+      ("events", true),
     ],
   ),
 ];
 
+// (command, local resource identifiers it returns)
+type CommandReturns = &'static [(&'static str, &'static [LocalResource])];
+// (plugin_name, CommandReturns)
+const COMMAND_RETURNS: &[(&str, CommandReturns)] = &[
+  (
+    "path",
+    &[
+      ("resolve_directory", &[LocalResource::Path]),
+      ("resolve", &[LocalResource::Path]),
+    ],
+  ),
+  ("webview", &[("drag_drop_paths", &[LocalResource::Path])]),
+  ("menu", &[("new", &[LocalResource::Handle])]),
+  ("tray", &[("new", &[LocalResource::Handle])]),
+];
+
 // checks if the given Cargo feature is enabled.
 fn has_feature(feature: &str) -> bool {
   CHECKED_FEATURES
@@ -308,9 +332,13 @@ fn define_permissions(out_dir: &Path) {
     let autogenerated = PathBuf::from(format!("permissions/{plugin}/autogenerated/"));
     let commands_dir = autogenerated.join("commands");
 
-    tauri_utils::acl::build::autogenerate_command_permissions(
+    tauri_utils::acl::build::autogenerate_command_permissions_with_returns(
       &commands_dir,
       &commands.iter().map(|(cmd, _)| *cmd).collect::<Vec<_>>(),
+      COMMAND_RETURNS
+        .iter()
+        .find(|(name, _)| name == plugin)
+        .map_or(&[], |(_, returns)| returns),
       license_header,
     );
     let default_permissions = commands
@@ -344,6 +372,10 @@ permissions = [{default_permissions}]
       out_dir,
     )
     .unwrap_or_else(|e| panic!("failed to define permissions for {plugin}: {e}"));
+    tauri_utils::acl::build::define_plugin_version(
+      env!("CARGO_PKG_VERSION"),
+      &format!("tauri:{plugin}"),
+    );
     tauri_utils::acl::build::generate_schema(&permissions, format!("./permissions/{plugin}"))
       .unwrap_or_else(|e| panic!("failed to generate schema for {plugin}: {e}"));
   }
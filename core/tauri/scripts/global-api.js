@@ -0,0 +1,92 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+;(function () {
+  const tauri = window.__TAURI__
+  if (!tauri) {
+    return
+  }
+
+  const acl = __TEMPLATE_acl__
+  const plugins = __TEMPLATE_plugins__
+  const localOrigins = __TEMPLATE_local_origins__
+
+  function globMatches(pattern, value) {
+    const regex = pattern
+      .split('*')
+      .map(function (part) {
+        return part
+          .split('?')
+          .map(function (p) {
+            return p.replace(/[.+^${}()|[\]\\]/g, '\\$&')
+          })
+          .join('.')
+      })
+      .join('.*')
+    return new RegExp(`^${regex}$`).test(value)
+  }
+
+  // the ACL context of the page is evaluated on each navigation, since this script runs on every page load
+  const isLocal =
+    localOrigins.includes(`${window.location.protocol}//${window.location.host}`) ||
+    localOrigins.includes(`${window.location.protocol}//${window.location.hostname}`)
+  const contexts = acl.contexts.filter(function (context) {
+    return context.remote === null
+      ? isLocal
+      : !isLocal && globMatches(context.remote, window.location.hostname)
+  })
+  const denied = new Set()
+  contexts.forEach(function (context) {
+    context.deny.forEach(function (command) {
+      denied.add(command)
+    })
+  })
+  const allowed = new Set()
+  contexts.forEach(function (context) {
+    context.allow.forEach(function (command) {
+      if (!denied.has(command)) {
+        allowed.add(command)
+      }
+    })
+  })
+
+  function isPluginAllowed(plugin) {
+    const prefix = `plugin:${plugin}|`
+    for (const command of allowed) {
+      if (command.startsWith(prefix)) {
+        return true
+      }
+    }
+    return false
+  }
+
+  let pruned = false
+  const api = {}
+  Object.keys(tauri).forEach(function (namespace) {
+    if (plugins.includes(namespace) && !isPluginAllowed(namespace)) {
+      pruned = true
+      return
+    }
+    const functions = acl.functions[namespace]
+    if (!functions) {
+      api[namespace] = tauri[namespace]
+      return
+    }
+    const members = {}
+    Object.keys(tauri[namespace]).forEach(function (name) {
+      const command = functions[name]
+      if (command && !allowed.has(`plugin:${namespace}|${command}`)) {
+        pruned = true
+      } else {
+        members[name] = tauri[namespace][name]
+      }
+    })
+    api[namespace] = Object.freeze(members)
+  })
+
+  // keep the original object when everything is allowed
+  if (pruned) {
+    window.__TAURI__ = Object.freeze(api)
+  }
+})()
@@ -7,10 +7,25 @@
   const osName = __TEMPLATE_os_name__
   const fetchChannelDataCommand = __TEMPLATE_fetch_channel_data_command__
   const useCustomProtocol = __TEMPLATE_use_custom_protocol__
+  const sendWebviewLabel = __TEMPLATE_send_webview_label__
 
   Object.defineProperty(window.__TAURI_INTERNALS__, 'postMessage', {
     value: (message) => {
-      const { cmd, callback, error, payload, options } = message
+      const { cmd, callback, error, payload } = message
+      // the label is only sent in debug builds to flag spoofing attempts,
+      // the ACL always resolves the webview from the IPC channel itself
+      const webviewLabel = sendWebviewLabel
+        ? window.__TAURI_INTERNALS__.metadata?.currentWebview?.label
+        : undefined
+      const options = webviewLabel
+        ? {
+            ...message.options,
+            headers: {
+              'Tauri-Webview-Label': webviewLabel,
+              ...message.options?.headers
+            }
+          }
+        : message.options
 
       // use custom protocol for IPC if:
       // - the flag is set to true or
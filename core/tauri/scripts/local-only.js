@@ -0,0 +1,15 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+;(function () {
+  const localOrigins = __TEMPLATE_local_origins__
+
+  // the origin is checked on each navigation, since the initialization scripts run on every page load
+  if (
+    localOrigins.includes(`${window.location.protocol}//${window.location.host}`) ||
+    localOrigins.includes(`${window.location.protocol}//${window.location.hostname}`)
+  ) {
+    __RAW_script__
+  }
+})()
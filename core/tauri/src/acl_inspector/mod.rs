@@ -0,0 +1,475 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! A window rendering the resolved Access Control List, only available in debug builds.
+
+use std::{
+  collections::BTreeMap,
+  io::{self, Write},
+};
+
+use serde::Serialize;
+use tauri_utils::acl::{
+  resolved::{
+    CommandKey, ResolvedCommand, ResolvedScope, ScopeKey, WebviewPattern, WindowAncestor,
+    WindowGlobalScope,
+  },
+  window::WindowPattern,
+  ExecutionContext, Value, PLUGIN_COMMAND_PREFIX, PLUGIN_COMMAND_SEPARATOR,
+};
+use url::Url;
+
+use crate::{
+  command,
+  plugin::{Builder as PluginBuilder, TauriPlugin},
+  Manager, Runtime, Webview,
+};
+
+/// The name of the internal plugin serving the inspector.
+pub(crate) const ACL_INSPECTOR_PLUGIN_NAME: &str = "__TAURI_ACL_INSPECTOR__";
+/// The label of the inspector window.
+pub(crate) const ACL_INSPECTOR_WINDOW_LABEL: &str = "__tauri_acl_inspector";
+/// The command returning the resolved ACL, only allowed on the inspector window.
+const RESOLVED_COMMAND: &str = "plugin:__TAURI_ACL_INSPECTOR__|resolved";
+/// The command returning the authorization timings, only allowed on the inspector window.
+const TIMINGS_COMMAND: &str = "plugin:__TAURI_ACL_INSPECTOR__|timings";
+/// The URI scheme serving the inspector page.
+const ACL_INSPECTOR_SCHEME: &str = "tauri-acl-inspector";
+
+const INSPECTOR_HTML: &str = include_str!("./inspector.html");
+
+/// The environment variable logging the summary of the resolved ACL on startup when set to `1`.
+pub(crate) const ACL_PRINT_ENV_VAR: &str = "TAURI_ACL_PRINT";
+/// The maximum width of a column of the summary, longer cells are truncated.
+const SUMMARY_COLUMN_WIDTH: usize = 40;
+
+/// The URL of the inspector page.
+pub(crate) fn inspector_url() -> Url {
+  if cfg!(windows) || cfg!(target_os = "android") {
+    Url::parse(&format!("http://{ACL_INSPECTOR_SCHEME}.localhost/")).unwrap()
+  } else {
+    Url::parse(&format!("{ACL_INSPECTOR_SCHEME}://localhost/")).unwrap()
+  }
+}
+
+/// Whether the inspector command can be executed by the webview,
+/// bypassing the ACL since the internal plugin does not define permissions.
+///
+/// Only the commands serving the inspector page are allowed, on its own window.
+/// Returns `false` for any other command, including the `open` command invoked by the keyboard shortcut,
+/// which is authorized by the ACL like any plugin command.
+pub(crate) fn is_command_allowed(command: &str, webview_label: &str, current_url: &Url) -> bool {
+  match command {
+    RESOLVED_COMMAND | TIMINGS_COMMAND => {
+      let inspector_url = inspector_url();
+      webview_label == ACL_INSPECTOR_WINDOW_LABEL
+        && current_url.scheme() == inspector_url.scheme()
+        && current_url.host() == inspector_url.host()
+    }
+    _ => false,
+  }
+}
+
+/// The resolved ACL in the shape rendered by the inspector.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Inspection {
+  commands: Vec<InspectedCommand>,
+  global_scopes: BTreeMap<String, Vec<InspectedWindowScope>>,
+  /// The estimated heap size of the runtime authority, see [`command::RuntimeAuthority::memory_stats`].
+  memory_bytes: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InspectedCommand {
+  name: String,
+  context: String,
+  denied: bool,
+  windows: Vec<String>,
+  window_ancestors: Vec<String>,
+  webviews: Vec<String>,
+  pages: Vec<InspectedPageGrant>,
+  scopes: Vec<InspectedWindowScope>,
+  classes: Vec<String>,
+  via_default: bool,
+  referenced_by: Vec<InspectedReference>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InspectedPageGrant {
+  windows: Vec<String>,
+  window_ancestors: Vec<String>,
+  webviews: Vec<String>,
+  pages: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InspectedWindowScope {
+  windows: Vec<String>,
+  window_ancestors: Vec<String>,
+  webviews: Vec<String>,
+  pages: Vec<String>,
+  #[serde(flatten)]
+  scope: InspectedScope,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct InspectedScope {
+  allow: Vec<serde_json::Value>,
+  deny: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InspectedReference {
+  capability: String,
+  permission: String,
+  delegated_by: Option<String>,
+}
+
+impl Inspection {
+  pub(crate) fn new<'a>(
+    allowed_commands: &BTreeMap<CommandKey, ResolvedCommand>,
+    denied_commands: &BTreeMap<CommandKey, ResolvedCommand>,
+    command_scope: impl Fn(&ScopeKey) -> Option<&'a ResolvedScope>,
+    global_scope: &BTreeMap<String, Vec<WindowGlobalScope>>,
+    memory_bytes: usize,
+  ) -> Self {
+    let commands = allowed_commands
+      .iter()
+      .map(|(key, command)| (key, command, false))
+      .chain(
+        denied_commands
+          .iter()
+          .map(|(key, command)| (key, command, true)),
+      )
+      .map(|(key, command, denied)| InspectedCommand {
+        name: key.name.clone(),
+        context: match &key.context {
+          ExecutionContext::Local => "local".into(),
+          ExecutionContext::Remote { domain, .. } => format!("remote: {}", domain.as_str()),
+        },
+        denied,
+        windows: patterns(&command.windows),
+        window_ancestors: ancestors(&command.window_ancestors),
+        webviews: webviews(&command.webviews),
+        pages: command
+          .pages
+          .iter()
+          .map(|grant| InspectedPageGrant {
+            windows: patterns(&grant.windows),
+            window_ancestors: ancestors(&grant.window_ancestors),
+            webviews: webviews(&grant.webviews),
+            pages: pages(&grant.pages),
+          })
+          .collect(),
+        scopes: command
+          .scopes
+          .iter()
+          .map(|scope| InspectedWindowScope {
+            windows: patterns(&scope.windows),
+            window_ancestors: ancestors(&scope.window_ancestors),
+            webviews: webviews(&scope.webviews),
+            pages: pages(&scope.pages),
+            scope: command_scope(&scope.scope)
+              .map(InspectedScope::from)
+              .unwrap_or_default(),
+          })
+          .collect(),
+        classes: command.classes.clone(),
+        via_default: command.via_default,
+        referenced_by: command
+          .referenced_by
+          .iter()
+          .map(|reference| InspectedReference {
+            capability: reference.capability.clone(),
+            permission: reference.permission.clone(),
+            delegated_by: reference.delegated_by.clone(),
+          })
+          .collect(),
+      })
+      .collect();
+
+    Self {
+      commands,
+      global_scopes: global_scope
+        .iter()
+        .map(|(plugin, scopes)| {
+          let scopes = scopes
+            .iter()
+            .map(|scope| InspectedWindowScope {
+              windows: patterns(&scope.windows),
+              window_ancestors: ancestors(&scope.window_ancestors),
+              webviews: webviews(&scope.webviews),
+              pages: pages(&scope.pages),
+              scope: (&scope.scope).into(),
+            })
+            .collect();
+          (plugin.clone(), scopes)
+        })
+        .collect(),
+      memory_bytes,
+    }
+  }
+}
+
+impl Inspection {
+  /// Writes the allowed commands as a table grouped by plugin,
+  /// see [`RuntimeAuthority::print_summary`](crate::command::RuntimeAuthority::print_summary).
+  pub(crate) fn write_summary(&self, writer: &mut impl Write) -> io::Result<()> {
+    let mut rows = self
+      .commands
+      .iter()
+      .filter(|command| !command.denied)
+      .map(|command| {
+        let (plugin, name) = command
+          .name
+          .strip_prefix(PLUGIN_COMMAND_PREFIX)
+          .and_then(|name| name.split_once(PLUGIN_COMMAND_SEPARATOR))
+          .unwrap_or(("(app)", &command.name));
+        let windows = command
+          .windows
+          .iter()
+          .chain(&command.window_ancestors)
+          .chain(&command.webviews)
+          .map(String::as_str)
+          .collect::<Vec<_>>()
+          .join(", ");
+        let command_scope = command
+          .scopes
+          .iter()
+          .any(|scope| !(scope.scope.allow.is_empty() && scope.scope.deny.is_empty()));
+        let scope = match (command_scope, self.global_scopes.contains_key(plugin)) {
+          (true, true) => "command, global",
+          (true, false) => "command",
+          (false, true) => "global",
+          (false, false) => "-",
+        };
+        [
+          plugin.to_string(),
+          name.to_string(),
+          command.context.clone(),
+          windows,
+          scope.to_string(),
+        ]
+      })
+      .collect::<Vec<_>>();
+    rows.sort();
+
+    if rows.is_empty() {
+      return writeln!(writer, "no command is allowed by the ACL");
+    }
+
+    // the plugin is only written on the first row of its commands
+    for i in (1..rows.len()).rev() {
+      if rows[i][0] == rows[i - 1][0] {
+        rows[i][0].clear();
+      }
+    }
+
+    let header = ["PLUGIN", "COMMAND", "CONTEXT", "WINDOWS", "SCOPE"].map(String::from);
+    let mut widths = [0; 5];
+    for row in std::iter::once(&header).chain(&rows) {
+      for (width, cell) in widths.iter_mut().zip(row) {
+        *width = (*width).max(cell.chars().count()).min(SUMMARY_COLUMN_WIDTH);
+      }
+    }
+
+    for row in std::iter::once(&header).chain(&rows) {
+      let line = row
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:width$}", truncate(cell, width)))
+        .collect::<Vec<_>>()
+        .join("  ");
+      writeln!(writer, "{}", line.trim_end())?;
+    }
+    Ok(())
+  }
+}
+
+/// Truncates the cell to the given number of characters, ending it with an ellipsis.
+fn truncate(cell: &str, width: usize) -> String {
+  if cell.chars().count() <= width {
+    cell.to_string()
+  } else {
+    let mut truncated = cell.chars().take(width - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+  }
+}
+
+impl From<&ResolvedScope> for InspectedScope {
+  fn from(scope: &ResolvedScope) -> Self {
+    let values = |values: &[Value]| values.iter().cloned().map(Into::into).collect();
+    Self {
+      allow: values(&scope.allow),
+      deny: values(&scope.deny),
+    }
+  }
+}
+
+fn patterns(patterns: &[WindowPattern]) -> Vec<String> {
+  patterns.iter().map(|p| p.as_str().to_string()).collect()
+}
+
+fn pages(pages: &[glob::Pattern]) -> Vec<String> {
+  pages.iter().map(|p| p.as_str().to_string()).collect()
+}
+
+fn ancestors(ancestors: &[WindowAncestor]) -> Vec<String> {
+  ancestors
+    .iter()
+    .map(|a| {
+      if a.parent_only {
+        format!("parent-of {}", a.pattern.as_str())
+      } else {
+        format!("descendant-of {}", a.pattern.as_str())
+      }
+    })
+    .collect()
+}
+
+fn webviews(webviews: &[WebviewPattern]) -> Vec<String> {
+  webviews
+    .iter()
+    .map(|w| format!("{} in {}", w.webview.as_str(), w.window.as_str()))
+    .collect()
+}
+
+#[command(root = "crate")]
+fn resolved<R: Runtime>(webview: Webview<R>) -> Inspection {
+  webview.acl().inspect()
+}
+
+#[command(root = "crate")]
+fn timings<R: Runtime>(webview: Webview<R>) -> crate::command::AclTimings {
+  webview.app_handle().acl_timings()
+}
+
+#[cfg(desktop)]
+#[command(root = "crate")]
+async fn open<R: Runtime>(webview: Webview<R>) -> crate::Result<()> {
+  webview.app_handle().open_acl_inspector().map(|_| ())
+}
+
+pub(crate) fn plugin<R: Runtime>() -> TauriPlugin<R> {
+  #[allow(unused_mut)]
+  let mut builder = PluginBuilder::new(ACL_INSPECTOR_PLUGIN_NAME).register_uri_scheme_protocol(
+    ACL_INSPECTOR_SCHEME,
+    |_app, _request| {
+      http::Response::builder()
+        .header(http::header::CONTENT_TYPE, "text/html")
+        .body(INSPECTOR_HTML.as_bytes().to_vec())
+        .unwrap()
+    },
+  );
+
+  #[cfg(desktop)]
+  {
+    builder = builder
+      .js_init_script(include_str!("./shortcut.js").to_string())
+      .invoke_handler(crate::generate_handler![resolved, timings, open]);
+  }
+  #[cfg(mobile)]
+  {
+    builder = builder.invoke_handler(crate::generate_handler![resolved, timings]);
+  }
+
+  builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::command::RuntimeAuthority;
+  use tauri_utils::acl::resolved::Resolved;
+
+  #[test]
+  fn summary_table() {
+    let resolved = Resolved::builder()
+      .allow_command(
+        "plugin:fs|read_file",
+        ExecutionContext::Local,
+        ["main", "editor-with-a-very-long-window-label-*"],
+      )
+      .unwrap()
+      .allow_command_scoped(
+        "plugin:fs|write_file",
+        ExecutionContext::Local,
+        ["main"],
+        ResolvedScope {
+          allow: vec![serde_json::json!({ "path": "$APPDATA/*" }).into()],
+          deny: Vec::new(),
+          external: None,
+        },
+      )
+      .unwrap()
+      .deny_command("plugin:fs|remove", ExecutionContext::Local, ["main"])
+      .unwrap()
+      .allow_command("plugin:http|fetch", ExecutionContext::Local, ["*"])
+      .unwrap()
+      .global_scope("http", ResolvedScope::default())
+      .allow_command("greet", ExecutionContext::Local, ["main"])
+      .unwrap()
+      .build();
+
+    let mut summary = Vec::new();
+    RuntimeAuthority::new(resolved)
+      .print_summary(&mut summary)
+      .unwrap();
+    assert_eq!(
+      String::from_utf8(summary).unwrap(),
+      "\
+PLUGIN  COMMAND     CONTEXT  WINDOWS                                   SCOPE
+(app)   greet       local    main                                      -
+fs      read_file   local    main, editor-with-a-very-long-window-la…  -
+        write_file  local    main                                      command
+http    fetch       local    *                                         global
+"
+    );
+  }
+
+  #[test]
+  fn inspector_commands_are_gated() {
+    let inspector = inspector_url();
+    let app = Url::parse("tauri://localhost/").unwrap();
+    let remote = Url::parse("https://tauri.app/").unwrap();
+
+    assert!(is_command_allowed(
+      RESOLVED_COMMAND,
+      ACL_INSPECTOR_WINDOW_LABEL,
+      &inspector
+    ));
+    assert!(!is_command_allowed(RESOLVED_COMMAND, "main", &inspector));
+    assert!(!is_command_allowed(
+      RESOLVED_COMMAND,
+      ACL_INSPECTOR_WINDOW_LABEL,
+      &remote
+    ));
+    assert!(is_command_allowed(
+      TIMINGS_COMMAND,
+      ACL_INSPECTOR_WINDOW_LABEL,
+      &inspector
+    ));
+    assert!(!is_command_allowed(TIMINGS_COMMAND, "main", &app));
+
+    // opening the inspector is not granted to every local page
+    let open = "plugin:__TAURI_ACL_INSPECTOR__|open";
+    assert!(!is_command_allowed(open, "main", &app));
+    assert!(!is_command_allowed(
+      open,
+      ACL_INSPECTOR_WINDOW_LABEL,
+      &inspector
+    ));
+    assert!(!is_command_allowed(
+      "plugin:fs|read",
+      ACL_INSPECTOR_WINDOW_LABEL,
+      &inspector
+    ));
+  }
+}
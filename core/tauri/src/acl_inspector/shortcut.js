@@ -0,0 +1,17 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+window.addEventListener('keydown', function (event) {
+  if (
+    (event.ctrlKey || event.metaKey) &&
+    event.shiftKey &&
+    event.altKey &&
+    event.code === 'KeyA'
+  ) {
+    event.preventDefault()
+    window.__TAURI_INTERNALS__
+      .invoke('plugin:__TAURI_ACL_INSPECTOR__|open')
+      .catch(console.error)
+  }
+})
@@ -3,10 +3,10 @@
 // SPDX-License-Identifier: MIT
 
 use crate::{
-  command::{CommandArg, CommandItem},
+  command::{CommandArg, CommandItem, FirstUseDecision, ScopeProvider},
   ipc::{
-    channel::ChannelDataIpcQueue, CallbackFn, Invoke, InvokeError, InvokeHandler, InvokeResponder,
-    InvokeResponse,
+    channel::ChannelDataIpcQueue, CallbackFn, ContextPolicy, Invoke, InvokeError, InvokeHandler,
+    InvokeResponder, InvokeResponse,
   },
   manager::{
     webview::{UriSchemeProtocol, WebviewLabelDef},
@@ -20,7 +20,7 @@ use crate::{
   sealed::{ManagerBase, RuntimeOrDispatch},
   utils::config::Config,
   utils::{assets::Assets, Env},
-  webview::PageLoadPayload,
+  webview::{InvokeRequest, PageLoadPayload},
   Context, DeviceEventFilter, EventLoopMessage, Icon, Manager, Monitor, Runtime, Scopes,
   StateManager, Theme, Webview, WebviewWindowBuilder, Window,
 };
@@ -41,11 +41,11 @@ use tauri_runtime::{
   },
   RuntimeInitArgs,
 };
-use tauri_utils::{debug_eprintln, PackageInfo};
+use tauri_utils::{acl::command::CommandId, debug_eprintln, PackageInfo};
 
 use std::{
   borrow::Cow,
-  collections::HashMap,
+  collections::{BTreeSet, HashMap},
   fmt,
   sync::{mpsc::Sender, Arc},
 };
@@ -69,6 +69,13 @@ pub type SetupHook<R> =
 /// A closure that is run every time a page starts or finishes loading.
 pub type OnPageLoad<R> = dyn Fn(&Webview<R>, &PageLoadPayload<'_>) + Send + Sync + 'static;
 
+/// A closure that is run the first time a webview uses a class of commands.
+pub type OnFirstUse<R> = dyn Fn(&Webview<R>, &str) -> FirstUseDecision + Send + Sync + 'static;
+
+/// A closure validating the IPC requests of the commands whose permissions reference it,
+/// see [`Builder::isolation_validator`].
+pub type IsolationValidator = dyn Fn(&InvokeRequest) -> Result<(), String> + Send + Sync + 'static;
+
 /// The exit code on [`RunEvent::ExitRequested`] when [`AppHandle#method.restart`] is called.
 pub const RESTART_EXIT_CODE: i32 = i32::MAX;
 
@@ -390,6 +397,87 @@ impl<R: Runtime> AppHandle<R> {
     }
     crate::process::restart(&self.env());
   }
+
+  /// A snapshot of the ACL decisions of the plugin commands invoked since startup.
+  ///
+  /// The counters are always on and cover every command of the resolved ACL,
+  /// the commands it does not reference being counted together.
+  pub fn acl_metrics(&self) -> crate::command::AclMetrics {
+    self.manager().runtime_authority.metrics()
+  }
+
+  /// The percentiles of the time spent authorizing each command invoked since startup,
+  /// split between the runtime authority decision and the retrieval of the command scopes.
+  ///
+  /// Only available in debug builds or with the `acl-timing` feature, the measurements are compiled out otherwise.
+  #[cfg(any(debug_assertions, feature = "acl-timing"))]
+  pub fn acl_timings(&self) -> crate::command::AclTimings {
+    self.manager().runtime_authority.timings()
+  }
+
+  /// Calls the given callback with a snapshot of the ACL decisions every `interval`,
+  /// e.g. to export them to the app telemetry. See [`Self::acl_metrics`].
+  ///
+  /// The callback runs on a dedicated thread for the rest of the app lifetime.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use std::time::Duration;
+  ///
+  /// tauri::Builder::default()
+  ///   .setup(|app| {
+  ///     app.handle().on_acl_metrics(Duration::from_secs(60), |_app, metrics| {
+  ///       for (command, counts) in metrics.commands {
+  ///         println!("{command}: {} denied", counts.denied + counts.not_allowed);
+  ///       }
+  ///     });
+  ///     Ok(())
+  ///   });
+  /// ```
+  pub fn on_acl_metrics<F>(&self, interval: std::time::Duration, callback: F)
+  where
+    F: Fn(&AppHandle<R>, crate::command::AclMetrics) + Send + 'static,
+  {
+    let app = self.clone();
+    std::thread::spawn(move || loop {
+      std::thread::sleep(interval);
+      callback(&app, app.acl_metrics());
+    });
+  }
+
+  /// The capability window patterns that matched no window or webview created since startup,
+  /// usually a typo like `"mian"`. Only available in debug builds.
+  ///
+  /// Windows can be created at any time, so this is only conclusive once the app has created all its windows.
+  /// The unmatched patterns are also logged as warnings when the app exits.
+  #[cfg(debug_assertions)]
+  pub fn unmatched_window_patterns(&self) -> Vec<crate::command::UnmatchedWindowPattern> {
+    self.manager().runtime_authority.unmatched_window_patterns()
+  }
+
+  /// Opens the ACL inspector window, rendering the resolved Access Control List.
+  ///
+  /// Focuses the window if it is already open. Only available in debug builds,
+  /// where it can also be opened with `Ctrl+Shift+Alt+A` (`Cmd+Shift+Alt+A` on macOS)
+  /// on the webviews the ACL allows to invoke `plugin:__TAURI_ACL_INSPECTOR__|open`.
+  #[cfg(all(desktop, debug_assertions))]
+  pub fn open_acl_inspector(&self) -> crate::Result<crate::WebviewWindow<R>> {
+    if let Some(window) = self.get_webview_window(crate::acl_inspector::ACL_INSPECTOR_WINDOW_LABEL)
+    {
+      window.set_focus()?;
+      return Ok(window);
+    }
+
+    crate::WebviewWindowBuilder::new(
+      self,
+      crate::acl_inspector::ACL_INSPECTOR_WINDOW_LABEL,
+      crate::WebviewUrl::External(crate::acl_inspector::inspector_url()),
+    )
+    .title("ACL Inspector")
+    .inner_size(900., 700.)
+    .build()
+  }
 }
 
 impl<R: Runtime> Manager<R> for AppHandle<R> {}
@@ -962,12 +1050,30 @@ pub struct Builder<R: Runtime> {
   /// Page load hook.
   on_page_load: Option<Arc<OnPageLoad<R>>>,
 
+  /// Command class first use hook.
+  on_first_use: Option<Arc<OnFirstUse<R>>>,
+
+  /// The validators of the IPC requests referenced by the permissions.
+  isolation_validators: HashMap<String, Arc<IsolationValidator>>,
+
+  /// The provider of the scopes stored out of the resolved ACL.
+  scope_provider: Option<Arc<dyn ScopeProvider>>,
+
+  /// The policy classifying the pages sending IPC requests as local or remote.
+  invoke_context_policy: ContextPolicy,
+
   /// All passed plugins
   plugins: PluginStore<R>,
 
+  /// Whether the app fails to build when the ACL grants commands of plugins that are not registered.
+  deny_unregistered_plugins: bool,
+
   /// The webview protocols available to all windows.
   uri_scheme_protocols: HashMap<String, Arc<UriSchemeProtocol<R>>>,
 
+  /// The commands a webview must be allowed to load the URI scheme protocols, keyed by URI scheme.
+  uri_scheme_permissions: HashMap<String, String>,
+
   /// App state.
   state: StateManager,
 
@@ -995,6 +1101,8 @@ struct InvokeInitializationScript<'a> {
   os_name: &'a str,
   fetch_channel_data_command: &'a str,
   use_custom_protocol: bool,
+  /// Whether the requests carry the webview label to flag spoofing attempts, only in debug builds.
+  send_webview_label: bool,
 }
 
 /// Make `Wry` the default `Runtime` for `Builder`
@@ -1028,13 +1136,20 @@ impl<R: Runtime> Builder<R> {
         os_name: std::env::consts::OS,
         fetch_channel_data_command: crate::ipc::channel::FETCH_CHANNEL_DATA_COMMAND,
         use_custom_protocol: cfg!(ipc_custom_protocol),
+        send_webview_label: cfg!(debug_assertions),
       }
       .render_default(&Default::default())
       .unwrap()
       .into_string(),
       on_page_load: None,
+      on_first_use: None,
+      isolation_validators: Default::default(),
+      scope_provider: None,
+      invoke_context_policy: ContextPolicy::default(),
       plugins: PluginStore::default(),
+      deny_unregistered_plugins: false,
       uri_scheme_protocols: Default::default(),
+      uri_scheme_permissions: Default::default(),
       state: StateManager::new(),
       #[cfg(desktop)]
       menu: None,
@@ -1134,6 +1249,104 @@ tauri::Builder::default()
     self
   }
 
+  /// Defines the hook consulted the first time a webview uses a class of commands.
+  ///
+  /// Permissions can declare the side-effect class of the commands they allow, such as `fs-read` or `network`.
+  /// Before running a command, the hook is called once for each class of the command the webview has not used yet,
+  /// and its [`FirstUseDecision`] is cached for the rest of the session.
+  ///
+  /// Apps that do not define this hook allow every class.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use tauri::command::FirstUseDecision;
+  ///
+  /// tauri::Builder::default().on_first_use(|webview, class| {
+  ///   if webview.label() == "main" || class != "shell" {
+  ///     FirstUseDecision::Allow
+  ///   } else {
+  ///     FirstUseDecision::Deny
+  ///   }
+  /// });
+  /// ```
+  #[must_use]
+  pub fn on_first_use<F>(mut self, on_first_use: F) -> Self
+  where
+    F: Fn(&Webview<R>, &str) -> FirstUseDecision + Send + Sync + 'static,
+  {
+    self.on_first_use.replace(Arc::new(on_first_use));
+    self
+  }
+
+  /// Registers a validator of the IPC requests, referenced by the `validator` field of the permissions.
+  ///
+  /// Once the ACL allows a request, the validators of the permissions allowing the command run before it executes,
+  /// and the request is rejected with the error of the first validator failing.
+  /// Apps using the isolation pattern can use them to check the payloads per command
+  /// instead of in a single hook of the isolation application.
+  ///
+  /// Building the app fails if a permission granted by the capabilities references a validator that is not registered.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use tauri::ipc::InvokeBody;
+  ///
+  /// tauri::Builder::default().isolation_validator("strip-pii", |request| match &request.body {
+  ///   InvokeBody::Json(body) if body.to_string().contains('@') => {
+  ///     Err("email addresses are not allowed".into())
+  ///   }
+  ///   _ => Ok(()),
+  /// });
+  /// ```
+  #[must_use]
+  pub fn isolation_validator<F>(mut self, id: impl Into<String>, validator: F) -> Self
+  where
+    F: Fn(&InvokeRequest) -> Result<(), String> + Send + Sync + 'static,
+  {
+    self
+      .isolation_validators
+      .insert(id.into(), Arc::new(validator));
+    self
+  }
+
+  /// Registers the provider loading the values of the scopes stored out of the resolved ACL,
+  /// declared by the plugins with `external-scope = true` in their permission files.
+  ///
+  /// Building the app fails if the resolved ACL references such a scope and no provider is registered.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,ignore
+  /// tauri::Builder::default().scope_provider(tauri::command::EmbeddedScopeProvider::new(
+  ///   include_bytes!(concat!(env!("OUT_DIR"), "/acl-external-scopes.br")),
+  /// ));
+  /// ```
+  #[must_use]
+  pub fn scope_provider<P: ScopeProvider>(mut self, provider: P) -> Self {
+    self.scope_provider.replace(Arc::new(provider));
+    self
+  }
+
+  /// Sets the policy classifying the pages sending IPC requests as local or remote,
+  /// which decides whether the local or the remote capabilities apply to them.
+  ///
+  /// The origins served by the app are always local, the policy lists the other local origins.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// // the frontend is served by an embedded web server
+  /// tauri::Builder::default()
+  ///   .invoke_context_policy(tauri::ipc::ContextPolicy::new().local_origin("http://localhost:8080"));
+  /// ```
+  #[must_use]
+  pub fn invoke_context_policy(mut self, policy: ContextPolicy) -> Self {
+    self.invoke_context_policy = policy;
+    self
+  }
+
   /// Adds a Tauri application plugin.
   ///
   /// A plugin is created using the [`crate::plugin::Builder`] struct.Check its documentation for more information.
@@ -1439,6 +1652,53 @@ tauri::Builder::default()
     self
   }
 
+  /// Fails to build the app when the resolved ACL grants commands of plugins that are not registered.
+  ///
+  /// A capability granting the commands of a plugin the app does not register is usually a missing
+  /// [`Self::plugin`] call or a plugin removed without updating the capabilities, and every invoke of its
+  /// commands fails with an unknown command error. By default the app only logs these plugins in debug builds.
+  /// The plugins must be registered on this builder, the plugins registered in the [`Self::setup`] hook
+  /// with [`AppHandle::plugin`] are not registered yet when the app is built.
+  ///
+  /// # Examples
+  /// ```
+  /// tauri::Builder::default()
+  ///   .deny_unregistered_plugins(!cfg!(debug_assertions));
+  /// ```
+  #[must_use]
+  pub fn deny_unregistered_plugins(mut self, deny: bool) -> Self {
+    self.deny_unregistered_plugins = deny;
+    self
+  }
+
+  /// Requires the webviews to be allowed the given plugin command to load the URI scheme protocol.
+  ///
+  /// The command is never invoked, it only names the permission a capability must grant,
+  /// e.g. a command of a plugin registering the protocol with [`crate::plugin::Builder::require_uri_scheme_permission`].
+  /// The protocol requests of a webview not allowed the command for the page it has loaded
+  /// are answered with `403 Forbidden` without calling the protocol handler.
+  /// The protocols without a required permission are available to all webviews.
+  ///
+  /// # Examples
+  /// ```
+  /// tauri::Builder::default()
+  ///   .register_uri_scheme_protocol("app-files", |_app, _request| {
+  ///     http::Response::builder().body(Vec::new()).unwrap()
+  ///   })
+  ///   .require_uri_scheme_permission("app-files", "plugin:files|protocol");
+  /// ```
+  #[must_use]
+  pub fn require_uri_scheme_permission<N: Into<String>, C: Into<String>>(
+    mut self,
+    uri_scheme: N,
+    command: C,
+  ) -> Self {
+    self
+      .uri_scheme_permissions
+      .insert(uri_scheme.into(), command.into());
+    self
+  }
+
   /// Change the device event filter mode.
   ///
   /// Since the DeviceEvent capture can lead to high CPU usage for unfocused windows, [`tao`]
@@ -1475,11 +1735,50 @@ tauri::Builder::default()
       }));
     }
 
+    if let Some((key, validator)) = context
+      .resolved_acl
+      .allowed_commands
+      .iter()
+      .flat_map(|(key, command)| command.validators.iter().map(move |v| (key, v)))
+      .find(|(_key, validator)| !self.isolation_validators.contains_key(*validator))
+    {
+      return Err(crate::Error::UnknownIsolationValidator {
+        validator: validator.clone(),
+        command: key.name.clone(),
+      });
+    }
+
+    if self.scope_provider.is_none() {
+      if let Some(external) = context
+        .resolved_acl
+        .command_scope
+        .values()
+        .chain(
+          context
+            .resolved_acl
+            .global_scope
+            .values()
+            .flatten()
+            .map(|window_scope| &window_scope.scope),
+        )
+        .find_map(|scope| scope.external.as_ref())
+      {
+        return Err(crate::Error::MissingScopeProvider(external.key.clone()));
+      }
+    }
+
+    let builder_plugins = self.plugins.names();
+    let granted_plugins = granted_plugins(&context.resolved_acl);
+
     let manager = Arc::new(AppManager::with_handlers(
       context,
       self.plugins,
       self.invoke_handler,
       self.on_page_load,
+      self.on_first_use,
+      self.isolation_validators,
+      self.scope_provider,
+      self.invoke_context_policy,
       self.uri_scheme_protocols,
       self.state,
       self.window_event_listeners,
@@ -1488,6 +1787,12 @@ tauri::Builder::default()
       (self.invoke_responder, self.invoke_initialization_script),
     ));
 
+    for (uri_scheme, command) in self.uri_scheme_permissions {
+      manager
+        .webview
+        .require_uri_scheme_permission(uri_scheme, command);
+    }
+
     let runtime_args = RuntimeInitArgs {
       #[cfg(windows)]
       msg_hook: {
@@ -1583,6 +1888,8 @@ tauri::Builder::default()
 
     app.manage(ChannelDataIpcQueue::default());
     app.handle.plugin(crate::ipc::channel::plugin())?;
+    #[cfg(debug_assertions)]
+    app.handle.plugin(crate::acl_inspector::plugin())?;
 
     #[cfg(windows)]
     {
@@ -1633,6 +1940,29 @@ tauri::Builder::default()
       }
     }
 
+    let registered_plugins = app.manager.plugins.lock().unwrap().names();
+    let (unregistered, ungranted) =
+      plugin_grant_mismatches(&granted_plugins, &registered_plugins, &builder_plugins);
+    if !unregistered.is_empty() {
+      if self.deny_unregistered_plugins {
+        return Err(crate::Error::UnregisteredPlugins(unregistered));
+      }
+      #[cfg(debug_assertions)]
+      log::warn!(
+        "the capabilities grant commands of the plugins {}, which are not registered",
+        unregistered.join(", ")
+      );
+    }
+    #[cfg(debug_assertions)]
+    if !ungranted.is_empty() {
+      log::warn!(
+        "no capability grants a command of the registered plugins {}",
+        ungranted.join(", ")
+      );
+    }
+    #[cfg(not(debug_assertions))]
+    let _ = ungranted;
+
     app.manager.initialize_plugins(handle)?;
 
     Ok(app)
@@ -1690,6 +2020,19 @@ unsafe impl<R: Runtime> HasRawDisplayHandle for App<R> {
 fn setup<R: Runtime>(app: &mut App<R>) -> crate::Result<()> {
   app.ran_setup = true;
 
+  #[cfg(debug_assertions)]
+  if std::env::var(crate::acl_inspector::ACL_PRINT_ENV_VAR).as_deref() == Ok("1") {
+    let mut summary = Vec::new();
+    if app
+      .manager
+      .runtime_authority
+      .print_summary(&mut summary)
+      .is_ok()
+    {
+      log::info!("resolved ACL:\n{}", String::from_utf8_lossy(&summary));
+    }
+  }
+
   let window_labels = app
     .config()
     .tauri
@@ -1710,6 +2053,14 @@ fn setup<R: Runtime>(app: &mut App<R>) -> crate::Result<()> {
       .build_internal(&window_labels, &webview_labels)?;
   }
 
+  // decode the lazily loaded scopes of the main window commands before their first invoke
+  if let Some(label) = window_labels.first().cloned() {
+    let manager = app.manager_owned();
+    if manager.runtime_authority.has_lazy_scopes() {
+      std::thread::spawn(move || manager.runtime_authority.prewarm_scopes(&label));
+    }
+  }
+
   if let Some(setup) = app.setup.take() {
     (setup)(app).map_err(|e| crate::Error::Setup(e.into()))?;
   }
@@ -1717,6 +2068,50 @@ fn setup<R: Runtime>(app: &mut App<R>) -> crate::Result<()> {
   Ok(())
 }
 
+/// The plugins of the commands allowed by the ACL.
+fn granted_plugins(acl: &tauri_utils::acl::resolved::Resolved) -> BTreeSet<String> {
+  acl
+    .allowed_commands
+    .keys()
+    .filter_map(|key| CommandId::parse(&key.name).ok())
+    .map(|id| id.plugin().to_string())
+    .collect()
+}
+
+/// Cross-references the plugins granted commands by the ACL with the registered plugins.
+///
+/// Returns the granted plugins that are not registered,
+/// and the plugins registered with the builder that no capability grants a command.
+fn plugin_grant_mismatches<'a>(
+  granted: &BTreeSet<String>,
+  registered: &[&str],
+  builder_plugins: &[&'a str],
+) -> (Vec<String>, Vec<&'a str>) {
+  let unregistered = granted
+    .iter()
+    .filter(|plugin| !registered.contains(&plugin.as_str()))
+    .cloned()
+    .collect();
+  let ungranted = builder_plugins
+    .iter()
+    .filter(|plugin| !granted.contains(**plugin))
+    .copied()
+    .collect();
+  (unregistered, ungranted)
+}
+
+/// Warns about the capability window patterns that matched no window during the app lifetime.
+#[cfg(debug_assertions)]
+fn warn_unmatched_window_patterns<R: Runtime>(manager: &AppManager<R>) {
+  for unmatched in manager.runtime_authority.unmatched_window_patterns() {
+    log::warn!(
+      "window pattern {:?} of capabilities {} matched no window or webview created by the app, is it misspelled?",
+      unmatched.pattern,
+      unmatched.capabilities.join(", ")
+    );
+  }
+}
+
 fn on_event_loop_event<R: Runtime>(
   app_handle: &AppHandle<R>,
   event: RuntimeRunEvent<EventLoopMessage>,
@@ -1731,7 +2126,11 @@ fn on_event_loop_event<R: Runtime>(
   }
 
   let event = match event {
-    RuntimeRunEvent::Exit => RunEvent::Exit,
+    RuntimeRunEvent::Exit => {
+      #[cfg(debug_assertions)]
+      warn_unmatched_window_patterns(manager);
+      RunEvent::Exit
+    }
     RuntimeRunEvent::ExitRequested { code, tx } => RunEvent::ExitRequested {
       code,
       api: ExitRequestApi(tx),
@@ -1825,6 +2224,71 @@ fn on_event_loop_event<R: Runtime>(
 
 #[cfg(test)]
 mod tests {
+  use tauri_utils::acl::{
+    resolved::{CommandKey, ResolvedCommand},
+    ExecutionContext,
+  };
+
+  use super::{granted_plugins, plugin_grant_mismatches};
+  use crate::{
+    plugin::Builder as PluginBuilder,
+    test::{mock_builder, mock_context, noop_assets, MockRuntime},
+  };
+
+  fn grant(context: &mut crate::Context<crate::test::NoopAsset>, command: &str) {
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: command.into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["main".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+  }
+
+  #[test]
+  fn unregistered_plugins_fail_the_build_when_denied() {
+    let mut context = mock_context(noop_assets());
+    grant(&mut context, "plugin:fs|read");
+    grant(&mut context, "plugin:event|listen");
+
+    assert!(matches!(
+      mock_builder().deny_unregistered_plugins(true).build(context),
+      Err(crate::Error::UnregisteredPlugins(plugins)) if plugins == ["fs"]
+    ));
+
+    // the app only logs the unregistered plugins by default
+    let mut context = mock_context(noop_assets());
+    grant(&mut context, "plugin:fs|read");
+    assert!(mock_builder().build(context).is_ok());
+
+    let mut context = mock_context(noop_assets());
+    grant(&mut context, "plugin:fs|read");
+    assert!(mock_builder()
+      .plugin(PluginBuilder::<MockRuntime>::new("fs").build())
+      .deny_unregistered_plugins(true)
+      .build(context)
+      .is_ok());
+  }
+
+  #[test]
+  fn registered_plugins_without_grants() {
+    let mut context = mock_context(noop_assets());
+    grant(&mut context, "plugin:fs|read");
+    grant(&mut context, "app_command");
+
+    let builder = mock_builder()
+      .plugin(PluginBuilder::<MockRuntime>::new("fs").build())
+      .plugin(PluginBuilder::<MockRuntime>::new("shell").build());
+    let names = builder.plugins.names();
+    let (unregistered, ungranted) =
+      plugin_grant_mismatches(&granted_plugins(&context.resolved_acl), &names, &names);
+    assert!(unregistered.is_empty());
+    assert_eq!(ungranted, ["shell"]);
+  }
+
   #[test]
   fn is_send_sync() {
     crate::test_utils::assert_send::<super::AppHandle>();
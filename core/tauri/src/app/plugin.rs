@@ -39,6 +39,11 @@ pub fn app_hide<R: Runtime>(app: AppHandle<R>) -> crate::Result<()> {
   Ok(())
 }
 
+#[command(root = "crate")]
+pub fn relaunch<R: Runtime>(app: AppHandle<R>) {
+  app.restart();
+}
+
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
   Builder::new("app")
     .invoke_handler(crate::generate_handler![
@@ -46,7 +51,43 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
       name,
       tauri_version,
       app_show,
-      app_hide
+      app_hide,
+      relaunch
     ])
     .build()
 }
+
+#[cfg(test)]
+mod tests {
+  use tauri_utils::acl::{resolved::Resolved, ExecutionContext};
+
+  use crate::{
+    test::{get_ipc_response, invoke_request, mock_builder, mock_context, noop_assets},
+    WebviewWindowBuilder,
+  };
+
+  #[test]
+  fn relaunch_is_allowed_per_window() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl = Resolved::builder()
+      .allow_command("plugin:app|version", ExecutionContext::Local, ["*"])
+      .unwrap()
+      // what `app:allow-relaunch` resolves to in a capability only selecting the updater window
+      .allow_command("plugin:app|relaunch", ExecutionContext::Local, ["updater"])
+      .unwrap()
+      .build();
+    let app = mock_builder().build(context).unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let updater = WebviewWindowBuilder::new(&app, "updater", Default::default())
+      .build()
+      .unwrap();
+
+    assert!(get_ipc_response(&main, invoke_request("plugin:app|version")).is_ok());
+    assert!(get_ipc_response(&main, invoke_request("plugin:app|relaunch")).is_err());
+    // relaunching would restart the test process, only check the authorization
+    assert!(updater.as_ref().is_command_allowed("plugin:app|relaunch"));
+    assert!(!main.as_ref().is_command_allowed("plugin:app|relaunch"));
+  }
+}
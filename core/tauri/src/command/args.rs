@@ -0,0 +1,143 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::path::PathBuf;
+
+use glob::Pattern;
+use serde_json::Value as JsonValue;
+use tauri_utils::acl::args::{self, ArgumentError, ArgumentMatcher};
+use url::Url;
+
+use super::{CommandArg, CommandItem, CommandScope};
+use crate::{
+  ipc::{Invoke, InvokeBody, InvokeError, InvokeMessage},
+  scope::fs,
+  sealed::ManagerBase,
+  Manager, Runtime,
+};
+
+/// Checks the arguments of an allowed request against the argument rules of its command,
+/// see [`Permission#structfield.args`](crate::utils::acl::Permission#structfield.args).
+///
+/// The scope rules are checked against the command scope and the global scope of `plugin`
+/// granted to the calling webview. The entries granted at runtime are not consumed.
+///
+/// The rejection holds the error message and the [`ArgumentError`] under the `error` field.
+pub(crate) fn check_arguments<R: Runtime>(
+  invoke: &Invoke<R>,
+  command: &str,
+  plugin: Option<&str>,
+) -> Result<(), InvokeError> {
+  let Some(acl) = invoke.acl.as_ref().filter(|acl| !acl.args.is_empty()) else {
+    return Ok(());
+  };
+  let message = &invoke.message;
+  let payload = match &message.payload {
+    InvokeBody::Json(payload) => Some(payload),
+    InvokeBody::Raw(_) => None,
+  };
+
+  // the scopes are only loaded for the commands with scope rules
+  let mut scopes = None;
+  args::check_arguments(&acl.args, payload, |matcher, value| {
+    scopes
+      .get_or_insert_with(|| ArgumentScopes::load(message, &invoke.acl, plugin))
+      .allows(message, matcher, value)
+  })
+  .map_err(|error: ArgumentError| {
+    InvokeError(serde_json::json!({
+      "message": format!("Command {command} rejected: {error}"),
+      "error": error,
+    }))
+  })
+}
+
+/// The allowed and denied entries of the scopes of a command.
+#[derive(Default)]
+struct ArgumentScopes {
+  allow: Vec<JsonValue>,
+  deny: Vec<JsonValue>,
+}
+
+impl ArgumentScopes {
+  /// Loads the scopes, failing closed with empty scopes when they cannot be read.
+  fn load<R: Runtime>(
+    message: &InvokeMessage<R>,
+    acl: &Option<crate::utils::acl::resolved::ResolvedCommand>,
+    plugin: Option<&str>,
+  ) -> Self {
+    let mut scopes = Self::default();
+
+    let item = CommandItem {
+      plugin: None,
+      name: "",
+      key: "",
+      message,
+      acl,
+    };
+    let Ok(command_scope) = CommandScope::<JsonValue>::from_command(item) else {
+      return scopes;
+    };
+    scopes.allow.extend(command_scope.allows().iter().cloned());
+    scopes.deny.extend(command_scope.denies().iter().cloned());
+
+    if let Some(plugin) = plugin {
+      let webview = &message.webview;
+      let Ok(global_scope) = webview
+        .manager()
+        .runtime_authority
+        .webview_global_scope::<_, JsonValue>(webview, plugin, message.page.as_deref())
+      else {
+        return Self::default();
+      };
+      scopes.allow.extend(global_scope.allows().iter().cloned());
+      scopes.allow.extend(global_scope.ephemeral().cloned());
+      scopes.deny.extend(global_scope.denies().iter().cloned());
+    }
+
+    scopes
+  }
+
+  fn allows<R: Runtime>(
+    &self,
+    message: &InvokeMessage<R>,
+    matcher: &ArgumentMatcher,
+    value: &str,
+  ) -> bool {
+    match matcher {
+      ArgumentMatcher::PathScope => {
+        let resolve = |entries: &[JsonValue]| {
+          entries
+            .iter()
+            .filter_map(|entry| entry_str(entry, "path"))
+            .filter_map(|path| message.webview.path().parse(path).ok())
+            .collect::<Vec<PathBuf>>()
+        };
+        fs::Scope::from_patterns(resolve(&self.allow), resolve(&self.deny))
+          .is_ok_and(|scope| scope.is_allowed(value))
+      }
+      ArgumentMatcher::UrlScope => {
+        let Ok(url) = Url::parse(value) else {
+          return false;
+        };
+        let matches = |entries: &[JsonValue]| {
+          entries
+            .iter()
+            .filter_map(|entry| entry_str(entry, "url"))
+            .any(|pattern| {
+              Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(url.as_str()))
+                || Url::parse(pattern).is_ok_and(|pattern| pattern == url)
+            })
+        };
+        !matches(&self.deny) && matches(&self.allow)
+      }
+      ArgumentMatcher::Enum { .. } | ArgumentMatcher::Range { .. } => true,
+    }
+  }
+}
+
+/// The pattern of a scope entry, either a string or an object with the field.
+fn entry_str<'a>(entry: &'a JsonValue, field: &str) -> Option<&'a str> {
+  entry.as_str().or_else(|| entry.get(field)?.as_str())
+}
@@ -3,21 +3,42 @@
 // SPDX-License-Identifier: MIT
 
 use std::fmt::{Debug, Display};
-use std::{collections::BTreeMap, ops::Deref};
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap},
+  mem::size_of,
+  ops::Deref,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
 
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 use state::TypeMap;
+use tauri_runtime::window::WindowId;
 
-use tauri_utils::acl::Value;
 use tauri_utils::acl::{
-  resolved::{CommandKey, Resolved, ResolvedCommand, ResolvedScope, ScopeKey},
-  ExecutionContext,
+  capability::NavigationEvidence,
+  resolved::{
+    CommandKey, ExternalScope, PageGrant, RemoteOrigin, Resolved, ResolvedCommand, ResolvedScope,
+    ScopeKey, WebviewPattern, WindowGlobalScope,
+  },
+  tables::ScopeShards,
+  CommandId, ExecutionContext, PLUGIN_COMMAND_PREFIX, PLUGIN_COMMAND_SEPARATOR,
+};
+use tauri_utils::acl::{
+  memory::{HeapSize, HeapStats, MemoryStats},
+  Value,
 };
 
 use crate::{ipc::InvokeError, sealed::ManagerBase, Runtime};
 use crate::{AppHandle, Manager};
 
-use super::{CommandArg, CommandItem};
+use super::{
+  ephemeral::{EphemeralGrant, EphemeralGrants, EphemeralPolicy},
+  grants::{self, RuntimeGrants, TimedCapability},
+  metrics::{AclCounters, AclMetrics, AuthorityMemoryStats},
+  roles::RoleGrants,
+  CommandArg, CommandItem,
+};
 
 /// The runtime authority used to authorize IPC execution based on the Access Control List.
 pub struct RuntimeAuthority {
@@ -26,9 +47,54 @@ pub struct RuntimeAuthority {
   allowed_commands: BTreeMap<CommandKey, ResolvedCommand>,
   denied_commands: BTreeMap<CommandKey, ResolvedCommand>,
   pub(crate) scope_manager: ScopeManager,
+  counters: AclCounters,
+  #[cfg(any(debug_assertions, feature = "acl-timing"))]
+  timers: super::timing::AclTimers,
+  remote_origins: Vec<RemoteOrigin>,
+  inheriting_webviews: Vec<WebviewPattern>,
+  global_api: BTreeMap<String, BTreeMap<String, String>>,
+  command_aliases: BTreeMap<String, BTreeMap<String, String>>,
+  lockdown_remote: bool,
+  ephemeral: EphemeralGrants,
+  roles: RoleGrants,
+  #[cfg(debug_assertions)]
+  labels: super::labels::LabelRegistry,
+}
+
+/// The commands a webview can use per execution context and the plugin functions invoking them,
+/// used to inject only the allowed part of `window.__TAURI__`, see [`RuntimeAuthority::global_api`].
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GlobalApi<'a> {
+  /// The allowed and denied commands of each execution context.
+  contexts: Vec<GlobalApiContext<'a>>,
+  /// The functions of the plugins global API mapped to the commands they invoke.
+  functions: &'a BTreeMap<String, BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct GlobalApiContext<'a> {
+  /// The remote domain pattern, `None` for the local context.
+  remote: Option<&'a str>,
+  allow: BTreeSet<&'a str>,
+  deny: BTreeSet<&'a str>,
+}
+
+/// The decision of the [`crate::Builder::on_first_use`] hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirstUseDecision {
+  /// Allow the webview to use the class of commands for the rest of the session.
+  Allow,
+  /// Deny the webview from using the class of commands for the rest of the session.
+  Deny,
+  /// The decision is pending, e.g. while the app asks the user for consent.
+  ///
+  /// The command is rejected and the hook is consulted again on the next use.
+  Prompt,
 }
 
 /// The origin trying to access the IPC.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Origin {
   /// Local app origin.
   Local,
@@ -36,14 +102,215 @@ pub enum Origin {
   Remote {
     /// Remote origin domain.
     domain: String,
+    /// What the webview observed while navigating to the origin,
+    /// used to verify the attestation required by remote capabilities.
+    navigation: Option<NavigationEvidence>,
+  },
+}
+
+/// Finds the label capability window patterns select a webview by, given a pattern matcher.
+///
+/// Capabilities do not target webviews explicitly yet, so the window label takes precedence:
+/// the patterns are matched against the label of the window hosting the webview first,
+/// and against the webview label only if it differs, i.e. for the webviews of a multiwebview window.
+/// A [`WebviewWindow`](crate::WebviewWindow) has the same label for both.
+/// The webviews selected explicitly with [`CapabilityWindow::Webview`](crate::utils::acl::capability::CapabilityWindow::Webview)
+/// are matched separately, with both labels at once.
+///
+/// The command authorization and the command scopes both match window patterns through this function.
+fn matched_label<'a>(
+  window: &'a str,
+  webview: &'a str,
+  matches: impl Fn(&str) -> bool,
+) -> Option<&'a str> {
+  std::iter::once(window)
+    .chain((webview != window).then_some(webview))
+    .find(|label| matches(label))
+}
+
+/// An IPC execution to authorize with [`RuntimeAuthority::authorize`].
+///
+/// Capability window patterns select the calling webview by the label of its window first,
+/// and then by its own label, see [`Self::matched_label`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AccessRequest<'a> {
+  /// The command name.
+  pub command: &'a str,
+  /// The label of the window hosting the webview calling the command.
+  pub window: &'a str,
+  /// The label of the webview calling the command.
+  ///
+  /// This is the window label for a [`WebviewWindow`](crate::WebviewWindow).
+  pub webview: &'a str,
+  /// The labels of the window parent chain, ordered from its parent to the root window.
+  pub ancestors: &'a [String],
+  /// The origin calling the command.
+  pub origin: &'a Origin,
+  /// The path of the local page calling the command, e.g. `/settings.html`, `None` for a remote page.
+  ///
+  /// Selects the commands granted on some pages only,
+  /// see [`Capability#structfield.pages`](crate::utils::acl::capability::Capability#structfield.pages).
+  pub page: Option<&'a str>,
+}
+
+impl<'a> AccessRequest<'a> {
+  /// The label the given window pattern matcher selects the calling webview by, if any.
+  ///
+  /// The window label takes precedence: the webview label is only matched
+  /// if it differs from the window label and the window label is not matched.
+  pub fn matched_label(&self, matches: impl Fn(&str) -> bool) -> Option<&'a str> {
+    matched_label(self.window, self.webview, matches)
+  }
+
+  /// Whether the resolved command is granted to the calling webview,
+  /// by a window selector or by a webview selector matching both of its labels,
+  /// or by the selectors of a page grant matching the calling page.
+  fn matches(&self, authority: &RuntimeAuthority, resolved: &ResolvedCommand) -> bool {
+    authority
+      .matched_label(self.window, self.webview, |label| {
+        resolved.matches_window(label, self.ancestors)
+      })
+      .is_some()
+      || resolved.matches_webview(self.window, self.webview)
+      || resolved
+        .pages
+        .iter()
+        .any(|grant| grant.matches_page(self.page) && self.matches_page_grant(authority, grant))
+  }
+
+  /// Whether the page grant selects the calling webview, on any page.
+  fn matches_page_grant(&self, authority: &RuntimeAuthority, grant: &PageGrant) -> bool {
+    authority
+      .matched_label(self.window, self.webview, |label| {
+        grant.matches_window(label, self.ancestors)
+      })
+      .is_some()
+      || grant.matches_webview(self.window, self.webview)
+  }
+
+  /// Whether the origin satisfies the attestation required by the context, logging why it does not.
+  fn attestation_satisfied(&self, context: &ExecutionContext) -> bool {
+    match self.origin.verify_attestation(context) {
+      Ok(()) => true,
+      Err(reason) => {
+        log::warn!(
+          "capabilities requiring an attestation do not allow command {} from {}: {reason}",
+          self.command,
+          self.origin
+        );
+        false
+      }
+    }
+  }
+}
+
+/// Why the ACL does not allow an IPC execution, see [`RuntimeAuthority::explain`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "camelCase")]
+pub enum Denial {
+  /// The command is not allowed. Release builds do not give more details.
+  NotAllowed,
+  /// No capability grants the command.
+  #[serde(rename_all = "camelCase")]
+  NotGranted {
+    /// Permissions of the plugin that allow the command, the `allow-*` ones first.
+    suggested_permissions: Vec<String>,
+  },
+  /// The command is granted on this origin, but not to this window.
+  #[serde(rename_all = "camelCase")]
+  WindowNotMatched {
+    /// The window patterns the command is granted to.
+    windows: Vec<String>,
+  },
+  /// A permission explicitly denies the command on this origin.
+  #[serde(rename_all = "camelCase")]
+  Denied {
+    /// The capabilities denying the command.
+    capabilities: Vec<String>,
+  },
+  /// The command is granted, but not on this origin.
+  #[serde(rename_all = "camelCase")]
+  OriginNotMatched {
+    /// The origins the command is granted on.
+    origins: Vec<String>,
+  },
+  /// The app rejects every invoke from a remote origin,
+  /// see [`LOCKDOWN_REMOTE_CAPABILITY`](crate::utils::acl::capability::LOCKDOWN_REMOTE_CAPABILITY).
+  RemoteLockdown,
+  /// The command is granted on this origin once the webview navigation to it satisfies an attestation.
+  #[serde(rename_all = "camelCase")]
+  AttestationNotVerified {
+    /// Why the attestation is not satisfied.
+    details: String,
   },
 }
 
+impl Display for Denial {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NotAllowed => write!(f, "not allowed by ACL"),
+      Self::NotGranted {
+        suggested_permissions,
+      } if suggested_permissions.is_empty() => write!(f, "not granted by any capability"),
+      Self::NotGranted {
+        suggested_permissions,
+      } => write!(
+        f,
+        "not granted by any capability, add one of these permissions to a capability: {}",
+        suggested_permissions.join(", ")
+      ),
+      Self::WindowNotMatched { windows } => write!(
+        f,
+        "not allowed on this window, expected one of {}",
+        windows.join(", ")
+      ),
+      Self::Denied { capabilities } => write!(
+        f,
+        "denied by capabilities: {}",
+        capabilities.join(", ")
+      ),
+      Self::OriginNotMatched { origins } => write!(
+        f,
+        "not allowed on this origin, create a capability with this origin on the context field. Granted on: {}",
+        origins.join(", ")
+      ),
+      Self::RemoteLockdown => write!(
+        f,
+        "not allowed on a remote origin, the remote IPC is disabled by the core:lockdown-remote capability"
+      ),
+      Self::AttestationNotVerified { details } => {
+        write!(f, "not allowed on this origin until its attestation is verified: {details}")
+      }
+    }
+  }
+}
+
+/// The authorization of an [`AccessRequest`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDecision<'a> {
+  /// The command is allowed with the given [`ResolvedCommand`].
+  Allowed(&'a ResolvedCommand),
+  /// The command is denied by a permission on this origin, or by the remote lockdown.
+  Denied,
+  /// No permission allows the command on this window and origin.
+  NotAllowed,
+}
+
+impl<'a> AccessDecision<'a> {
+  /// The [`ResolvedCommand`] if the command is allowed.
+  pub fn allowed(self) -> Option<&'a ResolvedCommand> {
+    match self {
+      Self::Allowed(resolved) => Some(resolved),
+      Self::Denied | Self::NotAllowed => None,
+    }
+  }
+}
+
 impl Display for Origin {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
       Self::Local => write!(f, "local"),
-      Self::Remote { domain } => write!(f, "remote: {domain}"),
+      Self::Remote { domain, .. } => write!(f, "remote: {domain}"),
     }
   }
 }
@@ -53,23 +320,101 @@ impl Origin {
     match (self, context) {
       (Self::Local, ExecutionContext::Local) => true,
       (
-        Self::Remote { domain },
+        Self::Remote { domain, .. },
         ExecutionContext::Remote {
           domain: domain_pattern,
+          ..
         },
       ) => domain_pattern.matches(domain),
       _ => false,
     }
   }
+
+  /// Verifies the attestation required by a remote context, returning why it is not satisfied.
+  fn verify_attestation(&self, context: &ExecutionContext) -> Result<(), String> {
+    match (self, context) {
+      (
+        Self::Remote { navigation, .. },
+        ExecutionContext::Remote {
+          attestation: Some(attestation),
+          ..
+        },
+      ) => attestation.verify(navigation.as_ref()),
+      _ => Ok(()),
+    }
+  }
 }
 
 impl RuntimeAuthority {
-  pub(crate) fn new(resolved_acl: Resolved) -> Self {
+  pub(crate) fn new(mut resolved_acl: Resolved) -> Self {
+    // the scopes of the role grants are cached along the others, the grants only switch which entries apply
+    let role_grants = resolved_acl.take_role_grants();
+    let mut role_global_scopes = BTreeMap::<String, BTreeMap<usize, usize>>::new();
+    for (index, grant) in role_grants.iter().enumerate() {
+      for (plugin, scopes) in &grant.acl.global_scope {
+        let entries = resolved_acl.global_scope.entry(plugin.clone()).or_default();
+        let role_entries = role_global_scopes.entry(plugin.clone()).or_default();
+        for scope in scopes {
+          role_entries.insert(entries.len(), index);
+          entries.push(scope.clone());
+        }
+      }
+    }
+
     let command_cache = resolved_acl
       .command_scope
       .keys()
+      .chain(resolved_acl.command_scope_shards.keys())
       .map(|key| (*key, <TypeMap![Send + Sync]>::new()))
       .collect();
+    let global_scope_cache = resolved_acl
+      .global_scope
+      .iter()
+      .map(|(plugin, scopes)| {
+        (
+          plugin.clone(),
+          scopes
+            .iter()
+            .map(|_| <TypeMap![Send + Sync]>::new())
+            .collect(),
+        )
+      })
+      .collect();
+    let flattened_global_scope_cache = resolved_acl
+      .global_scope
+      .keys()
+      .map(|plugin| (plugin.clone(), <TypeMap![Send + Sync]>::new()))
+      .collect();
+    let counters = AclCounters::new(
+      resolved_acl
+        .allowed_commands
+        .keys()
+        .chain(resolved_acl.denied_commands.keys())
+        .chain(role_grants.iter().flat_map(|grant| {
+          grant
+            .acl
+            .allowed_commands
+            .keys()
+            .chain(grant.acl.denied_commands.keys())
+        }))
+        .map(|key| key.name.as_str()),
+    );
+    #[cfg(any(debug_assertions, feature = "acl-timing"))]
+    let timers = super::timing::AclTimers::new(
+      resolved_acl
+        .allowed_commands
+        .keys()
+        .chain(resolved_acl.denied_commands.keys())
+        .chain(role_grants.iter().flat_map(|grant| {
+          grant
+            .acl
+            .allowed_commands
+            .keys()
+            .chain(grant.acl.denied_commands.keys())
+        }))
+        .map(|key| key.name.as_str()),
+    );
+    let remote_origins = resolved_acl.remote_origins();
     Self {
       #[cfg(debug_assertions)]
       acl: resolved_acl.acl,
@@ -77,562 +422,3453 @@ impl RuntimeAuthority {
       denied_commands: resolved_acl.denied_commands,
       scope_manager: ScopeManager {
         command_scope: resolved_acl.command_scope,
+        command_scope_shards: resolved_acl.command_scope_shards,
         global_scope: resolved_acl.global_scope,
+        role_global_scopes,
+        shares_scope_with: resolved_acl.shares_scope_with,
         command_cache,
-        global_scope_cache: Default::default(),
+        global_scope_cache,
+        flattened_global_scope_cache,
+        empty_scope_cache: Default::default(),
+        external_scopes: Default::default(),
       },
+      counters,
+      #[cfg(any(debug_assertions, feature = "acl-timing"))]
+      timers,
+      remote_origins,
+      inheriting_webviews: resolved_acl.inheriting_webviews,
+      global_api: resolved_acl.global_api,
+      command_aliases: resolved_acl.command_aliases,
+      lockdown_remote: resolved_acl.lockdown_remote,
+      ephemeral: Default::default(),
+      roles: RoleGrants::new(role_grants),
+      #[cfg(debug_assertions)]
+      labels: Default::default(),
     }
   }
 
-  #[cfg(debug_assertions)]
-  pub(crate) fn resolve_access_message(
-    &self,
-    plugin: &str,
-    command_name: &str,
-    window: &str,
-    origin: &Origin,
-  ) -> String {
-    fn print_references(resolved: &ResolvedCommand) -> String {
-      resolved
-        .referenced_by
-        .iter()
-        .map(|r| format!("capability: {}, permission: {}", r.capability, r.permission))
-        .collect::<Vec<_>>()
-        .join(" || ")
+  /// Checks the expiries of the capability grants against the given clock.
+  #[cfg(test)]
+  pub(crate) fn with_clock(mut self, clock: super::clock::Clock) -> Self {
+    self.roles = std::mem::take(&mut self.roles).with_clock(clock);
+    self
+  }
+
+  /// Sets the provider loading the values of the external scopes, see [`crate::Builder::scope_provider`].
+  pub(crate) fn with_scope_provider(mut self, provider: Option<Arc<dyn ScopeProvider>>) -> Self {
+    self.scope_manager.external_scopes.provider = provider;
+    self
+  }
+
+  /// Whether every invoke from a remote origin is rejected,
+  /// see [`LOCKDOWN_REMOTE_CAPABILITY`](crate::utils::acl::capability::LOCKDOWN_REMOTE_CAPABILITY).
+  pub fn lockdown_remote(&self) -> bool {
+    self.lockdown_remote
+  }
+
+  /// Sets the active user roles, applying the grants of the capabilities restricted to any of them
+  /// and withdrawing the others at once, see [`Capability#structfield.roles`](crate::utils::acl::capability::Capability#structfield.roles).
+  ///
+  /// The change applies from the next invoke. The scope caches are kept per scope entry,
+  /// so the scopes of the withdrawn grants are not served anymore either.
+  /// The decisions of the [`crate::Builder::on_first_use`] hook are not reset.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,no_run
+  /// use tauri::Manager;
+  ///
+  /// #[tauri::command]
+  /// fn login(app: tauri::AppHandle) {
+  ///   app.acl().set_roles(&["admin"]);
+  /// }
+  ///
+  /// #[tauri::command]
+  /// fn logout(app: tauri::AppHandle) {
+  ///   app.acl().set_roles(&[] as &[&str]);
+  /// }
+  /// ```
+  pub fn set_roles<S: AsRef<str>>(&self, roles: &[S]) {
+    self.roles.set(roles);
+  }
+
+  /// The active user roles, see [`Self::set_roles`].
+  pub fn roles(&self) -> Vec<String> {
+    self.roles.roles()
+  }
+
+  /// Grants the capability until the duration elapsed, whatever the active user roles,
+  /// e.g. for an "allow for 15 minutes" prompt.
+  ///
+  /// The capability must be restricted to user roles, see [`Capability#structfield.roles`](crate::utils::acl::capability::Capability#structfield.roles):
+  /// its permissions are resolved at build time against the plugin manifests and only granted at runtime.
+  /// Granting it again replaces its expiry. Once expired, the grant and its scope entries stop applying
+  /// from the next authorization, see [`Self::purge_expired`] to drop the expired grants beforehand.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,no_run
+  /// use std::time::Duration;
+  /// use tauri::Manager;
+  ///
+  /// #[tauri::command]
+  /// fn allow_export(app: tauri::AppHandle) -> Result<(), String> {
+  ///   app
+  ///     .acl()
+  ///     .add_capability_with_expiry("export", Duration::from_secs(15 * 60))
+  ///     .map_err(|e| e.to_string())
+  /// }
+  /// ```
+  pub fn add_capability_with_expiry(&self, capability: &str, ttl: Duration) -> crate::Result<()> {
+    let expires_at = self.roles.now() + ttl;
+    if self.roles.grant_until(capability, expires_at) {
+      Ok(())
+    } else {
+      Err(crate::Error::UnknownRuntimeCapability(capability.into()))
     }
+  }
 
-    fn has_permissions_allowing_command(
-      manifest: &crate::utils::acl::plugin::Manifest,
-      set: &crate::utils::acl::PermissionSet,
-      command: &str,
-    ) -> bool {
-      for permission_id in &set.permissions {
-        if permission_id == "default" {
-          if let Some(default) = &manifest.default_permission {
-            if has_permissions_allowing_command(manifest, default, command) {
-              return true;
-            }
-          }
-        } else if let Some(ref_set) = manifest.permission_sets.get(permission_id) {
-          if has_permissions_allowing_command(manifest, ref_set, command) {
-            return true;
-          }
-        } else if let Some(permission) = manifest.permissions.get(permission_id) {
-          if permission.commands.allow.contains(&command.into()) {
-            return true;
-          }
-        }
+  /// Drops the expired capability grants, returning the identifiers of their capabilities,
+  /// see [`Self::add_capability_with_expiry`].
+  ///
+  /// The authorization checks already ignore the expired grants, this only frees them earlier.
+  pub fn purge_expired(&self) -> Vec<String> {
+    self.roles.purge_expired()
+  }
+
+  /// Exports the active user roles and the capabilities granted until an expiry as a blob signed with the key,
+  /// so they can be persisted and restored on the next launch with [`Self::import_runtime_grants`].
+  ///
+  /// The expiries are stored as absolute times. The ephemeral grants are bound to the windows
+  /// of this run and are not exported, see [`EphemeralPolicy`].
+  pub fn export_runtime_grants(&self, key: &[u8]) -> Vec<u8> {
+    let grants = RuntimeGrants {
+      roles: self.roles.roles(),
+      capabilities: self
+        .roles
+        .timed()
+        .into_iter()
+        .map(|(capability, expires_at)| TimedCapability::new(capability, expires_at))
+        .collect(),
+    };
+    grants::sign(&grants, key)
+  }
+
+  /// Restores the runtime grants exported by [`Self::export_runtime_grants`] with the same key,
+  /// returning the identifiers of the capabilities skipped because their grant already expired.
+  ///
+  /// The signature is verified, then every role and capability is checked against the capabilities
+  /// restricted to user roles compiled into the app. On failure, nothing is applied and
+  /// [`crate::Error::RuntimeGrantsImport`] reports the entry that was rejected.
+  /// The imported roles replace the active ones, see [`Self::set_roles`].
+  ///
+  /// # Examples
+  ///
+  /// ```rust,no_run
+  /// use tauri::Manager;
+  ///
+  /// tauri::Builder::default()
+  ///   .setup(|app| {
+  ///     let key = std::env::var("GRANTS_KEY")?;
+  ///     if let Ok(blob) = std::fs::read(app.path().app_data_dir()?.join("grants")) {
+  ///       app.acl().import_runtime_grants(&blob, key.as_bytes())?;
+  ///     }
+  ///     Ok(())
+  ///   });
+  /// ```
+  pub fn import_runtime_grants(&self, blob: &[u8], key: &[u8]) -> crate::Result<Vec<String>> {
+    let grants = grants::verify(blob, key).map_err(|reason| crate::Error::RuntimeGrantsImport {
+      entry: None,
+      reason,
+    })?;
+
+    for role in &grants.roles {
+      if !self.roles.has_role(role) {
+        return Err(crate::Error::RuntimeGrantsImport {
+          entry: Some(format!("role {role}")),
+          reason: "no capability is restricted to this role".into(),
+        });
+      }
+    }
+    for timed in &grants.capabilities {
+      if !self.roles.has_capability(&timed.capability) {
+        return Err(crate::Error::RuntimeGrantsImport {
+          entry: Some(format!("capability {}", timed.capability)),
+          reason: "the capability is not restricted to user roles".into(),
+        });
       }
-      false
     }
 
-    let command = format!("plugin:{plugin}|{command_name}");
-    if let Some((_cmd, resolved)) = self
-      .denied_commands
-      .iter()
-      .find(|(cmd, _)| cmd.name == command && origin.matches(&cmd.context))
-    {
-      format!(
-        "{plugin}.{command_name} denied on origin {origin}, referenced by: {}",
-        print_references(resolved)
-      )
-    } else {
-      let command_matches = self
-        .allowed_commands
-        .iter()
-        .filter(|(cmd, _)| cmd.name == command)
-        .collect::<BTreeMap<_, _>>();
-
-      if let Some((_cmd, resolved)) = command_matches
-        .iter()
-        .find(|(cmd, _)| origin.matches(&cmd.context))
-      {
-        if resolved.windows.iter().any(|w| w.matches(window)) {
-          "allowed".to_string()
-        } else {
-          format!("{plugin}.{command_name} not allowed on window {window}, expected one of {}, referenced by {}", resolved.windows.iter().map(|w| w.as_str()).collect::<Vec<_>>().join(", "), print_references(resolved))
-        }
+    self.set_roles(&grants.roles);
+    let now = self.roles.now();
+    let mut expired = Vec::new();
+    for timed in grants.capabilities {
+      let expires_at = timed.expires_at();
+      if expires_at <= now {
+        expired.push(timed.capability);
       } else {
-        let permission_error_detail = if let Some(manifest) = self.acl.get(plugin) {
-          let mut permissions_referencing_command = Vec::new();
-
-          if let Some(default) = &manifest.default_permission {
-            if has_permissions_allowing_command(manifest, default, command_name) {
-              permissions_referencing_command.push("default".into());
-            }
-          }
-          for set in manifest.permission_sets.values() {
-            if has_permissions_allowing_command(manifest, set, command_name) {
-              permissions_referencing_command.push(set.identifier.clone());
-            }
-          }
-          for permission in manifest.permissions.values() {
-            if permission.commands.allow.contains(&command_name.into()) {
-              permissions_referencing_command.push(permission.identifier.clone());
-            }
-          }
+        self.roles.grant_until(&timed.capability, expires_at);
+      }
+    }
+    Ok(expired)
+  }
 
-          permissions_referencing_command.sort();
+  /// The canonical name of the given command when it is a plugin command alias,
+  /// see [`Manifest#structfield.aliases`](crate::utils::acl::plugin::Manifest#structfield.aliases).
+  ///
+  /// Aliases are translated before the authorization, so the canonical command permissions apply.
+  pub(crate) fn canonical_command(&self, command: &str) -> Option<String> {
+    let id = CommandId::parse(command).ok()?;
+    let canonical = self.command_aliases.get(id.plugin())?.get(id.command())?;
+    CommandId::new(id.plugin(), canonical.as_str())
+      .ok()
+      .map(|canonical| canonical.to_string())
+  }
 
-          format!(
-            "Permissions associated with this command: {}",
-            permissions_referencing_command
-              .iter()
-              .map(|p| format!("{plugin}:{p}"))
-              .collect::<Vec<_>>()
-              .join(", ")
-          )
-        } else {
-          "Plugin did not define its manifest".to_string()
-        };
+  /// The CSP sources of the remote origins the capabilities grant to the given window,
+  /// see [`SecurityConfig#structfield.capability_connect_src`](crate::utils::config::SecurityConfig#structfield.capability_connect_src).
+  ///
+  /// The window ancestors are ordered from its parent to the root window.
+  pub fn connect_src<S: AsRef<str>>(&self, window: &str, ancestors: &[S]) -> Vec<String> {
+    self
+      .remote_origins
+      .iter()
+      .filter(|origin| origin.matches_window(window, ancestors))
+      .filter_map(RemoteOrigin::csp_source)
+      .collect()
+  }
 
-        if command_matches.is_empty() {
-          format!("{plugin}.{command_name} not allowed. {permission_error_detail}")
-        } else {
-          format!(
-            "{plugin}.{command_name} not allowed on origin [{}]. Please create a capability that has this origin on the context field.\n\nFound matches for: {}\n\n{permission_error_detail}",
-            origin,
-            command_matches
-              .iter()
-              .map(|(cmd, resolved)| {
-                let context = match &cmd.context {
-                  ExecutionContext::Local => "[local]".to_string(),
-                  ExecutionContext::Remote { domain } => format!("[remote: {}]", domain.as_str()),
-                };
-                format!(
-                  "- context: {context}, referenced by: {}",
-                  print_references(resolved)
-                )
-              })
-              .collect::<Vec<_>>()
-              .join("\n")
-          )
-        }
+  /// The commands the given webview can use per execution context, for the global API injected in it.
+  ///
+  /// The remote contexts are matched against the page domain by the injected script on each navigation.
+  /// Hiding the functions is not what denies a command: the IPC still authorizes every invoke.
+  pub(crate) fn global_api(
+    &self,
+    window: &str,
+    webview: &str,
+    ancestors: &[String],
+  ) -> GlobalApi<'_> {
+    let request = AccessRequest {
+      command: "",
+      window,
+      webview,
+      ancestors,
+      origin: &Origin::Local,
+      page: None,
+    };
+    // the functions of the commands granted on some pages are available on every page
+    let allowed = self
+      .allowed_commands
+      .iter()
+      .filter(|(_, resolved)| {
+        request.matches(self, resolved)
+          || resolved
+            .pages
+            .iter()
+            .any(|grant| request.matches_page_grant(self, grant))
+      })
+      .map(|(key, _)| (key, true));
+    // denied commands are denied on every webview
+    let denied = self.denied_commands.keys().map(|key| (key, false));
+    // the role grants are read on each injection, a role change applies from the next navigation
+    let role_grants = self.roles.active().collect::<Vec<_>>();
+    let role_allowed = role_grants
+      .iter()
+      .flat_map(|grant| &grant.acl.allowed_commands)
+      .filter(|(_, resolved)| {
+        request.matches(self, resolved)
+          || resolved
+            .pages
+            .iter()
+            .any(|grant| request.matches_page_grant(self, grant))
+      })
+      .map(|(key, _)| (key, true));
+    let role_denied = role_grants
+      .iter()
+      .flat_map(|grant| grant.acl.denied_commands.keys())
+      .map(|key| (key, false));
+
+    let mut contexts = BTreeMap::new();
+    for (key, allow) in allowed.chain(role_allowed).chain(denied).chain(role_denied) {
+      let remote = match &key.context {
+        ExecutionContext::Local => None,
+        // the remote contexts get no function at all
+        ExecutionContext::Remote { .. } if self.lockdown_remote => continue,
+        ExecutionContext::Remote { domain, .. } => Some(domain.as_str()),
+      };
+      let context = contexts.entry(remote).or_insert_with(|| GlobalApiContext {
+        remote,
+        ..Default::default()
+      });
+      if allow {
+        context.allow.insert(key.name.as_str());
+      } else {
+        context.deny.insert(key.name.as_str());
       }
     }
+
+    GlobalApi {
+      contexts: contexts.into_values().collect(),
+      functions: &self.global_api,
+    }
   }
 
-  /// Checks if the given IPC execution is allowed and returns the [`ResolvedCommand`] if it is.
-  pub fn resolve_access(
+  /// Finds the label capability window patterns select a webview by, like [`AccessRequest::matched_label`].
+  ///
+  /// The webviews inheriting the grants of their window are not selected by their window label,
+  /// their inherited grants are matched by their webview selectors instead,
+  /// see [`Capability#structfield.inherit_from_window`](crate::utils::acl::capability::Capability#structfield.inherit_from_window).
+  pub(crate) fn matched_label<'a>(
     &self,
-    command: &str,
-    window: &str,
-    origin: &Origin,
-  ) -> Option<&ResolvedCommand> {
-    if self
-      .denied_commands
-      .keys()
-      .any(|cmd| cmd.name == command && origin.matches(&cmd.context))
+    window: &'a str,
+    webview: &'a str,
+    matches: impl Fn(&str) -> bool,
+  ) -> Option<&'a str> {
+    if webview != window
+      && self
+        .inheriting_webviews
+        .iter()
+        .any(|pattern| pattern.matches(window, webview))
     {
-      None
+      Some(webview).filter(|label| matches(label))
     } else {
-      self
-        .allowed_commands
-        .iter()
-        .find(|(cmd, _)| cmd.name == command && origin.matches(&cmd.context))
-        .map(|(_cmd, resolved)| resolved)
-        .filter(|resolved| resolved.windows.iter().any(|w| w.matches(window)))
+      matched_label(window, webview, matches)
     }
   }
-}
-
-/// List of allowed and denied objects that match either the command-specific or plugin global scope criterias.
-#[derive(Debug)]
-pub struct ScopeValue<T: ScopeObject> {
-  allow: Vec<T>,
-  deny: Vec<T>,
-}
 
-impl<T: ScopeObject> ScopeValue<T> {
-  /// What this access scope allows.
-  pub fn allows(&self) -> &Vec<T> {
-    &self.allow
+  /// Whether some command scopes are decoded on their first use, see [`Self::prewarm_scopes`].
+  pub(crate) fn has_lazy_scopes(&self) -> bool {
+    !self.scope_manager.command_scope_shards.is_empty()
   }
 
-  /// What this access scope denies.
-  pub fn denies(&self) -> &Vec<T> {
-    &self.deny
+  /// Decodes the lazily loaded scopes of the commands allowed on the given root window,
+  /// so their first invoke does not wait for it.
+  pub(crate) fn prewarm_scopes(&self, window: &str) {
+    self.scope_manager.command_scope_shards.prewarm(
+      self
+        .allowed_commands
+        .values()
+        .flat_map(|command| command.window_scope_keys(window, &[] as &[&str])),
+    );
   }
-}
 
-#[derive(Debug)]
-enum OwnedOrRef<'a, T: Debug> {
-  Owned(T),
-  Ref(&'a T),
-}
+  /// Grants a global scope entry of the plugin to the window at runtime,
+  /// e.g. the path a user picked in a file dialog so the filesystem commands can access it.
+  ///
+  /// The entry is added to the [`GlobalScope`] allowed entries after the ones of the capabilities,
+  /// so a denied entry still wins. [`GlobalScope::is_allowed`] and the scopes built from the ACL,
+  /// such as [`crate::scope::fs::Scope::from_acl`], consume its uses.
+  /// It is dropped when the policy exhausts it or when the window is destroyed,
+  /// a new window reusing the label does not get it.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,no_run
+  /// use std::{path::PathBuf, time::Duration};
+  /// use tauri::{command::EphemeralPolicy, Manager, Window};
+  ///
+  /// #[tauri::command]
+  /// fn pick_file(window: Window) -> PathBuf {
+  ///   let path = PathBuf::from("/home/tauri/report.pdf");
+  ///   window.acl().grant_ephemeral(
+  ///     "fs",
+  ///     &window,
+  ///     serde_json::json!({ "path": path }),
+  ///     EphemeralPolicy::new().uses(1).expires_in(Duration::from_secs(60)),
+  ///   );
+  ///   path
+  /// }
+  /// ```
+  pub fn grant_ephemeral<R: Runtime>(
+    &self,
+    plugin: &str,
+    window: &crate::Window<R>,
+    value: impl Into<Value>,
+    policy: EphemeralPolicy,
+  ) {
+    self
+      .ephemeral
+      .grant(plugin, window.window.id, value.into(), policy);
+  }
 
-impl<'a, T: Debug> Deref for OwnedOrRef<'a, T> {
-  type Target = T;
-  fn deref(&self) -> &Self::Target {
-    match self {
-      Self::Owned(t) => t,
-      Self::Ref(r) => r,
-    }
+  /// Drops the scope entries granted to the window at runtime, see [`Self::grant_ephemeral`].
+  pub(crate) fn forget_ephemeral(&self, window: WindowId) {
+    self.ephemeral.forget(window);
   }
-}
 
-/// Access scope for a command that can be retrieved directly in the command function.
-#[derive(Debug)]
-pub struct CommandScope<'a, T: ScopeObject>(OwnedOrRef<'a, ScopeValue<T>>);
+  /// The usable scope entries of the plugin granted to the window at runtime.
+  fn ephemeral_entries<R: Runtime, T: ScopeObject>(
+    &self,
+    app: &AppHandle<R>,
+    plugin: &str,
+    window: WindowId,
+  ) -> crate::Result<Vec<(Arc<EphemeralGrant>, T)>> {
+    self
+      .ephemeral
+      .get(plugin, window)
+      .into_iter()
+      .map(|grant| {
+        T::deserialize(app, grant.value.clone())
+          .map(|entry| (grant, entry))
+          .map_err(|e| crate::Error::CannotDeserializeScope(Box::new(e)))
+      })
+      .collect()
+  }
 
-impl<'a, T: ScopeObject> CommandScope<'a, T> {
-  /// What this access scope allows.
-  pub fn allows(&self) -> &Vec<T> {
-    &self.0.allow
+  /// The global scope of the plugin granted to the window, by the capabilities whose global scope entries match
+  /// and at runtime, along with the global scopes of the plugins it shares the scope with.
+  fn window_global_scope<R: Runtime, T: ScopeObject>(
+    &self,
+    app: &AppHandle<R>,
+    plugin: &str,
+    window: WindowId,
+    matches: impl Fn(&WindowGlobalScope) -> bool,
+  ) -> crate::Result<GlobalScope<'_, T>> {
+    let scope_manager = &self.scope_manager;
+    let active_roles = self.roles.active_indexes();
+    let ephemeral = self.ephemeral_entries(app, plugin, window)?;
+    // the usable runtime entries are allowed after the entries of the capabilities
+    let runtime_scope = (!ephemeral.is_empty()).then(|| ResolvedScope {
+      allow: ephemeral
+        .iter()
+        .map(|(grant, _)| grant.value.clone())
+        .collect(),
+      ..Default::default()
+    });
+    let scope = scope_manager.get_window_global_scope_typed(
+      app,
+      plugin,
+      &active_roles,
+      &matches,
+      runtime_scope.as_ref(),
+    )?;
+    // the shared scopes are read under their plugin, they are not copied to this one
+    let shared = scope_manager
+      .shares_scope_with
+      .get(plugin)
+      .into_iter()
+      .flatten()
+      .map(|shared| {
+        scope_manager
+          .get_window_global_scope_typed(app, shared, &active_roles, &matches, None)
+          .map(|scope| (shared.as_str(), scope))
+      })
+      .collect::<crate::Result<_>>()?;
+    Ok(GlobalScope(scope, ephemeral, shared))
   }
 
-  /// What this access scope denies.
-  pub fn denies(&self) -> &Vec<T> {
-    &self.0.deny
+  /// The global scope of the plugin granted to the webview loaded on the page, see [`GlobalScope`].
+  pub(crate) fn webview_global_scope<R: Runtime, T: ScopeObject>(
+    &self,
+    webview: &crate::Webview<R>,
+    plugin: &str,
+    page: Option<&str>,
+  ) -> crate::Result<GlobalScope<'_, T>> {
+    let window = webview.window();
+    let ancestors = webview.manager().window.ancestors(window.label());
+    self.window_global_scope(webview.app_handle(), plugin, window.window.id, |scope| {
+      scope.matches_page(page)
+        && (self
+          .matched_label(window.label(), webview.label(), |label| {
+            scope.matches_window(label, &ancestors)
+          })
+          .is_some()
+          || scope.matches_webview(window.label(), webview.label()))
+    })
   }
-}
 
-impl<'a, R: Runtime, T: ScopeObject> CommandArg<'a, R> for CommandScope<'a, T> {
-  /// Grabs the [`ResolvedScope`] from the [`CommandItem`] and returns the associated [`CommandScope`].
-  fn from_command(command: CommandItem<'a, R>) -> Result<Self, InvokeError> {
-    if let Some(scope_id) = command.acl.as_ref().and_then(|resolved| resolved.scope) {
-      Ok(CommandScope(OwnedOrRef::Ref(
-        command
-          .message
-          .webview
-          .manager()
-          .runtime_authority
-          .scope_manager
-          .get_command_scope_typed(command.message.webview.app_handle(), &scope_id)?,
-      )))
-    } else {
-      Ok(CommandScope(OwnedOrRef::Owned(ScopeValue {
-        allow: Vec::new(),
-        deny: Vec::new(),
-      })))
+  /// Estimates the heap footprint of the authority: the resolved ACL, the scope caches,
+  /// the external scopes loaded so far, the runtime grants and the decision counters.
+  ///
+  /// The scopes deserialized into the types of the commands are only counted, their size is unknown.
+  pub fn memory_stats(&self) -> AuthorityMemoryStats {
+    let scopes = &self.scope_manager;
+    let mut acl = MemoryStats::default();
+    acl.add_commands(&self.allowed_commands, false);
+    acl.add_commands(&self.denied_commands, true);
+    acl.add_command_scopes(
+      self.allowed_commands.iter().chain(&self.denied_commands),
+      &scopes.command_scope,
+    );
+    acl.add_scope_shards(&scopes.command_scope_shards);
+    acl.add_global_scope(&scopes.global_scope);
+    acl.add_other(&scopes.shares_scope_with);
+    acl.add_inheriting_webviews(&self.inheriting_webviews);
+    acl.add_other(&self.global_api);
+    acl.add_other(&self.command_aliases);
+    acl.add_other(&self.remote_origins);
+
+    let cached_scopes = scopes
+      .command_cache
+      .values()
+      .chain(scopes.global_scope_cache.values().flatten())
+      .chain(scopes.flattened_global_scope_cache.values())
+      .chain([&scopes.empty_scope_cache])
+      .map(|cache| cache.len())
+      .sum();
+
+    let mut external_scopes = HeapStats::default();
+    for (key, scope) in scopes.external_scopes.loaded.lock().unwrap().iter() {
+      external_scopes.add(size_of::<ResolvedScope>() + key.heap_size() + scope.heap_size());
+    }
+
+    let mut counters = HeapStats::default();
+    counters.add(self.counters.heap_size());
+
+    AuthorityMemoryStats {
+      acl,
+      cached_scopes,
+      external_scopes,
+      ephemeral_grants: self.ephemeral.heap_stats(),
+      counters,
     }
   }
-}
 
-/// Global access scope that can be retrieved directly in the command function.
-#[derive(Debug)]
-pub struct GlobalScope<'a, T: ScopeObject>(&'a ScopeValue<T>);
+  /// A snapshot of the ACL decisions of the plugin commands invoked since startup.
+  pub fn metrics(&self) -> AclMetrics {
+    self.counters.snapshot()
+  }
 
-impl<'a, T: ScopeObject> GlobalScope<'a, T> {
-  /// What this access scope allows.
-  pub fn allows(&self) -> &Vec<T> {
-    &self.0.allow
+  /// Counts the ACL decision of an IPC call, see [`Self::metrics`].
+  pub(crate) fn record(&self, command: &str, decision: &AccessDecision<'_>) {
+    self.counters.record(command, decision);
   }
 
-  /// What this access scope denies.
-  pub fn denies(&self) -> &Vec<T> {
-    &self.0.deny
+  /// The percentiles of the authorization durations of the commands invoked since startup,
+  /// only available in debug builds or with the `acl-timing` feature.
+  #[cfg(any(debug_assertions, feature = "acl-timing"))]
+  pub fn timings(&self) -> super::AclTimings {
+    self.timers.snapshot()
   }
-}
 
-impl<'a, R: Runtime, T: ScopeObject> CommandArg<'a, R> for GlobalScope<'a, T> {
-  /// Grabs the [`ResolvedScope`] from the [`CommandItem`] and returns the associated [`GlobalScope`].
-  fn from_command(command: CommandItem<'a, R>) -> Result<Self, InvokeError> {
-    command
-      .plugin
-      .ok_or_else(|| {
-        InvokeError::from_anyhow(anyhow::anyhow!(
-          "global scope not available for app commands"
-        ))
+  /// Measures an authorization phase of an IPC call, see [`Self::timings`].
+  #[cfg(any(debug_assertions, feature = "acl-timing"))]
+  pub(crate) fn record_timing(
+    &self,
+    plugin: Option<&str>,
+    command: &str,
+    phase: super::AuthorizationPhase,
+    elapsed: std::time::Duration,
+  ) {
+    self.timers.record(plugin, command, phase, elapsed);
+  }
+
+  /// Records the label of a created window or webview, see [`Self::unmatched_window_patterns`].
+  #[cfg(debug_assertions)]
+  pub(crate) fn record_label(&self, label: &str) {
+    self.labels.record(label);
+  }
+
+  /// The window patterns of the resolved commands that matched no window or webview created since startup,
+  /// usually a typo in a capability. Only available in debug builds.
+  ///
+  /// Windows can be created at any time, so this is only conclusive once the app has created all its windows.
+  #[cfg(debug_assertions)]
+  pub fn unmatched_window_patterns(&self) -> Vec<super::UnmatchedWindowPattern> {
+    self.labels.unmatched(
+      self
+        .allowed_commands
+        .values()
+        .chain(self.denied_commands.values()),
+    )
+  }
+
+  /// The resolved ACL in the shape rendered by the ACL inspector.
+  #[cfg(debug_assertions)]
+  pub(crate) fn inspect(&self) -> crate::acl_inspector::Inspection {
+    crate::acl_inspector::Inspection::new(
+      &self.allowed_commands,
+      &self.denied_commands,
+      |key| self.scope_manager.command_scope(key),
+      &self.scope_manager.global_scope,
+      self.memory_stats().total_bytes(),
+    )
+  }
+
+  /// Writes a table of the allowed commands grouped by plugin, with their windows and whether a scope is attached.
+  /// Only available in debug builds.
+  ///
+  /// Rows are sorted by plugin, command and context, and long cells are truncated.
+  /// The app logs this table on startup when the `TAURI_ACL_PRINT` environment variable is set to `1`.
+  #[cfg(debug_assertions)]
+  pub fn print_summary(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+    self.inspect().write_summary(writer)
+  }
+
+  /// Explains why the ACL does not allow the given IPC execution, returning [`None`] if it is allowed.
+  ///
+  /// The webview is assumed to be the one of a [`WebviewWindow`](crate::WebviewWindow) labeled `window`,
+  /// use [`Self::explain_request`] for the webviews of a multiwebview window.
+  ///
+  /// Release builds always explain a denial with [`Denial::NotAllowed`] to avoid leaking the app policy.
+  pub fn explain(
+    &self,
+    command: &str,
+    window: &str,
+    ancestors: &[String],
+    origin: &Origin,
+  ) -> Option<Denial> {
+    self.explain_request(&AccessRequest {
+      command,
+      window,
+      webview: window,
+      ancestors,
+      origin,
+      page: None,
+    })
+  }
+
+  /// Explains why the ACL does not allow the given IPC execution, returning [`None`] if it is allowed.
+  ///
+  /// Release builds always explain a denial with [`Denial::NotAllowed`] to avoid leaking the app policy.
+  pub fn explain_request(&self, request: &AccessRequest<'_>) -> Option<Denial> {
+    match self.authorize(request) {
+      AccessDecision::Allowed(_) => None,
+      #[cfg(debug_assertions)]
+      AccessDecision::Denied | AccessDecision::NotAllowed => Some(self.explain_denial(request)),
+      #[cfg(not(debug_assertions))]
+      AccessDecision::Denied | AccessDecision::NotAllowed => Some(Denial::NotAllowed),
+    }
+  }
+
+  #[cfg(debug_assertions)]
+  fn explain_denial(&self, request: &AccessRequest<'_>) -> Denial {
+    if self.lockdown_remote && !matches!(request.origin, Origin::Local) {
+      return Denial::RemoteLockdown;
+    }
+
+    fn has_permissions_allowing_command<'a>(
+      manifest: &'a crate::utils::acl::plugin::Manifest,
+      set: &'a crate::utils::acl::PermissionSet,
+      command: &str,
+      parents: &mut Vec<&'a str>,
+    ) -> bool {
+      // cyclic sets fail the resolution, so they are never suggested
+      if parents.contains(&set.identifier.as_str()) {
+        return false;
+      }
+      parents.push(&set.identifier);
+      let allowed = set
+        .permissions
+        .iter()
+        .any(|id| reference_allows_command(manifest, id, command, parents))
+        && set
+          .intersect
+          .iter()
+          .all(|id| reference_allows_command(manifest, id, command, parents))
+        && !set
+          .exclude
+          .iter()
+          .any(|id| reference_allows_command(manifest, id, command, parents));
+      parents.pop();
+      allowed
+    }
+
+    fn reference_allows_command<'a>(
+      manifest: &'a crate::utils::acl::plugin::Manifest,
+      permission_id: &str,
+      command: &str,
+      parents: &mut Vec<&'a str>,
+    ) -> bool {
+      if permission_id == "default" {
+        if let Some(default) = &manifest.default_permission {
+          return has_permissions_allowing_command(manifest, default, command, parents);
+        }
+      }
+      if let Some(ref_set) = manifest.permission_sets.get(permission_id) {
+        has_permissions_allowing_command(manifest, ref_set, command, parents)
+      } else if let Some(permission) = manifest.permissions.get(permission_id) {
+        permission.commands.allows(command)
+      } else {
+        false
+      }
+    }
+
+    let denied = self
+      .denied_commands
+      .iter()
+      .filter(|(cmd, _)| cmd.name == request.command && request.origin.matches(&cmd.context))
+      .flat_map(|(_cmd, resolved)| &resolved.referenced_by)
+      .map(|r| r.capability.clone())
+      .collect::<BTreeSet<_>>();
+    if !denied.is_empty() {
+      return Denial::Denied {
+        capabilities: denied.into_iter().collect(),
+      };
+    }
+
+    let command_matches = self
+      .allowed_commands
+      .iter()
+      .filter(|(cmd, _)| cmd.name == request.command)
+      .collect::<Vec<_>>();
+
+    let origin_matches = command_matches
+      .iter()
+      .filter(|(cmd, _)| request.origin.matches(&cmd.context))
+      .collect::<Vec<_>>();
+
+    if let Some((_cmd, resolved)) = origin_matches
+      .iter()
+      .find(|(cmd, _)| request.origin.verify_attestation(&cmd.context).is_ok())
+    {
+      fn selectors(
+        windows: &[crate::utils::acl::window::WindowPattern],
+        window_ancestors: &[crate::utils::acl::resolved::WindowAncestor],
+        webviews: &[WebviewPattern],
+      ) -> Vec<String> {
+        windows
+          .iter()
+          .map(|w| w.as_str().to_string())
+          .chain(window_ancestors.iter().map(|a| {
+            if a.parent_only {
+              format!("parent-of {}", a.pattern.as_str())
+            } else {
+              format!("descendant-of {}", a.pattern.as_str())
+            }
+          }))
+          .chain(
+            webviews
+              .iter()
+              .map(|w| format!("webview {} in {}", w.webview.as_str(), w.window.as_str())),
+          )
+          .collect()
+      }
+
+      Denial::WindowNotMatched {
+        windows: selectors(
+          &resolved.windows,
+          &resolved.window_ancestors,
+          &resolved.webviews,
+        )
+        .into_iter()
+        .chain(resolved.pages.iter().map(|grant| {
+          let pages = grant
+            .pages
+            .iter()
+            .map(|page| page.as_str())
+            .collect::<Vec<_>>();
+          format!(
+            "{} on pages {}",
+            selectors(&grant.windows, &grant.window_ancestors, &grant.webviews).join(", "),
+            pages.join(", ")
+          )
+        }))
+        .collect(),
+      }
+    } else if let Some((cmd, _)) = origin_matches.first() {
+      Denial::AttestationNotVerified {
+        details: request.origin.verify_attestation(&cmd.context).unwrap_err(),
+      }
+    } else if !command_matches.is_empty() {
+      Denial::OriginNotMatched {
+        origins: command_matches
+          .iter()
+          .map(|(cmd, _)| match &cmd.context {
+            ExecutionContext::Local => Origin::Local.to_string(),
+            ExecutionContext::Remote { domain, .. } => format!("remote: {}", domain.as_str()),
+          })
+          .collect(),
+      }
+    } else {
+      let mut suggested_permissions = Vec::new();
+
+      let command_id = CommandId::parse(request.command);
+      if let Some((id, manifest)) = command_id
+        .as_ref()
+        .ok()
+        .and_then(|id| self.acl.get(id.plugin()).map(|manifest| (id, manifest)))
+      {
+        let (plugin, command) = (id.plugin(), id.command());
+        let mut permissions = Vec::new();
+        if let Some(default) = &manifest.default_permission {
+          if has_permissions_allowing_command(manifest, default, command, &mut Vec::new()) {
+            permissions.push("default");
+          }
+        }
+        for set in manifest.permission_sets.values() {
+          if has_permissions_allowing_command(manifest, set, command, &mut Vec::new()) {
+            permissions.push(&set.identifier);
+          }
+        }
+        for permission in manifest.permissions.values() {
+          if permission.commands.allows(command) {
+            permissions.push(&permission.identifier);
+          }
+        }
+        permissions.sort();
+        // the `allow-*` permissions are usually the ones to add to a capability
+        permissions.sort_by_key(|p| !p.starts_with("allow-"));
+        suggested_permissions.extend(permissions.into_iter().map(|p| format!("{plugin}:{p}")));
+      }
+
+      Denial::NotGranted {
+        suggested_permissions,
+      }
+    }
+  }
+
+  /// Checks if the given IPC execution is allowed and returns the [`ResolvedCommand`] if it is.
+  ///
+  /// The webview is assumed to be the one of a [`WebviewWindow`](crate::WebviewWindow) labeled `window`,
+  /// use [`Self::authorize`] for the webviews of a multiwebview window.
+  ///
+  /// The window ancestors are the labels of its parent chain, ordered from its parent to the root window.
+  pub fn resolve_access(
+    &self,
+    command: &str,
+    window: &str,
+    ancestors: &[String],
+    origin: &Origin,
+  ) -> Option<&ResolvedCommand> {
+    self
+      .authorize(&AccessRequest {
+        command,
+        window,
+        webview: window,
+        ancestors,
+        origin,
+        page: None,
       })
-      .and_then(|plugin| {
-        command
-          .message
-          .webview
-          .manager()
-          .runtime_authority
-          .scope_manager
-          .get_global_scope_typed(command.message.webview.app_handle(), plugin)
-          .map_err(InvokeError::from_error)
+      .allowed()
+  }
+
+  /// Authorizes a batch of IPC executions, returning the decision of each request in the same order.
+  ///
+  /// The batch shares the lookups of [`Self::authorize`]: the commands are looked up once per command name,
+  /// the capability window patterns are matched once per calling webview and command entry,
+  /// and identical requests are only resolved once. All requests see the same active roles, see [`Self::set_roles`].
+  /// A denied request does not affect the decision of the other requests.
+  ///
+  /// Used by the IPC requests running several commands at once, see [`BATCH_INVOKE_COMMAND`](crate::ipc::BATCH_INVOKE_COMMAND).
+  pub fn authorize_all(&self, requests: &[AccessRequest<'_>]) -> Vec<AccessDecision<'_>> {
+    let role_grants = self.roles.active().collect::<Vec<_>>();
+
+    let mut commands = requests
+      .iter()
+      .map(|request| (request.command, BatchCommands::default()))
+      .collect::<HashMap<_, _>>();
+    for key in self.denied_commands.keys().chain(
+      role_grants
+        .iter()
+        .flat_map(|grant| grant.acl.denied_commands.keys()),
+    ) {
+      if let Some(batch) = commands.get_mut(key.name.as_str()) {
+        batch.denied.push(key);
+      }
+    }
+    for (key, resolved) in self.allowed_commands.iter().chain(
+      role_grants
+        .iter()
+        .flat_map(|grant| &grant.acl.allowed_commands),
+    ) {
+      if let Some(batch) = commands.get_mut(key.name.as_str()) {
+        batch.allowed.push((key, resolved));
+      }
+    }
+
+    let mut label_matches = HashMap::new();
+    let mut decisions = HashMap::new();
+    requests
+      .iter()
+      .map(|request| {
+        *decisions.entry(request).or_insert_with(|| {
+          let batch = &commands[request.command];
+          self.decide(
+            request,
+            batch.denied.iter().copied(),
+            batch.allowed.iter().copied(),
+            |resolved| {
+              let caller = (
+                request.window,
+                request.webview,
+                request.ancestors,
+                request.page,
+                resolved as *const ResolvedCommand,
+              );
+              *label_matches
+                .entry(caller)
+                .or_insert_with(|| request.matches(self, resolved))
+            },
+          )
+        })
       })
-      .map(GlobalScope)
+      .collect()
+  }
+
+  /// The commands of the given plugin allowed on at least one window or origin, sorted and without the `plugin:{name}|` prefix.
+  ///
+  /// A command denied on the same origin it is allowed on is not included.
+  pub fn allowed_commands_for_plugin(&self, plugin: &str) -> Vec<&str> {
+    let prefix = format!("{PLUGIN_COMMAND_PREFIX}{plugin}{PLUGIN_COMMAND_SEPARATOR}");
+    let commands = self
+      .allowed_commands
+      .keys()
+      .filter(|key| !self.denied_commands.contains_key(key))
+      .filter_map(|key| key.name.strip_prefix(&prefix))
+      .collect::<BTreeSet<_>>();
+    commands.into_iter().collect()
+  }
+
+  /// Whether any command of the given plugin is allowed.
+  ///
+  /// Plugins can check this in their setup hook to skip initializing features the app cannot use.
+  pub fn plugin_has_any_allowed_command(&self, plugin: &str) -> bool {
+    !self.allowed_commands_for_plugin(plugin).is_empty()
+  }
+
+  /// Authorizes an IPC execution.
+  pub fn authorize(&self, request: &AccessRequest<'_>) -> AccessDecision<'_> {
+    // the grants of the active roles are checked after the grants of every user
+    let role_grants = self.roles.active().collect::<Vec<_>>();
+    self.decide(
+      request,
+      self.denied_commands.keys().chain(
+        role_grants
+          .iter()
+          .flat_map(|grant| grant.acl.denied_commands.keys()),
+      ),
+      self.allowed_commands.iter().chain(
+        role_grants
+          .iter()
+          .flat_map(|grant| &grant.acl.allowed_commands),
+      ),
+      |resolved| request.matches(self, resolved),
+    )
+  }
+
+  /// Decides on the request given the denied and allowed commands to check,
+  /// and whether an allowed command entry is granted to the calling webview.
+  fn decide<'a>(
+    &'a self,
+    request: &AccessRequest<'_>,
+    denied: impl IntoIterator<Item = &'a CommandKey>,
+    allowed: impl IntoIterator<Item = (&'a CommandKey, &'a ResolvedCommand)>,
+    mut matches: impl FnMut(&'a ResolvedCommand) -> bool,
+  ) -> AccessDecision<'a> {
+    // the remote lockdown prevails over every remote grant
+    if self.lockdown_remote && !matches!(request.origin, Origin::Local) {
+      return AccessDecision::Denied;
+    }
+
+    if denied
+      .into_iter()
+      .any(|cmd| cmd.name == request.command && request.origin.matches(&cmd.context))
+    {
+      AccessDecision::Denied
+    } else {
+      allowed
+        .into_iter()
+        // the origin and the webview must be granted together by the same entry,
+        // so an allowed origin loaded in another webview does not get its commands
+        .find(|(cmd, resolved)| {
+          cmd.name == request.command
+            && request.origin.matches(&cmd.context)
+            && matches(resolved)
+            && request.attestation_satisfied(&cmd.context)
+        })
+        .map_or(AccessDecision::NotAllowed, |(_cmd, resolved)| {
+          AccessDecision::Allowed(resolved)
+        })
+    }
   }
 }
 
+/// The denied and allowed entries of a command, looked up once for a batch, see [`RuntimeAuthority::authorize_all`].
+#[derive(Default)]
+struct BatchCommands<'a> {
+  denied: Vec<&'a CommandKey>,
+  allowed: Vec<(&'a CommandKey, &'a ResolvedCommand)>,
+}
+
+/// List of allowed and denied objects that match either the command-specific or plugin global scope criterias.
 #[derive(Debug)]
-pub struct ScopeManager {
-  command_scope: BTreeMap<ScopeKey, ResolvedScope>,
-  global_scope: BTreeMap<String, ResolvedScope>,
-  command_cache: BTreeMap<ScopeKey, TypeMap![Send + Sync]>,
-  global_scope_cache: TypeMap![Send + Sync],
+pub struct ScopeValue<T: ScopeObject> {
+  allow: Vec<T>,
+  deny: Vec<T>,
 }
 
-/// Marks a type as a scope object.
-///
-/// Usually you will just rely on [`serde::de::DeserializeOwned`] instead of implementing it manually,
-/// though this is useful if you need to do some initialization logic on the type itself.
-pub trait ScopeObject: Sized + Send + Sync + Debug + 'static {
-  /// The error type.
-  type Error: std::error::Error;
-  /// Deserialize the raw scope value.
-  fn deserialize<R: Runtime>(app: &AppHandle<R>, raw: Value) -> Result<Self, Self::Error>;
+impl<T: ScopeObject> ScopeValue<T> {
+  fn deserialize<R: Runtime>(app: &AppHandle<R>, scope: &ResolvedScope) -> crate::Result<Self> {
+    let deserialize = |raw: &Value| {
+      T::deserialize(app, raw.clone())
+        .map_err(|e| crate::Error::CannotDeserializeScope(Box::new(e)))
+    };
+    Ok(Self {
+      allow: scope
+        .allow
+        .iter()
+        .map(deserialize)
+        .collect::<crate::Result<_>>()?,
+      deny: scope
+        .deny
+        .iter()
+        .map(deserialize)
+        .collect::<crate::Result<_>>()?,
+    })
+  }
+
+  /// What this access scope allows.
+  pub fn allows(&self) -> &Vec<T> {
+    &self.allow
+  }
+
+  /// What this access scope denies.
+  pub fn denies(&self) -> &Vec<T> {
+    &self.deny
+  }
 }
 
-impl<T: Send + Sync + Debug + DeserializeOwned + 'static> ScopeObject for T {
-  type Error = serde_json::Error;
-  fn deserialize<R: Runtime>(_app: &AppHandle<R>, raw: Value) -> Result<Self, Self::Error> {
-    serde_json::from_value(raw.into())
+#[derive(Debug)]
+enum OwnedOrRef<'a, T: Debug> {
+  Owned(T),
+  Ref(&'a T),
+}
+
+impl<'a, T: Debug> Deref for OwnedOrRef<'a, T> {
+  type Target = T;
+  fn deref(&self) -> &Self::Target {
+    match self {
+      Self::Owned(t) => t,
+      Self::Ref(r) => r,
+    }
   }
 }
 
-impl ScopeManager {
-  pub(crate) fn get_global_scope_typed<R: Runtime, T: ScopeObject>(
-    &self,
-    app: &AppHandle<R>,
-    plugin: &str,
-  ) -> crate::Result<&ScopeValue<T>> {
-    match self.global_scope_cache.try_get() {
-      Some(cached) => Ok(cached),
-      None => {
-        let mut allow: Vec<T> = Vec::new();
-        let mut deny: Vec<T> = Vec::new();
-
-        if let Some(global_scope) = self.global_scope.get(plugin) {
-          for allowed in &global_scope.allow {
-            allow.push(
-              T::deserialize(app, allowed.clone())
-                .map_err(|e| crate::Error::CannotDeserializeScope(Box::new(e)))?,
-            );
-          }
-          for denied in &global_scope.deny {
-            deny.push(
-              T::deserialize(app, denied.clone())
-                .map_err(|e| crate::Error::CannotDeserializeScope(Box::new(e)))?,
-            );
+/// Access scope for a command that can be retrieved directly in the command function.
+#[derive(Debug)]
+pub struct CommandScope<'a, T: ScopeObject>(OwnedOrRef<'a, Arc<ScopeValue<T>>>);
+
+impl<'a, T: ScopeObject> CommandScope<'a, T> {
+  /// What this access scope allows.
+  pub fn allows(&self) -> &Vec<T> {
+    &self.0.allow
+  }
+
+  /// What this access scope denies.
+  pub fn denies(&self) -> &Vec<T> {
+    &self.0.deny
+  }
+
+  /// Creates a validator for the items the command discovers after its invocation,
+  /// such as the entries of a directory walk or the URLs of a feed streamed through a [`crate::ipc::Channel`].
+  ///
+  /// An item is allowed when it matches an allowed scope entry and no denied entry,
+  /// using the given function to match an entry against an item.
+  /// Combine it with [`ScopeValidator::and`] to also check scopes mutated at runtime,
+  /// e.g. with [`crate::scope::fs::Scope::validator`].
+  ///
+  /// # Examples
+  ///
+  /// A directory walker streaming the entries allowed by its scope:
+  ///
+  /// ```rust,no_run
+  /// use std::path::{Path, PathBuf};
+  /// use tauri::{command::CommandScope, ipc::Channel};
+  ///
+  /// #[derive(Debug, serde::Deserialize)]
+  /// struct Entry {
+  ///   path: PathBuf,
+  /// }
+  ///
+  /// #[tauri::command]
+  /// fn walk_dir(
+  ///   path: PathBuf,
+  ///   on_entry: Channel,
+  ///   scope: CommandScope<'_, Entry>,
+  /// ) -> Result<(), String> {
+  ///   let validator = scope.validator(|entry: &Entry, path: &Path| path.starts_with(&entry.path));
+  ///   std::thread::spawn(move || {
+  ///     let mut dirs = vec![path];
+  ///     while let Some(dir) = dirs.pop() {
+  ///       for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+  ///         let path = entry.path();
+  ///         // entries are discovered after the invocation, check each one
+  ///         if !validator.is_allowed(&path) {
+  ///           continue;
+  ///         }
+  ///         if path.is_dir() {
+  ///           dirs.push(path.clone());
+  ///         }
+  ///         let _ = on_entry.send(path.display().to_string());
+  ///       }
+  ///     }
+  ///   });
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn validator<I, F>(&self, matches: F) -> ScopeValidator<I>
+  where
+    I: ?Sized,
+    F: Fn(&T, &I) -> bool + Send + Sync + 'static,
+  {
+    let scope = Arc::clone(&self.0);
+    ScopeValidator::new(move |item| {
+      !scope.deny.iter().any(|entry| matches(entry, item))
+        && scope.allow.iter().any(|entry| matches(entry, item))
+    })
+  }
+}
+
+/// A cheap, clonable checker for the items a command discovers after its invocation,
+/// see [`CommandScope::validator`].
+pub struct ScopeValidator<I: ?Sized>(Arc<dyn Fn(&I) -> bool + Send + Sync>);
+
+impl<I: ?Sized> Clone for ScopeValidator<I> {
+  fn clone(&self) -> Self {
+    Self(Arc::clone(&self.0))
+  }
+}
+
+impl<I: ?Sized> Debug for ScopeValidator<I> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ScopeValidator").finish_non_exhaustive()
+  }
+}
+
+impl<I: ?Sized> ScopeValidator<I> {
+  /// Creates a validator from a function checking whether an item is allowed.
+  pub fn new<F: Fn(&I) -> bool + Send + Sync + 'static>(is_allowed: F) -> Self {
+    Self(Arc::new(is_allowed))
+  }
+
+  /// Whether the item is allowed.
+  pub fn is_allowed(&self, item: &I) -> bool {
+    (self.0)(item)
+  }
+
+  /// Creates a validator that only allows the items allowed by both validators.
+  #[must_use]
+  pub fn and(self, other: Self) -> Self
+  where
+    I: 'static,
+  {
+    Self::new(move |item| self.is_allowed(item) && other.is_allowed(item))
+  }
+}
+
+impl<'a, R: Runtime, T: ScopeObject> CommandArg<'a, R> for CommandScope<'a, T> {
+  /// Grabs the [`ResolvedScope`] from the [`CommandItem`] and returns the associated [`CommandScope`].
+  fn from_command(command: CommandItem<'a, R>) -> Result<Self, InvokeError> {
+    #[cfg(any(debug_assertions, feature = "acl-timing"))]
+    let start = std::time::Instant::now();
+    let webview = &command.message.webview;
+    let manager = webview.manager();
+    let scope_keys = command
+      .acl
+      .as_ref()
+      .map(|resolved| {
+        let window = webview.window();
+        let ancestors = manager.window.ancestors(window.label());
+        let mut keys = manager
+          .runtime_authority
+          .matched_label(window.label(), webview.label(), |label| {
+            resolved.matches_window(label, &ancestors)
+          })
+          .map(|label| resolved.window_scope_keys(label, &ancestors))
+          .unwrap_or_default();
+        for key in resolved.webview_scope_keys(window.label(), webview.label()) {
+          if !keys.contains(&key) {
+            keys.push(key);
           }
         }
+        keys
+      })
+      .unwrap_or_default();
+
+    let scope = manager
+      .runtime_authority
+      .scope_manager
+      .get_command_scopes_typed(webview.app_handle(), &scope_keys)
+      .map(CommandScope)
+      .map_err(InvokeError::from_error);
+
+    #[cfg(any(debug_assertions, feature = "acl-timing"))]
+    manager.runtime_authority.record_timing(
+      command.plugin,
+      &command.message.command,
+      super::AuthorizationPhase::Scope,
+      start.elapsed(),
+    );
 
-        let scope = ScopeValue { allow, deny };
-        let _ = self.global_scope_cache.set(scope);
-        Ok(self.global_scope_cache.get())
-      }
-    }
+    scope
   }
+}
 
-  fn get_command_scope_typed<R: Runtime, T: ScopeObject>(
-    &self,
-    app: &AppHandle<R>,
-    key: &ScopeKey,
-  ) -> crate::Result<&ScopeValue<T>> {
-    let cache = self.command_cache.get(key).unwrap();
-    match cache.try_get() {
-      Some(cached) => Ok(cached),
-      None => {
-        let resolved_scope = self
-          .command_scope
-          .get(key)
-          .unwrap_or_else(|| panic!("missing command scope for key {key}"));
-
-        let mut allow: Vec<T> = Vec::new();
-        let mut deny: Vec<T> = Vec::new();
-
-        for allowed in &resolved_scope.allow {
-          allow.push(
-            T::deserialize(app, allowed.clone())
-              .map_err(|e| crate::Error::CannotDeserializeScope(Box::new(e)))?,
-          );
-        }
-        for denied in &resolved_scope.deny {
-          deny.push(
-            T::deserialize(app, denied.clone())
-              .map_err(|e| crate::Error::CannotDeserializeScope(Box::new(e)))?,
-          );
-        }
+/// Global access scope that can be retrieved directly in the command function.
+///
+/// Only holds the global scope entries granted to the window that invoked the command,
+/// by the capabilities whose window selectors match it
+/// and at runtime with [`RuntimeAuthority::grant_ephemeral`].
+#[derive(Debug)]
+pub struct GlobalScope<'a, T: ScopeObject>(
+  OwnedOrRef<'a, Arc<ScopeValue<T>>>,
+  Vec<(Arc<EphemeralGrant>, T)>,
+  Vec<(&'a str, OwnedOrRef<'a, Arc<ScopeValue<T>>>)>,
+);
 
-        let value = ScopeValue { allow, deny };
+impl<'a, T: ScopeObject> GlobalScope<'a, T> {
+  /// What this access scope allows, the entries of the capabilities followed by the [`Self::ephemeral`] ones.
+  pub fn allows(&self) -> &Vec<T> {
+    &self.0.allow
+  }
 
-        let _ = cache.set(value);
-        Ok(cache.get())
-      }
+  /// The allowed entries of the capabilities, without the ones granted at runtime.
+  fn static_allows(&self) -> &[T] {
+    &self.0.allow[..self.0.allow.len() - self.1.len()]
+  }
+
+  /// What this access scope denies.
+  pub fn denies(&self) -> &Vec<T> {
+    &self.0.deny
+  }
+
+  /// The entries granted to the window at runtime that were usable when the command was invoked,
+  /// see [`RuntimeAuthority::grant_ephemeral`].
+  pub fn ephemeral(&self) -> impl Iterator<Item = &T> {
+    self.1.iter().map(|(_, entry)| entry)
+  }
+
+  /// Consumes a use of each entry granted at runtime, when the allowed entries are used as a whole
+  /// to build another scope instead of being checked with [`Self::is_allowed`].
+  pub(crate) fn consume_ephemeral(&self) {
+    for (grant, _) in &self.1 {
+      grant.consume();
     }
   }
+
+  /// Whether the item is allowed, using the given function to match an entry against an item.
+  ///
+  /// A denied entry always wins. Otherwise the item is allowed by an allowed entry,
+  /// or else by an entry granted at runtime, consuming one of its uses.
+  pub fn is_allowed<I: ?Sized>(&self, item: &I, matches: impl Fn(&T, &I) -> bool) -> bool {
+    if self.0.deny.iter().any(|entry| matches(entry, item)) {
+      return false;
+    }
+    self
+      .static_allows()
+      .iter()
+      .any(|entry| matches(entry, item))
+      || self
+        .1
+        .iter()
+        .any(|(grant, entry)| matches(entry, item) && grant.consume())
+  }
+
+  /// The global scope granted to the window for another plugin, e.g. the `fs` scope for a persistence plugin.
+  ///
+  /// Only available for the plugins listed in the `shares-scope-with` field of the plugin permission files,
+  /// see [`Manifest#structfield.shares_scope_with`](crate::utils::acl::plugin::Manifest#structfield.shares_scope_with).
+  /// The entries granted to the other plugin at runtime are not included.
+  pub fn shared(&self, plugin: &str) -> Option<&ScopeValue<T>> {
+    self
+      .2
+      .iter()
+      .find(|(shared, _)| *shared == plugin)
+      .map(|(_, scope)| &***scope)
+  }
+}
+
+impl<'a, R: Runtime, T: ScopeObject> CommandArg<'a, R> for GlobalScope<'a, T> {
+  /// Grabs the [`ResolvedScope`] from the [`CommandItem`] and returns the associated [`GlobalScope`].
+  fn from_command(command: CommandItem<'a, R>) -> Result<Self, InvokeError> {
+    let plugin = command.plugin.ok_or_else(|| {
+      InvokeError::from_anyhow(anyhow::anyhow!(
+        "global scope not available for app commands"
+      ))
+    })?;
+
+    #[cfg(any(debug_assertions, feature = "acl-timing"))]
+    let start = std::time::Instant::now();
+    let webview = &command.message.webview;
+    let authority = &webview.manager().runtime_authority;
+    let scope = authority
+      .webview_global_scope(webview, plugin, command.message.page.as_deref())
+      .map_err(InvokeError::from_error);
+
+    #[cfg(any(debug_assertions, feature = "acl-timing"))]
+    authority.record_timing(
+      Some(plugin),
+      &command.message.command,
+      super::AuthorizationPhase::Scope,
+      start.elapsed(),
+    );
+
+    scope
+  }
+}
+
+/// The configuration payloads the capabilities granting a command supply to the calling window,
+/// set with the `config` field of the capability permission entries.
+///
+/// Each capability granting the command to the window with a configuration yields an entry.
+/// The extraction fails if any of them cannot be deserialized into `T`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use tauri::command::CommandConfig;
+///
+/// #[derive(serde::Deserialize)]
+/// #[serde(rename_all = "kebab-case")]
+/// struct FetchConfig {
+///   user_agent_suffix: String,
+/// }
+///
+/// #[tauri::command]
+/// fn fetch(config: CommandConfig<FetchConfig>) -> String {
+///   let suffixes = config
+///     .configs()
+///     .map(|config| config.user_agent_suffix.as_str())
+///     .collect::<Vec<_>>();
+///   format!("tauri {}", suffixes.join(" "))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CommandConfig<T> {
+  entries: Vec<CommandConfigEntry<T>>,
+}
+
+/// A configuration payload supplied by a capability, see [`CommandConfig`].
+#[derive(Debug)]
+pub struct CommandConfigEntry<T> {
+  /// The identifier of the capability supplying the configuration.
+  pub capability: String,
+  /// The configuration.
+  pub config: T,
+}
+
+impl<T> CommandConfig<T> {
+  /// The configurations along with the capabilities supplying them, in capability identifier order.
+  pub fn entries(&self) -> &[CommandConfigEntry<T>] {
+    &self.entries
+  }
+
+  /// The configurations, in capability identifier order.
+  pub fn configs(&self) -> impl Iterator<Item = &T> {
+    self.entries.iter().map(|entry| &entry.config)
+  }
+
+  /// Whether no capability supplies a configuration to the calling window.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+}
+
+impl<'a, R: Runtime, T: DeserializeOwned> CommandArg<'a, R> for CommandConfig<T> {
+  /// Grabs the configurations of the [`ResolvedCommand`] that apply to the calling window.
+  fn from_command(command: CommandItem<'a, R>) -> Result<Self, InvokeError> {
+    let Some(resolved) = command.acl else {
+      return Ok(Self {
+        entries: Vec::new(),
+      });
+    };
+    let webview = &command.message.webview;
+    let window = webview.window();
+    let ancestors = webview.manager().window.ancestors(window.label());
+    let label =
+      webview
+        .manager()
+        .runtime_authority
+        .matched_label(window.label(), webview.label(), |label| {
+          resolved.matches_window(label, &ancestors)
+        });
+
+    resolved
+      .configs
+      .iter()
+      .filter(|config| {
+        label.is_some_and(|label| config.matches_window(label, &ancestors))
+          || config.matches_webview(window.label(), webview.label())
+      })
+      .map(|config| {
+        serde_json::from_value(config.config.clone().into())
+          .map(|value| CommandConfigEntry {
+            capability: config.capability.clone(),
+            config: value,
+          })
+          .map_err(|error| {
+            InvokeError::from_error(crate::Error::CannotDeserializeCommandConfig {
+              capability: config.capability.clone(),
+              error,
+            })
+          })
+      })
+      .collect::<Result<_, _>>()
+      .map(|entries| Self { entries })
+  }
+}
+
+/// Resolved scopes and the values they were deserialized into.
+///
+/// Scopes are deserialized once per scope and type, and the result is cached for the lifetime of the app
+/// since resolved scopes cannot be changed at runtime.
+/// Each cache is a [`TypeMap`] so plugins can deserialize the same scope into different types.
+#[derive(Debug)]
+pub struct ScopeManager {
+  command_scope: BTreeMap<ScopeKey, ResolvedScope>,
+  command_scope_shards: ScopeShards,
+  global_scope: BTreeMap<String, Vec<WindowGlobalScope>>,
+  /// The entries of [`Self::global_scope`] granted by a role grant, mapped to the index of the grant.
+  role_global_scopes: BTreeMap<String, BTreeMap<usize, usize>>,
+  /// The plugins whose global scopes each plugin reads, see [`GlobalScope::shared`].
+  shares_scope_with: BTreeMap<String, Vec<String>>,
+  command_cache: BTreeMap<ScopeKey, TypeMap![Send + Sync]>,
+  /// Cache of each entry of the global scopes, in the order of [`Self::global_scope`].
+  global_scope_cache: BTreeMap<String, Vec<TypeMap![Send + Sync]>>,
+  /// Cache of the global scopes merged across all windows, see [`Self::get_global_scope_typed`].
+  flattened_global_scope_cache: BTreeMap<String, TypeMap![Send + Sync]>,
+  /// Cache shared by the plugins that do not have a global scope.
+  empty_scope_cache: TypeMap![Send + Sync],
+  /// The values of the scopes stored out of the resolved ACL.
+  external_scopes: ExternalScopeLoader,
+}
+
+/// Loads the values of the scopes stored out of the resolved ACL,
+/// declared by the plugins with `external-scope = true` in their permission files.
+///
+/// Registered with [`crate::Builder::scope_provider`]. The values are loaded on the first use of their scope,
+/// verified against the content hash computed on resolution and cached for the lifetime of the app.
+///
+/// Closures taking the [`ExternalScope::key`] implement this trait.
+pub trait ScopeProvider: Send + Sync + 'static {
+  /// Loads the scope values stored with the given key.
+  fn load(&self, key: &str) -> Result<ResolvedScope, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+impl<F> ScopeProvider for F
+where
+  F: Fn(&str) -> Result<ResolvedScope, Box<dyn std::error::Error + Send + Sync>>
+    + Send
+    + Sync
+    + 'static,
+{
+  fn load(&self, key: &str) -> Result<ResolvedScope, Box<dyn std::error::Error + Send + Sync>> {
+    self(key)
+  }
+}
+
+/// A [`ScopeProvider`] loading the scopes from the compressed asset written by `tauri-build`
+/// to `$OUT_DIR/acl-external-scopes.br`, decompressed on the first load.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// tauri::Builder::default().scope_provider(tauri::command::EmbeddedScopeProvider::new(
+///   include_bytes!(concat!(env!("OUT_DIR"), "/acl-external-scopes.br")),
+/// ));
+/// ```
+#[cfg(feature = "compression")]
+pub struct EmbeddedScopeProvider {
+  bytes: &'static [u8],
+  scopes: std::sync::OnceLock<Result<tauri_utils::acl::tables::ExternalScopes, String>>,
+}
+
+#[cfg(feature = "compression")]
+impl EmbeddedScopeProvider {
+  /// Creates a provider loading the scopes from the embedded asset.
+  pub fn new(bytes: &'static [u8]) -> Self {
+    Self {
+      bytes,
+      scopes: Default::default(),
+    }
+  }
+}
+
+#[cfg(feature = "compression")]
+impl ScopeProvider for EmbeddedScopeProvider {
+  fn load(&self, key: &str) -> Result<ResolvedScope, Box<dyn std::error::Error + Send + Sync>> {
+    let scopes = self
+      .scopes
+      .get_or_init(|| {
+        tauri_utils::acl::tables::ExternalScopes::decompress(self.bytes).map_err(|e| e.to_string())
+      })
+      .as_ref()
+      .map_err(Clone::clone)?;
+    scopes
+      .get(key)
+      .cloned()
+      .ok_or_else(|| format!("no scope is stored with the key {key}").into())
+  }
+}
+
+/// The [`ScopeProvider`] of the app and the values it loaded.
+#[derive(Default)]
+struct ExternalScopeLoader {
+  provider: Option<Arc<dyn ScopeProvider>>,
+  loaded: Mutex<HashMap<String, Arc<ResolvedScope>>>,
+}
+
+impl Debug for ExternalScopeLoader {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ExternalScopeLoader")
+      .field("provider", &self.provider.is_some())
+      .field("loaded", &self.loaded.lock().unwrap().len())
+      .finish()
+  }
+}
+
+impl ExternalScopeLoader {
+  fn load(&self, external: &ExternalScope) -> crate::Result<Arc<ResolvedScope>> {
+    if let Some(scope) = self.loaded.lock().unwrap().get(&external.key) {
+      return Ok(scope.clone());
+    }
+
+    let error = |error: String| crate::Error::ExternalScope {
+      key: external.key.clone(),
+      error,
+    };
+    let provider = self
+      .provider
+      .as_ref()
+      .ok_or_else(|| crate::Error::MissingScopeProvider(external.key.clone()))?;
+    let scope = provider
+      .load(&external.key)
+      .map_err(|e| error(e.to_string()))?;
+    let hash = scope.content_hash();
+    if hash != external.hash {
+      return Err(error(format!(
+        "the content hash {hash} does not match the resolved hash {}",
+        external.hash
+      )));
+    }
+
+    let scope = Arc::new(scope);
+    self
+      .loaded
+      .lock()
+      .unwrap()
+      .insert(external.key.clone(), scope.clone());
+    Ok(scope)
+  }
+}
+
+/// Marks a type as a scope object.
+///
+/// Usually you will just rely on [`serde::de::DeserializeOwned`] instead of implementing it manually,
+/// though this is useful if you need to do some initialization logic on the type itself.
+pub trait ScopeObject: Sized + Send + Sync + Debug + 'static {
+  /// The error type.
+  type Error: std::error::Error;
+  /// Deserialize the raw scope value.
+  fn deserialize<R: Runtime>(app: &AppHandle<R>, raw: Value) -> Result<Self, Self::Error>;
+}
+
+impl<T: Send + Sync + Debug + DeserializeOwned + 'static> ScopeObject for T {
+  type Error = serde_json::Error;
+  fn deserialize<R: Runtime>(_app: &AppHandle<R>, raw: Value) -> Result<Self, Self::Error> {
+    serde_json::from_value(raw.into())
+  }
+}
+
+impl ScopeManager {
+  /// Gets the global scope of the plugin merged across all windows.
+  ///
+  /// Only used by [`PluginApi::scope`](crate::plugin::PluginApi::scope),
+  /// which predates the global scopes being narrowed to the windows of their capabilities.
+  /// The entries granted to user roles are not included, they are only applied per window.
+  pub(crate) fn get_global_scope_typed<R: Runtime, T: ScopeObject>(
+    &self,
+    app: &AppHandle<R>,
+    plugin: &str,
+  ) -> crate::Result<&ScopeValue<T>> {
+    let (Some(cache), Some(scopes)) = (
+      self.flattened_global_scope_cache.get(plugin),
+      self.global_scope.get(plugin),
+    ) else {
+      return self
+        .get_typed(app, &self.empty_scope_cache, &ResolvedScope::default())
+        .map(|scope| &**scope);
+    };
+    if cache.try_get::<Arc<ScopeValue<T>>>().is_none() {
+      let role_entries = self.role_global_scopes.get(plugin);
+      let flattened = self.merge(
+        scopes
+          .iter()
+          .enumerate()
+          .filter(|(index, _)| role_entries.map_or(true, |entries| !entries.contains_key(index)))
+          .map(|(_, window_scope)| &window_scope.scope),
+      )?;
+      let _ = cache.set(Arc::new(ScopeValue::<T>::deserialize(app, &flattened)?));
+    }
+    Ok(&**cache.get::<Arc<ScopeValue<T>>>())
+  }
+
+  /// Gets the merged entries of the global scope of the plugin that apply to the window that invoked the command.
+  ///
+  /// The entries of the role grants only apply while their grant is one of the given active grants.
+  fn get_window_global_scope_typed<R: Runtime, T: ScopeObject>(
+    &self,
+    app: &AppHandle<R>,
+    plugin: &str,
+    active_roles: &[usize],
+    matches: impl Fn(&WindowGlobalScope) -> bool,
+    runtime_scope: Option<&ResolvedScope>,
+  ) -> crate::Result<OwnedOrRef<'_, Arc<ScopeValue<T>>>> {
+    let scopes = self.global_scope.get(plugin).map_or(&[][..], Vec::as_slice);
+    let role_entries = self.role_global_scopes.get(plugin);
+    let indexes = scopes
+      .iter()
+      .enumerate()
+      .filter(|(index, _)| {
+        role_entries
+          .and_then(|entries| entries.get(index))
+          .map_or(true, |grant| active_roles.contains(grant))
+      })
+      .filter(|(_, scope)| matches(scope))
+      .map(|(index, _)| index)
+      .collect::<Vec<_>>();
+    if let Some(runtime_scope) = runtime_scope {
+      // the runtime entries change on each use, so the scope is not cached
+      return ScopeValue::deserialize(
+        app,
+        &self.merge(
+          indexes
+            .iter()
+            .map(|index| &scopes[*index].scope)
+            .chain(std::iter::once(runtime_scope)),
+        )?,
+      )
+      .map(|scope| OwnedOrRef::Owned(Arc::new(scope)));
+    }
+    match indexes.as_slice() {
+      [] => self
+        .get_typed(app, &self.empty_scope_cache, &ResolvedScope::default())
+        .map(OwnedOrRef::Ref),
+      [index] => self
+        .get_typed(
+          app,
+          &self.global_scope_cache[plugin][*index],
+          &scopes[*index].scope,
+        )
+        .map(OwnedOrRef::Ref),
+      // entries granted to the window by capabilities on different windows are merged on each use
+      indexes => ScopeValue::deserialize(
+        app,
+        &self.merge(indexes.iter().map(|index| &scopes[*index].scope))?,
+      )
+      .map(|scope| OwnedOrRef::Owned(Arc::new(scope))),
+    }
+  }
+
+  /// Gets the merged scopes of a command for the window that invoked it.
+  fn get_command_scopes_typed<R: Runtime, T: ScopeObject>(
+    &self,
+    app: &AppHandle<R>,
+    keys: &[ScopeKey],
+  ) -> crate::Result<OwnedOrRef<'_, Arc<ScopeValue<T>>>> {
+    match keys {
+      [] => Ok(OwnedOrRef::Owned(Arc::new(ScopeValue {
+        allow: Vec::new(),
+        deny: Vec::new(),
+      }))),
+      [key] => self.get_command_scope_typed(app, key).map(OwnedOrRef::Ref),
+      // scopes granted to the window by different capabilities are merged on each use
+      keys => ScopeValue::deserialize(
+        app,
+        &self.merge(keys.iter().map(|key| self.resolved_command_scope(key)))?,
+      )
+      .map(|scope| OwnedOrRef::Owned(Arc::new(scope))),
+    }
+  }
+
+  /// Gets a command scope, decoding its shard on the first access if it is lazily loaded.
+  fn command_scope(&self, key: &ScopeKey) -> Option<&ResolvedScope> {
+    self
+      .command_scope
+      .get(key)
+      .or_else(|| self.command_scope_shards.get(*key))
+  }
+
+  fn resolved_command_scope(&self, key: &ScopeKey) -> &ResolvedScope {
+    self
+      .command_scope(key)
+      .unwrap_or_else(|| panic!("missing command scope for key {key}"))
+  }
+
+  fn get_command_scope_typed<R: Runtime, T: ScopeObject>(
+    &self,
+    app: &AppHandle<R>,
+    key: &ScopeKey,
+  ) -> crate::Result<&Arc<ScopeValue<T>>> {
+    let resolved_scope = self.resolved_command_scope(key);
+    self.get_typed(app, self.command_cache.get(key).unwrap(), resolved_scope)
+  }
+
+  fn get_typed<'a, R: Runtime, T: ScopeObject>(
+    &self,
+    app: &AppHandle<R>,
+    cache: &'a TypeMap![Send + Sync],
+    scope: &ResolvedScope,
+  ) -> crate::Result<&'a Arc<ScopeValue<T>>> {
+    match cache.try_get() {
+      Some(cached) => Ok(cached),
+      None => {
+        let loaded = self.load(scope)?;
+        let scope = loaded.as_deref().unwrap_or(scope);
+        let _ = cache.set(Arc::new(ScopeValue::<T>::deserialize(app, scope)?));
+        Ok(cache.get())
+      }
+    }
+  }
+
+  /// Loads the values of the scope if they are stored out of the resolved ACL, see [`ScopeProvider`].
+  fn load(&self, scope: &ResolvedScope) -> crate::Result<Option<Arc<ResolvedScope>>> {
+    scope
+      .external
+      .as_ref()
+      .map(|external| self.external_scopes.load(external))
+      .transpose()
+  }
+
+  /// Merges the values of the scopes, loading the external ones.
+  fn merge<'a>(
+    &self,
+    scopes: impl IntoIterator<Item = &'a ResolvedScope>,
+  ) -> crate::Result<ResolvedScope> {
+    let mut merged = ResolvedScope::default();
+    for scope in scopes {
+      match self.load(scope)? {
+        Some(loaded) => merged += &*loaded,
+        None => merged += scope,
+      }
+    }
+    Ok(merged)
+  }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+  };
+
   use glob::Pattern;
   use tauri_utils::acl::{
-    resolved::{CommandKey, Resolved, ResolvedCommand},
-    ExecutionContext,
+    resolved::{
+      CommandKey, Resolved, ResolvedCommand, ResolvedScope, WebviewPattern, WindowAncestor,
+      WindowGlobalScope, WindowScope,
+    },
+    window::WindowPattern,
+    ExecutionContext, Value,
   };
 
   use crate::command::Origin;
 
-  use super::RuntimeAuthority;
+  use super::{
+    grants::{self, RuntimeGrants, TimedCapability},
+    matched_label, AccessDecision, AccessRequest, CommandScope, EphemeralPolicy, OwnedOrRef,
+    RuntimeAuthority, ScopeValue, WindowId,
+  };
+
+  #[derive(Debug, serde::Deserialize)]
+  struct PathEntry {
+    path: String,
+  }
+
+  fn path_scope(paths: impl IntoIterator<Item = String>) -> ResolvedScope {
+    ResolvedScope {
+      allow: paths
+        .into_iter()
+        .map(|path| Value::Map([("path".into(), Value::String(path))].into_iter().collect()))
+        .collect(),
+      deny: Vec::new(),
+      external: None,
+    }
+  }
+
+  fn global_scope(window: &str, path: &str) -> WindowGlobalScope {
+    WindowGlobalScope {
+      windows: vec![WindowPattern::new(window).unwrap()],
+      scope: path_scope([path.to_string()]),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn window_glob_pattern_matches() {
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Local,
+    };
+    let window = "main-*";
+
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![WindowPattern::new(window).unwrap()],
+      ..Default::default()
+    };
+    let allowed_commands = [(command.clone(), resolved_cmd.clone())]
+      .into_iter()
+      .collect();
+
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands,
+      ..Default::default()
+    });
+
+    assert_eq!(
+      authority.resolve_access(
+        &command.name,
+        &window.replace('*', "something"),
+        &[],
+        &Origin::Local
+      ),
+      Some(&resolved_cmd)
+    );
+  }
+
+  #[test]
+  fn remote_domain_matches() {
+    let domain = "tauri.app";
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Remote {
+        domain: Pattern::new(domain).unwrap(),
+        attestation: None,
+      },
+    };
+    let window = "main";
+
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![WindowPattern::new(window).unwrap()],
+      scopes: Vec::new(),
+      ..Default::default()
+    };
+    let allowed_commands = [(command.clone(), resolved_cmd.clone())]
+      .into_iter()
+      .collect();
+
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands,
+      ..Default::default()
+    });
+
+    assert_eq!(
+      authority.resolve_access(
+        &command.name,
+        window,
+        &[],
+        &Origin::Remote {
+          domain: domain.into(),
+          navigation: None,
+        }
+      ),
+      Some(&resolved_cmd)
+    );
+  }
+
+  #[test]
+  fn remote_domain_glob_pattern_matches() {
+    let domain = "tauri.*";
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Remote {
+        domain: Pattern::new(domain).unwrap(),
+        attestation: None,
+      },
+    };
+    let window = "main";
+
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![WindowPattern::new(window).unwrap()],
+      scopes: Vec::new(),
+      ..Default::default()
+    };
+    let allowed_commands = [(command.clone(), resolved_cmd.clone())]
+      .into_iter()
+      .collect();
+
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands,
+      ..Default::default()
+    });
+
+    assert_eq!(
+      authority.resolve_access(
+        &command.name,
+        window,
+        &[],
+        &Origin::Remote {
+          domain: domain.replace('*', "studio"),
+          navigation: None,
+        }
+      ),
+      Some(&resolved_cmd)
+    );
+  }
+
+  #[test]
+  fn remote_context_denied() {
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Local,
+    };
+    let window = "main";
+
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![WindowPattern::new(window).unwrap()],
+      scopes: Vec::new(),
+      ..Default::default()
+    };
+    let allowed_commands = [(command.clone(), resolved_cmd.clone())]
+      .into_iter()
+      .collect();
+
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands,
+      ..Default::default()
+    });
+
+    assert!(authority
+      .resolve_access(
+        &command.name,
+        window,
+        &[],
+        &Origin::Remote {
+          domain: "tauri.app".into(),
+          navigation: None,
+        }
+      )
+      .is_none());
+  }
+
+  #[test]
+  fn command_aliases() {
+    let authority = RuntimeAuthority::new(Resolved {
+      command_aliases: [(
+        "fs".to_string(),
+        [("readTextFile".to_string(), "read_text_file".to_string())].into(),
+      )]
+      .into(),
+      ..Default::default()
+    });
+
+    assert_eq!(
+      authority
+        .canonical_command("plugin:fs|readTextFile")
+        .as_deref(),
+      Some("plugin:fs|read_text_file")
+    );
+    assert!(authority
+      .canonical_command("plugin:fs|read_text_file")
+      .is_none());
+    assert!(authority.canonical_command("readTextFile").is_none());
+    assert!(authority.canonical_command("plugin:fs").is_none());
+  }
+
+  #[test]
+  fn remote_lockdown_rejects_remote_grants() {
+    let remote = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Remote {
+        domain: Pattern::new("tauri.app").unwrap(),
+        attestation: None,
+      },
+    };
+    let local = CommandKey {
+      context: ExecutionContext::Local,
+      ..remote.clone()
+    };
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![WindowPattern::new("main").unwrap()],
+      ..Default::default()
+    };
+
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [
+        (remote.clone(), resolved_cmd.clone()),
+        (local, resolved_cmd.clone()),
+      ]
+      .into_iter()
+      .collect(),
+      lockdown_remote: true,
+      ..Default::default()
+    });
+    assert!(authority.lockdown_remote());
+
+    let origin = Origin::Remote {
+      domain: "tauri.app".into(),
+      navigation: None,
+    };
+    assert!(authority
+      .resolve_access(&remote.name, "main", &[], &origin)
+      .is_none());
+    #[cfg(debug_assertions)]
+    assert_eq!(
+      authority.explain(&remote.name, "main", &[], &origin),
+      Some(super::Denial::RemoteLockdown)
+    );
+    assert_eq!(
+      authority.resolve_access(&remote.name, "main", &[], &Origin::Local),
+      Some(&resolved_cmd)
+    );
+
+    // the remote contexts get no global API function
+    let global_api = authority.global_api("main", "main", &[]);
+    assert_eq!(global_api.contexts.len(), 1);
+    assert!(global_api.contexts[0].remote.is_none());
+  }
+
+  #[test]
+  fn remote_attestation_fails_closed() {
+    use tauri_utils::acl::capability::{NavigationEvidence, RemoteAttestation, RequiredHeader};
+
+    let attestation = RemoteAttestation {
+      required_header: Some(RequiredHeader {
+        name: "X-Tauri-Attest".into(),
+        value: "token".into(),
+      }),
+      certificate_pin: None,
+    };
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Remote {
+        domain: Pattern::new("tauri.app").unwrap(),
+        attestation: Some(attestation),
+      },
+    };
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![WindowPattern::new("main").unwrap()],
+      ..Default::default()
+    };
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [(command.clone(), resolved_cmd.clone())]
+        .into_iter()
+        .collect(),
+      ..Default::default()
+    });
+    let origin = |headers: Option<Vec<(String, String)>>| Origin::Remote {
+      domain: "tauri.app".into(),
+      navigation: Some(NavigationEvidence {
+        headers,
+        certificate_pins: None,
+      }),
+    };
+
+    // the webview does not expose the response headers
+    let unverifiable = origin(None);
+    assert!(authority
+      .resolve_access(&command.name, "main", &[], &unverifiable)
+      .is_none());
+    #[cfg(debug_assertions)]
+    assert!(matches!(
+      authority.explain(&command.name, "main", &[], &unverifiable),
+      Some(super::Denial::AttestationNotVerified { .. })
+    ));
+
+    let missing_header = origin(Some(Vec::new()));
+    assert!(authority
+      .resolve_access(&command.name, "main", &[], &missing_header)
+      .is_none());
+
+    let attested = origin(Some(vec![("x-tauri-attest".into(), "token".into())]));
+    assert_eq!(
+      authority.resolve_access(&command.name, "main", &[], &attested),
+      Some(&resolved_cmd)
+    );
+  }
+
+  #[test]
+  fn denied_command_takes_precendence() {
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Local,
+    };
+    let window = "main";
+    let windows = vec![WindowPattern::new(window).unwrap()];
+    let allowed_commands = [(
+      command.clone(),
+      ResolvedCommand {
+        windows: windows.clone(),
+        ..Default::default()
+      },
+    )]
+    .into_iter()
+    .collect();
+    let denied_commands = [(
+      command.clone(),
+      ResolvedCommand {
+        windows: windows.clone(),
+        ..Default::default()
+      },
+    )]
+    .into_iter()
+    .collect();
+
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands,
+      denied_commands,
+      ..Default::default()
+    });
+
+    assert!(authority
+      .resolve_access(&command.name, window, &[], &Origin::Local)
+      .is_none());
+  }
+
+  #[cfg(debug_assertions)]
+  #[test]
+  fn explain_denials() {
+    use tauri_utils::acl::{
+      plugin::{Manifest, PermissionFile},
+      resolved::ResolvedCommandReference,
+    };
+
+    use super::Denial;
+
+    let key = |name: &str, context: ExecutionContext| CommandKey {
+      name: name.into(),
+      context,
+    };
+    let resolved_cmd = |window: &str, capability: &str| ResolvedCommand {
+      referenced_by: vec![ResolvedCommandReference {
+        capability: capability.into(),
+        permission: "fs:permission".into(),
+        delegated_by: None,
+      }],
+      windows: vec![WindowPattern::new(window).unwrap()],
+      ..Default::default()
+    };
+    let permissions: PermissionFile = serde_json::from_value(serde_json::json!({
+      "permission": [
+        { "identifier": "read-all", "commands": { "allow": ["read", "write"] } },
+        { "identifier": "allow-read", "commands": { "allow": ["read"] } },
+        { "identifier": "allow-write", "commands": { "allow": ["write"] } }
+      ]
+    }))
+    .unwrap();
+
+    let authority = RuntimeAuthority::new(Resolved {
+      acl: [("fs".to_string(), Manifest::new(vec![permissions], None))]
+        .into_iter()
+        .collect(),
+      allowed_commands: [
+        (
+          key("plugin:fs|write", ExecutionContext::Local),
+          resolved_cmd("main", "main"),
+        ),
+        (
+          key(
+            "plugin:fs|exists",
+            ExecutionContext::Remote {
+              domain: Pattern::new("tauri.app").unwrap(),
+              attestation: None,
+            },
+          ),
+          resolved_cmd("main", "remote"),
+        ),
+        (
+          key("plugin:fs|remove", ExecutionContext::Local),
+          resolved_cmd("main", "main"),
+        ),
+      ]
+      .into_iter()
+      .collect(),
+      denied_commands: [(
+        key("plugin:fs|remove", ExecutionContext::Local),
+        resolved_cmd("*", "deny-remove"),
+      )]
+      .into_iter()
+      .collect(),
+      ..Default::default()
+    });
+    let explain =
+      |command: &str, window: &str| authority.explain(command, window, &[], &Origin::Local);
+
+    assert_eq!(explain("plugin:fs|write", "main"), None);
+    assert_eq!(
+      explain("plugin:fs|read", "main"),
+      Some(Denial::NotGranted {
+        suggested_permissions: vec!["fs:allow-read".into(), "fs:read-all".into()]
+      })
+    );
+    assert_eq!(
+      explain("plugin:fs|write", "other"),
+      Some(Denial::WindowNotMatched {
+        windows: vec!["main".into()]
+      })
+    );
+    assert_eq!(
+      explain("plugin:fs|remove", "main"),
+      Some(Denial::Denied {
+        capabilities: vec!["deny-remove".into()]
+      })
+    );
+    assert_eq!(
+      explain("plugin:fs|exists", "main"),
+      Some(Denial::OriginNotMatched {
+        origins: vec!["remote: tauri.app".into()]
+      })
+    );
+    assert_eq!(
+      serde_json::to_value(explain("plugin:fs|write", "other")).unwrap(),
+      serde_json::json!({ "reason": "windowNotMatched", "windows": ["main"] })
+    );
+  }
+
+  #[test]
+  fn authorize_all_batch() {
+    let key = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Local,
+    };
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![WindowPattern::new("main").unwrap()],
+      ..Default::default()
+    };
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [
+        (key("allowed"), resolved_cmd.clone()),
+        (key("denied"), resolved_cmd.clone()),
+      ]
+      .into_iter()
+      .collect(),
+      denied_commands: [(key("denied"), resolved_cmd.clone())]
+        .into_iter()
+        .collect(),
+      ..Default::default()
+    });
+
+    let request = |command, window| AccessRequest {
+      command,
+      window,
+      webview: window,
+      ancestors: &[],
+      origin: &Origin::Local,
+      page: None,
+    };
+    let decisions = authority.authorize_all(&[
+      request("allowed", "main"),
+      request("denied", "main"),
+      request("allowed", "other"),
+      request("unknown", "main"),
+      request("allowed", "main"),
+    ]);
+    assert_eq!(
+      decisions,
+      [
+        AccessDecision::Allowed(&resolved_cmd),
+        AccessDecision::Denied,
+        AccessDecision::NotAllowed,
+        AccessDecision::NotAllowed,
+        AccessDecision::Allowed(&resolved_cmd),
+      ]
+    );
+    assert!(authority.authorize_all(&[]).is_empty());
+  }
+
+  #[test]
+  fn authorize_all_matches_authorize() {
+    let key = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Local,
+    };
+    let granted = |window: &str| ResolvedCommand {
+      windows: vec![WindowPattern::new(window).unwrap()],
+      ..Default::default()
+    };
+    let mut admin = Resolved::default();
+    admin.allowed_commands.insert(key("write"), granted("main"));
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [
+        (key("read"), granted("main")),
+        (key("write"), granted("editor-*")),
+        (key("remove"), granted("*")),
+      ]
+      .into_iter()
+      .collect(),
+      denied_commands: [(key("remove"), granted("other"))].into_iter().collect(),
+      role_grants: vec![tauri_utils::acl::resolved::RoleGrant {
+        capability: "admin".into(),
+        roles: vec!["admin".into()],
+        acl: admin,
+      }],
+      ..Default::default()
+    });
+
+    let origins = [
+      Origin::Local,
+      Origin::Remote {
+        domain: "tauri.app".into(),
+        navigation: None,
+      },
+    ];
+    let mut requests = Vec::new();
+    for command in ["read", "write", "remove", "unknown"] {
+      for window in ["main", "editor-1", "other"] {
+        for origin in &origins {
+          requests.push(AccessRequest {
+            command,
+            window,
+            webview: window,
+            ancestors: &[],
+            origin,
+            page: None,
+          });
+        }
+      }
+    }
+    // the batch keeps the order of the requests, including the repeated ones
+    requests.extend(requests.clone().into_iter().rev());
+
+    for roles in [&[] as &[&str], &["admin"], &["user"]] {
+      authority.set_roles(roles);
+      let decisions = authority.authorize_all(&requests);
+      let expected = requests
+        .iter()
+        .map(|request| authority.authorize(request))
+        .collect::<Vec<_>>();
+      assert_eq!(decisions, expected);
+      assert_eq!(
+        // `write` from the local `main` window
+        decisions[6].allowed().is_some(),
+        roles == ["admin"]
+      );
+    }
+  }
+
+  #[test]
+  fn capability_grants_expire() {
+    use super::super::clock::MockClock;
+
+    let key = CommandKey {
+      name: "export".into(),
+      context: ExecutionContext::Local,
+    };
+    let mut export = Resolved::builder()
+      .window_global_scope("fs", ["main"], path_scope(["$DOWNLOAD".to_string()]))
+      .unwrap()
+      .build();
+    export.allowed_commands.insert(
+      key.clone(),
+      ResolvedCommand {
+        windows: vec![WindowPattern::new("main").unwrap()],
+        ..Default::default()
+      },
+    );
+    let clock = MockClock::new();
+    let authority = RuntimeAuthority::new(Resolved {
+      role_grants: vec![tauri_utils::acl::resolved::RoleGrant {
+        capability: "export".into(),
+        roles: vec!["admin".into()],
+        acl: export,
+      }],
+      ..Default::default()
+    })
+    .with_clock(clock.clock());
+    let app = crate::test::mock_app();
+    let paths = || {
+      authority
+        .window_global_scope::<_, PathEntry>(app.handle(), "fs", WindowId::from(0), |scope| {
+          scope.matches_window("main", &[] as &[&str])
+        })
+        .unwrap()
+        .allows()
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<Vec<_>>()
+    };
+    let allowed = || {
+      authority
+        .resolve_access(&key.name, "main", &[], &Origin::Local)
+        .is_some()
+    };
+
+    assert!(!allowed());
+    assert!(matches!(
+      authority.add_capability_with_expiry("unknown", Duration::from_secs(60)),
+      Err(crate::Error::UnknownRuntimeCapability(_))
+    ));
+    authority
+      .add_capability_with_expiry("export", Duration::from_secs(60))
+      .unwrap();
+    assert!(allowed());
+    assert_eq!(paths(), [std::path::PathBuf::from("$DOWNLOAD")]);
+
+    // granting it again replaces the expiry
+    clock.advance(Duration::from_secs(50));
+    authority
+      .add_capability_with_expiry("export", Duration::from_secs(60))
+      .unwrap();
+    clock.advance(Duration::from_secs(50));
+    assert!(allowed());
+    assert!(authority.purge_expired().is_empty());
+
+    // the expired grant and its scope entries stop applying on the next check
+    clock.advance(Duration::from_secs(10));
+    assert!(!allowed());
+    assert!(paths().is_empty());
+    assert!(authority.purge_expired().is_empty());
+
+    // the grants of the active roles do not expire
+    authority.set_roles(&["admin"]);
+    authority
+      .add_capability_with_expiry("export", Duration::from_secs(60))
+      .unwrap();
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(authority.purge_expired(), ["export"]);
+    assert!(allowed());
+  }
+
+  #[test]
+  fn runtime_grants_round_trip() {
+    use super::super::clock::MockClock;
+
+    let role_grant = |capability: &str, role: &str| tauri_utils::acl::resolved::RoleGrant {
+      capability: capability.into(),
+      roles: vec![role.into()],
+      acl: Resolved::default(),
+    };
+    let clock = MockClock::new();
+    let authority = || {
+      RuntimeAuthority::new(Resolved {
+        role_grants: vec![role_grant("export", "admin"), role_grant("share", "editor")],
+        ..Default::default()
+      })
+      .with_clock(clock.clock())
+    };
+    let import_error = |result: crate::Result<Vec<String>>| match result {
+      Err(crate::Error::RuntimeGrantsImport { entry, .. }) => entry,
+      other => panic!("unexpected import result {other:?}"),
+    };
+
+    let exported = authority();
+    exported.set_roles(&["editor"]);
+    exported
+      .add_capability_with_expiry("export", Duration::from_secs(60))
+      .unwrap();
+    let blob = exported.export_runtime_grants(b"key");
+
+    let imported = authority();
+    assert!(imported
+      .import_runtime_grants(&blob, b"key")
+      .unwrap()
+      .is_empty());
+    assert_eq!(imported.roles(), ["editor"]);
+    assert_eq!(
+      imported.roles.timed(),
+      [(
+        "export".to_string(),
+        std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+      )]
+    );
+
+    // the blob is rejected as a whole when its signature does not match
+    let imported = authority();
+    assert_eq!(
+      import_error(imported.import_runtime_grants(&blob, b"other key")),
+      None
+    );
+    let tampered = String::from_utf8(blob.clone())
+      .unwrap()
+      .replace("editor", "admin")
+      .into_bytes();
+    assert_eq!(
+      import_error(imported.import_runtime_grants(&tampered, b"key")),
+      None
+    );
+    assert!(imported.roles().is_empty());
+
+    // signed entries are still checked against the capabilities compiled into the app
+    let unknown = grants::sign(
+      &RuntimeGrants {
+        roles: vec!["editor".into()],
+        capabilities: vec![TimedCapability::new(
+          "unknown".into(),
+          std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+        )],
+      },
+      b"key",
+    );
+    assert_eq!(
+      import_error(imported.import_runtime_grants(&unknown, b"key")).as_deref(),
+      Some("capability unknown")
+    );
+    assert!(imported.roles().is_empty());
+    let unknown = grants::sign(
+      &RuntimeGrants {
+        roles: vec!["owner".into()],
+        capabilities: Vec::new(),
+      },
+      b"key",
+    );
+    assert_eq!(
+      import_error(imported.import_runtime_grants(&unknown, b"key")).as_deref(),
+      Some("role owner")
+    );
+
+    // the grants that expired since the export are skipped
+    clock.advance(Duration::from_secs(60));
+    let imported = authority();
+    assert_eq!(
+      imported.import_runtime_grants(&blob, b"key").unwrap(),
+      ["export"]
+    );
+    assert!(imported.roles.timed().is_empty());
+    assert_eq!(imported.roles(), ["editor"]);
+  }
+
+  #[test]
+  fn window_ancestors_match() {
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Local,
+    };
+    let authority = |parent_only| {
+      let resolved_cmd = ResolvedCommand {
+        window_ancestors: vec![WindowAncestor {
+          pattern: WindowPattern::new("main").unwrap(),
+          parent_only,
+        }],
+        ..Default::default()
+      };
+      RuntimeAuthority::new(Resolved {
+        allowed_commands: [(command.clone(), resolved_cmd)].into_iter().collect(),
+        ..Default::default()
+      })
+    };
+    let chain = ["popup".to_string(), "main".to_string()];
+
+    let descendant_of = authority(false);
+    assert!(descendant_of
+      .resolve_access(&command.name, "main", &[], &Origin::Local)
+      .is_none());
+    assert!(descendant_of
+      .resolve_access(&command.name, "popup", &chain[1..], &Origin::Local)
+      .is_some());
+    assert!(descendant_of
+      .resolve_access(&command.name, "preview", &chain, &Origin::Local)
+      .is_some());
+
+    let parent_of = authority(true);
+    assert!(parent_of
+      .resolve_access(&command.name, "popup", &chain[1..], &Origin::Local)
+      .is_some());
+    assert!(parent_of
+      .resolve_access(&command.name, "preview", &chain, &Origin::Local)
+      .is_none());
+  }
+
+  #[test]
+  fn webview_window_matches_window_patterns() {
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Local,
+    };
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![WindowPattern::new("main").unwrap()],
+      ..Default::default()
+    };
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [(command.clone(), resolved_cmd)].into_iter().collect(),
+      ..Default::default()
+    });
+    let request = |label| AccessRequest {
+      command: &command.name,
+      window: label,
+      webview: label,
+      ancestors: &[],
+      origin: &Origin::Local,
+      page: None,
+    };
+
+    assert!(authority.authorize(&request("main")).allowed().is_some());
+    assert!(authority.authorize(&request("other")).allowed().is_none());
+    assert_eq!(request("main").matched_label(|l| l == "main"), Some("main"));
+    assert_eq!(request("other").matched_label(|l| l == "main"), None);
+  }
+
+  #[test]
+  fn multiwebview_window_matches_window_then_webview_label() {
+    let command = CommandKey {
+      name: "my-command".into(),
+      context: ExecutionContext::Local,
+    };
+    let resolved_cmd = ResolvedCommand {
+      windows: vec![
+        WindowPattern::new("main").unwrap(),
+        WindowPattern::new("left").unwrap(),
+      ],
+      ..Default::default()
+    };
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [(command.clone(), resolved_cmd)].into_iter().collect(),
+      ..Default::default()
+    });
+    let request = |window, webview| AccessRequest {
+      command: &command.name,
+      window,
+      webview,
+      ancestors: &[],
+      origin: &Origin::Local,
+      page: None,
+    };
+
+    // every webview of a matched window is granted the command
+    assert!(authority
+      .authorize(&request("main", "right"))
+      .allowed()
+      .is_some());
+    // the webview label is matched when the window label is not
+    assert!(authority
+      .authorize(&request("other", "left"))
+      .allowed()
+      .is_some());
+    assert!(authority
+      .authorize(&request("other", "right"))
+      .allowed()
+      .is_none());
+
+    // the window label takes precedence when both labels match
+    let both = |label: &str| label == "main" || label == "left";
+    assert_eq!(request("main", "left").matched_label(both), Some("main"));
+    assert_eq!(request("other", "left").matched_label(both), Some("left"));
+  }
 
   #[test]
-  fn window_glob_pattern_matches() {
-    let command = CommandKey {
-      name: "my-command".into(),
-      context: ExecutionContext::Local,
+  fn inheriting_webview_is_not_selected_by_window_label() {
+    let sidebar = WebviewPattern {
+      window: WindowPattern::new("main").unwrap(),
+      webview: WindowPattern::new("sidebar").unwrap(),
     };
-    let window = "main-*";
-
-    let resolved_cmd = ResolvedCommand {
-      windows: vec![Pattern::new(window).unwrap()],
-      ..Default::default()
+    let command = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Local,
     };
-    let allowed_commands = [(command.clone(), resolved_cmd.clone())]
-      .into_iter()
-      .collect();
-
     let authority = RuntimeAuthority::new(Resolved {
-      allowed_commands,
+      allowed_commands: [
+        // materialized on resolution for the inheriting webview
+        (
+          command("read"),
+          ResolvedCommand {
+            windows: vec![WindowPattern::new("main").unwrap()],
+            webviews: vec![sidebar.clone()],
+            ..Default::default()
+          },
+        ),
+        // granted to the window after the resolution, e.g. by a dynamic capability
+        (
+          command("write"),
+          ResolvedCommand {
+            windows: vec![WindowPattern::new("main").unwrap()],
+            ..Default::default()
+          },
+        ),
+      ]
+      .into_iter()
+      .collect(),
+      inheriting_webviews: vec![sidebar],
       ..Default::default()
     });
+    let request = |command, webview| AccessRequest {
+      command,
+      window: "main",
+      webview,
+      ancestors: &[],
+      origin: &Origin::Local,
+      page: None,
+    };
 
+    assert!(authority
+      .authorize(&request("read", "sidebar"))
+      .allowed()
+      .is_some());
+    assert!(authority
+      .authorize(&request("write", "sidebar"))
+      .allowed()
+      .is_none());
+    // the other webviews of the window are still selected by the window label
+    assert!(authority
+      .authorize(&request("write", "other"))
+      .allowed()
+      .is_some());
     assert_eq!(
-      authority.resolve_access(
-        &command.name,
-        &window.replace('*', "something"),
-        &Origin::Local
-      ),
-      Some(&resolved_cmd)
+      authority.matched_label("main", "sidebar", |label| label == "main"),
+      None
     );
   }
 
   #[test]
-  fn remote_domain_matches() {
-    let domain = "tauri.app";
+  fn remote_origin_is_granted_to_selected_webview_only() {
     let command = CommandKey {
       name: "my-command".into(),
       context: ExecutionContext::Remote {
-        domain: Pattern::new(domain).unwrap(),
+        domain: Pattern::new("partner.com").unwrap(),
+        attestation: None,
       },
     };
-    let window = "main";
+    let resolved_cmd = ResolvedCommand {
+      webviews: vec![WebviewPattern {
+        window: WindowPattern::new("*").unwrap(),
+        webview: WindowPattern::new("partner").unwrap(),
+      }],
+      ..Default::default()
+    };
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [(command.clone(), resolved_cmd)].into_iter().collect(),
+      ..Default::default()
+    });
+    let origin = Origin::Remote {
+      domain: "partner.com".into(),
+      navigation: None,
+    };
+    let request = |window, webview| AccessRequest {
+      command: &command.name,
+      window,
+      webview,
+      ancestors: &[],
+      origin: &origin,
+      page: None,
+    };
+
+    assert!(authority
+      .authorize(&request("main", "partner"))
+      .allowed()
+      .is_some());
+    assert!(authority
+      .authorize(&request("settings", "partner"))
+      .allowed()
+      .is_some());
+    // the same origin loaded in another webview of the window is not granted the command
+    assert!(authority
+      .authorize(&request("main", "preview"))
+      .allowed()
+      .is_none());
+    // nor is a window whose label is the selected webview label
+    assert!(authority
+      .authorize(&request("partner", "preview"))
+      .allowed()
+      .is_none());
+  }
 
+  #[test]
+  fn multiwebview_window_scopes_follow_matched_label() {
+    let window_scope = |window: &str, scope| WindowScope {
+      windows: vec![WindowPattern::new(window).unwrap()],
+      window_ancestors: Vec::new(),
+      webviews: Vec::new(),
+      pages: Vec::new(),
+      scope,
+    };
     let resolved_cmd = ResolvedCommand {
-      windows: vec![Pattern::new(window).unwrap()],
-      scope: None,
+      windows: vec![
+        WindowPattern::new("main").unwrap(),
+        WindowPattern::new("left").unwrap(),
+      ],
+      scopes: vec![window_scope("main", 1), window_scope("left", 2)],
       ..Default::default()
     };
-    let allowed_commands = [(command.clone(), resolved_cmd.clone())]
+    let scope_keys = |window, webview| {
+      matched_label(window, webview, |label| {
+        resolved_cmd.matches_window(label, &[] as &[&str])
+      })
+      .map(|label| resolved_cmd.window_scope_keys(label, &[] as &[&str]))
+      .unwrap_or_default()
+    };
+
+    assert_eq!(scope_keys("main", "main"), vec![1]);
+    assert_eq!(scope_keys("main", "left"), vec![1]);
+    assert_eq!(scope_keys("other", "left"), vec![2]);
+    assert!(scope_keys("other", "right").is_empty());
+  }
+
+  #[test]
+  fn global_scope_cache_is_per_plugin() {
+    let authority = RuntimeAuthority::new(Resolved {
+      global_scope: [
+        ("fs".to_string(), vec![global_scope("*", "$APPDATA")]),
+        ("sql".to_string(), vec![global_scope("*", "$APPCONFIG")]),
+      ]
       .into_iter()
-      .collect();
+      .collect(),
+      ..Default::default()
+    });
+    let app = crate::test::mock_app();
+
+    for (plugin, path) in [
+      ("fs", "$APPDATA"),
+      ("sql", "$APPCONFIG"),
+      ("fs", "$APPDATA"),
+    ] {
+      let scope = authority
+        .scope_manager
+        .get_global_scope_typed::<_, PathEntry>(app.handle(), plugin)
+        .unwrap();
+      assert_eq!(scope.allows()[0].path, path);
+    }
+    assert!(authority
+      .scope_manager
+      .get_global_scope_typed::<_, PathEntry>(app.handle(), "http")
+      .unwrap()
+      .allows()
+      .is_empty());
+  }
 
+  #[test]
+  fn global_scopes_are_picked_per_window() {
     let authority = RuntimeAuthority::new(Resolved {
-      allowed_commands,
+      global_scope: [(
+        "fs".to_string(),
+        vec![
+          global_scope("*", "$APPDATA"),
+          global_scope("admin", "$HOME"),
+        ],
+      )]
+      .into_iter()
+      .collect(),
+      ..Default::default()
+    });
+    let app = crate::test::mock_app();
+
+    let paths = |window: &str| {
+      authority
+        .scope_manager
+        .get_window_global_scope_typed::<_, PathEntry>(
+          app.handle(),
+          "fs",
+          &[],
+          |scope| scope.matches_window(window, &[] as &[&str]),
+          None,
+        )
+        .unwrap()
+        .allow
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(paths("main"), ["$APPDATA"]);
+    assert_eq!(paths("admin"), ["$APPDATA", "$HOME"]);
+
+    // the plugin-level accessor keeps returning the scope of every window
+    let flattened = authority
+      .scope_manager
+      .get_global_scope_typed::<_, PathEntry>(app.handle(), "fs")
+      .unwrap();
+    assert_eq!(flattened.allows().len(), 2);
+  }
+
+  #[test]
+  fn ephemeral_grants_follow_their_policy() {
+    let mut scope = global_scope("main", "/home/tauri/documents/");
+    scope.scope.deny = path_scope(["/home/tauri/.ssh/".to_string()]).allow;
+    let authority = RuntimeAuthority::new(Resolved {
+      global_scope: [("fs".to_string(), vec![scope])].into_iter().collect(),
       ..Default::default()
     });
+    let app = crate::test::mock_app();
+    let window_id = |window: &str| WindowId::from(if window == "main" { 0 } else { 1 });
+    let grant = |window: &str, path: &str, policy: EphemeralPolicy| {
+      authority.ephemeral.grant(
+        "fs",
+        window_id(window),
+        serde_json::json!({ "path": path }).into(),
+        policy,
+      )
+    };
+    let scope = |window: &str| {
+      authority
+        .window_global_scope::<_, PathEntry>(app.handle(), "fs", window_id(window), |scope| {
+          scope.matches_window(window, &[] as &[&str])
+        })
+        .unwrap()
+    };
+    let matches = |entry: &PathEntry, path: &str| path.starts_with(&entry.path);
+
+    grant(
+      "main",
+      "/home/tauri/report.pdf",
+      EphemeralPolicy::new().uses(2),
+    );
+    grant(
+      "main",
+      "/home/tauri/.ssh/id_ed25519",
+      EphemeralPolicy::new().uses(1),
+    );
+    grant(
+      "main",
+      "/tmp/",
+      EphemeralPolicy::new().expires_in(Duration::ZERO),
+    );
+    grant("other", "/etc/hosts", EphemeralPolicy::new());
 
+    let main = scope("main");
+    assert_eq!(main.ephemeral().count(), 2);
+    // the usable runtime entries follow the capability entries
     assert_eq!(
-      authority.resolve_access(
-        &command.name,
-        window,
-        &Origin::Remote {
-          domain: domain.into()
-        }
-      ),
-      Some(&resolved_cmd)
+      main
+        .allows()
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .collect::<Vec<_>>(),
+      [
+        "/home/tauri/documents/",
+        "/home/tauri/report.pdf",
+        "/home/tauri/.ssh/id_ed25519"
+      ]
+    );
+    // the capability entries do not consume the runtime grants
+    assert!(main.is_allowed("/home/tauri/documents/notes.txt", matches));
+    assert!(main.is_allowed("/home/tauri/report.pdf", matches));
+    assert!(main.is_allowed("/home/tauri/report.pdf", matches));
+    assert!(!main.is_allowed("/home/tauri/report.pdf", matches));
+    // a denied entry wins over a runtime grant, without consuming it
+    assert!(!main.is_allowed("/home/tauri/.ssh/id_ed25519", matches));
+    assert!(!main.is_allowed("/tmp/cache", matches));
+    assert!(!main.is_allowed("/etc/hosts", matches));
+
+    // the exhausted grant is dropped on the next invoke
+    let main = scope("main");
+    assert_eq!(
+      main
+        .ephemeral()
+        .map(|entry| entry.path.as_str())
+        .collect::<Vec<_>>(),
+      ["/home/tauri/.ssh/id_ed25519"]
     );
+
+    assert!(scope("other").is_allowed("/etc/hosts", matches));
+    authority.forget_ephemeral(window_id("other"));
+    assert!(!scope("other").is_allowed("/etc/hosts", matches));
+    assert_eq!(scope("other").allows().len(), 0);
   }
 
   #[test]
-  fn remote_domain_glob_pattern_matches() {
-    let domain = "tauri.*";
-    let command = CommandKey {
-      name: "my-command".into(),
-      context: ExecutionContext::Remote {
-        domain: Pattern::new(domain).unwrap(),
+  fn ephemeral_grants_extend_the_acl_scopes() {
+    let scope = WindowGlobalScope {
+      windows: vec![WindowPattern::new("main").unwrap()],
+      scope: ResolvedScope {
+        allow: vec![Value::String("https://docs.ourapp.com/*".into())],
+        deny: vec![Value::String("https://docs.ourapp.com/internal/*".into())],
+        external: None,
       },
-    };
-    let window = "main";
-
-    let resolved_cmd = ResolvedCommand {
-      windows: vec![Pattern::new(window).unwrap()],
-      scope: None,
       ..Default::default()
     };
-    let allowed_commands = [(command.clone(), resolved_cmd.clone())]
-      .into_iter()
-      .collect();
-
     let authority = RuntimeAuthority::new(Resolved {
-      allowed_commands,
+      global_scope: [("opener".to_string(), vec![scope])].into_iter().collect(),
       ..Default::default()
     });
+    let app = crate::test::mock_app();
+    let main = WindowId::from(0);
+    for url in [
+      "https://ourapp.com/report",
+      "https://docs.ourapp.com/internal/keys",
+    ] {
+      authority.ephemeral.grant(
+        "opener",
+        main,
+        Value::String(url.into()),
+        EphemeralPolicy::new().uses(1),
+      );
+    }
+    let url_scope = || {
+      let global = authority
+        .window_global_scope::<_, crate::scope::url::Entry>(app.handle(), "opener", main, |scope| {
+          scope.matches_window("main", &[] as &[&str])
+        })
+        .unwrap();
+      let command = CommandScope(OwnedOrRef::Owned(Arc::new(ScopeValue {
+        allow: Vec::new(),
+        deny: Vec::new(),
+      })));
+      crate::scope::url::Scope::from_acl(&command, &global).unwrap()
+    };
+
+    let scope = url_scope();
+    assert!(scope.check("https://ourapp.com/report").is_ok());
+    // the static deny still wins over a runtime grant
+    assert!(scope
+      .check("https://docs.ourapp.com/internal/keys")
+      .is_err());
+
+    // building the scope consumed the single use of the grants
+    let scope = url_scope();
+    assert!(scope.check("https://ourapp.com/report").is_err());
+    assert!(scope.check("https://docs.ourapp.com/guide").is_ok());
+  }
+
+  #[test]
+  fn shared_global_scopes_are_read_under_their_plugin() {
+    let authority = RuntimeAuthority::new(
+      Resolved::builder()
+        .window_global_scope("fs", ["main"], path_scope(["$APPDATA".to_string()]))
+        .unwrap()
+        .window_global_scope("fs", ["admin"], path_scope(["$HOME".to_string()]))
+        .unwrap()
+        .share_scope("store", "fs")
+        .build(),
+    );
+    let app = crate::test::mock_app();
+    let scope = |plugin: &str, window: &str| {
+      authority
+        .window_global_scope::<_, PathEntry>(app.handle(), plugin, WindowId::from(0), |scope| {
+          scope.matches_window(window, &[] as &[&str])
+        })
+        .unwrap()
+    };
+    let paths = |scope: &ScopeValue<PathEntry>| {
+      scope
+        .allows()
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<Vec<_>>()
+    };
 
+    let store = scope("store", "main");
+    assert!(store.allows().is_empty());
+    assert_eq!(paths(store.shared("fs").unwrap()), ["$APPDATA"]);
     assert_eq!(
-      authority.resolve_access(
-        &command.name,
-        window,
-        &Origin::Remote {
-          domain: domain.replace('*', "studio")
-        }
-      ),
-      Some(&resolved_cmd)
+      paths(scope("store", "admin").shared("fs").unwrap()),
+      ["$HOME"]
     );
+    assert!(store.shared("http").is_none());
+    // the sharing is not mutual
+    assert!(scope("fs", "main").shared("store").is_none());
+    assert!(!authority.scope_manager.global_scope.contains_key("store"));
   }
 
   #[test]
-  fn remote_context_denied() {
-    let command = CommandKey {
-      name: "my-command".into(),
-      context: ExecutionContext::Local,
-    };
-    let window = "main";
+  #[ignore = "benchmark"]
+  fn lookups_stay_within_budget() {
+    // only catches gross regressions, `benches/authority.rs` measures the actual cost
+    let mut builder = Resolved::builder();
+    for i in 0..1000 {
+      builder = builder
+        .allow_command(
+          format!("plugin:bench|allow_{i}"),
+          ExecutionContext::Local,
+          ["main"],
+        )
+        .unwrap()
+        .allow_command(
+          format!("plugin:bench|pool_{i}"),
+          ExecutionContext::Local,
+          ["pool-*"],
+        )
+        .unwrap();
+    }
+    let authority = RuntimeAuthority::new(builder.build());
+
+    let start = Instant::now();
+    for _ in 0..1000 {
+      assert!(authority
+        .resolve_access("plugin:bench|allow_999", "main", &[], &Origin::Local)
+        .is_some());
+      assert!(authority
+        .resolve_access("plugin:bench|pool_999", "pool-42", &[], &Origin::Local)
+        .is_some());
+      assert!(authority
+        .resolve_access("plugin:bench|missing", "main", &[], &Origin::Local)
+        .is_none());
+    }
+    let elapsed = start.elapsed();
+    assert!(
+      elapsed < Duration::from_secs(5),
+      "3000 lookups in an ACL of 2000 commands took {elapsed:?}"
+    );
+  }
+
+  #[test]
+  fn memory_stats_include_runtime_state() {
+    let authority = RuntimeAuthority::new(Resolved {
+      global_scope: [("fs".to_string(), vec![global_scope("main", "$APPDATA")])]
+        .into_iter()
+        .collect(),
+      ..Default::default()
+    });
+    let app = crate::test::mock_app();
+
+    let before = authority.memory_stats();
+    assert_eq!(before.acl.global_scope["fs"].count, 1);
+    assert_eq!(before.cached_scopes, 0);
+    assert_eq!(before.ephemeral_grants.count, 0);
+
+    authority.ephemeral.grant(
+      "fs",
+      WindowId::from(0),
+      serde_json::json!({ "path": "/home/tauri/report.pdf" }).into(),
+      EphemeralPolicy::new(),
+    );
+    authority
+      .scope_manager
+      .get_window_global_scope_typed::<_, PathEntry>(app.handle(), "fs", &[], |_| true, None)
+      .unwrap();
+
+    let after = authority.memory_stats();
+    assert_eq!(after.cached_scopes, 1);
+    assert_eq!(after.ephemeral_grants.count, 1);
+    assert!(after.total_bytes() > before.total_bytes());
+  }
 
+  #[test]
+  fn command_scopes_are_picked_per_window() {
+    let window_scope = |window: &str, scope| WindowScope {
+      windows: vec![WindowPattern::new(window).unwrap()],
+      window_ancestors: Vec::new(),
+      webviews: Vec::new(),
+      pages: Vec::new(),
+      scope,
+    };
     let resolved_cmd = ResolvedCommand {
-      windows: vec![Pattern::new(window).unwrap()],
-      scope: None,
+      windows: vec![
+        WindowPattern::new("editor-*").unwrap(),
+        WindowPattern::new("importer").unwrap(),
+      ],
+      scopes: vec![
+        window_scope("editor-*", 1),
+        window_scope("importer", 2),
+        window_scope("*", 3),
+      ],
       ..Default::default()
     };
-    let allowed_commands = [(command.clone(), resolved_cmd.clone())]
+    let authority = RuntimeAuthority::new(Resolved {
+      command_scope: [
+        (1, path_scope(["$DOC/**".into()])),
+        (2, path_scope(["$TMP/**".into()])),
+        (3, path_scope(["$APPDATA/**".into()])),
+      ]
       .into_iter()
+      .collect(),
+      ..Default::default()
+    });
+    let app = crate::test::mock_app();
+
+    for (window, paths) in [
+      ("editor-1", vec!["$DOC/**", "$APPDATA/**"]),
+      ("importer", vec!["$TMP/**", "$APPDATA/**"]),
+      ("other", vec!["$APPDATA/**"]),
+    ] {
+      let keys = resolved_cmd.window_scope_keys(window, &[] as &[&str]);
+      let scope = authority
+        .scope_manager
+        .get_command_scopes_typed::<_, PathEntry>(app.handle(), &keys)
+        .unwrap();
+      assert_eq!(
+        scope.allows().iter().map(|e| &e.path).collect::<Vec<_>>(),
+        paths
+      );
+    }
+  }
+
+  #[test]
+  fn lazy_command_scopes_are_decoded_on_use() {
+    use tauri_utils::acl::tables::ScopeShards;
+
+    let command_scope = (0..4)
+      .map(|key| (key, path_scope([format!("$APPDATA/{key}")])))
       .collect();
+    let (keys, shards) = ScopeShards::encode(&command_scope).unwrap();
+    let command = CommandKey {
+      name: "plugin:fs|read".into(),
+      context: ExecutionContext::Local,
+    };
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [(
+        command.clone(),
+        ResolvedCommand {
+          windows: vec![WindowPattern::new("main").unwrap()],
+          scopes: vec![WindowScope {
+            windows: vec![WindowPattern::new("main").unwrap()],
+            window_ancestors: Vec::new(),
+            webviews: Vec::new(),
+            pages: Vec::new(),
+            scope: 2,
+          }],
+          ..Default::default()
+        },
+      )]
+      .into_iter()
+      .collect(),
+      command_scope_shards: ScopeShards::new(keys, shards),
+      ..Default::default()
+    });
+    let app = crate::test::mock_app();
+
+    assert!(authority.has_lazy_scopes());
+    authority.prewarm_scopes("main");
+    let scope = authority
+      .scope_manager
+      .get_command_scopes_typed::<_, PathEntry>(app.handle(), &[2])
+      .unwrap();
+    assert_eq!(scope.allows()[0].path, "$APPDATA/2");
+  }
+
+  #[test]
+  fn external_scopes_are_loaded_through_the_provider() {
+    use tauri_utils::acl::resolved::ExternalScope;
+
+    let external = |scope: &ResolvedScope| ResolvedScope {
+      external: Some(ExternalScope {
+        key: "fs/scope".into(),
+        hash: scope.content_hash(),
+      }),
+      ..Default::default()
+    };
+    let loaded = path_scope(["$APPDATA/**".into()]);
+    let authority = |stored: ResolvedScope| {
+      let provided = loaded.clone();
+      RuntimeAuthority::new(Resolved {
+        command_scope: [(0, external(&stored))].into_iter().collect(),
+        ..Default::default()
+      })
+      .with_scope_provider(Some(Arc::new(
+        move |key: &str| -> Result<ResolvedScope, Box<dyn std::error::Error + Send + Sync>> {
+          assert_eq!(key, "fs/scope");
+          Ok(provided.clone())
+        },
+      )))
+    };
+    let app = crate::test::mock_app();
+
+    let valid = authority(loaded.clone());
+    let scope = valid
+      .scope_manager
+      .get_command_scopes_typed::<_, PathEntry>(app.handle(), &[0])
+      .unwrap();
+    assert_eq!(scope.allows()[0].path, "$APPDATA/**");
+
+    let error = authority(path_scope(["$HOME/**".into()]))
+      .scope_manager
+      .get_command_scopes_typed::<_, PathEntry>(app.handle(), &[0])
+      .unwrap_err();
+    assert!(matches!(error, crate::Error::ExternalScope { ref key, .. } if key == "fs/scope"));
+    assert!(error.to_string().contains("does not match"));
+
+    let error = RuntimeAuthority::new(Resolved {
+      command_scope: [(0, external(&loaded))].into_iter().collect(),
+      ..Default::default()
+    })
+    .scope_manager
+    .get_command_scopes_typed::<_, PathEntry>(app.handle(), &[0])
+    .unwrap_err();
+    assert!(matches!(error, crate::Error::MissingScopeProvider(_)));
+  }
+
+  /// Compares deserializing a 500-entry scope on each invoke with the cached value.
+  ///
+  /// Run with `cargo test --lib scope_cache_benchmark -- --ignored --nocapture`.
+  #[test]
+  #[ignore = "benchmark"]
+  fn scope_cache_benchmark() {
+    const ENTRIES: usize = 500;
+    const INVOCATIONS: u32 = 10_000;
 
+    let key = 0;
+    let scope = path_scope((0..ENTRIES).map(|i| format!("$APPDATA/{i}/**")));
     let authority = RuntimeAuthority::new(Resolved {
-      allowed_commands,
+      command_scope: [(key, scope.clone())].into_iter().collect(),
       ..Default::default()
     });
+    let app = crate::test::mock_app();
 
-    assert!(authority
-      .resolve_access(
-        &command.name,
-        window,
-        &Origin::Remote {
-          domain: "tauri.app".into()
-        }
-      )
-      .is_none());
+    let start = Instant::now();
+    for _ in 0..INVOCATIONS {
+      ScopeValue::<PathEntry>::deserialize(app.handle(), &scope).unwrap();
+    }
+    let uncached = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..INVOCATIONS {
+      authority
+        .scope_manager
+        .get_command_scope_typed::<_, PathEntry>(app.handle(), &key)
+        .unwrap();
+    }
+    let cached = start.elapsed();
+
+    println!(
+      "{INVOCATIONS} invokes of a {ENTRIES}-entry scope: {:?}/invoke uncached, {:?}/invoke cached",
+      uncached / INVOCATIONS,
+      cached / INVOCATIONS
+    );
+    assert!(cached < uncached);
   }
 
   #[test]
-  fn denied_command_takes_precendence() {
-    let command = CommandKey {
-      name: "my-command".into(),
+  fn scope_validator_checks_streamed_items() {
+    let entry = |path: &str| PathEntry { path: path.into() };
+    let scope = CommandScope(OwnedOrRef::Owned(Arc::new(ScopeValue {
+      allow: vec![entry("/home/tauri/"), entry("/tmp/")],
+      deny: vec![entry("/home/tauri/.ssh/")],
+    })));
+    let validator = scope.validator(|entry: &PathEntry, path: &str| path.starts_with(&entry.path));
+    drop(scope);
+
+    let items = [
+      "/home/tauri/notes.txt",
+      "/home/tauri/.ssh/id_ed25519",
+      "/etc/passwd",
+      "/tmp/cache",
+    ];
+    let handle = {
+      let validator = validator.clone();
+      std::thread::spawn(move || {
+        items
+          .into_iter()
+          .filter(|item| validator.is_allowed(item))
+          .collect::<Vec<_>>()
+      })
+    };
+    assert_eq!(
+      handle.join().unwrap(),
+      ["/home/tauri/notes.txt", "/tmp/cache"]
+    );
+
+    let no_tmp = validator.and(super::ScopeValidator::new(|path: &str| {
+      !path.starts_with("/tmp/")
+    }));
+    assert!(no_tmp.is_allowed("/home/tauri/notes.txt"));
+    assert!(!no_tmp.is_allowed("/tmp/cache"));
+  }
+
+  #[test]
+  fn allowed_commands_for_plugin() {
+    let local = |name: &str| CommandKey {
+      name: name.into(),
       context: ExecutionContext::Local,
     };
-    let window = "main";
-    let windows = vec![Pattern::new(window).unwrap()];
-    let allowed_commands = [(
-      command.clone(),
-      ResolvedCommand {
-        windows: windows.clone(),
-        ..Default::default()
-      },
-    )]
-    .into_iter()
-    .collect();
-    let denied_commands = [(
-      command.clone(),
-      ResolvedCommand {
-        windows: windows.clone(),
-        ..Default::default()
+    let remote = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Remote {
+        domain: Pattern::new("tauri.app").unwrap(),
+        attestation: None,
       },
-    )]
-    .into_iter()
-    .collect();
+    };
+    let authority = RuntimeAuthority::new(Resolved {
+      allowed_commands: [
+        local("plugin:fs|write"),
+        local("plugin:fs|read"),
+        remote("plugin:fs|read"),
+        remote("plugin:fs|remove"),
+        local("plugin:fs-extra|copy"),
+        local("plugin:shell|open"),
+      ]
+      .into_iter()
+      .map(|key| (key, ResolvedCommand::default()))
+      .collect(),
+      denied_commands: [(local("plugin:shell|open"), ResolvedCommand::default())]
+        .into_iter()
+        .collect(),
+      ..Default::default()
+    });
+
+    assert_eq!(
+      authority.allowed_commands_for_plugin("fs"),
+      ["read", "remove", "write"]
+    );
+    assert!(authority.plugin_has_any_allowed_command("fs-extra"));
+    assert!(!authority.plugin_has_any_allowed_command("shell"));
+    assert!(!authority.plugin_has_any_allowed_command("dialog"));
+  }
 
+  #[test]
+  fn global_api_contexts() {
+    let command = |name: &str, context, window: &str| {
+      (
+        CommandKey {
+          name: name.into(),
+          context,
+        },
+        ResolvedCommand {
+          windows: vec![WindowPattern::new(window).unwrap()],
+          ..Default::default()
+        },
+      )
+    };
+    let remote = || ExecutionContext::Remote {
+      domain: Pattern::new("*.tauri.app").unwrap(),
+      attestation: None,
+    };
     let authority = RuntimeAuthority::new(Resolved {
-      allowed_commands,
-      denied_commands,
+      allowed_commands: [
+        command("plugin:app|name", ExecutionContext::Local, "main"),
+        command("plugin:app|version", ExecutionContext::Local, "*"),
+        command("plugin:app|app_hide", ExecutionContext::Local, "settings"),
+        command("plugin:path|join", remote(), "main"),
+      ]
+      .into_iter()
+      .collect(),
+      denied_commands: [command("plugin:app|version", remote(), "other")]
+        .into_iter()
+        .collect(),
+      global_api: [(
+        "app".to_string(),
+        [("getName".to_string(), "name".to_string())].into(),
+      )]
+      .into(),
       ..Default::default()
     });
 
-    assert!(authority
-      .resolve_access(&command.name, window, &Origin::Local)
+    assert_eq!(
+      serde_json::to_value(authority.global_api("main", "main", &[])).unwrap(),
+      serde_json::json!({
+        "contexts": [
+          { "remote": null, "allow": ["plugin:app|name", "plugin:app|version"], "deny": [] },
+          { "remote": "*.tauri.app", "allow": ["plugin:path|join"], "deny": ["plugin:app|version"] },
+        ],
+        "functions": { "app": { "getName": "name" } },
+      })
+    );
+  }
+
+  #[test]
+  fn plugin_setup_skips_heavy_init_without_grants() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::{plugin::Builder, Manager};
+
+    let initialized = Arc::new(AtomicBool::new(false));
+    let initialized_ = initialized.clone();
+    let app = crate::test::mock_builder()
+      .plugin(
+        Builder::<_, ()>::new("indexer")
+          .setup(move |app, _api| {
+            if app.acl().plugin_has_any_allowed_command("indexer") {
+              initialized_.store(true, Ordering::SeqCst);
+            }
+            Ok(())
+          })
+          .build(),
+      )
+      .build(crate::test::mock_context(crate::test::noop_assets()))
+      .unwrap();
+
+    assert!(app.acl().allowed_commands_for_plugin("indexer").is_empty());
+    assert!(!initialized.load(Ordering::SeqCst));
+  }
+
+  #[test]
+  fn runtime_resolved_acl() {
+    use crate::Manager;
+
+    let mut context = crate::test::mock_context(crate::test::noop_assets());
+    *context.resolved_acl_mut() = Resolved::builder()
+      .allow_command_scoped(
+        "plugin:fs|read",
+        ExecutionContext::Local,
+        ["main"],
+        ResolvedScope {
+          allow: vec![Value::String("$HOME".into())],
+          deny: Vec::new(),
+          external: None,
+        },
+      )
+      .unwrap()
+      .deny_command("plugin:fs|read", ExecutionContext::Local, ["main"])
+      .unwrap()
+      .allow_command("plugin:fs|write", ExecutionContext::Local, ["main"])
+      .unwrap()
+      .build();
+    let app = crate::test::mock_builder().build(context).unwrap();
+
+    assert_eq!(app.acl().allowed_commands_for_plugin("fs"), ["write"]);
+    assert!(app
+      .acl()
+      .resolve_access("plugin:fs|write", "main", &[], &Origin::Local)
+      .is_some());
+    assert!(app
+      .acl()
+      .resolve_access("plugin:fs|write", "other", &[], &Origin::Local)
       .is_none());
   }
 }
@@ -0,0 +1,51 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{fmt, sync::Arc, time::SystemTime};
+
+/// The wall clock the expiring capability grants are checked against,
+/// see [`RuntimeAuthority::add_capability_with_expiry`](super::RuntimeAuthority::add_capability_with_expiry).
+///
+/// The expiries are absolute so they keep their meaning once exported.
+#[derive(Clone)]
+pub(crate) struct Clock(Arc<dyn Fn() -> SystemTime + Send + Sync>);
+
+impl Clock {
+  pub(crate) fn now(&self) -> SystemTime {
+    (self.0)()
+  }
+}
+
+impl Default for Clock {
+  fn default() -> Self {
+    Self(Arc::new(SystemTime::now))
+  }
+}
+
+impl fmt::Debug for Clock {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("Clock").finish()
+  }
+}
+
+/// A clock only moving forward when the test advances it.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub(crate) struct MockClock(Arc<std::sync::Mutex<SystemTime>>);
+
+#[cfg(test)]
+impl MockClock {
+  pub(crate) fn new() -> Self {
+    Self(Arc::new(std::sync::Mutex::new(SystemTime::UNIX_EPOCH)))
+  }
+
+  pub(crate) fn advance(&self, duration: std::time::Duration) {
+    *self.0.lock().unwrap() += duration;
+  }
+
+  pub(crate) fn clock(&self) -> Clock {
+    let now = self.0.clone();
+    Clock(Arc::new(move || *now.lock().unwrap()))
+  }
+}
@@ -0,0 +1,140 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+  sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Mutex,
+  },
+  time::{Duration, Instant},
+};
+
+use tauri_runtime::window::WindowId;
+use tauri_utils::acl::{
+  memory::{HeapSize, HeapStats},
+  Value,
+};
+
+/// How long a scope entry granted with [`RuntimeAuthority::grant_ephemeral`](super::RuntimeAuthority::grant_ephemeral)
+/// stays usable.
+///
+/// The entry is always dropped when its window is destroyed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EphemeralPolicy {
+  uses: Option<u32>,
+  ttl: Option<Duration>,
+}
+
+impl EphemeralPolicy {
+  /// A policy keeping the entry until its window is destroyed.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Drops the entry once it allowed the given number of items,
+  /// see [`GlobalScope::is_allowed`](super::GlobalScope::is_allowed).
+  #[must_use]
+  pub fn uses(mut self, uses: u32) -> Self {
+    self.uses.replace(uses);
+    self
+  }
+
+  /// Drops the entry once the given duration elapsed.
+  #[must_use]
+  pub fn expires_in(mut self, ttl: Duration) -> Self {
+    self.ttl.replace(ttl);
+    self
+  }
+}
+
+/// A scope entry granted to a window at runtime.
+#[derive(Debug)]
+pub(crate) struct EphemeralGrant {
+  plugin: String,
+  window: WindowId,
+  pub(crate) value: Value,
+  remaining: Option<AtomicU32>,
+  expires_at: Option<Instant>,
+}
+
+impl EphemeralGrant {
+  fn is_expired(&self) -> bool {
+    self
+      .expires_at
+      .is_some_and(|expires_at| Instant::now() >= expires_at)
+  }
+
+  fn is_usable(&self) -> bool {
+    !self.is_expired()
+      && self
+        .remaining
+        .as_ref()
+        .map_or(true, |remaining| remaining.load(Ordering::SeqCst) > 0)
+  }
+
+  /// Consumes a use of the entry, returns `false` when it is exhausted or expired.
+  pub(crate) fn consume(&self) -> bool {
+    !self.is_expired()
+      && self.remaining.as_ref().map_or(true, |remaining| {
+        remaining
+          .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |uses| {
+            uses.checked_sub(1)
+          })
+          .is_ok()
+      })
+  }
+}
+
+/// The scope entries granted to the windows at runtime.
+#[derive(Debug, Default)]
+pub(crate) struct EphemeralGrants(Mutex<Vec<Arc<EphemeralGrant>>>);
+
+impl EphemeralGrants {
+  pub(crate) fn grant(
+    &self,
+    plugin: &str,
+    window: WindowId,
+    value: Value,
+    policy: EphemeralPolicy,
+  ) {
+    self.0.lock().unwrap().push(Arc::new(EphemeralGrant {
+      plugin: plugin.into(),
+      window,
+      value,
+      remaining: policy.uses.map(AtomicU32::new),
+      expires_at: policy.ttl.map(|ttl| Instant::now() + ttl),
+    }));
+  }
+
+  /// The usable entries of the plugin granted to the window, dropping the exhausted and expired ones.
+  pub(crate) fn get(&self, plugin: &str, window: WindowId) -> Vec<Arc<EphemeralGrant>> {
+    let mut grants = self.0.lock().unwrap();
+    grants.retain(|grant| grant.is_usable());
+    grants
+      .iter()
+      .filter(|grant| grant.plugin == plugin && grant.window == window)
+      .cloned()
+      .collect()
+  }
+
+  /// The number and estimated heap size of the entries.
+  pub(crate) fn heap_stats(&self) -> HeapStats {
+    let mut stats = HeapStats::default();
+    for grant in self.0.lock().unwrap().iter() {
+      stats.add(
+        std::mem::size_of::<EphemeralGrant>() + grant.plugin.heap_size() + grant.value.heap_size(),
+      );
+    }
+    stats
+  }
+
+  /// Drops the entries granted to the window.
+  pub(crate) fn forget(&self, window: WindowId) {
+    self
+      .0
+      .lock()
+      .unwrap()
+      .retain(|grant| grant.window != window);
+  }
+}
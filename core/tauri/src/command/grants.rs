@@ -0,0 +1,160 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The current version of the exported runtime grants.
+const VERSION: u32 = 1;
+
+/// The grants applied at runtime that survive an app restart,
+/// see [`RuntimeAuthority::export_runtime_grants`](super::RuntimeAuthority::export_runtime_grants).
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RuntimeGrants {
+  /// The active user roles.
+  pub(crate) roles: Vec<String>,
+  /// The capabilities granted until an expiry.
+  pub(crate) capabilities: Vec<TimedCapability>,
+}
+
+/// A capability granted until an absolute expiry.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TimedCapability {
+  pub(crate) capability: String,
+  /// Milliseconds since the UNIX epoch.
+  pub(crate) expires_at: u64,
+}
+
+impl TimedCapability {
+  pub(crate) fn new(capability: String, expires_at: SystemTime) -> Self {
+    Self {
+      capability,
+      expires_at: expires_at
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64),
+    }
+  }
+
+  pub(crate) fn expires_at(&self) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_millis(self.expires_at)
+  }
+}
+
+/// The signed blob, the signature covers the exact bytes of the payload.
+#[derive(Serialize, Deserialize)]
+struct SignedGrants {
+  version: u32,
+  payload: String,
+  signature: String,
+}
+
+/// Serializes the grants and signs them with the key.
+pub(crate) fn sign(grants: &RuntimeGrants, key: &[u8]) -> Vec<u8> {
+  let payload = serde_json::to_string(grants).expect("runtime grants are serializable");
+  let signature = hex(&hmac_sha256(key, payload.as_bytes()));
+  serde_json::to_vec(&SignedGrants {
+    version: VERSION,
+    payload,
+    signature,
+  })
+  .expect("runtime grants are serializable")
+}
+
+/// Verifies the signature of the blob with the key and deserializes the grants.
+pub(crate) fn verify(blob: &[u8], key: &[u8]) -> Result<RuntimeGrants, String> {
+  let signed = serde_json::from_slice::<SignedGrants>(blob).map_err(|e| e.to_string())?;
+  if signed.version != VERSION {
+    return Err(format!("unsupported version {}", signed.version));
+  }
+  let expected = hex(&hmac_sha256(key, signed.payload.as_bytes()));
+  // compares every byte so the time taken does not reveal the matching prefix
+  let matches = expected.len() == signed.signature.len()
+    && expected
+      .bytes()
+      .zip(signed.signature.bytes())
+      .fold(0, |diff, (a, b)| diff | (a ^ b))
+      == 0;
+  if !matches {
+    return Err("invalid signature".into());
+  }
+  serde_json::from_str(&signed.payload).map_err(|e| e.to_string())
+}
+
+/// HMAC-SHA256, see RFC 2104.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+  const BLOCK_SIZE: usize = 64;
+
+  let mut block = [0u8; BLOCK_SIZE];
+  if key.len() > BLOCK_SIZE {
+    block[..32].copy_from_slice(&Sha256::digest(key));
+  } else {
+    block[..key.len()].copy_from_slice(key);
+  }
+
+  let mut inner = Sha256::new();
+  inner.update(block.map(|b| b ^ 0x36));
+  inner.update(message);
+  let mut outer = Sha256::new();
+  outer.update(block.map(|b| b ^ 0x5c));
+  outer.update(inner.finalize());
+  outer.finalize().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hmac_sha256_test_vectors() {
+    // RFC 4231 test cases 2 and 6
+    assert_eq!(
+      hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+      "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+    assert_eq!(
+      hex(&hmac_sha256(
+        &[0xaa; 131],
+        b"Test Using Larger Than Block-Size Key - Hash Key First"
+      )),
+      "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+    );
+  }
+
+  #[test]
+  fn signed_grants_round_trip() {
+    let grants = RuntimeGrants {
+      roles: vec!["admin".into()],
+      capabilities: vec![TimedCapability::new(
+        "export".into(),
+        SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+      )],
+    };
+    let blob = sign(&grants, b"key");
+    let verified = verify(&blob, b"key").unwrap();
+    assert_eq!(verified.roles, grants.roles);
+    assert_eq!(verified.capabilities[0].capability, "export");
+    assert_eq!(
+      verified.capabilities[0].expires_at(),
+      SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+    );
+
+    assert_eq!(
+      verify(&blob, b"other key").unwrap_err(),
+      "invalid signature"
+    );
+    let tampered = String::from_utf8(blob)
+      .unwrap()
+      .replace("admin", "owner")
+      .into_bytes();
+    assert_eq!(verify(&tampered, b"key").unwrap_err(), "invalid signature");
+  }
+}
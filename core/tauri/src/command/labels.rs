@@ -0,0 +1,64 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  sync::Mutex,
+};
+
+use tauri_utils::acl::resolved::ResolvedCommand;
+
+/// The labels of every window and webview created since startup, only tracked in debug builds.
+#[derive(Debug, Default)]
+pub(crate) struct LabelRegistry(Mutex<BTreeSet<String>>);
+
+impl LabelRegistry {
+  pub(crate) fn record(&self, label: &str) {
+    let mut labels = self.0.lock().unwrap();
+    if !labels.contains(label) {
+      labels.insert(label.to_string());
+    }
+  }
+
+  /// The window patterns of the resolved commands that matched none of the recorded labels.
+  pub(crate) fn unmatched<'a>(
+    &self,
+    commands: impl IntoIterator<Item = &'a ResolvedCommand>,
+  ) -> Vec<UnmatchedWindowPattern> {
+    let labels = self.0.lock().unwrap();
+    let mut unmatched = BTreeMap::<String, BTreeSet<String>>::new();
+    for command in commands {
+      for pattern in &command.windows {
+        if !labels.iter().any(|label| pattern.matches(label)) {
+          unmatched
+            .entry(pattern.as_str().to_string())
+            .or_default()
+            .extend(
+              command
+                .referenced_by
+                .iter()
+                .map(|reference| reference.capability.clone()),
+            );
+        }
+      }
+    }
+    unmatched
+      .into_iter()
+      .map(|(pattern, capabilities)| UnmatchedWindowPattern {
+        pattern,
+        capabilities: capabilities.into_iter().collect(),
+      })
+      .collect()
+  }
+}
+
+/// A capability window pattern that matched no window or webview label created since startup,
+/// see [`crate::AppHandle::unmatched_window_patterns`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnmatchedWindowPattern {
+  /// The window label glob pattern.
+  pub pattern: String,
+  /// The identifiers of the capabilities granting the commands resolved for this pattern.
+  pub capabilities: Vec<String>,
+}
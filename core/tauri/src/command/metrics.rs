@@ -0,0 +1,140 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  mem::size_of,
+  sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::Serialize;
+use tauri_utils::acl::memory::{HeapStats, MemoryStats};
+
+use super::AccessDecision;
+
+/// The decision counters of a command.
+#[derive(Debug, Default)]
+struct Counters {
+  allowed: AtomicU64,
+  denied: AtomicU64,
+  not_allowed: AtomicU64,
+}
+
+impl Counters {
+  fn snapshot(&self) -> CommandMetrics {
+    CommandMetrics {
+      allowed: self.allowed.load(Ordering::Relaxed),
+      denied: self.denied.load(Ordering::Relaxed),
+      not_allowed: self.not_allowed.load(Ordering::Relaxed),
+    }
+  }
+}
+
+/// Counts the ACL decisions of the IPC calls since startup.
+///
+/// The counters are allocated once for the commands of the resolved ACL,
+/// so recording a decision only touches atomics and the memory use is bounded.
+#[derive(Debug, Default)]
+pub(crate) struct AclCounters {
+  commands: HashMap<String, Counters>,
+  other: Counters,
+}
+
+impl AclCounters {
+  pub(crate) fn new<'a>(commands: impl IntoIterator<Item = &'a str>) -> Self {
+    Self {
+      commands: commands
+        .into_iter()
+        .map(|command| (command.to_string(), Counters::default()))
+        .collect(),
+      other: Counters::default(),
+    }
+  }
+
+  pub(crate) fn record(&self, command: &str, decision: &AccessDecision<'_>) {
+    let counters = self.commands.get(command).unwrap_or(&self.other);
+    let counter = match decision {
+      AccessDecision::Allowed(_) => &counters.allowed,
+      AccessDecision::Denied => &counters.denied,
+      AccessDecision::NotAllowed => &counters.not_allowed,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// The estimated heap size of the counters, in bytes.
+  pub(crate) fn heap_size(&self) -> usize {
+    self
+      .commands
+      .keys()
+      .map(|command| size_of::<String>() + size_of::<Counters>() + command.capacity())
+      .sum()
+  }
+
+  pub(crate) fn snapshot(&self) -> AclMetrics {
+    AclMetrics {
+      commands: self
+        .commands
+        .iter()
+        .map(|(command, counters)| (command.clone(), counters.snapshot()))
+        .collect(),
+      other: self.other.snapshot(),
+    }
+  }
+}
+
+/// A snapshot of the ACL decisions of the plugin commands invoked since startup,
+/// see [`crate::AppHandle::acl_metrics`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AclMetrics {
+  /// The decision counts of each command of the resolved ACL.
+  pub commands: BTreeMap<String, CommandMetrics>,
+  /// The decision counts of the commands the resolved ACL does not reference.
+  pub other: CommandMetrics,
+}
+
+impl AclMetrics {
+  /// The decision counts of the given command.
+  pub fn command(&self, command: &str) -> CommandMetrics {
+    self.commands.get(command).copied().unwrap_or_default()
+  }
+}
+
+/// Estimated heap footprint of the runtime authority, see [`RuntimeAuthority::memory_stats`](super::RuntimeAuthority::memory_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorityMemoryStats {
+  /// The resolved ACL, with the scope shards decoded so far and the remote origins derived from it.
+  pub acl: MemoryStats,
+  /// Number of scopes deserialized into the types of the commands and cached for the next calls.
+  pub cached_scopes: usize,
+  /// The values loaded from the scope provider, see [`crate::Builder::scope_provider`].
+  pub external_scopes: HeapStats,
+  /// The scope entries granted at runtime, see [`RuntimeAuthority::grant_ephemeral`](super::RuntimeAuthority::grant_ephemeral).
+  pub ephemeral_grants: HeapStats,
+  /// The decision counters, see [`AclMetrics`].
+  pub counters: HeapStats,
+}
+
+impl AuthorityMemoryStats {
+  /// The estimated heap size of the authority, in bytes, without the deserialized scopes.
+  pub fn total_bytes(&self) -> usize {
+    self.acl.total_bytes()
+      + self.external_scopes.bytes
+      + self.ephemeral_grants.bytes
+      + self.counters.bytes
+  }
+}
+
+/// The decision counts of a command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandMetrics {
+  /// How many times the command was allowed.
+  pub allowed: u64,
+  /// How many times a permission explicitly denied the command.
+  pub denied: u64,
+  /// How many times no permission allowed the command on the calling webview and origin.
+  pub not_allowed: u64,
+}
@@ -16,10 +16,36 @@ use serde::{
   Deserialize, Deserializer,
 };
 
+mod args;
 mod authority;
-
-pub use authority::{CommandScope, GlobalScope, Origin, RuntimeAuthority, ScopeObject, ScopeValue};
+mod clock;
+mod ephemeral;
+mod grants;
+#[cfg(debug_assertions)]
+mod labels;
+mod metrics;
+mod roles;
+#[cfg(any(debug_assertions, feature = "acl-timing"))]
+mod timing;
+
+pub(crate) use args::check_arguments;
+#[cfg(feature = "compression")]
+pub use authority::EmbeddedScopeProvider;
+pub(crate) use authority::GlobalApi;
+pub use authority::{
+  AccessDecision, AccessRequest, CommandConfig, CommandConfigEntry, CommandScope, Denial,
+  FirstUseDecision, GlobalScope, Origin, RuntimeAuthority, ScopeObject, ScopeProvider,
+  ScopeValidator, ScopeValue,
+};
+pub use ephemeral::EphemeralPolicy;
+#[cfg(debug_assertions)]
+pub use labels::UnmatchedWindowPattern;
+pub use metrics::{AclMetrics, AuthorityMemoryStats, CommandMetrics};
 use tauri_utils::acl::resolved::ResolvedCommand;
+#[cfg(any(debug_assertions, feature = "acl-timing"))]
+pub(crate) use timing::AuthorizationPhase;
+#[cfg(any(debug_assertions, feature = "acl-timing"))]
+pub use timing::{AclTimings, CommandTimings, TimingSummary};
 
 /// Represents a custom command.
 pub struct CommandItem<'a, R: Runtime> {
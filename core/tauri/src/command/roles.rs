@@ -0,0 +1,159 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+  sync::{Arc, Mutex, RwLock},
+  time::SystemTime,
+};
+
+use tauri_utils::acl::resolved::RoleGrant;
+
+use super::clock::Clock;
+
+/// The user roles active at runtime and the grants they activate.
+#[derive(Debug, Default)]
+struct ActiveRoles {
+  roles: Vec<String>,
+  /// Indexes of the active grants in [`RoleGrants::grants`].
+  grants: Vec<usize>,
+}
+
+/// The grants of the capabilities restricted to user roles,
+/// see [`RuntimeAuthority::set_roles`](super::RuntimeAuthority::set_roles).
+///
+/// The grants themselves never change, only the set of active ones is swapped,
+/// so the authority can keep handing out references to their commands.
+/// A grant is also active until its expiry when it was granted for a duration,
+/// see [`RuntimeAuthority::add_capability_with_expiry`](super::RuntimeAuthority::add_capability_with_expiry).
+#[derive(Debug, Default)]
+pub(crate) struct RoleGrants {
+  grants: Vec<RoleGrant>,
+  active: RwLock<Arc<ActiveRoles>>,
+  /// Indexes of the grants active until an expiry, whatever the active roles.
+  timed: Mutex<Vec<(usize, SystemTime)>>,
+  clock: Clock,
+}
+
+impl RoleGrants {
+  pub(crate) fn new(grants: Vec<RoleGrant>) -> Self {
+    Self {
+      grants,
+      ..Default::default()
+    }
+  }
+
+  #[cfg(test)]
+  pub(crate) fn with_clock(mut self, clock: Clock) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  /// Activates the grant of the capability until the expiry, replacing its previous expiry.
+  ///
+  /// Returns `false` if no capability restricted to user roles has this identifier.
+  pub(crate) fn grant_until(&self, capability: &str, expires_at: SystemTime) -> bool {
+    let index = match self
+      .grants
+      .iter()
+      .position(|grant| grant.capability == capability)
+    {
+      Some(index) => index,
+      None => return false,
+    };
+    let mut timed = self.timed.lock().unwrap();
+    timed.retain(|(granted, _)| *granted != index);
+    timed.push((index, expires_at));
+    true
+  }
+
+  /// Drops the expired grants, returning the identifiers of their capabilities.
+  pub(crate) fn purge_expired(&self) -> Vec<String> {
+    let now = self.clock.now();
+    let mut purged = Vec::new();
+    self.timed.lock().unwrap().retain(|(index, expires_at)| {
+      let expired = *expires_at <= now;
+      if expired {
+        purged.push(self.grants[*index].capability.clone());
+      }
+      !expired
+    });
+    purged
+  }
+
+  /// The identifiers of the capabilities granted until an expiry, with their expiries.
+  pub(crate) fn timed(&self) -> Vec<(String, SystemTime)> {
+    self
+      .timed
+      .lock()
+      .unwrap()
+      .iter()
+      .map(|(index, expires_at)| (self.grants[*index].capability.clone(), *expires_at))
+      .collect()
+  }
+
+  /// Whether a capability restricted to user roles has this identifier.
+  pub(crate) fn has_capability(&self, capability: &str) -> bool {
+    self
+      .grants
+      .iter()
+      .any(|grant| grant.capability == capability)
+  }
+
+  /// Whether a capability is restricted to this role.
+  pub(crate) fn has_role(&self, role: &str) -> bool {
+    self
+      .grants
+      .iter()
+      .any(|grant| grant.roles.iter().any(|r| r == role))
+  }
+
+  /// The current time of the clock the expiries are checked against.
+  pub(crate) fn now(&self) -> SystemTime {
+    self.clock.now()
+  }
+
+  /// Activates the grants of the given roles, replacing the previously active ones at once.
+  pub(crate) fn set<S: AsRef<str>>(&self, roles: &[S]) {
+    let active = ActiveRoles {
+      roles: roles.iter().map(|role| role.as_ref().to_string()).collect(),
+      grants: self
+        .grants
+        .iter()
+        .enumerate()
+        .filter(|(_, grant)| grant.is_active(roles))
+        .map(|(index, _)| index)
+        .collect(),
+    };
+    *self.active.write().unwrap() = Arc::new(active);
+  }
+
+  pub(crate) fn roles(&self) -> Vec<String> {
+    self.active.read().unwrap().roles.clone()
+  }
+
+  /// Indexes of the active grants, a snapshot so one invoke sees a single set of roles.
+  ///
+  /// The expired grants are purged first, so they stop applying from the first authorization after their expiry.
+  pub(crate) fn active_indexes(&self) -> Vec<usize> {
+    if self.grants.is_empty() {
+      return Vec::new();
+    }
+    self.purge_expired();
+    let mut indexes = self.active.read().unwrap().grants.clone();
+    for (index, _) in self.timed.lock().unwrap().iter() {
+      if !indexes.contains(index) {
+        indexes.push(*index);
+      }
+    }
+    indexes
+  }
+
+  /// The active grants.
+  pub(crate) fn active(&self) -> impl Iterator<Item = &RoleGrant> {
+    self
+      .active_indexes()
+      .into_iter()
+      .map(|index| &self.grants[index])
+  }
+}
@@ -0,0 +1,292 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  sync::atomic::{AtomicU64, Ordering},
+  time::Duration,
+};
+
+use serde::Serialize;
+use tauri_utils::acl::{PLUGIN_COMMAND_PREFIX, PLUGIN_COMMAND_SEPARATOR};
+
+/// Number of histogram bins. Bin `0` holds the zero durations
+/// and bin `i` the durations from `2^(i - 1)` to `2^i` nanoseconds, the last one holding anything longer.
+const BINS: usize = 40;
+
+/// A phase of the authorization of an IPC call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AuthorizationPhase {
+  /// The runtime authority decision, see [`RuntimeAuthority::authorize`](super::RuntimeAuthority::authorize).
+  Decision,
+  /// The retrieval of a command or global scope by a command argument.
+  Scope,
+}
+
+/// A log-scaled histogram of durations, with its bins allocated upfront.
+#[derive(Debug)]
+struct Histogram {
+  bins: [AtomicU64; BINS],
+}
+
+impl Default for Histogram {
+  fn default() -> Self {
+    Self {
+      bins: std::array::from_fn(|_| AtomicU64::new(0)),
+    }
+  }
+}
+
+impl Histogram {
+  fn record(&self, elapsed: Duration) {
+    let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+    let bin = (u64::BITS - nanos.leading_zeros()) as usize;
+    self.bins[bin.min(BINS - 1)].fetch_add(1, Ordering::Relaxed);
+  }
+
+  fn summary(&self) -> TimingSummary {
+    let bins = self
+      .bins
+      .iter()
+      .map(|bin| bin.load(Ordering::Relaxed))
+      .collect::<Vec<_>>();
+    let count = bins.iter().sum::<u64>();
+
+    // the upper bound of the bin holding the sample of the given rank
+    let percentile = |percent: u64| {
+      if count == 0 {
+        return 0;
+      }
+      let rank = ((count * percent + 99) / 100).max(1);
+      let mut seen = 0;
+      for (bin, samples) in bins.iter().enumerate() {
+        seen += samples;
+        if seen >= rank {
+          return upper_bound(bin);
+        }
+      }
+      upper_bound(BINS - 1)
+    };
+
+    TimingSummary {
+      count,
+      p50_nanos: percentile(50),
+      p90_nanos: percentile(90),
+      p99_nanos: percentile(99),
+      max_nanos: percentile(100),
+    }
+  }
+}
+
+/// The largest duration held by a bin, in nanoseconds.
+fn upper_bound(bin: usize) -> u64 {
+  match bin {
+    0 => 0,
+    _ => (1u64 << bin) - 1,
+  }
+}
+
+#[derive(Debug, Default)]
+struct CommandHistograms {
+  decision: Histogram,
+  scope: Histogram,
+}
+
+impl CommandHistograms {
+  fn record(&self, phase: AuthorizationPhase, elapsed: Duration) {
+    match phase {
+      AuthorizationPhase::Decision => self.decision.record(elapsed),
+      AuthorizationPhase::Scope => self.scope.record(elapsed),
+    }
+  }
+
+  fn summary(&self) -> CommandTimings {
+    CommandTimings {
+      decision: self.decision.summary(),
+      scope: self.scope.summary(),
+    }
+  }
+}
+
+/// Measures the authorization of the IPC calls since startup, only available in debug builds
+/// or with the `acl-timing` feature.
+///
+/// The histograms are allocated once for the commands of the resolved ACL, keyed by plugin then command
+/// so both the full command names and the names given to the plugins are looked up without allocating.
+#[derive(Debug, Default)]
+pub(crate) struct AclTimers {
+  commands: HashMap<String, HashMap<String, CommandHistograms>>,
+  other: CommandHistograms,
+}
+
+impl AclTimers {
+  pub(crate) fn new<'a>(commands: impl IntoIterator<Item = &'a str>) -> Self {
+    let mut timers = Self::default();
+    for command in commands {
+      let (plugin, name) = split_command(None, command);
+      timers
+        .commands
+        .entry(plugin.to_string())
+        .or_default()
+        .entry(name.to_string())
+        .or_default();
+    }
+    timers
+  }
+
+  /// Records the duration of an authorization phase of a command,
+  /// named by its plugin and plugin command name or by its full name.
+  pub(crate) fn record(
+    &self,
+    plugin: Option<&str>,
+    command: &str,
+    phase: AuthorizationPhase,
+    elapsed: Duration,
+  ) {
+    let (plugin, command) = split_command(plugin, command);
+    self
+      .commands
+      .get(plugin)
+      .and_then(|commands| commands.get(command))
+      .unwrap_or(&self.other)
+      .record(phase, elapsed);
+  }
+
+  pub(crate) fn snapshot(&self) -> AclTimings {
+    AclTimings {
+      commands: self
+        .commands
+        .iter()
+        .flat_map(|(plugin, commands)| {
+          commands.iter().map(move |(command, histograms)| {
+            let name = if plugin.is_empty() {
+              command.clone()
+            } else {
+              format!("{PLUGIN_COMMAND_PREFIX}{plugin}{PLUGIN_COMMAND_SEPARATOR}{command}")
+            };
+            (name, histograms.summary())
+          })
+        })
+        .collect(),
+      other: self.other.summary(),
+    }
+  }
+}
+
+/// The plugin, empty for the app commands, and the plugin command name of a command.
+fn split_command<'a>(plugin: Option<&'a str>, command: &'a str) -> (&'a str, &'a str) {
+  match plugin {
+    Some(plugin) => (plugin, command),
+    None => command
+      .strip_prefix(PLUGIN_COMMAND_PREFIX)
+      .and_then(|command| command.split_once(PLUGIN_COMMAND_SEPARATOR))
+      .unwrap_or(("", command)),
+  }
+}
+
+/// The percentiles of the authorization durations of the commands invoked since startup,
+/// see [`crate::AppHandle::acl_timings`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AclTimings {
+  /// The durations of each command of the resolved ACL.
+  pub commands: BTreeMap<String, CommandTimings>,
+  /// The durations of the commands the resolved ACL does not reference.
+  pub other: CommandTimings,
+}
+
+impl AclTimings {
+  /// The durations of the given command.
+  pub fn command(&self, command: &str) -> CommandTimings {
+    self.commands.get(command).copied().unwrap_or_default()
+  }
+}
+
+/// The authorization durations of a command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandTimings {
+  /// The runtime authority decisions.
+  pub decision: TimingSummary,
+  /// The retrievals of the command and global scopes by the command arguments.
+  pub scope: TimingSummary,
+}
+
+/// The percentiles of a set of durations.
+///
+/// The durations are counted in bins of power of two nanoseconds,
+/// so each percentile is the upper bound of the bin holding it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingSummary {
+  /// Number of measured durations.
+  pub count: u64,
+  /// The median duration, in nanoseconds.
+  pub p50_nanos: u64,
+  /// The 90th percentile, in nanoseconds.
+  pub p90_nanos: u64,
+  /// The 99th percentile, in nanoseconds.
+  pub p99_nanos: u64,
+  /// The longest duration, in nanoseconds.
+  pub max_nanos: u64,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn histogram_percentiles() {
+    let histogram = Histogram::default();
+    assert_eq!(histogram.summary(), TimingSummary::default());
+
+    for _ in 0..90 {
+      histogram.record(Duration::from_nanos(100));
+    }
+    for _ in 0..9 {
+      histogram.record(Duration::from_micros(10));
+    }
+    histogram.record(Duration::from_millis(5));
+    // longer than the last bin
+    histogram.record(Duration::from_secs(3600));
+
+    assert_eq!(
+      histogram.summary(),
+      TimingSummary {
+        count: 101,
+        p50_nanos: 127,
+        p90_nanos: 16_383,
+        p99_nanos: 8_388_607,
+        max_nanos: (1 << 39) - 1,
+      }
+    );
+  }
+
+  #[test]
+  fn commands_are_keyed_by_plugin() {
+    let timers = AclTimers::new(["plugin:fs|read", "greet"]);
+    let elapsed = Duration::from_nanos(10);
+    timers.record(
+      None,
+      "plugin:fs|read",
+      AuthorizationPhase::Decision,
+      elapsed,
+    );
+    timers.record(Some("fs"), "read", AuthorizationPhase::Scope, elapsed);
+    timers.record(None, "greet", AuthorizationPhase::Decision, elapsed);
+    timers.record(
+      None,
+      "plugin:fs|write",
+      AuthorizationPhase::Decision,
+      elapsed,
+    );
+
+    let timings = timers.snapshot();
+    assert_eq!(timings.commands.len(), 2);
+    assert_eq!(timings.command("plugin:fs|read").decision.count, 1);
+    assert_eq!(timings.command("plugin:fs|read").scope.count, 1);
+    assert_eq!(timings.command("greet").decision.count, 1);
+    assert_eq!(timings.other.decision.count, 1);
+  }
+}
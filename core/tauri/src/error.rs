@@ -148,6 +148,77 @@ pub enum Error {
   /// Failed to deserialize scope object.
   #[error("error deserializing scope: {0}")]
   CannotDeserializeScope(Box<dyn std::error::Error>),
+  /// Failed to deserialize a command configuration supplied by a capability.
+  #[error("error deserializing the command config of capability {capability}: {error}")]
+  CannotDeserializeCommandConfig {
+    /// The identifier of the capability supplying the configuration.
+    capability: String,
+    /// The deserialization error.
+    error: serde_json::Error,
+  },
+  /// The capabilities of the calling webview do not allow emitting events to the target.
+  #[error("emitting events to {0} is not allowed by the capability targets")]
+  EventTargetNotAllowed(String),
+  /// The ACL does not allow a command invoked from Rust, see [`crate::webview::InvokeAccess::AsWindow`].
+  #[error("command {command} {denial}")]
+  CommandNotAllowed {
+    /// The command name.
+    command: String,
+    /// Why the ACL does not allow the command.
+    denial: crate::command::Denial,
+  },
+  /// A permission granting a command references a validator that is not registered,
+  /// see [`crate::Builder::isolation_validator`].
+  #[error("command {command} references the validator {validator}, which is not registered")]
+  UnknownIsolationValidator {
+    /// The validator identifier.
+    validator: String,
+    /// The command name.
+    command: String,
+  },
+  /// The resolved ACL references a scope stored out of it but no provider is registered,
+  /// see [`crate::Builder::scope_provider`].
+  #[error("the ACL references the external scope {0}, but no scope provider is registered")]
+  MissingScopeProvider(String),
+  /// The resolved ACL grants commands of plugins that are not registered,
+  /// see [`crate::Builder::deny_unregistered_plugins`].
+  #[error("the capabilities grant commands of the plugins {}, which are not registered", .0.join(", "))]
+  UnregisteredPlugins(Vec<String>),
+  /// The values of a scope stored out of the resolved ACL could not be loaded or do not match their content hash,
+  /// see [`crate::command::ScopeProvider`].
+  #[error("failed to load the external scope {key}: {error}")]
+  ExternalScope {
+    /// The key of the scope values.
+    key: String,
+    /// The loading error.
+    error: String,
+  },
+  /// The path is not allowed by the filesystem scope, see [`crate::scope::fs::Scope`].
+  #[error("path {0} is not allowed by the scope")]
+  PathNotAllowed(std::path::PathBuf),
+  /// The URL is not allowed by the URL scope, see [`crate::scope::url::Scope`].
+  ///
+  /// Only holds the scheme and host of the URL, leaving out its path and query string.
+  #[error("url {scheme}:{} is not allowed by the scope", .host.as_ref().map(|host| format!("//{host}")).unwrap_or_default())]
+  UrlNotAllowed {
+    /// The URL scheme.
+    scheme: String,
+    /// The URL host, if any.
+    host: Option<String>,
+  },
+  /// The capability cannot be granted at runtime, only the capabilities restricted to user roles can,
+  /// see [`crate::command::RuntimeAuthority::add_capability_with_expiry`].
+  #[error("capability {0} cannot be granted at runtime, it is not restricted to user roles")]
+  UnknownRuntimeCapability(String),
+  /// The runtime grants could not be imported, none of them were applied,
+  /// see [`crate::command::RuntimeAuthority::import_runtime_grants`].
+  #[error("failed to import the runtime grants{}: {reason}", .entry.as_ref().map(|entry| format!(" at {entry}")).unwrap_or_default())]
+  RuntimeGrantsImport {
+    /// The entry that failed the validation, `None` if the blob itself was rejected.
+    entry: Option<String>,
+    /// Why the import failed.
+    reason: String,
+  },
 }
 
 /// `Result<T, ::tauri::Error>`
@@ -5,7 +5,9 @@
 use serde::{Deserialize, Deserializer};
 use serde_json::Value as JsonValue;
 use tauri_runtime::window::is_label_valid;
+use tauri_utils::{acl::window::WindowPattern, normalize_label};
 
+use crate::command::CommandScope;
 use crate::plugin::{Builder, TauriPlugin};
 use crate::sealed::ManagerBase;
 use crate::{command, ipc::CallbackFn, EventId, Manager, Result, Runtime};
@@ -89,6 +91,59 @@ pub fn unlisten<R: Runtime>(
   webview.unlisten_js(event.as_ref(), event_id)
 }
 
+/// A scope entry of the `emit` command restricting the labels the frontend can target.
+///
+/// Each target is a window or webview label glob pattern.
+/// Broadcasting the event to every listener is only allowed by an explicit `"*"` target.
+/// When no capability of the calling webview scopes the `emit` command, every target is allowed.
+///
+/// ```json
+/// { "identifier": "allow-emit", "allow": [{ "targets": ["status-bar"] }] }
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct EmitTargets {
+  targets: Vec<String>,
+}
+
+impl EmitTargets {
+  fn matches(&self, label: &str) -> bool {
+    let label = normalize_label(label);
+    self.targets.iter().any(|target| {
+      WindowPattern::new(&normalize_label(target)).is_ok_and(|pattern| pattern.matches(&label))
+    })
+  }
+
+  fn broadcasts(&self) -> bool {
+    self.targets.iter().any(|target| target == "*")
+  }
+}
+
+/// Checks that the scope of the `emit` command allows the target.
+fn check_emit_target(scope: &CommandScope<'_, EmitTargets>, target: &EventSource) -> Result<()> {
+  if scope.allows().is_empty() && scope.denies().is_empty() {
+    return Ok(());
+  }
+  let allowed = match target {
+    // a broadcast reaches every webview, so no label can be denied
+    EventSource::Global => {
+      scope.allows().iter().any(EmitTargets::broadcasts) && scope.denies().is_empty()
+    }
+    EventSource::Window { label } | EventSource::Webview { label } => {
+      scope.allows().iter().any(|entry| entry.matches(label))
+        && !scope.denies().iter().any(|entry| entry.matches(label))
+    }
+  };
+  if allowed {
+    Ok(())
+  } else {
+    Err(crate::Error::EventTargetNotAllowed(match target {
+      EventSource::Global => "every target".into(),
+      EventSource::Window { label } => format!("window `{label}`"),
+      EventSource::Webview { label } => format!("webview `{label}`"),
+    }))
+  }
+}
+
 #[command(root = "crate")]
 pub fn emit<R: Runtime>(
   app: AppHandle<R>,
@@ -96,13 +151,16 @@ pub fn emit<R: Runtime>(
   event: EventName,
   target: Option<EventSource>,
   payload: Option<JsonValue>,
+  scope: CommandScope<'_, EmitTargets>,
 ) -> Result<()> {
   let target = target.unwrap_or(EventSource::Global);
-  match target {
+  check_emit_target(&scope, &target)?;
+  // the targets are already restricted by the scope of the command
+  crate::ipc::unbound(|| match target {
     EventSource::Global => app.emit(&event.0, payload),
     EventSource::Webview { label } => webview.emit_to(&label, &event.0, payload),
     EventSource::Window { label } => webview.window().emit_to(&label, &event.0, payload),
-  }
+  })
 }
 
 /// Initializes the event plugin.
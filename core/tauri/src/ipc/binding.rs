@@ -0,0 +1,126 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{cell::RefCell, future::Future};
+
+/// The window and webview that passed the ACL check for an invoke, see [`InvokeMessage::authorized`](super::InvokeMessage::authorized).
+///
+/// With [`SecurityConfig#structfield.bind_command_events`](crate::utils::config::SecurityConfig#structfield.bind_command_events),
+/// the events emitted while the command runs are only delivered to the JavaScript listeners of this webview.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizedWebview {
+  window: String,
+  webview: String,
+}
+
+impl AuthorizedWebview {
+  pub(crate) fn new(window: impl Into<String>, webview: impl Into<String>) -> Self {
+    Self {
+      window: window.into(),
+      webview: webview.into(),
+    }
+  }
+
+  /// The label of the window.
+  pub fn window(&self) -> &str {
+    &self.window
+  }
+
+  /// The label of the webview.
+  pub fn webview(&self) -> &str {
+    &self.webview
+  }
+}
+
+thread_local! {
+  // set while a command handler runs, or by `unbound`
+  static BINDING: RefCell<Option<Option<AuthorizedWebview>>> = const { RefCell::new(None) };
+}
+
+tokio::task_local! {
+  // set for the async tasks spawned by the command handlers
+  static TASK_BINDING: Option<AuthorizedWebview>;
+}
+
+/// Restores the previous binding of the thread when dropped, even if the command panics.
+struct RestoreBinding(Option<Option<AuthorizedWebview>>);
+
+impl Drop for RestoreBinding {
+  fn drop(&mut self) {
+    let previous = self.0.take();
+    BINDING.with(|binding| *binding.borrow_mut() = previous);
+  }
+}
+
+fn with_binding<T>(authorized: Option<AuthorizedWebview>, f: impl FnOnce() -> T) -> T {
+  let _restore = RestoreBinding(BINDING.with(|binding| binding.replace(Some(authorized))));
+  f()
+}
+
+/// Runs a command handler bound to the webview that passed the ACL check for its invoke,
+/// `None` if the invoke was not checked.
+pub(crate) fn bind<T>(authorized: Option<AuthorizedWebview>, f: impl FnOnce() -> T) -> T {
+  with_binding(authorized, f)
+}
+
+/// Binds an async task spawned by a command handler to the webview of the handler.
+pub(crate) fn bind_task<F: Future>(task: F) -> impl Future<Output = F::Output> {
+  TASK_BINDING.scope(current(), task)
+}
+
+/// The webview the events emitted on this thread or task are bound to.
+pub(crate) fn current() -> Option<AuthorizedWebview> {
+  BINDING
+    .with(|binding| binding.borrow().clone())
+    .unwrap_or_else(|| TASK_BINDING.try_with(Clone::clone).ok().flatten())
+}
+
+/// Runs `f` without binding the events it emits to the webview of the command being run.
+///
+/// With [`SecurityConfig#structfield.bind_command_events`](crate::utils::config::SecurityConfig#structfield.bind_command_events),
+/// a command broadcasting an event to other webviews on purpose explicitly opts out of the binding with this function.
+/// It has no effect outside of a command.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use tauri::Manager;
+///
+/// #[tauri::command]
+/// fn refresh(app: tauri::AppHandle) -> Result<(), String> {
+///   tauri::ipc::unbound(|| app.emit("refreshed", ())).map_err(|e| e.to_string())
+/// }
+/// ```
+pub fn unbound<T>(f: impl FnOnce() -> T) -> T {
+  with_binding(None, f)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bindings_are_scoped() {
+    let main = AuthorizedWebview::new("main", "main");
+    assert_eq!(current(), None);
+    bind(Some(main.clone()), || {
+      assert_eq!(current(), Some(main.clone()));
+      unbound(|| assert_eq!(current(), None));
+      assert_eq!(current(), Some(main.clone()));
+
+      // the async tasks keep the binding of the handler that spawned them
+      let task = bind_task(async { current() });
+      assert_eq!(
+        crate::async_runtime::block_on(crate::async_runtime::spawn(task)).unwrap(),
+        Some(main.clone())
+      );
+      let task = bind_task(async { unbound(current) });
+      assert_eq!(
+        crate::async_runtime::block_on(crate::async_runtime::spawn(task)).unwrap(),
+        None
+      );
+    });
+    assert_eq!(current(), None);
+  }
+}
@@ -32,8 +32,17 @@ static CHANNEL_COUNTER: AtomicU32 = AtomicU32::new(0);
 static CHANNEL_DATA_COUNTER: AtomicU32 = AtomicU32::new(0);
 
 /// Maps a channel id to a pending data that must be send to the JavaScript side via the IPC.
+///
+/// Each entry is bound to the label of the webview the data was sent to,
+/// so only that webview can fetch it, unless the channel opted out with [`JavaScriptChannelId::unbound_channel_on`].
 #[derive(Default, Clone)]
-pub struct ChannelDataIpcQueue(pub(crate) Arc<Mutex<HashMap<u32, InvokeBody>>>);
+pub struct ChannelDataIpcQueue(pub(crate) Arc<Mutex<HashMap<u32, ChannelData>>>);
+
+/// A pending channel message and the webview it is addressed to, `None` if any webview can fetch it.
+pub(crate) struct ChannelData {
+  webview_label: Option<String>,
+  body: InvokeBody,
+}
 
 /// An IPC channel.
 #[derive(Clone)]
@@ -88,9 +97,22 @@ impl FromStr for JavaScriptChannelId {
 
 impl JavaScriptChannelId {
   /// Gets a [`Channel`] for this channel ID on the given [`Webview`].
+  ///
+  /// The channel delivers its messages to the given webview only,
+  /// even if it is not the webview that invoked the command that received this ID.
+  /// Prefer a [`Channel`] command argument, which is always bound to the invoking webview.
   pub fn channel_on<R: Runtime>(&self, webview: Webview<R>) -> Channel {
     Channel::from_callback_fn(webview, self.0)
   }
+
+  /// Gets a [`Channel`] for this channel ID on the given [`Webview`],
+  /// whose messages can be fetched by any webview knowing their identifier.
+  ///
+  /// This explicitly opts the command out of binding the channel to a single webview, see [`Self::channel_on`].
+  /// Only use it for data that is not restricted by the ACL, since the webviews fetching it are not checked.
+  pub fn unbound_channel_on<R: Runtime>(&self, webview: Webview<R>) -> Channel {
+    Channel::deliver_on(webview, self.0, false)
+  }
 }
 
 impl<'de> Deserialize<'de> for JavaScriptChannelId {
@@ -132,6 +154,11 @@ impl Channel {
   }
 
   pub(crate) fn from_callback_fn<R: Runtime>(webview: Webview<R>, callback: CallbackFn) -> Self {
+    Self::deliver_on(webview, callback, true)
+  }
+
+  /// Creates a channel delivering its messages to the webview, bound to it if `bound` is set.
+  fn deliver_on<R: Runtime>(webview: Webview<R>, callback: CallbackFn, bound: bool) -> Self {
     Channel::new_with_id(callback.0, move |body| {
       let data_id = CHANNEL_DATA_COUNTER.fetch_add(1, Ordering::Relaxed);
       webview
@@ -139,7 +166,13 @@ impl Channel {
         .0
         .lock()
         .unwrap()
-        .insert(data_id, body);
+        .insert(
+          data_id,
+          ChannelData {
+            webview_label: bound.then(|| webview.label().to_string()),
+            body,
+          },
+        );
       webview.eval(&format!(
         "window.__TAURI_INTERNALS__.invoke('{FETCH_CHANNEL_DATA_COMMAND}', null, {{ headers: {{ '{CHANNEL_ID_HEADER_NAME}': '{data_id}' }} }}).then(window['_' + {}]).catch(console.error)",
         callback.0
@@ -161,6 +194,9 @@ impl Channel {
 
 impl<'de, R: Runtime> CommandArg<'de, R> for Channel {
   /// Grabs the [`Webview`] from the [`CommandItem`] and returns the associated [`Channel`].
+  ///
+  /// The channel is bound to the webview that invoked the command,
+  /// which is the webview that passed the ACL check for the invoke.
   fn from_command(command: CommandItem<'de, R>) -> Result<Self, InvokeError> {
     let name = command.name;
     let arg = command.key;
@@ -178,7 +214,8 @@ impl<'de, R: Runtime> CommandArg<'de, R> for Channel {
 }
 
 #[command(root = "crate")]
-fn fetch(
+fn fetch<R: Runtime>(
+  webview: Webview<R>,
   request: Request<'_>,
   cache: State<'_, ChannelDataIpcQueue>,
 ) -> Result<Response, &'static str> {
@@ -188,8 +225,15 @@ fn fetch(
     .and_then(|v| v.to_str().ok())
     .and_then(|id| id.parse().ok())
   {
-    if let Some(data) = cache.0.lock().unwrap().remove(&id) {
-      Ok(Response::new(data))
+    let mut cache = cache.0.lock().unwrap();
+    // data sent to another webview must not be readable by this one
+    if cache.get(&id).is_some_and(|data| {
+      data
+        .webview_label
+        .as_deref()
+        .map_or(true, |label| label == webview.label())
+    }) {
+      Ok(Response::new(cache.remove(&id).unwrap().body))
     } else {
       Err("data not found")
     }
@@ -203,3 +247,165 @@ pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
     .invoke_handler(crate::generate_handler![fetch])
     .build()
 }
+
+#[cfg(test)]
+mod tests {
+  use tauri_utils::acl::{
+    resolved::{CommandKey, ResolvedCommand},
+    ExecutionContext,
+  };
+
+  use super::*;
+  use crate::{
+    ipc::CallbackFn,
+    test::{get_ipc_response, mock_builder, mock_context, noop_assets, MockRuntime},
+    webview::InvokeRequest,
+    WebviewWindowBuilder,
+  };
+
+  #[command(root = "crate")]
+  fn produce(channel: Channel) {
+    channel.send("secret").unwrap();
+  }
+
+  #[command(root = "crate")]
+  fn produce_unbound(webview: Webview<MockRuntime>, channel: JavaScriptChannelId) {
+    channel.unbound_channel_on(webview).send("public").unwrap();
+  }
+
+  fn invoke_request(cmd: &str, headers: http::HeaderMap) -> InvokeRequest {
+    InvokeRequest {
+      cmd: cmd.into(),
+      callback: CallbackFn(0),
+      error: CallbackFn(1),
+      body: serde_json::json!({ "channel": format!("{IPC_PAYLOAD_PREFIX}1") }).into(),
+      headers,
+    }
+  }
+
+  #[test]
+  fn channel_data_is_bound_to_the_authorized_webview() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:producer|produce".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["main".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("producer")
+          .invoke_handler(crate::generate_handler![produce, produce_unbound])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let other = WebviewWindowBuilder::new(&app, "other", Default::default())
+      .build()
+      .unwrap();
+
+    // only the main webview is allowed to call the producing command
+    assert!(get_ipc_response(
+      &other,
+      invoke_request("plugin:producer|produce", Default::default())
+    )
+    .is_err());
+    assert!(get_ipc_response(
+      &main,
+      invoke_request("plugin:producer|produce", Default::default())
+    )
+    .is_ok());
+
+    let data_id = *app
+      .state::<ChannelDataIpcQueue>()
+      .0
+      .lock()
+      .unwrap()
+      .keys()
+      .next()
+      .expect("channel data not queued");
+    let mut headers = http::HeaderMap::new();
+    headers.insert(CHANNEL_ID_HEADER_NAME, data_id.to_string().parse().unwrap());
+
+    // the other webview cannot read the data even if it knows the channel data id
+    assert_eq!(
+      get_ipc_response(
+        &other,
+        invoke_request(FETCH_CHANNEL_DATA_COMMAND, headers.clone())
+      )
+      .unwrap_err(),
+      serde_json::Value::String("data not found".into())
+    );
+    assert_eq!(
+      get_ipc_response(&main, invoke_request(FETCH_CHANNEL_DATA_COMMAND, headers))
+        .unwrap()
+        .deserialize::<String>()
+        .unwrap(),
+      "secret"
+    );
+  }
+
+  #[test]
+  fn unbound_channel_data_is_fetched_by_any_webview() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:producer|produce_unbound".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["main".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("producer")
+          .invoke_handler(crate::generate_handler![produce, produce_unbound])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let other = WebviewWindowBuilder::new(&app, "other", Default::default())
+      .build()
+      .unwrap();
+
+    assert!(get_ipc_response(
+      &main,
+      invoke_request("plugin:producer|produce_unbound", Default::default())
+    )
+    .is_ok());
+
+    let data_id = *app
+      .state::<ChannelDataIpcQueue>()
+      .0
+      .lock()
+      .unwrap()
+      .keys()
+      .next()
+      .expect("channel data not queued");
+    let mut headers = http::HeaderMap::new();
+    headers.insert(CHANNEL_ID_HEADER_NAME, data_id.to_string().parse().unwrap());
+
+    // the command opted out of binding the channel to the main webview
+    assert_eq!(
+      get_ipc_response(&other, invoke_request(FETCH_CHANNEL_DATA_COMMAND, headers))
+        .unwrap()
+        .deserialize::<String>()
+        .unwrap(),
+      "public"
+    );
+  }
+}
@@ -0,0 +1,159 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::collections::BTreeSet;
+
+use url::Url;
+
+/// Classifies the pages sending IPC requests into the [`ExecutionContext`](crate::utils::acl::ExecutionContext)
+/// the capabilities are matched against.
+///
+/// A page is local when the origin of its URL is one of the local origins of the policy,
+/// and remote otherwise, keyed by the host of its origin.
+/// The origins served by the app are always local: its custom protocol and the configured `devPath`
+/// or `distDir` URL.
+///
+/// Additional local origins are listed as exact origin strings, `scheme://host[:port]`
+/// without a trailing slash and without the default port of the scheme,
+/// e.g. `http://localhost:8080` for an embedded web server, `myapp://localhost` for a custom URI scheme protocol
+/// or `file://` for the pages loaded from the file system.
+/// There is no wildcard: an origin not listed verbatim is remote.
+///
+/// Registered with [`Builder::invoke_context_policy`](crate::Builder::invoke_context_policy).
+///
+/// # Examples
+///
+/// ```
+/// use tauri::ipc::ContextPolicy;
+///
+/// let policy = ContextPolicy::new()
+///   .local_origin("http://localhost:8080")
+///   .local_origin("myapp://localhost");
+///
+/// assert!(policy.is_local(&"http://localhost:8080/index.html".parse().unwrap()));
+/// assert!(!policy.is_local(&"http://localhost:8081/index.html".parse().unwrap()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ContextPolicy {
+  local_origins: BTreeSet<String>,
+}
+
+impl ContextPolicy {
+  /// The default policy, where only the origins served by the app are local.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Classifies the pages loaded from the origin as local.
+  ///
+  /// The origin must match the origin of the page URL exactly, see [`Self::origin`].
+  #[must_use]
+  pub fn local_origin(mut self, origin: impl Into<String>) -> Self {
+    self.local_origins.insert(origin.into());
+    self
+  }
+
+  /// The origins classified as local.
+  pub fn local_origins(&self) -> impl Iterator<Item = &str> {
+    self.local_origins.iter().map(String::as_str)
+  }
+
+  /// Whether the IPC requests of the page loaded at the URL are local.
+  pub fn is_local(&self, url: &Url) -> bool {
+    self.local_origins.contains(&Self::origin(url))
+  }
+
+  /// The origin string of the URL the local origins are compared to, `scheme://host[:port]`.
+  ///
+  /// Unlike [`Url::origin`], the origin of a custom scheme or of a `file://` URL is not opaque.
+  pub fn origin(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+      Some(port) => format!("{}://{host}:{port}", url.scheme()),
+      None => format!("{}://{host}", url.scheme()),
+    }
+  }
+
+  /// Adds the origins served by the app to the local origins.
+  pub(crate) fn with_app_origins<'a>(mut self, urls: impl IntoIterator<Item = &'a Url>) -> Self {
+    self
+      .local_origins
+      .extend(urls.into_iter().map(Self::origin));
+    self
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn url(url: &str) -> Url {
+    url.parse().unwrap()
+  }
+
+  fn app_policy() -> ContextPolicy {
+    ContextPolicy::new().with_app_origins(&[url("tauri://localhost"), url("http://localhost:1420")])
+  }
+
+  #[test]
+  fn app_origins_are_local() {
+    let policy = app_policy();
+    assert!(policy.is_local(&url("tauri://localhost")));
+    assert!(policy.is_local(&url("tauri://localhost/settings/index.html")));
+    assert!(policy.is_local(&url("http://localhost:1420/index.html?tab=1#top")));
+
+    assert!(!policy.is_local(&url("https://tauri.app")));
+    assert!(!policy.is_local(&url("https://localhost:1420")));
+    assert!(!policy.is_local(&url("tauri://localhost.evil.com")));
+  }
+
+  #[test]
+  fn localhost_ports() {
+    let policy = app_policy().local_origin("http://localhost:8080");
+    assert!(policy.is_local(&url("http://localhost:8080/app")));
+    assert!(!policy.is_local(&url("http://localhost:8081/app")));
+    assert!(!policy.is_local(&url("http://localhost/app")));
+    assert!(!policy.is_local(&url("http://127.0.0.1:8080/app")));
+
+    // the default port is not part of the origin
+    let policy = app_policy().local_origin("http://localhost");
+    assert!(policy.is_local(&url("http://localhost:80/app")));
+    assert!(!policy.is_local(&url("http://localhost:8080/app")));
+    let policy = app_policy().local_origin("http://localhost:80");
+    assert!(!policy.is_local(&url("http://localhost:80/app")));
+  }
+
+  #[test]
+  fn custom_schemes() {
+    assert!(!app_policy().is_local(&url("myapp://localhost/index.html")));
+
+    let policy = app_policy().local_origin("myapp://localhost");
+    assert!(policy.is_local(&url("myapp://localhost/index.html")));
+    assert!(!policy.is_local(&url("myapp://other/index.html")));
+    assert!(!policy.is_local(&url("otherapp://localhost/index.html")));
+    assert_eq!(
+      ContextPolicy::origin(&url("myapp://localhost/index.html")),
+      "myapp://localhost"
+    );
+  }
+
+  #[test]
+  fn file_urls() {
+    assert!(!app_policy().is_local(&url("file:///home/user/index.html")));
+
+    let policy = app_policy().local_origin("file://");
+    assert!(policy.is_local(&url("file:///home/user/index.html")));
+    assert!(policy.is_local(&url("file:///C:/app/index.html")));
+    assert!(!policy.is_local(&url("file://server/share/index.html")));
+  }
+
+  #[test]
+  fn origins_are_exact() {
+    let policy = app_policy()
+      .local_origin("https://*.tauri.app")
+      .local_origin("https://tauri.app/");
+    assert!(!policy.is_local(&url("https://api.tauri.app")));
+    assert!(!policy.is_local(&url("https://tauri.app")));
+  }
+}
@@ -22,12 +22,24 @@ use crate::{
   Runtime, StateManager,
 };
 
+pub(crate) mod binding;
 pub(crate) mod channel;
+mod context;
 #[cfg(any(target_os = "macos", target_os = "ios", not(ipc_custom_protocol)))]
 pub(crate) mod format_callback;
 pub(crate) mod protocol;
 
+pub use binding::{unbound, AuthorizedWebview};
 pub use channel::{Channel, JavaScriptChannelId};
+pub use context::ContextPolicy;
+
+/// The command of the IPC requests running several commands at once.
+///
+/// The request body is the list of the commands, `[{ "cmd": "plugin:fs|read", "payload": { .. } }, ..]`,
+/// authorized together with [`RuntimeAuthority::authorize_all`](crate::command::RuntimeAuthority::authorize_all).
+/// The commands run in order and the request resolves with the list of their responses in the same order once they all responded,
+/// `{ "ok": value }` for a command that succeeded and `{ "error": value }` for a command that failed or that the ACL denied.
+pub const BATCH_INVOKE_COMMAND: &str = "plugin:__TAURI_IPC__|batch";
 
 /// A closure that is run every time Tauri receives a message it doesn't explicitly handle.
 pub type InvokeHandler<R> = dyn Fn(Invoke<R>) -> bool + Send + Sync + 'static;
@@ -286,7 +298,7 @@ impl<R: Runtime> InvokeResolver<R> {
     T: IpcResponse,
     F: Future<Output = Result<T, InvokeError>> + Send + 'static,
   {
-    crate::async_runtime::spawn(async move {
+    crate::async_runtime::spawn(binding::bind_task(async move {
       Self::return_task(
         self.webview,
         self.responder,
@@ -296,7 +308,7 @@ impl<R: Runtime> InvokeResolver<R> {
         self.error,
       )
       .await;
-    });
+    }));
   }
 
   /// Reply to the invoke promise with an async task which is already serialized.
@@ -304,7 +316,7 @@ impl<R: Runtime> InvokeResolver<R> {
   where
     F: Future<Output = Result<InvokeBody, InvokeError>> + Send + 'static,
   {
-    crate::async_runtime::spawn(async move {
+    crate::async_runtime::spawn(binding::bind_task(async move {
       let response = match task.await {
         Ok(ok) => InvokeResponse::Ok(ok),
         Err(err) => InvokeResponse::Err(err),
@@ -317,7 +329,7 @@ impl<R: Runtime> InvokeResolver<R> {
         self.callback,
         self.error,
       )
-    });
+    }));
   }
 
   /// Reply to the invoke promise with a serializable value.
@@ -438,6 +450,10 @@ pub struct InvokeMessage<R: Runtime> {
   pub(crate) payload: InvokeBody,
   /// The request headers.
   pub(crate) headers: HeaderMap,
+  /// The path of the local page that sent the message, `None` for a remote page.
+  pub(crate) page: Option<String>,
+  /// The window and webview that passed the ACL check, `None` if the invoke was not checked.
+  pub(crate) authorized: Option<AuthorizedWebview>,
 }
 
 impl<R: Runtime> Clone for InvokeMessage<R> {
@@ -448,6 +464,8 @@ impl<R: Runtime> Clone for InvokeMessage<R> {
       command: self.command.clone(),
       payload: self.payload.clone(),
       headers: self.headers.clone(),
+      page: self.page.clone(),
+      authorized: self.authorized.clone(),
     }
   }
 }
@@ -467,6 +485,8 @@ impl<R: Runtime> InvokeMessage<R> {
       command,
       payload,
       headers,
+      page: None,
+      authorized: None,
     }
   }
 
@@ -511,6 +531,13 @@ impl<R: Runtime> InvokeMessage<R> {
   pub fn headers(&self) -> &HeaderMap {
     &self.headers
   }
+
+  /// The window and webview that passed the ACL check for this invoke,
+  /// `None` if the command was invoked without checking the ACL.
+  #[inline(always)]
+  pub fn authorized(&self) -> Option<&AuthorizedWebview> {
+    self.authorized.as_ref()
+  }
 }
 
 /// The `Callback` type is the return value of the `transformCallback` JavaScript function.
@@ -139,7 +139,9 @@ pub fn get<R: Runtime>(manager: Arc<AppManager<R>>, label: String) -> UriSchemeP
         let mut r = http::Response::new(Vec::new().into());
         r.headers_mut().insert(
           ACCESS_CONTROL_ALLOW_HEADERS,
-          HeaderValue::from_static("Content-Type, Tauri-Callback, Tauri-Error, Tauri-Channel-Id"),
+          HeaderValue::from_static(
+            "Content-Type, Tauri-Callback, Tauri-Error, Tauri-Channel-Id, Tauri-Webview-Label",
+          ),
         );
         respond(r);
       }
@@ -443,3 +445,51 @@ fn parse_invoke_request<R: Runtime>(
 
   Ok(payload)
 }
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use http::{header::ACCESS_CONTROL_ALLOW_HEADERS, Method, Request};
+
+  use crate::{
+    app::UriSchemeResponder, ipc::channel::CHANNEL_ID_HEADER_NAME, sealed::ManagerBase,
+    test::mock_app, webview::TAURI_WEBVIEW_LABEL_HEADER_NAME,
+  };
+
+  #[test]
+  fn preflight_allows_the_ipc_headers() {
+    let app = mock_app();
+    let handler = super::get(app.manager_owned(), "main".into());
+
+    let allowed = Arc::new(Mutex::new(None));
+    let allowed_ = allowed.clone();
+    let request = Request::builder()
+      .method(Method::OPTIONS)
+      .uri("ipc://localhost/ping")
+      .body(Vec::new())
+      .unwrap();
+    handler(
+      request,
+      UriSchemeResponder(Box::new(move |response| {
+        *allowed_.lock().unwrap() = response
+          .headers()
+          .get(ACCESS_CONTROL_ALLOW_HEADERS)
+          .and_then(|value| value.to_str().ok())
+          .map(ToString::to_string);
+      })),
+    );
+
+    let allowed = allowed.lock().unwrap().clone().unwrap();
+    let allowed = allowed.split(", ").collect::<Vec<_>>();
+    for header in [
+      "Content-Type",
+      super::TAURI_CALLBACK_HEADER_NAME,
+      super::TAURI_ERROR_HEADER_NAME,
+      CHANNEL_ID_HEADER_NAME,
+      TAURI_WEBVIEW_LABEL_HEADER_NAME,
+    ] {
+      assert!(allowed.contains(&header), "{header} is not allowed");
+    }
+  }
+}
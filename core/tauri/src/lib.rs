@@ -16,6 +16,7 @@
 //! - **unstable**: Enables unstable features. Be careful, it might introduce breaking changes in future minor releases.
 //! - **tracing**: Enables [`tracing`](https://docs.rs/tracing/latest/tracing) for window startup, plugins, `Window::eval`, events, IPC, updater and custom protocol request handlers.
 //! - **test**: Enables the [`mod@test`] module exposing unit test helpers.
+//! - **acl-timing**: Measures the authorization of the IPC calls in release builds, see [`AppHandle::acl_timings`]. Always enabled on debug builds.
 //! - **objc-exception**: Wrap each msg_send! in a @try/@catch and panics if an exception is caught, preventing Objective-C from unwinding into Rust.
 //! - **linux-ipc-protocol**: Use custom protocol for faster IPC on Linux. Requires webkit2gtk v2.40 or above.
 //! - **linux-libxdo**: Enables linking to libxdo which enables Cut, Copy, Paste and SelectAll menu items to work on Linux.
@@ -76,6 +77,8 @@ pub use swift_rs;
 pub use tauri_macros::mobile_entry_point;
 pub use tauri_macros::{command, generate_handler};
 
+#[cfg(debug_assertions)]
+mod acl_inspector;
 pub(crate) mod app;
 pub mod async_runtime;
 pub mod command;
@@ -518,6 +521,21 @@ impl<A: Assets> Context<A> {
     &self.pattern
   }
 
+  /// The resolved Access Control List the runtime authority is created with.
+  #[inline(always)]
+  pub fn resolved_acl(&self) -> &Resolved {
+    &self.resolved_acl
+  }
+
+  /// A mutable reference to the resolved Access Control List.
+  ///
+  /// Applications that do not resolve their capabilities at build time can replace it
+  /// with one constructed by [`Resolved::builder`] before calling [`Builder::build`].
+  #[inline(always)]
+  pub fn resolved_acl_mut(&mut self) -> &mut Resolved {
+    &mut self.resolved_acl
+  }
+
   /// Create a new [`Context`] from the minimal required items.
   #[inline(always)]
   #[allow(clippy::too_many_arguments)]
@@ -689,6 +707,7 @@ pub trait Manager<R: Runtime>: sealed::ManagerBase<R> {
     tracing::instrument("app::emit::to", skip(self, payload))
   )]
   fn emit_to<S: Serialize + Clone>(&self, label: &str, event: &str, payload: S) -> Result<()> {
+    let label = utils::normalize_label(label);
     self
       .manager()
       .emit_filter(event, EventSource::Global, payload, |w| label == w.label())
@@ -925,6 +944,35 @@ pub trait Manager<R: Runtime>: sealed::ManagerBase<R> {
   fn path(&self) -> &crate::path::PathResolver<R> {
     self.state::<crate::path::PathResolver<R>>().inner()
   }
+
+  /// The runtime authority holding the resolved Access Control List.
+  ///
+  /// # Examples
+  ///
+  /// A plugin that only starts its background work if the app grants any of its commands:
+  ///
+  /// ```rust,no_run
+  /// use tauri::{
+  ///   plugin::{Builder, TauriPlugin},
+  ///   Manager, Runtime,
+  /// };
+  ///
+  /// struct Indexer;
+  ///
+  /// pub fn init<R: Runtime>() -> TauriPlugin<R> {
+  ///   Builder::new("indexer")
+  ///     .setup(|app, _api| {
+  ///       if app.acl().plugin_has_any_allowed_command("indexer") {
+  ///         app.manage(Indexer);
+  ///       }
+  ///       Ok(())
+  ///     })
+  ///     .build()
+  /// }
+  /// ```
+  fn acl(&self) -> &command::RuntimeAuthority {
+    &self.manager().runtime_authority
+  }
 }
 
 /// Prevent implementation details from leaking out of the [`Manager`] trait.
@@ -18,13 +18,14 @@ use tauri_utils::{
   assets::{AssetKey, CspHash},
   config::{Csp, CspDirectiveSources},
   html::{SCRIPT_NONCE_TOKEN, STYLE_NONCE_TOKEN},
+  normalize_label,
 };
 
 use crate::{
-  app::{AppHandle, GlobalWindowEventListener, OnPageLoad},
-  command::RuntimeAuthority,
+  app::{AppHandle, GlobalWindowEventListener, IsolationValidator, OnFirstUse, OnPageLoad},
+  command::{Origin, RuntimeAuthority, ScopeProvider},
   event::{assert_event_name_is_valid, Event, EventId, EventSource, Listeners},
-  ipc::{Invoke, InvokeHandler, InvokeResponder},
+  ipc::{ContextPolicy, Invoke, InvokeHandler, InvokeResponder},
   plugin::PluginStore,
   utils::{
     assets::Assets,
@@ -201,6 +202,9 @@ pub struct AppManager<R: Runtime> {
   /// Application pattern.
   pub pattern: Arc<Pattern>,
 
+  /// Classifies the pages sending IPC requests as local or remote, including the origins served by the app.
+  pub(crate) context_policy: ContextPolicy,
+
   /// Application Resources Table
   pub(crate) resources_table: Arc<Mutex<ResourceTable>>,
 }
@@ -233,6 +237,10 @@ impl<R: Runtime> AppManager<R> {
     plugins: PluginStore<R>,
     invoke_handler: Box<InvokeHandler<R>>,
     on_page_load: Option<Arc<OnPageLoad<R>>>,
+    on_first_use: Option<Arc<OnFirstUse<R>>>,
+    isolation_validators: HashMap<String, Arc<IsolationValidator>>,
+    scope_provider: Option<Arc<dyn ScopeProvider>>,
+    context_policy: ContextPolicy,
     uri_scheme_protocols: HashMap<String, Arc<webview::UriSchemeProtocol<R>>>,
     state: StateManager,
     window_event_listeners: Vec<GlobalWindowEventListener<R>>,
@@ -248,10 +256,12 @@ impl<R: Runtime> AppManager<R> {
       *key = uuid::Uuid::new_v4().to_string();
     }
 
-    Self {
-      runtime_authority: RuntimeAuthority::new(context.resolved_acl),
+    let mut manager = Self {
+      runtime_authority: RuntimeAuthority::new(context.resolved_acl)
+        .with_scope_provider(scope_provider),
       window: window::WindowManager {
         windows: Mutex::default(),
+        parents: Mutex::default(),
         default_icon: context.default_window_icon,
         event_listeners: Arc::new(window_event_listeners),
       },
@@ -259,7 +269,12 @@ impl<R: Runtime> AppManager<R> {
         webviews: Mutex::default(),
         invoke_handler,
         on_page_load,
+        on_first_use,
+        first_use_decisions: Default::default(),
+        isolation_validators,
+        navigations: Default::default(),
         uri_scheme_protocols: Mutex::new(uri_scheme_protocols),
+        uri_scheme_permissions: Default::default(),
         invoke_responder,
         invoke_initialization_script,
       },
@@ -286,7 +301,10 @@ impl<R: Runtime> AppManager<R> {
       package_info: context.package_info,
       pattern: Arc::new(context.pattern),
       resources_table: Arc::default(),
-    }
+      context_policy: ContextPolicy::default(),
+    };
+    manager.context_policy = context_policy.with_app_origins(&manager.app_origins());
+    manager
   }
 
   /// State managed by the application.
@@ -326,6 +344,38 @@ impl<R: Runtime> AppManager<R> {
     }
   }
 
+  /// The URLs of the origins served by the app, local for the [`ContextPolicy`].
+  fn app_origins(&self) -> Vec<Url> {
+    let mut origins = vec![
+      self.get_url().into_owned(),
+      self.protocol_url().into_owned(),
+    ];
+    if cfg!(dev) {
+      origins.push(Url::parse("http://tauri.localhost").unwrap());
+      origins.push(Url::parse("https://tauri.localhost").unwrap());
+    }
+    origins
+  }
+
+  /// Whether the IPC requests of the page loaded at the URL are local, see [`ContextPolicy`].
+  pub(crate) fn is_local_url(&self, url: &Url) -> bool {
+    self.context_policy.is_local(url)
+  }
+
+  /// Classifies the ACL origin of the IPC requests sent by the page loaded at the URL in the webview.
+  ///
+  /// Remote origins carry the navigation evidence recorded for the webview, see [`Origin::Remote`].
+  pub(crate) fn ipc_origin(&self, webview: &str, url: &Url, is_local: bool) -> Origin {
+    if is_local {
+      Origin::Local
+    } else {
+      Origin::Remote {
+        domain: url.domain().map(|d| d.to_string()).unwrap_or_default(),
+        navigation: self.webview.navigation_evidence(webview, url),
+      }
+    }
+  }
+
   fn csp(&self) -> Option<Csp> {
     if cfg!(feature = "custom-protocol") {
       self.config.tauri.security.csp.clone()
@@ -417,6 +467,46 @@ impl<R: Runtime> AppManager<R> {
     }
   }
 
+  /// Extends the `connect-src` directive of a CSP served to the given window with the remote origins its capabilities grant,
+  /// see [`crate::utils::config::SecurityConfig#structfield.capability_connect_src`].
+  pub(crate) fn extend_connect_src(&self, csp: String, window: &str) -> String {
+    let security = &self.config.tauri.security;
+    if !security.capability_connect_src
+      || !security
+        .dangerous_disable_asset_csp_modification
+        .can_modify("connect-src")
+    {
+      return csp;
+    }
+
+    let ancestors = self.window.ancestors(window);
+    let sources = self.runtime_authority.connect_src(window, &ancestors);
+    let mut directives: HashMap<String, CspDirectiveSources> = Csp::Policy(csp.clone()).into();
+    // without connect-src, the fetches fall back to default-src, or are not restricted at all
+    let Some(connect_src) = directives
+      .get("connect-src")
+      .or_else(|| directives.get("default-src"))
+      .cloned()
+    else {
+      return csp;
+    };
+    let connect_src = directives
+      .entry("connect-src".into())
+      .or_insert(connect_src);
+    let mut extended = false;
+    for source in sources {
+      if !connect_src.contains(&source) {
+        connect_src.push(source);
+        extended = true;
+      }
+    }
+    if extended {
+      Csp::DirectiveMap(directives).to_string()
+    } else {
+      csp
+    }
+  }
+
   pub(crate) fn listeners(&self) -> &Listeners<R> {
     &self.listeners
   }
@@ -475,6 +565,16 @@ impl<R: Runtime> AppManager<R> {
       .once(event, webview.and_then(|w| self.get_webview(&w)), handler)
   }
 
+  /// Whether the JavaScript listeners of the webview receive the events emitted by the command being run,
+  /// only the ones of the webview that passed the ACL check for its invoke with
+  /// [`SecurityConfig#structfield.bind_command_events`](crate::utils::config::SecurityConfig#structfield.bind_command_events).
+  ///
+  /// The Rust listeners are not restricted.
+  fn is_bound_event_target(&self, webview: &Webview<R>) -> bool {
+    !self.config.tauri.security.bind_command_events
+      || crate::ipc::binding::current().map_or(true, |bound| bound.webview() == webview.label())
+  }
+
   pub fn emit_filter<S, F>(
     &self,
     event: &str,
@@ -498,6 +598,7 @@ impl<R: Runtime> AppManager<R> {
       .values()
       .filter(|w| w.has_js_listener(&source, event))
       .filter(|w| filter(w))
+      .filter(|w| self.is_bound_event_target(w))
       .try_for_each(|webview| webview.emit_js(&emit_args))?;
 
     self.listeners().emit_filter(&emit_args, Some(filter))?;
@@ -522,6 +623,7 @@ impl<R: Runtime> AppManager<R> {
       .webviews_lock()
       .values()
       .filter(|w| w.has_js_listener(&source, event))
+      .filter(|w| self.is_bound_event_target(w))
       .try_for_each(|window| window.emit_js(&emit_args))?;
 
     self.listeners().emit(&emit_args)?;
@@ -530,7 +632,11 @@ impl<R: Runtime> AppManager<R> {
   }
 
   pub fn get_window(&self, label: &str) -> Option<Window<R>> {
-    self.window.windows_lock().get(label).cloned()
+    self
+      .window
+      .windows_lock()
+      .get(normalize_label(label).as_ref())
+      .cloned()
   }
 
   pub fn get_focused_window(&self) -> Option<Window<R>> {
@@ -543,9 +649,13 @@ impl<R: Runtime> AppManager<R> {
   }
 
   pub(crate) fn on_window_close(&self, label: &str) {
-    if let Some(window) = self.window.windows_lock().remove(label) {
+    let window = self.window.windows_lock().remove(label);
+    if let Some(window) = window {
+      self.runtime_authority.forget_ephemeral(window.window.id);
+      self.window.forget_parent_links(window.window.id);
       for webview in window.webviews() {
         self.webview.webviews_lock().remove(webview.label());
+        self.webview.forget(webview.label());
       }
     }
   }
@@ -555,7 +665,11 @@ impl<R: Runtime> AppManager<R> {
   }
 
   pub fn get_webview(&self, label: &str) -> Option<Webview<R>> {
-    self.webview.webviews_lock().get(label).cloned()
+    self
+      .webview
+      .webviews_lock()
+      .get(normalize_label(label).as_ref())
+      .cloned()
   }
 
   pub fn webviews(&self) -> HashMap<String, Webview<R>> {
@@ -630,6 +744,47 @@ mod test {
   const APP_LISTEN_GLOBAL_ID: &str = "App::listen_global";
   const TEST_EVENT_NAME: &str = "event";
 
+  #[test]
+  fn connect_src_is_extended_per_window() {
+    use crate::sealed::ManagerBase;
+    use tauri_utils::acl::{resolved::Resolved, ExecutionContext};
+
+    let remote = |domain: &str| ExecutionContext::Remote {
+      domain: domain.parse().unwrap(),
+      attestation: None,
+    };
+    let mut context = crate::test::mock_context(crate::test::noop_assets());
+    context.config.tauri.security.capability_connect_src = true;
+    context.resolved_acl = Resolved::builder()
+      .allow_command("plugin:http|fetch", remote("api.tauri.app"), ["main"])
+      .unwrap()
+      .allow_command("plugin:http|fetch", remote("*.partner.app"), ["partner-*"])
+      .unwrap()
+      .build();
+    let app = crate::test::mock_builder().build(context).unwrap();
+    let manager = app.manager();
+
+    assert_eq!(
+      manager.extend_connect_src("connect-src ipc: https://api.tauri.app".into(), "main"),
+      "connect-src ipc: https://api.tauri.app"
+    );
+    assert_eq!(
+      manager.extend_connect_src("connect-src ipc:".into(), "partner-1"),
+      "connect-src ipc: https://*.partner.app"
+    );
+    // connect-src inherits the default-src sources when it is not set
+    let directives: std::collections::HashMap<_, _> =
+      super::Csp::Policy(manager.extend_connect_src("default-src 'self'".into(), "main")).into();
+    assert_eq!(
+      Vec::<String>::from(directives["connect-src"].clone()),
+      ["'self'", "https://api.tauri.app"]
+    );
+    assert_eq!(
+      manager.extend_connect_src("script-src 'self'".into(), "main"),
+      "script-src 'self'"
+    );
+  }
+
   #[test]
   fn check_get_url() {
     let context = generate_context!("test/fixture/src-tauri/tauri.conf.json", crate);
@@ -638,6 +793,10 @@ mod test {
       PluginStore::default(),
       Box::new(|_| false),
       None,
+      None,
+      Default::default(),
+      None,
+      Default::default(),
       Default::default(),
       StateManager::new(),
       Default::default(),
@@ -821,4 +980,106 @@ mod test {
     }
     assert_events(&received, &[other_webview_listen_id]);
   }
+
+  #[test]
+  fn window_ancestors() {
+    let app = mock_app();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let popup = WebviewWindowBuilder::new(&app, "popup", Default::default())
+      .parent(main.as_ref().window())
+      .build()
+      .unwrap();
+    let preview = WebviewWindowBuilder::new(&app, "preview", Default::default())
+      .parent(popup.as_ref().window())
+      .build()
+      .unwrap();
+
+    let windows = &app.handle().manager.window;
+    assert_eq!(windows.ancestors("preview"), ["popup", "main"]);
+    assert_eq!(windows.ancestors("main"), Vec::<String>::new());
+    assert_eq!(preview.as_ref().window().parent().unwrap().label(), "popup");
+
+    // cycles are not walked twice
+    main
+      .as_ref()
+      .window()
+      .set_parent(Some(preview.as_ref().window()));
+    assert_eq!(windows.ancestors("preview"), ["popup", "main"]);
+    assert_eq!(windows.ancestors("main"), ["preview", "popup"]);
+
+    popup.as_ref().window().set_parent(None);
+    assert_eq!(windows.ancestors("preview"), ["popup"]);
+  }
+
+  #[test]
+  fn reused_parent_label_does_not_inherit_children() {
+    let app = mock_app();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let popup = WebviewWindowBuilder::new(&app, "popup", Default::default())
+      .parent(main.as_ref().window())
+      .build()
+      .unwrap();
+
+    let manager = &app.handle().manager;
+    assert_eq!(manager.window.ancestors("popup"), ["main"]);
+
+    // the children of a closed window become root windows
+    manager.on_window_close("main");
+    assert_eq!(manager.window.ancestors("popup"), Vec::<String>::new());
+    assert!(popup.as_ref().window().parent().is_none());
+
+    // a new window taking the label is not the parent of the previous window children
+    let _main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    assert_eq!(manager.window.ancestors("popup"), Vec::<String>::new());
+    assert!(popup.as_ref().window().parent().is_none());
+    assert!(manager.window.parents.lock().unwrap().is_empty());
+  }
+
+  #[test]
+  fn remote_lockdown_gates_the_ipc_bridge_per_navigation() {
+    use crate::sealed::ManagerBase;
+    use tauri_runtime::webview::{PendingWebview, WebviewAttributes};
+    use tauri_utils::config::WebviewUrl;
+
+    let scripts = |lockdown_remote: bool| {
+      let mut context = crate::test::mock_context(crate::test::noop_assets());
+      context.resolved_acl.lockdown_remote = lockdown_remote;
+      let app = crate::test::mock_builder().build(context).unwrap();
+      let manager = app.manager();
+      // the webview starts on a local page, but can navigate to a remote one afterwards
+      let pending = PendingWebview::new(
+        WebviewAttributes::new(WebviewUrl::App("index.html".into())),
+        "main",
+      )
+      .unwrap();
+      let pending = manager
+        .webview
+        .prepare_webview(&app, pending, "main", &["main".into()], &[])
+        .unwrap();
+      let invoke_script = manager.webview.invoke_initialization_script.clone();
+      (
+        pending.webview_attributes.initialization_scripts,
+        invoke_script,
+      )
+    };
+
+    let (scripts_, invoke_script) = scripts(false);
+    assert!(scripts_.contains(&invoke_script));
+
+    // the bridge is still injected, but only set up when the page origin is local
+    let (scripts_, invoke_script) = scripts(true);
+    assert!(!scripts_.contains(&invoke_script));
+    let bridge = scripts_
+      .iter()
+      .find(|script| script.contains(&invoke_script))
+      .unwrap();
+    assert!(bridge.contains("localOrigins.includes"));
+    assert!(bridge.contains(r#""tauri://localhost""#));
+  }
 }
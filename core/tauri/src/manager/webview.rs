@@ -12,16 +12,20 @@ use std::{
 
 use serde::Serialize;
 use serialize_to_javascript::{default_template, DefaultTemplate, Template};
-use tauri_runtime::webview::{DetachedWebview, PendingWebview};
-use tauri_utils::config::WebviewUrl;
+use tauri_runtime::{
+  webview::{DetachedWebview, PendingWebview},
+  window::WindowId,
+};
+use tauri_utils::{acl::capability::NavigationEvidence, config::WebviewUrl};
 use url::Url;
 
 use crate::{
-  app::{OnPageLoad, UriSchemeResponder},
+  app::{IsolationValidator, OnFirstUse, OnPageLoad, UriSchemeResponder},
+  command::{FirstUseDecision, GlobalApi},
   ipc::{InvokeHandler, InvokeResponder},
   pattern::PatternJavascript,
   sealed::ManagerBase,
-  webview::PageLoadPayload,
+  webview::{InvokeRequest, PageLoadPayload},
   AppHandle, EventLoopMessage, Manager, Runtime, Webview, Window,
 };
 
@@ -70,8 +74,21 @@ pub struct WebviewManager<R: Runtime> {
   pub invoke_handler: Box<InvokeHandler<R>>,
   /// The page load hook, invoked when the webview performs a navigation.
   pub on_page_load: Option<Arc<OnPageLoad<R>>>,
+  /// The hook consulted the first time a webview uses a class of commands.
+  pub on_first_use: Option<Arc<OnFirstUse<R>>>,
+  /// The cached decisions of the first use hook, keyed by the runtime id of the webview window,
+  /// webview label and command class.
+  ///
+  /// A webview reusing the label of a destroyed one never gets its decisions, see [`Self::forget`].
+  pub first_use_decisions: Mutex<HashMap<(WindowId, String, String), bool>>,
+  /// The validators of the IPC requests, keyed by identifier.
+  pub isolation_validators: HashMap<String, Arc<IsolationValidator>>,
+  /// The origin each webview last navigated to and what it observed, keyed by webview label.
+  pub navigations: Mutex<HashMap<String, (url::Origin, NavigationEvidence)>>,
   /// The webview protocols available to all webviews.
   pub uri_scheme_protocols: Mutex<HashMap<String, Arc<UriSchemeProtocol<R>>>>,
+  /// The commands a webview must be allowed to load the URI scheme protocols, keyed by URI scheme.
+  pub uri_scheme_permissions: Mutex<HashMap<String, String>>,
 
   /// Responder for invoke calls.
   pub invoke_responder: Option<Arc<InvokeResponder<R>>>,
@@ -91,6 +108,95 @@ impl<R: Runtime> fmt::Debug for WebviewManager<R> {
 }
 
 impl<R: Runtime> WebviewManager<R> {
+  /// Consults the first use hook for the command classes the webview has not used yet.
+  ///
+  /// Returns the first class the webview is not allowed to use.
+  pub(crate) fn check_first_use(&self, webview: &Webview<R>, classes: &[String]) -> Option<String> {
+    let on_first_use = self.on_first_use.as_ref()?;
+
+    for class in classes {
+      let key = (
+        webview.window.window.id,
+        webview.label().to_string(),
+        class.clone(),
+      );
+      let cached = self.first_use_decisions.lock().unwrap().get(&key).copied();
+      let allowed = match cached {
+        Some(allowed) => allowed,
+        // the lock is not held while the hook runs since it might wait for the user consent
+        None => match on_first_use(webview, class) {
+          FirstUseDecision::Allow => {
+            self.first_use_decisions.lock().unwrap().insert(key, true);
+            true
+          }
+          FirstUseDecision::Deny => {
+            self.first_use_decisions.lock().unwrap().insert(key, false);
+            false
+          }
+          FirstUseDecision::Prompt => false,
+        },
+      };
+
+      if !allowed {
+        return Some(class.clone());
+      }
+    }
+
+    None
+  }
+
+  /// Runs the validators of the allowed command on the IPC request,
+  /// returning the error of the first validator failing along with its identifier.
+  ///
+  /// The validators are registered on app startup, see [`crate::Builder::isolation_validator`].
+  pub(crate) fn validate(
+    &self,
+    request: &InvokeRequest,
+    validators: &[String],
+  ) -> Result<(), (String, String)> {
+    for id in validators {
+      if let Some(validator) = self.isolation_validators.get(id) {
+        validator(request).map_err(|error| (id.clone(), error))?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Records the navigation of a webview, used to verify the attestations required by remote capabilities.
+  pub(crate) fn record_navigation(&self, label: &str, url: &Url) {
+    // the runtimes do not expose the response headers nor the certificates of a navigation yet,
+    // so attestations cannot be verified and remote capabilities requiring them fail closed
+    let evidence = NavigationEvidence::default();
+    self
+      .navigations
+      .lock()
+      .unwrap()
+      .insert(label.into(), (url.origin(), evidence));
+  }
+
+  /// Forgets what was recorded for the webview with the given label, when it is destroyed
+  /// or before a new webview takes its label, so the new webview does not inherit
+  /// the first use decisions nor the navigation evidence of the previous one.
+  pub(crate) fn forget(&self, label: &str) {
+    self.navigations.lock().unwrap().remove(label);
+    self
+      .first_use_decisions
+      .lock()
+      .unwrap()
+      .retain(|(_, webview, _), _| webview != label);
+  }
+
+  /// What the webview observed while navigating to the origin of the URL, if it was recorded.
+  pub(crate) fn navigation_evidence(&self, label: &str, url: &Url) -> Option<NavigationEvidence> {
+    self
+      .navigations
+      .lock()
+      .unwrap()
+      .get(label)
+      .filter(|(origin, _)| *origin == url.origin())
+      .map(|(_, evidence)| evidence.clone())
+  }
+
   pub(crate) fn register_uri_scheme_protocol<N: Into<String>>(
     &self,
     uri_scheme: N,
@@ -104,6 +210,37 @@ impl<R: Runtime> WebviewManager<R> {
       .insert(uri_scheme, protocol);
   }
 
+  pub(crate) fn require_uri_scheme_permission<N: Into<String>, C: Into<String>>(
+    &self,
+    uri_scheme: N,
+    command: C,
+  ) {
+    self
+      .uri_scheme_permissions
+      .lock()
+      .unwrap()
+      .insert(uri_scheme.into(), command.into());
+  }
+
+  /// Whether the webview is allowed to load the URI scheme protocol,
+  /// see [`crate::Builder::require_uri_scheme_permission`].
+  ///
+  /// The protocols without a required permission are allowed to all webviews.
+  /// The requests of a webview that is not registered yet are denied.
+  pub(crate) fn is_uri_scheme_allowed(&self, label: &str, uri_scheme: &str) -> bool {
+    let Some(command) = self
+      .uri_scheme_permissions
+      .lock()
+      .unwrap()
+      .get(uri_scheme)
+      .cloned()
+    else {
+      return true;
+    };
+    let webview = self.webviews_lock().get(label).cloned();
+    webview.is_some_and(|webview| webview.is_command_allowed(&command))
+  }
+
   /// Get a locked handle to the webviews.
   pub(crate) fn webviews_lock(&self) -> MutexGuard<'_, HashMap<String, Webview<R>>> {
     self.webviews.lock().expect("poisoned webview manager")
@@ -134,14 +271,18 @@ impl<R: Runtime> WebviewManager<R> {
 
     let mut webview_attributes = pending.webview_attributes;
 
-    let ipc_init = IpcJavascript {
-      isolation_origin: &match &*app_manager.pattern {
-        #[cfg(feature = "isolation")]
-        crate::Pattern::Isolation { schema, .. } => crate::pattern::format_real_schema(schema),
-        _ => "".to_string(),
-      },
-    }
-    .render_default(&Default::default())?;
+    let ipc_init = self.ipc_bridge_script(
+      app_manager,
+      &IpcJavascript {
+        isolation_origin: &match &*app_manager.pattern {
+          #[cfg(feature = "isolation")]
+          crate::Pattern::Isolation { schema, .. } => crate::pattern::format_real_schema(schema),
+          _ => "".to_string(),
+        },
+      }
+      .render_default(&Default::default())?
+      .into_string(),
+    )?;
 
     let mut webview_labels = webview_labels.to_vec();
     if !webview_labels.iter().any(|w| w.label == label) {
@@ -150,9 +291,8 @@ impl<R: Runtime> WebviewManager<R> {
         label: label.to_string(),
       });
     }
-    webview_attributes = webview_attributes
-      .initialization_script(
-        r#"
+    webview_attributes = webview_attributes.initialization_script(
+      r#"
         if (!window.__TAURI_INTERNALS__) {
           Object.defineProperty(window, '__TAURI_INTERNALS__', {
             value: {
@@ -161,8 +301,9 @@ impl<R: Runtime> WebviewManager<R> {
           })
         }
       "#,
-      )
-      .initialization_script(&self.invoke_initialization_script)
+    );
+    webview_attributes = webview_attributes
+      .initialization_script(&self.ipc_bridge_script(app_manager, &self.invoke_initialization_script)?)
       .initialization_script(&format!(
         r#"
           Object.defineProperty(window.__TAURI_INTERNALS__, 'metadata', {{
@@ -181,12 +322,20 @@ impl<R: Runtime> WebviewManager<R> {
       ))
       .initialization_script(&self.initialization_script(
         app_manager,
-        &ipc_init.into_string(),
+        &ipc_init,
         &pattern_init.into_string(),
         &plugin_init,
         is_init_global,
       )?);
 
+    if is_init_global {
+      webview_attributes = webview_attributes.initialization_script(&self.global_api_script(
+        app_manager,
+        window_label,
+        label,
+      )?);
+    }
+
     #[cfg(feature = "isolation")]
     if let crate::Pattern::Isolation { schema, .. } = &*app_manager.pattern {
       webview_attributes = webview_attributes.initialization_script(
@@ -207,12 +356,24 @@ impl<R: Runtime> WebviewManager<R> {
       registered_scheme_protocols.push(uri_scheme.clone());
       let protocol = protocol.clone();
       let app_handle = Mutex::new(manager.app_handle().clone());
+      let scheme = uri_scheme.clone();
+      let webview_label = label.to_string();
       pending.register_uri_scheme_protocol(uri_scheme.clone(), move |p, responder| {
-        (protocol.protocol)(
-          &app_handle.lock().unwrap(),
-          p,
-          UriSchemeResponder(responder),
-        )
+        let app_handle = app_handle.lock().unwrap();
+        if !app_handle
+          .manager
+          .webview
+          .is_uri_scheme_allowed(&webview_label, &scheme)
+        {
+          responder(
+            http::Response::builder()
+              .status(http::StatusCode::FORBIDDEN)
+              .body(Cow::Borrowed(&[][..]))
+              .unwrap(),
+          );
+          return;
+        }
+        (protocol.protocol)(&app_handle, p, UriSchemeResponder(responder))
       });
     }
 
@@ -240,6 +401,7 @@ impl<R: Runtime> WebviewManager<R> {
       let web_resource_request_handler = pending.web_resource_request_handler.take();
       let protocol = crate::protocol::tauri::get(
         manager.manager_owned(),
+        window_label,
         &window_origin,
         web_resource_request_handler,
       );
@@ -379,6 +541,67 @@ impl<R: Runtime> WebviewManager<R> {
     .map_err(Into::into)
   }
 
+  /// Gates the IPC bridge script on the page origin when the remote IPC is disabled,
+  /// so a webview navigating from a local page to a remote one does not expose the bridge to it.
+  ///
+  /// The initialization scripts run on every page load, so the origin is checked on each navigation.
+  /// The IPC handler still rejects the remote invokes, see [`RuntimeAuthority::authorize`](crate::command::RuntimeAuthority::authorize).
+  fn ipc_bridge_script(&self, app_manager: &AppManager<R>, script: &str) -> crate::Result<String> {
+    #[derive(Template)]
+    #[default_template("../../scripts/local-only.js")]
+    struct LocalOnlyJavascript<'a> {
+      local_origins: &'a [String],
+      #[raw]
+      script: &'a str,
+    }
+
+    if !app_manager.runtime_authority.lockdown_remote() {
+      return Ok(script.to_string());
+    }
+
+    LocalOnlyJavascript {
+      local_origins: &local_origins(app_manager),
+      script,
+    }
+    .render_default(&Default::default())
+    .map(|s| s.into_string())
+    .map_err(Into::into)
+  }
+
+  /// The script removing from `window.__TAURI__` the plugins and functions the webview is not allowed to use,
+  /// picking the allowed commands of the page context on each navigation.
+  fn global_api_script(
+    &self,
+    app_manager: &AppManager<R>,
+    window_label: &str,
+    label: &str,
+  ) -> crate::Result<String> {
+    #[derive(Template)]
+    #[default_template("../../scripts/global-api.js")]
+    struct GlobalApiJavascript<'a> {
+      acl: &'a GlobalApi<'a>,
+      plugins: &'a [&'static str],
+      local_origins: &'a [String],
+    }
+
+    let local_origins = local_origins(app_manager);
+    let ancestors = app_manager.window.ancestors(window_label);
+    GlobalApiJavascript {
+      acl: &app_manager
+        .runtime_authority
+        .global_api(window_label, label, &ancestors),
+      plugins: &app_manager
+        .plugins
+        .lock()
+        .expect("poisoned plugin store")
+        .names(),
+      local_origins: &local_origins,
+    }
+    .render_default(&Default::default())
+    .map(|s| s.into_string())
+    .map_err(Into::into)
+  }
+
   pub fn prepare_webview<M: Manager<R>>(
     &self,
     manager: &M,
@@ -390,6 +613,8 @@ impl<R: Runtime> WebviewManager<R> {
     if self.webviews_lock().contains_key(&pending.label) {
       return Err(crate::Error::WebviewLabelAlreadyExists(pending.label));
     }
+    // the label is free, anything recorded for it belongs to a destroyed webview
+    self.forget(&pending.label);
 
     let app_manager = manager.manager();
 
@@ -533,7 +758,7 @@ impl<R: Runtime> WebviewManager<R> {
         }
       }
       let webview = app_manager.webview.webviews_lock().get(&label).cloned();
-      if let Some(w) = webview {
+      let allowed = if let Some(w) = webview {
         app_manager
           .plugins
           .lock()
@@ -541,7 +766,11 @@ impl<R: Runtime> WebviewManager<R> {
           .on_navigation(&w, url)
       } else {
         true
+      };
+      if allowed {
+        app_manager.webview.record_navigation(&label, url);
       }
+      allowed
     }));
 
     Ok(pending)
@@ -560,6 +789,11 @@ impl<R: Runtime> WebviewManager<R> {
         .webviews_lock()
         .insert(webview.label().to_string(), webview.clone());
     }
+    #[cfg(debug_assertions)]
+    webview
+      .manager()
+      .runtime_authority
+      .record_label(webview.label());
 
     // let plugins know that a new webview has been added to the manager
     let manager = webview.manager_owned().clone();
@@ -597,3 +831,12 @@ impl<R: Runtime> WebviewManager<R> {
     self.webviews_lock().keys().cloned().collect()
   }
 }
+
+/// The origins of the pages classified as local, compared to the page origin by the initialization scripts.
+fn local_origins<R: Runtime>(app_manager: &AppManager<R>) -> Vec<String> {
+  app_manager
+    .context_policy
+    .local_origins()
+    .map(ToString::to_string)
+    .collect()
+}
@@ -14,7 +14,7 @@ use tauri_runtime::{
   window::WindowBuilder,
   window::{
     dpi::{PhysicalPosition, PhysicalSize},
-    DetachedWindow, FileDropEvent, PendingWindow,
+    DetachedWindow, FileDropEvent, PendingWindow, WindowId,
   },
 };
 
@@ -37,8 +37,16 @@ const WINDOW_FILE_DROP_EVENT: &str = "tauri://file-drop";
 const WINDOW_FILE_DROP_HOVER_EVENT: &str = "tauri://file-drop-hover";
 const WINDOW_FILE_DROP_CANCELLED_EVENT: &str = "tauri://file-drop-cancelled";
 
+/// The core permission command granting the dropped file paths to a webview,
+/// allowed by `webview:allow-drag-drop-paths` which is part of `webview:default`.
+pub(crate) const DRAG_DROP_PATHS_COMMAND: &str = "plugin:webview|drag_drop_paths";
+
 pub struct WindowManager<R: Runtime> {
   pub windows: Mutex<HashMap<String, Window<R>>>,
+  /// The parent of each window, see [`Window::set_parent`].
+  ///
+  /// Keyed by window id, so a window reusing the label of a closed one does not inherit its links.
+  pub parents: Mutex<HashMap<WindowId, WindowId>>,
   pub default_icon: Option<Icon>,
   /// Window event listeners to all windows.
   pub event_listeners: Arc<Vec<GlobalWindowEventListener<R>>>,
@@ -58,6 +66,66 @@ impl<R: Runtime> WindowManager<R> {
     self.windows.lock().expect("poisoned window manager")
   }
 
+  pub(crate) fn set_parent(&self, window: WindowId, parent: Option<WindowId>) {
+    let mut parents = self.parents.lock().expect("poisoned window manager");
+    match parent {
+      Some(parent) => parents.insert(window, parent),
+      None => parents.remove(&window),
+    };
+  }
+
+  pub(crate) fn parent(&self, window: WindowId) -> Option<Window<R>> {
+    let parent = *self
+      .parents
+      .lock()
+      .expect("poisoned window manager")
+      .get(&window)?;
+    self
+      .windows_lock()
+      .values()
+      .find(|window| window.window.id == parent)
+      .cloned()
+  }
+
+  /// Drops the links from and to a closed window, its children become root windows.
+  pub(crate) fn forget_parent_links(&self, window: WindowId) {
+    self
+      .parents
+      .lock()
+      .expect("poisoned window manager")
+      .retain(|child, parent| *child != window && *parent != window);
+  }
+
+  /// Labels of the ancestors of the given window, ordered from its parent to the root window.
+  pub(crate) fn ancestors(&self, label: &str) -> Vec<String> {
+    let windows = self.windows_lock();
+    let Some(window) = windows.get(label) else {
+      return Vec::new();
+    };
+    let labels = windows
+      .values()
+      .map(|window| (window.window.id, window.label()))
+      .collect::<HashMap<_, _>>();
+
+    let parents = self.parents.lock().expect("poisoned window manager");
+    let mut ancestors: Vec<String> = Vec::new();
+    let mut visited = vec![window.window.id];
+    let mut current = window.window.id;
+    while let Some(&parent) = parents.get(&current) {
+      // parent chains should not have cycles, but stop walking them if they do
+      if visited.contains(&parent) {
+        break;
+      }
+      let Some(label) = labels.get(&parent) else {
+        break;
+      };
+      ancestors.push(label.to_string());
+      visited.push(parent);
+      current = parent;
+    }
+    ancestors
+  }
+
   pub fn prepare_window(
     &self,
     mut pending: PendingWindow<EventLoopMessage, R>,
@@ -109,6 +177,11 @@ impl<R: Runtime> WindowManager<R> {
         .windows_lock()
         .insert(window.label().to_string(), window.clone());
     }
+    #[cfg(debug_assertions)]
+    window
+      .manager
+      .runtime_authority
+      .record_label(window.label());
 
     // let plugins know that a new window has been added to the manager
     let manager = window.manager.clone();
@@ -131,9 +204,50 @@ impl<R: Runtime> WindowManager<R> {
 }
 
 #[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 struct FileDropPayload<'a> {
-  paths: &'a Vec<PathBuf>,
+  paths: &'a [PathBuf],
   position: &'a PhysicalPosition<f64>,
+  /// Set instead of the paths for the webviews not allowed [`DRAG_DROP_PATHS_COMMAND`].
+  #[serde(skip_serializing_if = "std::ops::Not::not")]
+  files_dropped: bool,
+}
+
+/// Emits a file drop event to the webviews of the window,
+/// stripping the paths for the webviews whose capabilities do not grant them.
+fn emit_file_drop<R: Runtime>(
+  window: &Window<R>,
+  event: &str,
+  paths: &[PathBuf],
+  position: &PhysicalPosition<f64>,
+) -> crate::Result<()> {
+  let (granted, stripped): (Vec<_>, Vec<_>) = window
+    .webviews()
+    .into_iter()
+    .partition(|webview| webview.is_command_allowed(DRAG_DROP_PATHS_COMMAND));
+  if !granted.is_empty() {
+    window.emit_filter(
+      event,
+      FileDropPayload {
+        paths,
+        position,
+        files_dropped: false,
+      },
+      |webview| granted.contains(webview),
+    )?;
+  }
+  if !stripped.is_empty() {
+    window.emit_filter(
+      event,
+      FileDropPayload {
+        paths: &[],
+        position,
+        files_dropped: !paths.is_empty(),
+      },
+      |webview| stripped.contains(webview),
+    )?;
+  }
+  Ok(())
 }
 
 fn on_window_event<R: Runtime>(
@@ -189,20 +303,25 @@ fn on_window_event<R: Runtime>(
     )?,
     WindowEvent::FileDrop(event) => match event {
       FileDropEvent::Hovered { paths, position } => {
-        let payload = FileDropPayload { paths, position };
-        window.emit(WINDOW_FILE_DROP_HOVER_EVENT, payload)?
+        emit_file_drop(window, WINDOW_FILE_DROP_HOVER_EVENT, paths, position)?;
       }
       FileDropEvent::Dropped { paths, position } => {
-        let scopes = window.state::<Scopes>();
-        for path in paths {
-          if path.is_file() {
-            let _ = scopes.allow_file(path);
-          } else {
-            let _ = scopes.allow_directory(path, false);
+        // the dropped paths are only added to the scopes when a webview can know them
+        if window
+          .webviews()
+          .iter()
+          .any(|webview| webview.is_command_allowed(DRAG_DROP_PATHS_COMMAND))
+        {
+          let scopes = window.state::<Scopes>();
+          for path in paths {
+            if path.is_file() {
+              let _ = scopes.allow_file(path);
+            } else {
+              let _ = scopes.allow_directory(path, false);
+            }
           }
         }
-        let payload = FileDropPayload { paths, position };
-        window.emit(WINDOW_FILE_DROP_EVENT, payload)?
+        emit_file_drop(window, WINDOW_FILE_DROP_EVENT, paths, position)?;
       }
       FileDropEvent::Cancelled => window.emit(WINDOW_FILE_DROP_CANCELLED_EVENT, ())?,
       _ => unimplemented!(),
@@ -218,3 +337,79 @@ struct ScaleFactorChanged {
   scale_factor: f64,
   size: PhysicalSize<u32>,
 }
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+  };
+
+  use tauri_runtime::window::{dpi::PhysicalPosition, FileDropEvent};
+  use tauri_utils::acl::{
+    resolved::{CommandKey, ResolvedCommand},
+    ExecutionContext,
+  };
+
+  use super::{on_window_event, DRAG_DROP_PATHS_COMMAND, WINDOW_FILE_DROP_EVENT};
+  use crate::{
+    sealed::ManagerBase,
+    test::{mock_builder, mock_context, noop_assets},
+    WebviewWindowBuilder, WindowEvent,
+  };
+
+  #[test]
+  fn file_drop_paths_are_stripped_without_permission() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: DRAG_DROP_PATHS_COMMAND.into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["main".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+    let app = mock_builder().build(context).unwrap();
+
+    let payloads = Arc::new(Mutex::new(Vec::new()));
+    for label in ["main", "other"] {
+      let webview = WebviewWindowBuilder::new(&app, label, Default::default())
+        .build()
+        .unwrap();
+      let payloads = payloads.clone();
+      webview.listen(WINDOW_FILE_DROP_EVENT, move |event| {
+        payloads
+          .lock()
+          .unwrap()
+          .push((label, event.payload().to_string()));
+      });
+    }
+
+    let event = WindowEvent::FileDrop(FileDropEvent::Dropped {
+      paths: vec![PathBuf::from("/home/user/secret.txt")],
+      position: PhysicalPosition::new(1., 2.),
+    });
+    for label in ["main", "other"] {
+      let window = app.manager().get_window(label).unwrap();
+      on_window_event(&window, app.manager(), &event).unwrap();
+    }
+
+    let mut payloads = payloads.lock().unwrap().clone();
+    payloads.sort();
+    assert_eq!(
+      payloads,
+      [
+        (
+          "main",
+          r#"{"paths":["/home/user/secret.txt"],"position":{"x":1.0,"y":2.0}}"#.to_string()
+        ),
+        (
+          "other",
+          r#"{"paths":[],"position":{"x":1.0,"y":2.0},"filesDropped":true}"#.to_string()
+        ),
+      ]
+    );
+  }
+}
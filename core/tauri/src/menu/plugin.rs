@@ -147,10 +147,7 @@ impl CheckMenuItemPayload {
       let handler = handler.channel_on(webview.clone());
       webview
         .state::<MenuChannels>()
-        .0
-        .lock()
-        .unwrap()
-        .insert(item.id().clone(), handler);
+        .insert(item.id().clone(), webview, handler);
     }
 
     item
@@ -199,10 +196,7 @@ impl IconMenuItemPayload {
       let handler = handler.channel_on(webview.clone());
       webview
         .state::<MenuChannels>()
-        .0
-        .lock()
-        .unwrap()
-        .insert(item.id().clone(), handler);
+        .insert(item.id().clone(), webview, handler);
     }
 
     item
@@ -238,10 +232,7 @@ impl MenuItemPayload {
       let handler = handler.channel_on(webview.clone());
       webview
         .state::<MenuChannels>()
-        .0
-        .lock()
-        .unwrap()
-        .insert(item.id().clone(), handler);
+        .insert(item.id().clone(), webview, handler);
     }
 
     item
@@ -428,7 +419,7 @@ fn new<R: Runtime>(
     }
   };
 
-  channels.0.lock().unwrap().insert(id.clone(), handler);
+  channels.insert(id.clone(), &webview, handler);
 
   Ok((rid, id))
 }
@@ -834,19 +825,56 @@ fn set_icon<R: Runtime>(
   }
 }
 
-struct MenuChannels(Mutex<HashMap<MenuId, Channel>>);
+/// The core permission command granting the menu events to the webview that created the menu item,
+/// allowed by `menu:allow-events` which is part of `menu:default`.
+pub(crate) const MENU_EVENTS_COMMAND: &str = "plugin:menu|events";
+
+/// The channels of the menu items created by the webviews, along with the label of their webview.
+#[derive(Default)]
+struct MenuChannels(Mutex<HashMap<MenuId, (String, Channel)>>);
+
+impl MenuChannels {
+  fn insert<R: Runtime>(&self, id: MenuId, webview: &Webview<R>, channel: Channel) {
+    self
+      .0
+      .lock()
+      .unwrap()
+      .insert(id, (webview.label().to_string(), channel));
+  }
+}
+
+/// Forwards a menu event to the webview that created the item, if its capabilities grant [`MENU_EVENTS_COMMAND`].
+///
+/// The Rust handlers registered with [`AppHandle::on_menu_event`] and [`Window::on_menu_event`] are not affected.
+fn forward_menu_event<R: Runtime>(app: &AppHandle<R>, event: &MenuEvent) {
+  let Some((label, channel)) = app
+    .state::<MenuChannels>()
+    .0
+    .lock()
+    .unwrap()
+    .get(&event.id)
+    .cloned()
+  else {
+    return;
+  };
+  if app
+    .manager()
+    .get_webview(&label)
+    .is_some_and(|webview| webview.is_command_allowed(MENU_EVENTS_COMMAND))
+  {
+    let _ = channel.send(&event.id);
+  }
+}
 
 pub(crate) fn init<R: Runtime>() -> TauriPlugin<R> {
   Builder::new("menu")
     .setup(|app, _api| {
-      app.manage(MenuChannels(Mutex::default()));
+      app.manage(MenuChannels::default());
       Ok(())
     })
     .on_event(|app, e| {
       if let RunEvent::MenuEvent(e) = e {
-        if let Some(channel) = app.state::<MenuChannels>().0.lock().unwrap().get(&e.id) {
-          let _ = channel.send(&e.id);
-        }
+        forward_menu_event(app, e);
       }
     })
     .invoke_handler(crate::generate_handler![
@@ -875,3 +903,78 @@ pub(crate) fn init<R: Runtime>() -> TauriPlugin<R> {
     ])
     .build()
 }
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use tauri_utils::acl::{
+    resolved::{CommandKey, ResolvedCommand},
+    ExecutionContext,
+  };
+
+  use super::{forward_menu_event, MenuChannels, MENU_EVENTS_COMMAND};
+  use crate::{
+    ipc::{Channel, InvokeBody},
+    menu::{MenuEvent, MenuId},
+    test::{mock_builder, mock_context, noop_assets},
+    Manager, WebviewUrl, WebviewWindowBuilder,
+  };
+
+  #[test]
+  fn menu_events_are_only_forwarded_to_allowed_webviews() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: MENU_EVENTS_COMMAND.into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["*".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+    let app = mock_builder().build(context).unwrap();
+    let local = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let remote = WebviewWindowBuilder::new(
+      &app,
+      "remote",
+      WebviewUrl::External("https://remote.tauri.app".parse().unwrap()),
+    )
+    .build()
+    .unwrap();
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    for webview in [&local, &remote] {
+      let label = webview.label().to_string();
+      let received = received.clone();
+      app.state::<MenuChannels>().insert(
+        MenuId::new(&label),
+        webview.as_ref(),
+        Channel::new(move |body| {
+          if let InvokeBody::Json(payload) = body {
+            received.lock().unwrap().push((label.clone(), payload));
+          }
+          Ok(())
+        }),
+      );
+    }
+
+    for id in ["main", "remote"] {
+      forward_menu_event(
+        app.handle(),
+        &MenuEvent {
+          id: MenuId::new(id),
+        },
+      );
+    }
+
+    // the remote page is not granted the menu events by the local capability
+    assert_eq!(
+      *received.lock().unwrap(),
+      [("main".to_string(), serde_json::json!("main"))]
+    );
+  }
+}
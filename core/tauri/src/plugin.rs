@@ -140,6 +140,9 @@ impl<R: Runtime, C: DeserializeOwned> PluginApi<R, C> {
   }
 
   /// Gets the global scope defined on the permissions that are part of the app ACL.
+  ///
+  /// The scope is merged across all windows. Commands should prefer the [`GlobalScope`](crate::command::GlobalScope)
+  /// argument, which only holds the global scope granted to the window that invoked them.
   pub fn scope<T: ScopeObject>(&self) -> crate::Result<&ScopeValue<T>> {
     self
       .handle
@@ -234,6 +237,7 @@ pub struct Builder<R: Runtime, C: DeserializeOwned = ()> {
   on_event: Box<OnEvent<R>>,
   on_drop: Option<Box<OnDrop<R>>>,
   uri_scheme_protocols: HashMap<String, Arc<UriSchemeProtocol<R>>>,
+  uri_scheme_permissions: HashMap<String, String>,
 }
 
 impl<R: Runtime, C: DeserializeOwned> Builder<R, C> {
@@ -251,6 +255,7 @@ impl<R: Runtime, C: DeserializeOwned> Builder<R, C> {
       on_event: Box::new(|_, _| ()),
       on_drop: None,
       uri_scheme_protocols: Default::default(),
+      uri_scheme_permissions: Default::default(),
     }
   }
 
@@ -615,6 +620,40 @@ impl<R: Runtime, C: DeserializeOwned> Builder<R, C> {
     self
   }
 
+  /// Requires the webviews to be allowed the given command of this plugin to load the URI scheme protocol.
+  ///
+  /// The command is never invoked, it only names the permission a capability must grant:
+  /// list it in the commands of the plugin build script to generate its `allow-*` permission.
+  /// The protocol requests of a webview not allowed the command for the page it has loaded
+  /// are answered with `403 Forbidden` without calling the protocol handler.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use tauri::{plugin::{Builder, TauriPlugin}, Runtime};
+  ///
+  /// fn init<R: Runtime>() -> TauriPlugin<R> {
+  ///   Builder::new("myplugin")
+  ///     .register_uri_scheme_protocol("myscheme", |app, req| {
+  ///       http::Response::builder().body(Vec::new()).unwrap()
+  ///     })
+  ///     // granted by the `myplugin:allow-protocol` permission
+  ///     .require_uri_scheme_permission("myscheme", "protocol")
+  ///     .build()
+  /// }
+  /// ```
+  #[must_use]
+  pub fn require_uri_scheme_permission<N: Into<String>, S: Into<String>>(
+    mut self,
+    uri_scheme: N,
+    command: S,
+  ) -> Self {
+    self
+      .uri_scheme_permissions
+      .insert(uri_scheme.into(), command.into());
+    self
+  }
+
   /// Builds the [TauriPlugin].
   pub fn build(self) -> TauriPlugin<R, C> {
     TauriPlugin {
@@ -630,6 +669,7 @@ impl<R: Runtime, C: DeserializeOwned> Builder<R, C> {
       on_event: self.on_event,
       on_drop: self.on_drop,
       uri_scheme_protocols: self.uri_scheme_protocols,
+      uri_scheme_permissions: self.uri_scheme_permissions,
     }
   }
 }
@@ -648,6 +688,7 @@ pub struct TauriPlugin<R: Runtime, C: DeserializeOwned = ()> {
   on_event: Box<OnEvent<R>>,
   on_drop: Option<Box<OnDrop<R>>>,
   uri_scheme_protocols: HashMap<String, Arc<UriSchemeProtocol<R>>>,
+  uri_scheme_permissions: HashMap<String, String>,
 }
 
 impl<R: Runtime, C: DeserializeOwned> Drop for TauriPlugin<R, C> {
@@ -687,6 +728,17 @@ impl<R: Runtime, C: DeserializeOwned> Plugin<R> for TauriPlugin<R, C> {
         .webview
         .register_uri_scheme_protocol(uri_scheme, protocol.clone())
     }
+    for (uri_scheme, command) in &self.uri_scheme_permissions {
+      app.manager.webview.require_uri_scheme_permission(
+        uri_scheme,
+        format!(
+          "{}{}{}{command}",
+          crate::utils::acl::PLUGIN_COMMAND_PREFIX,
+          self.name,
+          crate::utils::acl::PLUGIN_COMMAND_SEPARATOR
+        ),
+      );
+    }
     Ok(())
   }
 
@@ -781,6 +833,11 @@ impl<R: Runtime> PluginStore<R> {
       .try_for_each(|plugin| initialize(plugin, app, config))
   }
 
+  /// The names of the plugins in the store.
+  pub(crate) fn names(&self) -> Vec<&'static str> {
+    self.store.iter().map(|plugin| plugin.name()).collect()
+  }
+
   /// Generates an initialization script from all plugins in the store.
   pub(crate) fn initialization_script(&self) -> String {
     self
@@ -25,6 +25,7 @@ struct CachedResponse {
 
 pub fn get<R: Runtime>(
   #[allow(unused_variables)] manager: Arc<AppManager<R>>,
+  #[allow(unused_variables)] window_label: &str,
   window_origin: &str,
   web_resource_request_handler: Option<Box<WebResourceRequestHandler>>,
 ) -> UriSchemeProtocolHandler {
@@ -38,6 +39,8 @@ pub fn get<R: Runtime>(
   };
 
   let manager = manager.clone();
+  #[cfg(not(all(dev, mobile)))]
+  let window_label = window_label.to_string();
   let window_origin = window_origin.to_string();
 
   #[cfg(all(dev, mobile))]
@@ -47,6 +50,8 @@ pub fn get<R: Runtime>(
     match get_response(
       request,
       &manager,
+      #[cfg(not(all(dev, mobile)))]
+      &window_label,
       &window_origin,
       web_resource_request_handler.as_deref(),
       #[cfg(all(dev, mobile))]
@@ -68,6 +73,7 @@ pub fn get<R: Runtime>(
 fn get_response<R: Runtime>(
   request: Request<Vec<u8>>,
   #[allow(unused_variables)] manager: &AppManager<R>,
+  #[cfg(not(all(dev, mobile)))] window_label: &str,
   window_origin: &str,
   web_resource_request_handler: Option<&WebResourceRequestHandler>,
   #[cfg(all(dev, mobile))] (url, response_cache): (
@@ -156,8 +162,11 @@ fn get_response<R: Runtime>(
   let mut response = {
     let asset = manager.get_asset(path)?;
     builder = builder.header(CONTENT_TYPE, &asset.mime_type);
-    if let Some(csp) = &asset.csp_header {
-      builder = builder.header("Content-Security-Policy", csp);
+    if let Some(csp) = asset.csp_header {
+      builder = builder.header(
+        "Content-Security-Policy",
+        manager.extend_connect_src(csp, window_label),
+      );
     }
     builder.body(asset.bytes.into())?
   };
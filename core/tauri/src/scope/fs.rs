@@ -5,7 +5,7 @@
 use std::{
   collections::{HashMap, HashSet},
   fmt,
-  path::{Path, PathBuf, MAIN_SEPARATOR},
+  path::{Component, Path, PathBuf, MAIN_SEPARATOR},
   sync::{
     atomic::{AtomicU32, Ordering},
     Arc, Mutex,
@@ -14,7 +14,10 @@ use std::{
 
 use tauri_utils::config::FsScope;
 
-use crate::ScopeEventId;
+use crate::{
+  command::{CommandScope, GlobalScope, ScopeObject},
+  ScopeEventId,
+};
 
 pub use glob::Pattern;
 
@@ -179,6 +182,54 @@ impl Scope {
     })
   }
 
+  /// Creates a scope from the path entries of a command scope merged with the plugin global scope,
+  /// so commands that do not receive their paths upfront, such as filesystem watchers,
+  /// are restricted the same way as the commands checking their scope directly.
+  ///
+  /// The entries are glob patterns of already resolved paths. The usable entries granted at runtime are included,
+  /// consuming one of their uses, see [`RuntimeAuthority::grant_ephemeral`](crate::command::RuntimeAuthority::grant_ephemeral).
+  pub fn from_acl<T: ScopeObject + AsRef<Path>>(
+    command_scope: &CommandScope<'_, T>,
+    global_scope: &GlobalScope<'_, T>,
+  ) -> crate::Result<Self> {
+    global_scope.consume_ephemeral();
+    Self::from_patterns(
+      command_scope.allows().iter().chain(global_scope.allows()),
+      command_scope.denies().iter().chain(global_scope.denies()),
+    )
+  }
+
+  /// Creates a scope from glob patterns of already resolved paths.
+  pub(crate) fn from_patterns<P: AsRef<Path>>(
+    allowed: impl IntoIterator<Item = P>,
+    forbidden: impl IntoIterator<Item = P>,
+  ) -> crate::Result<Self> {
+    let mut allowed_patterns = HashSet::new();
+    for pattern in allowed {
+      push_pattern(&mut allowed_patterns, pattern, Pattern::new)?;
+    }
+
+    let mut forbidden_patterns = HashSet::new();
+    for pattern in forbidden {
+      push_pattern(&mut forbidden_patterns, pattern, Pattern::new)?;
+    }
+
+    Ok(Self {
+      allowed_patterns: Arc::new(Mutex::new(allowed_patterns)),
+      forbidden_patterns: Arc::new(Mutex::new(forbidden_patterns)),
+      event_listeners: Default::default(),
+      next_event_id: Default::default(),
+      match_options: glob::MatchOptions {
+        // this is needed so `/dir/*` doesn't match files within subdirectories such as `/dir/subdir/file.txt`
+        // see: https://github.com/tauri-apps/tauri/security/advisories/GHSA-6mv3-wm7j-h4w5
+        require_literal_separator: true,
+        // dotfiles are not supposed to be exposed by default on unix
+        require_literal_leading_dot: cfg!(unix),
+        ..Default::default()
+      },
+    })
+  }
+
   /// The list of allowed patterns.
   pub fn allowed_patterns(&self) -> HashSet<Pattern> {
     self.allowed_patterns.lock().unwrap().clone()
@@ -327,6 +378,94 @@ impl Scope {
       false
     }
   }
+
+  /// Creates a validator checking the paths against this scope, see [`crate::command::ScopeValidator`].
+  ///
+  /// The validator shares the patterns of this scope,
+  /// so paths allowed or forbidden after its creation are taken into account.
+  pub fn validator(&self) -> crate::command::ScopeValidator<Path> {
+    let scope = self.clone();
+    crate::command::ScopeValidator::new(move |path| scope.is_allowed(path))
+  }
+
+  /// Resolves the paths to watch for a watch request on the given path.
+  ///
+  /// An allowed path is watched as is. A recursive watch on a path that is not allowed
+  /// is clipped to the allowed directories below it, which are returned instead.
+  /// Returns [`crate::Error::PathNotAllowed`] when nothing under the path can be watched.
+  ///
+  /// The events of a clipped watch can still reference paths that are not allowed,
+  /// e.g. for globs such as `/dir/*.txt`, so they must be checked with [`Self::is_allowed`] before being emitted.
+  pub fn watch_roots<P: AsRef<Path>>(
+    &self,
+    path: P,
+    recursive: bool,
+  ) -> crate::Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    if self.is_allowed(path) {
+      return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut roots: Vec<PathBuf> = Vec::new();
+    if recursive {
+      let path: PathBuf = path.components().collect();
+      let mut candidates = self
+        .allowed_patterns
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|pattern| literal_prefix(pattern.as_str()))
+        .filter(|root| root != &path && root.starts_with(&path))
+        .collect::<Vec<_>>();
+      // ancestors are sorted before their descendants
+      candidates.sort();
+
+      let forbidden_patterns = self.forbidden_patterns.lock().unwrap();
+      for root in candidates {
+        let forbidden = forbidden_patterns
+          .iter()
+          .any(|p| p.matches_path_with(&root, self.match_options));
+        if !forbidden && !roots.iter().any(|r| root.starts_with(r)) {
+          roots.push(root);
+        }
+      }
+    }
+
+    if roots.is_empty() {
+      Err(crate::Error::PathNotAllowed(path.to_path_buf()))
+    } else {
+      Ok(roots)
+    }
+  }
+}
+
+/// The leading components of a pattern that only match themselves,
+/// unescaping the sequences written by [`Pattern::escape`].
+fn literal_prefix(pattern: &str) -> PathBuf {
+  let mut prefix = PathBuf::new();
+  for component in Path::new(pattern).components() {
+    match component {
+      Component::Normal(name) => {
+        let name = name.to_string_lossy();
+        let mut literal = String::new();
+        let mut chars = name.chars();
+        while let Some(c) = chars.next() {
+          match c {
+            '*' | '?' => return prefix,
+            // `[c]` escapes a single character, anything else is a character class
+            '[' => match (chars.next(), chars.next()) {
+              (Some(c), Some(']')) if matches!(c, '*' | '?' | '[' | ']') => literal.push(c),
+              _ => return prefix,
+            },
+            c => literal.push(c),
+          }
+        }
+        prefix.push(literal);
+      }
+      component => prefix.push(component),
+    }
+  }
+  prefix
 }
 
 fn escaped_pattern(p: &str) -> Result<Pattern, glob::PatternError> {
@@ -365,6 +504,83 @@ mod tests {
     }
   }
 
+  #[test]
+  fn validator_follows_scope_changes() {
+    let scope = new_scope();
+    #[cfg(unix)]
+    let (dir, files) = (
+      "/home/tauri",
+      ["/home/tauri/a", "/home/tauri/b", "/home/tauri/c"],
+    );
+    #[cfg(windows)]
+    let (dir, files) = (
+      "C:\\home\\tauri",
+      [
+        "C:\\home\\tauri\\a",
+        "C:\\home\\tauri\\b",
+        "C:\\home\\tauri\\c",
+      ],
+    );
+    scope.allow_directory(dir, false).unwrap();
+
+    let validator = scope.validator();
+    let mut streamed = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+      // the scope changes while the items are streamed
+      if i == 1 {
+        scope.forbid_file(files[2]).unwrap();
+      }
+      if validator.clone().is_allowed(file.as_ref()) {
+        streamed.push(*file);
+      }
+    }
+    assert_eq!(streamed, &files[..2]);
+  }
+
+  #[test]
+  fn recursive_watch_is_clipped_to_allowed_directories() {
+    use std::path::PathBuf;
+
+    let scope = new_scope();
+    #[cfg(unix)]
+    let (parent, projects, notes, secrets) = (
+      "/home/tauri",
+      "/home/tauri/projects",
+      "/home/tauri/[notes]",
+      "/home/tauri/projects/secrets",
+    );
+    #[cfg(windows)]
+    let (parent, projects, notes, secrets) = (
+      "C:\\home\\tauri",
+      "C:\\home\\tauri\\projects",
+      "C:\\home\\tauri\\[notes]",
+      "C:\\home\\tauri\\projects\\secrets",
+    );
+    scope.allow_directory(projects, true).unwrap();
+    scope.allow_directory(secrets, true).unwrap();
+    scope.allow_directory(notes, false).unwrap();
+
+    assert_eq!(
+      scope.watch_roots(parent, true).unwrap(),
+      vec![PathBuf::from(notes), PathBuf::from(projects)]
+    );
+    assert!(matches!(
+      scope.watch_roots(parent, false),
+      Err(crate::Error::PathNotAllowed(_))
+    ));
+    assert_eq!(
+      scope.watch_roots(projects, true).unwrap(),
+      vec![PathBuf::from(projects)]
+    );
+
+    // the nested allowed directory becomes a root once its parent is forbidden
+    scope.forbid_file(projects).unwrap();
+    assert_eq!(
+      scope.watch_roots(parent, true).unwrap(),
+      vec![PathBuf::from(notes), PathBuf::from(secrets)]
+    );
+  }
+
   #[test]
   fn path_is_escaped() {
     let scope = new_scope();
@@ -4,6 +4,8 @@
 
 /// FS scope.
 pub mod fs;
+/// URL scope.
+pub mod url;
 
 use std::path::Path;
 
@@ -0,0 +1,373 @@
+// Copyright 2019-2023 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::fmt;
+
+use glob::Pattern;
+use serde::Deserialize;
+use url::{Position, Url};
+
+use crate::command::{CommandScope, GlobalScope, ScopeObject};
+
+/// The schemes whose hosts are case insensitive and normalized by the URL parser.
+const SPECIAL_SCHEMES: &[&str] = &["http", "https", "ws", "wss", "ftp", "file"];
+
+/// A URL scope entry, either a glob pattern or an object with a `url` field.
+///
+/// ```json
+/// ["https://docs.ourapp.com/*", { "url": "mailto:*" }]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Entry {
+  /// The URL pattern.
+  Pattern(String),
+  /// An object holding the URL pattern.
+  Object {
+    /// The URL pattern.
+    url: String,
+  },
+}
+
+impl AsRef<str> for Entry {
+  fn as_ref(&self) -> &str {
+    match self {
+      Self::Pattern(url) | Self::Object { url } => url,
+    }
+  }
+}
+
+/// Scope for the URLs a command can open or load, such as the `open` command of the opener plugin.
+///
+/// The patterns are matched against the parsed URL, so the scheme, and the host of the
+/// special schemes such as `https`, are matched case insensitively. A denied pattern always wins.
+///
+/// The scheme, host and port, path and query string are matched separately, so a wildcard never spans
+/// two of them: `https://*.ourapp.com/*` does not match `https://evil.com/x.ourapp.com/`.
+/// A `?` in a pattern starts the query string pattern, and a pattern without one accepts any query string.
+#[derive(Clone)]
+pub struct Scope {
+  allowed_patterns: Vec<UrlPattern>,
+  forbidden_patterns: Vec<UrlPattern>,
+}
+
+impl fmt::Debug for Scope {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let patterns = |patterns: &[UrlPattern]| {
+      patterns
+        .iter()
+        .map(|p| p.as_str().to_string())
+        .collect::<Vec<_>>()
+    };
+    f.debug_struct("Scope")
+      .field("allowed_patterns", &patterns(&self.allowed_patterns))
+      .field("forbidden_patterns", &patterns(&self.forbidden_patterns))
+      .finish()
+  }
+}
+
+impl Scope {
+  /// Creates a scope from the URL entries of a command scope merged with the plugin global scope.
+  ///
+  /// Both only hold the entries granted to the invoking window, so each window gets its own allow list.
+  /// The usable entries granted at runtime are included, consuming one of their uses,
+  /// see [`RuntimeAuthority::grant_ephemeral`](crate::command::RuntimeAuthority::grant_ephemeral).
+  pub fn from_acl<T: ScopeObject + AsRef<str>>(
+    command_scope: &CommandScope<'_, T>,
+    global_scope: &GlobalScope<'_, T>,
+  ) -> crate::Result<Self> {
+    global_scope.consume_ephemeral();
+    Self::from_patterns(
+      command_scope.allows().iter().chain(global_scope.allows()),
+      command_scope.denies().iter().chain(global_scope.denies()),
+    )
+  }
+
+  /// Creates a scope from glob patterns of URLs.
+  pub fn from_patterns<P: AsRef<str>>(
+    allowed: impl IntoIterator<Item = P>,
+    forbidden: impl IntoIterator<Item = P>,
+  ) -> crate::Result<Self> {
+    let compile = |patterns: &mut dyn Iterator<Item = P>| {
+      patterns
+        .map(|pattern| UrlPattern::new(pattern.as_ref()))
+        .collect::<Result<Vec<_>, _>>()
+    };
+    Ok(Self {
+      allowed_patterns: compile(&mut allowed.into_iter())?,
+      forbidden_patterns: compile(&mut forbidden.into_iter())?,
+    })
+  }
+
+  /// Whether the URL is allowed by the scope.
+  pub fn is_allowed(&self, url: &Url) -> bool {
+    !self.forbidden_patterns.iter().any(|p| p.matches(url))
+      && self.allowed_patterns.iter().any(|p| p.matches(url))
+  }
+
+  /// Parses the URL and checks it against the scope.
+  ///
+  /// Returns [`crate::Error::UrlNotAllowed`] with the scheme and host of the URL only,
+  /// so the path and query string of a denied URL do not end up in the logs.
+  pub fn check(&self, url: &str) -> crate::Result<Url> {
+    let url = Url::parse(url).map_err(crate::Error::InvalidUrl)?;
+    if self.is_allowed(&url) {
+      Ok(url)
+    } else {
+      Err(crate::Error::UrlNotAllowed {
+        scheme: url.scheme().into(),
+        host: url.host_str().map(Into::into),
+      })
+    }
+  }
+}
+
+/// A URL pattern, split in the components matched separately.
+#[derive(Clone)]
+enum UrlPattern {
+  /// A pattern without a scheme, matched against the whole URL.
+  Url(Pattern),
+  Components {
+    source: String,
+    scheme: Pattern,
+    /// The host and port pattern of the patterns starting with `scheme://`.
+    authority: Option<Pattern>,
+    /// The path pattern, or everything after the scheme for the patterns without an authority.
+    path: Pattern,
+    query: Option<Pattern>,
+  },
+}
+
+impl UrlPattern {
+  /// Lowercases the scheme of the pattern, and its host for the special schemes,
+  /// matching the serialization of a parsed [`Url`], and compiles its components.
+  fn new(pattern: &str) -> Result<Self, glob::PatternError> {
+    let Some((scheme, rest)) = pattern
+      .split_once(':')
+      .filter(|(scheme, _)| is_scheme(scheme))
+    else {
+      return Pattern::new(pattern).map(Self::Url);
+    };
+
+    let scheme = scheme.to_ascii_lowercase();
+    let (authority, rest) = match rest.strip_prefix("//") {
+      Some(rest) => {
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let authority = if SPECIAL_SCHEMES.contains(&scheme.as_str()) {
+          authority.to_ascii_lowercase()
+        } else {
+          authority.to_string()
+        };
+        (Some(authority), path)
+      }
+      None => (None, rest),
+    };
+    let (path, query) = match rest.split_once('?') {
+      Some((path, query)) => (path, Some(query)),
+      None => (rest, None),
+    };
+
+    Ok(Self::Components {
+      source: match &authority {
+        Some(authority) => format!("{scheme}://{authority}{rest}"),
+        None => format!("{scheme}:{rest}"),
+      },
+      scheme: Pattern::new(&scheme)?,
+      authority: authority.as_deref().map(Pattern::new).transpose()?,
+      path: Pattern::new(path)?,
+      query: query.map(Pattern::new).transpose()?,
+    })
+  }
+
+  fn as_str(&self) -> &str {
+    match self {
+      Self::Url(pattern) => pattern.as_str(),
+      Self::Components { source, .. } => source,
+    }
+  }
+
+  fn matches(&self, url: &Url) -> bool {
+    let Self::Components {
+      scheme,
+      authority,
+      path,
+      query,
+      ..
+    } = self
+    else {
+      return matches!(self, Self::Url(pattern) if pattern.matches(url.as_str()));
+    };
+
+    let url_path = match authority {
+      Some(authority) => {
+        if !url.has_authority()
+          || !authority.matches(&url[Position::BeforeHost..Position::AfterPort])
+        {
+          return false;
+        }
+        &url[Position::BeforePath..Position::AfterPath]
+      }
+      // everything after `scheme:`, e.g. the address of a `mailto:` URL
+      None => &url[..Position::AfterPath][url.scheme().len() + 1..],
+    };
+    let path_matches = if path.as_str().is_empty() {
+      url_path.is_empty() || url_path == "/"
+    } else {
+      path.matches(url_path)
+    };
+
+    scheme.matches(url.scheme())
+      && path_matches
+      && query
+        .as_ref()
+        .map_or(true, |query| url.query().is_some_and(|q| query.matches(q)))
+  }
+}
+
+/// Whether the pattern prefix is a URL scheme.
+fn is_scheme(scheme: &str) -> bool {
+  !scheme.is_empty()
+    && scheme
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn url_scope(allowed: &[&str], forbidden: &[&str]) -> Scope {
+    Scope::from_patterns(allowed, forbidden).unwrap()
+  }
+
+  fn denied(scope: &Scope, url: &str) -> (String, Option<String>) {
+    match scope.check(url) {
+      Err(crate::Error::UrlNotAllowed { scheme, host }) => (scheme, host),
+      other => panic!("expected {url} to be denied, got {other:?}"),
+    }
+  }
+
+  #[test]
+  fn web_urls() {
+    let scope = url_scope(
+      &["https://docs.ourapp.com/*"],
+      &["https://docs.ourapp.com/internal/*"],
+    );
+
+    assert!(scope.check("https://docs.ourapp.com/guide/intro").is_ok());
+    assert!(scope
+      .check("https://docs.ourapp.com/search?q=scope")
+      .is_ok());
+    assert_eq!(
+      denied(&scope, "https://docs.ourapp.com/internal/keys?token=secret"),
+      ("https".into(), Some("docs.ourapp.com".into()))
+    );
+    assert_eq!(
+      denied(&scope, "http://docs.ourapp.com/guide"),
+      ("http".into(), Some("docs.ourapp.com".into()))
+    );
+    assert!(matches!(
+      scope.check("not a url"),
+      Err(crate::Error::InvalidUrl(_))
+    ));
+
+    // an empty scope opens nothing
+    let empty = url_scope(&[], &[]);
+    assert_eq!(
+      denied(&empty, "https://docs.ourapp.com/guide"),
+      ("https".into(), Some("docs.ourapp.com".into()))
+    );
+  }
+
+  #[test]
+  fn mailto() {
+    let scope = url_scope(&["mailto:*@ourapp.com"], &["mailto:billing@ourapp.com"]);
+
+    assert!(scope.check("mailto:support@ourapp.com").is_ok());
+    assert_eq!(
+      denied(&scope, "mailto:billing@ourapp.com"),
+      ("mailto".into(), None)
+    );
+    assert_eq!(
+      denied(&scope, "mailto:someone@example.com"),
+      ("mailto".into(), None)
+    );
+  }
+
+  #[test]
+  fn custom_schemes() {
+    let scope = url_scope(&["ourapp://open/*"], &[]);
+
+    assert!(scope.check("ourapp://open/project/1").is_ok());
+    assert_eq!(
+      denied(&scope, "ourapp://settings/reset"),
+      ("ourapp".into(), Some("settings".into()))
+    );
+    assert_eq!(
+      denied(&scope, "otherapp://open/project/1"),
+      ("otherapp".into(), Some("open".into()))
+    );
+  }
+
+  #[test]
+  fn uppercase_schemes() {
+    let scope = url_scope(
+      &["HTTPS://Docs.OurApp.com/*", "OurApp://open/*", "MAILTO:*"],
+      &["https://docs.ourapp.com/internal/*"],
+    );
+
+    assert!(scope.check("https://docs.ourapp.com/guide").is_ok());
+    assert!(scope.check("HTTPS://DOCS.OURAPP.COM/guide").is_ok());
+    assert!(scope.check("Mailto:support@ourapp.com").is_ok());
+    assert!(scope.check("OURAPP://open/project/1").is_ok());
+    assert_eq!(
+      denied(&scope, "HtTpS://docs.ourapp.com/internal/keys"),
+      ("https".into(), Some("docs.ourapp.com".into()))
+    );
+  }
+
+  #[test]
+  fn wildcards_stay_within_components() {
+    let scope = url_scope(&["https://*.ourapp.com/*", "mailto:*@ourapp.com"], &[]);
+
+    assert!(scope.check("https://docs.ourapp.com/guide/intro").is_ok());
+    assert_eq!(
+      denied(&scope, "https://evil.com/x.ourapp.com/y"),
+      ("https".into(), Some("evil.com".into()))
+    );
+    assert_eq!(
+      denied(&scope, "https://evil.com?x.ourapp.com/y"),
+      ("https".into(), Some("evil.com".into()))
+    );
+    assert!(scope.check("mailto:support@ourapp.com").is_ok());
+    assert_eq!(
+      denied(&scope, "mailto:a@evil.com?cc=b@ourapp.com"),
+      ("mailto".into(), None)
+    );
+  }
+
+  #[test]
+  fn query_patterns() {
+    let scope = url_scope(
+      &["https://docs.ourapp.com", "https://ourapp.com/search?q=*"],
+      &[],
+    );
+
+    assert!(scope.check("https://docs.ourapp.com").is_ok());
+    assert!(scope.check("https://docs.ourapp.com/").is_ok());
+    assert!(scope.check("https://docs.ourapp.com/?lang=en").is_ok());
+    assert!(scope.check("https://docs.ourapp.com/guide").is_err());
+    assert!(scope.check("https://ourapp.com/search?q=scope").is_ok());
+    assert!(scope.check("https://ourapp.com/search").is_err());
+  }
+
+  #[test]
+  fn entries() {
+    let entries: Vec<Entry> =
+      serde_json::from_str(r#"["https://docs.ourapp.com/*", { "url": "mailto:*" }]"#).unwrap();
+    let scope = Scope::from_patterns(&entries, &[]).unwrap();
+    assert!(scope.check("https://docs.ourapp.com/guide").is_ok());
+    assert!(scope.check("mailto:support@ourapp.com").is_ok());
+  }
+}
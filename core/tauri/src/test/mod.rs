@@ -55,8 +55,9 @@ use serde::Serialize;
 use std::{borrow::Cow, collections::HashMap, fmt::Debug};
 
 use crate::{
-  ipc::{InvokeBody, InvokeError, InvokeResponse},
-  webview::InvokeRequest,
+  command::Denial,
+  ipc::{CallbackFn, InvokeBody, InvokeError, InvokeResponse},
+  webview::{InvokeRequest, Url},
   App, Builder, Context, Pattern, Webview,
 };
 use tauri_utils::{
@@ -130,7 +131,14 @@ pub fn mock_context<A: Assets>(assets: A) -> crate::Context<A> {
       allowed_commands: Default::default(),
       denied_commands: Default::default(),
       command_scope: Default::default(),
+      command_scope_shards: Default::default(),
       global_scope: Default::default(),
+      inheriting_webviews: Vec::new(),
+      global_api: Default::default(),
+      command_aliases: Default::default(),
+      shares_scope_with: Default::default(),
+      lockdown_remote: false,
+      role_grants: Vec::new(),
     },
   }
 }
@@ -258,26 +266,262 @@ pub fn get_ipc_response<W: AsRef<Webview<MockRuntime>>>(
   webview: &W,
   request: InvokeRequest,
 ) -> Result<InvokeBody, serde_json::Value> {
-  let (tx, rx) = std::sync::mpsc::sync_channel(1);
-  webview.as_ref().clone().on_message(
-    request,
-    Box::new(move |_window, _cmd, response, _callback, _error| {
-      tx.send(response).unwrap();
-    }),
+  let url = webview.as_ref().url();
+  run_ipc_request(webview, &url, request).1
+}
+
+/// Creates an IPC request for the command with an empty body.
+pub fn invoke_request<C: Into<String>>(cmd: C) -> InvokeRequest {
+  InvokeRequest {
+    cmd: cmd.into(),
+    callback: CallbackFn(0),
+    error: CallbackFn(1),
+    body: Default::default(),
+    headers: Default::default(),
+  }
+}
+
+/// Executes the given IPC message as if it was sent by the page loaded at `origin` in the webview and get the return value.
+///
+/// The request goes through the origin classification and the ACL checks of the IPC requests sent by the webview,
+/// so the remote capabilities of an app or plugin can be tested without loading the origin in a browser.
+/// Remote origins only get the navigation evidence recorded for the webview, so capabilities requiring an attestation deny them.
+///
+/// # Examples
+///
+/// ```rust
+/// use tauri::{
+///   test::{assert_ipc_denied_from, assert_ipc_response_from, invoke_request, mock_builder, mock_context, noop_assets},
+///   utils::acl::{resolved::Resolved, ExecutionContext},
+///   webview::Url,
+/// };
+///
+/// #[tauri::command]
+/// fn ping() -> &'static str {
+///     "pong"
+/// }
+///
+/// fn main() {
+///     let mut context = mock_context(noop_assets());
+///     // the ACL of a remote capability granting the command to `app.example.com`
+///     *context.resolved_acl_mut() = Resolved::builder()
+///         .allow_command(
+///             "plugin:ping|ping",
+///             ExecutionContext::Remote { domain: "app.example.com".parse().unwrap(), attestation: None },
+///             ["main"],
+///         )
+///         .unwrap()
+///         .build();
+///
+///     let app = mock_builder()
+///         .plugin(
+///             tauri::plugin::Builder::new("ping")
+///                 .invoke_handler(tauri::generate_handler![ping])
+///                 .build(),
+///         )
+///         .build(context)
+///         .unwrap();
+///     let webview = tauri::WebviewWindowBuilder::new(&app, "main", Default::default()).build().unwrap();
+///
+///     let app_origin = Url::parse("https://app.example.com/index.html").unwrap();
+///     assert_ipc_response_from(&webview, &app_origin, invoke_request("plugin:ping|ping"), Ok("pong"));
+///
+///     let evil_origin = Url::parse("https://evil.example.com").unwrap();
+///     assert_ipc_denied_from(&webview, &evil_origin, invoke_request("plugin:ping|ping"));
+/// }
+/// ```
+pub fn get_ipc_response_from<W: AsRef<Webview<MockRuntime>>>(
+  webview: &W,
+  origin: &Url,
+  request: InvokeRequest,
+) -> Result<InvokeBody, serde_json::Value> {
+  run_ipc_request(webview, origin, request).1
+}
+
+/// Executes the given IPC message as if it was sent by the page loaded at `origin` in the webview
+/// and assert the response matches the expected value, see [`get_ipc_response_from`].
+pub fn assert_ipc_response_from<
+  T: Serialize + Debug + Send + Sync + 'static,
+  W: AsRef<Webview<MockRuntime>>,
+>(
+  webview: &W,
+  origin: &Url,
+  request: InvokeRequest,
+  expected: Result<T, T>,
+) {
+  let response = get_ipc_response_from(webview, origin, request)
+    .map(|b| b.deserialize::<serde_json::Value>().unwrap());
+  assert_eq!(
+    response,
+    expected
+      .map(|e| serde_json::to_value(e).unwrap())
+      .map_err(|e| serde_json::to_value(e).unwrap())
   );
+}
+
+/// Executes the given IPC message as if it was sent by the page loaded at `origin` in the webview
+/// and assert the ACL denies it, returning the [`Denial`], see [`get_ipc_response_from`].
+///
+/// Unlike an error response, this does not match the commands that fail on their own.
+pub fn assert_ipc_denied_from<W: AsRef<Webview<MockRuntime>>>(
+  webview: &W,
+  origin: &Url,
+  request: InvokeRequest,
+) -> Denial {
+  let cmd = request.cmd.clone();
+  match run_ipc_request(webview, origin, request) {
+    (Some(denial), _) => denial,
+    (None, response) => {
+      panic!("expected the ACL to deny `{cmd}` from {origin}, got the response {response:?}")
+    }
+  }
+}
+
+/// Runs the request on the webview for the page loaded at the URL,
+/// returning the [`Denial`] if the ACL rejected it and the response.
+fn run_ipc_request<W: AsRef<Webview<MockRuntime>>>(
+  webview: &W,
+  url: &Url,
+  request: InvokeRequest,
+) -> (Option<Denial>, Result<InvokeBody, serde_json::Value>) {
+  let (tx, rx) = std::sync::mpsc::sync_channel(1);
+  let denial = webview
+    .as_ref()
+    .clone()
+    .run_invoke(
+      request,
+      Box::new(move |_window, _cmd, response, _callback, _error| {
+        tx.send(response).unwrap();
+      }),
+      true,
+      url.clone(),
+    )
+    .err();
 
   let res = rx.recv().expect("Failed to receive result from command");
-  match res {
+  let response = match res {
     InvokeResponse::Ok(b) => Ok(b),
     InvokeResponse::Err(InvokeError(v)) => Err(v),
-  }
+  };
+  (denial, response)
 }
 
 #[cfg(test)]
 mod tests {
   use std::time::Duration;
 
-  use super::mock_app;
+  use tauri_utils::acl::{resolved::Resolved, ExecutionContext};
+
+  use super::{
+    assert_ipc_denied_from, assert_ipc_response_from, invoke_request, mock_app, mock_builder,
+    mock_context, noop_assets, MockRuntime,
+  };
+  use crate::{command::Denial, plugin::Builder as PluginBuilder, webview::Url};
+
+  #[crate::command(root = "crate")]
+  fn read() -> &'static str {
+    "contents"
+  }
+
+  #[test]
+  fn remote_origins_are_authorized_without_a_browser() {
+    let mut context = mock_context(noop_assets());
+    *context.resolved_acl_mut() = Resolved::builder()
+      .allow_command(
+        "plugin:files|read",
+        ExecutionContext::Remote {
+          domain: "app.example.com".parse().unwrap(),
+          attestation: None,
+        },
+        ["main"],
+      )
+      .unwrap()
+      .build();
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let webview = crate::WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let url = |url: &str| Url::parse(url).unwrap();
+
+    assert_ipc_response_from(
+      &webview,
+      &url("https://app.example.com/editor"),
+      invoke_request("plugin:files|read"),
+      Ok("contents"),
+    );
+    // the app pages are local, and the capability only grants the remote domain
+    for origin in ["https://evil.example.com", "tauri://localhost/index.html"] {
+      let denial =
+        assert_ipc_denied_from(&webview, &url(origin), invoke_request("plugin:files|read"));
+      #[cfg(debug_assertions)]
+      assert_eq!(
+        denial,
+        Denial::OriginNotMatched {
+          origins: vec!["remote: app.example.com".into()]
+        }
+      );
+      #[cfg(not(debug_assertions))]
+      assert_eq!(denial, Denial::NotAllowed);
+    }
+  }
+
+  #[test]
+  fn page_grants_follow_the_calling_page() {
+    let mut context = mock_context(noop_assets());
+    *context.resolved_acl_mut() = Resolved::builder()
+      .allow_command_on_pages("plugin:files|read", ["main"], ["settings.html", "admin/*"])
+      .unwrap()
+      .build();
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let webview = crate::WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let url = |url: &str| Url::parse(url).unwrap();
+
+    for page in [
+      "tauri://localhost/settings.html",
+      "tauri://localhost/admin/users.html",
+    ] {
+      assert_ipc_response_from(
+        &webview,
+        &url(page),
+        invoke_request("plugin:files|read"),
+        Ok("contents"),
+      );
+    }
+    // navigating back to another page drops the grant on the next invoke
+    for page in [
+      "tauri://localhost/index.html",
+      "tauri://localhost/",
+      "tauri://localhost/admin/users/edit.html",
+    ] {
+      let denial =
+        assert_ipc_denied_from(&webview, &url(page), invoke_request("plugin:files|read"));
+      #[cfg(debug_assertions)]
+      assert_eq!(
+        denial,
+        Denial::WindowNotMatched {
+          windows: vec!["main on pages settings.html, admin/*".into()]
+        }
+      );
+      #[cfg(not(debug_assertions))]
+      assert_eq!(denial, Denial::NotAllowed);
+    }
+  }
 
   #[test]
   fn run_app() {
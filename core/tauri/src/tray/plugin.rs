@@ -12,8 +12,9 @@ use crate::{
   menu::{plugin::ItemKind, Menu, Submenu},
   plugin::{Builder, TauriPlugin},
   resources::ResourceId,
+  sealed::ManagerBase,
   tray::TrayIconBuilder,
-  AppHandle, IconDto, Manager, Runtime,
+  AppHandle, IconDto, Manager, Runtime, Webview,
 };
 
 use super::TrayIcon;
@@ -31,9 +32,14 @@ struct TrayIconOptions {
   menu_on_left_click: Option<bool>,
 }
 
+/// The core permission command granting the tray icon events to the webview that created the tray icon,
+/// allowed by `tray:allow-events` which is part of `tray:default`.
+pub(crate) const TRAY_EVENTS_COMMAND: &str = "plugin:tray|events";
+
 #[command(root = "crate")]
 fn new<R: Runtime>(
   app: AppHandle<R>,
+  webview: Webview<R>,
   options: TrayIconOptions,
   handler: Channel,
 ) -> crate::Result<(ResourceId, String)> {
@@ -43,8 +49,17 @@ fn new<R: Runtime>(
     TrayIconBuilder::<R>::new()
   };
 
-  builder = builder.on_tray_icon_event(move |_tray, e| {
-    let _ = handler.send(e);
+  let label = webview.label().to_string();
+  builder = builder.on_tray_icon_event(move |tray, e| {
+    // the Rust handlers of the tray icon events are not affected
+    if tray
+      .app_handle()
+      .manager()
+      .get_webview(&label)
+      .is_some_and(|webview| webview.is_command_allowed(TRAY_EVENTS_COMMAND))
+    {
+      let _ = handler.send(e);
+    }
   });
 
   let mut resources_table = app.resources_table();
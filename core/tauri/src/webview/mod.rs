@@ -22,15 +22,19 @@ use tauri_runtime::{
   window::dpi::{PhysicalPosition, PhysicalSize, Position, Size},
   WindowDispatch,
 };
-use tauri_utils::config::{WebviewUrl, WindowConfig};
+use tauri_utils::{
+  acl::{CommandId, PLUGIN_COMMAND_PREFIX},
+  config::{WebviewUrl, WindowConfig},
+  normalize_label,
+};
 pub use url::Url;
 
 use crate::{
   app::UriSchemeResponder,
-  command::{CommandArg, CommandItem, Origin},
+  command::{AccessDecision, AccessRequest, CommandArg, CommandItem, Denial},
   event::{EmitArgs, EventSource},
   ipc::{
-    CallbackFn, Invoke, InvokeBody, InvokeError, InvokeMessage, InvokeResolver,
+    CallbackFn, Invoke, InvokeBody, InvokeError, InvokeMessage, InvokeResolver, InvokeResponse,
     OwnedInvokeResponder,
   },
   manager::{webview::WebviewLabelDef, AppManager},
@@ -139,6 +143,11 @@ impl Hash for JsEventListenerKey {
   }
 }
 
+/// Header with the webview label claimed by the frontend on IPC requests.
+///
+/// The claimed label is never trusted: the ACL is always resolved for the webview that owns the IPC channel.
+pub(crate) const TAURI_WEBVIEW_LABEL_HEADER_NAME: &str = "Tauri-Webview-Label";
+
 /// The IPC invoke request.
 #[derive(Debug)]
 pub struct InvokeRequest {
@@ -154,6 +163,37 @@ pub struct InvokeRequest {
   pub headers: HeaderMap,
 }
 
+impl InvokeRequest {
+  /// The webview label claimed by the frontend, only used to flag spoofing attempts.
+  #[cfg(debug_assertions)]
+  fn claimed_webview_label(&self) -> Option<&str> {
+    self
+      .headers
+      .get(TAURI_WEBVIEW_LABEL_HEADER_NAME)
+      .and_then(|label| label.to_str().ok())
+  }
+}
+
+/// The responses of the commands of a batched invoke, see [`Webview::run_invoke_batch`].
+struct BatchResponses<R: Runtime> {
+  responses: Vec<Option<serde_json::Value>>,
+  responder: Option<Box<OwnedInvokeResponder<R>>>,
+}
+
+/// How [`Webview::invoke`] authorizes a command invoked from Rust.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum InvokeAccess {
+  /// Run the command without checking the ACL, like calling the command function directly from Rust.
+  ///
+  /// The command still receives the scopes the capabilities grant to the webview, if any.
+  #[default]
+  Bypass,
+  /// Run the full ACL check as if the window with the given label invoked the command from the page it has loaded.
+  ///
+  /// Lets plugins calling the commands of other plugins respect the same policy as the frontend.
+  AsWindow(String),
+}
+
 /// The platform webview handle. Accessed with [`Webview#method.with_webview`];
 #[cfg(feature = "wry")]
 #[cfg_attr(docsrs, doc(cfg(feature = "wry")))]
@@ -263,6 +303,9 @@ unstable_struct!(
 impl<R: Runtime> WebviewBuilder<R> {
   /// Initializes a webview builder with the given webview label and URL to load.
   ///
+  /// The label can only include Unicode alphanumeric characters, `-`, `/`, `:` and `_`,
+  /// and is [normalized](crate::utils::normalize_label) like the labels it is compared with.
+  ///
   /// # Known issues
   ///
   /// On Windows, this function deadlocks when used in a synchronous command, see [the Webview2 issue].
@@ -326,7 +369,7 @@ async fn create_window(app: tauri::AppHandle) {
   /// [the Webview2 issue]: https://github.com/tauri-apps/wry/issues/583
   pub fn new<L: Into<String>>(label: L, url: WebviewUrl) -> Self {
     Self {
-      label: label.into(),
+      label: normalize_label(&label.into()).into_owned(),
       webview_attributes: WebviewAttributes::new(url),
       web_resource_request_handler: None,
       navigation_handler: None,
@@ -365,7 +408,7 @@ async fn reopen_window(app: tauri::AppHandle) {
   /// [the Webview2 issue]: https://github.com/tauri-apps/wry/issues/583
   pub fn from_config(config: WindowConfig) -> Self {
     Self {
-      label: config.label.clone(),
+      label: normalize_label(&config.label).into_owned(),
       webview_attributes: WebviewAttributes::from(&config),
       web_resource_request_handler: None,
       navigation_handler: None,
@@ -1043,40 +1086,278 @@ fn main() {
     self.webview.dispatcher.navigate(url).unwrap();
   }
 
-  fn is_local_url(&self, current_url: &Url) -> bool {
-    self
-      .manager()
-      .get_url()
-      .make_relative(current_url)
+  /// Whether the capabilities of this webview allow the command for the page currently loaded.
+  ///
+  /// Used by the core permissions gating the data the runtime forwards to the webview,
+  /// which are resolved like commands but never invoked.
+  pub(crate) fn is_command_allowed(&self, command: &str) -> bool {
+    let manager = self.manager();
+    let current_url = self.url();
+    let is_local = manager.is_local_url(&current_url);
+    let origin = manager.ipc_origin(self.label(), &current_url, is_local);
+    let page = is_local.then(|| current_url.path());
+    let window = self.window();
+    let ancestors = manager.window.ancestors(window.label());
+    manager
+      .runtime_authority
+      .authorize(&AccessRequest {
+        command,
+        window: window.label(),
+        webview: self.label(),
+        ancestors: &ancestors,
+        origin: &origin,
+        page,
+      })
+      .allowed()
       .is_some()
-      || {
-        let protocol_url = self.manager().protocol_url();
-        current_url.scheme() == protocol_url.scheme()
-          && current_url.domain() == protocol_url.domain()
-      }
-      || (cfg!(dev) && current_url.domain() == Some("tauri.localhost"))
   }
 
   /// Handles this window receiving an [`InvokeRequest`].
   pub fn on_message(self, request: InvokeRequest, responder: Box<OwnedInvokeResponder<R>>) {
-    let manager = self.manager_owned();
     let current_url = self.url();
-    let is_local = self.is_local_url(&current_url);
+    let _ = self.run_invoke(request, responder, true, current_url);
+  }
 
+  /// Invokes a command from Rust, authorized with the given [`InvokeAccess`].
+  ///
+  /// The response is sent to the `responder` like for an IPC request handled by [`Self::on_message`].
+  /// With [`InvokeAccess::AsWindow`], the command runs on the webview of the named window instead of this one,
+  /// and a command the ACL does not allow is rejected and returns [`crate::Error::CommandNotAllowed`] with the [`Denial`].
+  pub fn invoke(
+    &self,
+    request: InvokeRequest,
+    access: InvokeAccess,
+    responder: Box<OwnedInvokeResponder<R>>,
+  ) -> crate::Result<()> {
+    match access {
+      InvokeAccess::Bypass => {
+        let _ = self
+          .clone()
+          .run_invoke(request, responder, false, self.url());
+        Ok(())
+      }
+      InvokeAccess::AsWindow(label) => {
+        let webview = self
+          .manager()
+          .get_window(&label)
+          .and_then(|window| {
+            let webviews = window.webviews();
+            webviews
+              .iter()
+              .find(|webview| webview.label() == label)
+              .or(webviews.first())
+              .cloned()
+          })
+          .ok_or(crate::Error::WindowNotFound)?;
+        let command = request.cmd.clone();
+        let current_url = webview.url();
+        webview
+          .run_invoke(request, responder, true, current_url)
+          .map_err(|denial| crate::Error::CommandNotAllowed { command, denial })
+      }
+    }
+  }
+
+  /// Runs the command of the request on this webview, returning the [`Denial`] if `check_acl` is set and the ACL rejects it.
+  ///
+  /// The request is authorized for the origin of `current_url`, the URL of the page that sent it.
+  /// A [`BATCH_INVOKE_COMMAND`](crate::ipc::BATCH_INVOKE_COMMAND) request runs each of its commands,
+  /// see [`Self::run_invoke_batch`].
+  pub(crate) fn run_invoke(
+    self,
+    request: InvokeRequest,
+    responder: Box<OwnedInvokeResponder<R>>,
+    check_acl: bool,
+    current_url: Url,
+  ) -> Result<(), Denial> {
     let custom_responder = self.manager().webview.invoke_responder.clone();
+    let responder: Box<OwnedInvokeResponder<R>> =
+      Box::new(move |webview: Webview<R>, cmd, response, callback, error| {
+        if let Some(responder) = &custom_responder {
+          (responder)(&webview, &cmd, &response, callback, error);
+        }
 
-    let resolver = InvokeResolver::new(
-      self.clone(),
-      Arc::new(Mutex::new(Some(Box::new(
-        #[allow(unused_variables)]
-        move |webview: Webview<R>, cmd, response, callback, error| {
-          if let Some(responder) = &custom_responder {
-            (responder)(&webview, &cmd, &response, callback, error);
-          }
+        responder(webview, cmd, response, callback, error);
+      });
+
+    if request.cmd == crate::ipc::BATCH_INVOKE_COMMAND {
+      self.run_invoke_batch(request, responder, check_acl, current_url);
+      Ok(())
+    } else {
+      self.run_command(request, responder, check_acl, current_url, None)
+    }
+  }
 
-          responder(webview, cmd, response, callback, error);
+  /// Runs the commands of a [`BATCH_INVOKE_COMMAND`](crate::ipc::BATCH_INVOKE_COMMAND) request,
+  /// authorized at once with [`RuntimeAuthority::authorize_all`](crate::command::RuntimeAuthority::authorize_all).
+  ///
+  /// The commands run in the order of the batch, each through the same checks as a single invoke.
+  /// The batch responds once every command responded, with their responses in the order of the batch,
+  /// so a denied or failing command only fails its own item.
+  fn run_invoke_batch(
+    self,
+    request: InvokeRequest,
+    responder: Box<OwnedInvokeResponder<R>>,
+    check_acl: bool,
+    current_url: Url,
+  ) {
+    #[derive(serde::Deserialize)]
+    struct BatchItem {
+      cmd: String,
+      #[serde(default)]
+      payload: serde_json::Value,
+    }
+
+    let items = match request.body {
+      InvokeBody::Json(body) => serde_json::from_value::<Vec<BatchItem>>(body),
+      InvokeBody::Raw(body) => serde_json::from_slice::<Vec<BatchItem>>(&body),
+    };
+    let items = match items {
+      Ok(items) => items,
+      Err(e) => {
+        responder(
+          self,
+          request.cmd,
+          InvokeResponse::Err(format!("invalid batched invoke: {e}").into()),
+          request.callback,
+          request.error,
+        );
+        return;
+      }
+    };
+
+    let manager = self.manager_owned();
+    let is_local = manager.is_local_url(&current_url);
+    let webview_label = self.label().to_string();
+    let window_label = self.window().label().to_string();
+    let ancestors = manager.window.ancestors(&window_label);
+    let origin = manager.ipc_origin(&webview_label, &current_url, is_local);
+    let page = is_local.then(|| current_url.path().to_string());
+
+    let commands = items
+      .iter()
+      .map(|item| {
+        manager
+          .runtime_authority
+          .canonical_command(&item.cmd)
+          .unwrap_or_else(|| item.cmd.clone())
+      })
+      .collect::<Vec<_>>();
+    let requests = commands
+      .iter()
+      .map(|command| AccessRequest {
+        command,
+        window: &window_label,
+        webview: &webview_label,
+        ancestors: &ancestors,
+        origin: &origin,
+        page: page.as_deref(),
+      })
+      .collect::<Vec<_>>();
+    let authorize_start = std::time::Instant::now();
+    let decisions = manager.runtime_authority.authorize_all(&requests);
+    // each command records its share of the batch authorization time
+    let authorize_time = authorize_start.elapsed() / items.len().max(1) as u32;
+    drop(requests);
+
+    let batch = Arc::new(Mutex::new(BatchResponses {
+      responses: vec![None; items.len()],
+      responder: Some(responder),
+    }));
+    let respond = {
+      let webview = self.clone();
+      let (cmd, callback, error) = (request.cmd.clone(), request.callback, request.error);
+      move |batch: &Mutex<BatchResponses<R>>| {
+        let mut batch = batch.lock().unwrap();
+        if batch.responses.iter().any(Option::is_none) {
+          return;
+        }
+        let responses = batch.responses.drain(..).flatten().collect();
+        let responder = batch.responder.take();
+        drop(batch);
+        if let Some(responder) = responder {
+          responder(
+            webview.clone(),
+            cmd.clone(),
+            InvokeResponse::Ok(serde_json::Value::Array(responses).into()),
+            callback,
+            error,
+          );
+        }
+      }
+    };
+    let respond = Arc::new(respond);
+
+    if items.is_empty() {
+      respond(&batch);
+      return;
+    }
+
+    for (index, ((item, command), decision)) in
+      items.into_iter().zip(commands).zip(decisions).enumerate()
+    {
+      let batch = batch.clone();
+      let respond = respond.clone();
+      let item_responder: Box<OwnedInvokeResponder<R>> =
+        Box::new(move |_webview, _cmd, response, _callback, _error| {
+          let response = match response {
+            InvokeResponse::Ok(body) => serde_json::json!({ "ok": body.into_json() }),
+            InvokeResponse::Err(error) => serde_json::json!({ "error": error.0 }),
+          };
+          batch.lock().unwrap().responses[index] = Some(response);
+          respond(&batch);
+        });
+      let _ = self.clone().run_command(
+        InvokeRequest {
+          cmd: command,
+          callback: request.callback,
+          error: request.error,
+          body: item.payload.into(),
+          headers: request.headers.clone(),
         },
-      )))),
+        item_responder,
+        check_acl,
+        current_url.clone(),
+        Some((decision, authorize_time)),
+      );
+    }
+  }
+
+  /// Runs the command of a single invoke, authorized with the decision of its batch if it is part of one.
+  fn run_command(
+    self,
+    mut request: InvokeRequest,
+    responder: Box<OwnedInvokeResponder<R>>,
+    check_acl: bool,
+    current_url: Url,
+    batched: Option<(AccessDecision<'_>, std::time::Duration)>,
+  ) -> Result<(), Denial> {
+    let manager = self.manager_owned();
+    // frontends invoking a renamed plugin command keep working, authorized as the canonical command
+    if let Some(canonical) = manager.runtime_authority.canonical_command(&request.cmd) {
+      request.cmd = canonical;
+    }
+    let is_local = manager.is_local_url(&current_url);
+
+    // the ACL is resolved for the webview that owns this IPC channel, never for a label the request claims
+    let webview_label = self.label().to_string();
+    let window_label = self.window().label().to_string();
+    let ancestors = manager.window.ancestors(&window_label);
+
+    #[cfg(debug_assertions)]
+    if let Some(claimed) = request
+      .claimed_webview_label()
+      .filter(|claimed| *claimed != webview_label)
+    {
+      tauri_utils::debug_eprintln!(
+        "[tauri] IPC request `{}` claims to come from webview `{claimed}` but was sent by `{webview_label}`, authorizing it as `{webview_label}`",
+        request.cmd
+      );
+    }
+
+    let resolver = InvokeResolver::new(
+      self.clone(),
+      Arc::new(Mutex::new(Some(responder))),
       request.cmd.clone(),
       request.callback,
       request.error,
@@ -1085,28 +1366,45 @@ fn main() {
     #[cfg(mobile)]
     let app_handle = self.window.app_handle.clone();
 
-    let message = InvokeMessage::new(
+    let acl_origin = manager.ipc_origin(&webview_label, &current_url, is_local);
+
+    // the grants restricted to some pages follow the page that sent the request
+    let page = is_local.then(|| current_url.path().to_string());
+    let access_request = AccessRequest {
+      command: &request.cmd,
+      window: &window_label,
+      webview: &webview_label,
+      ancestors: &ancestors,
+      origin: &acl_origin,
+      page: page.as_deref(),
+    };
+    let (decision, authorize_time) = batched.unwrap_or_else(|| {
+      let authorize_start = std::time::Instant::now();
+      let decision = manager.runtime_authority.authorize(&access_request);
+      (decision, authorize_start.elapsed())
+    });
+    #[cfg(not(any(debug_assertions, feature = "acl-timing")))]
+    let _ = authorize_time;
+    let resolved_acl = decision.allowed().map(|acl| acl.on_page(page.as_deref()));
+    // the validators run on the request before its body is moved to the message
+    let validation = resolved_acl
+      .as_ref()
+      .filter(|_| check_acl)
+      .map(|acl| manager.webview.validate(&request, &acl.validators));
+
+    let mut message = InvokeMessage::new(
       self,
       manager.state(),
       request.cmd.to_string(),
       request.body,
       request.headers,
     );
-
-    let acl_origin = if is_local {
-      Origin::Local
-    } else {
-      Origin::Remote {
-        domain: current_url
-          .domain()
-          .map(|d| d.to_string())
-          .unwrap_or_default(),
-      }
-    };
-    let resolved_acl = manager
-      .runtime_authority
-      .resolve_access(&request.cmd, &message.webview.webview.label, &acl_origin)
-      .cloned();
+    message.page.clone_from(&page);
+    // the events emitted by the command are bound to the webview that passed the ACL check
+    message.authorized = resolved_acl
+      .as_ref()
+      .filter(|_| check_acl)
+      .map(|_| crate::ipc::AuthorizedWebview::new(&window_label, &webview_label));
 
     let mut invoke = Invoke {
       message,
@@ -1114,33 +1412,88 @@ fn main() {
       acl: resolved_acl,
     };
 
-    if let Some((plugin, command_name)) = request.cmd.strip_prefix("plugin:").map(|raw_command| {
-      let mut tokens = raw_command.split('|');
-      // safe to unwrap: split always has a least one item
-      let plugin = tokens.next().unwrap();
-      let command = tokens.next().map(|c| c.to_string()).unwrap_or_default();
-      (plugin, command)
-    }) {
-      if request.cmd != crate::ipc::channel::FETCH_CHANNEL_DATA_COMMAND && invoke.acl.is_none() {
-        #[cfg(debug_assertions)]
-        {
+    if request.cmd.starts_with(PLUGIN_COMMAND_PREFIX) {
+      let command_id = match CommandId::parse(&request.cmd) {
+        Ok(command_id) => command_id,
+        Err(e) => {
           invoke
             .resolver
-            .reject(manager.runtime_authority.resolve_access_message(
-              plugin,
-              &command_name,
-              &invoke.message.webview.webview.label,
-              &acl_origin,
-            ));
+            .reject(format!("invalid plugin command {}: {e}", request.cmd));
+          return Ok(());
         }
+      };
+      let plugin = command_id.plugin();
+
+      // internal plugins without permissions authorize their own commands
+      let internal = request.cmd == crate::ipc::channel::FETCH_CHANNEL_DATA_COMMAND;
+      #[cfg(debug_assertions)]
+      let internal = internal
+        || crate::acl_inspector::is_command_allowed(&request.cmd, &webview_label, &current_url);
+      // commands invoked from Rust without checking the ACL are not counted either
+      let check_acl = check_acl && !internal;
+      if check_acl {
+        manager.runtime_authority.record(&request.cmd, &decision);
+        #[cfg(any(debug_assertions, feature = "acl-timing"))]
+        manager.runtime_authority.record_timing(
+          None,
+          &request.cmd,
+          crate::command::AuthorizationPhase::Decision,
+          authorize_time,
+        );
+      }
+
+      if check_acl && invoke.acl.is_none() {
+        #[cfg(debug_assertions)]
+        let denial = {
+          let denial = manager
+            .runtime_authority
+            .explain_request(&access_request)
+            .unwrap_or(Denial::NotAllowed);
+          invoke.resolver.reject(serde_json::json!({
+            "message": format!("{plugin}.{} {denial}", command_id.command()),
+            "denial": denial,
+          }));
+          denial
+        };
         #[cfg(not(debug_assertions))]
-        invoke
-          .resolver
-          .reject(format!("Command {} not allowed by ACL", request.cmd));
-        return;
+        let denial = {
+          invoke
+            .resolver
+            .reject(format!("Command {} not allowed by ACL", request.cmd));
+          Denial::NotAllowed
+        };
+        return Err(denial);
+      }
+
+      if let Some(class) = invoke.acl.as_ref().filter(|_| check_acl).and_then(|acl| {
+        manager
+          .webview
+          .check_first_use(&invoke.message.webview, &acl.classes)
+      }) {
+        invoke.resolver.reject(format!(
+          "Command {} not allowed: use of {class} not granted",
+          request.cmd
+        ));
+        return Ok(());
+      }
+
+      if let Some(Err((validator, error))) = validation {
+        invoke.resolver.reject(format!(
+          "Command {} rejected by validator {validator}: {error}",
+          request.cmd
+        ));
+        return Ok(());
+      }
+
+      // the argument rules run after the validators, before the plugin deserializes the arguments
+      if check_acl {
+        if let Err(error) = crate::command::check_arguments(&invoke, &request.cmd, Some(plugin)) {
+          invoke.resolver.invoke_error(error);
+          return Ok(());
+        }
       }
 
-      invoke.message.command = command_name;
+      invoke.message.command = command_id.command().to_string();
 
       let command = invoke.message.command.clone();
 
@@ -1148,7 +1501,9 @@ fn main() {
       let message = invoke.message.clone();
 
       #[allow(unused_mut)]
-      let mut handled = manager.extend_api(plugin, invoke);
+      let mut handled = crate::ipc::binding::bind(invoke.message.authorized.clone(), || {
+        manager.extend_api(plugin, invoke)
+      });
 
       #[cfg(mobile)]
       {
@@ -1184,7 +1539,7 @@ fn main() {
             },
           ) {
             resolver.reject(e.to_string());
-            return;
+            return Ok(());
           }
         }
       }
@@ -1193,12 +1548,33 @@ fn main() {
         resolver.reject(format!("Command {command} not found"));
       }
     } else {
+      #[cfg(any(debug_assertions, feature = "acl-timing"))]
+      if check_acl {
+        manager.runtime_authority.record_timing(
+          None,
+          &request.cmd,
+          crate::command::AuthorizationPhase::Decision,
+          authorize_time,
+        );
+      }
+
+      if check_acl {
+        if let Err(error) = crate::command::check_arguments(&invoke, &request.cmd, None) {
+          invoke.resolver.invoke_error(error);
+          return Ok(());
+        }
+      }
+
       let command = invoke.message.command.clone();
-      let handled = manager.run_invoke_handler(invoke);
+      let handled = crate::ipc::binding::bind(invoke.message.authorized.clone(), || {
+        manager.run_invoke_handler(invoke)
+      });
       if !handled {
         resolver.reject(format!("Command {command} not found"));
       }
     }
+
+    Ok(())
   }
 
   /// Evaluates JavaScript on this window.
@@ -1515,6 +1891,7 @@ impl<R: Runtime> Manager<R> for Webview<R> {
     event: &str,
     payload: S,
   ) -> crate::Result<()> {
+    let label = normalize_label(label);
     self.manager().emit_filter(
       event,
       EventSource::Webview {
@@ -1568,9 +1945,1216 @@ impl<'de, R: Runtime> CommandArg<'de, R> for Webview<R> {
 
 #[cfg(test)]
 mod tests {
+  use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  };
+
+  use tauri_utils::acl::{
+    resolved::{CommandKey, ResolvedCommand},
+    ExecutionContext,
+  };
+
+  use http::HeaderMap;
+
+  use super::InvokeRequest;
+  use crate::{
+    command::{CommandMetrics, FirstUseDecision},
+    ipc::{CallbackFn, InvokeBody},
+    plugin::Builder as PluginBuilder,
+    test::{get_ipc_response, mock_builder, mock_context, noop_assets, MockRuntime},
+    WebviewWindowBuilder,
+  };
+
   #[test]
   fn webview_is_send_sync() {
     crate::test_utils::assert_send::<super::Webview>();
     crate::test_utils::assert_sync::<super::Webview>();
   }
+
+  #[crate::command(root = "crate")]
+  fn read() -> &'static str {
+    "contents"
+  }
+
+  #[derive(serde::Deserialize)]
+  #[serde(rename_all = "kebab-case")]
+  struct FetchConfig {
+    user_agent_suffix: String,
+  }
+
+  #[crate::command(root = "crate")]
+  fn fetch(config: crate::command::CommandConfig<FetchConfig>) -> String {
+    config
+      .entries()
+      .iter()
+      .map(|entry| format!("{}={}", entry.capability, entry.config.user_agent_suffix))
+      .collect::<Vec<_>>()
+      .join(",")
+  }
+
+  #[test]
+  fn command_config_is_extracted_for_the_calling_window() {
+    use tauri_utils::acl::{resolved::WindowConfig, Value};
+
+    let config = |capability: &str, window: &str, suffix: Value| WindowConfig {
+      capability: capability.into(),
+      windows: vec![window.parse().unwrap()],
+      window_ancestors: Vec::new(),
+      webviews: Vec::new(),
+      pages: Vec::new(),
+      config: Value::Map([("user-agent-suffix".to_string(), suffix)].into()),
+    };
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:http|fetch".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["*".parse().unwrap()],
+        configs: vec![
+          config("beta", "partner-*", Value::String("beta".into())),
+          config("broken", "broken", Value::Bool(true)),
+          config("partner", "partner-*", Value::String("partner".into())),
+        ],
+        ..Default::default()
+      },
+    );
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("http")
+          .invoke_handler(crate::generate_handler![fetch])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let fetch = |label: &str| {
+      let webview = WebviewWindowBuilder::new(&app, label, Default::default())
+        .build()
+        .unwrap();
+      get_ipc_response(
+        &webview,
+        InvokeRequest {
+          cmd: "plugin:http|fetch".into(),
+          callback: CallbackFn(0),
+          error: CallbackFn(1),
+          body: Default::default(),
+          headers: Default::default(),
+        },
+      )
+    };
+
+    assert_eq!(fetch("main").unwrap().deserialize::<String>().unwrap(), "");
+    assert_eq!(
+      fetch("partner-1").unwrap().deserialize::<String>().unwrap(),
+      "beta=beta,partner=partner"
+    );
+    // a configuration of the wrong type fails the extraction
+    let error = fetch("broken").unwrap_err();
+    assert!(
+      error
+        .as_str()
+        .is_some_and(|error| error.contains("capability broken")),
+      "unexpected error {error}"
+    );
+  }
+
+  #[test]
+  fn first_use_hook_is_consulted_once_per_webview_and_class() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:files|read".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["*".parse().unwrap()],
+        classes: vec!["fs-read".into()],
+        ..Default::default()
+      },
+    );
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_ = calls.clone();
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .on_first_use(move |webview, class| {
+        assert_eq!(class, "fs-read");
+        calls_.fetch_add(1, Ordering::SeqCst);
+        if webview.label() == "main" {
+          FirstUseDecision::Allow
+        } else {
+          FirstUseDecision::Deny
+        }
+      })
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let other = WebviewWindowBuilder::new(&app, "other", Default::default())
+      .build()
+      .unwrap();
+
+    let request = || InvokeRequest {
+      cmd: "plugin:files|read".into(),
+      callback: CallbackFn(0),
+      error: CallbackFn(1),
+      body: Default::default(),
+      headers: Default::default(),
+    };
+
+    for _ in 0..2 {
+      assert!(get_ipc_response(&main, request()).is_ok());
+      assert!(get_ipc_response(&other, request()).is_err());
+    }
+    // the decisions are cached for the session
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+  }
+
+  #[test]
+  fn reused_label_does_not_inherit_first_use_decisions() {
+    use crate::{sealed::ManagerBase, test::get_ipc_response_from, webview::Url, WebviewUrl};
+
+    let mut context = mock_context(noop_assets());
+    for execution_context in [
+      ExecutionContext::Local,
+      ExecutionContext::Remote {
+        domain: "evil.example.com".parse().unwrap(),
+        attestation: None,
+      },
+    ] {
+      context.resolved_acl.allowed_commands.insert(
+        CommandKey {
+          name: "plugin:files|read".into(),
+          context: execution_context,
+        },
+        ResolvedCommand {
+          windows: vec!["main".parse().unwrap()],
+          classes: vec!["fs-read".into()],
+          ..Default::default()
+        },
+      );
+    }
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_ = calls.clone();
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      // only the first webview is allowed
+      .on_first_use(move |_webview, _class| {
+        if calls_.fetch_add(1, Ordering::SeqCst) == 0 {
+          FirstUseDecision::Allow
+        } else {
+          FirstUseDecision::Deny
+        }
+      })
+      .build(context)
+      .unwrap();
+    let request = || InvokeRequest {
+      cmd: "plugin:files|read".into(),
+      callback: CallbackFn(0),
+      error: CallbackFn(1),
+      body: Default::default(),
+      headers: Default::default(),
+    };
+
+    let local = Url::parse("tauri://localhost").unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    assert!(get_ipc_response_from(&main, &local, request()).is_ok());
+
+    // the window is destroyed and a window loading a remote page takes its label
+    app.manager().on_window_close("main");
+    let remote = Url::parse("https://evil.example.com").unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", WebviewUrl::External(remote.clone()))
+      .build()
+      .unwrap();
+    assert!(get_ipc_response_from(&main, &remote, request()).is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+  }
+
+  #[test]
+  fn isolation_validator_rejects_marked_payloads() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:files|read".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["*".parse().unwrap()],
+        validators: vec!["no-marker".into()],
+        ..Default::default()
+      },
+    );
+
+    // the app does not start without the validators referenced by the resolved commands
+    let mut missing = mock_context(noop_assets());
+    missing.resolved_acl.allowed_commands = context.resolved_acl.allowed_commands.clone();
+    assert!(matches!(
+      mock_builder().build(missing),
+      Err(crate::Error::UnknownIsolationValidator { validator, .. }) if validator == "no-marker"
+    ));
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .isolation_validator("no-marker", |request| match &request.body {
+        InvokeBody::Json(body) if body.to_string().contains("__MARKER__") => {
+          Err("payload contains the marker".into())
+        }
+        _ => Ok(()),
+      })
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+
+    let request = |value: &str| InvokeRequest {
+      cmd: "plugin:files|read".into(),
+      callback: CallbackFn(0),
+      error: CallbackFn(1),
+      body: serde_json::json!({ "value": value }).into(),
+      headers: Default::default(),
+    };
+
+    assert!(get_ipc_response(&main, request("hello")).is_ok());
+    let error = get_ipc_response(&main, request("__MARKER__")).unwrap_err();
+    assert!(
+      error
+        .as_str()
+        .is_some_and(|error| error.contains("rejected by validator no-marker")),
+      "unexpected error {error}"
+    );
+  }
+
+  #[test]
+  fn argument_rules_are_enforced_before_dispatch() {
+    use tauri_utils::acl::{
+      args::{ArgumentMatcher, ArgumentRule},
+      resolved::{ResolvedScope, WindowScope},
+      Number, Value,
+    };
+
+    let rule = |name: &str, optional: bool, matcher: ArgumentMatcher| ArgumentRule {
+      name: name.into(),
+      optional,
+      matcher,
+    };
+    let entry = |field: &str, pattern: &str| {
+      Value::Map([(field.to_string(), Value::String(pattern.into()))].into())
+    };
+
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:files|read".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["*".parse().unwrap()],
+        scopes: vec![WindowScope {
+          windows: vec!["*".parse().unwrap()],
+          window_ancestors: Vec::new(),
+          webviews: Vec::new(),
+          pages: Vec::new(),
+          scope: 0,
+        }],
+        args: vec![
+          rule(
+            "mode",
+            false,
+            ArgumentMatcher::Enum {
+              values: vec![Value::String("read".into()), Value::String("write".into())],
+            },
+          ),
+          rule(
+            "size",
+            true,
+            ArgumentMatcher::Range {
+              min: Some(Number::Int(1)),
+              max: Some(Number::Int(4096)),
+            },
+          ),
+          rule("source", true, ArgumentMatcher::UrlScope),
+          rule("path", true, ArgumentMatcher::PathScope),
+        ],
+        ..Default::default()
+      },
+    );
+    context.resolved_acl.command_scope.insert(
+      0,
+      ResolvedScope {
+        allow: vec![
+          entry("url", "https://tauri.app/*"),
+          entry("path", "/tmp/tauri-args/*"),
+        ],
+        deny: vec![
+          entry("url", "https://tauri.app/private/*"),
+          entry("path", "/tmp/tauri-args/secret"),
+        ],
+        external: None,
+      },
+    );
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+
+    let request = |body: InvokeBody| InvokeRequest {
+      cmd: "plugin:files|read".into(),
+      callback: CallbackFn(0),
+      error: CallbackFn(1),
+      body,
+      headers: Default::default(),
+    };
+    let invoke = |args: serde_json::Value| get_ipc_response(&main, request(args.into()));
+    let rejection = |args: serde_json::Value| {
+      let error = invoke(args).unwrap_err();
+      assert!(
+        error["message"]
+          .as_str()
+          .is_some_and(|message| message.starts_with("Command plugin:files|read rejected")),
+        "unexpected error {error}"
+      );
+      error["error"].clone()
+    };
+
+    // extra arguments are not checked
+    assert!(invoke(serde_json::json!({
+      "mode": "read",
+      "size": 4096,
+      "source": "https://tauri.app/docs",
+      "path": "/tmp/tauri-args/notes.txt",
+      "encoding": "utf-8",
+    }))
+    .is_ok());
+    assert!(invoke(serde_json::json!({ "mode": "write", "size": null })).is_ok());
+
+    assert_eq!(
+      rejection(serde_json::json!({ "size": 1 })),
+      serde_json::json!({ "reason": "missing", "argument": "mode" })
+    );
+    assert_eq!(
+      rejection(serde_json::json!({ "mode": "delete" })),
+      serde_json::json!({ "reason": "notAllowed", "argument": "mode" })
+    );
+    assert_eq!(
+      rejection(serde_json::json!({ "mode": "read", "size": 0 })),
+      serde_json::json!({ "reason": "outOfRange", "argument": "size", "min": 1.0, "max": 4096.0 })
+    );
+    assert_eq!(
+      rejection(serde_json::json!({ "mode": "read", "size": "big" })),
+      serde_json::json!({ "reason": "invalidType", "argument": "size", "expected": "number" })
+    );
+    for source in [
+      "https://tauri.app/private/key",
+      "https://evil.example.com",
+      "not a url",
+    ] {
+      assert_eq!(
+        rejection(serde_json::json!({ "mode": "read", "source": source })),
+        serde_json::json!({ "reason": "outOfScope", "argument": "source" })
+      );
+    }
+    for path in ["/tmp/tauri-args/secret", "/etc/passwd"] {
+      assert_eq!(
+        rejection(serde_json::json!({ "mode": "read", "path": path })),
+        serde_json::json!({ "reason": "outOfScope", "argument": "path" })
+      );
+    }
+
+    let error = get_ipc_response(&main, request(InvokeBody::Raw(b"raw".to_vec()))).unwrap_err();
+    assert_eq!(
+      error["error"],
+      serde_json::json!({ "reason": "notAnObject" })
+    );
+  }
+
+  #[test]
+  fn forged_webview_label_is_ignored() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:files|read".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["main".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let other = WebviewWindowBuilder::new(&app, "other", Default::default())
+      .build()
+      .unwrap();
+
+    let request = |claimed_label: &str| {
+      let mut headers = HeaderMap::new();
+      headers.insert(
+        super::TAURI_WEBVIEW_LABEL_HEADER_NAME,
+        claimed_label.parse().unwrap(),
+      );
+      InvokeRequest {
+        cmd: "plugin:files|read".into(),
+        callback: CallbackFn(0),
+        error: CallbackFn(1),
+        body: Default::default(),
+        headers,
+      }
+    };
+
+    // the request is authorized for the webview that sent it, not for the claimed label
+    assert!(get_ipc_response(&other, request("main")).is_err());
+    assert!(get_ipc_response(&main, request("other")).is_ok());
+  }
+
+  #[test]
+  fn acl_metrics_count_decisions() {
+    let mut context = mock_context(noop_assets());
+    let key = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Local,
+    };
+    let resolved = ResolvedCommand {
+      windows: vec!["main".parse().unwrap()],
+      ..Default::default()
+    };
+    context
+      .resolved_acl
+      .allowed_commands
+      .insert(key("plugin:files|read"), resolved.clone());
+    context
+      .resolved_acl
+      .denied_commands
+      .insert(key("plugin:files|remove"), resolved);
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let other = WebviewWindowBuilder::new(&app, "other", Default::default())
+      .build()
+      .unwrap();
+
+    let request = |cmd: &str| InvokeRequest {
+      cmd: cmd.into(),
+      callback: CallbackFn(0),
+      error: CallbackFn(1),
+      body: Default::default(),
+      headers: Default::default(),
+    };
+
+    assert!(get_ipc_response(&main, request("plugin:files|read")).is_ok());
+    assert!(get_ipc_response(&main, request("plugin:files|read")).is_ok());
+    assert!(get_ipc_response(&other, request("plugin:files|read")).is_err());
+    assert!(get_ipc_response(&main, request("plugin:files|remove")).is_err());
+    assert!(get_ipc_response(&main, request("plugin:files|write")).is_err());
+
+    let metrics = app.handle().acl_metrics();
+    assert_eq!(metrics.commands.len(), 2);
+    assert_eq!(
+      metrics.command("plugin:files|read"),
+      CommandMetrics {
+        allowed: 2,
+        denied: 0,
+        not_allowed: 1,
+      }
+    );
+    assert_eq!(
+      metrics.command("plugin:files|remove"),
+      CommandMetrics {
+        allowed: 0,
+        denied: 1,
+        not_allowed: 0,
+      }
+    );
+    assert_eq!(
+      metrics.other,
+      CommandMetrics {
+        allowed: 0,
+        denied: 0,
+        not_allowed: 1,
+      }
+    );
+  }
+
+  #[test]
+  fn role_grants_follow_the_active_roles() {
+    use crate::Manager;
+    use tauri_utils::acl::resolved::{Resolved, RoleGrant};
+
+    let mut context = mock_context(noop_assets());
+    let mut acl = Resolved::default();
+    acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:files|read".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["main".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+    context.resolved_acl.role_grants.push(RoleGrant {
+      capability: "admin".into(),
+      roles: vec!["admin".into()],
+      acl,
+    });
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+
+    let request = || InvokeRequest {
+      cmd: "plugin:files|read".into(),
+      callback: CallbackFn(0),
+      error: CallbackFn(1),
+      body: Default::default(),
+      headers: Default::default(),
+    };
+
+    assert!(get_ipc_response(&main, request()).is_err());
+
+    app.acl().set_roles(&["user", "admin"]);
+    assert_eq!(app.acl().roles(), vec!["user", "admin"]);
+    assert!(get_ipc_response(&main, request()).is_ok());
+
+    app.acl().set_roles(&["user"]);
+    assert!(get_ipc_response(&main, request()).is_err());
+
+    app.acl().set_roles(&[] as &[&str]);
+    assert!(app.acl().roles().is_empty());
+    assert!(get_ipc_response(&main, request()).is_err());
+  }
+
+  #[cfg(any(debug_assertions, feature = "acl-timing"))]
+  #[test]
+  fn acl_timings_count_invokes() {
+    let mut context = mock_context(noop_assets());
+    let key = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Local,
+    };
+    let resolved = ResolvedCommand {
+      windows: vec!["main".parse().unwrap()],
+      ..Default::default()
+    };
+    context
+      .resolved_acl
+      .allowed_commands
+      .insert(key("plugin:files|read"), resolved.clone());
+    context
+      .resolved_acl
+      .denied_commands
+      .insert(key("plugin:files|remove"), resolved);
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+
+    let request = |cmd: &str| InvokeRequest {
+      cmd: cmd.into(),
+      callback: CallbackFn(0),
+      error: CallbackFn(1),
+      body: Default::default(),
+      headers: Default::default(),
+    };
+
+    for _ in 0..3 {
+      assert!(get_ipc_response(&main, request("plugin:files|read")).is_ok());
+    }
+    assert!(get_ipc_response(&main, request("plugin:files|remove")).is_err());
+    assert!(get_ipc_response(&main, request("plugin:files|write")).is_err());
+
+    let timings = app.handle().acl_timings();
+    assert_eq!(timings.commands.len(), 2);
+    let read = timings.command("plugin:files|read").decision;
+    assert_eq!(read.count, 3);
+    assert!(read.p50_nanos <= read.p90_nanos && read.p99_nanos <= read.max_nanos);
+    assert_eq!(timings.command("plugin:files|remove").decision.count, 1);
+    assert_eq!(timings.other.decision.count, 1);
+  }
+
+  #[cfg(debug_assertions)]
+  #[test]
+  fn unmatched_window_patterns() {
+    use tauri_utils::acl::resolved::ResolvedCommandReference;
+
+    let mut context = mock_context(noop_assets());
+    let resolved = |capability: &str, windows: &[&str]| ResolvedCommand {
+      referenced_by: vec![ResolvedCommandReference {
+        capability: capability.into(),
+        permission: "allow-read".into(),
+        delegated_by: None,
+      }],
+      windows: windows.iter().map(|w| w.parse().unwrap()).collect(),
+      ..Default::default()
+    };
+    let key = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Local,
+    };
+    context.resolved_acl.allowed_commands.insert(
+      key("plugin:files|read"),
+      resolved("main", &["main", "mian", "editor-*"]),
+    );
+    context.resolved_acl.denied_commands.insert(
+      key("plugin:files|remove"),
+      resolved("restricted", &["mian"]),
+    );
+
+    let app = mock_builder().build(context).unwrap();
+    WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let unmatched = |app: &crate::App<MockRuntime>| {
+      app
+        .handle()
+        .unmatched_window_patterns()
+        .into_iter()
+        .map(|unmatched| (unmatched.pattern, unmatched.capabilities))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      unmatched(&app),
+      vec![
+        ("editor-*".to_string(), vec!["main".to_string()]),
+        (
+          "mian".to_string(),
+          vec!["main".to_string(), "restricted".to_string()]
+        ),
+      ]
+    );
+
+    // windows created later are taken into account
+    WebviewWindowBuilder::new(&app, "editor-1", Default::default())
+      .build()
+      .unwrap();
+    assert_eq!(
+      unmatched(&app),
+      vec![(
+        "mian".to_string(),
+        vec!["main".to_string(), "restricted".to_string()]
+      )]
+    );
+  }
+
+  #[test]
+  fn labels_are_normalized() {
+    use crate::Manager;
+
+    let app = mock_builder().build(mock_context(noop_assets())).unwrap();
+    // `e` followed by a combining acute accent is composed into `é`
+    let window = WebviewWindowBuilder::new(&app, "cafe\u{301}", Default::default())
+      .build()
+      .unwrap();
+    assert_eq!(window.label(), "caf\u{e9}");
+    assert!(app.get_webview_window("caf\u{e9}").is_some());
+    // lookups and event targets are normalized too
+    assert!(app.get_webview_window("cafe\u{301}").is_some());
+    let (tx, rx) = std::sync::mpsc::channel();
+    window.listen("label-event", move |_| tx.send(()).unwrap());
+    app.emit_to("cafe\u{301}", "label-event", ()).unwrap();
+    assert!(rx.recv_timeout(std::time::Duration::from_secs(1)).is_ok());
+
+    assert!(
+      WebviewWindowBuilder::new(&app, "editor-\u{1f600}", Default::default())
+        .build()
+        .is_err()
+    );
+  }
+
+  #[test]
+  fn emit_targets_are_scoped_per_capability() {
+    use crate::Manager;
+    use tauri_utils::acl::{
+      resolved::{ResolvedScope, WindowScope},
+      Value,
+    };
+
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:event|emit".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["*".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:event|emit".into(),
+        context: ExecutionContext::Remote {
+          domain: "remote.tauri.app".parse().unwrap(),
+          attestation: None,
+        },
+      },
+      ResolvedCommand {
+        windows: vec!["embedded".parse().unwrap()],
+        scopes: vec![WindowScope {
+          windows: vec!["embedded".parse().unwrap()],
+          window_ancestors: Vec::new(),
+          webviews: Vec::new(),
+          pages: Vec::new(),
+          scope: 0,
+        }],
+        ..Default::default()
+      },
+    );
+    context.resolved_acl.command_scope.insert(
+      0,
+      ResolvedScope {
+        allow: vec![Value::Map(
+          [(
+            "targets".to_string(),
+            Value::List(vec![Value::String("status-bar".into())]),
+          )]
+          .into(),
+        )],
+        deny: Vec::new(),
+        external: None,
+      },
+    );
+
+    let app = mock_builder().build(context).unwrap();
+    for label in ["main", "status-bar"] {
+      WebviewWindowBuilder::new(&app, label, Default::default())
+        .build()
+        .unwrap();
+    }
+    let embedded = WebviewWindowBuilder::new(
+      &app,
+      "embedded",
+      crate::WebviewUrl::External("https://remote.tauri.app".parse().unwrap()),
+    )
+    .build()
+    .unwrap();
+    let main = app.get_webview_window("main").unwrap();
+
+    let emit = |webview: &crate::WebviewWindow<MockRuntime>, target: serde_json::Value| {
+      get_ipc_response(
+        webview,
+        InvokeRequest {
+          cmd: "plugin:event|emit".into(),
+          callback: CallbackFn(0),
+          error: CallbackFn(1),
+          body: serde_json::json!({ "event": "status", "target": target }).into(),
+          headers: Default::default(),
+        },
+      )
+    };
+
+    assert!(emit(
+      &embedded,
+      serde_json::json!({ "kind": "webview", "label": "status-bar" })
+    )
+    .is_ok());
+    let error = emit(
+      &embedded,
+      serde_json::json!({ "kind": "webview", "label": "main" }),
+    )
+    .unwrap_err();
+    assert!(
+      error
+        .as_str()
+        .is_some_and(|error| error.contains("webview `main`")),
+      "unexpected error {error}"
+    );
+    // broadcasting requires an explicit `*` target
+    assert!(emit(&embedded, serde_json::json!({ "kind": "global" })).is_err());
+
+    // the unscoped local capability keeps every target allowed
+    assert!(emit(
+      &main,
+      serde_json::json!({ "kind": "webview", "label": "embedded" })
+    )
+    .is_ok());
+    assert!(emit(&main, serde_json::json!({ "kind": "global" })).is_ok());
+  }
+
+  #[crate::command(root = "crate")]
+  fn announce(app: crate::AppHandle<MockRuntime>, status: String) -> crate::Result<()> {
+    use crate::Manager;
+    app.emit("status", status)
+  }
+
+  #[crate::command(root = "crate")]
+  async fn announce_later(app: crate::AppHandle<MockRuntime>, status: String) -> crate::Result<()> {
+    use crate::Manager;
+    app.emit("status", status)
+  }
+
+  #[test]
+  fn command_events_are_bound_to_the_authorized_webview() {
+    use crate::{event::EventSource, Manager};
+
+    let mut context = mock_context(noop_assets());
+    context.config.tauri.security.bind_command_events = true;
+    for command in ["announce", "announce_later"] {
+      context.resolved_acl.allowed_commands.insert(
+        CommandKey {
+          name: format!("plugin:producer|{command}"),
+          context: ExecutionContext::Local,
+        },
+        ResolvedCommand {
+          windows: vec!["main".parse().unwrap()],
+          ..Default::default()
+        },
+      );
+    }
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("producer")
+          .invoke_handler(crate::generate_handler![announce, announce_later])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let other = WebviewWindowBuilder::new(&app, "other", Default::default())
+      .build()
+      .unwrap();
+
+    for webview in [&main, &other] {
+      webview
+        .as_ref()
+        .listen_js(EventSource::Global, "status".into(), CallbackFn(2))
+        .unwrap();
+    }
+    let (tx, rx) = std::sync::mpsc::channel();
+    app.listen_global("status", move |event| {
+      tx.send(event.payload().to_string()).unwrap();
+    });
+
+    let last_script = |webview: &crate::WebviewWindow<MockRuntime>| {
+      webview
+        .as_ref()
+        .webview
+        .dispatcher
+        .last_evaluated_script()
+        .unwrap_or_default()
+    };
+    let announce = |webview: &crate::WebviewWindow<MockRuntime>, command: &str, status: &str| {
+      get_ipc_response(
+        webview,
+        InvokeRequest {
+          cmd: format!("plugin:producer|{command}"),
+          callback: CallbackFn(0),
+          error: CallbackFn(1),
+          body: serde_json::json!({ "status": status }).into(),
+          headers: Default::default(),
+        },
+      )
+    };
+
+    // only the main webview is allowed to call the producing commands
+    assert!(announce(&other, "announce", "from-other").is_err());
+    assert!(!last_script(&main).contains("from-other"));
+    assert!(rx.try_recv().is_err());
+
+    for command in ["announce", "announce_later"] {
+      let status = format!("{command}-status");
+      assert!(announce(&main, command, &status).is_ok());
+      assert!(last_script(&main).contains(&status));
+      // the global event does not reach the listeners of the other webview, the Rust listeners still get it
+      assert!(!last_script(&other).contains(&status));
+      assert_eq!(
+        rx.recv_timeout(std::time::Duration::from_secs(1)).unwrap(),
+        format!("\"{status}\"")
+      );
+    }
+
+    // the events emitted outside of a command are not bound
+    app.emit("status", "broadcast").unwrap();
+    assert!(last_script(&main).contains("broadcast"));
+    assert!(last_script(&other).contains("broadcast"));
+  }
+
+  #[crate::command(root = "crate")]
+  async fn read_later(path: String) -> String {
+    // responds after the commands that follow it in the batch
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    path
+  }
+
+  #[test]
+  fn batched_invokes_are_authorized_per_item() {
+    use crate::ipc::BATCH_INVOKE_COMMAND;
+
+    let mut context = mock_context(noop_assets());
+    let key = |name: &str| CommandKey {
+      name: name.into(),
+      context: ExecutionContext::Local,
+    };
+    let resolved = ResolvedCommand {
+      windows: vec!["main".parse().unwrap()],
+      ..Default::default()
+    };
+    for command in ["plugin:files|read", "plugin:files|read_later"] {
+      context
+        .resolved_acl
+        .allowed_commands
+        .insert(key(command), resolved.clone());
+    }
+    context
+      .resolved_acl
+      .denied_commands
+      .insert(key("plugin:files|remove"), resolved);
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read, read_later])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let other = WebviewWindowBuilder::new(&app, "other", Default::default())
+      .build()
+      .unwrap();
+
+    let batch = |webview: &crate::WebviewWindow<MockRuntime>, items: serde_json::Value| {
+      get_ipc_response(
+        webview,
+        InvokeRequest {
+          cmd: BATCH_INVOKE_COMMAND.into(),
+          callback: CallbackFn(0),
+          error: CallbackFn(1),
+          body: items.into(),
+          headers: Default::default(),
+        },
+      )
+      .map(|body| body.deserialize::<Vec<serde_json::Value>>().unwrap())
+    };
+    let items = serde_json::json!([
+      { "cmd": "plugin:files|read_later", "payload": { "path": "first" } },
+      { "cmd": "plugin:files|read" },
+      { "cmd": "plugin:files|remove" },
+      { "cmd": "plugin:files|write" },
+      { "cmd": "plugin:files|read" },
+    ]);
+
+    // the responses keep the order of the batch, the denied items only fail themselves
+    let responses = batch(&main, items.clone()).unwrap();
+    assert_eq!(responses.len(), 5);
+    assert_eq!(responses[0], serde_json::json!({ "ok": "first" }));
+    assert_eq!(responses[1], serde_json::json!({ "ok": "contents" }));
+    assert!(responses[2].get("error").is_some());
+    assert!(responses[3].get("error").is_some());
+    assert_eq!(responses[4], serde_json::json!({ "ok": "contents" }));
+
+    // the items are authorized for the webview that sent the batch
+    let responses = batch(&other, items).unwrap();
+    assert!(responses
+      .iter()
+      .all(|response| response.get("error").is_some()));
+
+    assert_eq!(
+      batch(&main, serde_json::json!([])).unwrap(),
+      Vec::<serde_json::Value>::new()
+    );
+    assert!(batch(&main, serde_json::json!({ "cmd": "plugin:files|read" })).is_err());
+  }
+
+  #[test]
+  fn rust_invoke_as_window_runs_the_authority_check() {
+    use super::InvokeAccess;
+    use crate::{command::Denial, ipc::InvokeResponse};
+
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:files|read".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["main".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .invoke_handler(crate::generate_handler![read])
+          .build(),
+      )
+      .build(context)
+      .unwrap();
+    let main = WebviewWindowBuilder::new(&app, "main", Default::default())
+      .build()
+      .unwrap();
+    let popup = WebviewWindowBuilder::new(&app, "popup", Default::default())
+      .build()
+      .unwrap();
+
+    let invoke = |webview: &crate::WebviewWindow<MockRuntime>, access: InvokeAccess| {
+      let (tx, rx) = std::sync::mpsc::sync_channel(1);
+      let result = webview.invoke(
+        InvokeRequest {
+          cmd: "plugin:files|read".into(),
+          callback: CallbackFn(0),
+          error: CallbackFn(1),
+          body: Default::default(),
+          headers: HeaderMap::default(),
+        },
+        access,
+        Box::new(move |webview, _cmd, response, _callback, _error| {
+          tx.send((webview.label().to_string(), response)).unwrap();
+        }),
+      );
+      let (label, response) = rx.recv().unwrap();
+      (result, label, matches!(response, InvokeResponse::Ok(_)))
+    };
+
+    let (result, label, ok) = invoke(&popup, InvokeAccess::AsWindow("main".into()));
+    assert!(result.is_ok());
+    assert_eq!(label, "main");
+    assert!(ok);
+
+    // the command is rejected and the structured denial returned to the Rust caller
+    let (result, label, ok) = invoke(&main, InvokeAccess::AsWindow("popup".into()));
+    assert_eq!(label, "popup");
+    assert!(!ok);
+    match result {
+      Err(crate::Error::CommandNotAllowed { command, denial }) => {
+        assert_eq!(command, "plugin:files|read");
+        assert_eq!(
+          denial,
+          Denial::WindowNotMatched {
+            windows: vec!["main".into()]
+          }
+        );
+      }
+      result => panic!("unexpected result {result:?}"),
+    }
+
+    // the bypass runs the command on the calling webview, whatever the ACL grants
+    let (result, label, ok) = invoke(&popup, InvokeAccess::Bypass);
+    assert!(result.is_ok());
+    assert_eq!(label, "popup");
+    assert!(ok);
+    assert!(matches!(
+      main.invoke(
+        InvokeRequest {
+          cmd: "plugin:files|read".into(),
+          callback: CallbackFn(0),
+          error: CallbackFn(1),
+          body: Default::default(),
+          headers: HeaderMap::default(),
+        },
+        InvokeAccess::AsWindow("missing".into()),
+        Box::new(|_, _, _, _, _| {}),
+      ),
+      Err(crate::Error::WindowNotFound)
+    ));
+  }
+
+  #[test]
+  fn uri_scheme_permission_is_checked_per_webview() {
+    let mut context = mock_context(noop_assets());
+    context.resolved_acl.allowed_commands.insert(
+      CommandKey {
+        name: "plugin:files|protocol".into(),
+        context: ExecutionContext::Local,
+      },
+      ResolvedCommand {
+        windows: vec!["main".parse().unwrap()],
+        ..Default::default()
+      },
+    );
+
+    let app = mock_builder()
+      .plugin(
+        PluginBuilder::<MockRuntime>::new("files")
+          .register_uri_scheme_protocol("files", |_app, _request| {
+            http::Response::builder().body(Vec::new()).unwrap()
+          })
+          .require_uri_scheme_permission("files", "protocol")
+          .build(),
+      )
+      .register_uri_scheme_protocol("public", |_app, _request| {
+        http::Response::builder().body(Vec::new()).unwrap()
+      })
+      .build(context)
+      .unwrap();
+    for label in ["main", "secondary"] {
+      WebviewWindowBuilder::new(&app, label, Default::default())
+        .build()
+        .unwrap();
+    }
+
+    let manager = &crate::sealed::ManagerBase::manager(&app).webview;
+    assert!(manager.is_uri_scheme_allowed("main", "files"));
+    assert!(!manager.is_uri_scheme_allowed("secondary", "files"));
+    // unknown webviews fail closed
+    assert!(!manager.is_uri_scheme_allowed("other", "files"));
+    // protocols without a permission keep being available to every webview
+    assert!(manager.is_uri_scheme_allowed("secondary", "public"));
+  }
 }
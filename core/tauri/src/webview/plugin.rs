@@ -19,7 +19,7 @@ mod desktop_commands {
   use super::*;
   use crate::{
     command, sealed::ManagerBase, utils::config::WindowEffectsConfig, AppHandle, Webview,
-    WebviewWindowBuilder,
+    WebviewWindowBuilder, Window,
   };
 
   #[derive(Debug, PartialEq, Clone, Deserialize)]
@@ -45,9 +45,12 @@ mod desktop_commands {
   #[command(root = "crate")]
   pub async fn create_webview_window<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     options: WindowConfig,
   ) -> crate::Result<()> {
-    WebviewWindowBuilder::from_config(&app, options).build()?;
+    WebviewWindowBuilder::from_config(&app, options)
+      .parent(&window)
+      .build()?;
     Ok(())
   }
   #[cfg(not(feature = "unstable"))]
@@ -51,6 +51,9 @@ pub struct WebviewWindowBuilder<'a, R: Runtime, M: Manager<R>> {
 impl<'a, R: Runtime, M: Manager<R>> WebviewWindowBuilder<'a, R, M> {
   /// Initializes a window builder with the given window label.
   ///
+  /// The label can only include Unicode alphanumeric characters, `-`, `/`, `:` and `_`,
+  /// and is [normalized](crate::utils::normalize_label) like the labels it is compared with.
+  ///
   /// # Known issues
   ///
   /// On Windows, this function deadlocks when used in a synchronous command, see [the Webview2 issue].
@@ -560,6 +563,15 @@ impl<'a, R: Runtime, M: Manager<R>> WebviewWindowBuilder<'a, R, M> {
     self
   }
 
+  /// Sets the window that creates the window to be created, see [`Window::set_parent`].
+  ///
+  /// This does not create a native child window, see `parent_window` for that.
+  #[must_use]
+  pub fn parent(mut self, parent: &Window<R>) -> Self {
+    self.window_builder = self.window_builder.parent(parent);
+    self
+  }
+
   /// Sets a parent to the window to be created.
   ///
   /// A child window has the WS_CHILD style and is confined to the client area of its parent window.
@@ -1528,6 +1540,16 @@ impl<R: Runtime> WebviewWindow<R> {
     self.webview.on_message(request, responder)
   }
 
+  /// Invokes a command from Rust, see [`Webview::invoke`].
+  pub fn invoke(
+    &self,
+    request: crate::webview::InvokeRequest,
+    access: crate::webview::InvokeAccess,
+    responder: Box<OwnedInvokeResponder<R>>,
+  ) -> crate::Result<()> {
+    self.webview.invoke(request, access, responder)
+  }
+
   /// Evaluates JavaScript on this window.
   pub fn eval(&self, js: &str) -> crate::Result<()> {
     self.webview.eval(js)
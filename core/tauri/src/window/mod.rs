@@ -9,7 +9,10 @@ pub(crate) mod plugin;
 use tauri_runtime::ResizeDirection;
 use tauri_runtime::{
   webview::PendingWebview,
-  window::dpi::{PhysicalPosition, PhysicalSize},
+  window::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    WindowId,
+  },
 };
 pub use tauri_utils::{config::Color, WindowEffect as Effect, WindowEffectState as EffectState};
 
@@ -26,7 +29,10 @@ use crate::{
   },
   sealed::ManagerBase,
   sealed::RuntimeOrDispatch,
-  utils::config::{WindowConfig, WindowEffectsConfig},
+  utils::{
+    config::{WindowConfig, WindowEffectsConfig},
+    normalize_label,
+  },
   webview::WebviewBuilder,
   EventLoopMessage, Manager, Runtime, Theme, Webview, WindowEvent,
 };
@@ -120,6 +126,7 @@ unstable_struct!(
     #[cfg(desktop)]
     on_menu_event: Option<crate::app::GlobalMenuEventListener<Window<R>>>,
     window_effects: Option<WindowEffectsConfig>,
+    parent: Option<WindowId>,
   }
 );
 
@@ -136,6 +143,9 @@ impl<'a, R: Runtime, M: Manager<R>> fmt::Debug for WindowBuilder<'a, R, M> {
 impl<'a, R: Runtime, M: Manager<R>> WindowBuilder<'a, R, M> {
   /// Initializes a window builder with the given window label.
   ///
+  /// The label can only include Unicode alphanumeric characters, `-`, `/`, `:` and `_`,
+  /// and is [normalized](crate::utils::normalize_label) like the labels it is compared with.
+  ///
   /// # Known issues
   ///
   /// On Windows, this function deadlocks when used in a synchronous command, see [the Webview2 issue].
@@ -198,7 +208,7 @@ async fn create_window(app: tauri::AppHandle) {
   pub fn new<L: Into<String>>(manager: &'a M, label: L) -> Self {
     Self {
       manager,
-      label: label.into(),
+      label: normalize_label(&label.into()).into_owned(),
       window_builder: <R::WindowDispatcher as WindowDispatch<EventLoopMessage>>::WindowBuilder::new(
       ),
       #[cfg(desktop)]
@@ -206,6 +216,7 @@ async fn create_window(app: tauri::AppHandle) {
       #[cfg(desktop)]
       on_menu_event: None,
       window_effects: None,
+      parent: None,
     }
   }
 
@@ -240,8 +251,9 @@ async fn reopen_window(app: tauri::AppHandle) {
   pub fn from_config(manager: &'a M, config: WindowConfig) -> Self {
     Self {
       manager,
-      label: config.label.clone(),
+      label: normalize_label(&config.label).into_owned(),
       window_effects: config.window_effects.clone(),
+      parent: None,
       window_builder:
         <R::WindowDispatcher as WindowDispatch<EventLoopMessage>>::WindowBuilder::with_config(
           config,
@@ -395,6 +407,12 @@ tauri::Builder::default()
         window_menu,
       );
 
+      if let Some(parent) = self.parent {
+        app_manager
+          .window
+          .set_parent(window.window.id, Some(parent));
+      }
+
       if let Some(webview) = detached_window.webview {
         app_manager.webview.attach_webview(window.clone(), webview);
       }
@@ -645,6 +663,15 @@ impl<'a, R: Runtime, M: Manager<R>> WindowBuilder<'a, R, M> {
     self
   }
 
+  /// Sets the window that creates the window to be created, see [`Window::set_parent`].
+  ///
+  /// This does not create a native child window, see `parent_window` for that.
+  #[must_use]
+  pub fn parent(mut self, parent: &Window<R>) -> Self {
+    self.parent.replace(parent.window.id);
+    self
+  }
+
   /// Sets a parent to the window to be created.
   ///
   /// A child window has the WS_CHILD style and is confined to the client area of its parent window.
@@ -828,6 +855,7 @@ impl<R: Runtime> Manager<R> for Window<R> {
     event: &str,
     payload: S,
   ) -> crate::Result<()> {
+    let label = normalize_label(label);
     self.manager().emit_filter(
       event,
       EventSource::Window {
@@ -950,6 +978,23 @@ impl<R: Runtime> Window<R> {
     &self.window.label
   }
 
+  /// The window that created this window, see [`Self::set_parent`].
+  pub fn parent(&self) -> Option<Window<R>> {
+    self.manager.window.parent(self.window.id)
+  }
+
+  /// Sets the window that created this window, or removes it with `None`.
+  ///
+  /// Capabilities can select the windows created by another window with the `parent-of` and `descendant-of` window selectors,
+  /// which are evaluated against the current parent chain on each IPC call.
+  /// This does not make it a native child window.
+  pub fn set_parent(&self, parent: Option<&Window<R>>) {
+    self
+      .manager
+      .window
+      .set_parent(self.window.id, parent.map(|p| p.window.id));
+  }
+
   /// Registers a window event listener.
   pub fn on_window_event<F: Fn(&WindowEvent) + Send + 'static>(&self, f: F) {
     self
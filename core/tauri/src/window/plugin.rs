@@ -60,8 +60,14 @@ mod desktop_commands {
   }
 
   #[command(root = "crate")]
-  pub async fn create<R: Runtime>(app: AppHandle<R>, options: WindowConfig) -> crate::Result<()> {
-    WindowBuilder::from_config(&app, options).build()?;
+  pub async fn create<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    options: WindowConfig,
+  ) -> crate::Result<()> {
+    WindowBuilder::from_config(&app, options)
+      .parent(&window)
+      .build()?;
     Ok(())
   }
 
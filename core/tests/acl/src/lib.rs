@@ -5,14 +5,25 @@
 #[cfg(test)]
 mod tests {
   use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     env::temp_dir,
-    fs::{read_dir, read_to_string},
-    path::Path,
+    fs::{copy, create_dir_all, read_dir, read_to_string, write},
+    path::{Path, PathBuf},
   };
 
   use tauri_utils::{
-    acl::{build::parse_capabilities, plugin::Manifest, resolved::Resolved},
+    acl::{
+      build::{
+        migrate_capability_file, parse_capabilities, parse_target_capabilities, UnknownFields,
+      },
+      capability::CapabilityGroups,
+      diff::AclDiffAllowlist,
+      fixtures::{self, CapabilityBuilder},
+      plugin::Manifest,
+      resolved::{CommandKey, Resolved},
+      Error, ExecutionContext, Value,
+    },
+    assert_command_allowed, assert_command_denied,
     platform::Target,
   };
 
@@ -38,36 +49,542 @@ mod tests {
     manifests
   }
 
+  fn fixtures() -> Vec<(String, PathBuf)> {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixtures_path = manifest_dir.join("fixtures").join("capabilities");
+    read_dir(fixtures_path)
+      .expect("failed to read fixtures")
+      .map(|fixture_path| {
+        let fixture_entry = fixture_path.expect("failed to read fixture entry");
+        (
+          fixture_entry.file_name().to_string_lossy().to_string(),
+          fixture_entry.path(),
+        )
+      })
+      .collect()
+  }
+
+  fn resolve_fixture(fixture_path: &Path) -> Resolved {
+    resolve_fixture_for(fixture_path, Target::current())
+  }
+
+  fn resolve_fixture_for(fixture_path: &Path, target: Target) -> Resolved {
+    let fixture_plugins_str = read_to_string(fixture_path.join("required-plugins.json"))
+      .expect("failed to read fixture required-plugins.json file");
+    let fixture_plugins: Vec<String> = serde_json::from_str(&fixture_plugins_str)
+      .expect("required-plugins.json is not a valid JSON");
+
+    let manifests = load_plugins(&fixture_plugins);
+    let capabilities = parse_capabilities(
+      &format!("{}/cap*", fixture_path.display()),
+      UnknownFields::Deny,
+    )
+    .expect("failed to parse capabilities");
+
+    Resolved::resolve(manifests, capabilities, target).expect("failed to resolve ACL")
+  }
+
   #[test]
   fn resolve_acl() {
     let mut settings = insta::Settings::clone_current();
     settings.set_snapshot_path("../fixtures/snapshots");
     let _guard = settings.bind_to_scope();
 
+    for (fixture, fixture_path) in fixtures() {
+      let resolved = resolve_fixture(&fixture_path);
+      insta::assert_debug_snapshot!(fixture, resolved);
+    }
+  }
+
+  /// Resolves each conformance case for the targets listed in its `targets.json` file, Linux by default,
+  /// and compares the resolved ACL with the case golden files.
+  ///
+  /// Set `TAURI_UPDATE_GOLDEN_FILES=1` to update the golden files after an intended resolution change.
+  #[test]
+  fn conformance() {
     let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
-    let fixtures_path = manifest_dir.join("fixtures").join("capabilities");
-    for fixture_path in read_dir(&fixtures_path).expect("failed to read fixtures") {
-      let fixture_entry = fixture_path.expect("failed to read fixture entry");
-      let fixture_plugins_str = read_to_string(fixture_entry.path().join("required-plugins.json"))
-        .expect("failed to read fixture required-plugins.json file");
-      let fixture_plugins: Vec<String> = serde_json::from_str(&fixture_plugins_str)
-        .expect("required-plugins.json is not a valid JSON");
-
-      let manifests = load_plugins(&fixture_plugins);
-      let capabilities = parse_capabilities(&format!("{}/cap*", fixture_entry.path().display()))
-        .expect("failed to parse capabilities");
-
-      let resolved = Resolved::resolve(manifests, capabilities, Target::current())
-        .expect("failed to resolve ACL");
+    let cases = read_dir(manifest_dir.join("fixtures").join("conformance"))
+      .expect("failed to read conformance cases");
+
+    for case in cases {
+      let case_path = case.expect("failed to read conformance case").path();
+      let targets: Vec<Target> = match read_to_string(case_path.join("targets.json")) {
+        Ok(targets) => serde_json::from_str(&targets).expect("targets.json is not a valid JSON"),
+        Err(_) => vec![Target::Linux],
+      };
 
-      insta::assert_debug_snapshot!(
-        fixture_entry
-          .path()
-          .file_name()
+      for target in targets {
+        let resolved = resolve_fixture_for(&case_path, target);
+        fixtures::assert_golden(&resolved, case_path.join(format!("resolved-{target}.json")));
+      }
+    }
+  }
+
+  /// Migrates the capability files of each fixture to the current format and checks they resolve to the same ACL.
+  #[test]
+  fn migrated_capabilities_resolve_identically() {
+    for (fixture, fixture_path) in fixtures() {
+      let migrated_path = temp_dir().join(format!("acl-tests-migrated-{fixture}"));
+      create_dir_all(&migrated_path).unwrap();
+      let mut changes = BTreeMap::new();
+      for entry in read_dir(&fixture_path).unwrap() {
+        let path = entry.unwrap().path();
+        let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+        let migrated = migrated_path.join(&file_name);
+        copy(&path, &migrated).unwrap();
+        if file_name.starts_with("cap") && !file_name.ends_with(".yaml") {
+          let file_changes = migrate_capability_file(&migrated)
+            .unwrap_or_else(|e| panic!("failed to migrate {fixture}/{file_name}: {e}"));
+          changes.insert(file_name, file_changes);
+        }
+      }
+
+      // migrating a migrated file changes nothing
+      for file_name in changes.keys() {
+        assert!(migrate_capability_file(&migrated_path.join(file_name))
           .unwrap()
-          .to_string_lossy()
-          .to_string(),
-        resolved
+          .is_empty());
+      }
+
+      assert_eq!(
+        format!("{:#?}", resolve_fixture(&fixture_path)),
+        format!("{:#?}", resolve_fixture(&migrated_path)),
+        "{fixture} resolves differently after the migration"
+      );
+
+      if fixture == "format-v1" {
+        let changes = changes
+          .iter()
+          .flat_map(|(file, changes)| {
+            changes
+              .iter()
+              .map(move |change| format!("{file}: {change}"))
+          })
+          .collect::<Vec<_>>();
+        assert_eq!(
+          changes,
+          [
+            "cap-editor.toml: added `version` = 2",
+            "cap-editor.toml: moved `permissions[1].deny` to `permissions[1].scope.deny`",
+            "cap-reader.json: added `version` = 2",
+            "cap-reader.json: moved `capabilities[0].permissions[1].allow` to `capabilities[0].permissions[1].scope.allow`",
+            "cap-reader.json: moved `capabilities[0].permissions[1].deny` to `capabilities[0].permissions[1].scope.deny`",
+          ]
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn yaml_anchors() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixture_path = manifest_dir.join("fixtures/capabilities/yaml-anchors");
+    let capabilities = parse_capabilities(
+      &format!("{}/cap*", fixture_path.display()),
+      UnknownFields::Deny,
+    )
+    .expect("failed to parse capabilities");
+
+    assert_eq!(
+      capabilities.keys().collect::<Vec<_>>(),
+      ["editor-move", "editor-ping", "editor-read", "main"]
+    );
+    for identifier in ["editor-move", "editor-ping", "editor-read"] {
+      let windows = capabilities[identifier]
+        .windows
+        .iter()
+        .map(|window| window.pattern())
+        .collect::<Vec<_>>();
+      assert_eq!(windows, ["editor", "editor-*"], "{identifier}");
+    }
+    assert_eq!(
+      capabilities["editor-move"].permissions[0]
+        .identifier()
+        .get(),
+      "fs:allow-move-temp"
+    );
+
+    let duplicate_dir = temp_dir().join("acl-tests-duplicate-capability");
+    create_dir_all(&duplicate_dir).unwrap();
+    write(
+      duplicate_dir.join("cap-main.json"),
+      r#"{ "identifier": "editor-read", "windows": ["main"], "permissions": [] }"#,
+    )
+    .unwrap();
+    copy(
+      fixture_path.join("cap-editors.yaml"),
+      duplicate_dir.join("cap-editors.yml"),
+    )
+    .unwrap();
+    let error = parse_capabilities(
+      &format!("{}/cap*", duplicate_dir.display()),
+      UnknownFields::Deny,
+    )
+    .unwrap_err();
+    assert!(matches!(
+      error,
+      Error::DuplicateCapability { identifier, .. } if identifier == "editor-read"
+    ));
+  }
+
+  #[test]
+  fn capability_groups() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixture_path = manifest_dir.join("fixtures/capabilities/capability-groups");
+    let pattern = format!("{}/cap*", fixture_path.display());
+    let plugins = ["ping".to_string(), "fs".to_string()];
+
+    let resolve = |groups: CapabilityGroups| {
+      let mut capabilities =
+        parse_capabilities(&pattern, UnknownFields::Deny).expect("failed to parse capabilities");
+      let filtered = groups.filter(&mut capabilities)?;
+      let resolved = Resolved::resolve(load_plugins(&plugins), capabilities, Target::current())
+        .expect("failed to resolve ACL");
+      let commands = resolved
+        .allowed_commands
+        .keys()
+        .map(|key| key.name.clone())
+        .collect::<BTreeSet<_>>();
+      Ok::<_, Error>((filtered, commands))
+    };
+
+    let (filtered, all) = resolve(CapabilityGroups::new()).unwrap();
+    assert!(filtered.is_empty());
+    assert_eq!(
+      all,
+      BTreeSet::from([
+        "plugin:fs|move".to_string(),
+        "plugin:fs|read_dir".to_string(),
+        "plugin:ping|ping".to_string(),
+      ])
+    );
+
+    let (filtered, free) = resolve(CapabilityGroups::new().enable("free").disable("pro")).unwrap();
+    assert_eq!(filtered, ["pro-move"]);
+    assert!(!free.contains("plugin:fs|move"));
+    assert!(free.contains("plugin:fs|read_dir"));
+
+    let (filtered, pro) = resolve(CapabilityGroups::new().enable("free").enable("pro")).unwrap();
+    assert!(filtered.is_empty());
+    assert_eq!(pro, all);
+
+    let error = resolve(CapabilityGroups::new().enable("free")).unwrap_err();
+    assert!(matches!(
+      error,
+      Error::UnknownCapabilityGroup { capability, group, .. }
+        if capability == "pro-move" && group == "pro"
+    ));
+  }
+
+  #[test]
+  fn profile_diff() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixture_path = manifest_dir.join("fixtures/profile-diff");
+    let pattern = format!("{}/cap*", fixture_path.display());
+    let plugins = ["ping".to_string(), "fs".to_string()];
+
+    let resolve = |groups: CapabilityGroups| {
+      let mut capabilities =
+        parse_capabilities(&pattern, UnknownFields::Deny).expect("failed to parse capabilities");
+      groups.filter(&mut capabilities).unwrap();
+      Resolved::resolve(load_plugins(&plugins), capabilities, Target::current())
+        .expect("failed to resolve ACL")
+    };
+    let dev = resolve(
+      CapabilityGroups::new()
+        .enable("devtools")
+        .disable("release"),
+    );
+    let release = resolve(
+      CapabilityGroups::new()
+        .disable("devtools")
+        .enable("release"),
+    );
+
+    // the capabilities granting ping differ, but only provenance fields record it
+    assert!(dev.diff(&dev).is_empty());
+    let differences = dev.diff(&release);
+    assert_eq!(
+      differences
+        .iter()
+        .map(|difference| difference.subject.as_str())
+        .collect::<Vec<_>>(),
+      [
+        "allowed plugin:fs|move (local)",
+        "allowed plugin:ping|ping (local) windows",
+      ]
+    );
+    assert!(differences[0].right.is_none());
+    assert_eq!(
+      differences[1].to_string(),
+      r#"allowed plugin:ping|ping (local) windows: ["inspector","main"] -> ["main"]"#
+    );
+
+    let allowlist = AclDiffAllowlist::parse(
+      &read_to_string(fixture_path.join("acl-diff-allowlist.txt")).unwrap(),
+    )
+    .expect("failed to parse allowlist");
+    assert!(allowlist.allows(&differences[0]));
+    assert!(!allowlist.allows(&differences[1]));
+
+    let (line, _error) =
+      AclDiffAllowlist::parse("# comment\n\nallowed plugin:fs|[move").unwrap_err();
+    assert_eq!(line, 3);
+  }
+
+  #[test]
+  fn target_capabilities() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixture_path = manifest_dir.join("fixtures/target-capabilities");
+    let plugins = ["ping".to_string(), "fs".to_string()];
+
+    let resolve = |target: Target| {
+      let (capabilities, overrides) =
+        parse_target_capabilities(&fixture_path, target, UnknownFields::Deny)
+          .expect("failed to parse capabilities");
+      let identifiers = capabilities.keys().cloned().collect::<Vec<_>>();
+      let resolved = Resolved::resolve(load_plugins(&plugins), capabilities, target)
+        .expect("failed to resolve ACL");
+      (identifiers, overrides, resolved)
+    };
+
+    let (capabilities, overrides, resolved) = resolve(Target::Android);
+    assert_eq!(capabilities, ["main"]);
+    assert_eq!(overrides.len(), 1);
+    assert_eq!(overrides[0].identifier, "main");
+    assert_eq!(overrides[0].overridden, fixture_path.join("main.json"));
+    assert_eq!(
+      overrides[0].by,
+      fixture_path
+        .join("mobile")
+        .join("android")
+        .join("main.json")
+    );
+    assert_command_allowed!(resolved, "plugin:ping|ping", window = "main");
+    assert_command_denied!(resolved, "plugin:fs|read_dir", window = "main");
+
+    let (capabilities, overrides, resolved) = resolve(Target::Ios);
+    assert_eq!(capabilities, ["main", "share"]);
+    assert!(overrides.is_empty());
+    assert_command_allowed!(resolved, "plugin:fs|read_dir", window = "main");
+
+    for target in [Target::Linux, Target::MacOS, Target::Windows] {
+      let (capabilities, overrides, resolved) = resolve(target);
+      assert_eq!(capabilities, ["main", "tray"], "{target}");
+      assert!(overrides.is_empty());
+      assert_command_allowed!(resolved, "plugin:fs|read_dir", window = "main");
+      assert_command_allowed!(resolved, "plugin:ping|ping", window = "tray");
+    }
+  }
+
+  #[test]
+  fn plugin_fixtures_harness() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let manifest = fixtures::load_manifest("fs", manifest_dir.join("fixtures/plugins/fs"))
+      .expect("failed to load fs permissions");
+    let resolved = fixtures::resolve(
+      [manifest],
+      [
+        CapabilityBuilder::new("main")
+          .window("main")
+          .permission("fs:read")
+          .permission_scoped(
+            "fs:allow-move-temp",
+            [Value::Map(
+              [("path".into(), Value::String("$APPDATA".into()))]
+                .into_iter()
+                .collect(),
+            )],
+            [],
+          )
+          .build(),
+        CapabilityBuilder::new("web")
+          .window("main")
+          .remote(["tauri.app"])
+          .permission("fs:allow-read-dir")
+          .permission("fs:deny-read-file")
+          .build(),
+      ],
+      Target::current(),
+    )
+    .expect("failed to resolve ACL");
+
+    assert_command_allowed!(resolved, "plugin:fs|read_file", window = "main");
+    assert_command_allowed!(resolved, "plugin:fs|move", window = "main");
+    assert_command_denied!(resolved, "plugin:fs|read_file", window = "other");
+    assert_command_allowed!(
+      resolved,
+      "plugin:fs|read_dir",
+      window = "main",
+      remote = "tauri.app"
+    );
+    assert_command_denied!(
+      resolved,
+      "plugin:fs|read_file",
+      window = "main",
+      remote = "tauri.app"
+    );
+    fixtures::assert_golden(&resolved, manifest_dir.join("fixtures/golden/fs.json"));
+  }
+
+  #[test]
+  fn url_scope_per_window() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let resolved = resolve_fixture(&manifest_dir.join("fixtures/capabilities/url-scope"));
+
+    // the URL patterns of the command scope merged with the global scope, as the opener loads them
+    let urls = |window: &str| {
+      let open = &resolved.allowed_commands[&CommandKey {
+        name: "plugin:opener|open".into(),
+        context: ExecutionContext::Local,
+      }];
+      let command_scopes = open
+        .scopes
+        .iter()
+        .filter(|scope| scope.matches_window::<&str>(window, &[]))
+        .map(|scope| &resolved.command_scope[&scope.scope]);
+      let global_scopes = resolved.global_scope["opener"]
+        .iter()
+        .filter(|scope| scope.matches_window::<&str>(window, &[]))
+        .map(|scope| &scope.scope);
+      let url = |entries: &[Value]| {
+        entries
+          .iter()
+          .map(|entry| match entry {
+            Value::Map(entry) => match &entry["url"] {
+              Value::String(url) => url.clone(),
+              url => panic!("unexpected url {url:?}"),
+            },
+            entry => panic!("unexpected entry {entry:?}"),
+          })
+          .collect::<Vec<_>>()
+      };
+      command_scopes.chain(global_scopes).fold(
+        (Vec::new(), Vec::new()),
+        |(mut allow, mut deny), scope| {
+          allow.extend(url(&scope.allow));
+          deny.extend(url(&scope.deny));
+          (allow, deny)
+        },
+      )
+    };
+
+    for window in ["main", "viewer"] {
+      assert!(is_allowed(
+        &resolved,
+        "plugin:opener|open",
+        window,
+        &[],
+        None
+      ));
+    }
+    assert_eq!(
+      urls("main"),
+      (
+        vec![
+          "https://docs.ourapp.com/*".to_string(),
+          "HTTPS://Status.OurApp.com/*".into(),
+          "ourapp://open/*".into(),
+          "mailto:support@ourapp.com".into(),
+        ],
+        vec!["https://docs.ourapp.com/internal/*".to_string()]
+      )
+    );
+    // the embedded viewer can invoke the command but its URL scope is empty
+    assert_eq!(urls("viewer"), (Vec::new(), Vec::new()));
+  }
+
+  /// Mirrors the IPC authorization of the runtime authority, with `None` as the local origin.
+  fn is_allowed(
+    resolved: &Resolved,
+    command: &str,
+    window: &str,
+    ancestors: &[&str],
+    domain: Option<&str>,
+  ) -> bool {
+    let matches = |key: &CommandKey| {
+      key.name == command
+        && match (&key.context, domain) {
+          (ExecutionContext::Local, None) => true,
+          (
+            ExecutionContext::Remote {
+              domain: pattern, ..
+            },
+            Some(domain),
+          ) => pattern.matches(domain),
+          _ => false,
+        }
+    };
+
+    !resolved.denied_commands.keys().any(matches)
+      && resolved
+        .allowed_commands
+        .iter()
+        .find(|(key, _)| matches(key))
+        .is_some_and(|(_, command)| command.matches_window(window, ancestors))
+  }
+
+  #[test]
+  fn minimal_codegen_detail() {
+    let windows: [(&str, &[&str]); 5] = [
+      ("main", &[]),
+      ("other", &[]),
+      ("popup", &["main"]),
+      ("preview", &["popup", "main"]),
+      ("preview", &["popup", "editor-1"]),
+    ];
+    let domains = [
+      None,
+      Some("tauri.app"),
+      Some("api.tauri.app"),
+      Some("tauri.studio"),
+      Some("example.com"),
+    ];
+
+    for (fixture, fixture_path) in fixtures() {
+      let full = resolve_fixture(&fixture_path);
+      let mut minimal = resolve_fixture(&fixture_path);
+      minimal.minimize();
+
+      if fixture == "window-selectors" {
+        assert!(minimal.allowed_commands.len() < full.allowed_commands.len());
+        assert!(minimal.command_scope.len() < full.command_scope.len());
+      }
+
+      let commands = full
+        .allowed_commands
+        .keys()
+        .chain(full.denied_commands.keys())
+        .map(|key| key.name.as_str())
+        .chain(["plugin:fs|unknown"])
+        .collect::<BTreeSet<_>>();
+
+      for command in commands {
+        for (window, ancestors) in windows {
+          for domain in domains {
+            assert_eq!(
+              is_allowed(&full, command, window, ancestors, domain),
+              is_allowed(&minimal, command, window, ancestors, domain),
+              "{fixture}: {command} on window {window} ({ancestors:?}) from {domain:?}"
+            );
+          }
+        }
+      }
+    }
+  }
+
+  /// Keeps the estimated heap footprint of the resolved fixtures under a budget,
+  /// so an unexpected growth of the ACL types is caught in CI.
+  #[test]
+  fn memory_footprint() {
+    const BUDGET: usize = 16 * 1024;
+
+    for (fixture, fixture_path) in fixtures() {
+      let stats = resolve_fixture(&fixture_path).memory_stats();
+      assert!(
+        stats.total_bytes() < BUDGET,
+        "{fixture} uses {} bytes: {stats:#?}",
+        stats.total_bytes()
       );
     }
   }
@@ -70,7 +70,10 @@ fn ping<R: tauri::Runtime>(
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
   Builder::new("sample")
     .setup(|app, api| {
-      println!("global scope: {:?}", api.scope::<SampleScope>());
+      // the scope is only relevant if the app exposes any of the plugin commands
+      if app.acl().plugin_has_any_allowed_command("sample") {
+        println!("global scope: {:?}", api.scope::<SampleScope>());
+      }
       #[cfg(mobile)]
       let sample = mobile::init(app, api)?;
       #[cfg(desktop)]
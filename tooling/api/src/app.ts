@@ -83,4 +83,21 @@ async function hide(): Promise<void> {
   return invoke('plugin:app|app_hide')
 }
 
-export { getName, getVersion, getTauriVersion, show, hide }
+/**
+ * Restarts the application.
+ *
+ * Requires the `app:allow-relaunch` permission, which is not part of the default permissions.
+ *
+ * @example
+ * ```typescript
+ * import { relaunch } from '@tauri-apps/api/app';
+ * await relaunch();
+ * ```
+ *
+ * @since 2.0.0
+ */
+async function relaunch(): Promise<void> {
+  return invoke('plugin:app|relaunch')
+}
+
+export { getName, getVersion, getTauriVersion, show, hide, relaunch }
@@ -27,6 +27,11 @@ import type { WindowOptions } from './window'
 interface FileDropPayload {
   paths: string[]
   position: PhysicalPosition
+  /**
+   * Set when files were dropped or hovered but the webview is not allowed to know their paths,
+   * in which case `paths` is empty. See the `webview:allow-drag-drop-paths` permission.
+   */
+  filesDropped?: boolean
 }
 
 /** The file drop event types. */
@@ -474,7 +479,8 @@ class Webview {
           payload: {
             type: 'drop',
             paths: event.payload.paths,
-            position: mapPhysicalPosition(event.payload.position)
+            position: mapPhysicalPosition(event.payload.position),
+            filesDropped: event.payload.filesDropped
           }
         })
       }
@@ -488,7 +494,8 @@ class Webview {
           payload: {
             type: 'hover',
             paths: event.payload.paths,
-            position: mapPhysicalPosition(event.payload.position)
+            position: mapPhysicalPosition(event.payload.position),
+            filesDropped: event.payload.filesDropped
           }
         })
       }
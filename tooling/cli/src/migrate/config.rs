@@ -5,18 +5,9 @@
 use crate::Result;
 
 use serde_json::{Map, Value};
-use tauri_utils::{
-  acl::{
-    capability::{Capability, CapabilityContext, PermissionEntry},
-    Scopes, Value as AclValue,
-  },
-  platform::Target,
-};
-
-use std::{
-  fs::{create_dir_all, write},
-  path::Path,
-};
+use tauri_utils::acl::migrate::{migrate_allowlist, MigratedAllowlist};
+
+use std::{fs::write, path::Path};
 
 macro_rules! move_allowlist_object {
   ($plugins: ident, $value: expr, $plugin: literal, $field: literal) => {{
@@ -38,51 +29,24 @@ pub fn migrate(tauri_dir: &Path) -> Result<()> {
     let migrated = migrate_config(&mut config)?;
     write(&config_path, serde_json::to_string_pretty(&config)?)?;
 
-    let mut permissions: Vec<PermissionEntry> = vec![
-      "path:default",
-      "event:default",
-      "window:default",
-      "app:default",
-      "resources:default",
-      "menu:default",
-      "tray:default",
-    ]
-    .into_iter()
-    .map(|p| PermissionEntry::PermissionRef(p.to_string().try_into().unwrap()))
-    .collect();
-    permissions.extend(migrated.permissions);
-
-    let capabilities_path = config_path.parent().unwrap().join("capabilities");
-    create_dir_all(&capabilities_path)?;
-    write(
-      capabilities_path.join("migrated.json"),
-      serde_json::to_string_pretty(&Capability {
-        identifier: "migrated".to_string(),
-        description: "permissions that were migrated from v1".into(),
-        context: CapabilityContext::Local,
-        windows: vec!["main".into()],
-        permissions,
-        platforms: vec![
-          Target::Linux,
-          Target::MacOS,
-          Target::Windows,
-          Target::Android,
-          Target::Ios,
-        ],
-      })?,
-    )?;
+    for entry in &migrated.allowlist.unsupported {
+      log::warn!("`{entry}` has no equivalent permission and was not migrated");
+    }
+    migrated
+      .allowlist
+      .write(&config_path.parent().unwrap().join("capabilities"))?;
   }
 
   Ok(())
 }
 
 struct MigratedConfig {
-  permissions: Vec<PermissionEntry>,
+  allowlist: MigratedAllowlist,
 }
 
 fn migrate_config(config: &mut Value) -> Result<MigratedConfig> {
   let mut migrated = MigratedConfig {
-    permissions: Vec::new(),
+    allowlist: migrate_allowlist(&Value::Null),
   };
 
   if let Some(config) = config.as_object_mut() {
@@ -96,9 +60,8 @@ fn migrate_config(config: &mut Value) -> Result<MigratedConfig> {
     if let Some(tauri_config) = config.get_mut("tauri").and_then(|c| c.as_object_mut()) {
       // allowlist
       if let Some(allowlist) = tauri_config.remove("allowlist") {
-        let allowlist = process_allowlist(tauri_config, &mut plugins, allowlist)?;
-        let permissions = allowlist_to_permissions(allowlist);
-        migrated.permissions = permissions;
+        process_allowlist(tauri_config, &mut plugins, allowlist.clone())?;
+        migrated.allowlist = migrate_allowlist(&allowlist);
       }
 
       if let Some(security) = tauri_config
@@ -171,7 +134,7 @@ fn process_allowlist(
   tauri_config: &mut Map<String, Value>,
   plugins: &mut Map<String, Value>,
   allowlist: Value,
-) -> Result<tauri_utils_v1::config::AllowlistConfig> {
+) -> Result<()> {
   let allowlist: tauri_utils_v1::config::AllowlistConfig = serde_json::from_value(allowlist)?;
 
   move_allowlist_object!(plugins, allowlist.shell.open, "shell", "open");
@@ -194,188 +157,7 @@ fn process_allowlist(
     security.insert("assetProtocol".into(), asset_protocol.into());
   }
 
-  Ok(allowlist)
-}
-
-fn allowlist_to_permissions(
-  allowlist: tauri_utils_v1::config::AllowlistConfig,
-) -> Vec<PermissionEntry> {
-  macro_rules! permissions {
-    ($allowlist: ident, $permissions_list: ident, $object: ident, $field: ident => $associated_permission: expr) => {
-      if $allowlist.all || $allowlist.$object.all || $allowlist.$object.$field {
-        $permissions_list.push(PermissionEntry::PermissionRef(
-          $associated_permission.to_string().try_into().unwrap(),
-        ));
-      }
-    };
-  }
-
-  let mut permissions = Vec::new();
-
-  // fs
-  permissions!(allowlist, permissions, fs, read_file => "fs:allow-read-file");
-  permissions!(allowlist, permissions, fs, write_file => "fs:allow-write-file");
-  permissions!(allowlist, permissions, fs, read_dir => "fs:allow-read-dir");
-  permissions!(allowlist, permissions, fs, copy_file => "fs:allow-copy-file");
-  permissions!(allowlist, permissions, fs, create_dir => "fs:allow-mkdir");
-  permissions!(allowlist, permissions, fs, remove_dir => "fs:allow-remove");
-  permissions!(allowlist, permissions, fs, remove_file => "fs:allow-remove");
-  permissions!(allowlist, permissions, fs, rename_file => "fs:allow-rename");
-  permissions!(allowlist, permissions, fs, exists => "fs:allow-exists");
-  let (fs_allowed, fs_denied) = match allowlist.fs.scope {
-    tauri_utils_v1::config::FsAllowlistScope::AllowedPaths(paths) => (paths, Vec::new()),
-    tauri_utils_v1::config::FsAllowlistScope::Scope { allow, deny, .. } => (allow, deny),
-  };
-  if !(fs_allowed.is_empty() && fs_denied.is_empty()) {
-    let fs_allowed = fs_allowed
-      .into_iter()
-      .map(|p| AclValue::String(p.to_string_lossy().into()))
-      .collect::<Vec<_>>();
-    let fs_denied = fs_denied
-      .into_iter()
-      .map(|p| AclValue::String(p.to_string_lossy().into()))
-      .collect::<Vec<_>>();
-    permissions.push(PermissionEntry::ExtendedPermission {
-      identifier: "fs:scope".to_string().try_into().unwrap(),
-      scope: Scopes {
-        allow: if fs_allowed.is_empty() {
-          None
-        } else {
-          Some(fs_allowed)
-        },
-        deny: if fs_denied.is_empty() {
-          None
-        } else {
-          Some(fs_denied)
-        },
-      },
-    });
-  }
-
-  // window
-  permissions!(allowlist, permissions, window, create => "window:allow-create");
-  permissions!(allowlist, permissions, window, center => "window:allow-center");
-  permissions!(allowlist, permissions, window, request_user_attention => "window:allow-request-user-attention");
-  permissions!(allowlist, permissions, window, set_resizable => "window:allow-set-resizable");
-  permissions!(allowlist, permissions, window, set_maximizable => "window:allow-set-maximizable");
-  permissions!(allowlist, permissions, window, set_minimizable => "window:allow-set-minimizable");
-  permissions!(allowlist, permissions, window, set_closable => "window:allow-set-closable");
-  permissions!(allowlist, permissions, window, set_title => "window:allow-set-title");
-  permissions!(allowlist, permissions, window, maximize => "window:allow-maximize");
-  permissions!(allowlist, permissions, window, unmaximize => "window:allow-unmaximize");
-  permissions!(allowlist, permissions, window, minimize => "window:allow-minimize");
-  permissions!(allowlist, permissions, window, unminimize => "window:allow-unminimize");
-  permissions!(allowlist, permissions, window, show => "window:allow-show");
-  permissions!(allowlist, permissions, window, hide => "window:allow-hide");
-  permissions!(allowlist, permissions, window, close => "window:allow-close");
-  permissions!(allowlist, permissions, window, set_decorations => "window:allow-set-decorations");
-  permissions!(allowlist, permissions, window, set_always_on_top => "window:allow-set-always-on-top");
-  permissions!(allowlist, permissions, window, set_content_protected => "window:allow-set-content-protected");
-  permissions!(allowlist, permissions, window, set_size => "window:allow-set-size");
-  permissions!(allowlist, permissions, window, set_min_size => "window:allow-set-min-size");
-  permissions!(allowlist, permissions, window, set_max_size => "window:allow-set-max-size");
-  permissions!(allowlist, permissions, window, set_position => "window:allow-set-position");
-  permissions!(allowlist, permissions, window, set_fullscreen => "window:allow-set-fullscreen");
-  permissions!(allowlist, permissions, window, set_focus => "window:allow-set-focus");
-  permissions!(allowlist, permissions, window, set_icon => "window:allow-set-icon");
-  permissions!(allowlist, permissions, window, set_skip_taskbar => "window:allow-set-skip-taskbar");
-  permissions!(allowlist, permissions, window, set_cursor_grab => "window:allow-set-cursor-grab");
-  permissions!(allowlist, permissions, window, set_cursor_visible => "window:allow-set-cursor-visible");
-  permissions!(allowlist, permissions, window, set_cursor_icon => "window:allow-set-cursor-icon");
-  permissions!(allowlist, permissions, window, set_cursor_position => "window:allow-set-cursor-position");
-  permissions!(allowlist, permissions, window, set_ignore_cursor_events => "window:allow-set-ignore-cursor-events");
-  permissions!(allowlist, permissions, window, start_dragging => "window:allow-start-dragging");
-  permissions!(allowlist, permissions, window, print => "webview:allow-print");
-
-  // shell
-  if allowlist.shell.scope.0.is_empty() {
-    permissions!(allowlist, permissions, shell, execute => "shell:allow-execute");
-    permissions!(allowlist, permissions, shell, sidecar => "shell:allow-execute");
-  } else {
-    let allowed = allowlist
-      .shell
-      .scope
-      .0
-      .into_iter()
-      .map(|p| serde_json::to_value(p).unwrap().into())
-      .collect::<Vec<_>>();
-
-    permissions.push(PermissionEntry::ExtendedPermission {
-      identifier: "shell:allow-execute".to_string().try_into().unwrap(),
-      scope: Scopes {
-        allow: Some(allowed),
-        deny: None,
-      },
-    });
-  }
-
-  if allowlist.all
-    || allowlist.shell.all
-    || !matches!(
-      allowlist.shell.open,
-      tauri_utils_v1::config::ShellAllowlistOpen::Flag(false)
-    )
-  {
-    permissions.push(PermissionEntry::PermissionRef(
-      "shell:allow-open".to_string().try_into().unwrap(),
-    ));
-  }
-  // dialog
-  permissions!(allowlist, permissions, dialog, open => "dialog:allow-open");
-  permissions!(allowlist, permissions, dialog, save => "dialog:allow-save");
-  permissions!(allowlist, permissions, dialog, message => "dialog:allow-message");
-  permissions!(allowlist, permissions, dialog, ask => "dialog:allow-ask");
-  permissions!(allowlist, permissions, dialog, confirm => "dialog:allow-confirm");
-
-  // http
-  if allowlist.http.scope.0.is_empty() {
-    permissions!(allowlist, permissions, http, request => "http:default");
-  } else {
-    let allowed = allowlist
-      .http
-      .scope
-      .0
-      .into_iter()
-      .map(|p| AclValue::String(p.to_string()))
-      .collect::<Vec<_>>();
-
-    permissions.push(PermissionEntry::ExtendedPermission {
-      identifier: "http:default".to_string().try_into().unwrap(),
-      scope: Scopes {
-        allow: Some(allowed),
-        deny: None,
-      },
-    });
-  }
-
-  // notification
-  permissions!(allowlist, permissions, notification, all => "notification:default");
-  // global-shortcut
-  permissions!(allowlist, permissions, global_shortcut, all => "global-shortcut:allow-is-registered");
-  permissions!(allowlist, permissions, global_shortcut, all => "global-shortcut:allow-register");
-  permissions!(allowlist, permissions, global_shortcut, all => "global-shortcut:allow-register-all");
-  permissions!(allowlist, permissions, global_shortcut, all => "global-shortcut:allow-unregister");
-  permissions!(allowlist, permissions, global_shortcut, all => "global-shortcut:allow-unregister-all");
-  // os
-  permissions!(allowlist, permissions, os, all => "os:allow-platform");
-  permissions!(allowlist, permissions, os, all => "os:allow-version");
-  permissions!(allowlist, permissions, os, all => "os:allow-os-type");
-  permissions!(allowlist, permissions, os, all => "os:allow-family");
-  permissions!(allowlist, permissions, os, all => "os:allow-arch");
-  permissions!(allowlist, permissions, os, all => "os:allow-exe-extension");
-  permissions!(allowlist, permissions, os, all => "os:allow-locale");
-  permissions!(allowlist, permissions, os, all => "os:allow-hostname");
-  // process
-  permissions!(allowlist, permissions, process, relaunch => "process:allow-restart");
-  permissions!(allowlist, permissions, process, exit => "process:allow-exit");
-  // clipboard
-  permissions!(allowlist, permissions, clipboard, read_text => "clipboard-manager:allow-read");
-  permissions!(allowlist, permissions, clipboard, write_text => "clipboard-manager:allow-write");
-  // app
-  permissions!(allowlist, permissions, app, show => "app:allow-app-show");
-  permissions!(allowlist, permissions, app, hide => "app:allow-app-hide");
-
-  permissions
+  Ok(())
 }
 
 fn process_cli(plugins: &mut Map<String, Value>, cli: Value) -> Result<()> {